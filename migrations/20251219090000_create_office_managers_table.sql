@@ -0,0 +1,5 @@
+CREATE TABLE office_managers (
+    subject VARCHAR(100) NOT NULL,
+    office_id INT NOT NULL REFERENCES offices(id) ON DELETE CASCADE,
+    PRIMARY KEY (subject, office_id)
+);
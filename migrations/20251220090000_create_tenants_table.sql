@@ -0,0 +1,21 @@
+CREATE TABLE tenants (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL UNIQUE
+);
+
+-- existing single-company data belongs to the default tenant
+INSERT INTO tenants (id, name) VALUES (1, 'default');
+SELECT setval('tenants_id_seq', 1);
+
+ALTER TABLE offices ADD COLUMN tenant_id INT NOT NULL DEFAULT 1 REFERENCES tenants(id);
+ALTER TABLE offices ALTER COLUMN tenant_id DROP DEFAULT;
+ALTER TABLE offices DROP CONSTRAINT offices_name_key;
+ALTER TABLE offices ADD CONSTRAINT offices_tenant_id_name_key UNIQUE (tenant_id, name);
+ALTER TABLE offices ADD CONSTRAINT offices_tenant_id_id_key UNIQUE (tenant_id, id);
+
+-- employees can only reference offices of their own tenant
+ALTER TABLE employees ADD COLUMN tenant_id INT NOT NULL DEFAULT 1 REFERENCES tenants(id);
+ALTER TABLE employees ALTER COLUMN tenant_id DROP DEFAULT;
+ALTER TABLE employees DROP CONSTRAINT employees_office_id_fkey;
+ALTER TABLE employees ADD CONSTRAINT employees_tenant_id_office_id_fkey
+    FOREIGN KEY (tenant_id, office_id) REFERENCES offices(tenant_id, id);
@@ -0,0 +1,14 @@
+CREATE TABLE idempotency_keys (
+    tenant_id INT NOT NULL REFERENCES tenants(id),
+    subject VARCHAR(100) NOT NULL,
+    route VARCHAR(100) NOT NULL,
+    idempotency_key VARCHAR(255) NOT NULL,
+    request_hash CHAR(64) NOT NULL,
+    status_code INT,
+    content_type VARCHAR(100),
+    response_body BYTEA, -- byte exact, responses can be MessagePack
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (tenant_id, subject, route, idempotency_key)
+);
+
+CREATE INDEX idempotency_keys_created_at_idx ON idempotency_keys (created_at);
@@ -0,0 +1,24 @@
+-- Employees merged into another employee, moved here from employees so they no longer hold a seat
+CREATE TABLE archived_employees (
+    id INT PRIMARY KEY, -- the former employees.id
+    tenant_id INT NOT NULL REFERENCES tenants(id),
+    first_name VARCHAR(100) NOT NULL,
+    last_name VARCHAR(100) NOT NULL,
+    birth_date DATE NOT NULL,
+    office_id INT NOT NULL,
+    merged_into INT REFERENCES employees(id) ON DELETE SET NULL,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Who changed what, entries outlive the employee they are about so employee_id is no foreign key
+CREATE TABLE audit_entries (
+    id BIGSERIAL PRIMARY KEY,
+    tenant_id INT NOT NULL REFERENCES tenants(id),
+    subject VARCHAR(100) NOT NULL,
+    action VARCHAR(50) NOT NULL,
+    employee_id INT,
+    details TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX audit_entries_employee_idx ON audit_entries (tenant_id, employee_id);
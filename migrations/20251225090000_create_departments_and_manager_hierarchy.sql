@@ -0,0 +1,21 @@
+CREATE TABLE departments (
+    id SERIAL PRIMARY KEY,
+    tenant_id INT NOT NULL REFERENCES tenants(id),
+    name VARCHAR(100) NOT NULL,
+    CONSTRAINT departments_tenant_id_name_key UNIQUE (tenant_id, name),
+    CONSTRAINT departments_tenant_id_id_key UNIQUE (tenant_id, id)
+);
+
+-- departments and managers are optional and always of the employee's own tenant
+ALTER TABLE employees ADD COLUMN department_id INT;
+ALTER TABLE employees ADD COLUMN manager_id INT;
+ALTER TABLE employees ADD CONSTRAINT employees_tenant_id_id_key UNIQUE (tenant_id, id);
+ALTER TABLE employees ADD CONSTRAINT employees_tenant_id_department_id_fkey
+    FOREIGN KEY (tenant_id, department_id) REFERENCES departments(tenant_id, id);
+-- deleting a manager leaves the direct reports without a manager, tenant_id is kept
+ALTER TABLE employees ADD CONSTRAINT employees_tenant_id_manager_id_fkey
+    FOREIGN KEY (tenant_id, manager_id) REFERENCES employees(tenant_id, id) ON DELETE SET NULL (manager_id);
+-- longer cycles are prevented by the service before writing
+ALTER TABLE employees ADD CONSTRAINT employees_manager_id_check CHECK (manager_id <> id);
+
+CREATE INDEX employees_manager_id_idx ON employees (tenant_id, manager_id);
@@ -0,0 +1,14 @@
+-- employees already present were hired before the lifecycle was tracked, they start out active as of today
+ALTER TABLE employees ADD COLUMN hire_date DATE NOT NULL DEFAULT CURRENT_DATE;
+ALTER TABLE employees ADD COLUMN termination_date DATE;
+ALTER TABLE employees ADD COLUMN status TEXT NOT NULL DEFAULT 'active';
+ALTER TABLE employees ALTER COLUMN hire_date DROP DEFAULT;
+ALTER TABLE employees ALTER COLUMN status DROP DEFAULT;
+
+ALTER TABLE employees ADD CONSTRAINT employees_status_check
+    CHECK (status IN ('pending', 'active', 'on_leave', 'terminated'));
+-- a seat is held from hire_date through termination_date
+ALTER TABLE employees ADD CONSTRAINT employees_termination_date_check
+    CHECK (termination_date IS NULL OR termination_date >= hire_date);
+
+CREATE INDEX employees_office_id_hire_date_idx ON employees (tenant_id, office_id, hire_date);
@@ -0,0 +1,27 @@
+-- Planned moves of an employee to another office, applied by the transfer job on effective_date
+CREATE TABLE transfers (
+    id SERIAL PRIMARY KEY,
+    tenant_id INT NOT NULL REFERENCES tenants(id),
+    employee_id INT NOT NULL,
+    from_office_id INT NOT NULL,
+    to_office_id INT NOT NULL,
+    effective_date DATE NOT NULL,
+    status TEXT NOT NULL,
+    requested_by VARCHAR(100) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    -- a deleted employee or office takes its transfers along
+    CONSTRAINT transfers_tenant_id_employee_id_fkey
+        FOREIGN KEY (tenant_id, employee_id) REFERENCES employees(tenant_id, id) ON DELETE CASCADE,
+    CONSTRAINT transfers_tenant_id_from_office_id_fkey
+        FOREIGN KEY (tenant_id, from_office_id) REFERENCES offices(tenant_id, id) ON DELETE CASCADE,
+    CONSTRAINT transfers_tenant_id_to_office_id_fkey
+        FOREIGN KEY (tenant_id, to_office_id) REFERENCES offices(tenant_id, id) ON DELETE CASCADE,
+    CONSTRAINT transfers_status_check CHECK (status IN ('pending', 'applied', 'cancelled', 'failed')),
+    CONSTRAINT transfers_office_check CHECK (from_office_id <> to_office_id)
+);
+
+-- at most one pending transfer per employee
+CREATE UNIQUE INDEX transfers_employee_id_pending_key ON transfers (employee_id) WHERE status = 'pending';
+CREATE INDEX transfers_to_office_id_idx ON transfers (tenant_id, to_office_id);
+CREATE INDEX transfers_from_office_id_idx ON transfers (tenant_id, from_office_id);
+CREATE INDEX transfers_pending_effective_date_idx ON transfers (effective_date) WHERE status = 'pending';
@@ -0,0 +1,46 @@
+-- Proposed office and employee changes that are applied once every approval step signed off
+CREATE TABLE change_requests (
+    id SERIAL PRIMARY KEY,
+    tenant_id INT NOT NULL REFERENCES tenants(id),
+    change JSONB NOT NULL,
+    status TEXT NOT NULL,
+    requested_by VARCHAR(100) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    resolved_at TIMESTAMPTZ,
+    failure_reason TEXT,
+    CONSTRAINT change_requests_tenant_id_id_key UNIQUE (tenant_id, id),
+    -- expired is never stored, a pending request past expires_at reads as expired
+    CONSTRAINT change_requests_status_check CHECK (status IN ('pending', 'approved', 'applied', 'failed', 'rejected', 'withdrawn'))
+);
+
+CREATE INDEX change_requests_tenant_id_status_idx ON change_requests (tenant_id, status);
+
+-- One row per required sign-off, decided once
+CREATE TABLE change_request_approvals (
+    tenant_id INT NOT NULL,
+    change_request_id INT NOT NULL,
+    step VARCHAR(50) NOT NULL,
+    position INT NOT NULL, -- order the steps were listed in
+    decision TEXT,
+    decided_by VARCHAR(100),
+    decided_at TIMESTAMPTZ,
+    PRIMARY KEY (change_request_id, step),
+    CONSTRAINT change_request_approvals_change_request_fkey
+        FOREIGN KEY (tenant_id, change_request_id) REFERENCES change_requests(tenant_id, id) ON DELETE CASCADE,
+    CONSTRAINT change_request_approvals_decision_check CHECK (decision IN ('approved', 'rejected')),
+    CONSTRAINT change_request_approvals_decided_check CHECK ((decision IS NULL) = (decided_by IS NULL))
+);
+
+CREATE TABLE change_request_comments (
+    id SERIAL PRIMARY KEY,
+    tenant_id INT NOT NULL,
+    change_request_id INT NOT NULL,
+    author VARCHAR(100) NOT NULL,
+    body TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT change_request_comments_change_request_fkey
+        FOREIGN KEY (tenant_id, change_request_id) REFERENCES change_requests(tenant_id, id) ON DELETE CASCADE
+);
+
+CREATE INDEX change_request_comments_change_request_id_idx ON change_request_comments (change_request_id);
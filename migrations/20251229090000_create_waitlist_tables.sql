@@ -0,0 +1,40 @@
+-- Hires waiting for a seat in a full office, placed or offered by the waitlist job in order of arrival
+CREATE TABLE waitlist_entries (
+    id SERIAL PRIMARY KEY,
+    tenant_id INT NOT NULL REFERENCES tenants(id),
+    office_id INT NOT NULL,
+    first_name VARCHAR(100) NOT NULL,
+    last_name VARCHAR(100) NOT NULL,
+    birth_date DATE NOT NULL,
+    department_id INT,
+    manager_id INT,
+    hire_date DATE,
+    confirm BOOLEAN NOT NULL DEFAULT false,
+    status TEXT NOT NULL,
+    employee_id INT,
+    requested_by VARCHAR(100) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT waitlist_entries_tenant_id_id_key UNIQUE (tenant_id, id),
+    -- a deleted office takes its waitlist along
+    CONSTRAINT waitlist_entries_tenant_id_office_id_fkey
+        FOREIGN KEY (tenant_id, office_id) REFERENCES offices(tenant_id, id) ON DELETE CASCADE,
+    CONSTRAINT waitlist_entries_status_check CHECK (status IN ('waiting', 'offered', 'placed', 'declined', 'cancelled', 'failed'))
+);
+
+CREATE INDEX waitlist_entries_office_id_idx ON waitlist_entries (tenant_id, office_id);
+CREATE INDEX waitlist_entries_waiting_idx ON waitlist_entries (office_id) WHERE status = 'waiting';
+
+-- What happened to an entry, oldest first
+CREATE TABLE waitlist_events (
+    id SERIAL PRIMARY KEY,
+    tenant_id INT NOT NULL,
+    entry_id INT NOT NULL,
+    event TEXT NOT NULL,
+    details TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT waitlist_events_entry_fkey
+        FOREIGN KEY (tenant_id, entry_id) REFERENCES waitlist_entries(tenant_id, id) ON DELETE CASCADE,
+    CONSTRAINT waitlist_events_event_check CHECK (event IN ('enqueued', 'offered', 'placed', 'offer_lapsed', 'declined', 'cancelled', 'failed'))
+);
+
+CREATE INDEX waitlist_events_entry_id_idx ON waitlist_events (entry_id);
@@ -0,0 +1,14 @@
+-- address, timezone and coordinates of an office, all optional so existing offices stay valid
+ALTER TABLE offices ADD COLUMN street VARCHAR(100);
+ALTER TABLE offices ADD COLUMN postal_code VARCHAR(20);
+ALTER TABLE offices ADD COLUMN city VARCHAR(100);
+ALTER TABLE offices ADD COLUMN country CHAR(2); -- ISO 3166-1 alpha-2
+ALTER TABLE offices ADD COLUMN timezone VARCHAR(64); -- IANA name, checked by the service
+ALTER TABLE offices ADD COLUMN latitude DOUBLE PRECISION;
+ALTER TABLE offices ADD COLUMN longitude DOUBLE PRECISION;
+
+ALTER TABLE offices ADD CONSTRAINT offices_country_check CHECK (country ~ '^[A-Z]{2}$');
+ALTER TABLE offices ADD CONSTRAINT offices_latitude_check CHECK (latitude BETWEEN -90 AND 90);
+ALTER TABLE offices ADD CONSTRAINT offices_longitude_check CHECK (longitude BETWEEN -180 AND 180);
+-- a position needs both coordinates
+ALTER TABLE offices ADD CONSTRAINT offices_coordinates_check CHECK ((latitude IS NULL) = (longitude IS NULL));
@@ -0,0 +1,42 @@
+-- Location tree of a tenant: region -> site -> building -> floor -> room
+-- A site is an office, buildings, floors and rooms carry the office of the site above them.
+-- The capacity of a site is the max_occupancy of its office, the other levels may have their own
+CREATE TABLE locations (
+    id SERIAL PRIMARY KEY,
+    tenant_id INT NOT NULL REFERENCES tenants(id),
+    parent_id INT,
+    kind TEXT NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    capacity INT,
+    office_id INT,
+    CONSTRAINT locations_tenant_id_id_key UNIQUE (tenant_id, id),
+    -- a location with children cannot be deleted, deleting an office takes its site and everything below along
+    CONSTRAINT locations_tenant_id_parent_id_fkey
+        FOREIGN KEY (tenant_id, parent_id) REFERENCES locations(tenant_id, id),
+    CONSTRAINT locations_tenant_id_office_id_fkey
+        FOREIGN KEY (tenant_id, office_id) REFERENCES offices(tenant_id, id) ON DELETE CASCADE,
+    CONSTRAINT locations_kind_check CHECK (kind IN ('region', 'site', 'building', 'floor', 'room')),
+    CONSTRAINT locations_capacity_check CHECK (capacity IS NULL OR (capacity > 0 AND kind <> 'site')),
+    CONSTRAINT locations_office_id_check CHECK ((kind = 'region') = (office_id IS NULL))
+);
+
+-- one site per office
+CREATE UNIQUE INDEX locations_office_id_site_key ON locations (office_id) WHERE kind = 'site';
+CREATE INDEX locations_parent_id_idx ON locations (tenant_id, parent_id);
+
+-- Room of an employee, it counts while the employee stays in the office of the room
+CREATE TABLE location_assignments (
+    employee_id INT PRIMARY KEY,
+    tenant_id INT NOT NULL,
+    location_id INT NOT NULL,
+    CONSTRAINT location_assignments_tenant_id_employee_id_fkey
+        FOREIGN KEY (tenant_id, employee_id) REFERENCES employees(tenant_id, id) ON DELETE CASCADE,
+    CONSTRAINT location_assignments_tenant_id_location_id_fkey
+        FOREIGN KEY (tenant_id, location_id) REFERENCES locations(tenant_id, id) ON DELETE CASCADE
+);
+
+CREATE INDEX location_assignments_location_id_idx ON location_assignments (tenant_id, location_id);
+
+-- every existing office becomes a site at the top of the tree, its employees sit in the site until they get a room
+INSERT INTO locations (tenant_id, kind, name, office_id)
+SELECT tenant_id, 'site', name, id FROM offices;
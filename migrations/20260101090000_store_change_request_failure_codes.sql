@@ -0,0 +1,6 @@
+-- Why an approved change failed is kept as error codes with their arguments, so it can be shown in any language
+-- Reasons stored as text before are kept as the argument of change_request.failed
+ALTER TABLE change_requests ALTER COLUMN failure_reason TYPE JSONB USING
+    CASE WHEN failure_reason IS NULL THEN NULL
+    ELSE jsonb_build_array(jsonb_build_object('code', 'change_request.failed', 'args', jsonb_build_array(jsonb_build_array('reason', failure_reason))))
+    END;
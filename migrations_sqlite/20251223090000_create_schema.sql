@@ -0,0 +1,62 @@
+-- SQLite schema, mirrors the state of the Postgres migrations in ../migrations
+-- Foreign keys are only enforced with PRAGMA foreign_keys = ON, Settings sets it on every connection
+
+CREATE TABLE tenants (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR(100) NOT NULL UNIQUE CHECK (length(name) <= 100)
+);
+
+INSERT INTO tenants (id, name) VALUES (1, 'default');
+
+CREATE TABLE offices (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id),
+    name VARCHAR(100) NOT NULL CHECK (length(name) <= 100),
+    max_occupancy INTEGER NOT NULL CHECK (max_occupancy > 0),
+    UNIQUE (tenant_id, name),
+    UNIQUE (tenant_id, id)
+);
+
+CREATE TABLE employees (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id),
+    first_name VARCHAR(100) NOT NULL CHECK (length(first_name) <= 100),
+    last_name VARCHAR(100) NOT NULL CHECK (length(last_name) <= 100),
+    birth_date DATE NOT NULL,
+    office_id INTEGER NOT NULL,
+    FOREIGN KEY (tenant_id, office_id) REFERENCES offices(tenant_id, id)
+);
+
+-- CHECK constraints cannot use date('now'), triggers stand in for CHECK (birth_date < CURRENT_DATE)
+CREATE TRIGGER employees_birth_date_insert_check BEFORE INSERT ON employees
+WHEN NEW.birth_date >= date('now')
+BEGIN
+    SELECT RAISE(ABORT, 'employees_birth_date_check: birth_date must be in the past');
+END;
+
+CREATE TRIGGER employees_birth_date_update_check BEFORE UPDATE OF birth_date ON employees
+WHEN NEW.birth_date >= date('now')
+BEGIN
+    SELECT RAISE(ABORT, 'employees_birth_date_check: birth_date must be in the past');
+END;
+
+CREATE TABLE office_managers (
+    subject VARCHAR(100) NOT NULL CHECK (length(subject) <= 100),
+    office_id INTEGER NOT NULL REFERENCES offices(id) ON DELETE CASCADE,
+    PRIMARY KEY (subject, office_id)
+);
+
+CREATE TABLE idempotency_keys (
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id),
+    subject VARCHAR(100) NOT NULL,
+    route VARCHAR(100) NOT NULL,
+    idempotency_key VARCHAR(255) NOT NULL,
+    request_hash CHAR(64) NOT NULL,
+    status_code INTEGER,
+    content_type VARCHAR(100),
+    response_body BLOB,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (tenant_id, subject, route, idempotency_key)
+);
+
+CREATE INDEX idempotency_keys_created_at_idx ON idempotency_keys (created_at);
@@ -0,0 +1,24 @@
+-- Mirrors ../migrations/20251224090000_create_archived_employees_and_audit_entries.sql
+
+CREATE TABLE archived_employees (
+    id INTEGER PRIMARY KEY,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id),
+    first_name VARCHAR(100) NOT NULL,
+    last_name VARCHAR(100) NOT NULL,
+    birth_date DATE NOT NULL,
+    office_id INTEGER NOT NULL,
+    merged_into INTEGER REFERENCES employees(id) ON DELETE SET NULL,
+    archived_at TEXT NOT NULL
+);
+
+CREATE TABLE audit_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id),
+    subject VARCHAR(100) NOT NULL,
+    action VARCHAR(50) NOT NULL,
+    employee_id INTEGER,
+    details TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX audit_entries_employee_idx ON audit_entries (tenant_id, employee_id);
@@ -0,0 +1,40 @@
+-- Mirrors ../migrations/20251225090000_create_departments_and_manager_hierarchy.sql
+-- SQLite cannot SET NULL a single column of a composite foreign key, so manager_id references the ID only
+-- and the tenant of department and manager is checked by triggers
+
+CREATE TABLE departments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id),
+    name VARCHAR(100) NOT NULL CHECK (length(name) <= 100),
+    UNIQUE (tenant_id, name),
+    UNIQUE (tenant_id, id)
+);
+
+ALTER TABLE employees ADD COLUMN department_id INTEGER REFERENCES departments(id);
+ALTER TABLE employees ADD COLUMN manager_id INTEGER REFERENCES employees(id) ON DELETE SET NULL CHECK (manager_id <> id);
+
+CREATE INDEX employees_manager_id_idx ON employees (tenant_id, manager_id);
+
+CREATE TRIGGER employees_department_tenant_insert_check BEFORE INSERT ON employees
+WHEN NEW.department_id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM departments WHERE id = NEW.department_id AND tenant_id = NEW.tenant_id)
+BEGIN
+    SELECT RAISE(ABORT, 'FOREIGN KEY constraint failed: employees_tenant_id_department_id_fkey');
+END;
+
+CREATE TRIGGER employees_department_tenant_update_check BEFORE UPDATE OF department_id ON employees
+WHEN NEW.department_id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM departments WHERE id = NEW.department_id AND tenant_id = NEW.tenant_id)
+BEGIN
+    SELECT RAISE(ABORT, 'FOREIGN KEY constraint failed: employees_tenant_id_department_id_fkey');
+END;
+
+CREATE TRIGGER employees_manager_tenant_insert_check BEFORE INSERT ON employees
+WHEN NEW.manager_id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM employees WHERE id = NEW.manager_id AND tenant_id = NEW.tenant_id)
+BEGIN
+    SELECT RAISE(ABORT, 'FOREIGN KEY constraint failed: employees_tenant_id_manager_id_fkey');
+END;
+
+CREATE TRIGGER employees_manager_tenant_update_check BEFORE UPDATE OF manager_id ON employees
+WHEN NEW.manager_id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM employees WHERE id = NEW.manager_id AND tenant_id = NEW.tenant_id)
+BEGIN
+    SELECT RAISE(ABORT, 'FOREIGN KEY constraint failed: employees_tenant_id_manager_id_fkey');
+END;
@@ -0,0 +1,11 @@
+-- Mirrors ../migrations/20251226090000_add_employment_lifecycle.sql
+-- SQLite cannot add a column with a non-constant default, so existing employees get today's hire date in a second step
+-- and hire_date stays nullable at the schema level, the repository always writes it
+
+ALTER TABLE employees ADD COLUMN hire_date DATE;
+ALTER TABLE employees ADD COLUMN termination_date DATE CHECK (termination_date IS NULL OR termination_date >= hire_date);
+ALTER TABLE employees ADD COLUMN status TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('pending', 'active', 'on_leave', 'terminated'));
+
+UPDATE employees SET hire_date = date('now');
+
+CREATE INDEX employees_office_id_hire_date_idx ON employees (tenant_id, office_id, hire_date);
@@ -0,0 +1,25 @@
+-- Mirrors ../migrations/20251227090000_create_transfers_table.sql
+-- The composite foreign key to employees needs the UNIQUE (tenant_id, id) the Postgres schema already has
+
+CREATE UNIQUE INDEX employees_tenant_id_id_key ON employees (tenant_id, id);
+
+CREATE TABLE transfers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id),
+    employee_id INTEGER NOT NULL,
+    from_office_id INTEGER NOT NULL,
+    to_office_id INTEGER NOT NULL,
+    effective_date DATE NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'applied', 'cancelled', 'failed')),
+    requested_by VARCHAR(100) NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (tenant_id, employee_id) REFERENCES employees(tenant_id, id) ON DELETE CASCADE,
+    FOREIGN KEY (tenant_id, from_office_id) REFERENCES offices(tenant_id, id) ON DELETE CASCADE,
+    FOREIGN KEY (tenant_id, to_office_id) REFERENCES offices(tenant_id, id) ON DELETE CASCADE,
+    CHECK (from_office_id <> to_office_id)
+);
+
+CREATE UNIQUE INDEX transfers_employee_id_pending_key ON transfers (employee_id) WHERE status = 'pending';
+CREATE INDEX transfers_to_office_id_idx ON transfers (tenant_id, to_office_id);
+CREATE INDEX transfers_from_office_id_idx ON transfers (tenant_id, from_office_id);
+CREATE INDEX transfers_pending_effective_date_idx ON transfers (effective_date) WHERE status = 'pending';
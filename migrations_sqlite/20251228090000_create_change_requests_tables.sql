@@ -0,0 +1,41 @@
+-- Mirrors ../migrations/20251228090000_create_change_requests_tables.sql, change holds JSON text
+
+CREATE TABLE change_requests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id),
+    change TEXT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'approved', 'applied', 'failed', 'rejected', 'withdrawn')),
+    requested_by VARCHAR(100) NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    resolved_at TEXT,
+    failure_reason TEXT,
+    UNIQUE (tenant_id, id)
+);
+
+CREATE INDEX change_requests_tenant_id_status_idx ON change_requests (tenant_id, status);
+
+CREATE TABLE change_request_approvals (
+    tenant_id INTEGER NOT NULL,
+    change_request_id INTEGER NOT NULL,
+    step VARCHAR(50) NOT NULL,
+    position INTEGER NOT NULL,
+    decision TEXT CHECK (decision IN ('approved', 'rejected')),
+    decided_by VARCHAR(100),
+    decided_at TEXT,
+    PRIMARY KEY (change_request_id, step),
+    FOREIGN KEY (tenant_id, change_request_id) REFERENCES change_requests(tenant_id, id) ON DELETE CASCADE,
+    CHECK ((decision IS NULL) = (decided_by IS NULL))
+);
+
+CREATE TABLE change_request_comments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tenant_id INTEGER NOT NULL,
+    change_request_id INTEGER NOT NULL,
+    author VARCHAR(100) NOT NULL,
+    body TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (tenant_id, change_request_id) REFERENCES change_requests(tenant_id, id) ON DELETE CASCADE
+);
+
+CREATE INDEX change_request_comments_change_request_id_idx ON change_request_comments (change_request_id);
@@ -0,0 +1,35 @@
+-- Mirrors ../migrations/20251229090000_create_waitlist_tables.sql
+
+CREATE TABLE waitlist_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id),
+    office_id INTEGER NOT NULL,
+    first_name VARCHAR(100) NOT NULL,
+    last_name VARCHAR(100) NOT NULL,
+    birth_date DATE NOT NULL,
+    department_id INTEGER,
+    manager_id INTEGER,
+    hire_date DATE,
+    confirm BOOLEAN NOT NULL DEFAULT 0,
+    status TEXT NOT NULL CHECK (status IN ('waiting', 'offered', 'placed', 'declined', 'cancelled', 'failed')),
+    employee_id INTEGER,
+    requested_by VARCHAR(100) NOT NULL,
+    created_at TEXT NOT NULL,
+    UNIQUE (tenant_id, id),
+    FOREIGN KEY (tenant_id, office_id) REFERENCES offices(tenant_id, id) ON DELETE CASCADE
+);
+
+CREATE INDEX waitlist_entries_office_id_idx ON waitlist_entries (tenant_id, office_id);
+CREATE INDEX waitlist_entries_waiting_idx ON waitlist_entries (office_id) WHERE status = 'waiting';
+
+CREATE TABLE waitlist_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tenant_id INTEGER NOT NULL,
+    entry_id INTEGER NOT NULL,
+    event TEXT NOT NULL CHECK (event IN ('enqueued', 'offered', 'placed', 'offer_lapsed', 'declined', 'cancelled', 'failed')),
+    details TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (tenant_id, entry_id) REFERENCES waitlist_entries(tenant_id, id) ON DELETE CASCADE
+);
+
+CREATE INDEX waitlist_events_entry_id_idx ON waitlist_events (entry_id);
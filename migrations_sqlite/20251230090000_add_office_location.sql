@@ -0,0 +1,11 @@
+-- Mirrors ../migrations/20251230090000_add_office_location.sql
+-- SQLite cannot add table constraints to an existing table, the checks sit on the columns instead
+
+ALTER TABLE offices ADD COLUMN street VARCHAR(100);
+ALTER TABLE offices ADD COLUMN postal_code VARCHAR(20);
+ALTER TABLE offices ADD COLUMN city VARCHAR(100);
+ALTER TABLE offices ADD COLUMN country CHAR(2) CHECK (country GLOB '[A-Z][A-Z]');
+ALTER TABLE offices ADD COLUMN timezone VARCHAR(64);
+ALTER TABLE offices ADD COLUMN latitude DOUBLE PRECISION CHECK (latitude BETWEEN -90 AND 90);
+ALTER TABLE offices ADD COLUMN longitude DOUBLE PRECISION CHECK (longitude BETWEEN -180 AND 180)
+    CHECK ((latitude IS NULL) = (longitude IS NULL));
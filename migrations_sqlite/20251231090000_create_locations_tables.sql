@@ -0,0 +1,30 @@
+-- Mirrors ../migrations/20251231090000_create_locations_tables.sql
+
+CREATE TABLE locations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id),
+    parent_id INTEGER,
+    kind TEXT NOT NULL CHECK (kind IN ('region', 'site', 'building', 'floor', 'room')),
+    name VARCHAR(100) NOT NULL,
+    capacity INTEGER CHECK (capacity IS NULL OR (capacity > 0 AND kind <> 'site')),
+    office_id INTEGER CHECK ((kind = 'region') = (office_id IS NULL)),
+    UNIQUE (tenant_id, id),
+    FOREIGN KEY (tenant_id, parent_id) REFERENCES locations(tenant_id, id),
+    FOREIGN KEY (tenant_id, office_id) REFERENCES offices(tenant_id, id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX locations_office_id_site_key ON locations (office_id) WHERE kind = 'site';
+CREATE INDEX locations_parent_id_idx ON locations (tenant_id, parent_id);
+
+CREATE TABLE location_assignments (
+    employee_id INTEGER PRIMARY KEY,
+    tenant_id INTEGER NOT NULL,
+    location_id INTEGER NOT NULL,
+    FOREIGN KEY (tenant_id, employee_id) REFERENCES employees(tenant_id, id) ON DELETE CASCADE,
+    FOREIGN KEY (tenant_id, location_id) REFERENCES locations(tenant_id, id) ON DELETE CASCADE
+);
+
+CREATE INDEX location_assignments_location_id_idx ON location_assignments (tenant_id, location_id);
+
+INSERT INTO locations (tenant_id, kind, name, office_id)
+SELECT tenant_id, 'site', name, id FROM offices;
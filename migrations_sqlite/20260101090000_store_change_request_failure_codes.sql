@@ -0,0 +1,4 @@
+-- Mirrors ../migrations/20260101090000_store_change_request_failure_codes.sql, failure_reason holds JSON text
+UPDATE change_requests
+SET failure_reason = json_array(json_object('code', 'change_request.failed', 'args', json_array(json_array('reason', failure_reason))))
+WHERE failure_reason IS NOT NULL;
@@ -0,0 +1,4 @@
+pub mod permission;
+pub mod principal;
+pub mod scope;
+pub mod token;
\ No newline at end of file
@@ -0,0 +1,157 @@
+/// Roles and permissions
+/// A role is a named bundle of permissions, a route requires exactly one permission.
+///
+/// viewer: read offices, employees and departments
+/// hr_editor: viewer + create, update and delete employees and departments
+/// facilities_admin: viewer + create, update and delete offices
+/// office_manager: offices and departments + read and update employees, limited to managed offices (see auth::scope)
+/// admin: everything, including who manages which office
+///
+/// Change requests: every role reads them, hr_editor, facilities_admin and office_manager submit them,
+/// facilities_admin and office_manager approve the steps that are theirs (see service::change_request_service)
+/// Moving employees to another office and changing max_occupancy are only made through approved change requests,
+/// applied within the offices whose managers signed off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Viewer,
+    HrEditor,
+    FacilitiesAdmin,
+    OfficeManager,
+    Admin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    OfficesRead,
+    OfficesCreate,
+    OfficesUpdate,
+    OfficesDelete,
+    OfficesManagers,
+    EmployeesRead,
+    EmployeesCreate,
+    EmployeesUpdate,
+    EmployeesDelete,
+    DepartmentsRead,
+    DepartmentsCreate,
+    DepartmentsUpdate,
+    DepartmentsDelete,
+    ChangesRead,
+    ChangesSubmit,
+    ChangesApprove,
+}
+
+impl Role {
+    // Parses the role name used in tokens, unknown names are ignored by the caller
+    pub fn parse(name: &str) -> Option<Role> {
+        match name {
+            "viewer" => Some(Role::Viewer),
+            "hr_editor" => Some(Role::HrEditor),
+            "facilities_admin" => Some(Role::FacilitiesAdmin),
+            "office_manager" => Some(Role::OfficeManager),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    // Role name as used in tokens
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::HrEditor => "hr_editor",
+            Role::FacilitiesAdmin => "facilities_admin",
+            Role::OfficeManager => "office_manager",
+            Role::Admin => "admin",
+        }
+    }
+
+    // True if the role's permissions only apply to offices the subject manages
+    pub fn is_office_scoped(&self) -> bool {
+        matches!(self, Role::OfficeManager)
+    }
+
+    // Permissions granted by the role
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            Role::Viewer => &[OfficesRead, EmployeesRead, DepartmentsRead, ChangesRead],
+            Role::HrEditor => &[
+                OfficesRead, EmployeesRead, EmployeesCreate, EmployeesUpdate, EmployeesDelete,
+                DepartmentsRead, DepartmentsCreate, DepartmentsUpdate, DepartmentsDelete,
+                ChangesRead, ChangesSubmit,
+            ],
+            Role::FacilitiesAdmin => &[
+                OfficesRead, EmployeesRead, DepartmentsRead, OfficesCreate, OfficesUpdate, OfficesDelete,
+                ChangesRead, ChangesSubmit, ChangesApprove,
+            ],
+            Role::OfficeManager => &[OfficesRead, EmployeesRead, DepartmentsRead, EmployeesUpdate, ChangesRead, ChangesSubmit, ChangesApprove],
+            Role::Admin => &[
+                OfficesRead, OfficesCreate, OfficesUpdate, OfficesDelete, OfficesManagers,
+                EmployeesRead, EmployeesCreate, EmployeesUpdate, EmployeesDelete,
+                DepartmentsRead, DepartmentsCreate, DepartmentsUpdate, DepartmentsDelete,
+                ChangesRead, ChangesSubmit, ChangesApprove,
+            ],
+        }
+    }
+}
+
+impl Permission {
+    // Permission name, also used as OpenAPI security scope
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::OfficesRead => "offices:read",
+            Permission::OfficesCreate => "offices:create",
+            Permission::OfficesUpdate => "offices:update",
+            Permission::OfficesDelete => "offices:delete",
+            Permission::OfficesManagers => "offices:managers",
+            Permission::EmployeesRead => "employees:read",
+            Permission::EmployeesCreate => "employees:create",
+            Permission::EmployeesUpdate => "employees:update",
+            Permission::EmployeesDelete => "employees:delete",
+            Permission::DepartmentsRead => "departments:read",
+            Permission::DepartmentsCreate => "departments:create",
+            Permission::DepartmentsUpdate => "departments:update",
+            Permission::DepartmentsDelete => "departments:delete",
+            Permission::ChangesRead => "changes:read",
+            Permission::ChangesSubmit => "changes:submit",
+            Permission::ChangesApprove => "changes:approve",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_facilities_and_admin_update_offices() {
+        assert!(!Role::Viewer.permissions().contains(&Permission::OfficesUpdate));
+        assert!(!Role::HrEditor.permissions().contains(&Permission::OfficesUpdate));
+        assert!(Role::FacilitiesAdmin.permissions().contains(&Permission::OfficesUpdate));
+        assert!(Role::Admin.permissions().contains(&Permission::OfficesUpdate));
+    }
+
+    #[test]
+    fn test_only_hr_and_admin_create_employees() {
+        assert!(!Role::Viewer.permissions().contains(&Permission::EmployeesCreate));
+        assert!(!Role::FacilitiesAdmin.permissions().contains(&Permission::EmployeesCreate));
+        assert!(Role::HrEditor.permissions().contains(&Permission::EmployeesCreate));
+        assert!(Role::Admin.permissions().contains(&Permission::EmployeesCreate));
+    }
+
+    #[test]
+    fn test_viewers_read_but_do_not_decide_changes() {
+        assert!(Role::Viewer.permissions().contains(&Permission::ChangesRead));
+        assert!(!Role::Viewer.permissions().contains(&Permission::ChangesSubmit));
+        assert!(!Role::HrEditor.permissions().contains(&Permission::ChangesApprove));
+        assert!(Role::OfficeManager.permissions().contains(&Permission::ChangesApprove));
+        assert!(Role::FacilitiesAdmin.permissions().contains(&Permission::ChangesApprove));
+    }
+
+    #[test]
+    fn test_role_names_round_trip() {
+        for role in [Role::Viewer, Role::HrEditor, Role::FacilitiesAdmin, Role::OfficeManager, Role::Admin] {
+            assert_eq!(Role::parse(role.as_str()), Some(role));
+        }
+        assert_eq!(Role::parse("superuser"), None);
+    }
+}
@@ -0,0 +1,106 @@
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use crate::auth::permission::{Permission, Role};
+use crate::auth::token::TokenKeys;
+use crate::dto::problem_dto::ProblemDetails;
+
+/// Header naming the tenant of a request, it must match the token's tenant_id claim
+pub const TENANT_HEADER: &str = "x-tenant-id";
+
+/// Authenticated caller
+/// Inserted into the request extensions by `authenticate` and extracted by handlers.
+/// Handlers call `require` with the permission of the route before doing any work.
+/// Services read tenant_id to filter every query to the caller's tenant.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub subject: String,
+    pub roles: Vec<Role>,
+    pub tenant_id: i32,
+}
+
+impl Principal {
+    // True if any of the principal's roles grants the permission
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.roles.iter().any(|r| r.permissions().contains(&permission))
+    }
+
+    // True if the permission is only granted through office-scoped roles
+    pub fn is_office_scoped(&self, permission: Permission) -> bool {
+        !self.roles.iter().any(|r| !r.is_office_scoped() && r.permissions().contains(&permission))
+    }
+
+    // Ok if permitted, otherwise a 403 problem naming the missing permission
+    pub fn require(&self, permission: Permission) -> Result<(), ProblemDetails> {
+        if self.has_permission(permission) {
+            return Ok(());
+        }
+        tracing::warn!("Denied {} to {}", permission.as_str(), self.subject);
+        Err(ProblemDetails::forbidden(format!("Missing permission '{}'", permission.as_str())))
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Principal {
+    type Rejection = ProblemDetails;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Principal>()
+            .cloned()
+            .ok_or_else(|| ProblemDetails::unauthorized("Missing bearer token"))
+    }
+}
+
+/// Authentication middleware
+/// Verifies an `Authorization: Bearer <jwt>` header and stores the Principal in the request.
+/// Requests without the header pass through unauthenticated, invalid tokens are rejected with 401.
+///
+/// Tenant resolution: the token's tenant_id claim, tokens without one are rejected with 401.
+/// The X-Tenant-Id header cannot pick another tenant, a header contradicting the claim is rejected with 403.
+pub async fn authenticate(State(keys): State<Arc<TokenKeys>>, mut req: Request, next: Next) -> Response {
+    let header_tenant = match req.headers().get(TENANT_HEADER).map(|v| v.to_str().ok().and_then(|v| v.trim().parse::<i32>().ok())) {
+        None => None,
+        Some(Some(tenant_id)) => Some(tenant_id),
+        Some(None) => {
+            return ProblemDetails::new(StatusCode::BAD_REQUEST, "X-Tenant-Id must be an integer tenant id").into_response();
+        }
+    };
+
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string);
+
+    if let Some(token) = token {
+        match keys.verify(&token) {
+            Ok(claims) => {
+                let Some(tenant_id) = claims.tenant_id else {
+                    tracing::warn!("Rejected bearer token of {} without tenant_id claim", claims.sub);
+                    return ProblemDetails::unauthorized("Bearer token has no tenant_id claim").into_response();
+                };
+                if let Some(requested) = header_tenant
+                    && requested != tenant_id
+                {
+                    tracing::warn!("{} of tenant {} requested tenant {}", claims.sub, tenant_id, requested);
+                    return ProblemDetails::forbidden("Token is not valid for the requested tenant").into_response();
+                }
+                let roles = claims.roles.iter().filter_map(|r| Role::parse(r)).collect();
+                req.extensions_mut().insert(Principal { subject: claims.sub, roles, tenant_id });
+            }
+            Err(e) => {
+                tracing::warn!("Rejected bearer token: {}", e);
+                return ProblemDetails::unauthorized("Invalid or expired bearer token").into_response();
+            }
+        }
+    }
+
+    next.run(req).await
+}
@@ -0,0 +1,39 @@
+/// Office scope of a principal for one permission
+/// All: the permission comes from an unscoped role
+/// Offices: the permission only comes from office_manager, limited to the managed offices
+///
+/// Rows outside the scope are treated as if they did not exist, so out-of-scope ids give 404.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OfficeScope {
+    All,
+    Offices(Vec<i32>),
+}
+
+impl OfficeScope {
+    // True if rows of the office are visible within the scope
+    pub fn allows(&self, office_id: i32) -> bool {
+        match self {
+            OfficeScope::All => true,
+            OfficeScope::Offices(ids) => ids.contains(&office_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_allows_any_office() {
+        assert!(OfficeScope::All.allows(1));
+        assert!(OfficeScope::All.allows(42));
+    }
+
+    #[test]
+    fn test_offices_allows_only_listed() {
+        let scope = OfficeScope::Offices(vec![1, 3]);
+        assert!(scope.allows(3));
+        assert!(!scope.allows(2));
+        assert!(!OfficeScope::Offices(vec![]).allows(1));
+    }
+}
@@ -0,0 +1,56 @@
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::permission::Role;
+
+/// Bearer token handling
+/// Tokens are HS256 JWTs signed with the shared secret from AuthSettings.
+/// The identity provider issues them, the API only verifies them.
+///
+/// claims:
+/// sub: subject (user name or service id)
+/// roles: role names, see auth::permission::Role
+/// tenant_id: tenant the subject belongs to, required by the authentication middleware
+/// exp: expiry as unix timestamp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<i32>,
+    pub exp: i64,
+}
+
+#[derive(Clone)]
+pub struct TokenKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+impl TokenKeys {
+    /// Constructor for TokenKeys from the shared secret
+    pub fn from_secret(secret: &str) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+        }
+    }
+
+    /// Verifies signature and expiry and returns the claims
+    pub fn verify(&self, token: &str) -> anyhow::Result<Claims> {
+        let data = decode::<Claims>(token, &self.decoding, &Validation::default())?;
+        Ok(data.claims)
+    }
+
+    /// Issues a token, used by tests and for local development
+    pub fn issue(&self, subject: &str, roles: &[Role], tenant_id: Option<i32>, ttl: chrono::Duration) -> anyhow::Result<String> {
+        let claims = Claims {
+            sub: subject.to_string(),
+            roles: roles.iter().map(|r| r.as_str().to_string()).collect(),
+            tenant_id,
+            exp: (chrono::Utc::now() + ttl).timestamp(),
+        };
+        Ok(encode(&Header::default(), &claims, &self.encoding)?)
+    }
+}
@@ -0,0 +1,29 @@
+use std::sync::Arc;
+
+use crate::auth::token::TokenKeys;
+
+/// Authentication configuration
+/// Loads the shared JWT secret used to verify bearer tokens.
+/// Expected environment variables:
+/// - JWT_SECRET
+#[derive(Debug, Clone)]
+pub struct AuthSettings {
+    pub jwt_secret: String,
+}
+
+impl AuthSettings {
+    // Loads the JWT secret from the environment
+    // Panics if JWT_SECRET is missing
+    pub fn load_from_env() -> anyhow::Result<Self> {
+        tracing::info!("Loading authentication configuration");
+
+        let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set in env");
+
+        Ok(Self { jwt_secret })
+    }
+
+    // Builds the token keys shared by the authentication middleware
+    pub fn token_keys(&self) -> Arc<TokenKeys> {
+        Arc::new(TokenKeys::from_secret(&self.jwt_secret))
+    }
+}
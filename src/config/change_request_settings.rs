@@ -0,0 +1,24 @@
+/// Change request configuration
+/// Expected environment variables (optional):
+/// - CHANGE_REQUEST_TTL_DAYS: how long a change request can be decided when it names no expiry, defaults to 14
+#[derive(Debug, Clone)]
+pub struct ChangeRequestSettings {
+    pub ttl: chrono::Duration,
+}
+
+impl ChangeRequestSettings {
+    // Loads the default expiry from the environment, falling back to 14 days
+    pub fn load_from_env() -> anyhow::Result<Self> {
+        tracing::info!("Loading change request configuration");
+
+        let days = match std::env::var("CHANGE_REQUEST_TTL_DAYS") {
+            Ok(value) => value.parse::<i64>()?,
+            Err(_) => 14,
+        };
+        if days <= 0 {
+            anyhow::bail!("CHANGE_REQUEST_TTL_DAYS must be greater than 0");
+        }
+
+        Ok(Self { ttl: chrono::Duration::days(days) })
+    }
+}
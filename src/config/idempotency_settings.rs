@@ -0,0 +1,24 @@
+/// Idempotency configuration
+/// Expected environment variables (optional):
+/// - IDEMPOTENCY_TTL_HOURS: how long stored responses are replayed, defaults to 24
+#[derive(Debug, Clone)]
+pub struct IdempotencySettings {
+    pub ttl: chrono::Duration,
+}
+
+impl IdempotencySettings {
+    // Loads the replay window from the environment, falling back to 24 hours
+    pub fn load_from_env() -> anyhow::Result<Self> {
+        tracing::info!("Loading idempotency configuration");
+
+        let hours = match std::env::var("IDEMPOTENCY_TTL_HOURS") {
+            Ok(value) => value.parse::<i64>()?,
+            Err(_) => 24,
+        };
+        if hours <= 0 {
+            anyhow::bail!("IDEMPOTENCY_TTL_HOURS must be greater than 0");
+        }
+
+        Ok(Self { ttl: chrono::Duration::hours(hours) })
+    }
+}
@@ -1 +1,7 @@
-pub mod db_settings;
\ No newline at end of file
+pub mod db_settings;
+pub mod auth_settings;
+pub mod idempotency_settings;
+pub mod validation_settings;
+pub mod transfer_settings;
+pub mod change_request_settings;
+pub mod waitlist_settings;
\ No newline at end of file
@@ -0,0 +1,24 @@
+/// Transfer job configuration
+/// Expected environment variables (optional):
+/// - TRANSFER_JOB_INTERVAL_SECONDS: how often due transfers are applied, defaults to 300
+#[derive(Debug, Clone)]
+pub struct TransferSettings {
+    pub interval: std::time::Duration,
+}
+
+impl TransferSettings {
+    // Loads the job interval from the environment, falling back to 5 minutes
+    pub fn load_from_env() -> anyhow::Result<Self> {
+        tracing::info!("Loading transfer job configuration");
+
+        let seconds = match std::env::var("TRANSFER_JOB_INTERVAL_SECONDS") {
+            Ok(value) => value.parse::<u64>()?,
+            Err(_) => 300,
+        };
+        if seconds == 0 {
+            anyhow::bail!("TRANSFER_JOB_INTERVAL_SECONDS must be greater than 0");
+        }
+
+        Ok(Self { interval: std::time::Duration::from_secs(seconds) })
+    }
+}
@@ -0,0 +1,48 @@
+use crate::entity::validation_rules::ValidationRules;
+
+/// Validation configuration
+/// Expected environment variables (optional):
+/// - VALIDATION_RULES_FILE: JSON file with employee rules and tenant/office overrides, see entity::validation_rules.
+///   Without it the default rules apply: age 18 to 100, names of 1 to 100 characters
+#[derive(Debug, Clone)]
+pub struct ValidationSettings {
+    pub rules: ValidationRules,
+}
+
+impl ValidationSettings {
+    // Loads the rules file named in the environment, falling back to the default rules
+    pub fn load_from_env() -> anyhow::Result<Self> {
+        tracing::info!("Loading validation configuration");
+
+        let rules = match std::env::var("VALIDATION_RULES_FILE") {
+            Ok(path) if !path.is_empty() => {
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|e| anyhow::anyhow!("Cannot read VALIDATION_RULES_FILE {}: {}", path, e))?;
+                Self::parse(&content).map_err(|e| anyhow::anyhow!("Invalid VALIDATION_RULES_FILE {}: {}", path, e))?
+            }
+            _ => ValidationRules::default(),
+        };
+
+        Ok(Self { rules })
+    }
+
+    // Parses and checks a rules document
+    fn parse(content: &str) -> anyhow::Result<ValidationRules> {
+        let rules: ValidationRules = serde_json::from_str(content)?;
+        rules.check_consistency().map_err(|e| anyhow::anyhow!(e))?;
+        Ok(rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_file_is_checked() {
+        let rules = ValidationSettings::parse(r#"{ "employee": { "min_age": 16 } }"#).unwrap();
+        assert_eq!(rules.employee.min_age, 16);
+        assert!(ValidationSettings::parse(r#"{ "employee": { "min_age": 16, "max_age": 15 } }"#).is_err());
+        assert!(ValidationSettings::parse(r#"{ "employee": { "min_age": "sixteen" } }"#).is_err());
+    }
+}
@@ -0,0 +1,24 @@
+/// Waitlist job configuration
+/// Expected environment variables (optional):
+/// - WAITLIST_JOB_INTERVAL_SECONDS: how often freed seats are handed to waiting hires, defaults to 60
+#[derive(Debug, Clone)]
+pub struct WaitlistSettings {
+    pub interval: std::time::Duration,
+}
+
+impl WaitlistSettings {
+    // Loads the job interval from the environment, falling back to 1 minute
+    pub fn load_from_env() -> anyhow::Result<Self> {
+        tracing::info!("Loading waitlist job configuration");
+
+        let seconds = match std::env::var("WAITLIST_JOB_INTERVAL_SECONDS") {
+            Ok(value) => value.parse::<u64>()?,
+            Err(_) => 60,
+        };
+        if seconds == 0 {
+            anyhow::bail!("WAITLIST_JOB_INTERVAL_SECONDS must be greater than 0");
+        }
+
+        Ok(Self { interval: std::time::Duration::from_secs(seconds) })
+    }
+}
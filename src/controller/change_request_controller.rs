@@ -0,0 +1,343 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    response::IntoResponse,
+    http::StatusCode,
+    Json, Router,
+};
+use std::sync::Arc;
+use chrono::Utc;
+use crate::service::change_request_service::ChangeRequestService;
+use crate::entity::change_request::ChangeRequestDetails;
+use crate::dto::change_request_dto::{ChangeRequestListParams, ChangeRequestResponse, CommentRequest, CommentResponse, DecisionRequest, SubmitChangeRequest};
+use crate::dto::problem_dto::ProblemDetails;
+use crate::controller::negotiation::AcceptLanguage;
+use crate::utils::CodedError;
+use crate::utils::i18n::{localize, Language};
+use crate::auth::{permission::Permission, principal::Principal};
+
+/// Creates the change request API router.
+///
+/// Routes:
+/// Submit an office or employee change for approval: POST /change-requests
+/// List change requests: GET /change-requests?status=pending
+/// Get change request by ID: GET /change-requests/{id}
+/// Approve the caller's steps: POST /change-requests/{id}/approve
+/// Reject the request: POST /change-requests/{id}/reject
+/// Withdraw a pending request: POST /change-requests/{id}/withdraw
+/// Comment on a pending request: POST /change-requests/{id}/comments
+///
+/// Who signs off which step is described in service::change_request_service
+/// Bodies and responses are JSON, as the proposed change nests the office or employee data
+/// Every route requires a bearer token, the permission per route is listed in its OpenAPI security section
+/// Error and validation messages follow Accept-Language: Danish (da) or English (default)
+pub fn create_router(service: Arc<ChangeRequestService>) -> Router {
+    Router::new()
+        .route("/change-requests", post(submit_change_request).get(list_change_requests))
+        .route("/change-requests/{id}", get(get_change_request_by_id))
+        .route("/change-requests/{id}/approve", post(approve_change_request))
+        .route("/change-requests/{id}/reject", post(reject_change_request))
+        .route("/change-requests/{id}/withdraw", post(withdraw_change_request))
+        .route("/change-requests/{id}/comments", post(add_comment))
+        .with_state(service)
+}
+
+/// Submits a change for approval
+/// Expects the proposed change, an optional reason and an optional expiry as JSON body
+/// Success returns 201 Created with the pending request and its approval steps
+/// Failure returns 422 Unprocessable Entity listing every broken rule, 404 Not Found or 400 Bad Request
+#[utoipa::path(
+    post,
+    path = "/change-requests",
+    request_body = SubmitChangeRequest,
+    security(("bearer_auth" = ["changes:submit"])),
+    responses(
+        (status = 201, description = "Change request submitted", body = ChangeRequestResponse),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "Employee or office not found"),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission changes:submit", body = ProblemDetails)
+    )
+)]
+pub async fn submit_change_request(
+    State(service): State<Arc<ChangeRequestService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Json(req): Json<SubmitChangeRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::ChangesSubmit) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to submit a change request");
+    match service.submit_change_request(&principal, req.change, req.comment.as_deref(), req.expires_at).await {
+        Ok(created) => {
+            tracing::info!("Successfully submitted change request with ID: {:?}", created.request.id);
+            (StatusCode::CREATED, Json(created.to_response(Utc::now(), language))).into_response()
+        }
+        Err(e) => change_request_error(e, language),
+    }
+}
+
+/// Lists the change requests within the caller's scope
+/// Optionally ?status= to only list requests with that status, expired included
+/// Success returns 200 OK with the requests ordered by ID
+/// Failure returns 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/change-requests",
+    params(ChangeRequestListParams),
+    security(("bearer_auth" = ["changes:read"])),
+    responses(
+        (status = 200, description = "List of change requests", body = Vec<ChangeRequestResponse>),
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission changes:read", body = ProblemDetails)
+    )
+)]
+pub async fn list_change_requests(
+    State(service): State<Arc<ChangeRequestService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Query(params): Query<ChangeRequestListParams>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::ChangesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to list change requests");
+    match service.list_change_requests(&principal, params.status).await {
+        Ok(requests) => {
+            let now = Utc::now();
+            let response: Vec<_> = requests.iter().map(|r| r.to_response(now, language)).collect();
+            Json(response).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Error listing change requests: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Retrieves change request by ID
+/// Expects change request ID as a path parameter
+/// Success returns 200 OK with the request, its approval steps and comments
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/change-requests/{id}",
+    params(
+        ("id" = i32, Path, description = "Change request ID")
+    ),
+    security(("bearer_auth" = ["changes:read"])),
+    responses(
+        (status = 200, description = "Change request found", body = ChangeRequestResponse),
+        (status = 404, description = "Change request not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission changes:read", body = ProblemDetails)
+    )
+)]
+pub async fn get_change_request_by_id(
+    State(service): State<Arc<ChangeRequestService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::ChangesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to get change request by id: {}", id);
+    match service.find_change_request_by_id(&principal, id).await {
+        Ok(Some(details)) => Json(details.to_response(Utc::now(), language)).into_response(),
+        Ok(None) => {
+            tracing::warn!("Change request with id {} not found", id);
+            (StatusCode::NOT_FOUND, "Change request not found").into_response()
+        }
+        Err(e) => {
+            tracing::error!("Error finding change request {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Approves the steps of a pending request the caller signs off
+/// Expects change request ID as a path parameter and optionally a JSON body with a comment
+/// Success returns 200 OK with the request, applied or failed once every step approved
+/// Failure returns 409 Conflict when the request is no longer pending, 403 Forbidden for the requester
+/// or a caller with no open step to sign off, 422 Unprocessable Entity for an invalid comment or 404 Not Found
+#[utoipa::path(
+    post,
+    path = "/change-requests/{id}/approve",
+    params(
+        ("id" = i32, Path, description = "Change request ID")
+    ),
+    request_body(content = Option<DecisionRequest>),
+    security(("bearer_auth" = ["changes:approve"])),
+    responses(
+        (status = 200, description = "Steps approved", body = ChangeRequestResponse),
+        (status = 404, description = "Change request not found"),
+        (status = 409, description = "Change request was decided, withdrawn or expired already", body = ProblemDetails),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission changes:approve, own request or no step to sign off", body = ProblemDetails)
+    )
+)]
+pub async fn approve_change_request(
+    State(service): State<Arc<ChangeRequestService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Path(id): Path<i32>,
+    req: Option<Json<DecisionRequest>>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::ChangesApprove) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to approve change request with id: {}", id);
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+    render_change_request(service.approve_change_request(&principal, id, req.comment.as_deref()).await, language)
+}
+
+/// Rejects a pending request on the steps the caller signs off
+/// Expects change request ID as a path parameter and optionally a JSON body with a comment
+/// Success returns 200 OK with the rejected request
+/// Failure returns 409 Conflict when the request is no longer pending, 403 Forbidden for the requester
+/// or a caller with no open step to sign off, 422 Unprocessable Entity for an invalid comment or 404 Not Found
+#[utoipa::path(
+    post,
+    path = "/change-requests/{id}/reject",
+    params(
+        ("id" = i32, Path, description = "Change request ID")
+    ),
+    request_body(content = Option<DecisionRequest>),
+    security(("bearer_auth" = ["changes:approve"])),
+    responses(
+        (status = 200, description = "Change request rejected", body = ChangeRequestResponse),
+        (status = 404, description = "Change request not found"),
+        (status = 409, description = "Change request was decided, withdrawn or expired already", body = ProblemDetails),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission changes:approve, own request or no step to sign off", body = ProblemDetails)
+    )
+)]
+pub async fn reject_change_request(
+    State(service): State<Arc<ChangeRequestService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Path(id): Path<i32>,
+    req: Option<Json<DecisionRequest>>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::ChangesApprove) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to reject change request with id: {}", id);
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+    render_change_request(service.reject_change_request(&principal, id, req.comment.as_deref()).await, language)
+}
+
+/// Withdraws a pending request
+/// Expects change request ID as a path parameter, only the requester or an admin may withdraw
+/// Success returns 200 OK with the withdrawn request
+/// Failure returns 409 Conflict when the request is no longer pending, 403 Forbidden or 404 Not Found
+#[utoipa::path(
+    post,
+    path = "/change-requests/{id}/withdraw",
+    params(
+        ("id" = i32, Path, description = "Change request ID")
+    ),
+    security(("bearer_auth" = ["changes:submit"])),
+    responses(
+        (status = 200, description = "Change request withdrawn", body = ChangeRequestResponse),
+        (status = 404, description = "Change request not found"),
+        (status = 409, description = "Change request was decided, withdrawn or expired already", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission changes:submit or not the requester", body = ProblemDetails)
+    )
+)]
+pub async fn withdraw_change_request(
+    State(service): State<Arc<ChangeRequestService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::ChangesSubmit) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to withdraw change request with id: {}", id);
+    render_change_request(service.withdraw_change_request(&principal, id).await, language)
+}
+
+/// Comments on a pending request
+/// Expects change request ID as a path parameter and the comment as JSON body
+/// Success returns 201 Created with the comment
+/// Failure returns 422 Unprocessable Entity for a blank or too long comment, 409 Conflict when the request
+/// is no longer pending or 404 Not Found
+#[utoipa::path(
+    post,
+    path = "/change-requests/{id}/comments",
+    params(
+        ("id" = i32, Path, description = "Change request ID")
+    ),
+    request_body = CommentRequest,
+    security(("bearer_auth" = ["changes:submit"])),
+    responses(
+        (status = 201, description = "Comment added", body = CommentResponse),
+        (status = 404, description = "Change request not found"),
+        (status = 409, description = "Change request was decided, withdrawn or expired already", body = ProblemDetails),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission changes:submit", body = ProblemDetails)
+    )
+)]
+pub async fn add_comment(
+    State(service): State<Arc<ChangeRequestService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Path(id): Path<i32>,
+    Json(req): Json<CommentRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::ChangesSubmit) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to comment on change request with id: {}", id);
+    match service.add_comment(&principal, id, &req.body).await {
+        Ok(comment) => (StatusCode::CREATED, Json(comment.to_response())).into_response(),
+        Err(e) => change_request_error(e, language),
+    }
+}
+
+// Response of the routes changing a pending request
+fn render_change_request(result: anyhow::Result<ChangeRequestDetails>, language: Language) -> axum::response::Response {
+    match result {
+        Ok(details) => {
+            tracing::info!("Change request {:?} is {}", details.request.id, details.request.status);
+            Json(details.to_response(Utc::now(), language)).into_response()
+        }
+        Err(e) => change_request_error(e, language),
+    }
+}
+
+// Validation errors are 422, deciding without being an approver 403, a change of a settled request 409
+fn change_request_error(e: anyhow::Error, language: Language) -> axum::response::Response {
+    if let Some(problem) = ProblemDetails::from_validation_error(&e, language) {
+        tracing::warn!("Change request failed validation: {}", e);
+        return problem.into_response();
+    }
+    match e.downcast_ref::<CodedError>().map(|coded| coded.code.as_str()) {
+        Some("change_request.own_request" | "change_request.not_approver" | "change_request.not_requester") => {
+            tracing::warn!("Change request refused: {}", e);
+            return ProblemDetails::forbidden(localize(&e, language)).into_response();
+        }
+        Some("change_request.not_pending") => {
+            tracing::warn!("Change request refused: {}", e);
+            return ProblemDetails::new(StatusCode::CONFLICT, localize(&e, language)).into_response();
+        }
+        _ => {}
+    }
+    let error_msg = e.to_string();
+    tracing::warn!("Failed to process change request: {}", error_msg);
+    if error_msg.contains("does not exist") {
+        (StatusCode::NOT_FOUND, localize(&e, language)).into_response()
+    } else {
+        (StatusCode::BAD_REQUEST, localize(&e, language)).into_response()
+    }
+}
@@ -0,0 +1,265 @@
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    response::IntoResponse,
+    http::StatusCode,
+    Router,
+};
+use std::sync::Arc;
+use crate::service::department_service::DepartmentService;
+use crate::entity::department::Department;
+use crate::dto::department_dto::{CreateDepartmentRequest, DepartmentResponse};
+use crate::dto::problem_dto::ProblemDetails;
+use crate::controller::negotiation::{AcceptFormat, AcceptLanguage, Negotiated};
+use crate::utils::CodedError;
+use crate::utils::i18n::localize;
+use crate::auth::{permission::Permission, principal::Principal};
+
+/// Creates the department API router.
+///
+/// Routes:
+/// Create a new department: POST /departments
+/// Get department by ID: GET /departments/{id}
+/// List all departments: GET /departments
+/// Rename department by ID: PUT /departments/{id}
+/// Delete department by ID: DELETE /departments/{id}
+///
+/// Department bodies and responses follow Content-Type and Accept: JSON (default), CSV, XML or MessagePack
+/// Every route requires a bearer token, the permission per route is listed in its OpenAPI security section
+/// Error and validation messages follow Accept-Language: Danish (da) or English (default)
+pub fn create_router(service: Arc<DepartmentService>) -> Router {
+    Router::new()
+        .route("/departments", get(list_all_departments).post(create_department))
+        .route("/departments/{id}", get(get_department_by_id).put(update_department).delete(delete_department))
+        .with_state(service)
+}
+
+/// Creates department
+/// Expects body with department data as JSON, CSV, XML or MessagePack (Content-Type)
+/// Success returns 201 Created with department data
+/// Failure returns 422 Unprocessable Entity listing every broken rule, or 400 Bad Request with error message
+#[utoipa::path(
+    post,
+    path = "/departments",
+    request_body(content((CreateDepartmentRequest = "application/json"), (CreateDepartmentRequest = "text/csv"), (CreateDepartmentRequest = "application/xml"), (CreateDepartmentRequest = "application/msgpack"))),
+    security(("bearer_auth" = ["departments:create"])),
+    responses(
+        (status = 201, description = "Department created successfully", content((DepartmentResponse = "application/json"), (DepartmentResponse = "text/csv"), (DepartmentResponse = "application/xml"), (DepartmentResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request"),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 415, description = "Content-Type is not a supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission departments:create", body = ProblemDetails)
+    )
+)]
+pub async fn create_department(
+    State(service): State<Arc<DepartmentService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Negotiated(req): Negotiated<CreateDepartmentRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::DepartmentsCreate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to create department: {}", req.name);
+    let department = Department::from_create_request(req);
+
+    match service.add_department(&principal, &department).await {
+        Ok(created) => {
+            tracing::info!("Successfully created department with ID: {:?}", created.id);
+            (StatusCode::CREATED, format.render(&created.to_response())).into_response()
+        },
+        Err(e) => {
+            if let Some(problem) = ProblemDetails::from_validation_error(&e, language) {
+                tracing::warn!("Department failed validation: {}", e);
+                return problem.into_response();
+            }
+            tracing::warn!("Failed to process department creation: {}", e);
+            (StatusCode::BAD_REQUEST, localize(&e, language)).into_response()
+        }
+    }
+}
+
+/// Retrieves department by ID
+/// Expects department ID as a path parameter
+/// Success returns 200 OK with department data
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/departments/{id}",
+    params(
+        ("id" = i32, Path, description = "Department ID")
+    ),
+    security(("bearer_auth" = ["departments:read"])),
+    responses(
+        (status = 200, description = "Department found", content((DepartmentResponse = "application/json"), (DepartmentResponse = "text/csv"), (DepartmentResponse = "application/xml"), (DepartmentResponse = "application/msgpack"))),
+        (status = 404, description = "Department not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission departments:read", body = ProblemDetails)
+    )
+)]
+pub async fn get_department_by_id(
+    State(service): State<Arc<DepartmentService>>,
+    principal: Principal,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::DepartmentsRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to get department by id: {}", id);
+    match service.find_department_by_id(&principal, id).await {
+        Ok(Some(department)) => format.render(&department.to_response()),
+        Ok(None) => {
+            tracing::warn!("Department with id {} not found", id);
+            (StatusCode::NOT_FOUND, "Department not found").into_response()
+        }
+        Err(e) => {
+            tracing::error!("Error finding department {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Lists all departments
+/// No parameters required
+/// Success returns 200 OK with a list of departments
+/// Failure returns 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/departments",
+    security(("bearer_auth" = ["departments:read"])),
+    responses(
+        (status = 200, description = "List of all departments", content((Vec<DepartmentResponse> = "application/json"), (Vec<DepartmentResponse> = "text/csv"), (Vec<DepartmentResponse> = "application/xml"), (Vec<DepartmentResponse> = "application/msgpack"))),
+        (status = 500, description = "Internal server error"),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission departments:read", body = ProblemDetails)
+    )
+)]
+pub async fn list_all_departments(
+    State(service): State<Arc<DepartmentService>>,
+    principal: Principal,
+    AcceptFormat(format): AcceptFormat,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::DepartmentsRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to list all departments");
+    match service.list_all_departments(&principal).await {
+        Ok(departments) => {
+            let responses: Vec<_> = departments.iter().map(|d| d.to_response()).collect();
+            format.render_list(&responses)
+        }
+        Err(e) => {
+            tracing::error!("Failed to list departments: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Renames department by ID
+/// Expects department ID as a path parameter and body with the new name (JSON, CSV, XML or MessagePack)
+/// Success returns 200 OK with updated department data
+/// Failure returns 404 Not Found, 422 Unprocessable Entity listing every broken rule, or 400 Bad Request
+#[utoipa::path(
+    put,
+    path = "/departments/{id}",
+    params(
+        ("id" = i32, Path, description = "Department ID")
+    ),
+    request_body(content((CreateDepartmentRequest = "application/json"), (CreateDepartmentRequest = "text/csv"), (CreateDepartmentRequest = "application/xml"), (CreateDepartmentRequest = "application/msgpack"))),
+    security(("bearer_auth" = ["departments:update"])),
+    responses(
+        (status = 200, description = "Department updated successfully", content((DepartmentResponse = "application/json"), (DepartmentResponse = "text/csv"), (DepartmentResponse = "application/xml"), (DepartmentResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "Department not found"),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 415, description = "Content-Type is not a supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission departments:update", body = ProblemDetails)
+    )
+)]
+pub async fn update_department(
+    State(service): State<Arc<DepartmentService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+    Negotiated(req): Negotiated<CreateDepartmentRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::DepartmentsUpdate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to update department with id: {}", id);
+    let department = Department::from_create_request(req);
+
+    match service.update_department(&principal, id, &department).await {
+        Ok(updated) => (StatusCode::OK, format.render(&updated.to_response())).into_response(),
+        Err(e) => {
+            if let Some(problem) = ProblemDetails::from_validation_error(&e, language) {
+                tracing::warn!("Department failed validation: {}", e);
+                return problem.into_response();
+            }
+            let error_msg = e.to_string();
+            tracing::warn!("Failed to update department ID {}: {}", id, error_msg);
+            if error_msg.contains("does not exist") {
+                (StatusCode::NOT_FOUND, localize(&e, language)).into_response()
+            } else {
+                (StatusCode::BAD_REQUEST, localize(&e, language)).into_response()
+            }
+        }
+    }
+}
+
+/// Deletes department by ID
+/// Expects department ID as a path parameter
+/// Success returns 204 No Content
+/// Failure returns 404 Not Found, 409 Conflict while employees belong to it, or 500 Internal Server Error
+#[utoipa::path(
+    delete,
+    path = "/departments/{id}",
+    params(
+        ("id" = i32, Path, description = "Department ID")
+    ),
+    security(("bearer_auth" = ["departments:delete"])),
+    responses(
+        (status = 204, description = "Department deleted successfully"),
+        (status = 404, description = "Department not found"),
+        (status = 409, description = "Employees still belong to the department", body = ProblemDetails),
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission departments:delete", body = ProblemDetails)
+    )
+)]
+pub async fn delete_department(
+    State(service): State<Arc<DepartmentService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::DepartmentsDelete) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to delete department with id: {}", id);
+    match service.remove_department(&principal, id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => {
+            tracing::warn!("Failed as department not found for department with id: {}", id);
+            (StatusCode::NOT_FOUND, "Department not found").into_response()
+        }
+        Err(e) if e.downcast_ref::<CodedError>().is_some_and(|coded| coded.code == "department.has_employees") => {
+            tracing::warn!("Department {} still has employees: {}", id, e);
+            ProblemDetails::new(StatusCode::CONFLICT, localize(&e, language)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Error to delete department ID {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
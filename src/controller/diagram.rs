@@ -0,0 +1,190 @@
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use crate::dto::diagram_dto::{CompanyDiagram, OfficeDiagramNode};
+use crate::dto::problem_dto::ProblemDetails;
+use crate::controller::negotiation::{accepted_media_types, JSON_CONTENT_TYPE};
+
+pub const DOT_CONTENT_TYPE: &str = "text/vnd.graphviz; charset=utf-8";
+pub const MERMAID_CONTENT_TYPE: &str = "text/vnd.mermaid; charset=utf-8";
+
+// Fill of office nodes that have no free seats left
+const FULL_FILL: &str = "#f4cccc";
+const FULL_STROKE: &str = "#cc0000";
+
+/// Formats supported by GET /offices/diagram
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagramFormat {
+    Dot,
+    Mermaid,
+    Json,
+}
+
+impl DiagramFormat {
+    /// Parses the ?format= query value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "dot" | "graphviz" => Some(DiagramFormat::Dot),
+            "mermaid" => Some(DiagramFormat::Mermaid),
+            "json" => Some(DiagramFormat::Json),
+            _ => None,
+        }
+    }
+
+    // Maps a media type from the Accept header
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "text/vnd.graphviz" => Some(DiagramFormat::Dot),
+            "text/vnd.mermaid" => Some(DiagramFormat::Mermaid),
+            "application/json" | "application/*" | "*/*" => Some(DiagramFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Picks the format: ?format= wins, else the most preferred acceptable Accept entry, else JSON
+    /// Returns None when nothing requested can be produced
+    pub fn negotiate(format: Option<&str>, headers: &HeaderMap) -> Option<Self> {
+        if let Some(format) = format {
+            return DiagramFormat::parse(format);
+        }
+        match accepted_media_types(headers) {
+            None => Some(DiagramFormat::Json),
+            Some(media_types) => media_types.iter().find_map(|m| DiagramFormat::from_media_type(m)),
+        }
+    }
+}
+
+/// 406 answer for a format or Accept header the diagram cannot be drawn in
+pub fn not_acceptable() -> Response {
+    ProblemDetails::new(
+        StatusCode::NOT_ACCEPTABLE,
+        "The diagram is available as text/vnd.graphviz, text/vnd.mermaid or application/json (?format=dot|mermaid|json)",
+    )
+    .into_response()
+}
+
+/// Renders the diagram in the chosen format
+pub fn diagram_response(format: DiagramFormat, diagram: &CompanyDiagram) -> Response {
+    match format {
+        DiagramFormat::Dot => ([(header::CONTENT_TYPE, DOT_CONTENT_TYPE)], to_dot(diagram)).into_response(),
+        DiagramFormat::Mermaid => ([(header::CONTENT_TYPE, MERMAID_CONTENT_TYPE)], to_mermaid(diagram)).into_response(),
+        DiagramFormat::Json => ([(header::CONTENT_TYPE, JSON_CONTENT_TYPE)], Json(diagram)).into_response(),
+    }
+}
+
+// Label of an office node, name and occupancy versus max_occupancy
+fn office_label(office: &OfficeDiagramNode) -> String {
+    let full = if office.full { ", full" } else { "" };
+    format!("{} ({}/{}{})", office.name, office.occupancy, office.max_occupancy, full)
+}
+
+/// Graphviz DOT: one cluster per office with an office node pointing at its employees
+pub fn to_dot(diagram: &CompanyDiagram) -> String {
+    let mut dot = String::from("digraph company {\n    rankdir=LR;\n    node [shape=box];\n");
+    for office in &diagram.offices {
+        dot.push_str(&format!("    subgraph cluster_office_{} {{\n", office.id));
+        let style = if office.full {
+            format!(", style=filled, fillcolor=\"{}\", color=\"{}\"", FULL_FILL, FULL_STROKE)
+        } else {
+            String::new()
+        };
+        dot.push_str(&format!("        office_{} [label=\"{}\", shape=folder{}];\n", office.id, dot_escape(&office_label(office)), style));
+        for employee in &office.employees {
+            let name = format!("{} {}", employee.first_name, employee.last_name);
+            dot.push_str(&format!("        employee_{} [label=\"{}\"];\n", employee.id, dot_escape(&name)));
+            dot.push_str(&format!("        office_{} -> employee_{};\n", office.id, employee.id));
+        }
+        dot.push_str("    }\n");
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Mermaid flowchart: office nodes with edges to their employees, full offices get the `full` class
+pub fn to_mermaid(diagram: &CompanyDiagram) -> String {
+    let mut mermaid = String::from("flowchart TD\n");
+    let mut full = Vec::new();
+    for office in &diagram.offices {
+        mermaid.push_str(&format!("    office_{}[\"{}\"]\n", office.id, mermaid_escape(&office_label(office))));
+        for employee in &office.employees {
+            let name = format!("{} {}", employee.first_name, employee.last_name);
+            mermaid.push_str(&format!("    office_{} --> employee_{}[\"{}\"]\n", office.id, employee.id, mermaid_escape(&name)));
+        }
+        if office.full {
+            full.push(format!("office_{}", office.id));
+        }
+    }
+    if !full.is_empty() {
+        mermaid.push_str(&format!("    classDef full fill:{},stroke:{}\n", FULL_FILL, FULL_STROKE));
+        mermaid.push_str(&format!("    class {} full\n", full.join(",")));
+    }
+    mermaid
+}
+
+// Escapes a DOT double quoted string
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+// Mermaid labels cannot contain quotes, they are written as entity codes
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use crate::dto::diagram_dto::EmployeeDiagramNode;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    fn diagram() -> CompanyDiagram {
+        CompanyDiagram {
+            offices: vec![
+                OfficeDiagramNode {
+                    id: 1,
+                    name: "Vejle \"Nord\"".to_string(),
+                    occupancy: 1,
+                    max_occupancy: 1,
+                    full: true,
+                    employees: vec![EmployeeDiagramNode { id: 7, first_name: "Ida".to_string(), last_name: "Hansen".to_string() }],
+                },
+                OfficeDiagramNode { id: 2, name: "Aarhus".to_string(), occupancy: 0, max_occupancy: 5, full: false, employees: vec![] },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_negotiate_query_wins_over_accept() {
+        assert_eq!(DiagramFormat::negotiate(Some("mermaid"), &accept("text/vnd.graphviz")), Some(DiagramFormat::Mermaid));
+        assert_eq!(DiagramFormat::negotiate(Some("svg"), &HeaderMap::new()), None);
+        assert_eq!(DiagramFormat::negotiate(None, &HeaderMap::new()), Some(DiagramFormat::Json));
+        assert_eq!(DiagramFormat::negotiate(None, &accept("text/vnd.graphviz")), Some(DiagramFormat::Dot));
+        assert_eq!(DiagramFormat::negotiate(None, &accept("text/csv")), None);
+    }
+
+    #[test]
+    fn test_dot_highlights_full_offices_and_escapes_labels() {
+        let dot = to_dot(&diagram());
+        assert!(dot.starts_with("digraph company {"));
+        assert!(dot.contains("office_1 [label=\"Vejle \\\"Nord\\\" (1/1, full)\", shape=folder, style=filled, fillcolor=\"#f4cccc\""));
+        assert!(dot.contains("office_2 [label=\"Aarhus (0/5)\", shape=folder];"));
+        assert!(dot.contains("office_1 -> employee_7;"));
+    }
+
+    #[test]
+    fn test_mermaid_marks_full_offices() {
+        let mermaid = to_mermaid(&diagram());
+        assert!(mermaid.starts_with("flowchart TD\n"));
+        assert!(mermaid.contains("office_1[\"Vejle #quot;Nord#quot; (1/1, full)\"]"));
+        assert!(mermaid.contains("office_1 --> employee_7[\"Ida Hansen\"]"));
+        assert!(mermaid.ends_with("    class office_1 full\n"));
+    }
+}
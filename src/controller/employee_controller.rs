@@ -1,14 +1,30 @@
 use axum::{
-    extract::{Path, State},
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
     routing::{get, post},
     Json, Router,
     response::IntoResponse,
     http::StatusCode,
 };
 use std::sync::Arc;
+use axum::handler::Handler;
+use axum::middleware::from_fn_with_state;
 use crate::service::employee_service::EmployeeService;
-use crate::entity::employee::Employee;
-use crate::dto::employee_dto::{CreateEmployeeRequest, EmployeeResponse};
+use crate::entity::employee::{Employee, EmploymentStatus};
+use crate::dto::employee_dto::{CreateEmployeeRequest, DuplicateClusterResponse, EmployeeResponse, TerminateEmployeeRequest};
+use crate::dto::merge_dto::{MergeEmployeeRequest, MergeEmployeeResponse};
+use crate::dto::import_dto::{ImportEmployeeRow, ImportMode, ImportParams, ImportReport};
+use crate::dto::problem_dto::ProblemDetails;
+use crate::dto::validation_dto::{ValidationRulesParams, ValidationRulesResponse};
+use crate::controller::negotiation::{AcceptFormat, AcceptLanguage, MediaFormat, Negotiated};
+use crate::utils::CodedError;
+use crate::utils::i18n::{localize, Language};
+use crate::dto::export_dto::{EmployeeExportParams, EmployeeExportRow};
+use crate::controller::export::{export_response, not_acceptable, ExportFormat};
+use crate::controller::idempotency::idempotent;
+use crate::service::idempotency_service::IdempotencyService;
+use crate::auth::{permission::Permission, principal::Principal};
 
 /// Creates the employee API router.
 ///
@@ -17,46 +33,114 @@ use crate::dto::employee_dto::{CreateEmployeeRequest, EmployeeResponse};
 /// Get employee by ID: GET /employees/{id}
 /// List all employees: GET /employees
 /// List employees by office ID: GET /employees/office/{office_id}
+/// Bulk import employees from CSV or NDJSON: POST /employees/import
+/// Export employees as CSV, NDJSON or XLSX: GET /employees/export
+/// List probable duplicate employees: GET /employees/duplicates
 /// Update employee by ID: PUT /employees/{id}
 /// Delete employee by ID: DELETE /employees/{id}
-
-pub fn create_router(service: Arc<EmployeeService>) -> Router {
+/// Merge another employee into one: POST /employees/{id}/merge
+/// Direct reports of an employee: GET /employees/{id}/reports
+/// Managers above an employee up to the top: GET /employees/{id}/chain
+/// Everyone below an employee: GET /employees/{id}/subtree
+/// Start a pending employee or end a leave: POST /employees/{id}/activate
+/// Put an employee on leave: POST /employees/{id}/leave
+/// Terminate an employee with a last day: POST /employees/{id}/terminate
+/// Employee validation rules, optionally for an office: GET /validation-rules?office_id={office_id}
+///
+/// POST /employees accepts an Idempotency-Key header, retries with the same key and body replay the first response
+/// Employee bodies and responses follow Content-Type and Accept: JSON (default), CSV, XML or MessagePack
+/// Every route requires a bearer token, the permission per route is listed in its OpenAPI security section
+/// Error and validation messages follow Accept-Language: Danish (da) or English (default)
+/// Office managers only see their own offices' employees, anything else answers 404
+/// Creating an employee that probably exists already answers X-Possible-Duplicates or 409, see DuplicatePolicy
+pub fn create_router(service: Arc<EmployeeService>, idempotency: Arc<IdempotencyService>) -> Router {
 
     Router::new()
-        .route("/employees", post(create_employee).get(list_all_employees))
+        .route("/employees", post(create_employee.layer(from_fn_with_state(idempotency, idempotent))).get(list_all_employees))
         .route("/employees/{id}", get(get_employee_by_id).delete(delete_employee).put(update_employee))
         .route("/employees/office/{office_id}", get(list_employees_by_office_id))
+        .route("/employees/import", post(import_employees))
+        .route("/employees/export", get(export_employees))
+        .route("/employees/duplicates", get(list_duplicates))
+        .route("/employees/{id}/merge", post(merge_employees))
+        .route("/employees/{id}/reports", get(list_direct_reports))
+        .route("/employees/{id}/chain", get(get_reporting_chain))
+        .route("/employees/{id}/subtree", get(list_subtree))
+        .route("/employees/{id}/activate", post(activate_employee))
+        .route("/employees/{id}/leave", post(put_employee_on_leave))
+        .route("/employees/{id}/terminate", post(terminate_employee))
+        .route("/validation-rules", get(get_validation_rules))
         .with_state(service)
 }
 
+/// Response header listing the IDs of employees the created one probably duplicates, comma separated
+pub const POSSIBLE_DUPLICATES_HEADER: &str = "x-possible-duplicates";
+
 /// Creates employee
-/// Expects JSON body with employee data
-/// Success returns 201 Created with employee data
-/// Failure returns 400 Bad Request with error message
+/// Expects body with employee data as JSON, CSV, XML or MessagePack (Content-Type)
+/// Success returns 201 Created with employee data, and X-Possible-Duplicates when the person probably exists already
+/// Failure returns 422 Unprocessable Entity listing every broken rule, 409 Conflict for a blocked duplicate or an office with a waitlist,
+/// or 400 Bad Request with error message
 #[utoipa::path(
     post,
     path = "/employees",
-    request_body = CreateEmployeeRequest,
+    request_body(content((CreateEmployeeRequest = "application/json"), (CreateEmployeeRequest = "text/csv"), (CreateEmployeeRequest = "application/xml"), (CreateEmployeeRequest = "application/msgpack"))),
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Client chosen key, retries with the same key and body replay the first response")
+    ),
+    security(("bearer_auth" = ["employees:create"])),
     responses(
-        (status = 201, description = "Employee created successfully", body = EmployeeResponse),
-        (status = 400, description = "Bad request")
+        (status = 201, description = "Employee created successfully", content((EmployeeResponse = "application/json"), (EmployeeResponse = "text/csv"), (EmployeeResponse = "application/xml"), (EmployeeResponse = "application/msgpack")),
+            headers(("X-Possible-Duplicates" = String, description = "IDs of employees with the same normalized name and birth date, only when there are any"))),
+        (status = 400, description = "Bad request"),
+        (status = 409, description = "The duplicate policy blocks an employee with the same normalized name and birth date, or a request with the same Idempotency-Key is still being processed, or hires are waiting for a seat in the office", body = ProblemDetails),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule, or the Idempotency-Key was already used with a different body", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 415, description = "Content-Type is not a supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:create", body = ProblemDetails)
     )
 )]
 pub async fn create_employee(
     State(service): State<Arc<EmployeeService>>,
-    Json(req): Json<CreateEmployeeRequest>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Negotiated(req): Negotiated<CreateEmployeeRequest>,
 ) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesCreate) {
+        return denied.into_response();
+    }
     tracing::info!("Received request to create employee: {} {}", req.first_name, req.last_name);
     let employee = Employee::from_create_request(req);
     
-    match service.add_employee(&employee).await {
-        Ok(new_employee) => {
+    match service.add_employee_checked(&principal, &employee).await {
+        Ok((new_employee, duplicates)) => {
             tracing::info!("Successfully created employee with ID: {:?}", new_employee.id.unwrap());
-            (StatusCode::CREATED, Json(new_employee.to_response())).into_response()
+            let mut response = (StatusCode::CREATED, format.render(&new_employee.to_response())).into_response();
+            if !duplicates.is_empty() {
+                let ids: Vec<String> = duplicates.iter().map(|id| id.to_string()).collect();
+                if let Ok(value) = ids.join(",").parse() {
+                    response.headers_mut().insert(POSSIBLE_DUPLICATES_HEADER, value);
+                }
+            }
+            response
         },
         Err(e) => {
+            if let Some(problem) = ProblemDetails::from_validation_error(&e, language) {
+                tracing::warn!("Employee failed validation: {}", e);
+                return problem.into_response();
+            }
+            if e.downcast_ref::<CodedError>().is_some_and(|coded| coded.code == "employee.duplicate") {
+                tracing::warn!("Employee creation blocked as duplicate: {}", e);
+                return ProblemDetails::new(StatusCode::CONFLICT, localize(&e, language)).into_response();
+            }
+            if e.downcast_ref::<CodedError>().is_some_and(|coded| coded.code == "office.waitlisted") {
+                tracing::warn!("Employee creation blocked by the office waitlist: {}", e);
+                return ProblemDetails::new(StatusCode::CONFLICT, localize(&e, language)).into_response();
+            }
             tracing::warn!("Failed to process employee creation: {}", e);
-            (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response()
+            (StatusCode::BAD_REQUEST, Json(localize(&e, language))).into_response()
         }
     }
 }
@@ -71,29 +155,39 @@ pub async fn create_employee(
     params(
         ("id" = i32, Path, description = "Employee ID")
     ),
+    security(("bearer_auth" = ["employees:read"])),
     responses(
-        (status = 200, description = "Employee found", body = EmployeeResponse),
-        (status = 404, description = "Employee not found"),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "Employee found", content((EmployeeResponse = "application/json"), (EmployeeResponse = "text/csv"), (EmployeeResponse = "application/xml"), (EmployeeResponse = "application/msgpack"))),
+        (status = 404, description = "Employee not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
     )
 )]
 pub async fn get_employee_by_id(
     State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
     Path(id): Path<i32>,
 ) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
     tracing::info!("Received request to get employee by id: {}", id);
-    match service.find_employee_by_id(id).await {
+    match service.find_employee_by_id(&principal, id).await {
         Ok(Some(employee)) => {
             tracing::info!("Employee with id {} found", id);
-            Json(employee.to_response()).into_response()
+            format.render(&employee.to_response())
         }
         Ok(None) => {
             tracing::warn!("Employee with id {} not found", id);
-            (StatusCode::NOT_FOUND, "Employee not found").into_response()
+            employee_not_found(id, language)
         }
         Err(e) => {
             tracing::error!("Error finding employee {}: {}", id, e);
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+            ProblemDetails::internal(language).into_response()
         }
     }
 }
@@ -105,24 +199,34 @@ pub async fn get_employee_by_id(
 #[utoipa::path(
     get,
     path = "/employees",
+    security(("bearer_auth" = ["employees:read"])),
     responses(
-        (status = 200, description = "List of all employees", body = Vec<EmployeeResponse>),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "List of all employees", content((Vec<EmployeeResponse> = "application/json"), (Vec<EmployeeResponse> = "text/csv"), (Vec<EmployeeResponse> = "application/xml"), (Vec<EmployeeResponse> = "application/msgpack"))),
+        (status = 500, description = "Internal server error", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
     )
 )]
 pub async fn list_all_employees(
     State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
 ) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
     tracing::info!("Received request to list all employees");
-    match service.list_all_employees().await {
+    match service.list_all_employees(&principal).await {
         Ok(employees) => {
             tracing::info!("Successfully retrieved {} employees", employees.len());
             let response: Vec<_> = employees.into_iter().map(|e| e.to_response()).collect();
-            Json(response).into_response()
+            format.render_list(&response)
         },
         Err(e) => {
             tracing::error!("Error listing employees: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+            ProblemDetails::internal(language).into_response()
         }
     }
 }
@@ -137,69 +241,105 @@ pub async fn list_all_employees(
     params(
         ("office_id" = i32, Path, description = "Office ID")
     ),
+    security(("bearer_auth" = ["employees:read"])),
     responses(
-        (status = 200, description = "List of employees in office", body = Vec<EmployeeResponse>),
-        (status = 404, description = "Office not found"),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "List of employees in office", content((Vec<EmployeeResponse> = "application/json"), (Vec<EmployeeResponse> = "text/csv"), (Vec<EmployeeResponse> = "application/xml"), (Vec<EmployeeResponse> = "application/msgpack"))),
+        (status = 404, description = "Office not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
     )
 )]
 pub async fn list_employees_by_office_id(
     State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
     Path(office_id): Path<i32>,
 ) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
     tracing::info!("Received request to list employees for office id: {}", office_id);
 
-    match service.list_employees_by_office_id(office_id).await {
+    match service.list_employees_by_office_id(&principal, office_id).await {
         Ok(employees) => {
             tracing::info!("Successfully retrieved {} employees for office id {}", employees.len(), office_id);
             let response: Vec<_> = employees.into_iter().map(|e| e.to_response()).collect();
-            Json(response).into_response()
+            format.render_list(&response)
         },
         Err(e) => {
-            let error_msg = e.to_string();
-            if error_msg.contains("does not exist") {
-                tracing::warn!("Office lookup failed: {}", error_msg);
-                (StatusCode::NOT_FOUND, error_msg).into_response()
-            } else {
-                tracing::error!("Database error listing employees: {}", error_msg);
-                (StatusCode::INTERNAL_SERVER_ERROR, error_msg).into_response()
+            if let Some(problem) = ProblemDetails::from_not_found_error(&e, language) {
+                tracing::warn!("Office lookup failed: {}", e);
+                return problem.into_response();
             }
+            tracing::error!("Database error listing employees: {}", e);
+            ProblemDetails::internal(language).into_response()
         }
     }
 }
 
 /// Updates employee by ID
-/// Expects employee ID as a path parameter and JSON body with updated data
+/// Expects employee ID as a path parameter and body with updated data (JSON, CSV, XML or MessagePack),
+/// the hire date is kept when the body leaves hire_date out
 /// Success returns 200 OK with updated employee data
-/// Failure returns 400 Bad Request or 500 Internal Server Error
+/// Failure returns 422 Unprocessable Entity listing every broken rule, 400 Bad Request, 404 Not Found
+/// or 409 Conflict when the employee would change office without an approved change request
 #[utoipa::path(
     put,
     path = "/employees/{id}",
     params(
         ("id" = i32, Path, description = "Employee ID")
     ),
-    request_body = CreateEmployeeRequest,
+    request_body(content((CreateEmployeeRequest = "application/json"), (CreateEmployeeRequest = "text/csv"), (CreateEmployeeRequest = "application/xml"), (CreateEmployeeRequest = "application/msgpack"))),
+    security(("bearer_auth" = ["employees:update"])),
     responses(
-        (status = 200, description = "Employee updated successfully", body = EmployeeResponse),
-        (status = 400, description = "Bad request")
+        (status = 200, description = "Employee updated successfully", content((EmployeeResponse = "application/json"), (EmployeeResponse = "text/csv"), (EmployeeResponse = "application/xml"), (EmployeeResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "Employee or office not found", body = ProblemDetails),
+        (status = 409, description = "Moving to another office needs an approved change request", body = ProblemDetails),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 415, description = "Content-Type is not a supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:update", body = ProblemDetails)
     )
 )]
 pub async fn update_employee(
     State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
     Path(id): Path<i32>,
-    Json(req): Json<CreateEmployeeRequest>,
+    Negotiated(req): Negotiated<CreateEmployeeRequest>,
 ) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesUpdate) {
+        return denied.into_response();
+    }
     tracing::info!("Received request to update employee with id: {}", id);
+    let hire_date = req.hire_date; // kept when the request leaves it out
     let employee = Employee::from_create_request(req);
 
-    match service.update_employee(id, &employee).await {
+    match service.update_employee(&principal, id, &employee, hire_date).await {
         Ok(updated) => {
             tracing::info!("Successfully updated employee with id: {}", id);
-            (StatusCode::OK, Json(updated.to_response())).into_response()
+            (StatusCode::OK, format.render(&updated.to_response())).into_response()
         },
         Err(e) => {
+            if let Some(problem) = ProblemDetails::from_validation_error(&e, language) {
+                tracing::warn!("Employee failed validation: {}", e);
+                return problem.into_response();
+            }
+            if e.downcast_ref::<CodedError>().is_some_and(|coded| coded.code == "employee.move_needs_approval") {
+                tracing::warn!("Employee move refused without approval: {}", e);
+                return ProblemDetails::new(StatusCode::CONFLICT, localize(&e, language)).into_response();
+            }
             tracing::warn!("Failed to update employee ID {}: {}", id, e);
-            (StatusCode::BAD_REQUEST, e.to_string()).into_response()
+            if let Some(problem) = ProblemDetails::from_not_found_error(&e, language) {
+                return problem.into_response();
+            }
+            (StatusCode::BAD_REQUEST, localize(&e, language)).into_response()
         },
     }
 }
@@ -214,29 +354,561 @@ pub async fn update_employee(
     params(
         ("id" = i32, Path, description = "Employee ID")
     ),
+    security(("bearer_auth" = ["employees:delete"])),
     responses(
         (status = 204, description = "Employee deleted successfully"),
-        (status = 404, description = "Employee not found"),
-        (status = 500, description = "Internal server error")
+        (status = 404, description = "Employee not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:delete", body = ProblemDetails)
     )
 )]
 pub async fn delete_employee(
     State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
     Path(id): Path<i32>,
 ) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesDelete) {
+        return denied.into_response();
+    }
     tracing::info!("Received request to delete employee with id: {}", id);
-    match service.remove_employee(id).await {
+    match service.remove_employee(&principal, id).await {
         Ok(true) => {
             tracing::info!("Successfully deleted employee with id: {}", id);
             (StatusCode::NO_CONTENT).into_response()
         },
         Ok(false) => {
             tracing::warn!("Failed as employee not found for employee with id: {}", id);
-            (StatusCode::NOT_FOUND, "Employee not found").into_response()
+            employee_not_found(id, language)
         },
         Err(e) => {
             tracing::error!("Error deleting employee {}: {}", id, e);
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+            ProblemDetails::internal(language).into_response()
+        }
+    }
+}
+
+/// Bulk imports employees
+/// Expects a CSV (text/csv, with header row) or NDJSON (application/x-ndjson) body,
+/// see ImportEmployeeRow for the columns. Offices are given by office_id or office_name.
+/// Query: mode=atomic|partial (default atomic), dry_run=true|false (default false)
+/// Success returns 200 OK with a per-row report
+/// Failure returns 415 Unsupported Media Type, or 422 with the report if an atomic import has failing rows
+#[utoipa::path(
+    post,
+    path = "/employees/import",
+    params(ImportParams),
+    request_body(content = String, description = "CSV with header first_name,last_name,birth_date,office_id,office_name and optionally hire_date, or NDJSON with the same fields", content_type = "text/csv"),
+    security(("bearer_auth" = ["employees:create"])),
+    responses(
+        (status = 200, description = "Import report", body = ImportReport),
+        (status = 415, description = "Body is neither CSV nor NDJSON", body = ProblemDetails),
+        (status = 422, description = "Atomic import rejected, nothing was written", body = ImportReport),
+        (status = 500, description = "Internal server error", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:create", body = ProblemDetails)
+    )
+)]
+pub async fn import_employees(
+    State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Query(params): Query<ImportParams>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesCreate) {
+        return denied.into_response();
+    }
+    let content_type = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or("");
+    let media_type = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    tracing::info!("Received request to import employees as {}", media_type);
+
+    let rows = match media_type.as_str() {
+        "text/csv" => ImportEmployeeRow::parse_csv(&body),
+        "application/x-ndjson" | "application/ndjson" | "application/jsonl" => ImportEmployeeRow::parse_ndjson(&body),
+        _ => {
+            tracing::warn!("Unsupported import content type: {}", content_type);
+            let detail = CodedError::new("import.unsupported_media_type").message(language);
+            return ProblemDetails::new(StatusCode::UNSUPPORTED_MEDIA_TYPE, detail).into_response();
+        }
+    };
+
+    match service.import_employees(&principal, rows, params.mode, params.dry_run, language).await {
+        Ok(report) if report.mode == ImportMode::Atomic && report.failed > 0 => {
+            tracing::warn!("Atomic import rejected: {} of {} rows failed", report.failed, report.total);
+            (StatusCode::UNPROCESSABLE_ENTITY, Json(report)).into_response()
+        }
+        Ok(report) => {
+            tracing::info!("Import processed: {} imported, {} failed", report.imported, report.failed);
+            (StatusCode::OK, Json(report)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Error importing employees: {}", e);
+            ProblemDetails::internal(language).into_response()
+        }
+    }
+}
+
+/// Exports employees
+/// Format from ?format=csv|ndjson|xlsx or the Accept header, CSV by default
+/// Query: office_id limits to one office, include_office=true adds an office_name column
+/// Success returns 200 OK with the file as attachment, rows are streamed from the database
+/// Failure returns 404 Not Found for an unknown office, 406 Not Acceptable for an unsupported format
+#[utoipa::path(
+    get,
+    path = "/employees/export",
+    params(EmployeeExportParams),
+    security(("bearer_auth" = ["employees:read"])),
+    responses(
+        (status = 200, description = "Employee export", content(
+            (String = "text/csv"),
+            (EmployeeExportRow = "application/x-ndjson"),
+            (Vec<u8> = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+        )),
+        (status = 404, description = "Office not found", body = ProblemDetails),
+        (status = 406, description = "Requested format is not supported", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
+    )
+)]
+pub async fn export_employees(
+    State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Query(params): Query<EmployeeExportParams>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
+    let Some(format) = ExportFormat::negotiate(params.format.as_deref(), &headers) else {
+        tracing::warn!("Unsupported employee export format requested");
+        return not_acceptable();
+    };
+    tracing::info!("Received request to export employees as {:?}", format);
+
+    match service.export_employees(&principal, params.office_id, params.include_office).await {
+        Ok(rows) => export_response(format, EmployeeExportRow::headers(params.include_office), rows, "employees").await,
+        Err(e) => {
+            if let Some(problem) = ProblemDetails::from_not_found_error(&e, language) {
+                tracing::warn!("Employee export failed: {}", e);
+                return problem.into_response();
+            }
+            tracing::error!("Error exporting employees: {}", e);
+            ProblemDetails::internal(language).into_response()
+        }
+    }
+}
+
+/// Gets the employee validation rules of the caller's tenant
+/// Expects optional ?office_id= to include that office's overrides
+/// Success returns 200 OK with the rules and today's allowed birth date range
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/validation-rules",
+    params(ValidationRulesParams),
+    security(("bearer_auth" = ["employees:read"])),
+    responses(
+        (status = 200, description = "Effective validation rules", body = ValidationRulesResponse),
+        (status = 404, description = "Office not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
+    )
+)]
+pub async fn get_validation_rules(
+    State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Query(params): Query<ValidationRulesParams>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request for validation rules, office filter {:?}", params.office_id);
+
+    match service.validation_rules(&principal, params.office_id).await {
+        Ok(rules) => {
+            let today = chrono::Utc::now().date_naive();
+            Json(ValidationRulesResponse::new(principal.tenant_id, params.office_id, rules, today)).into_response()
+        }
+        Err(e) => {
+            if let Some(problem) = ProblemDetails::from_not_found_error(&e, language) {
+                tracing::warn!("Office lookup failed: {}", e);
+                return problem.into_response();
+            }
+            tracing::error!("Database error reading validation rules: {}", e);
+            ProblemDetails::internal(language).into_response()
+        }
+    }
+}
+
+/// Lists clusters of employees that are probably the same person
+/// Employees match on birth date and name, ignoring case, accents, spaces, hyphens and apostrophes
+/// Only employees within the caller's scope are considered
+/// Success returns 200 OK with the clusters, ordered by birth date
+/// Failure returns 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/employees/duplicates",
+    security(("bearer_auth" = ["employees:read"])),
+    responses(
+        (status = 200, description = "Probable duplicate clusters, empty when there are none", body = Vec<DuplicateClusterResponse>),
+        (status = 500, description = "Internal server error", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
+    )
+)]
+pub async fn list_duplicates(
+    State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to list duplicate employees");
+
+    match service.find_duplicates(&principal).await {
+        Ok(clusters) => {
+            let clusters: Vec<DuplicateClusterResponse> = clusters
+                .into_iter()
+                .map(|(birth_date, employees)| DuplicateClusterResponse {
+                    birth_date,
+                    employees: employees.iter().map(|e| e.to_response()).collect(),
+                })
+                .collect();
+            Json(clusters).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Database error searching duplicates: {}", e);
+            ProblemDetails::internal(language).into_response()
+        }
+    }
+}
+
+/// Merges a duplicate employee into this one
+/// Expects the surviving employee ID as path parameter and the source employee plus merge strategy as JSON body
+/// The source is archived, its records move to the survivor and the merge is audited, all in one transaction
+/// Needs both employees:update and employees:delete, as the source stops being an employee
+/// Success returns 200 OK with the surviving employee
+/// Failure returns 422 Unprocessable Entity for merged values breaking the rules, 404 Not Found or 400 Bad Request
+#[utoipa::path(
+    post,
+    path = "/employees/{id}/merge",
+    params(
+        ("id" = i32, Path, description = "ID of the surviving employee")
+    ),
+    request_body = MergeEmployeeRequest,
+    security(("bearer_auth" = ["employees:update", "employees:delete"])),
+    responses(
+        (status = 200, description = "Employees merged", body = MergeEmployeeResponse),
+        (status = 400, description = "Bad request, e.g. merging an employee into itself"),
+        (status = 404, description = "Employee not found", body = ProblemDetails),
+        (status = 422, description = "Merged values break validation rules, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:update or employees:delete", body = ProblemDetails)
+    )
+)]
+pub async fn merge_employees(
+    State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Path(id): Path<i32>,
+    Json(req): Json<MergeEmployeeRequest>,
+) -> impl IntoResponse {
+    for permission in [Permission::EmployeesUpdate, Permission::EmployeesDelete] {
+        if let Err(denied) = principal.require(permission) {
+            return denied.into_response();
+        }
+    }
+    tracing::info!("Received request to merge employee {} into {}", req.source_id, id);
+
+    match service.merge_employees(&principal, id, &req).await {
+        Ok((survivor, entry)) => {
+            tracing::info!("Merged employee {} into {}, audit entry {:?}", req.source_id, id, entry.id);
+            Json(MergeEmployeeResponse {
+                employee: survivor.to_response(),
+                archived_id: req.source_id,
+                audit_entry_id: entry.id.unwrap_or_default(),
+            })
+            .into_response()
+        }
+        Err(e) => {
+            if let Some(problem) = ProblemDetails::from_validation_error(&e, language) {
+                tracing::warn!("Merged employee failed validation: {}", e);
+                return problem.into_response();
+            }
+            tracing::warn!("Failed to merge employee {} into {}: {}", req.source_id, id, e);
+            if let Some(problem) = ProblemDetails::from_not_found_error(&e, language) {
+                return problem.into_response();
+            }
+            (StatusCode::BAD_REQUEST, localize(&e, language)).into_response()
+        }
+    }
+}
+
+/// Lists the employees reporting directly to an employee
+/// Expects employee ID as a path parameter
+/// Success returns 200 OK with the reports ordered by ID, those outside the caller's scope left out
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/employees/{id}/reports",
+    params(
+        ("id" = i32, Path, description = "Employee ID")
+    ),
+    security(("bearer_auth" = ["employees:read"])),
+    responses(
+        (status = 200, description = "Direct reports", content((Vec<EmployeeResponse> = "application/json"), (Vec<EmployeeResponse> = "text/csv"), (Vec<EmployeeResponse> = "application/xml"), (Vec<EmployeeResponse> = "application/msgpack"))),
+        (status = 404, description = "Employee not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
+    )
+)]
+pub async fn list_direct_reports(
+    State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to list direct reports of employee id: {}", id);
+    render_hierarchy(service.list_direct_reports(&principal, id).await, format, language)
+}
+
+/// Retrieves the reporting chain of an employee
+/// Expects employee ID as a path parameter
+/// Success returns 200 OK with the managers, nearest first and the top of the hierarchy last, empty without a manager
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/employees/{id}/chain",
+    params(
+        ("id" = i32, Path, description = "Employee ID")
+    ),
+    security(("bearer_auth" = ["employees:read"])),
+    responses(
+        (status = 200, description = "Managers above the employee", content((Vec<EmployeeResponse> = "application/json"), (Vec<EmployeeResponse> = "text/csv"), (Vec<EmployeeResponse> = "application/xml"), (Vec<EmployeeResponse> = "application/msgpack"))),
+        (status = 404, description = "Employee not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
+    )
+)]
+pub async fn get_reporting_chain(
+    State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request for the reporting chain of employee id: {}", id);
+    render_hierarchy(service.list_reporting_chain(&principal, id).await, format, language)
+}
+
+/// Lists everyone below an employee, direct and indirect reports
+/// Expects employee ID as a path parameter
+/// Success returns 200 OK with the employees level by level, by ID within a level; manager_id links them into a tree
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/employees/{id}/subtree",
+    params(
+        ("id" = i32, Path, description = "Employee ID")
+    ),
+    security(("bearer_auth" = ["employees:read"])),
+    responses(
+        (status = 200, description = "Employees below the employee", content((Vec<EmployeeResponse> = "application/json"), (Vec<EmployeeResponse> = "text/csv"), (Vec<EmployeeResponse> = "application/xml"), (Vec<EmployeeResponse> = "application/msgpack"))),
+        (status = 404, description = "Employee not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
+    )
+)]
+pub async fn list_subtree(
+    State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request for the subtree of employee id: {}", id);
+    render_hierarchy(service.list_subtree(&principal, id).await, format, language)
+}
+
+// 404 for an employee ID that is unknown or outside the caller's scope
+fn employee_not_found(id: i32, language: Language) -> axum::response::Response {
+    let detail = CodedError::new("employee.not_found").arg("id", id).message(language);
+    ProblemDetails::new(StatusCode::NOT_FOUND, detail).into_response()
+}
+
+// Response of the hierarchy routes, an unknown or out-of-scope employee is 404
+fn render_hierarchy(result: anyhow::Result<Vec<Employee>>, format: MediaFormat, language: Language) -> axum::response::Response {
+    match result {
+        Ok(employees) => {
+            let response: Vec<_> = employees.iter().map(|e| e.to_response()).collect();
+            format.render_list(&response)
+        }
+        Err(e) => {
+            if let Some(problem) = ProblemDetails::from_not_found_error(&e, language) {
+                tracing::warn!("Employee lookup failed: {}", e);
+                return problem.into_response();
+            }
+            tracing::error!("Database error reading the hierarchy: {}", e);
+            ProblemDetails::internal(language).into_response()
+        }
+    }
+}
+
+/// Makes an employee active: a pending employee on or after the hire date, or an employee returning from leave
+/// Expects employee ID as a path parameter
+/// Success returns 200 OK with the employee
+/// Failure returns 409 Conflict when the status does not allow it or the hire date lies ahead, 404 Not Found or 400 Bad Request
+#[utoipa::path(
+    post,
+    path = "/employees/{id}/activate",
+    params(
+        ("id" = i32, Path, description = "Employee ID")
+    ),
+    security(("bearer_auth" = ["employees:update"])),
+    responses(
+        (status = 200, description = "Employee is active", content((EmployeeResponse = "application/json"), (EmployeeResponse = "text/csv"), (EmployeeResponse = "application/xml"), (EmployeeResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "Employee not found", body = ProblemDetails),
+        (status = 409, description = "Not allowed from the current status, or the hire date lies ahead", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:update", body = ProblemDetails)
+    )
+)]
+pub async fn activate_employee(
+    State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesUpdate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to activate employee with id: {}", id);
+    render_transition(service.transition_employee(&principal, id, EmploymentStatus::Active, None).await, format, language)
+}
+
+/// Puts an active employee on leave, the seat is kept
+/// Expects employee ID as a path parameter
+/// Success returns 200 OK with the employee
+/// Failure returns 409 Conflict when the status does not allow it, 404 Not Found or 400 Bad Request
+#[utoipa::path(
+    post,
+    path = "/employees/{id}/leave",
+    params(
+        ("id" = i32, Path, description = "Employee ID")
+    ),
+    security(("bearer_auth" = ["employees:update"])),
+    responses(
+        (status = 200, description = "Employee is on leave", content((EmployeeResponse = "application/json"), (EmployeeResponse = "text/csv"), (EmployeeResponse = "application/xml"), (EmployeeResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "Employee not found", body = ProblemDetails),
+        (status = 409, description = "Not allowed from the current status", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:update", body = ProblemDetails)
+    )
+)]
+pub async fn put_employee_on_leave(
+    State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesUpdate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to put employee with id: {} on leave", id);
+    render_transition(service.transition_employee(&principal, id, EmploymentStatus::OnLeave, None).await, format, language)
+}
+
+/// Terminates an employee, the seat counts toward occupancy through the termination date
+/// Expects employee ID as a path parameter and the last day as JSON body
+/// Success returns 200 OK with the employee
+/// Failure returns 422 Unprocessable Entity for a termination before the hire date, 409 Conflict when already terminated,
+/// 404 Not Found or 400 Bad Request
+#[utoipa::path(
+    post,
+    path = "/employees/{id}/terminate",
+    params(
+        ("id" = i32, Path, description = "Employee ID")
+    ),
+    request_body = TerminateEmployeeRequest,
+    security(("bearer_auth" = ["employees:update"])),
+    responses(
+        (status = 200, description = "Employee is terminated", content((EmployeeResponse = "application/json"), (EmployeeResponse = "text/csv"), (EmployeeResponse = "application/xml"), (EmployeeResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "Employee not found", body = ProblemDetails),
+        (status = 409, description = "Employee is terminated already", body = ProblemDetails),
+        (status = 422, description = "Termination date before the hire date", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:update", body = ProblemDetails)
+    )
+)]
+pub async fn terminate_employee(
+    State(service): State<Arc<EmployeeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+    Json(req): Json<TerminateEmployeeRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesUpdate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to terminate employee with id: {} as of {}", id, req.termination_date);
+    let result = service.transition_employee(&principal, id, EmploymentStatus::Terminated, Some(req.termination_date)).await;
+    render_transition(result, format, language)
+}
+
+// Response of the lifecycle routes, transitions the state machine does not allow are 409
+fn render_transition(result: anyhow::Result<Employee>, format: MediaFormat, language: Language) -> axum::response::Response {
+    match result {
+        Ok(employee) => {
+            tracing::info!("Employee {:?} is now {}", employee.id, employee.status);
+            format.render(&employee.to_response())
+        }
+        Err(e) => {
+            if let Some(problem) = ProblemDetails::from_validation_error(&e, language) {
+                tracing::warn!("Employee transition failed validation: {}", e);
+                return problem.into_response();
+            }
+            if e.downcast_ref::<CodedError>().is_some_and(|coded| coded.code == "employee.invalid_transition" || coded.code == "employee.not_started") {
+                tracing::warn!("Employee transition refused: {}", e);
+                return ProblemDetails::new(StatusCode::CONFLICT, localize(&e, language)).into_response();
+            }
+            tracing::warn!("Failed to change employee status: {}", e);
+            if let Some(problem) = ProblemDetails::from_not_found_error(&e, language) {
+                return problem.into_response();
+            }
+            (StatusCode::BAD_REQUEST, localize(&e, language)).into_response()
         }
     }
 }
\ No newline at end of file
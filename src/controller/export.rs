@@ -0,0 +1,206 @@
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook};
+use chrono::Datelike;
+use crate::dto::export_dto::{ExportCell, ExportRecord};
+use crate::dto::problem_dto::ProblemDetails;
+use crate::controller::negotiation::accepted_media_types;
+
+pub const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+pub const XLSX_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// Formats supported by the export endpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+    Xlsx,
+}
+
+impl ExportFormat {
+    /// Parses the ?format= query value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "ndjson" | "jsonl" => Some(ExportFormat::Ndjson),
+            "xlsx" => Some(ExportFormat::Xlsx),
+            _ => None,
+        }
+    }
+
+    // Maps a media type from the Accept header
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "text/csv" | "text/*" | "*/*" => Some(ExportFormat::Csv),
+            "application/x-ndjson" | "application/ndjson" | "application/jsonl" => Some(ExportFormat::Ndjson),
+            XLSX_CONTENT_TYPE => Some(ExportFormat::Xlsx),
+            _ => None,
+        }
+    }
+
+    /// Picks the format: ?format= wins, else the most preferred acceptable Accept entry, else CSV
+    /// Returns None when nothing requested can be produced
+    pub fn negotiate(format: Option<&str>, headers: &HeaderMap) -> Option<Self> {
+        if let Some(format) = format {
+            return ExportFormat::parse(format);
+        }
+        match accepted_media_types(headers) {
+            None => Some(ExportFormat::Csv),
+            Some(media_types) => media_types.iter().find_map(|m| ExportFormat::from_media_type(m)),
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => CSV_CONTENT_TYPE,
+            ExportFormat::Ndjson => NDJSON_CONTENT_TYPE,
+            ExportFormat::Xlsx => XLSX_CONTENT_TYPE,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+}
+
+/// 406 answer for a format or Accept header the export cannot produce
+pub fn not_acceptable() -> Response {
+    ProblemDetails::new(
+        StatusCode::NOT_ACCEPTABLE,
+        "Export is available as text/csv, application/x-ndjson or xlsx (?format=csv|ndjson|xlsx)",
+    )
+    .into_response()
+}
+
+/// Builds the download response for an export
+/// CSV and NDJSON are written to the body row by row as they arrive from the database.
+/// XLSX is a zip archive that can only be finished after the last row, so its rows go to a
+/// constant memory worksheet (flushed to a temp file per row) and the file is sent when complete.
+/// A database error mid-stream aborts the body, clients see a truncated download instead of a status
+pub async fn export_response<R: ExportRecord>(
+    format: ExportFormat,
+    headers: Vec<&'static str>,
+    rows: BoxStream<'static, anyhow::Result<R>>,
+    file_stem: &str,
+) -> Response {
+    let disposition = format!("attachment; filename=\"{}.{}\"", file_stem, format.extension());
+    let rows = rows.inspect_err(|e| tracing::error!("Export aborted: {}", e));
+
+    let body = match format {
+        ExportFormat::Csv => {
+            let header_line = csv_line(headers.iter().map(|h| h.to_string()));
+            let lines = rows.map_ok(|row| csv_line(row.cells().into_iter().map(cell_text)));
+            Body::from_stream(stream::once(async move { Ok::<_, anyhow::Error>(header_line) }).chain(lines))
+        }
+        ExportFormat::Ndjson => Body::from_stream(rows.map(|row| {
+            let mut line = serde_json::to_vec(&row?)?;
+            line.push(b'\n');
+            Ok::<_, anyhow::Error>(line)
+        })),
+        ExportFormat::Xlsx => match xlsx_workbook(&headers, rows).await {
+            Ok(bytes) => Body::from(bytes),
+            Err(e) => {
+                tracing::error!("Error building xlsx export: {}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+            }
+        },
+    };
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, format.content_type().to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        body,
+    )
+        .into_response()
+}
+
+// Encodes one CSV record with quoting as needed
+fn csv_line(fields: impl Iterator<Item = String>) -> Vec<u8> {
+    let mut writer = csv::WriterBuilder::new().from_writer(Vec::new());
+    let fields: Vec<String> = fields.collect();
+    // writing to a Vec cannot fail
+    writer.write_record(&fields).expect("csv write to memory");
+    writer.into_inner().expect("csv flush to memory")
+}
+
+// Text form of a cell for CSV
+fn cell_text(cell: ExportCell) -> String {
+    match cell {
+        ExportCell::Int(value) => value.to_string(),
+        ExportCell::Text(value) => value,
+        ExportCell::Date(value) => value.to_string(),
+    }
+}
+
+// Writes header and rows to a single sheet, dates as real Excel dates
+async fn xlsx_workbook<R: ExportRecord>(
+    headers: &[&'static str],
+    mut rows: impl futures::Stream<Item = anyhow::Result<R>> + Unpin,
+) -> anyhow::Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    let date_format = Format::new().set_num_format("yyyy-mm-dd");
+    let sheet = workbook.add_worksheet_with_constant_memory();
+
+    for (col, name) in headers.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *name, &bold)?;
+    }
+    let mut row_nr: u32 = 1;
+    while let Some(row) = rows.next().await {
+        for (col, cell) in row?.cells().into_iter().enumerate() {
+            let col = col as u16;
+            match cell {
+                ExportCell::Int(value) => sheet.write_number(row_nr, col, value as f64)?,
+                ExportCell::Text(value) => sheet.write_string(row_nr, col, value)?,
+                ExportCell::Date(value) => {
+                    let date = ExcelDateTime::from_ymd(value.year() as u16, value.month() as u8, value.day() as u8)?;
+                    sheet.write_datetime_with_format(row_nr, col, &date, &date_format)?
+                }
+            };
+        }
+        row_nr += 1;
+    }
+    Ok(workbook.save_to_buffer()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_negotiate_query_wins_over_accept() {
+        assert_eq!(ExportFormat::negotiate(Some("xlsx"), &accept("text/csv")), Some(ExportFormat::Xlsx));
+        assert_eq!(ExportFormat::negotiate(Some("pdf"), &accept("text/csv")), None);
+    }
+
+    #[test]
+    fn test_negotiate_accept_header() {
+        assert_eq!(ExportFormat::negotiate(None, &HeaderMap::new()), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::negotiate(None, &accept("application/x-ndjson")), Some(ExportFormat::Ndjson));
+        assert_eq!(ExportFormat::negotiate(None, &accept("application/pdf, */*;q=0.1")), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::negotiate(None, &accept("text/csv;q=0, application/pdf")), None);
+        assert_eq!(ExportFormat::negotiate(None, &accept(XLSX_CONTENT_TYPE)), Some(ExportFormat::Xlsx));
+    }
+
+    #[test]
+    fn test_csv_line_quotes_fields() {
+        let line = csv_line(vec!["1".to_string(), "Hansen, Jens".to_string()].into_iter());
+        assert_eq!(line, b"1,\"Hansen, Jens\"\n");
+    }
+}
@@ -0,0 +1,55 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use std::sync::Arc;
+use crate::dto::health_dto::HealthResponse;
+use crate::service::health_service::HealthService;
+
+/// Creates the health probe router.
+///
+/// Routes:
+/// Liveness, the process is serving: GET /health/live
+/// Readiness, the database is reachable: GET /health/ready
+///
+/// The probes are meant for orchestrators and sit outside the bearer token authentication
+pub fn create_router(service: Arc<HealthService>) -> Router {
+    Router::new()
+        .route("/health/live", get(live))
+        .route("/health/ready", get(ready))
+        .with_state(service)
+}
+
+/// Liveness probe
+/// Always returns 200 OK while the server accepts requests
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    responses(
+        (status = 200, description = "Server is running", body = HealthResponse)
+    )
+)]
+pub async fn live() -> impl IntoResponse {
+    Json(HealthResponse { status: "live".to_string() })
+}
+
+/// Readiness probe
+/// Returns 200 OK once the database answers, 503 Service Unavailable until then
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    responses(
+        (status = 200, description = "Database is reachable", body = HealthResponse),
+        (status = 503, description = "Database is not reachable yet", body = HealthResponse)
+    )
+)]
+pub async fn ready(State(service): State<Arc<HealthService>>) -> impl IntoResponse {
+    if service.is_ready().await {
+        (StatusCode::OK, Json(HealthResponse { status: "ready".to_string() }))
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, Json(HealthResponse { status: "not_ready".to_string() }))
+    }
+}
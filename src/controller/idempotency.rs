@@ -0,0 +1,103 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use crate::auth::principal::Principal;
+use crate::dto::problem_dto::ProblemDetails;
+use crate::entity::idempotency_record::IdempotencyKey;
+use crate::service::idempotency_service::{IdempotencyOutcome, IdempotencyService};
+
+/// Request header carrying the client chosen key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Response header set when a stored response is replayed
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Idempotency middleware for POST handlers
+/// Without an Idempotency-Key header the request passes through unchanged.
+/// With a key the first response (anything but 5xx) is stored and replayed for retries with the same body.
+///
+/// Failures:
+/// 400 key is empty or longer than 255 characters
+/// 409 first request with the key is still running
+/// 422 key was already used with a different body
+pub async fn idempotent(State(service): State<Arc<IdempotencyService>>, req: Request, next: Next) -> Response {
+    let Some(raw_key) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(req).await;
+    };
+    let key = match raw_key.to_str().map(str::trim) {
+        Ok(key) if !key.is_empty() && key.len() <= 255 => key.to_string(),
+        _ => return ProblemDetails::new(StatusCode::BAD_REQUEST, "Idempotency-Key must be 1 to 255 visible characters").into_response(),
+    };
+    // unauthenticated requests are answered by the handler with 401, nothing to remember
+    let Some(principal) = req.extensions().get::<Principal>().cloned() else {
+        return next.run(req).await;
+    };
+
+    let key = IdempotencyKey {
+        tenant_id: principal.tenant_id,
+        subject: principal.subject,
+        route: format!("{} {}", req.method(), req.uri().path()),
+        key,
+    };
+    let (parts, body) = req.into_parts();
+    let bytes = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return ProblemDetails::new(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large").into_response(),
+    };
+
+    match service.begin(&key, &IdempotencyService::hash_body(&bytes)).await {
+        Ok(IdempotencyOutcome::Proceed) => {}
+        Ok(IdempotencyOutcome::Replay(record)) => {
+            let status = record.status_code
+                .and_then(|s| StatusCode::from_u16(s as u16).ok())
+                .unwrap_or(StatusCode::OK);
+            let mut response = (status, record.response_body.unwrap_or_default()).into_response();
+            if let Some(content_type) = record.content_type.and_then(|c| HeaderValue::from_str(&c).ok()) {
+                response.headers_mut().insert(header::CONTENT_TYPE, content_type);
+            }
+            response.headers_mut().insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+            return response;
+        }
+        Ok(IdempotencyOutcome::InProgress) => {
+            return ProblemDetails::new(StatusCode::CONFLICT, "A request with this Idempotency-Key is still being processed").into_response();
+        }
+        Ok(IdempotencyOutcome::Mismatch) => {
+            return ProblemDetails::new(StatusCode::UNPROCESSABLE_ENTITY, "Idempotency-Key was already used with a different request body").into_response();
+        }
+        Err(e) => {
+            tracing::error!("Idempotency lookup failed: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+        }
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+
+    // server errors are not remembered so the client can retry with the same key
+    if response.status().is_server_error() {
+        if let Err(e) = service.release(&key).await {
+            tracing::error!("Failed to release idempotency key {}: {}", key.key, e);
+        }
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to buffer response for idempotency key {}: {}", key.key, e);
+            let _ = service.release(&key).await;
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let content_type = parts.headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+    if let Err(e) = service.complete(&key, parts.status.as_u16(), content_type, &bytes).await {
+        tracing::error!("Failed to store response for idempotency key {}: {}", key.key, e);
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
@@ -0,0 +1,346 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, put},
+    response::IntoResponse,
+    http::StatusCode,
+    Router,
+};
+use std::sync::Arc;
+use crate::service::location_service::LocationService;
+use crate::dto::location_dto::{CreateLocationRequest, LocationResponse, UpdateLocationRequest};
+use crate::dto::problem_dto::ProblemDetails;
+use crate::controller::negotiation::{AcceptFormat, AcceptLanguage, Negotiated};
+use crate::utils::CodedError;
+use crate::utils::i18n::{localize, Language};
+use crate::auth::{permission::Permission, principal::Principal};
+
+/// Creates the location API router.
+///
+/// Routes:
+/// Create a region, site, building, floor or room: POST /locations
+/// List all locations in tree order: GET /locations
+/// Get location by ID: GET /locations/{id}
+/// Rename a location, set its capacity or move a site: PUT /locations/{id}
+/// Delete an empty location: DELETE /locations/{id}
+/// Put an employee in a room: PUT /locations/{id}/employees/{employee_id}
+/// Take an employee out of a room: DELETE /locations/{id}/employees/{employee_id}
+///
+/// Every location is reported with its capacity and occupancy rolled up from the levels below, see LocationService
+/// Location bodies and responses follow Content-Type and Accept: JSON (default), CSV, XML or MessagePack
+/// Every route requires a bearer token, the permission per route is listed in its OpenAPI security section
+/// Error and validation messages follow Accept-Language: Danish (da) or English (default)
+pub fn create_router(service: Arc<LocationService>) -> Router {
+    Router::new()
+        .route("/locations", get(list_locations).post(create_location))
+        .route("/locations/{id}", get(get_location_by_id).put(update_location).delete(delete_location))
+        .route("/locations/{id}/employees/{employee_id}", put(assign_employee).delete(unassign_employee))
+        .with_state(service)
+}
+
+/// Creates a new location
+/// Expects the kind, name, parent and either own capacity or (for a site) office as body (Content-Type)
+/// Success returns 201 Created with the location
+/// Failure returns 422 Unprocessable Entity listing every broken rule, 409 Conflict when the office has a site already,
+/// 404 Not Found for an unknown parent or office, or 400 Bad Request
+#[utoipa::path(
+    post,
+    path = "/locations",
+    request_body(content((CreateLocationRequest = "application/json"), (CreateLocationRequest = "text/csv"), (CreateLocationRequest = "application/xml"), (CreateLocationRequest = "application/msgpack"))),
+    security(("bearer_auth" = ["offices:create"])),
+    responses(
+        (status = 201, description = "Location created successfully", content((LocationResponse = "application/json"), (LocationResponse = "text/csv"), (LocationResponse = "application/xml"), (LocationResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "Parent location or office not found"),
+        (status = 409, description = "The office has a site already", body = ProblemDetails),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 415, description = "Content-Type is not a supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:create", body = ProblemDetails)
+    )
+)]
+pub async fn create_location(
+    State(service): State<Arc<LocationService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Negotiated(req): Negotiated<CreateLocationRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesCreate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to create {} location: {}", req.kind, req.name);
+    match service.create_location(&principal, req).await {
+        Ok(created) => {
+            tracing::info!("Successfully created location with ID: {:?}", created.id);
+            (StatusCode::CREATED, format.render(&created)).into_response()
+        }
+        Err(e) => location_error(e, language),
+    }
+}
+
+/// Lists all locations, each followed by the ones below it
+/// Success returns 200 OK with the locations, their rolled up capacity and occupancy
+/// Failure returns 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/locations",
+    security(("bearer_auth" = ["offices:read"])),
+    responses(
+        (status = 200, description = "List of all locations", content((Vec<LocationResponse> = "application/json"), (Vec<LocationResponse> = "text/csv"), (Vec<LocationResponse> = "application/xml"), (Vec<LocationResponse> = "application/msgpack"))),
+        (status = 500, description = "Internal server error"),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:read", body = ProblemDetails)
+    )
+)]
+pub async fn list_locations(
+    State(service): State<Arc<LocationService>>,
+    principal: Principal,
+    AcceptFormat(format): AcceptFormat,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to list all locations");
+    match service.list_locations(&principal).await {
+        Ok(locations) => format.render_list(&locations),
+        Err(e) => {
+            tracing::error!("Failed to list locations: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Retrieves location by ID
+/// Expects location ID as a path parameter
+/// Success returns 200 OK with the location, its rolled up capacity and occupancy
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/locations/{id}",
+    params(
+        ("id" = i32, Path, description = "Location ID")
+    ),
+    security(("bearer_auth" = ["offices:read"])),
+    responses(
+        (status = 200, description = "Location found", content((LocationResponse = "application/json"), (LocationResponse = "text/csv"), (LocationResponse = "application/xml"), (LocationResponse = "application/msgpack"))),
+        (status = 404, description = "Location not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:read", body = ProblemDetails)
+    )
+)]
+pub async fn get_location_by_id(
+    State(service): State<Arc<LocationService>>,
+    principal: Principal,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to get location by id: {}", id);
+    match service.find_location(&principal, id).await {
+        Ok(Some(location)) => format.render(&location),
+        Ok(None) => {
+            tracing::warn!("Location with id {} not found", id);
+            (StatusCode::NOT_FOUND, "Location not found").into_response()
+        }
+        Err(e) => {
+            tracing::error!("Error finding location {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Updates location by ID
+/// Expects location ID as a path parameter and the name, own capacity and parent as body (Content-Type)
+/// Success returns 200 OK with the updated location
+/// Failure returns 422 Unprocessable Entity listing every broken rule, 409 Conflict when the capacity would drop below
+/// the occupancy or the new region is full, 404 Not Found, or 400 Bad Request
+#[utoipa::path(
+    put,
+    path = "/locations/{id}",
+    params(
+        ("id" = i32, Path, description = "Location ID")
+    ),
+    request_body(content((UpdateLocationRequest = "application/json"), (UpdateLocationRequest = "text/csv"), (UpdateLocationRequest = "application/xml"), (UpdateLocationRequest = "application/msgpack"))),
+    security(("bearer_auth" = ["offices:update"])),
+    responses(
+        (status = 200, description = "Location updated successfully", content((LocationResponse = "application/json"), (LocationResponse = "text/csv"), (LocationResponse = "application/xml"), (LocationResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "Location not found"),
+        (status = 409, description = "Capacity below occupancy or the new region is full", body = ProblemDetails),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 415, description = "Content-Type is not a supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:update", body = ProblemDetails)
+    )
+)]
+pub async fn update_location(
+    State(service): State<Arc<LocationService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+    Negotiated(req): Negotiated<UpdateLocationRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesUpdate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to update location with id: {}", id);
+    match service.update_location(&principal, id, req).await {
+        Ok(updated) => (StatusCode::OK, format.render(&updated)).into_response(),
+        Err(e) => location_error(e, language),
+    }
+}
+
+/// Deletes location by ID
+/// Expects location ID as a path parameter
+/// Success returns 204 No Content
+/// Failure returns 404 Not Found, 409 Conflict while locations lie below it or employees sit in it,
+/// or 500 Internal Server Error
+#[utoipa::path(
+    delete,
+    path = "/locations/{id}",
+    params(
+        ("id" = i32, Path, description = "Location ID")
+    ),
+    security(("bearer_auth" = ["offices:delete"])),
+    responses(
+        (status = 204, description = "Location deleted successfully"),
+        (status = 404, description = "Location not found"),
+        (status = 409, description = "Locations lie below it or employees sit in it", body = ProblemDetails),
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:delete", body = ProblemDetails)
+    )
+)]
+pub async fn delete_location(
+    State(service): State<Arc<LocationService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesDelete) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to delete location with id: {}", id);
+    match service.delete_location(&principal, id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => {
+            tracing::warn!("Failed as location not found for location with id: {}", id);
+            (StatusCode::NOT_FOUND, "Location not found").into_response()
+        }
+        Err(e) if e.downcast_ref::<CodedError>().is_some_and(|coded| coded.code == "location.not_empty") => {
+            tracing::warn!("Location {} is not empty: {}", id, e);
+            ProblemDetails::new(StatusCode::CONFLICT, localize(&e, language)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Error to delete location ID {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Puts an employee in a room of the employee's office, replacing the room it had
+/// Expects room ID and employee ID as path parameters
+/// Success returns 200 OK with the room and its new occupancy
+/// Failure returns 409 Conflict when the room, or a level above it the employee does not sit in yet, is full
+/// or the room belongs to another office, 404 Not Found, or 400 Bad Request when the location is not a room
+#[utoipa::path(
+    put,
+    path = "/locations/{id}/employees/{employee_id}",
+    params(
+        ("id" = i32, Path, description = "Room ID"),
+        ("employee_id" = i32, Path, description = "Employee ID")
+    ),
+    security(("bearer_auth" = ["employees:update"])),
+    responses(
+        (status = 200, description = "Employee put in the room", content((LocationResponse = "application/json"), (LocationResponse = "text/csv"), (LocationResponse = "application/xml"), (LocationResponse = "application/msgpack"))),
+        (status = 400, description = "The location is not a room"),
+        (status = 404, description = "Room or employee not found"),
+        (status = 409, description = "A level has no free seat, or the room belongs to another office", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:update", body = ProblemDetails)
+    )
+)]
+pub async fn assign_employee(
+    State(service): State<Arc<LocationService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path((id, employee_id)): Path<(i32, i32)>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesUpdate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to put employee {} in location {}", employee_id, id);
+    match service.assign_employee(&principal, id, employee_id).await {
+        Ok(room) => format.render(&room),
+        Err(e) => location_error(e, language),
+    }
+}
+
+/// Takes an employee out of a room, the employee sits in the site of its office again
+/// Expects room ID and employee ID as path parameters
+/// Success returns 204 No Content
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    delete,
+    path = "/locations/{id}/employees/{employee_id}",
+    params(
+        ("id" = i32, Path, description = "Room ID"),
+        ("employee_id" = i32, Path, description = "Employee ID")
+    ),
+    security(("bearer_auth" = ["employees:update"])),
+    responses(
+        (status = 204, description = "Employee taken out of the room"),
+        (status = 404, description = "Employee not in the room"),
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:update", body = ProblemDetails)
+    )
+)]
+pub async fn unassign_employee(
+    State(service): State<Arc<LocationService>>,
+    principal: Principal,
+    Path((id, employee_id)): Path<(i32, i32)>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesUpdate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to take employee {} out of location {}", employee_id, id);
+    match service.unassign_employee(&principal, id, employee_id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, "Employee not in the room").into_response(),
+        Err(e) => {
+            tracing::error!("Error taking employee {} out of location {}: {}", employee_id, id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+// Maps errors of creating, changing and filling locations to responses
+fn location_error(e: anyhow::Error, language: Language) -> axum::response::Response {
+    if let Some(problem) = ProblemDetails::from_validation_error(&e, language) {
+        tracing::warn!("Location failed validation: {}", e);
+        return problem.into_response();
+    }
+    let conflict = |code: &str| matches!(code, "location.full" | "location.capacity_below_occupancy" | "location.site_exists" | "location.other_office");
+    if e.downcast_ref::<CodedError>().is_some_and(|coded| conflict(&coded.code)) {
+        tracing::warn!("Location change refused: {}", e);
+        return ProblemDetails::new(StatusCode::CONFLICT, localize(&e, language)).into_response();
+    }
+    let error_msg = e.to_string();
+    tracing::warn!("Failed to process location: {}", error_msg);
+    if error_msg.contains("does not exist") {
+        (StatusCode::NOT_FOUND, localize(&e, language)).into_response()
+    } else {
+        (StatusCode::BAD_REQUEST, localize(&e, language)).into_response()
+    }
+}
\ No newline at end of file
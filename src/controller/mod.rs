@@ -1,2 +1,13 @@
 pub mod office_controller;
-pub mod employee_controller;
\ No newline at end of file
+pub mod employee_controller;
+pub mod department_controller;
+pub mod transfer_controller;
+pub mod change_request_controller;
+pub mod waitlist_controller;
+pub mod location_controller;
+pub mod health_controller;
+pub mod idempotency;
+pub mod export;
+pub mod diagram;
+pub mod negotiation;
+pub mod request_scope;
\ No newline at end of file
@@ -0,0 +1,332 @@
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use crate::dto::department_dto::DepartmentResponse;
+use crate::dto::employee_dto::EmployeeResponse;
+use crate::dto::location_dto::LocationResponse;
+use crate::dto::office_dto::OfficeResponse;
+use crate::dto::problem_dto::ProblemDetails;
+use crate::dto::transfer_dto::TransferResponse;
+use crate::dto::waitlist_dto::{WaitlistEntryResponse, WaitlistEventResponse};
+use crate::utils::i18n::Language;
+
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+pub const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+pub const XML_CONTENT_TYPE: &str = "application/xml";
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// Wire formats for resource bodies
+/// JSON is the default, the others are picked with Accept (responses) and Content-Type (requests)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaFormat {
+    Json,
+    Csv,
+    Xml,
+    MessagePack,
+}
+
+/// Resource that can be rendered in every MediaFormat
+/// XML needs element names, CSV and MessagePack use the serde field names like JSON
+pub trait Representation: Serialize {
+    const ELEMENT: &'static str; // root element of one resource
+    const LIST_ELEMENT: &'static str; // root element wrapping a list
+}
+
+impl Representation for EmployeeResponse {
+    const ELEMENT: &'static str = "employee";
+    const LIST_ELEMENT: &'static str = "employees";
+}
+
+impl Representation for OfficeResponse {
+    const ELEMENT: &'static str = "office";
+    const LIST_ELEMENT: &'static str = "offices";
+}
+
+impl Representation for DepartmentResponse {
+    const ELEMENT: &'static str = "department";
+    const LIST_ELEMENT: &'static str = "departments";
+}
+
+impl Representation for TransferResponse {
+    const ELEMENT: &'static str = "transfer";
+    const LIST_ELEMENT: &'static str = "transfers";
+}
+
+impl Representation for WaitlistEntryResponse {
+    const ELEMENT: &'static str = "waitlist_entry";
+    const LIST_ELEMENT: &'static str = "waitlist_entries";
+}
+
+impl Representation for WaitlistEventResponse {
+    const ELEMENT: &'static str = "waitlist_event";
+    const LIST_ELEMENT: &'static str = "waitlist_events";
+}
+
+impl Representation for LocationResponse {
+    const ELEMENT: &'static str = "location";
+    const LIST_ELEMENT: &'static str = "locations";
+}
+
+/// Media types of an Accept header ordered by preference (q value, then position)
+/// Entries with q=0 are dropped. None when the header is missing or empty
+pub fn accepted_media_types(headers: &HeaderMap) -> Option<Vec<String>> {
+    let accept = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok())?;
+    if accept.trim().is_empty() {
+        return None;
+    }
+    let mut entries: Vec<(String, f32)> = accept
+        .split(',')
+        .map(|entry| {
+            let mut parts = entry.split(';');
+            let media_type = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+            let q = parts
+                .find_map(|param| param.trim().strip_prefix("q=").and_then(|q| q.trim().parse::<f32>().ok()))
+                .unwrap_or(1.0);
+            (media_type, q)
+        })
+        .filter(|(media_type, q)| !media_type.is_empty() && *q > 0.0)
+        .collect();
+    // stable sort keeps header order for equal q
+    entries.sort_by(|a, b| b.1.total_cmp(&a.1));
+    Some(entries.into_iter().map(|(media_type, _)| media_type).collect())
+}
+
+// Media type without parameters, lowercased
+fn essence(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase()
+}
+
+impl MediaFormat {
+    // Maps a media type to a format, wildcards fall back to JSON
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "application/json" | "application/*" | "*/*" => Some(MediaFormat::Json),
+            "text/csv" | "text/*" => Some(MediaFormat::Csv),
+            "application/xml" | "text/xml" => Some(MediaFormat::Xml),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Some(MediaFormat::MessagePack),
+            _ => None,
+        }
+    }
+
+    /// Picks the response format from the Accept header, JSON when absent
+    /// None when nothing acceptable can be produced
+    pub fn from_accept(headers: &HeaderMap) -> Option<Self> {
+        match accepted_media_types(headers) {
+            None => Some(MediaFormat::Json),
+            Some(media_types) => media_types.iter().find_map(|m| MediaFormat::from_media_type(m)),
+        }
+    }
+
+    /// Picks the request body format from Content-Type, JSON when absent
+    pub fn from_content_type(headers: &HeaderMap) -> Option<Self> {
+        match headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()) {
+            None => Some(MediaFormat::Json),
+            Some(content_type) => match essence(content_type).as_str() {
+                "" => Some(MediaFormat::Json),
+                "*/*" | "application/*" | "text/*" => None,
+                media_type => MediaFormat::from_media_type(media_type),
+            },
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            MediaFormat::Json => JSON_CONTENT_TYPE,
+            MediaFormat::Csv => CSV_CONTENT_TYPE,
+            MediaFormat::Xml => XML_CONTENT_TYPE,
+            MediaFormat::MessagePack => MSGPACK_CONTENT_TYPE,
+        }
+    }
+
+    /// Renders one resource, CSV is a header row plus one data row
+    pub fn render<T: Representation>(self, value: &T) -> Response {
+        let encoded = match self {
+            MediaFormat::Json => serde_json::to_vec(value).map_err(anyhow::Error::from),
+            MediaFormat::Csv => csv_rows(std::slice::from_ref(value)),
+            MediaFormat::Xml => quick_xml::se::to_string_with_root(T::ELEMENT, value)
+                .map(String::into_bytes)
+                .map_err(anyhow::Error::from),
+            MediaFormat::MessagePack => rmp_serde::to_vec_named(value).map_err(anyhow::Error::from),
+        };
+        self.respond(encoded)
+    }
+
+    /// Renders a list, CSV has one row per resource and XML wraps the items in LIST_ELEMENT
+    pub fn render_list<T: Representation>(self, values: &[T]) -> Response {
+        let encoded = match self {
+            MediaFormat::Json => serde_json::to_vec(values).map_err(anyhow::Error::from),
+            MediaFormat::Csv => csv_rows(values),
+            MediaFormat::Xml => xml_list(values),
+            MediaFormat::MessagePack => rmp_serde::to_vec_named(values).map_err(anyhow::Error::from),
+        };
+        self.respond(encoded)
+    }
+
+    fn respond(self, encoded: anyhow::Result<Vec<u8>>) -> Response {
+        match encoded {
+            Ok(body) => ([(header::CONTENT_TYPE, HeaderValue::from_static(self.content_type()))], body).into_response(),
+            Err(e) => {
+                tracing::error!("Failed to encode {:?} response: {}", self, e);
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+            }
+        }
+    }
+
+    /// Decodes a request body, a CSV body is a header row plus exactly one data row
+    pub fn decode<T: DeserializeOwned>(self, body: &[u8]) -> anyhow::Result<T> {
+        match self {
+            MediaFormat::Json => Ok(serde_json::from_slice(body)?),
+            MediaFormat::Csv => {
+                let mut rows = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(body).into_deserialize::<T>();
+                let row = rows.next().ok_or_else(|| anyhow::anyhow!("CSV body has no data row"))??;
+                if rows.next().is_some() {
+                    return Err(anyhow::anyhow!("CSV body must contain exactly one data row"));
+                }
+                Ok(row)
+            }
+            MediaFormat::Xml => Ok(quick_xml::de::from_str(std::str::from_utf8(body)?)?),
+            MediaFormat::MessagePack => Ok(rmp_serde::from_slice(body)?),
+        }
+    }
+}
+
+// Header row plus one row per value
+fn csv_rows<T: Serialize>(values: &[T]) -> anyhow::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for value in values {
+        writer.serialize(value)?;
+    }
+    writer.into_inner().map_err(|e| anyhow::anyhow!(e.to_string()))
+}
+
+// <employees><employee>..</employee>..</employees>
+fn xml_list<T: Representation>(values: &[T]) -> anyhow::Result<Vec<u8>> {
+    let mut xml = format!("<{}>", T::LIST_ELEMENT);
+    for value in values {
+        xml.push_str(&quick_xml::se::to_string_with_root(T::ELEMENT, value)?);
+    }
+    xml.push_str(&format!("</{}>", T::LIST_ELEMENT));
+    Ok(xml.into_bytes())
+}
+
+/// Response format negotiated from the Accept header
+/// Rejects with 406 Not Acceptable before the handler runs
+#[derive(Debug, Clone, Copy)]
+pub struct AcceptFormat(pub MediaFormat);
+
+impl<S: Send + Sync> FromRequestParts<S> for AcceptFormat {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        MediaFormat::from_accept(&parts.headers).map(AcceptFormat).ok_or_else(|| {
+            tracing::warn!("Not acceptable: {:?}", parts.headers.get(header::ACCEPT));
+            ProblemDetails::new(
+                StatusCode::NOT_ACCEPTABLE,
+                "Responses are available as application/json, text/csv, application/xml or application/msgpack",
+            )
+            .into_response()
+        })
+    }
+}
+
+/// Language of error messages picked from the Accept-Language header, English when absent or unsupported
+/// Never rejects, an unsupported language is no reason to fail the request
+pub struct AcceptLanguage(pub Language);
+
+impl<S: Send + Sync> FromRequestParts<S> for AcceptLanguage {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header = parts.headers.get(header::ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok());
+        Ok(AcceptLanguage(Language::from_accept_language(header)))
+    }
+}
+
+/// Request body decoded according to its Content-Type
+/// Replaces Json<T> for resource bodies: JSON, CSV, XML and MessagePack are accepted.
+/// Rejects with 415 for other content types and 400 for bodies that do not decode
+#[derive(Debug)]
+pub struct Negotiated<T>(pub T);
+
+impl<T: DeserializeOwned, S: Send + Sync> FromRequest<S> for Negotiated<T> {
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Some(format) = MediaFormat::from_content_type(req.headers()) else {
+            return Err(ProblemDetails::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "Request bodies are accepted as application/json, text/csv, application/xml or application/msgpack",
+            )
+            .into_response());
+        };
+        let body = Bytes::from_request(req, state).await.map_err(IntoResponse::into_response)?;
+        format.decode(&body).map(Negotiated).map_err(|e| {
+            tracing::warn!("Malformed {:?} request body: {}", format, e);
+            ProblemDetails::new(StatusCode::BAD_REQUEST, format!("Malformed request body: {}", e)).into_response()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::office_dto::CreateOfficeRequest;
+    use crate::entity::office::Office;
+
+    fn headers(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_accept_prefers_highest_q() {
+        let accept = headers(header::ACCEPT, "application/json;q=0.5, application/xml, text/csv;q=0.9");
+        assert_eq!(MediaFormat::from_accept(&accept), Some(MediaFormat::Xml));
+        assert_eq!(MediaFormat::from_accept(&HeaderMap::new()), Some(MediaFormat::Json));
+        assert_eq!(MediaFormat::from_accept(&headers(header::ACCEPT, "*/*")), Some(MediaFormat::Json));
+        assert_eq!(MediaFormat::from_accept(&headers(header::ACCEPT, "application/pdf, application/json;q=0")), None);
+    }
+
+    #[test]
+    fn test_content_type_defaults_to_json() {
+        assert_eq!(MediaFormat::from_content_type(&HeaderMap::new()), Some(MediaFormat::Json));
+        let msgpack = headers(header::CONTENT_TYPE, "application/x-msgpack");
+        assert_eq!(MediaFormat::from_content_type(&msgpack), Some(MediaFormat::MessagePack));
+        assert_eq!(MediaFormat::from_content_type(&headers(header::CONTENT_TYPE, "text/plain")), None);
+    }
+
+    #[test]
+    fn test_xml_list_wraps_items() {
+        let offices = vec![
+            Office { id: Some(1), name: "Aalborg".into(), max_occupancy: 10, ..Default::default() }.to_response(),
+            Office { id: Some(2), name: "Aarhus".into(), max_occupancy: 5, ..Default::default() }.to_response(),
+        ];
+        let xml = String::from_utf8(xml_list(&offices).unwrap()).unwrap();
+        assert_eq!(
+            xml,
+            "<offices><office><id>1</id><name>Aalborg</name><max_occupancy>10</max_occupancy>\
+             <street/><postal_code/><city/><country/><timezone/><latitude/><longitude/></office>\
+             <office><id>2</id><name>Aarhus</name><max_occupancy>5</max_occupancy>\
+             <street/><postal_code/><city/><country/><timezone/><latitude/><longitude/></office></offices>"
+        );
+    }
+
+    #[test]
+    fn test_decode_every_format() {
+        let csv: CreateOfficeRequest = MediaFormat::Csv.decode(b"name,max_occupancy\nAalborg,10\n").unwrap();
+        assert_eq!(csv.name, "Aalborg");
+        let xml: CreateOfficeRequest = MediaFormat::Xml
+            .decode(b"<office><name>Aalborg</name><max_occupancy>10</max_occupancy></office>")
+            .unwrap();
+        assert_eq!(xml.max_occupancy, 10);
+        let packed = rmp_serde::to_vec_named(&serde_json::json!({ "name": "Aalborg", "max_occupancy": 10 })).unwrap();
+        let msgpack: CreateOfficeRequest = MediaFormat::MessagePack.decode(&packed).unwrap();
+        assert_eq!(msgpack.name, "Aalborg");
+        assert!(MediaFormat::Csv.decode::<CreateOfficeRequest>(b"name,max_occupancy\nA,1\nB,2\n").is_err());
+    }
+}
@@ -1,14 +1,28 @@
 use axum::{
-    extract::{Path, State},
-    routing::{get, post},
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    routing::{delete, get, post},
     Json, Router,
     response::IntoResponse,
     http::StatusCode,
 };
 use std::sync::Arc;
+use axum::handler::Handler;
+use axum::middleware::from_fn_with_state;
 use crate::service::office_service::OfficeService;
 use crate::entity::office::Office;
-use crate::dto::office_dto::{CreateOfficeRequest, OfficeResponse};
+use crate::dto::office_dto::{CreateOfficeRequest, DeleteOfficeParams, OfficeListParams, OfficeResponse};
+use crate::dto::problem_dto::ProblemDetails;
+use crate::controller::negotiation::{AcceptFormat, AcceptLanguage, Negotiated};
+use crate::utils::i18n::localize;
+use crate::dto::export_dto::{OfficeExportParams, OfficeExportRow};
+use crate::controller::export::{export_response, not_acceptable, ExportFormat};
+use crate::controller::diagram::{self, diagram_response, DiagramFormat};
+use crate::dto::diagram_dto::{CompanyDiagram, OfficeDiagramParams};
+use crate::utils::CodedError;
+use crate::controller::idempotency::idempotent;
+use crate::service::idempotency_service::IdempotencyService;
+use crate::auth::{permission::Permission, principal::Principal};
 
 /// Creates the office API router.
 ///
@@ -16,44 +30,78 @@ use crate::dto::office_dto::{CreateOfficeRequest, OfficeResponse};
 /// Create a new office: POST /offices
 /// Get office by ID: GET /offices/{id}
 /// List all offices: GET /offices
+/// List offices within radius_km of a point, nearest first: GET /offices?near={lat},{lon}&radius_km={km}
+/// Export offices as CSV, NDJSON or XLSX: GET /offices/export
+/// Draw offices and their employees as Graphviz DOT, Mermaid or a JSON tree: GET /offices/diagram?office_ids=1,2
 /// Update office by ID: PUT /offices/{id}
-/// Delete office by ID: DELETE /offices/{id}
-
-pub fn create_router(service: Arc<OfficeService>) -> Router {
+/// Delete office by ID, optionally moving its employees: DELETE /offices/{id}?reassign_to={office_id}
+/// List managers of office: GET /offices/{id}/managers
+/// Assign office manager: PUT /offices/{id}/managers/{subject}
+/// Remove office manager: DELETE /offices/{id}/managers/{subject}
+///
+/// POST /offices accepts an Idempotency-Key header, retries with the same key and body replay the first response
+/// Office bodies and responses follow Content-Type and Accept: JSON (default), CSV, XML or MessagePack
+/// Every route requires a bearer token, the permission per route is listed in its OpenAPI security section
+/// Error and validation messages follow Accept-Language: Danish (da) or English (default)
+pub fn create_router(service: Arc<OfficeService>, idempotency: Arc<IdempotencyService>) -> Router {
     Router::new()
-        .route("/offices", post(create_office).get(list_all_offices))
+        .route("/offices", post(create_office.layer(from_fn_with_state(idempotency, idempotent))).get(list_all_offices))
+        .route("/offices/export", get(export_offices))
+        .route("/offices/diagram", get(get_office_diagram))
         .route("/offices/{id}", get(get_office_by_id). put(update_office).delete(delete_office))
+        .route("/offices/{id}/managers", get(list_office_managers))
+        .route("/offices/{id}/managers/{subject}", delete(remove_office_manager).put(assign_office_manager))
         .with_state(service)
 }
 
 /// Creates office
-/// Expects JSON body with office data
+/// Expects body with office data as JSON, CSV, XML or MessagePack (Content-Type)
 /// Success returns 201 Created with office data
-/// Failure returns 400 Bad Request with error message
+/// Failure returns 422 Unprocessable Entity listing every broken rule, or 400 Bad Request with error message
 #[utoipa::path(
     post,
     path = "/offices",
-    request_body = CreateOfficeRequest,
+    request_body(content((CreateOfficeRequest = "application/json"), (CreateOfficeRequest = "text/csv"), (CreateOfficeRequest = "application/xml"), (CreateOfficeRequest = "application/msgpack"))),
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Client chosen key, retries with the same key and body replay the first response")
+    ),
+    security(("bearer_auth" = ["offices:create"])),
     responses(
-        (status = 201, description = "Office created successfully", body = OfficeResponse),
-        (status = 400, description = "Bad request")
+        (status = 201, description = "Office created successfully", content((OfficeResponse = "application/json"), (OfficeResponse = "text/csv"), (OfficeResponse = "application/xml"), (OfficeResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request"),
+        (status = 409, description = "A request with the same Idempotency-Key is still being processed", body = ProblemDetails),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule, or the Idempotency-Key was already used with a different body", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 415, description = "Content-Type is not a supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:create", body = ProblemDetails)
     )
 )]
 pub async fn create_office(
     State(service): State<Arc<OfficeService>>,
-    Json(req): Json<CreateOfficeRequest>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Negotiated(req): Negotiated<CreateOfficeRequest>,
 ) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesCreate) {
+        return denied.into_response();
+    }
     tracing::info!("Received request to create office: {}", req.name);
     let office = Office::from_create_request(req);
     
-    match service.add_office(&office).await {
+    match service.add_office(&principal, &office).await {
         Ok(new_office) => {
             tracing::info!("Successfully created office with ID: {:?}", new_office.id.unwrap());
-            (StatusCode::CREATED, Json(new_office.to_response())).into_response()
+            (StatusCode::CREATED, format.render(&new_office.to_response())).into_response()
         },
         Err(e) => {
+            if let Some(problem) = ProblemDetails::from_validation_error(&e, language) {
+                tracing::warn!("Office failed validation: {}", e);
+                return problem.into_response();
+            }
             tracing::warn!("Failed to process office creation: {}", e);
-            (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response()
+            (StatusCode::BAD_REQUEST, Json(localize(&e, language))).into_response()
         }
     }
 }
@@ -68,21 +116,30 @@ pub async fn create_office(
     params(
         ("id" = i32, Path, description = "Office ID")
     ),
+    security(("bearer_auth" = ["offices:read"])),
     responses(
-        (status = 200, description = "Office found", body = OfficeResponse),
+        (status = 200, description = "Office found", content((OfficeResponse = "application/json"), (OfficeResponse = "text/csv"), (OfficeResponse = "application/xml"), (OfficeResponse = "application/msgpack"))),
         (status = 404, description = "Office not found"),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error"),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:read", body = ProblemDetails)
     )
 )]
 pub async fn get_office_by_id(
     State(service): State<Arc<OfficeService>>,
+    principal: Principal,
+    AcceptFormat(format): AcceptFormat,
     Path(id): Path<i32>,
 ) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesRead) {
+        return denied.into_response();
+    }
     tracing::info!("Received request to get office by id: {}", id);
-    match service.find_office_by_id(id).await {
+    match service.find_office_by_id(&principal, id).await {
         Ok(Some(office)) => {
             tracing::info!("Office with id {} found", id);
-            Json(office.to_response()).into_response()
+            format.render(&office.to_response())
         }
         Ok(None) => {
             tracing::warn!("Office with id {} not found", id);
@@ -95,27 +152,64 @@ pub async fn get_office_by_id(
     }
 }
 
-/// Lists all offices
-/// No parameters required
+/// Lists all offices, or the offices near a point
+/// Optional query parameters near=lat,lon and radius_km list the offices within radius_km, nearest first with distance_km
 /// Success returns 200 OK with a list of offices
-/// Failure returns 500 Internal Server Error
+/// Failure returns 422 Unprocessable Entity for a malformed near or radius_km, or 500 Internal Server Error
 #[utoipa::path(
     get,
     path = "/offices",
+    params(OfficeListParams),
+    security(("bearer_auth" = ["offices:read"])),
     responses(
-        (status = 200, description = "List of all offices", body = Vec<OfficeResponse>),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "List of all offices, or the offices within radius_km of near ordered by distance", content((Vec<OfficeResponse> = "application/json"), (Vec<OfficeResponse> = "text/csv"), (Vec<OfficeResponse> = "application/xml"), (Vec<OfficeResponse> = "application/msgpack"))),
+        (status = 422, description = "near or radius_km is malformed, missing its counterpart or out of range, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 500, description = "Internal server error"),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:read", body = ProblemDetails)
     )
 )]
 pub async fn list_all_offices(
     State(service): State<Arc<OfficeService>>,
+    principal: Principal,
+    Query(params): Query<OfficeListParams>,
+    AcceptFormat(format): AcceptFormat,
+    AcceptLanguage(language): AcceptLanguage,
 ) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesRead) {
+        return denied.into_response();
+    }
+    let area = match params.area() {
+        Ok(area) => area,
+        Err(errors) => {
+            tracing::warn!("Invalid nearby office search: {}", errors);
+            return ProblemDetails::validation(&errors.localized(language)).into_response();
+        }
+    };
+    if let Some(area) = area {
+        tracing::info!("Received request to list offices near {},{}", area.latitude, area.longitude);
+        return match service.list_offices_near(&principal, &area).await {
+            Ok(offices) => {
+                let responses: Vec<_> = offices
+                    .into_iter()
+                    .map(|(o, distance_km)| OfficeResponse { distance_km: Some(distance_km), ..o.to_response() })
+                    .collect();
+                tracing::info!("Found {} offices within {} km", responses.len(), area.radius_km);
+                format.render_list(&responses)
+            }
+            Err(e) => {
+                tracing::error!("Failed to list nearby offices: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+            }
+        };
+    }
     tracing::info!("Received request to list all offices");
-    match service.list_all_offices().await {
+    match service.list_all_offices(&principal).await {
         Ok(offices) => {
             let responses: Vec<_> = offices.into_iter().map(|o| o.to_response()).collect();
             tracing::info!("Found a total of {} offices", responses.len());
-            Json(responses).into_response()
+            format.render_list(&responses)
         }
         Err(e) => {
             tracing::error!("Failed to list offices: {}", e);
@@ -125,63 +219,109 @@ pub async fn list_all_offices(
 }
 
 /// Updates office by ID
-/// Expects office ID as a path parameter and JSON body with updated data
+/// Expects office ID as a path parameter and body with updated data (JSON, CSV, XML or MessagePack)
 /// Success returns 200 OK with updated office data
-/// Failure returns 400 Bad Request
+/// Failure returns 422 Unprocessable Entity listing every broken rule, 400 Bad Request
+/// or 409 Conflict when max_occupancy would change without an approved change request
 #[utoipa::path(
     put,
     path = "/offices/{id}",
     params(
         ("id" = i32, Path, description = "Office ID")
     ),
-    request_body = CreateOfficeRequest,
+    request_body(content((CreateOfficeRequest = "application/json"), (CreateOfficeRequest = "text/csv"), (CreateOfficeRequest = "application/xml"), (CreateOfficeRequest = "application/msgpack"))),
+    security(("bearer_auth" = ["offices:update"])),
     responses(
-        (status = 200, description = "Office updated successfully", body = OfficeResponse),
-        (status = 400, description = "Bad request")
+        (status = 200, description = "Office updated successfully", content((OfficeResponse = "application/json"), (OfficeResponse = "text/csv"), (OfficeResponse = "application/xml"), (OfficeResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request"),
+        (status = 409, description = "Changing max_occupancy needs an approved change request", body = ProblemDetails),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 415, description = "Content-Type is not a supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:update", body = ProblemDetails)
     )
 )]
 pub async fn update_office(
     State(service): State<Arc<OfficeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
     Path(id): Path<i32>,
-    Json(req): Json<CreateOfficeRequest>,
+    Negotiated(req): Negotiated<CreateOfficeRequest>,
 ) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesUpdate) {
+        return denied.into_response();
+    }
     tracing::info!("Received request to update office with id: {}", id);
     let office = Office::from_create_request(req);
 
-    match service.update_office(id, &office).await {
+    match service.update_office(&principal, id, &office).await {
         Ok(updated) => {
             tracing::info!("Sucessfully updated office with id: {}", id);
-            (StatusCode::OK, Json(updated.to_response())).into_response()
+            (StatusCode::OK, format.render(&updated.to_response())).into_response()
         },
         Err(e) => {
+            if let Some(problem) = ProblemDetails::from_validation_error(&e, language) {
+                tracing::warn!("Office failed validation: {}", e);
+                return problem.into_response();
+            }
+            if e.downcast_ref::<CodedError>().is_some_and(|coded| coded.code == "office.capacity_needs_approval") {
+                tracing::warn!("Office capacity change refused without approval: {}", e);
+                return ProblemDetails::new(StatusCode::CONFLICT, localize(&e, language)).into_response();
+            }
             tracing::warn!("Failed to update office ID {}: {}", id, e);
-            (StatusCode::BAD_REQUEST, e.to_string()).into_response()
+            (StatusCode::BAD_REQUEST, localize(&e, language)).into_response()
         },
     }
 }
 
 /// Deletes office by ID
-/// Expects office ID as a path parameter
+/// Expects office ID as a path parameter, ?reassign_to= moves the employees to another office first
 /// Success returns 204 No Content
-/// Failure returns 404 Not Found or 500 Internal Server Error
+/// Failure returns 404 Not Found, 400 Bad Request (reassignment not possible) or 500 Internal Server Error
 #[utoipa::path(
     delete,
     path = "/offices/{id}",
     params(
-        ("id" = i32, Path, description = "Office ID")
+        ("id" = i32, Path, description = "Office ID"),
+        DeleteOfficeParams
     ),
+    security(("bearer_auth" = ["offices:delete"])),
     responses(
         (status = 204, description = "Office deleted successfully"),
+        (status = 400, description = "Target office missing, the same office or without room for the employees"),
         (status = 404, description = "Office not found"),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:delete", body = ProblemDetails)
     )
 )]
 pub async fn delete_office(
     State(service): State<Arc<OfficeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
     Path(id): Path<i32>,
+    Query(params): Query<DeleteOfficeParams>,
 ) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesDelete) {
+        return denied.into_response();
+    }
     tracing::info!("Received request to delete office with id: {}", id);
-    match service.remove_office(id).await {
+    if let Some(target_id) = params.reassign_to {
+        return match service.remove_office_reassigning(&principal, id, target_id).await {
+            Ok(true) => {
+                tracing::info!("Successfully deleted office with id: {}, employees moved to {}", id, target_id);
+                StatusCode::NO_CONTENT.into_response()
+            },
+            Ok(false) => (StatusCode::NOT_FOUND, "Office not found").into_response(),
+            Err(e) => {
+                tracing::warn!("Failed to delete office ID {} reassigning to {}: {}", id, target_id, e);
+                (StatusCode::BAD_REQUEST, localize(&e, language)).into_response()
+            }
+        };
+    }
+    match service.remove_office(&principal, id).await {
         Ok(true) => {
             tracing::info!("Successfully deleted office with id: {}", id);
             StatusCode::NO_CONTENT.into_response()
@@ -195,4 +335,224 @@ pub async fn delete_office(
             (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
         }
     }
-}
\ No newline at end of file
+}
+
+/// Lists the subjects managing an office
+/// Expects office ID as a path parameter
+/// Success returns 200 OK with a list of subjects
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/offices/{id}/managers",
+    params(
+        ("id" = i32, Path, description = "Office ID")
+    ),
+    security(("bearer_auth" = ["offices:managers"])),
+    responses(
+        (status = 200, description = "Subjects managing the office", body = Vec<String>),
+        (status = 404, description = "Office not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:managers", body = ProblemDetails)
+    )
+)]
+pub async fn list_office_managers(
+    State(service): State<Arc<OfficeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesManagers) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to list managers of office id: {}", id);
+    match service.list_office_managers(&principal, id).await {
+        Ok(subjects) => Json(subjects).into_response(),
+        Err(e) => {
+            let error_msg = e.to_string();
+            if error_msg.contains("does not exist") {
+                tracing::warn!("Office lookup failed: {}", error_msg);
+                (StatusCode::NOT_FOUND, localize(&e, language)).into_response()
+            } else {
+                tracing::error!("Error listing managers of office {}: {}", id, error_msg);
+                (StatusCode::INTERNAL_SERVER_ERROR, error_msg).into_response()
+            }
+        }
+    }
+}
+
+/// Assigns a subject as office manager
+/// Expects office ID and token subject as path parameters
+/// Success returns 204 No Content, assigning twice is a no-op
+/// Failure returns 400 Bad Request or 404 Not Found
+#[utoipa::path(
+    put,
+    path = "/offices/{id}/managers/{subject}",
+    params(
+        ("id" = i32, Path, description = "Office ID"),
+        ("subject" = String, Path, description = "Token subject of the manager")
+    ),
+    security(("bearer_auth" = ["offices:managers"])),
+    responses(
+        (status = 204, description = "Manager assigned"),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "Office not found"),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:managers", body = ProblemDetails)
+    )
+)]
+pub async fn assign_office_manager(
+    State(service): State<Arc<OfficeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Path((id, subject)): Path<(i32, String)>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesManagers) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to assign {} as manager of office id: {}", subject, id);
+    match service.assign_office_manager(&principal, id, &subject).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => {
+            let error_msg = e.to_string();
+            tracing::warn!("Failed to assign manager of office {}: {}", id, error_msg);
+            if error_msg.contains("does not exist") {
+                (StatusCode::NOT_FOUND, localize(&e, language)).into_response()
+            } else {
+                (StatusCode::BAD_REQUEST, localize(&e, language)).into_response()
+            }
+        }
+    }
+}
+
+/// Removes a subject as office manager
+/// Expects office ID and token subject as path parameters
+/// Success returns 204 No Content
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    delete,
+    path = "/offices/{id}/managers/{subject}",
+    params(
+        ("id" = i32, Path, description = "Office ID"),
+        ("subject" = String, Path, description = "Token subject of the manager")
+    ),
+    security(("bearer_auth" = ["offices:managers"])),
+    responses(
+        (status = 204, description = "Manager removed"),
+        (status = 404, description = "Subject does not manage the office"),
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:managers", body = ProblemDetails)
+    )
+)]
+pub async fn remove_office_manager(
+    State(service): State<Arc<OfficeService>>,
+    principal: Principal,
+    Path((id, subject)): Path<(i32, String)>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesManagers) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to remove {} as manager of office id: {}", subject, id);
+    match service.remove_office_manager(&principal, id, &subject).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, "Office manager not found").into_response(),
+        Err(e) => {
+            tracing::error!("Error removing manager of office {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Exports all offices
+/// Format from ?format=csv|ndjson|xlsx or the Accept header, CSV by default
+/// Success returns 200 OK with the file as attachment, rows are streamed from the database
+/// Failure returns 406 Not Acceptable for a format that is not supported
+#[utoipa::path(
+    get,
+    path = "/offices/export",
+    params(OfficeExportParams),
+    security(("bearer_auth" = ["offices:read"])),
+    responses(
+        (status = 200, description = "Office export", content(
+            (String = "text/csv"),
+            (OfficeExportRow = "application/x-ndjson"),
+            (Vec<u8> = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+        )),
+        (status = 406, description = "Requested format is not supported", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:read", body = ProblemDetails)
+    )
+)]
+pub async fn export_offices(
+    State(service): State<Arc<OfficeService>>,
+    principal: Principal,
+    Query(params): Query<OfficeExportParams>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesRead) {
+        return denied.into_response();
+    }
+    let Some(format) = ExportFormat::negotiate(params.format.as_deref(), &headers) else {
+        tracing::warn!("Unsupported office export format requested");
+        return not_acceptable();
+    };
+    tracing::info!("Received request to export offices as {:?}", format);
+    export_response(format, OfficeExportRow::headers(), service.export_offices(&principal), "offices").await
+}
+/// Draws the company layout: every office with its employees, occupancy versus max_occupancy and full offices highlighted
+/// Format from ?format=dot|mermaid|json or the Accept header, JSON by default. ?office_ids=1,2 limits the offices
+/// Success returns 200 OK with the diagram
+/// Failure returns 404 Not Found for an unknown office in office_ids, 400 Bad Request or 406 Not Acceptable
+#[utoipa::path(
+    get,
+    path = "/offices/diagram",
+    params(OfficeDiagramParams),
+    security(("bearer_auth" = ["offices:read", "employees:read"])),
+    responses(
+        (status = 200, description = "Office diagram", content(
+            (String = "text/vnd.graphviz"),
+            (String = "text/vnd.mermaid"),
+            (CompanyDiagram = "application/json")
+        )),
+        (status = 400, description = "office_ids is not a comma separated list of IDs"),
+        (status = 404, description = "Office in office_ids not found"),
+        (status = 406, description = "Requested format is not supported", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission offices:read or employees:read", body = ProblemDetails)
+    )
+)]
+pub async fn get_office_diagram(
+    State(service): State<Arc<OfficeService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    Query(params): Query<OfficeDiagramParams>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::OfficesRead).and_then(|_| principal.require(Permission::EmployeesRead)) {
+        return denied.into_response();
+    }
+    let Some(format) = DiagramFormat::negotiate(params.format.as_deref(), &headers) else {
+        tracing::warn!("Unsupported office diagram format requested");
+        return diagram::not_acceptable();
+    };
+    let office_ids = match params.office_ids() {
+        Ok(office_ids) => office_ids,
+        Err(value) => {
+            let e = CodedError::new("diagram.invalid_office_id").arg("value", value).into();
+            return (StatusCode::BAD_REQUEST, localize(&e, language)).into_response();
+        }
+    };
+    tracing::info!("Received request to draw office diagram as {:?}", format);
+    match service.office_diagram(&principal, office_ids.as_deref()).await {
+        Ok(diagram) => diagram_response(format, &diagram),
+        Err(e) => {
+            if e.to_string().contains("does not exist") {
+                tracing::warn!("Office diagram asked for a missing office: {}", e);
+                return (StatusCode::NOT_FOUND, localize(&e, language)).into_response();
+            }
+            tracing::error!("Error drawing office diagram: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
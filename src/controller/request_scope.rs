@@ -0,0 +1,8 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+use crate::repository::read_routing::request_scope;
+
+/// Middleware giving every request its own write tracking
+/// With a read replica and read-your-writes on, list queries after a write in the same request read from the primary
+pub async fn track_writes(request: Request, next: Next) -> Response {
+    request_scope(next.run(request)).await
+}
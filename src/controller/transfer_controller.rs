@@ -0,0 +1,239 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    response::IntoResponse,
+    http::StatusCode,
+    Router,
+};
+use std::sync::Arc;
+use crate::service::transfer_service::TransferService;
+use crate::entity::transfer::Transfer;
+use crate::dto::transfer_dto::{RescheduleTransferRequest, TransferListParams, TransferResponse};
+use crate::dto::problem_dto::ProblemDetails;
+use crate::controller::negotiation::{AcceptFormat, AcceptLanguage, MediaFormat, Negotiated};
+use crate::utils::CodedError;
+use crate::utils::i18n::{localize, Language};
+use crate::auth::{permission::Permission, principal::Principal};
+
+/// Creates the transfer API router.
+///
+/// Routes:
+/// List transfers into and out of an office: GET /offices/{id}/transfers?status=pending
+/// Get transfer by ID: GET /transfers/{id}
+/// Cancel a pending transfer: POST /transfers/{id}/cancel
+/// Move a pending transfer to another date: POST /transfers/{id}/reschedule
+///
+/// Transfers are planned through approved change requests (POST /change-requests with kind transfer),
+/// pending transfers are applied by the transfer job on their effective date, see TransferService
+/// Transfer bodies and responses follow Content-Type and Accept: JSON (default), CSV, XML or MessagePack
+/// Every route requires a bearer token, the permission per route is listed in its OpenAPI security section
+/// Error and validation messages follow Accept-Language: Danish (da) or English (default)
+pub fn create_router(service: Arc<TransferService>) -> Router {
+    Router::new()
+        .route("/offices/{id}/transfers", get(list_office_transfers))
+        .route("/transfers/{id}", get(get_transfer_by_id))
+        .route("/transfers/{id}/cancel", post(cancel_transfer))
+        .route("/transfers/{id}/reschedule", post(reschedule_transfer))
+        .with_state(service)
+}
+
+/// Lists the transfers into and out of an office
+/// Expects office ID as a path parameter, optionally ?status= to only list transfers with that status
+/// Success returns 200 OK with the transfers ordered by effective date
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/offices/{id}/transfers",
+    params(
+        ("id" = i32, Path, description = "Office ID"),
+        TransferListParams
+    ),
+    security(("bearer_auth" = ["employees:read"])),
+    responses(
+        (status = 200, description = "Transfers of the office", content((Vec<TransferResponse> = "application/json"), (Vec<TransferResponse> = "text/csv"), (Vec<TransferResponse> = "application/xml"), (Vec<TransferResponse> = "application/msgpack"))),
+        (status = 404, description = "Office not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
+    )
+)]
+pub async fn list_office_transfers(
+    State(service): State<Arc<TransferService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+    Query(params): Query<TransferListParams>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to list transfers of office id: {}", id);
+    match service.list_office_transfers(&principal, id, params.status).await {
+        Ok(transfers) => {
+            let response: Vec<_> = transfers.iter().map(Transfer::to_response).collect();
+            format.render_list(&response)
+        }
+        Err(e) => {
+            let error_msg = e.to_string();
+            if error_msg.contains("does not exist") {
+                tracing::warn!("Office lookup failed: {}", error_msg);
+                (StatusCode::NOT_FOUND, localize(&e, language)).into_response()
+            } else {
+                tracing::error!("Error listing transfers of office {}: {}", id, error_msg);
+                (StatusCode::INTERNAL_SERVER_ERROR, error_msg).into_response()
+            }
+        }
+    }
+}
+
+/// Retrieves transfer by ID
+/// Expects transfer ID as a path parameter
+/// Success returns 200 OK with the transfer
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/transfers/{id}",
+    params(
+        ("id" = i32, Path, description = "Transfer ID")
+    ),
+    security(("bearer_auth" = ["employees:read"])),
+    responses(
+        (status = 200, description = "Transfer found", content((TransferResponse = "application/json"), (TransferResponse = "text/csv"), (TransferResponse = "application/xml"), (TransferResponse = "application/msgpack"))),
+        (status = 404, description = "Transfer not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
+    )
+)]
+pub async fn get_transfer_by_id(
+    State(service): State<Arc<TransferService>>,
+    principal: Principal,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to get transfer by id: {}", id);
+    match service.find_transfer_by_id(&principal, id).await {
+        Ok(Some(transfer)) => format.render(&transfer.to_response()),
+        Ok(None) => {
+            tracing::warn!("Transfer with id {} not found", id);
+            (StatusCode::NOT_FOUND, "Transfer not found").into_response()
+        }
+        Err(e) => {
+            tracing::error!("Error finding transfer {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Cancels a pending transfer
+/// Expects transfer ID as a path parameter
+/// Success returns 200 OK with the cancelled transfer
+/// Failure returns 409 Conflict when the transfer is no longer pending, 404 Not Found,
+/// or 400 Bad Request when the employee's current office no longer has the seat to keep
+#[utoipa::path(
+    post,
+    path = "/transfers/{id}/cancel",
+    params(
+        ("id" = i32, Path, description = "Transfer ID")
+    ),
+    security(("bearer_auth" = ["employees:update"])),
+    responses(
+        (status = 200, description = "Transfer cancelled", content((TransferResponse = "application/json"), (TransferResponse = "text/csv"), (TransferResponse = "application/xml"), (TransferResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request, e.g. the current office has been planned full meanwhile"),
+        (status = 404, description = "Transfer not found"),
+        (status = 409, description = "Transfer was applied, cancelled or failed already", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:update", body = ProblemDetails)
+    )
+)]
+pub async fn cancel_transfer(
+    State(service): State<Arc<TransferService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesUpdate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to cancel transfer with id: {}", id);
+    render_transfer(service.cancel_transfer(&principal, id).await, format, language)
+}
+
+/// Moves a pending transfer to another effective date
+/// Expects transfer ID as a path parameter and the new effective date as body (Content-Type)
+/// Success returns 200 OK with the rescheduled transfer
+/// Failure returns 422 Unprocessable Entity listing every broken rule, 409 Conflict when the transfer is no longer pending,
+/// 404 Not Found, or 400 Bad Request when the destination has no seat from the new date on
+/// or a later date leaves the current office without a seat in between
+#[utoipa::path(
+    post,
+    path = "/transfers/{id}/reschedule",
+    params(
+        ("id" = i32, Path, description = "Transfer ID")
+    ),
+    request_body(content((RescheduleTransferRequest = "application/json"), (RescheduleTransferRequest = "text/csv"), (RescheduleTransferRequest = "application/xml"), (RescheduleTransferRequest = "application/msgpack"))),
+    security(("bearer_auth" = ["employees:update"])),
+    responses(
+        (status = 200, description = "Transfer rescheduled", content((TransferResponse = "application/json"), (TransferResponse = "text/csv"), (TransferResponse = "application/xml"), (TransferResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request, e.g. the destination is full on or after the new date"),
+        (status = 404, description = "Transfer not found"),
+        (status = 409, description = "Transfer was applied, cancelled or failed already", body = ProblemDetails),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 415, description = "Content-Type is not a supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:update", body = ProblemDetails)
+    )
+)]
+pub async fn reschedule_transfer(
+    State(service): State<Arc<TransferService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+    Negotiated(req): Negotiated<RescheduleTransferRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesUpdate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to reschedule transfer {} to {}", id, req.effective_date);
+    render_transfer(service.reschedule_transfer(&principal, id, req.effective_date).await, format, language)
+}
+
+// Response of the routes changing a pending transfer
+fn render_transfer(result: anyhow::Result<Transfer>, format: MediaFormat, language: Language) -> axum::response::Response {
+    match result {
+        Ok(transfer) => {
+            tracing::info!("Transfer {:?} is {} for {}", transfer.id, transfer.status, transfer.effective_date);
+            format.render(&transfer.to_response())
+        }
+        Err(e) => transfer_error(e, language),
+    }
+}
+
+// Validation errors are 422, a transfer without approval, a second pending transfer or a change of a settled one 409
+fn transfer_error(e: anyhow::Error, language: Language) -> axum::response::Response {
+    if let Some(problem) = ProblemDetails::from_validation_error(&e, language) {
+        tracing::warn!("Transfer failed validation: {}", e);
+        return problem.into_response();
+    }
+    if e.downcast_ref::<CodedError>().is_some_and(|coded| coded.code == "transfer.pending_exists" || coded.code == "transfer.not_pending") {
+        tracing::warn!("Transfer refused: {}", e);
+        return ProblemDetails::new(StatusCode::CONFLICT, localize(&e, language)).into_response();
+    }
+    let error_msg = e.to_string();
+    tracing::warn!("Failed to process transfer: {}", error_msg);
+    if error_msg.contains("does not exist") {
+        (StatusCode::NOT_FOUND, localize(&e, language)).into_response()
+    } else {
+        (StatusCode::BAD_REQUEST, localize(&e, language)).into_response()
+    }
+}
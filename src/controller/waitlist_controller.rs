@@ -0,0 +1,365 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    response::IntoResponse,
+    http::StatusCode,
+    Router,
+};
+use std::sync::Arc;
+use crate::service::waitlist_service::WaitlistService;
+use crate::entity::waitlist::{WaitlistEntry, WaitlistEvent};
+use crate::dto::waitlist_dto::{WaitlistEntryResponse, WaitlistEventResponse, WaitlistListParams, WaitlistRequest};
+use crate::dto::problem_dto::ProblemDetails;
+use crate::controller::negotiation::{AcceptFormat, AcceptLanguage, MediaFormat, Negotiated};
+use crate::utils::CodedError;
+use crate::utils::i18n::{localize, Language};
+use crate::auth::{permission::Permission, principal::Principal};
+
+/// Creates the waitlist API router.
+///
+/// Routes:
+/// Put a hire on the waitlist of a full office: POST /offices/{id}/waitlist
+/// List the waitlist of an office: GET /offices/{id}/waitlist?status=waiting
+/// Get waitlist entry by ID: GET /waitlist/{id}
+/// List what happened to an entry: GET /waitlist/{id}/events
+/// Accept or decline an offered seat: POST /waitlist/{id}/accept, POST /waitlist/{id}/decline
+/// Take an entry off the waitlist: POST /waitlist/{id}/cancel
+///
+/// Free seats are handed to waiting entries by the waitlist job, see WaitlistService
+/// Waitlist bodies and responses follow Content-Type and Accept: JSON (default), CSV, XML or MessagePack
+/// Every route requires a bearer token, the permission per route is listed in its OpenAPI security section
+/// Error and validation messages follow Accept-Language: Danish (da) or English (default)
+pub fn create_router(service: Arc<WaitlistService>) -> Router {
+    Router::new()
+        .route("/offices/{id}/waitlist", post(enqueue).get(list_office_waitlist))
+        .route("/waitlist/{id}", get(get_entry_by_id))
+        .route("/waitlist/{id}/events", get(list_events))
+        .route("/waitlist/{id}/accept", post(accept_offer))
+        .route("/waitlist/{id}/decline", post(decline_offer))
+        .route("/waitlist/{id}/cancel", post(cancel_entry))
+        .with_state(service)
+}
+
+/// Puts a hire on the waitlist of a full office
+/// Expects office ID as a path parameter and the hire as body (Content-Type), confirm asks for an offer instead of placement
+/// Success returns 201 Created with the waiting entry and its queue position
+/// Failure returns 422 Unprocessable Entity listing every broken rule, 409 Conflict when the office has a free seat
+/// and nobody waiting, 404 Not Found, or 400 Bad Request
+#[utoipa::path(
+    post,
+    path = "/offices/{id}/waitlist",
+    params(
+        ("id" = i32, Path, description = "Office ID")
+    ),
+    request_body(content((WaitlistRequest = "application/json"), (WaitlistRequest = "text/csv"), (WaitlistRequest = "application/xml"), (WaitlistRequest = "application/msgpack"))),
+    security(("bearer_auth" = ["employees:create"])),
+    responses(
+        (status = 201, description = "Hire put on the waitlist", content((WaitlistEntryResponse = "application/json"), (WaitlistEntryResponse = "text/csv"), (WaitlistEntryResponse = "application/xml"), (WaitlistEntryResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "Office not found"),
+        (status = 409, description = "The office has a free seat and nobody waiting, hire directly", body = ProblemDetails),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 415, description = "Content-Type is not a supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:create", body = ProblemDetails)
+    )
+)]
+pub async fn enqueue(
+    State(service): State<Arc<WaitlistService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+    Negotiated(req): Negotiated<WaitlistRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesCreate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to put {} {} on the waitlist of office {}", req.first_name, req.last_name, id);
+    match service.enqueue(&principal, id, req).await {
+        Ok((created, position)) => {
+            tracing::info!("Successfully enqueued waitlist entry with ID: {:?} at position {:?}", created.id, position);
+            (StatusCode::CREATED, format.render(&created.to_response(position))).into_response()
+        }
+        Err(e) => waitlist_error(e, language),
+    }
+}
+
+/// Lists the waitlist of an office in order of arrival
+/// Expects office ID as a path parameter, optionally ?status= to only list entries with that status
+/// Success returns 200 OK with the entries, waiting ones with their queue position
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/offices/{id}/waitlist",
+    params(
+        ("id" = i32, Path, description = "Office ID"),
+        WaitlistListParams
+    ),
+    security(("bearer_auth" = ["employees:read"])),
+    responses(
+        (status = 200, description = "Waitlist of the office", content((Vec<WaitlistEntryResponse> = "application/json"), (Vec<WaitlistEntryResponse> = "text/csv"), (Vec<WaitlistEntryResponse> = "application/xml"), (Vec<WaitlistEntryResponse> = "application/msgpack"))),
+        (status = 404, description = "Office not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
+    )
+)]
+pub async fn list_office_waitlist(
+    State(service): State<Arc<WaitlistService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+    Query(params): Query<WaitlistListParams>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to list the waitlist of office id: {}", id);
+    match service.list_office_waitlist(&principal, id, params.status).await {
+        Ok(entries) => {
+            let response: Vec<_> = entries.iter().map(|(entry, position)| entry.to_response(*position)).collect();
+            format.render_list(&response)
+        }
+        Err(e) => {
+            let error_msg = e.to_string();
+            if error_msg.contains("does not exist") {
+                tracing::warn!("Office lookup failed: {}", error_msg);
+                (StatusCode::NOT_FOUND, localize(&e, language)).into_response()
+            } else {
+                tracing::error!("Error listing the waitlist of office {}: {}", id, error_msg);
+                (StatusCode::INTERNAL_SERVER_ERROR, error_msg).into_response()
+            }
+        }
+    }
+}
+
+/// Retrieves waitlist entry by ID
+/// Expects entry ID as a path parameter
+/// Success returns 200 OK with the entry and, while it waits, its queue position
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/waitlist/{id}",
+    params(
+        ("id" = i32, Path, description = "Waitlist entry ID")
+    ),
+    security(("bearer_auth" = ["employees:read"])),
+    responses(
+        (status = 200, description = "Waitlist entry found", content((WaitlistEntryResponse = "application/json"), (WaitlistEntryResponse = "text/csv"), (WaitlistEntryResponse = "application/xml"), (WaitlistEntryResponse = "application/msgpack"))),
+        (status = 404, description = "Waitlist entry not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
+    )
+)]
+pub async fn get_entry_by_id(
+    State(service): State<Arc<WaitlistService>>,
+    principal: Principal,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to get waitlist entry by id: {}", id);
+    match service.find_entry_by_id(&principal, id).await {
+        Ok(Some((entry, position))) => format.render(&entry.to_response(position)),
+        Ok(None) => {
+            tracing::warn!("Waitlist entry with id {} not found", id);
+            (StatusCode::NOT_FOUND, "Waitlist entry not found").into_response()
+        }
+        Err(e) => {
+            tracing::error!("Error finding waitlist entry {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Lists what happened to a waitlist entry
+/// Expects entry ID as a path parameter
+/// Success returns 200 OK with the events, oldest first
+/// Failure returns 404 Not Found or 500 Internal Server Error
+#[utoipa::path(
+    get,
+    path = "/waitlist/{id}/events",
+    params(
+        ("id" = i32, Path, description = "Waitlist entry ID")
+    ),
+    security(("bearer_auth" = ["employees:read"])),
+    responses(
+        (status = 200, description = "Events of the entry", content((Vec<WaitlistEventResponse> = "application/json"), (Vec<WaitlistEventResponse> = "text/csv"), (Vec<WaitlistEventResponse> = "application/xml"), (Vec<WaitlistEventResponse> = "application/msgpack"))),
+        (status = 404, description = "Waitlist entry not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:read", body = ProblemDetails)
+    )
+)]
+pub async fn list_events(
+    State(service): State<Arc<WaitlistService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesRead) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to list events of waitlist entry id: {}", id);
+    match service.list_events(&principal, id).await {
+        Ok(events) => {
+            let response: Vec<_> = events.iter().map(WaitlistEvent::to_response).collect();
+            format.render_list(&response)
+        }
+        Err(e) => {
+            let error_msg = e.to_string();
+            if error_msg.contains("does not exist") {
+                tracing::warn!("Waitlist entry lookup failed: {}", error_msg);
+                (StatusCode::NOT_FOUND, localize(&e, language)).into_response()
+            } else {
+                tracing::error!("Error listing events of waitlist entry {}: {}", id, error_msg);
+                (StatusCode::INTERNAL_SERVER_ERROR, error_msg).into_response()
+            }
+        }
+    }
+}
+
+/// Accepts the seat offered to a waitlist entry, which creates the employee
+/// Expects entry ID as a path parameter
+/// Success returns 200 OK with the placed entry, employee_id names the new employee
+/// Failure returns 409 Conflict when no seat is offered to the entry, 404 Not Found,
+/// or 400 Bad Request when the seat was taken meanwhile: the entry then waits again in its old place
+#[utoipa::path(
+    post,
+    path = "/waitlist/{id}/accept",
+    params(
+        ("id" = i32, Path, description = "Waitlist entry ID")
+    ),
+    security(("bearer_auth" = ["employees:create"])),
+    responses(
+        (status = 200, description = "Offer accepted, employee created", content((WaitlistEntryResponse = "application/json"), (WaitlistEntryResponse = "text/csv"), (WaitlistEntryResponse = "application/xml"), (WaitlistEntryResponse = "application/msgpack"))),
+        (status = 400, description = "Bad request, e.g. the offered seat was taken meanwhile"),
+        (status = 404, description = "Waitlist entry not found"),
+        (status = 409, description = "No seat is offered to the entry", body = ProblemDetails),
+        (status = 422, description = "Validation failed, `errors` lists every broken rule", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:create", body = ProblemDetails)
+    )
+)]
+pub async fn accept_offer(
+    State(service): State<Arc<WaitlistService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesCreate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to accept the offer of waitlist entry {}", id);
+    render_entry(service.accept(&principal, id).await, format, language)
+}
+
+/// Declines the seat offered to a waitlist entry, the seat goes to the next entry
+/// Expects entry ID as a path parameter
+/// Success returns 200 OK with the declined entry
+/// Failure returns 409 Conflict when no seat is offered to the entry, or 404 Not Found
+#[utoipa::path(
+    post,
+    path = "/waitlist/{id}/decline",
+    params(
+        ("id" = i32, Path, description = "Waitlist entry ID")
+    ),
+    security(("bearer_auth" = ["employees:create"])),
+    responses(
+        (status = 200, description = "Offer declined", content((WaitlistEntryResponse = "application/json"), (WaitlistEntryResponse = "text/csv"), (WaitlistEntryResponse = "application/xml"), (WaitlistEntryResponse = "application/msgpack"))),
+        (status = 404, description = "Waitlist entry not found"),
+        (status = 409, description = "No seat is offered to the entry", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:create", body = ProblemDetails)
+    )
+)]
+pub async fn decline_offer(
+    State(service): State<Arc<WaitlistService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesCreate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to decline the offer of waitlist entry {}", id);
+    render_entry(service.decline(&principal, id).await, format, language)
+}
+
+/// Takes a waiting or offered entry off the waitlist
+/// Expects entry ID as a path parameter
+/// Success returns 200 OK with the cancelled entry
+/// Failure returns 409 Conflict when the entry was placed, declined, cancelled or failed already, or 404 Not Found
+#[utoipa::path(
+    post,
+    path = "/waitlist/{id}/cancel",
+    params(
+        ("id" = i32, Path, description = "Waitlist entry ID")
+    ),
+    security(("bearer_auth" = ["employees:create"])),
+    responses(
+        (status = 200, description = "Entry cancelled", content((WaitlistEntryResponse = "application/json"), (WaitlistEntryResponse = "text/csv"), (WaitlistEntryResponse = "application/xml"), (WaitlistEntryResponse = "application/msgpack"))),
+        (status = 404, description = "Waitlist entry not found"),
+        (status = 409, description = "Entry is no longer on the waitlist", body = ProblemDetails),
+        (status = 406, description = "Accept header names no supported format", body = ProblemDetails),
+        (status = 401, description = "Missing or invalid bearer token", body = ProblemDetails),
+        (status = 403, description = "Missing permission employees:create", body = ProblemDetails)
+    )
+)]
+pub async fn cancel_entry(
+    State(service): State<Arc<WaitlistService>>,
+    principal: Principal,
+    AcceptLanguage(language): AcceptLanguage,
+    AcceptFormat(format): AcceptFormat,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    if let Err(denied) = principal.require(Permission::EmployeesCreate) {
+        return denied.into_response();
+    }
+    tracing::info!("Received request to cancel waitlist entry {}", id);
+    render_entry(service.cancel(&principal, id).await, format, language)
+}
+
+// Response of the routes deciding an entry, which no longer waits so it has no position
+fn render_entry(result: anyhow::Result<WaitlistEntry>, format: MediaFormat, language: Language) -> axum::response::Response {
+    match result {
+        Ok(entry) => {
+            tracing::info!("Waitlist entry {:?} is {}", entry.id, entry.status);
+            format.render(&entry.to_response(None))
+        }
+        Err(e) => waitlist_error(e, language),
+    }
+}
+
+// Validation errors are 422, an office with room or a decision on a settled entry 409
+fn waitlist_error(e: anyhow::Error, language: Language) -> axum::response::Response {
+    if let Some(problem) = ProblemDetails::from_validation_error(&e, language) {
+        tracing::warn!("Waitlist entry failed validation: {}", e);
+        return problem.into_response();
+    }
+    if e.downcast_ref::<CodedError>().is_some_and(|coded| matches!(coded.code.as_str(), "waitlist.office_has_room" | "waitlist.not_offered" | "waitlist.not_open")) {
+        tracing::warn!("Waitlist change refused: {}", e);
+        return ProblemDetails::new(StatusCode::CONFLICT, localize(&e, language)).into_response();
+    }
+    let error_msg = e.to_string();
+    tracing::warn!("Failed to process waitlist entry: {}", error_msg);
+    if error_msg.contains("does not exist") {
+        (StatusCode::NOT_FOUND, localize(&e, language)).into_response()
+    } else {
+        (StatusCode::BAD_REQUEST, localize(&e, language)).into_response()
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::entity::change_request::{ApprovalStep, ChangeRequestStatus, Decision, ProposedChange};
+
+/// Body of POST /change-requests
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SubmitChangeRequest {
+    pub change: ProposedChange,
+    #[serde(default)]
+    pub comment: Option<String>, // reason for the change, stored as the first comment
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>, // CHANGE_REQUEST_TTL_DAYS from now when missing
+}
+
+/// Body of POST /change-requests/{id}/approve and /reject
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct DecisionRequest {
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Body of POST /change-requests/{id}/comments
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CommentRequest {
+    pub body: String,
+}
+
+/// Data Transfer Object for change request responses
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChangeRequestResponse {
+    pub id: Option<i32>,
+    pub change: ProposedChange,
+    pub status: ChangeRequestStatus,
+    pub requested_by: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub failure_reason: Option<String>, // why an approved change could not be applied, in the Accept-Language
+    pub approvals: Vec<ApprovalResponse>,
+    pub comments: Vec<CommentResponse>,
+}
+
+/// One approval step of a change request, decision is missing while undecided
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApprovalResponse {
+    pub step: ApprovalStep,
+    pub decision: Option<Decision>,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+/// Data Transfer Object for comment responses
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommentResponse {
+    pub id: Option<i32>,
+    pub author: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query parameters of GET /change-requests
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ChangeRequestListParams {
+    pub status: Option<ChangeRequestStatus>, // only requests with this status, all when missing
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Data Transfer Object for creating or renaming a department
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateDepartmentRequest {
+    pub name: String,
+}
+
+/// Data Transfer Object for department GET responses
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DepartmentResponse {
+    pub id: Option<i32>,
+    pub name: String,
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Company layout of GET /offices/diagram as a nested tree: offices with the employees sitting there
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CompanyDiagram {
+    pub offices: Vec<OfficeDiagramNode>,
+}
+
+/// Office node of the diagram, full when occupancy has reached max_occupancy
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct OfficeDiagramNode {
+    pub id: i32,
+    pub name: String,
+    pub occupancy: i64,
+    pub max_occupancy: i32,
+    pub full: bool,
+    pub employees: Vec<EmployeeDiagramNode>,
+}
+
+/// Employee leaf of the diagram
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct EmployeeDiagramNode {
+    pub id: i32,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+/// Query parameters of GET /offices/diagram
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct OfficeDiagramParams {
+    pub format: Option<String>, // dot, mermaid or json, overrides the Accept header
+    pub office_ids: Option<String>, // comma separated office IDs, all offices in scope when missing
+}
+
+impl OfficeDiagramParams {
+    /// Parses office_ids, None when not given, Err with the first value that is not an ID
+    pub fn office_ids(&self) -> Result<Option<Vec<i32>>, String> {
+        let Some(office_ids) = &self.office_ids else {
+            return Ok(None);
+        };
+        office_ids
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| id.parse::<i32>().map_err(|_| id.to_string()))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(office_ids: Option<&str>) -> OfficeDiagramParams {
+        OfficeDiagramParams { format: None, office_ids: office_ids.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_office_ids_parses_comma_separated_list() {
+        assert_eq!(params(None).office_ids(), Ok(None));
+        assert_eq!(params(Some("3, 1,")).office_ids(), Ok(Some(vec![3, 1])));
+        assert_eq!(params(Some("1,vejle")).office_ids(), Err("vejle".to_string()));
+    }
+}
@@ -1,14 +1,21 @@
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDate;
 use utoipa::ToSchema;
+use crate::entity::employee::EmploymentStatus;
 
 /// Data Transfer Object for creating a new employee
-#[derive(Debug, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateEmployeeRequest {
     pub first_name: String,
     pub last_name: String,
     pub birth_date: NaiveDate,
     pub office_id: i32,
+    #[serde(default)]
+    pub department_id: Option<i32>,
+    #[serde(default)]
+    pub manager_id: Option<i32>, // employee to report to
+    #[serde(default)]
+    pub hire_date: Option<NaiveDate>, // today when missing, a future date makes the employee pending
 }
 
 /// Data Transfer Object for employee GET responses
@@ -19,4 +26,23 @@ pub struct EmployeeResponse {
     pub last_name: String,
     pub birth_date: NaiveDate,
     pub office_id: i32,
+    pub department_id: Option<i32>,
+    pub manager_id: Option<i32>,
+    pub hire_date: NaiveDate,
+    pub termination_date: Option<NaiveDate>,
+    pub status: EmploymentStatus,
+}
+
+/// Body of POST /employees/{id}/terminate
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TerminateEmployeeRequest {
+    pub termination_date: NaiveDate, // last day in the office, the seat is free from the day after
+}
+
+/// Employees that are probably the same person: same birth date and the same name
+/// once case, accents, spaces, hyphens and apostrophes are ignored
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DuplicateClusterResponse {
+    pub birth_date: NaiveDate,
+    pub employees: Vec<EmployeeResponse>, // ordered by ID, at least two
 }
\ No newline at end of file
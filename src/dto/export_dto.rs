@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use utoipa::{IntoParams, ToSchema};
+
+/// One cell of an exported row, keeps numbers and dates typed for XLSX
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportCell {
+    Int(i64),
+    Text(String),
+    Date(NaiveDate),
+}
+
+/// Row that can be written by every export format
+/// NDJSON uses the serde representation, CSV and XLSX use cells in header order
+pub trait ExportRecord: Serialize + Send + 'static {
+    fn cells(&self) -> Vec<ExportCell>;
+}
+
+/// Employee row of GET /employees/export
+/// office_name is only selected when the export joins offices
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
+pub struct EmployeeExportRow {
+    pub id: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub birth_date: NaiveDate,
+    pub office_id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub office_name: Option<String>,
+}
+
+impl EmployeeExportRow {
+    // Column names matching cells()
+    pub fn headers(include_office: bool) -> Vec<&'static str> {
+        let mut headers = vec!["id", "first_name", "last_name", "birth_date", "office_id"];
+        if include_office {
+            headers.push("office_name");
+        }
+        headers
+    }
+}
+
+impl ExportRecord for EmployeeExportRow {
+    fn cells(&self) -> Vec<ExportCell> {
+        let mut cells = vec![
+            ExportCell::Int(self.id as i64),
+            ExportCell::Text(self.first_name.clone()),
+            ExportCell::Text(self.last_name.clone()),
+            ExportCell::Date(self.birth_date),
+            ExportCell::Int(self.office_id as i64),
+        ];
+        if let Some(office_name) = &self.office_name {
+            cells.push(ExportCell::Text(office_name.clone()));
+        }
+        cells
+    }
+}
+
+/// Office row of GET /offices/export
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
+pub struct OfficeExportRow {
+    pub id: i32,
+    pub name: String,
+    pub max_occupancy: i32,
+}
+
+impl OfficeExportRow {
+    // Column names matching cells()
+    pub fn headers() -> Vec<&'static str> {
+        vec!["id", "name", "max_occupancy"]
+    }
+}
+
+impl ExportRecord for OfficeExportRow {
+    fn cells(&self) -> Vec<ExportCell> {
+        vec![
+            ExportCell::Int(self.id as i64),
+            ExportCell::Text(self.name.clone()),
+            ExportCell::Int(self.max_occupancy as i64),
+        ]
+    }
+}
+
+/// Query parameters of GET /employees/export
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct EmployeeExportParams {
+    pub format: Option<String>, // csv, ndjson or xlsx, overrides the Accept header
+    pub office_id: Option<i32>, // same filter as GET /employees/office/{office_id}
+    #[serde(default)]
+    pub include_office: bool, // join offices and add an office_name column
+}
+
+/// Query parameters of GET /offices/export
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct OfficeExportParams {
+    pub format: Option<String>, // csv, ndjson or xlsx, overrides the Accept header
+}
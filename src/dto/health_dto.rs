@@ -0,0 +1,8 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Body of the liveness and readiness probes
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: String, // "live", "ready" or "not_ready"
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::dto::employee_dto::EmployeeResponse;
+
+/// One row of a bulk employee import
+/// The office is given by office_id or by office_name, office_id wins if both are set.
+/// A row without hire_date is hired today
+///
+/// CSV header: first_name,last_name,birth_date,office_id,office_name,hire_date (hire_date may be left out)
+/// NDJSON: one JSON object with the same fields per line
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ImportEmployeeRow {
+    pub first_name: String,
+    pub last_name: String,
+    pub birth_date: NaiveDate,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub office_id: Option<i32>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub office_name: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub hire_date: Option<NaiveDate>,
+}
+
+/// How failing rows affect the rest of the batch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    #[default]
+    Atomic, // any failing row aborts the whole import
+    Partial, // valid rows are imported, failing rows are reported
+}
+
+/// Query parameters of POST /employees/import
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImportParams {
+    #[serde(default)]
+    pub mode: ImportMode, // atomic (default) or partial
+    #[serde(default)]
+    pub dry_run: bool, // validate and report only, nothing is written
+}
+
+/// Result of one imported row, row numbers are 1-based data rows (CSV header excluded)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportRowResult {
+    pub row: usize,
+    pub status: ImportRowStatus,
+    pub employee: Option<EmployeeResponse>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportRowStatus {
+    Imported, // row was written
+    Valid, // row passed every check but was not written (dry run or aborted atomic import)
+    Failed, // row failed a check, see errors
+}
+
+/// Report returned by POST /employees/import
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportReport {
+    pub mode: ImportMode,
+    pub dry_run: bool,
+    pub total: usize,
+    pub imported: usize,
+    pub failed: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+// Treats empty CSV cells as missing values
+fn empty_as_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::str::FromStr + Deserialize<'de>,
+    T::Err: std::fmt::Display,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw<T> {
+        Text(String),
+        Value(T),
+    }
+    match Option::<Raw<T>>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Raw::Text(text)) if text.trim().is_empty() => Ok(None),
+        Some(Raw::Text(text)) => text.trim().parse::<T>().map(Some).map_err(serde::de::Error::custom),
+        Some(Raw::Value(value)) => Ok(Some(value)),
+    }
+}
+
+impl ImportEmployeeRow {
+    /// Parses a CSV body with header row, a malformed row becomes an error for that row only
+    pub fn parse_csv(body: &[u8]) -> Vec<Result<ImportEmployeeRow, String>> {
+        csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(body)
+            .deserialize::<ImportEmployeeRow>()
+            .map(|row| row.map_err(|e| format!("Malformed row: {}", e)))
+            .collect()
+    }
+
+    /// Parses an NDJSON body, blank lines are skipped
+    pub fn parse_ndjson(body: &[u8]) -> Vec<Result<ImportEmployeeRow, String>> {
+        String::from_utf8_lossy(body)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str::<ImportEmployeeRow>(line).map_err(|e| format!("Malformed row: {}", e)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_with_id_or_name() {
+        let body = b"first_name,last_name,birth_date,office_id,office_name\nAnders,Andersen,1995-05-15,1,\nBente,Bentsen,1990-01-01,,Aalborg\n";
+        let rows = ImportEmployeeRow::parse_csv(body);
+        assert_eq!(rows.len(), 2);
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!(first.office_id, Some(1));
+        assert_eq!(first.office_name, None);
+        let second = rows[1].as_ref().unwrap();
+        assert_eq!(second.office_id, None);
+        assert_eq!(second.office_name.as_deref(), Some("Aalborg"));
+        assert_eq!(second.hire_date, None);
+    }
+
+    #[test]
+    fn test_parse_csv_with_hire_date() {
+        let body = b"first_name,last_name,birth_date,office_id,office_name,hire_date\nAnders,Andersen,1995-05-15,1,,2019-08-01\nBente,Bentsen,1990-01-01,1,,\n";
+        let rows = ImportEmployeeRow::parse_csv(body);
+        assert_eq!(rows[0].as_ref().unwrap().hire_date, NaiveDate::from_ymd_opt(2019, 8, 1));
+        assert_eq!(rows[1].as_ref().unwrap().hire_date, None);
+    }
+
+    #[test]
+    fn test_parse_csv_malformed_row_is_reported() {
+        let body = b"first_name,last_name,birth_date,office_id,office_name\nAnders,Andersen,not-a-date,1,\n";
+        let rows = ImportEmployeeRow::parse_csv(body);
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].as_ref().unwrap_err().starts_with("Malformed row"));
+    }
+
+    #[test]
+    fn test_parse_ndjson_skips_blank_lines() {
+        let body = b"{\"first_name\":\"Anders\",\"last_name\":\"Andersen\",\"birth_date\":\"1995-05-15\",\"office_id\":1}\n\n{\"first_name\":\"Bente\"}\n";
+        let rows = ImportEmployeeRow::parse_ndjson(body);
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].is_ok());
+        assert!(rows[1].is_err());
+    }
+}
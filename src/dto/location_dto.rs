@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::entity::location::LocationKind;
+
+/// Data Transfer Object for creating a location
+/// A site names its office, buildings, floors and rooms take the office of their parent
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateLocationRequest {
+    pub kind: LocationKind,
+    pub name: String,
+    #[serde(default)]
+    pub parent_id: Option<i32>, // a region for a site (optional), the site, building or floor above the others
+    #[serde(default)]
+    pub capacity: Option<i32>, // not for sites, their capacity is the max_occupancy of the office
+    #[serde(default)]
+    pub office_id: Option<i32>, // sites only
+}
+
+/// Data Transfer Object for updating a location, kind and office stay as they are
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateLocationRequest {
+    pub name: String,
+    #[serde(default)]
+    pub capacity: Option<i32>, // removes the own capacity when missing
+    #[serde(default)]
+    pub parent_id: Option<i32>, // only a site moves, to another region or to the top of the tree when missing
+}
+
+/// Data Transfer Object for location responses
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LocationResponse {
+    pub id: Option<i32>,
+    pub parent_id: Option<i32>,
+    pub kind: LocationKind,
+    pub name: String,
+    pub office_id: Option<i32>,
+    pub capacity: Option<i32>, // own capacity, the office's max_occupancy for a site
+    pub total_capacity: Option<i64>, // own capacity, else rolled up from the children when all of them have one
+    pub occupancy: i64, // employees with a seat today or later in the location or below it
+}
\ No newline at end of file
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::dto::employee_dto::EmployeeResponse;
+use crate::entity::employee::Employee;
+
+/// Record a surviving field value is taken from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeSide {
+    Target, // the employee in the path, which survives
+    Source, // the employee named by source_id, which is archived
+}
+
+/// Per-field exceptions to the merge strategy, unset fields follow the strategy
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct MergeFields {
+    pub first_name: Option<MergeSide>,
+    pub last_name: Option<MergeSide>,
+    pub birth_date: Option<MergeSide>,
+    pub office_id: Option<MergeSide>,
+    pub department_id: Option<MergeSide>,
+    pub manager_id: Option<MergeSide>,
+    pub employment: Option<MergeSide>, // hire_date, termination_date and status, always taken together
+}
+
+/// Body of POST /employees/{id}/merge
+/// The strategy is required, there is no implicit choice of which values survive
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct MergeEmployeeRequest {
+    pub source_id: i32,
+    pub strategy: MergeSide, // side every field is taken from unless listed in fields
+    #[serde(default)]
+    pub fields: MergeFields,
+}
+
+impl MergeEmployeeRequest {
+    /// The surviving employee: ID of the target, each field from the side the strategy picks
+    /// A picked manager that is one of the two employees would make the survivor its own manager,
+    /// the other side's manager is taken then, or none if that is one of the two as well
+    pub fn merged(&self, target: &Employee, source: &Employee) -> Employee {
+        let pick = |field: Option<MergeSide>| match field.unwrap_or(self.strategy) {
+            MergeSide::Target => target,
+            MergeSide::Source => source,
+        };
+        let (picked, other) = match self.fields.manager_id.unwrap_or(self.strategy) {
+            MergeSide::Target => (target, source),
+            MergeSide::Source => (source, target),
+        };
+        let is_merged = |manager_id: Option<i32>| manager_id.is_some() && (manager_id == target.id || manager_id == source.id);
+        let manager_id = match (is_merged(picked.manager_id), is_merged(other.manager_id)) {
+            (false, _) => picked.manager_id,
+            (true, false) => other.manager_id,
+            (true, true) => None,
+        };
+        Employee {
+            id: target.id,
+            first_name: pick(self.fields.first_name).first_name.clone(),
+            last_name: pick(self.fields.last_name).last_name.clone(),
+            birth_date: pick(self.fields.birth_date).birth_date,
+            office_id: pick(self.fields.office_id).office_id,
+            department_id: pick(self.fields.department_id).department_id,
+            manager_id,
+            hire_date: pick(self.fields.employment).hire_date,
+            termination_date: pick(self.fields.employment).termination_date,
+            status: pick(self.fields.employment).status,
+        }
+    }
+}
+
+/// Result of a merge: the surviving employee, the archived source and the audit entry recording it
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MergeEmployeeResponse {
+    pub employee: EmployeeResponse,
+    pub archived_id: i32,
+    pub audit_entry_id: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use crate::entity::employee::EmploymentStatus;
+
+    fn employee(id: i32, first_name: &str, office_id: i32) -> Employee {
+        Employee {
+            id: Some(id),
+            first_name: first_name.to_string(),
+            last_name: "Berg".to_string(),
+            birth_date: NaiveDate::from_ymd_opt(1980, 1, 1).unwrap(),
+            office_id,
+            department_id: None,
+            manager_id: None,
+            hire_date: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+            termination_date: None,
+            status: EmploymentStatus::Active,
+        }
+    }
+
+    #[test]
+    fn test_fields_follow_strategy_unless_listed() {
+        let target = employee(1, "Ida", 10);
+        let source = employee(2, "Idah", 20);
+        let request: MergeEmployeeRequest =
+            serde_json::from_str(r#"{ "source_id": 2, "strategy": "target", "fields": { "office_id": "source" } }"#).unwrap();
+        let merged = request.merged(&target, &source);
+        assert_eq!((merged.id, merged.first_name.as_str(), merged.office_id), (Some(1), "Ida", 20));
+
+        let request: MergeEmployeeRequest = serde_json::from_str(r#"{ "source_id": 2, "strategy": "source" }"#).unwrap();
+        let merged = request.merged(&target, &source);
+        assert_eq!((merged.id, merged.first_name.as_str(), merged.office_id), (Some(1), "Idah", 20));
+    }
+
+    #[test]
+    fn test_strategy_is_required() {
+        assert!(serde_json::from_str::<MergeEmployeeRequest>(r#"{ "source_id": 2 }"#).is_err());
+    }
+
+    #[test]
+    fn test_manager_never_is_one_of_the_merged() {
+        let mut target = employee(1, "Ida", 10);
+        let mut source = employee(2, "Idah", 20);
+        target.manager_id = Some(2);
+        source.manager_id = Some(7);
+        let request: MergeEmployeeRequest = serde_json::from_str(r#"{ "source_id": 2, "strategy": "target" }"#).unwrap();
+        assert_eq!(request.merged(&target, &source).manager_id, Some(7));
+
+        source.manager_id = Some(1);
+        assert_eq!(request.merged(&target, &source).manager_id, None);
+
+        target.manager_id = None;
+        source.manager_id = Some(7);
+        assert_eq!(request.merged(&target, &source).manager_id, None);
+    }
+}
@@ -1,2 +1,14 @@
 pub mod office_dto;
-pub mod employee_dto;
\ No newline at end of file
+pub mod employee_dto;
+pub mod problem_dto;
+pub mod import_dto;
+pub mod export_dto;
+pub mod health_dto;
+pub mod validation_dto;
+pub mod merge_dto;
+pub mod department_dto;
+pub mod diagram_dto;
+pub mod transfer_dto;
+pub mod change_request_dto;
+pub mod waitlist_dto;
+pub mod location_dto;
\ No newline at end of file
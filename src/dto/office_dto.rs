@@ -1,11 +1,28 @@
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+use crate::entity::office::{valid_latitude, valid_longitude};
+use crate::utils::ValidationErrors;
 
 /// Data Transfer Object for creating a new office
-#[derive(Debug, Deserialize, ToSchema)]
+/// Address, country, timezone and coordinates are optional, blank values count as missing
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 pub struct CreateOfficeRequest {
     pub name: String,
     pub max_occupancy: i32,
+    #[serde(default)]
+    pub street: Option<String>,
+    #[serde(default)]
+    pub postal_code: Option<String>,
+    #[serde(default)]
+    pub city: Option<String>,
+    #[serde(default)]
+    pub country: Option<String>, // ISO 3166-1 alpha-2 code, e.g. DK
+    #[serde(default)]
+    pub timezone: Option<String>, // IANA name, e.g. Europe/Copenhagen
+    #[serde(default)]
+    pub latitude: Option<f64>, // -90..=90, given together with longitude
+    #[serde(default)]
+    pub longitude: Option<f64>, // -180..=180
 }
 
 /// Data Transfer Object for office GET responses
@@ -14,4 +31,83 @@ pub struct OfficeResponse {
     pub id: Option<i32>,
     pub name: String,
     pub max_occupancy: i32,
+    pub street: Option<String>,
+    pub postal_code: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub timezone: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance_km: Option<f64>, // only in GET /offices?near= results
+}
+
+/// Query parameters of GET /offices
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct OfficeListParams {
+    pub near: Option<String>, // "latitude,longitude" in degrees, lists offices within radius_km of it, nearest first
+    pub radius_km: Option<f64>, // required with near
+}
+
+/// Point and radius of a nearby office search
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchArea {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub radius_km: f64,
+}
+
+impl OfficeListParams {
+    /// Parses near and radius_km, None when neither is given, every broken rule otherwise
+    pub fn area(&self) -> Result<Option<SearchArea>, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let point = self.near.as_deref().map(|near| {
+            let point = near.split_once(',').and_then(|(lat, lon)| Some((lat.trim().parse::<f64>().ok()?, lon.trim().parse::<f64>().ok()?)));
+            match point {
+                None => errors.add("near", "invalid", &[("value", &near)]),
+                Some((lat, lon)) if !valid_latitude(lat) || !valid_longitude(lon) => errors.add("near", "out_of_range", &[]),
+                _ => {}
+            }
+            point
+        });
+        match (&self.near, self.radius_km) {
+            (Some(_), None) => errors.add("radius_km", "missing", &[]),
+            (None, Some(_)) => errors.add("near", "missing", &[]),
+            (_, Some(radius)) if !(radius > 0.0 && radius.is_finite()) => errors.add("radius_km", "not_positive", &[]),
+            _ => {}
+        }
+        errors.into_result()?;
+        Ok(point.flatten().zip(self.radius_km).map(|((latitude, longitude), radius_km)| SearchArea { latitude, longitude, radius_km }))
+    }
+}
+
+/// Query parameters of DELETE /offices/{id}
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeleteOfficeParams {
+    pub reassign_to: Option<i32>, // office that takes over the employees, in the same transaction as the delete
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(near: Option<&str>, radius_km: Option<f64>) -> OfficeListParams {
+        OfficeListParams { near: near.map(str::to_string), radius_km }
+    }
+
+    #[test]
+    fn test_area_parses_near_and_radius() {
+        assert_eq!(params(None, None).area().unwrap(), None);
+        assert_eq!(
+            params(Some("57.05, 9.92"), Some(25.0)).area().unwrap(),
+            Some(SearchArea { latitude: 57.05, longitude: 9.92, radius_km: 25.0 })
+        );
+    }
+
+    #[test]
+    fn test_area_reports_every_broken_rule() {
+        assert_eq!(params(Some("aalborg"), None).area().unwrap_err().codes(), vec!["near.invalid", "radius_km.missing"]);
+        assert_eq!(params(Some("91,10"), Some(0.0)).area().unwrap_err().codes(), vec!["near.out_of_range", "radius_km.not_positive"]);
+        assert_eq!(params(None, Some(10.0)).area().unwrap_err().codes(), vec!["near.missing"]);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,77 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use crate::utils::{CodedError, FieldError, ValidationErrors};
+use crate::utils::i18n::{localize, translate, Language};
+
+/// Problem details body (RFC 9457) returned for denied or malformed requests
+/// Serialized as application/problem+json
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>, // every broken validation rule, only on 422 validation problems
+}
+
+impl ProblemDetails {
+    // Builds a problem with the default "about:blank" type and the canonical status title
+    pub fn new(status: StatusCode, detail: impl Into<String>) -> Self {
+        ProblemDetails {
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail: detail.into(),
+            errors: Vec::new(),
+        }
+    }
+
+    // 401, request carried no usable credentials
+    pub fn unauthorized(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, detail)
+    }
+
+    // 403, caller is authenticated but lacks the required permission
+    pub fn forbidden(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, detail)
+    }
+
+    // 422, the body was readable but broke validation rules, lists all of them
+    pub fn validation(errors: &ValidationErrors) -> Self {
+        let mut problem = Self::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{} validation error(s)", errors.errors.len()));
+        problem.errors = errors.errors.clone();
+        problem
+    }
+
+    // The 422 problem of a service error that is a ValidationErrors, messages in the language, None for any other error
+    pub fn from_validation_error(error: &anyhow::Error, language: Language) -> Option<Self> {
+        error.downcast_ref::<ValidationErrors>().map(|errors| Self::validation(&errors.localized(language)))
+    }
+
+    // The 404 problem of a service error coded as a missing record, message in the language, None for any other error
+    pub fn from_not_found_error(error: &anyhow::Error, language: Language) -> Option<Self> {
+        error
+            .downcast_ref::<CodedError>()
+            .filter(|coded| coded.is_not_found())
+            .map(|_| Self::new(StatusCode::NOT_FOUND, localize(error, language)))
+    }
+
+    // 500, a generic message in the language, the cause belongs in the log and not in the response
+    pub fn internal(language: Language) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, translate(language, "error.internal", &[]))
+    }
+}
+
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (status, [(header::CONTENT_TYPE, "application/problem+json")], Json(self)).into_response()
+    }
+}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::entity::transfer::TransferStatus;
+
+/// Data Transfer Object for moving a pending transfer to another date
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RescheduleTransferRequest {
+    pub effective_date: NaiveDate,
+}
+
+/// Data Transfer Object for transfer responses
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TransferResponse {
+    pub id: Option<i32>,
+    pub employee_id: i32,
+    pub from_office_id: i32,
+    pub to_office_id: i32,
+    pub effective_date: NaiveDate,
+    pub status: TransferStatus,
+    pub requested_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query parameters of GET /offices/{id}/transfers
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TransferListParams {
+    pub status: Option<TransferStatus>, // only transfers with this status, all when missing
+}
@@ -0,0 +1,45 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use crate::entity::validation_rules::{DuplicatePolicy, EmployeeRules, NameRule, SurnamePolicy};
+
+/// Query parameters of GET /validation-rules
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ValidationRulesParams {
+    pub office_id: Option<i32>, // include the overrides of this office
+}
+
+/// Effective employee validation rules for frontends to mirror
+/// The birth date bounds are the age limits resolved for today, both inclusive
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ValidationRulesResponse {
+    pub tenant_id: i32,
+    pub office_id: Option<i32>,
+    pub min_age: u32,
+    pub max_age: u32,
+    pub earliest_birth_date: NaiveDate,
+    pub latest_birth_date: NaiveDate,
+    pub first_name: NameRule,
+    pub last_name: NameRule, // with the surname policy applied
+    pub surname_policy: SurnamePolicy,
+    pub duplicates: DuplicatePolicy,
+}
+
+impl ValidationRulesResponse {
+    // Resolves the age limits of the rules on `today`
+    pub fn new(tenant_id: i32, office_id: Option<i32>, rules: EmployeeRules, today: NaiveDate) -> Self {
+        let (earliest_birth_date, latest_birth_date) = rules.birth_date_range(today);
+        Self {
+            tenant_id,
+            office_id,
+            min_age: rules.min_age,
+            max_age: rules.max_age,
+            earliest_birth_date,
+            latest_birth_date,
+            last_name: rules.effective_last_name(),
+            first_name: rules.first_name,
+            surname_policy: rules.surname_policy,
+            duplicates: rules.duplicates,
+        }
+    }
+}
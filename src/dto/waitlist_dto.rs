@@ -0,0 +1,56 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::entity::waitlist::{WaitlistEventKind, WaitlistStatus};
+
+/// Data Transfer Object for putting a hire on the waitlist of a full office, the office comes from the path
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WaitlistRequest {
+    pub first_name: String,
+    pub last_name: String,
+    pub birth_date: NaiveDate,
+    #[serde(default)]
+    pub department_id: Option<i32>,
+    #[serde(default)]
+    pub manager_id: Option<i32>,
+    #[serde(default)]
+    pub hire_date: Option<NaiveDate>, // the day of placement when missing
+    #[serde(default)]
+    pub confirm: bool, // offer the seat for confirmation instead of placing right away
+}
+
+/// Data Transfer Object for waitlist entry responses
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WaitlistEntryResponse {
+    pub id: Option<i32>,
+    pub office_id: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub birth_date: NaiveDate,
+    pub department_id: Option<i32>,
+    pub manager_id: Option<i32>,
+    pub hire_date: Option<NaiveDate>,
+    pub confirm: bool,
+    pub status: WaitlistStatus,
+    pub position: Option<usize>, // 1 for the next entry to get a seat, missing once the entry no longer waits
+    pub employee_id: Option<i32>, // the employee once placed
+    pub requested_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Data Transfer Object for waitlist event responses
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WaitlistEventResponse {
+    pub id: Option<i32>,
+    pub entry_id: i32,
+    pub event: WaitlistEventKind,
+    pub details: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query parameters of GET /offices/{id}/waitlist
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct WaitlistListParams {
+    pub status: Option<WaitlistStatus>, // only entries with this status, all when missing
+}
@@ -0,0 +1,25 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Archived employee entity
+/// An employee that was merged into another one. The row left the employees table,
+/// so it no longer counts against its office's max_occupancy, and is kept here for reference.
+///
+/// database schema:
+/// id INT PRIMARY KEY, the former employees.id
+/// tenant_id INT NOT NULL REFERENCES tenants(id),
+/// first_name VARCHAR(100) NOT NULL,
+/// last_name VARCHAR(100) NOT NULL,
+/// birth_date DATE NOT NULL,
+/// office_id INT NOT NULL,
+/// merged_into INT REFERENCES employees(id) ON DELETE SET NULL,
+/// archived_at TIMESTAMPTZ NOT NULL DEFAULT now()
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ArchivedEmployee {
+    pub id: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub birth_date: NaiveDate,
+    pub office_id: i32, // office at the time of archiving, it may be gone since
+    pub merged_into: Option<i32>, // surviving employee, None once that one was deleted
+    pub archived_at: DateTime<Utc>,
+}
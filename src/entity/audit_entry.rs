@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+
+/// Audit entry entity
+/// Records who performed an action and what it changed, e.g. "employee.merged".
+/// details holds the action specific data as JSON text.
+///
+/// database schema:
+/// id BIGSERIAL PRIMARY KEY,
+/// tenant_id INT NOT NULL REFERENCES tenants(id),
+/// subject VARCHAR(100) NOT NULL,
+/// action VARCHAR(50) NOT NULL,
+/// employee_id INT, no foreign key so entries outlive the employee
+/// details TEXT NOT NULL,
+/// created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: Option<i64>, // optional as it will be set by the database
+    pub subject: String, // who performed the action
+    pub action: String,
+    pub employee_id: Option<i32>, // employee the action is about, moved to the survivor when employees are merged
+    pub details: String, // JSON
+    pub created_at: DateTime<Utc>,
+}
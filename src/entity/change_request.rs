@@ -0,0 +1,425 @@
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::types::Json;
+use utoipa::ToSchema;
+
+use crate::dto::change_request_dto::{ApprovalResponse, ChangeRequestResponse, CommentResponse};
+use crate::dto::employee_dto::CreateEmployeeRequest;
+use crate::dto::office_dto::CreateOfficeRequest;
+use crate::utils::{CodedError, ValidationErrors};
+use crate::utils::i18n::Language;
+
+/// Longest comment body in characters
+pub const MAX_COMMENT_LENGTH: usize = 2000;
+
+/// Change request entity
+/// A proposed office or employee change that needs sign-off before it is made.
+/// The approval steps are fixed on submission, see ProposedChange::approval_steps. When every step
+/// approved, the change is applied through the service that makes it without approval, with the same validation.
+///
+/// database schema:
+/// id SERIAL PRIMARY KEY,
+/// tenant_id INT NOT NULL REFERENCES tenants(id),
+/// change JSONB NOT NULL,
+/// status TEXT NOT NULL CHECK (status IN ('pending', 'approved', 'applied', 'failed', 'rejected', 'withdrawn')),
+/// requested_by VARCHAR(100) NOT NULL,
+/// created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+/// expires_at TIMESTAMPTZ NOT NULL,
+/// resolved_at TIMESTAMPTZ,
+/// failure_reason JSONB, the CodedErrors of why an approved change failed
+/// UNIQUE (tenant_id, id)
+///
+/// tenant_id is not part of the entity, repositories take it per call
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ChangeRequest {
+    pub id: Option<i32>, // optional as it will be set by the database
+    pub change: Json<ProposedChange>,
+    pub status: ChangeRequestStatus,
+    pub requested_by: String, // subject that submitted the request
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>, // a request still pending then can no longer be decided
+    pub resolved_at: Option<DateTime<Utc>>, // when it was approved, rejected or withdrawn
+    pub failure_reason: Option<Json<Vec<CodedError>>>, // why an approved change could not be applied, as codes to localize
+}
+
+/// The change a request proposes, applied through the named service once approved
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProposedChange {
+    /// Plans a transfer of the employee, see TransferService::schedule_transfer_approved
+    Transfer { employee_id: i32, to_office_id: i32, effective_date: NaiveDate },
+    /// Replaces the employee's data, moving to another office right away, see EmployeeService::update_employee
+    EmployeeUpdate { employee_id: i32, employee: CreateEmployeeRequest },
+    /// Replaces name and max_occupancy of the office, see OfficeService::update_office
+    OfficeUpdate { office_id: i32, office: CreateOfficeRequest },
+}
+
+/// Where a change request is
+/// Pending until every step approved (approved, then applied or failed) or one rejected,
+/// or until the requester withdraws it. A pending request past expires_at is expired, which is never stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum ChangeRequestStatus {
+    Pending,
+    Approved,
+    Applied,
+    Failed,
+    Rejected,
+    Withdrawn,
+    Expired,
+}
+
+/// Decision on one approval step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum Decision {
+    Approved,
+    Rejected,
+}
+
+/// Sign-off a change request needs
+/// facilities: a facilities admin, office_manager: a manager of the office. An admin may sign off any step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "approver", rename_all = "snake_case")]
+pub enum ApprovalStep {
+    Facilities,
+    OfficeManager { office_id: i32 },
+}
+
+/// One approval step of a change request, undecided until decision is set
+///
+/// database schema:
+/// tenant_id INT NOT NULL,
+/// change_request_id INT NOT NULL,
+/// step VARCHAR(50) NOT NULL, ApprovalStep::key
+/// decision TEXT CHECK (decision IN ('approved', 'rejected')),
+/// decided_by VARCHAR(100),
+/// decided_at TIMESTAMPTZ,
+/// PRIMARY KEY (change_request_id, step),
+/// FOREIGN KEY (tenant_id, change_request_id) REFERENCES change_requests(tenant_id, id) ON DELETE CASCADE
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Approval {
+    pub change_request_id: i32,
+    pub step: String,
+    pub decision: Option<Decision>,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+/// Comment on a change request
+///
+/// database schema:
+/// id SERIAL PRIMARY KEY,
+/// tenant_id INT NOT NULL,
+/// change_request_id INT NOT NULL,
+/// author VARCHAR(100) NOT NULL,
+/// body TEXT NOT NULL,
+/// created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+/// FOREIGN KEY (tenant_id, change_request_id) REFERENCES change_requests(tenant_id, id) ON DELETE CASCADE
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Comment {
+    pub id: Option<i32>, // optional as it will be set by the database
+    pub change_request_id: i32,
+    pub author: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A change request with its approval steps and comments, as the service hands it out
+#[derive(Debug, Clone)]
+pub struct ChangeRequestDetails {
+    pub request: ChangeRequest,
+    pub approvals: Vec<Approval>,
+    pub comments: Vec<Comment>,
+}
+
+impl ChangeRequestStatus {
+    // Status as stored and serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeRequestStatus::Pending => "pending",
+            ChangeRequestStatus::Approved => "approved",
+            ChangeRequestStatus::Applied => "applied",
+            ChangeRequestStatus::Failed => "failed",
+            ChangeRequestStatus::Rejected => "rejected",
+            ChangeRequestStatus::Withdrawn => "withdrawn",
+            ChangeRequestStatus::Expired => "expired",
+        }
+    }
+}
+
+impl std::fmt::Display for ChangeRequestStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Decision {
+    // Decision as stored and serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Decision::Approved => "approved",
+            Decision::Rejected => "rejected",
+        }
+    }
+}
+
+impl ApprovalStep {
+    // Step as stored: "facilities" or "office_manager:<office_id>"
+    pub fn key(&self) -> String {
+        match self {
+            ApprovalStep::Facilities => "facilities".to_string(),
+            ApprovalStep::OfficeManager { office_id } => format!("office_manager:{}", office_id),
+        }
+    }
+
+    // Parses a stored step, None for unknown keys
+    pub fn parse(key: &str) -> Option<Self> {
+        match key.split_once(':') {
+            None if key == "facilities" => Some(ApprovalStep::Facilities),
+            Some(("office_manager", office_id)) => office_id.parse().ok().map(|office_id| ApprovalStep::OfficeManager { office_id }),
+            _ => None,
+        }
+    }
+}
+
+impl ProposedChange {
+    // Employee or office the change is made to
+    pub fn employee_id(&self) -> Option<i32> {
+        match self {
+            ProposedChange::Transfer { employee_id, .. } | ProposedChange::EmployeeUpdate { employee_id, .. } => Some(*employee_id),
+            ProposedChange::OfficeUpdate { .. } => None,
+        }
+    }
+
+    // Office the employee moves to, None if the change moves nobody
+    pub fn destination_office_id(&self) -> Option<i32> {
+        match self {
+            ProposedChange::Transfer { to_office_id, .. } => Some(*to_office_id),
+            ProposedChange::EmployeeUpdate { employee, .. } => Some(employee.office_id),
+            ProposedChange::OfficeUpdate { .. } => None,
+        }
+    }
+
+    // Sign-offs the change needs, given the office it starts from: the employee's office or the office changed
+    // Moves need the managers of both offices, office changes facilities and the office's managers
+    pub fn approval_steps(&self, office_id: i32) -> Vec<ApprovalStep> {
+        let mut steps = match self {
+            ProposedChange::OfficeUpdate { .. } => vec![ApprovalStep::Facilities],
+            _ => Vec::new(),
+        };
+        steps.push(ApprovalStep::OfficeManager { office_id });
+        if let Some(to_office_id) = self.destination_office_id()
+            && to_office_id != office_id
+        {
+            steps.push(ApprovalStep::OfficeManager { office_id: to_office_id });
+        }
+        steps
+    }
+}
+
+impl ChangeRequest {
+    // Submits a change, pending until the expiry
+    pub fn new(change: ProposedChange, requested_by: &str, expires_at: DateTime<Utc>) -> Self {
+        ChangeRequest {
+            id: None,
+            change: Json(change),
+            status: ChangeRequestStatus::Pending,
+            requested_by: requested_by.to_string(),
+            created_at: Utc::now(),
+            expires_at,
+            resolved_at: None,
+            failure_reason: None,
+        }
+    }
+
+    // Status at a point in time, a pending request past its expiry is expired
+    pub fn status_at(&self, now: DateTime<Utc>) -> ChangeRequestStatus {
+        if self.status == ChangeRequestStatus::Pending && now >= self.expires_at {
+            return ChangeRequestStatus::Expired;
+        }
+        self.status
+    }
+
+    // Ok while the request can still be decided, commented on or withdrawn, otherwise change_request.not_pending
+    pub fn check_pending(&self, now: DateTime<Utc>) -> Result<(), CodedError> {
+        match self.status_at(now) {
+            ChangeRequestStatus::Pending => Ok(()),
+            status => Err(CodedError::new("change_request.not_pending").arg("id", self.id.unwrap_or_default()).arg("status", status)),
+        }
+    }
+
+    // Checks the expiry of a new request against the time of submission
+    pub fn check(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if self.expires_at <= self.created_at {
+            errors.add("expires_at", "past", &[]);
+        }
+        errors.into_result()
+    }
+}
+
+impl Comment {
+    // A comment by the author, created now
+    pub fn new(change_request_id: i32, author: &str, body: &str) -> Self {
+        Comment {
+            id: None,
+            change_request_id,
+            author: author.to_string(),
+            body: body.trim().to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    // Checks the body is neither blank nor too long
+    pub fn check(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if self.body.is_empty() {
+            errors.add("body", "empty", &[]);
+        } else if self.body.chars().count() > MAX_COMMENT_LENGTH {
+            errors.add("body", "too_long", &[("max", &MAX_COMMENT_LENGTH)]);
+        }
+        errors.into_result()
+    }
+
+    // Converts the Comment entity into a CommentResponse DTO
+    pub fn to_response(&self) -> CommentResponse {
+        CommentResponse {
+            id: self.id,
+            author: self.author.clone(),
+            body: self.body.clone(),
+            created_at: self.created_at,
+        }
+    }
+}
+
+impl Approval {
+    // An undecided step of a change request
+    pub fn new(change_request_id: i32, step: ApprovalStep) -> Self {
+        Approval { change_request_id, step: step.key(), decision: None, decided_by: None, decided_at: None }
+    }
+
+    // The step, None for keys this version does not know
+    pub fn approval_step(&self) -> Option<ApprovalStep> {
+        ApprovalStep::parse(&self.step)
+    }
+}
+
+// Outcome of the steps: rejected once one step is, approved once every step is, None while undecided
+pub fn outcome(approvals: &[Approval]) -> Option<Decision> {
+    if approvals.iter().any(|a| a.decision == Some(Decision::Rejected)) {
+        return Some(Decision::Rejected);
+    }
+    if !approvals.is_empty() && approvals.iter().all(|a| a.decision == Some(Decision::Approved)) {
+        return Some(Decision::Approved);
+    }
+    None
+}
+
+impl ChangeRequestDetails {
+    // Offices the request is about, its office_manager steps
+    pub fn office_ids(&self) -> Vec<i32> {
+        self.approvals
+            .iter()
+            .filter_map(|a| match a.approval_step() {
+                Some(ApprovalStep::OfficeManager { office_id }) => Some(office_id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Converts the details into a ChangeRequestResponse DTO, the status as of now and the failure reason in the language
+    pub fn to_response(&self, now: DateTime<Utc>, language: Language) -> ChangeRequestResponse {
+        ChangeRequestResponse {
+            id: self.request.id,
+            change: self.request.change.0.clone(),
+            status: self.request.status_at(now),
+            requested_by: self.request.requested_by.clone(),
+            created_at: self.request.created_at,
+            expires_at: self.request.expires_at,
+            resolved_at: self.request.resolved_at,
+            failure_reason: self.request.failure_reason.as_ref().map(|reasons| {
+                reasons.iter().map(|reason| reason.message(language)).collect::<Vec<_>>().join("; ")
+            }),
+            approvals: self
+                .approvals
+                .iter()
+                .filter_map(|a| {
+                    a.approval_step().map(|step| ApprovalResponse {
+                        step,
+                        decision: a.decision,
+                        decided_by: a.decided_by.clone(),
+                        decided_at: a.decided_at,
+                    })
+                })
+                .collect(),
+            comments: self.comments.iter().map(Comment::to_response).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(to_office_id: i32) -> ProposedChange {
+        ProposedChange::Transfer { employee_id: 7, to_office_id, effective_date: NaiveDate::from_ymd_opt(2030, 1, 1).expect("Invalid date") }
+    }
+
+    fn approval(step: ApprovalStep, decision: Option<Decision>) -> Approval {
+        Approval { decision, decided_by: decision.map(|_| "boss".to_string()), ..Approval::new(1, step) }
+    }
+
+    #[test]
+    fn test_moves_need_both_office_managers_and_office_changes_facilities() {
+        assert_eq!(
+            transfer(2).approval_steps(1),
+            vec![ApprovalStep::OfficeManager { office_id: 1 }, ApprovalStep::OfficeManager { office_id: 2 }]
+        );
+        let office_update = ProposedChange::OfficeUpdate { office_id: 3, office: CreateOfficeRequest { name: "Nord".into(), max_occupancy: 9, ..Default::default() } };
+        assert_eq!(office_update.approval_steps(3), vec![ApprovalStep::Facilities, ApprovalStep::OfficeManager { office_id: 3 }]);
+    }
+
+    #[test]
+    fn test_step_keys_round_trip() {
+        for step in [ApprovalStep::Facilities, ApprovalStep::OfficeManager { office_id: 12 }] {
+            assert_eq!(ApprovalStep::parse(&step.key()), Some(step));
+        }
+        assert_eq!(ApprovalStep::parse("office_manager:x"), None);
+        assert_eq!(ApprovalStep::parse("hr"), None);
+    }
+
+    #[test]
+    fn test_outcome_needs_every_approval_and_one_rejection() {
+        let facilities = ApprovalStep::Facilities;
+        let manager = ApprovalStep::OfficeManager { office_id: 1 };
+        assert_eq!(outcome(&[approval(facilities, Some(Decision::Approved)), approval(manager, None)]), None);
+        assert_eq!(outcome(&[approval(facilities, Some(Decision::Approved)), approval(manager, Some(Decision::Approved))]), Some(Decision::Approved));
+        assert_eq!(outcome(&[approval(facilities, None), approval(manager, Some(Decision::Rejected))]), Some(Decision::Rejected));
+        assert_eq!(outcome(&[]), None);
+    }
+
+    #[test]
+    fn test_pending_request_expires() {
+        let now = Utc::now();
+        let request = ChangeRequest::new(transfer(2), "hr", now + chrono::Duration::days(1));
+        assert_eq!(request.status_at(now), ChangeRequestStatus::Pending);
+        assert_eq!(request.status_at(now + chrono::Duration::days(2)), ChangeRequestStatus::Expired);
+        assert_eq!(request.check_pending(now + chrono::Duration::days(2)).unwrap_err().code, "change_request.not_pending");
+        let past = ChangeRequest::new(transfer(2), "hr", now - chrono::Duration::days(1));
+        assert_eq!(past.check().unwrap_err().codes(), vec!["expires_at.past"]);
+    }
+
+    #[test]
+    fn test_failure_reason_is_localized() {
+        let reason = CodedError::new("office.full").arg("name", "Nord").arg("max", 2);
+        let failed = ChangeRequest { status: ChangeRequestStatus::Failed, failure_reason: Some(Json(vec![reason])), ..ChangeRequest::new(transfer(2), "hr", Utc::now()) };
+        let details = ChangeRequestDetails { request: failed, approvals: Vec::new(), comments: Vec::new() };
+        assert_eq!(details.to_response(Utc::now(), Language::English).failure_reason.as_deref(), Some("Office Nord is at full capacity: 2/2 employees"));
+        assert_eq!(details.to_response(Utc::now(), Language::Danish).failure_reason.as_deref(), Some("Kontoret Nord er fuldt: 2/2 medarbejdere"));
+    }
+}
@@ -0,0 +1,75 @@
+use serde::{Serialize, Deserialize};
+
+use crate::dto::department_dto::{CreateDepartmentRequest, DepartmentResponse};
+use crate::utils::{Validate, ValidationErrors};
+use crate::entity::validation_rules::MAX_NAME_LENGTH;
+
+/// Department entity
+/// Represents a department with an optional ID and a name, employees may belong to one department.
+///
+/// database schema:
+/// id SERIAL PRIMARY KEY,
+/// tenant_id INT NOT NULL REFERENCES tenants(id),
+/// name VARCHAR(100) NOT NULL,
+/// UNIQUE (tenant_id, name)
+///
+/// tenant_id is not part of the entity, repositories take it per call
+///
+/// Includes validation for the name
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Department {
+    pub id: Option<i32>, // optional as it will be set by the database
+    pub name: String, // name of the department, unique per tenant
+}
+
+impl Department {
+    // Converts a CreateDepartmentRequest DTO into a Department entity
+    pub fn from_create_request(req: CreateDepartmentRequest) -> Self {
+        Department {
+            id: None,
+            name: req.name.trim().to_string(),
+        }
+    }
+    // Converts the Department entity into a DepartmentResponse DTO
+    pub fn to_response(&self) -> DepartmentResponse {
+        DepartmentResponse {
+            id: self.id,
+            name: self.name.clone(),
+        }
+    }
+}
+
+// builds on validation trait to validate department data
+// the rules are prefixed, "name.empty" and "name.too_long" are worded for offices
+impl Validate for Department {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if self.name.trim().is_empty() {
+            errors.add("name", "department_empty", &[]);
+        } else if self.name.trim().chars().count() > MAX_NAME_LENGTH {
+            errors.add("name", "department_too_long", &[("max", &MAX_NAME_LENGTH)]);
+        }
+        errors.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_department() {
+        let department = Department { id: None, name: "Salg".to_string() };
+        assert!(department.validate().is_ok());
+    }
+
+    #[test]
+    fn test_name_is_required_and_bounded() {
+        let empty = Department { id: None, name: "  ".to_string() };
+        assert_eq!(empty.validate().unwrap_err().to_string(), "Department name cannot be empty");
+        let long = Department { id: None, name: "x".repeat(101) };
+        assert_eq!(long.validate().unwrap_err().codes(), vec!["name.department_too_long"]);
+    }
+}
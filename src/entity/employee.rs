@@ -1,43 +1,167 @@
 use serde::{Serialize, Deserialize};
+use chrono::NaiveDate;
+use utoipa::ToSchema;
 
 use crate ::dto::employee_dto::{CreateEmployeeRequest, EmployeeResponse};
-use crate::utils::Validate;
-use chrono::Datelike;
+use crate::dto::import_dto::ImportEmployeeRow;
+use crate::entity::validation_rules::EmployeeRules;
+use crate::utils::{Validate, ValidationErrors};
+use crate::utils::names::{duplicate_key, normalize_name};
 
 /// Employee entity
-/// Represents an employee with an optional ID, first name, last name, birth date, and connected office ID.
+/// Represents an employee with an optional ID, first name, last name, birth date, connected office ID,
+/// optionally a department and a manager (another employee of the tenant), and the employment period and status.
+/// The employee holds a seat in the office from hire_date through termination_date.
 /// 
 /// database schema:
 /// id SERIAL PRIMARY KEY,
 /// first_name VARCHAR(100) NOT NULL,
 /// last_name VARCHAR(100) NOT NULL,
 /// birth_date DATE NOT NULL CHECK (birth_date < CURRENT_DATE),
-/// office_id INT NOT NULL REFERENCES offices(id)
+/// office_id INT NOT NULL,
+/// tenant_id INT NOT NULL REFERENCES tenants(id),
+/// department_id INT,
+/// manager_id INT CHECK (manager_id <> id),
+/// FOREIGN KEY (tenant_id, office_id) REFERENCES offices(tenant_id, id),
+/// FOREIGN KEY (tenant_id, department_id) REFERENCES departments(tenant_id, id),
+/// hire_date DATE NOT NULL,
+/// termination_date DATE CHECK (termination_date IS NULL OR termination_date >= hire_date),
+/// status TEXT NOT NULL CHECK (status IN ('pending', 'active', 'on_leave', 'terminated')),
+/// FOREIGN KEY (tenant_id, manager_id) REFERENCES employees(tenant_id, id) ON DELETE SET NULL (manager_id)
 /// 
-/// Includes validation for last name and vampire/baby status
+/// Includes validation of names, age and employment period, see entity::validation_rules
 /// Includes validation tests
 
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Employee {
     pub id: Option<i32>, // optional as it will be set by the database
     pub first_name: String, // any name but last name 
     pub last_name: String, // last name of the employee
     pub birth_date: chrono::NaiveDate, // birth date of the employee
     pub office_id: i32, // foreign key to office
+    pub department_id: Option<i32>, // foreign key to department
+    pub manager_id: Option<i32>, // employee this one reports to, None at the top of the hierarchy
+    pub hire_date: NaiveDate, // first day in the office
+    pub termination_date: Option<NaiveDate>, // last day in the office, set when terminated
+    pub status: EmploymentStatus, // changed by transitions only, see EmploymentStatus::can_become
+}
+
+/// Where an employee is in the employment lifecycle
+/// pending -> active (on or after the hire date), active <-> on_leave, and any of them -> terminated, which is final
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum EmploymentStatus {
+    Pending, // hired with a future start date, the seat is reserved from then
+    Active,
+    OnLeave, // keeps the seat
+    Terminated, // keeps the seat through the termination date
+}
+
+impl EmploymentStatus {
+    // Status as stored and serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmploymentStatus::Pending => "pending",
+            EmploymentStatus::Active => "active",
+            EmploymentStatus::OnLeave => "on_leave",
+            EmploymentStatus::Terminated => "terminated",
+        }
+    }
+
+    // Parses a stored status
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(EmploymentStatus::Pending),
+            "active" => Some(EmploymentStatus::Active),
+            "on_leave" => Some(EmploymentStatus::OnLeave),
+            "terminated" => Some(EmploymentStatus::Terminated),
+            _ => None,
+        }
+    }
+
+    // Status of a new employee, pending until the hire date
+    pub fn on_hire(hire_date: NaiveDate, today: NaiveDate) -> Self {
+        if hire_date > today { EmploymentStatus::Pending } else { EmploymentStatus::Active }
+    }
+
+    // True if the state machine allows moving from self to next
+    pub fn can_become(&self, next: EmploymentStatus) -> bool {
+        use EmploymentStatus::*;
+        matches!(
+            (self, next),
+            (Pending, Active) | (Active, OnLeave) | (OnLeave, Active) | (Pending | Active | OnLeave, Terminated)
+        )
+    }
+}
+
+impl std::fmt::Display for EmploymentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Highest number of seats taken on any day from `from` through `until` (open ended when None),
+/// given the seat periods (hire_date, termination_date) of an office's employees
+/// Seats are only taken on hire dates, so only `from` and the hire dates after it need counting
+pub fn peak_seats(periods: &[(NaiveDate, Option<NaiveDate>)], from: NaiveDate, until: Option<NaiveDate>) -> i64 {
+    let seated_on = |day: NaiveDate| periods.iter()
+        .filter(|(hire_date, termination_date)| *hire_date <= day && termination_date.is_none_or(|end| end >= day))
+        .count() as i64;
+    periods.iter()
+        .map(|(hire_date, _)| *hire_date)
+        .filter(|day| *day > from && until.is_none_or(|until| *day <= until))
+        .chain(std::iter::once(from))
+        .map(seated_on)
+        .max()
+        .unwrap_or(0)
 }
 
 impl Employee {
-    // Converts a CreateEmployeeRequest DTO into an Employee entity
+    // Converts a CreateEmployeeRequest DTO into an Employee entity, names normalized (NFC, single spaces)
+    // Hired today unless a hire date is given, pending when that lies in the future
     pub fn from_create_request(req: CreateEmployeeRequest) -> Self {
+        let today = chrono::Utc::now().date_naive();
+        let hire_date = req.hire_date.unwrap_or(today);
         Employee {
             id: None,
-            first_name: req.first_name.trim().to_string(),
-            last_name: req.last_name.trim().to_string(),
+            first_name: normalize_name(&req.first_name),
+            last_name: normalize_name(&req.last_name),
             birth_date: req.birth_date,
             office_id: req.office_id,
+            department_id: req.department_id,
+            manager_id: req.manager_id,
+            hire_date,
+            termination_date: None,
+            status: EmploymentStatus::on_hire(hire_date, today),
+        }
+    }
+    // Converts an import row into an Employee entity, office resolved by the caller, hired today without hire_date
+    pub fn from_import_row(row: &ImportEmployeeRow, office_id: i32) -> Self {
+        let today = chrono::Utc::now().date_naive();
+        let hire_date = row.hire_date.unwrap_or(today);
+        Employee {
+            id: None,
+            first_name: normalize_name(&row.first_name),
+            last_name: normalize_name(&row.last_name),
+            birth_date: row.birth_date,
+            office_id,
+            department_id: None,
+            manager_id: None,
+            hire_date,
+            termination_date: None,
+            status: EmploymentStatus::on_hire(hire_date, today),
         }
     }
+    // First day from `today` on that the employee holds a seat
+    pub fn seat_from(&self, today: NaiveDate) -> NaiveDate {
+        self.hire_date.max(today)
+    }
+    // Key under which employees count as the same person together with the birth date
+    pub fn duplicate_key(&self) -> String {
+        duplicate_key(&self.first_name, &self.last_name)
+    }
     // Converts the Employee entity into an EmployeeResponse DTO
     pub fn to_response(&self) -> EmployeeResponse {
         EmployeeResponse {
@@ -46,35 +170,44 @@ impl Employee {
             last_name: self.last_name.clone(),
             birth_date: self.birth_date,
             office_id: self.office_id,
+            department_id: self.department_id,
+            manager_id: self.manager_id,
+            hire_date: self.hire_date,
+            termination_date: self.termination_date,
+            status: self.status,
+        }
+    }
+    // Validates names and birth date against configured rules, ages counted on `today`,
+    // and that the employment period fits the status
+    pub fn validate_with(&self, rules: &EmployeeRules, today: chrono::NaiveDate) -> Result<(), ValidationErrors> {
+        let mut errors = rules.check(&self.first_name, &self.last_name, self.birth_date, today).err().unwrap_or_default();
+        self.check_employment(today, &mut errors);
+        errors.into_result()
+    }
+    // Adds the violations of the employment period: started while active or on leave, not terminated before hired
+    pub fn check_employment(&self, today: NaiveDate, errors: &mut ValidationErrors) {
+        if matches!(self.status, EmploymentStatus::Active | EmploymentStatus::OnLeave) && self.hire_date > today {
+            errors.add("hire_date", "after_start", &[("status", &self.status)]);
+        }
+        if let Some(termination_date) = self.termination_date
+            && termination_date < self.hire_date
+        {
+            errors.add("termination_date", "before_hire", &[("hire_date", &self.hire_date)]);
         }
     }
 }
 
-// builds on validation trait to validate employee data
+// builds on validation trait to validate employee data with the default rules
 impl Validate for Employee {
-    fn validate(&self) -> Result<(), String> {
-        if self.first_name.trim().is_empty() {
-            return Err("First name cannot be empty".into());
-        }
-        if self.last_name.trim().is_empty() {
-            return Err("Last name cannot be empty".into());
-        }
-        if self.last_name.chars().any(|c| c.is_whitespace()) {
-            return Err("Last name cannot contain whitespace".into());
-        }
-        if self.birth_date.year() < 1919 {
-            return Err("Born pre versaille treaty".into());
-        }
-        if self.birth_date.year() > 2007 {
-            return Err("Born post rust".into());
-        }
-        Ok(())
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.validate_with(&EmployeeRules::default(), chrono::Utc::now().date_naive())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::entity::validation_rules::SurnamePolicy;
     use chrono::NaiveDate;
 
     fn create_valid_employee() -> Employee {
@@ -84,6 +217,11 @@ mod tests {
             last_name: "Doe".to_string(),
             birth_date: NaiveDate::from_ymd_opt(1980, 1, 1).unwrap(),
             office_id: 1,
+            department_id: None,
+            manager_id: None,
+            hire_date: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+            termination_date: None,
+            status: EmploymentStatus::Active,
         }
     }
 
@@ -96,28 +234,56 @@ mod tests {
     #[test]
     fn test_last_name_with_whitespace() {
         let mut employee = create_valid_employee();
-        employee.last_name = "Lionel Messi".to_string();
-        let result = employee.validate();
+        employee.last_name = "van der Berg".to_string();
+        assert!(employee.validate().is_ok());
+        let single_word = EmployeeRules { surname_policy: SurnamePolicy::SingleWord, ..Default::default() };
+        let result = employee.validate_with(&single_word, chrono::Utc::now().date_naive());
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Last name cannot contain whitespace");
+        assert_eq!(result.unwrap_err().to_string(), "Last name cannot contain whitespace");
     }
 
     #[test]
-    fn test_born_before_1919() {
+    fn test_requests_are_normalized() {
+        let req = CreateEmployeeRequest {
+            first_name: " Rene\u{301} ".to_string(),
+            last_name: "van  der\tBerg".to_string(),
+            birth_date: NaiveDate::from_ymd_opt(1980, 1, 1).unwrap(),
+            office_id: 1,
+            department_id: None,
+            manager_id: None,
+            hire_date: None,
+        };
+        let employee = Employee::from_create_request(req);
+        assert_eq!(employee.first_name, "Ren\u{e9}");
+        assert_eq!(employee.last_name, "van der Berg");
+        assert_eq!(employee.duplicate_key(), "rene|vanderberg");
+    }
+
+    #[test]
+    fn test_older_than_max_age() {
         let mut employee = create_valid_employee();
-        employee.birth_date = NaiveDate::from_ymd_opt(1918, 1, 1).unwrap();
+        employee.birth_date = chrono::Utc::now().date_naive() - chrono::Months::new(101 * 12);
         let result = employee.validate();
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Born pre versaille treaty");
+        assert_eq!(result.unwrap_err().to_string(), "Employee cannot be older than 100 years");
     }
 
     #[test]
-    fn test_born_after_2007() {
+    fn test_younger_than_min_age() {
         let mut employee = create_valid_employee();
-        employee.birth_date = NaiveDate::from_ymd_opt(2008, 1, 1).unwrap();
+        employee.birth_date = chrono::Utc::now().date_naive() - chrono::Months::new(17 * 12);
         let result = employee.validate();
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Born post rust");
+        assert_eq!(result.unwrap_err().to_string(), "Employee must be at least 18 years old");
+    }
+
+    #[test]
+    fn test_age_limits_move_with_today() {
+        let mut employee = create_valid_employee();
+        employee.birth_date = NaiveDate::from_ymd_opt(2008, 1, 1).unwrap();
+        let rules = EmployeeRules::default();
+        assert!(employee.validate_with(&rules, NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()).is_err());
+        assert!(employee.validate_with(&rules, NaiveDate::from_ymd_opt(2026, 6, 1).unwrap()).is_ok());
     }
 
     #[test]
@@ -126,7 +292,7 @@ mod tests {
         employee.last_name = "   ".to_string();
         let result = employee.validate();
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Last name cannot be empty");
+        assert_eq!(result.unwrap_err().to_string(), "Last name cannot be empty");
     }
     #[test]
     fn test_empty_first_name() {
@@ -134,6 +300,61 @@ mod tests {
         employee.first_name = "   ".to_string();
         let result = employee.validate();
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "First name cannot be empty");
+        assert_eq!(result.unwrap_err().to_string(), "First name cannot be empty");
+    }
+
+    #[test]
+    fn test_every_broken_field_is_reported() {
+        let mut employee = create_valid_employee();
+        employee.first_name = "".to_string();
+        employee.last_name = "Lionel Messi".to_string();
+        employee.birth_date = chrono::Utc::now().date_naive() - chrono::Months::new(12);
+        let single_word = EmployeeRules { surname_policy: SurnamePolicy::SingleWord, ..Default::default() };
+        let errors = employee.validate_with(&single_word, chrono::Utc::now().date_naive()).unwrap_err();
+        assert_eq!(errors.codes(), vec!["first_name.empty", "last_name.whitespace", "birth_date.too_young"]);
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        use EmploymentStatus::*;
+        assert!(Pending.can_become(Active));
+        assert!(Active.can_become(OnLeave) && OnLeave.can_become(Active));
+        assert!(Pending.can_become(Terminated) && OnLeave.can_become(Terminated));
+        assert!(!Pending.can_become(OnLeave));
+        assert!(!Terminated.can_become(Active));
+        assert!(!Active.can_become(Active));
+    }
+
+    #[test]
+    fn test_future_hire_is_pending() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        assert_eq!(EmploymentStatus::on_hire(today, today), EmploymentStatus::Active);
+        assert_eq!(EmploymentStatus::on_hire(today + chrono::Days::new(1), today), EmploymentStatus::Pending);
+    }
+
+    #[test]
+    fn test_employment_period_is_validated() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let mut employee = create_valid_employee();
+        employee.hire_date = NaiveDate::from_ymd_opt(2026, 4, 1).unwrap();
+        employee.termination_date = NaiveDate::from_ymd_opt(2026, 3, 15);
+        let errors = employee.validate_with(&EmployeeRules::default(), today).unwrap_err();
+        assert_eq!(errors.codes(), vec!["hire_date.after_start", "termination_date.before_hire"]);
+
+        employee.status = EmploymentStatus::Pending;
+        employee.termination_date = None;
+        assert!(employee.validate_with(&EmployeeRules::default(), today).is_ok());
+    }
+
+    #[test]
+    fn test_peak_seats_counts_overlapping_periods() {
+        let day = |d: u32| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        // leaves on the 10th, a new hire starts on the 11th, another one on the 20th
+        let periods = vec![(day(1), Some(day(10))), (day(11), None), (day(20), None), (day(1), None)];
+        assert_eq!(peak_seats(&periods, day(5), Some(day(5))), 2);
+        assert_eq!(peak_seats(&periods, day(5), Some(day(15))), 2);
+        assert_eq!(peak_seats(&periods, day(5), None), 3);
+        assert_eq!(peak_seats(&periods, day(21), None), 3);
+        assert_eq!(peak_seats(&[], day(1), None), 0);
     }
 }
\ No newline at end of file
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+
+/// Idempotency record entity
+/// Remembers the first response to a POST sent with an Idempotency-Key header.
+/// status_code is None while the first request is still being processed.
+///
+/// database schema:
+/// tenant_id INT NOT NULL REFERENCES tenants(id),
+/// subject VARCHAR(100) NOT NULL,
+/// route VARCHAR(100) NOT NULL,
+/// idempotency_key VARCHAR(255) NOT NULL,
+/// request_hash CHAR(64) NOT NULL,
+/// status_code INT,
+/// content_type VARCHAR(100),
+/// response_body BYTEA,
+/// created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+/// PRIMARY KEY (tenant_id, subject, route, idempotency_key)
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct IdempotencyRecord {
+    pub request_hash: String, // sha256 of the request body, hex encoded
+    pub status_code: Option<i32>, // stored response status, None while in progress
+    pub content_type: Option<String>, // stored response content type
+    pub response_body: Option<Vec<u8>>, // stored response body, byte exact
+    pub created_at: DateTime<Utc>, // reservation time, keys expire relative to it
+}
+
+/// Identifies one idempotency key, keys of different tenants, subjects or routes never collide
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey {
+    pub tenant_id: i32,
+    pub subject: String,
+    pub route: String,
+    pub key: String,
+}
@@ -0,0 +1,375 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+
+use crate::dto::location_dto::{CreateLocationRequest, LocationResponse};
+use crate::utils::{CodedError, Validate, ValidationErrors};
+use crate::entity::office::Office;
+use crate::entity::validation_rules::MAX_NAME_LENGTH;
+
+/// Location entity
+/// One node of a tenant's location tree: region -> site -> building -> floor -> room.
+/// A site is an office, buildings, floors and rooms carry the office of the site above them.
+/// Employees sit in a room when they have one, otherwise in the site of their office.
+///
+/// database schema:
+/// id SERIAL PRIMARY KEY,
+/// tenant_id INT NOT NULL REFERENCES tenants(id),
+/// parent_id INT,
+/// kind TEXT NOT NULL CHECK (kind IN ('region', 'site', 'building', 'floor', 'room')),
+/// name VARCHAR(100) NOT NULL,
+/// capacity INT CHECK (capacity IS NULL OR (capacity > 0 AND kind <> 'site')),
+/// office_id INT CHECK ((kind = 'region') = (office_id IS NULL)),
+/// FOREIGN KEY (tenant_id, parent_id) REFERENCES locations(tenant_id, id),
+/// FOREIGN KEY (tenant_id, office_id) REFERENCES offices(tenant_id, id) ON DELETE CASCADE,
+/// UNIQUE (office_id) WHERE kind = 'site'
+///
+/// tenant_id is not part of the entity, repositories take it per call
+///
+/// Includes validation for the name, capacity and office of the kind
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Location {
+    pub id: Option<i32>, // optional as it will be set by the database
+    pub parent_id: Option<i32>, // None for the top of the tree
+    pub kind: LocationKind,
+    pub name: String,
+    pub capacity: Option<i32>, // own capacity, read as the office's max_occupancy for a site
+    pub office_id: Option<i32>, // the office of a site and of everything below it, None for a region
+}
+
+/// Level of a location in the tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum LocationKind {
+    Region,
+    Site,
+    Building,
+    Floor,
+    Room,
+}
+
+impl LocationKind {
+    // Kind as stored and serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LocationKind::Region => "region",
+            LocationKind::Site => "site",
+            LocationKind::Building => "building",
+            LocationKind::Floor => "floor",
+            LocationKind::Room => "room",
+        }
+    }
+
+    // Kind of the parent a location of this kind is placed under, None for a region
+    // Sites may also stand at the top of the tree, as every office did before regions existed
+    pub fn parent_kind(&self) -> Option<LocationKind> {
+        match self {
+            LocationKind::Region => None,
+            LocationKind::Site => Some(LocationKind::Region),
+            LocationKind::Building => Some(LocationKind::Site),
+            LocationKind::Floor => Some(LocationKind::Building),
+            LocationKind::Room => Some(LocationKind::Floor),
+        }
+    }
+
+    // True if a location of this kind may have no parent
+    pub fn may_be_top(&self) -> bool {
+        matches!(self, LocationKind::Region | LocationKind::Site)
+    }
+}
+
+impl std::fmt::Display for LocationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Location {
+    // Converts a CreateLocationRequest DTO into a Location entity, office_id of buildings, floors and rooms comes from the parent
+    pub fn from_create_request(req: CreateLocationRequest) -> Self {
+        Location {
+            id: None,
+            parent_id: req.parent_id,
+            kind: req.kind,
+            name: req.name.trim().to_string(),
+            capacity: req.capacity,
+            office_id: req.office_id,
+        }
+    }
+
+    // The site of a new office, at the top of the tree until it is moved into a region
+    pub fn site_for(office: &Office) -> Self {
+        Location { id: None, parent_id: None, kind: LocationKind::Site, name: office.name.clone(), capacity: None, office_id: office.id }
+    }
+
+    // The capacity error naming this level as the one without a free seat
+    pub fn full(&self, capacity: i64) -> CodedError {
+        CodedError::new("location.full").arg("name", &self.name).arg("kind", self.kind).arg("capacity", capacity)
+    }
+
+    // Checks the location against the parent it is placed under
+    pub fn check_parent(&self, parent: Option<&Location>, errors: &mut ValidationErrors) {
+        match (parent, self.kind.parent_kind()) {
+            (None, _) if self.kind.may_be_top() => {}
+            (None, Some(expected)) => errors.add("parent_id", "missing", &[("kind", &self.kind), ("parent", &expected)]),
+            (Some(parent), Some(expected)) if parent.kind == expected => {}
+            (_, expected) => {
+                let expected = expected.map_or("nothing", |kind| kind.as_str());
+                errors.add("parent_id", "wrong_kind", &[("kind", &self.kind), ("parent", &expected)]);
+            }
+        }
+    }
+}
+
+// builds on validation trait to validate location data
+// the name rules are prefixed, "name.empty" and "name.too_long" are worded for offices
+impl Validate for Location {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if self.name.trim().is_empty() {
+            errors.add("name", "location_empty", &[]);
+        } else if self.name.trim().chars().count() > MAX_NAME_LENGTH {
+            errors.add("name", "location_too_long", &[("max", &MAX_NAME_LENGTH)]);
+        }
+        if self.kind == LocationKind::Site && self.capacity.is_some() {
+            errors.add("capacity", "on_site", &[]);
+        } else if self.capacity.is_some_and(|capacity| capacity <= 0) {
+            errors.add("capacity", "not_positive", &[]);
+        }
+        if self.kind == LocationKind::Site && self.office_id.is_none() {
+            errors.add("office_id", "missing", &[]);
+        }
+        errors.into_result()
+    }
+}
+
+/// Occupancy and capacity of a location with everything below it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LocationUsage {
+    pub occupancy: i64, // employees holding a seat today or later in the location or below it
+    pub total_capacity: Option<i64>, // own capacity, else the sum of the children's when all of them have one
+}
+
+/// The locations of a tenant with occupancy and capacity rolled up to the parents
+pub struct LocationTree {
+    locations: BTreeMap<i32, Location>,
+    children: HashMap<i32, Vec<i32>>,
+    usage: HashMap<i32, LocationUsage>,
+}
+
+impl LocationTree {
+    /// Builds the tree from all locations of a tenant and the number of employees sitting directly in each
+    pub fn new(locations: Vec<Location>, occupants: &[(i32, i64)]) -> Self {
+        let locations: BTreeMap<i32, Location> = locations.into_iter().filter_map(|l| Some((l.id?, l))).collect();
+        let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+        for location in locations.values() {
+            if let (Some(parent_id), Some(id)) = (location.parent_id, location.id) {
+                children.entry(parent_id).or_default().push(id);
+            }
+        }
+        let direct: HashMap<i32, i64> = occupants.iter().copied().collect();
+        let mut tree = LocationTree { locations, children, usage: HashMap::new() };
+        let tops: Vec<i32> = tree.locations.values().filter(|l| l.parent_id.is_none()).filter_map(|l| l.id).collect();
+        for id in tops {
+            tree.roll_up(id, &direct);
+        }
+        tree
+    }
+
+    // Computes the usage of a location after that of its children
+    fn roll_up(&mut self, id: i32, direct: &HashMap<i32, i64>) -> LocationUsage {
+        let children = self.children.get(&id).cloned().unwrap_or_default();
+        let mut occupancy = direct.get(&id).copied().unwrap_or(0);
+        let mut children_capacity = Some(0);
+        for child in &children {
+            let usage = self.roll_up(*child, direct);
+            occupancy += usage.occupancy;
+            children_capacity = children_capacity.zip(usage.total_capacity).map(|(sum, capacity)| sum + capacity);
+        }
+        let own = self.locations.get(&id).and_then(|l| l.capacity).map(i64::from);
+        let total_capacity = own.or(if children.is_empty() { None } else { children_capacity });
+        let usage = LocationUsage { occupancy, total_capacity };
+        self.usage.insert(id, usage);
+        usage
+    }
+
+    /// The location with the ID
+    pub fn get(&self, id: i32) -> Option<&Location> {
+        self.locations.get(&id)
+    }
+
+    /// Rolled up occupancy and capacity of the location
+    pub fn usage(&self, id: i32) -> LocationUsage {
+        self.usage.get(&id).copied().unwrap_or_default()
+    }
+
+    /// The site of an office, if it has one
+    pub fn site_of(&self, office_id: i32) -> Option<&Location> {
+        self.locations.values().find(|l| l.kind == LocationKind::Site && l.office_id == Some(office_id))
+    }
+
+    /// True if the location has locations below it
+    pub fn has_children(&self, id: i32) -> bool {
+        self.children.get(&id).is_some_and(|children| !children.is_empty())
+    }
+
+    /// The location and the ones above it, the location first and the top of the tree last
+    pub fn ancestors(&self, id: i32) -> Vec<&Location> {
+        let mut chain = Vec::new();
+        let mut next = self.locations.get(&id);
+        while let Some(location) = next {
+            chain.push(location);
+            next = location.parent_id.and_then(|parent_id| self.locations.get(&parent_id));
+        }
+        chain
+    }
+
+    /// True if `id` is `ancestor_id` or lies below it
+    pub fn is_within(&self, id: i32, ancestor_id: i32) -> bool {
+        self.ancestors(id).iter().any(|l| l.id == Some(ancestor_id))
+    }
+
+    /// The first location, from `target_id` up, that has no room for `arriving` more employees.
+    /// Levels that already hold them (`from_id` lies within) are skipped, they do not take a new seat there
+    pub fn first_full(&self, target_id: i32, from_id: Option<i32>, arriving: i64) -> Option<(&Location, i64)> {
+        self.ancestors(target_id).into_iter().find_map(|location| {
+            let id = location.id?;
+            if from_id.is_some_and(|from_id| self.is_within(from_id, id)) {
+                return None;
+            }
+            let usage = self.usage(id);
+            let capacity = usage.total_capacity?;
+            (usage.occupancy + arriving > capacity).then_some((location, capacity))
+        })
+    }
+
+    /// The first location above the site of an office without room for `arriving` more employees, who come from the site
+    /// of `from_office_id` or from outside the tree. The site itself is the office, the office's own checks count its seats
+    pub fn first_full_above_site(&self, office_id: i32, from_office_id: Option<i32>, arriving: i64) -> Option<(&Location, i64)> {
+        let parent_id = self.site_of(office_id)?.parent_id?;
+        let from_id = from_office_id.and_then(|id| self.site_of(id)).and_then(|site| site.id);
+        self.first_full(parent_id, from_id, arriving)
+    }
+
+    /// Locations in tree order, each followed by the ones below it, siblings by ID
+    pub fn in_order(&self) -> Vec<&Location> {
+        let mut ordered = Vec::with_capacity(self.locations.len());
+        let mut stack: Vec<i32> = self.locations.values().filter(|l| l.parent_id.is_none()).filter_map(|l| l.id).rev().collect();
+        while let Some(id) = stack.pop() {
+            if let Some(location) = self.locations.get(&id) {
+                ordered.push(location);
+            }
+            if let Some(children) = self.children.get(&id) {
+                let mut children = children.clone();
+                children.sort_unstable();
+                stack.extend(children.into_iter().rev());
+            }
+        }
+        ordered
+    }
+
+    /// Converts a location of the tree into a LocationResponse DTO with its rolled up figures
+    pub fn to_response(&self, location: &Location) -> LocationResponse {
+        let usage = location.id.map(|id| self.usage(id)).unwrap_or_default();
+        LocationResponse {
+            id: location.id,
+            parent_id: location.parent_id,
+            kind: location.kind,
+            name: location.name.clone(),
+            office_id: location.office_id,
+            capacity: location.capacity,
+            total_capacity: usage.total_capacity,
+            occupancy: usage.occupancy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(id: i32, parent_id: Option<i32>, kind: LocationKind, capacity: Option<i32>) -> Location {
+        let office_id = (kind != LocationKind::Region).then_some(1);
+        Location { id: Some(id), parent_id, kind, name: format!("{} {}", kind, id), capacity, office_id }
+    }
+
+    // region 1 -> site 2 (max 10) -> building 3 -> floor 4 -> rooms 5 (2 seats) and 6 (3 seats)
+    fn tree(occupants: &[(i32, i64)]) -> LocationTree {
+        LocationTree::new(
+            vec![
+                location(1, None, LocationKind::Region, None),
+                location(2, Some(1), LocationKind::Site, Some(10)),
+                location(3, Some(2), LocationKind::Building, None),
+                location(4, Some(3), LocationKind::Floor, None),
+                location(5, Some(4), LocationKind::Room, Some(2)),
+                location(6, Some(4), LocationKind::Room, Some(3)),
+            ],
+            occupants,
+        )
+    }
+
+    #[test]
+    fn test_capacity_and_occupancy_roll_up() {
+        let tree = tree(&[(2, 4), (5, 2), (6, 1)]);
+        assert_eq!(tree.usage(4), LocationUsage { occupancy: 3, total_capacity: Some(5) });
+        assert_eq!(tree.usage(3), LocationUsage { occupancy: 3, total_capacity: Some(5) });
+        assert_eq!(tree.usage(2), LocationUsage { occupancy: 7, total_capacity: Some(10) });
+        assert_eq!(tree.usage(1), LocationUsage { occupancy: 7, total_capacity: Some(10) });
+    }
+
+    #[test]
+    fn test_child_without_capacity_leaves_parent_open() {
+        let mut locations = vec![location(1, None, LocationKind::Building, None)];
+        locations.push(location(2, Some(1), LocationKind::Floor, Some(4)));
+        locations.push(location(3, Some(1), LocationKind::Floor, None));
+        let tree = LocationTree::new(locations, &[]);
+        assert_eq!(tree.usage(1).total_capacity, None);
+    }
+
+    #[test]
+    fn test_first_full_checks_every_level_not_yet_holding_the_employee() {
+        let tree = tree(&[(2, 4), (5, 2), (6, 1)]);
+        assert_eq!(tree.first_full(5, Some(2), 1).map(|(l, capacity)| (l.id, capacity)), Some((Some(5), 2)));
+        assert!(tree.first_full(6, Some(2), 1).is_none());
+        // moving within the floor needs a seat in the room only
+        let full_room = self::tree(&[(5, 1), (6, 3)]);
+        assert_eq!(full_room.first_full(6, Some(5), 1).map(|(l, _)| l.id), Some(Some(6)));
+        assert!(full_room.first_full(5, Some(6), 1).is_none());
+        // arriving from another office needs a seat in the site as well
+        let full_site = self::tree(&[(2, 8), (6, 2)]);
+        assert_eq!(full_site.first_full(6, None, 1).map(|(l, _)| l.id), Some(Some(2)));
+    }
+
+    #[test]
+    fn test_first_full_above_site_checks_the_region_only() {
+        let mut locations = vec![location(1, None, LocationKind::Region, Some(4)), location(2, Some(1), LocationKind::Site, Some(10))];
+        locations.push(Location { office_id: Some(2), ..location(3, Some(1), LocationKind::Site, Some(10)) });
+        locations.push(Location { office_id: Some(3), ..location(4, None, LocationKind::Site, Some(10)) });
+        let tree = LocationTree::new(locations, &[(2, 3)]);
+        assert!(tree.first_full_above_site(1, None, 1).is_none());
+        assert_eq!(tree.first_full_above_site(2, None, 2).map(|(l, capacity)| (l.id, capacity)), Some((Some(1), 4)));
+        // moving within the region or into a site without one takes no seat in a region
+        assert!(tree.first_full_above_site(2, Some(1), 2).is_none());
+        assert!(tree.first_full_above_site(3, None, 5).is_none());
+    }
+
+    #[test]
+    fn test_parent_kind_is_checked() {
+        let room = location(7, Some(3), LocationKind::Room, None);
+        let mut errors = ValidationErrors::new();
+        room.check_parent(Some(&location(3, Some(2), LocationKind::Building, None)), &mut errors);
+        room.check_parent(None, &mut errors);
+        location(8, None, LocationKind::Site, None).check_parent(None, &mut errors);
+        assert_eq!(errors.codes(), vec!["parent_id.wrong_kind", "parent_id.missing"]);
+    }
+
+    #[test]
+    fn test_in_order_lists_parents_before_children() {
+        let tree = tree(&[]);
+        let ids: Vec<_> = tree.in_order().iter().filter_map(|l| l.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5, 6]);
+    }
+}
\ No newline at end of file
@@ -1,2 +1,12 @@
 pub mod office;
-pub mod employee;
\ No newline at end of file
+pub mod employee;
+pub mod tenant;
+pub mod idempotency_record;
+pub mod validation_rules;
+pub mod archived_employee;
+pub mod audit_entry;
+pub mod department;
+pub mod transfer;
+pub mod change_request;
+pub mod waitlist;
+pub mod location;
\ No newline at end of file
@@ -1,24 +1,52 @@
 use serde::{Serialize, Deserialize};
 
 use crate::dto::office_dto::{CreateOfficeRequest, OfficeResponse};
-use crate::utils::Validate;
+use crate::utils::{Validate, ValidationErrors};
+use crate::entity::validation_rules::MAX_NAME_LENGTH;
+
+/// Longest street and city accepted, matches VARCHAR(100)
+pub const MAX_ADDRESS_LENGTH: usize = 100;
+/// Longest postal code accepted, matches VARCHAR(20)
+pub const MAX_POSTAL_CODE_LENGTH: usize = 20;
+/// Mean earth radius used for distances between offices
+pub const EARTH_RADIUS_KM: f64 = 6371.0;
 
 /// Office entity 
 /// Represents an office with an optional ID, name, and maximum occupancy.
+/// Address, country, timezone and coordinates are optional.
 /// 
 /// database schema:
 /// id SERIAL PRIMARY KEY,
-/// name TEXT NOT NULL UNIQUE,
-/// max_occupancy INT NOT NULL CHECK (max_occupancy > 0)
+/// name VARCHAR(100) NOT NULL,
+/// max_occupancy INT NOT NULL CHECK (max_occupancy > 0),
+/// tenant_id INT NOT NULL REFERENCES tenants(id),
+/// street VARCHAR(100),
+/// postal_code VARCHAR(20),
+/// city VARCHAR(100),
+/// country CHAR(2) CHECK (country ~ '^[A-Z]{2}$'),
+/// timezone VARCHAR(64),
+/// latitude DOUBLE PRECISION CHECK (latitude BETWEEN -90 AND 90),
+/// longitude DOUBLE PRECISION CHECK (longitude BETWEEN -180 AND 180),
+/// UNIQUE (tenant_id, name),
+/// CHECK ((latitude IS NULL) = (longitude IS NULL))
+///
+/// tenant_id is not part of the entity, repositories take it per call
 /// 
-/// Includes validation for occupancy and name
+/// Includes validation for occupancy, name, address lengths, country, timezone and coordinates
 
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Office {
     pub id: Option<i32>, // optional as it will be set by the database
-    pub name: String, // name of the office, unique
+    pub name: String, // name of the office, unique per tenant
     pub max_occupancy: i32, // maximum occupancy of the office
+    pub street: Option<String>,
+    pub postal_code: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>, // ISO 3166-1 alpha-2 code, upper case
+    pub timezone: Option<String>, // IANA name such as Europe/Copenhagen
+    pub latitude: Option<f64>, // set together with longitude
+    pub longitude: Option<f64>,
 }
 
 impl Office {
@@ -28,6 +56,13 @@ impl Office {
             id: None,
             name: req.name.trim().to_string(),
             max_occupancy: req.max_occupancy,
+            street: non_blank(req.street),
+            postal_code: non_blank(req.postal_code),
+            city: non_blank(req.city),
+            country: non_blank(req.country).map(|c| c.to_uppercase()),
+            timezone: non_blank(req.timezone),
+            latitude: req.latitude,
+            longitude: req.longitude,
         }
     }
     // Converts the Office entity into an OfficeResponse DTO
@@ -36,20 +71,90 @@ impl Office {
             id: self.id,
             name: self.name.clone(),
             max_occupancy: self.max_occupancy,
+            street: self.street.clone(),
+            postal_code: self.postal_code.clone(),
+            city: self.city.clone(),
+            country: self.country.clone(),
+            timezone: self.timezone.clone(),
+            latitude: self.latitude,
+            longitude: self.longitude,
+            distance_km: None,
         }
     }
+
+    // Great-circle distance in kilometres to a point, None when the office has no coordinates
+    pub fn distance_km(&self, latitude: f64, longitude: f64) -> Option<f64> {
+        Some(haversine_km(self.latitude?, self.longitude?, latitude, longitude))
+    }
+}
+
+// Empty and whitespace-only values are treated as missing, forms and CSV send them for blank fields
+fn non_blank(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Haversine distance in kilometres between two points given in degrees
+/// The SQL backends compute the same formula for GET /offices?near=
+pub fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
+
+/// Whether a latitude lies within -90..=90 degrees
+pub fn valid_latitude(latitude: f64) -> bool {
+    (-90.0..=90.0).contains(&latitude)
+}
+
+/// Whether a longitude lies within -180..=180 degrees
+pub fn valid_longitude(longitude: f64) -> bool {
+    (-180.0..=180.0).contains(&longitude)
 }
 
 // builds on validation trait to validate office data
 impl Validate for Office {
-    fn validate(&self) -> Result<(), String> {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if self.name.trim().is_empty() {
+            errors.add("name", "empty", &[]);
+        } else if self.name.trim().chars().count() > MAX_NAME_LENGTH {
+            errors.add("name", "too_long", &[("max", &MAX_NAME_LENGTH)]);
+        }
         if self.max_occupancy <= 0 {
-            return Err("Max occupancy must be greater than 0".to_string());
+            errors.add("max_occupancy", "not_positive", &[]);
         }
-        if self.name.trim().is_empty() {
-            return Err("Office name cannot be empty".to_string());
+        for (field, value, max) in [
+            ("street", &self.street, MAX_ADDRESS_LENGTH),
+            ("postal_code", &self.postal_code, MAX_POSTAL_CODE_LENGTH),
+            ("city", &self.city, MAX_ADDRESS_LENGTH),
+        ] {
+            if value.as_ref().is_some_and(|v| v.chars().count() > max) {
+                errors.add(field, "too_long", &[("max", &max)]);
+            }
+        }
+        if let Some(country) = &self.country
+            && !(country.len() == 2 && country.chars().all(|c| c.is_ascii_uppercase()))
+        {
+            errors.add("country", "invalid", &[("value", country)]);
+        }
+        if let Some(timezone) = &self.timezone
+            && timezone.parse::<chrono_tz::Tz>().is_err()
+        {
+            errors.add("timezone", "unknown", &[("value", timezone)]);
+        }
+        match (self.latitude, self.longitude) {
+            (Some(_), None) => errors.add("longitude", "missing", &[]),
+            (None, Some(_)) => errors.add("latitude", "missing", &[]),
+            _ => {}
         }
-        Ok(())
+        if self.latitude.is_some_and(|lat| !valid_latitude(lat)) {
+            errors.add("latitude", "out_of_range", &[]);
+        }
+        if self.longitude.is_some_and(|lon| !valid_longitude(lon)) {
+            errors.add("longitude", "out_of_range", &[]);
+        }
+        errors.into_result()
     }
 }
 
@@ -63,6 +168,7 @@ mod tests {
             id: None,
             name: "Aalborg".to_string(),
             max_occupancy: 3,
+            ..Default::default()
         }
     }
 
@@ -78,7 +184,7 @@ mod tests {
         office.max_occupancy = 0;
         let result = office.validate();
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Max occupancy must be greater than 0");
+        assert_eq!(result.unwrap_err().to_string(), "Max occupancy must be greater than 0");
     }
 
     #[test]
@@ -87,7 +193,7 @@ mod tests {
         office.max_occupancy = -5;
         let result = office.validate();
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Max occupancy must be greater than 0");
+        assert_eq!(result.unwrap_err().to_string(), "Max occupancy must be greater than 0");
     }
 
     #[test]
@@ -96,6 +202,66 @@ mod tests {
         office.name = "".to_string();
         let result = office.validate();
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Office name cannot be empty");
+        assert_eq!(result.unwrap_err().to_string(), "Office name cannot be empty");
+    }
+
+    #[test]
+    fn test_every_broken_field_is_reported() {
+        let office = Office { id: None, name: "x".repeat(101), max_occupancy: 0, ..Default::default() };
+        let errors = office.validate().unwrap_err();
+        assert_eq!(errors.codes(), vec!["name.too_long", "max_occupancy.not_positive"]);
+    }
+
+    #[test]
+    fn test_location_is_validated() {
+        let office = Office {
+            country: Some("dk".to_string()),
+            timezone: Some("Europe/Aalborg".to_string()),
+            latitude: Some(91.0),
+            postal_code: Some("9".repeat(21)),
+            ..create_valid_office()
+        };
+        let errors = office.validate().unwrap_err();
+        assert_eq!(
+            errors.codes(),
+            vec!["postal_code.too_long", "country.invalid", "timezone.unknown", "longitude.missing", "latitude.out_of_range"]
+        );
+
+        let office = Office {
+            country: Some("DK".to_string()),
+            timezone: Some("Europe/Copenhagen".to_string()),
+            latitude: Some(57.048),
+            longitude: Some(9.9187),
+            ..create_valid_office()
+        };
+        assert!(office.validate().is_ok());
+    }
+
+    #[test]
+    fn test_from_create_request_normalizes_location() {
+        let req = CreateOfficeRequest {
+            name: " Aalborg ".to_string(),
+            max_occupancy: 3,
+            street: Some(" Boulevarden 1 ".to_string()),
+            postal_code: Some("".to_string()),
+            city: None,
+            country: Some("dk".to_string()),
+            timezone: None,
+            latitude: None,
+            longitude: None,
+        };
+        let office = Office::from_create_request(req);
+        assert_eq!(office.street.as_deref(), Some("Boulevarden 1"));
+        assert_eq!(office.postal_code, None);
+        assert_eq!(office.country.as_deref(), Some("DK"));
+    }
+
+    #[test]
+    fn test_haversine_distance() {
+        // Aalborg to Aarhus is roughly 100 km as the crow flies
+        let distance = haversine_km(57.048, 9.9187, 56.1629, 10.2039);
+        assert!((distance - 100.0).abs() < 1.0, "{}", distance);
+        assert_eq!(haversine_km(55.0, 12.0, 55.0, 12.0), 0.0);
+        assert_eq!(create_valid_office().distance_km(55.0, 12.0), None);
     }
 }
\ No newline at end of file
@@ -0,0 +1,16 @@
+use serde::{Serialize, Deserialize};
+
+/// Tenant holding all pre-tenant data
+pub const DEFAULT_TENANT_ID: i32 = 1;
+
+/// Tenant entity
+/// Represents a company sharing the deployment, every office and employee belongs to exactly one tenant.
+///
+/// database schema:
+/// id SERIAL PRIMARY KEY,
+/// name VARCHAR(100) NOT NULL UNIQUE
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Tenant {
+    pub id: Option<i32>, // optional as it will be set by the database
+    pub name: String, // name of the company, unique
+}
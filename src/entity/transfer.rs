@@ -0,0 +1,172 @@
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use utoipa::ToSchema;
+
+use crate::dto::transfer_dto::TransferResponse;
+use crate::entity::employee::Employee;
+use crate::utils::ValidationErrors;
+
+/// Transfer entity
+/// A planned move of an employee to another office, applied by the transfer job on the effective date.
+/// The employee keeps the seat in the current office until the day before and takes the seat in the
+/// destination from the effective date.
+///
+/// database schema:
+/// id SERIAL PRIMARY KEY,
+/// tenant_id INT NOT NULL REFERENCES tenants(id),
+/// employee_id INT NOT NULL,
+/// from_office_id INT NOT NULL,
+/// to_office_id INT NOT NULL CHECK (from_office_id <> to_office_id),
+/// effective_date DATE NOT NULL,
+/// status TEXT NOT NULL CHECK (status IN ('pending', 'applied', 'cancelled', 'failed')),
+/// requested_by VARCHAR(100) NOT NULL,
+/// created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+/// FOREIGN KEY (tenant_id, employee_id) REFERENCES employees(tenant_id, id) ON DELETE CASCADE,
+/// FOREIGN KEY (tenant_id, from_office_id) REFERENCES offices(tenant_id, id) ON DELETE CASCADE,
+/// FOREIGN KEY (tenant_id, to_office_id) REFERENCES offices(tenant_id, id) ON DELETE CASCADE,
+/// UNIQUE (employee_id) WHERE status = 'pending'
+///
+/// tenant_id is not part of the entity, repositories take it per call
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Transfer {
+    pub id: Option<i32>, // optional as it will be set by the database
+    pub employee_id: i32,
+    pub from_office_id: i32, // office of the employee when the transfer was planned
+    pub to_office_id: i32,
+    pub effective_date: NaiveDate, // first day in the destination
+    pub status: TransferStatus,
+    pub requested_by: String, // subject that planned the transfer
+    pub created_at: DateTime<Utc>,
+}
+
+/// Where a transfer is: pending until the job applies it, or cancelled before
+/// A pending transfer the job cannot apply, e.g. because the destination filled up, becomes failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum TransferStatus {
+    Pending,
+    Applied,
+    Cancelled,
+    Failed,
+}
+
+impl TransferStatus {
+    // Status as stored and serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransferStatus::Pending => "pending",
+            TransferStatus::Applied => "applied",
+            TransferStatus::Cancelled => "cancelled",
+            TransferStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::fmt::Display for TransferStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Transfer {
+    // Plans a move of the employee out of its current office
+    pub fn new(employee: &Employee, to_office_id: i32, effective_date: NaiveDate, requested_by: &str) -> Self {
+        Transfer {
+            id: None,
+            employee_id: employee.id.unwrap_or_default(),
+            from_office_id: employee.office_id,
+            to_office_id,
+            effective_date,
+            status: TransferStatus::Pending,
+            requested_by: requested_by.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    // Converts the Transfer entity into a TransferResponse DTO
+    pub fn to_response(&self) -> TransferResponse {
+        TransferResponse {
+            id: self.id,
+            employee_id: self.employee_id,
+            from_office_id: self.from_office_id,
+            to_office_id: self.to_office_id,
+            effective_date: self.effective_date,
+            status: self.status,
+            requested_by: self.requested_by.clone(),
+            created_at: self.created_at,
+        }
+    }
+
+    // Checks the transfer against the employee it moves: to another office, not in the past
+    // and not after the employee's last day
+    pub fn check(&self, employee: &Employee, today: NaiveDate) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if self.to_office_id == employee.office_id {
+            errors.add("to_office_id", "same_office", &[]);
+        }
+        if self.effective_date < today {
+            errors.add("effective_date", "past", &[]);
+        }
+        if let Some(termination_date) = employee.termination_date
+            && self.effective_date > termination_date
+        {
+            errors.add("effective_date", "after_termination", &[("termination_date", &termination_date)]);
+        }
+        errors.into_result()
+    }
+
+    // Seat period of the employee in the destination office
+    pub fn seat_in_destination(&self, employee: &Employee) -> (NaiveDate, Option<NaiveDate>) {
+        (self.effective_date.max(employee.hire_date), employee.termination_date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::employee::EmploymentStatus;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).expect("Invalid date")
+    }
+
+    fn employee(termination_date: Option<NaiveDate>) -> Employee {
+        Employee {
+            id: Some(7),
+            first_name: "Ida".to_string(),
+            last_name: "Holm".to_string(),
+            birth_date: date(1980, 1, 1),
+            office_id: 1,
+            department_id: None,
+            manager_id: None,
+            hire_date: date(2026, 3, 1),
+            termination_date,
+            status: EmploymentStatus::Active,
+        }
+    }
+
+    #[test]
+    fn test_new_transfer_is_pending_from_current_office() {
+        let transfer = Transfer::new(&employee(None), 2, date(2026, 6, 1), "hr");
+        assert_eq!((transfer.employee_id, transfer.from_office_id, transfer.status), (7, 1, TransferStatus::Pending));
+        assert!(transfer.check(&employee(None), date(2026, 5, 1)).is_ok());
+    }
+
+    #[test]
+    fn test_check_lists_every_broken_rule() {
+        let leaving = employee(Some(date(2026, 4, 30)));
+        let transfer = Transfer::new(&leaving, 1, date(2026, 5, 1), "hr");
+        let errors = transfer.check(&leaving, date(2026, 5, 2)).unwrap_err();
+        let codes = errors.codes();
+        assert_eq!(codes, vec!["to_office_id.same_office", "effective_date.past", "effective_date.after_termination"]);
+    }
+
+    #[test]
+    fn test_seat_in_destination_starts_at_hire_date_at_the_earliest() {
+        let transfer = Transfer::new(&employee(None), 2, date(2026, 2, 1), "hr");
+        assert_eq!(transfer.seat_in_destination(&employee(None)), (date(2026, 3, 1), None));
+    }
+}
@@ -0,0 +1,362 @@
+use chrono::{Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+use crate::utils::ValidationErrors;
+use crate::utils::names::normalize_name;
+
+/// Longest name the VARCHAR(100) columns can store
+pub const MAX_NAME_LENGTH: usize = 100;
+
+// Employee validation rules
+// Ages are relative to the day of validation, so the allowed birth dates move with time.
+// Loaded from configuration (see config::validation_settings), with optional overrides per tenant and per office.
+//
+// Example rules file:
+// { "employee": { "min_age": 18, "max_age": 100, "surname_policy": "compound", "duplicates": "warn" },
+//   "tenants": { "2": { "min_age": 16, "duplicates": "block" } },
+//   "offices": { "7": { "last_name": { "min_length": 2, "max_length": 50, "allowed": ["letter", "hyphen"] } } } }
+
+/// Character classes a name may consist of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CharClass {
+    Letter, // alphabetic in any script, including accented letters
+    Digit,
+    Space,
+    Hyphen,
+    Apostrophe,
+    Period,
+}
+
+impl CharClass {
+    // True if the character belongs to the class
+    pub fn matches(&self, c: char) -> bool {
+        match self {
+            CharClass::Letter => c.is_alphabetic(),
+            CharClass::Digit => c.is_numeric(),
+            CharClass::Space => c == ' ',
+            CharClass::Hyphen => c == '-',
+            CharClass::Apostrophe => c == '\'' || c == '’',
+            CharClass::Period => c == '.',
+        }
+    }
+
+    // Class name as used in configuration and messages
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CharClass::Letter => "letter",
+            CharClass::Digit => "digit",
+            CharClass::Space => "space",
+            CharClass::Hyphen => "hyphen",
+            CharClass::Apostrophe => "apostrophe",
+            CharClass::Period => "period",
+        }
+    }
+}
+
+/// Length and character rules of a name, lengths count characters
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct NameRule {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub allowed: Vec<CharClass>,
+}
+
+impl NameRule {
+    // Checks a trimmed name, `field` is its path and the prefix of the error codes
+    fn check(&self, field: &str, name: &str, errors: &mut ValidationErrors) {
+        let length = name.chars().count();
+        if length == 0 {
+            errors.add(field, "empty", &[]);
+            return;
+        }
+        if length < self.min_length {
+            errors.add(field, "too_short", &[("min", &self.min_length)]);
+        }
+        if length > self.max_length {
+            errors.add(field, "too_long", &[("max", &self.max_length)]);
+        }
+        let mut disallowed = name.chars().filter(|c| !self.allowed.iter().any(|class| class.matches(*c)));
+        if disallowed.clone().any(char::is_whitespace) {
+            errors.add(field, "whitespace", &[]);
+        }
+        if let Some(c) = disallowed.find(|c| !c.is_whitespace()) {
+            let allowed: Vec<&str> = self.allowed.iter().map(|class| class.as_str()).collect();
+            errors.add(field, "invalid_character", &[("character", &c), ("allowed", &allowed.join(", "))]);
+        }
+    }
+}
+
+/// Whether a last name may consist of several words
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SurnamePolicy {
+    SingleWord, // one word, spaces are rejected whatever the allowed classes say
+    #[default]
+    Compound, // words separated by single spaces, e.g. "van der Berg" or "García Márquez"
+}
+
+/// What creating an employee does when one with the same normalized name and birth date exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    Off,
+    #[default]
+    Warn, // created, the response names the possible duplicates
+    Block, // rejected with 409 Conflict
+}
+
+/// Effective rules for one employee
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct EmployeeRules {
+    pub min_age: u32, // full years on the day of validation
+    pub max_age: u32,
+    pub first_name: NameRule,
+    pub last_name: NameRule,
+    pub surname_policy: SurnamePolicy,
+    pub duplicates: DuplicatePolicy,
+}
+
+impl Default for EmployeeRules {
+    fn default() -> Self {
+        use CharClass::*;
+        Self {
+            min_age: 18,
+            max_age: 100,
+            first_name: NameRule { min_length: 1, max_length: MAX_NAME_LENGTH, allowed: vec![Letter, Digit, Space, Hyphen, Apostrophe, Period] },
+            last_name: NameRule { min_length: 1, max_length: MAX_NAME_LENGTH, allowed: vec![Letter, Digit, Hyphen, Apostrophe] },
+            surname_policy: SurnamePolicy::Compound,
+            duplicates: DuplicatePolicy::Warn,
+        }
+    }
+}
+
+impl EmployeeRules {
+    /// Earliest and latest allowed birth date on `today`, both inclusive
+    /// The latest is before today even with min_age 0, the database only stores birth dates in the past
+    pub fn birth_date_range(&self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let years_before = |years: u32| today.checked_sub_months(Months::new(years.saturating_mul(12))).unwrap_or(NaiveDate::MIN);
+        let earliest = years_before(self.max_age.saturating_add(1)).succ_opt().unwrap_or(NaiveDate::MIN);
+        let latest = years_before(self.min_age).min(today.pred_opt().unwrap_or(NaiveDate::MIN));
+        (earliest, latest)
+    }
+
+    /// Last name rule with the surname policy applied: compound surnames may contain spaces, single-word ones never do
+    pub fn effective_last_name(&self) -> NameRule {
+        let mut rule = self.last_name.clone();
+        rule.allowed.retain(|class| *class != CharClass::Space);
+        if self.surname_policy == SurnamePolicy::Compound {
+            rule.allowed.push(CharClass::Space);
+        }
+        rule
+    }
+
+    /// Checks names and birth date, every broken rule is reported
+    /// Names are checked in their normalized form (NFC, single spaces), see utils::names
+    pub fn check(&self, first_name: &str, last_name: &str, birth_date: NaiveDate, today: NaiveDate) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        self.first_name.check("first_name", &normalize_name(first_name), &mut errors);
+        self.effective_last_name().check("last_name", &normalize_name(last_name), &mut errors);
+        let (earliest, latest) = self.birth_date_range(today);
+        if birth_date >= today {
+            errors.add("birth_date", "not_in_past", &[]);
+        } else if birth_date > latest {
+            errors.add("birth_date", "too_young", &[("min_age", &self.min_age)]);
+        } else if birth_date < earliest {
+            errors.add("birth_date", "too_old", &[("max_age", &self.max_age)]);
+        }
+        errors.into_result()
+    }
+
+    // Errors for rules that no name or birth date could satisfy, or that the columns cannot store
+    fn check_consistency(&self, source: &str) -> Result<(), String> {
+        if self.min_age > self.max_age {
+            return Err(format!("{}: min_age {} is above max_age {}", source, self.min_age, self.max_age));
+        }
+        for (field, rule) in [("first_name", &self.first_name), ("last_name", &self.last_name)] {
+            if rule.max_length > MAX_NAME_LENGTH {
+                return Err(format!("{}: {}.max_length cannot exceed {}", source, field, MAX_NAME_LENGTH));
+            }
+            if rule.min_length > rule.max_length {
+                return Err(format!("{}: {}.min_length is above max_length", source, field));
+            }
+            if rule.allowed.is_empty() {
+                return Err(format!("{}: {}.allowed needs at least one character class", source, field));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Partial rules of a tenant or office, unset fields keep the value they override
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleOverride {
+    pub min_age: Option<u32>,
+    pub max_age: Option<u32>,
+    pub first_name: Option<NameRule>,
+    pub last_name: Option<NameRule>,
+    pub surname_policy: Option<SurnamePolicy>,
+    pub duplicates: Option<DuplicatePolicy>,
+}
+
+impl RuleOverride {
+    // Applies the set fields on top of `rules`
+    fn apply(&self, rules: &mut EmployeeRules) {
+        if let Some(min_age) = self.min_age {
+            rules.min_age = min_age;
+        }
+        if let Some(max_age) = self.max_age {
+            rules.max_age = max_age;
+        }
+        if let Some(first_name) = &self.first_name {
+            rules.first_name = first_name.clone();
+        }
+        if let Some(last_name) = &self.last_name {
+            rules.last_name = last_name.clone();
+        }
+        if let Some(surname_policy) = self.surname_policy {
+            rules.surname_policy = surname_policy;
+        }
+        if let Some(duplicates) = self.duplicates {
+            rules.duplicates = duplicates;
+        }
+    }
+}
+
+/// All configured rules: defaults, tenant overrides, office overrides (by office ID)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidationRules {
+    pub employee: EmployeeRules,
+    pub tenants: HashMap<i32, RuleOverride>,
+    pub offices: HashMap<i32, RuleOverride>,
+}
+
+impl ValidationRules {
+    /// Effective rules for an employee of the tenant, in the office if given
+    /// Office overrides win over tenant overrides, which win over the defaults
+    pub fn for_employee(&self, tenant_id: i32, office_id: Option<i32>) -> EmployeeRules {
+        let mut rules = self.employee.clone();
+        if let Some(tenant) = self.tenants.get(&tenant_id) {
+            tenant.apply(&mut rules);
+        }
+        if let Some(office) = office_id.and_then(|id| self.offices.get(&id)) {
+            office.apply(&mut rules);
+        }
+        rules
+    }
+
+    /// Errors for defaults or overrides that cannot be satisfied or stored, office overrides are checked on top of the defaults
+    pub fn check_consistency(&self) -> Result<(), String> {
+        self.employee.check_consistency("employee")?;
+        for tenant_id in self.tenants.keys() {
+            self.for_employee(*tenant_id, None).check_consistency(&format!("tenants.{}", tenant_id))?;
+        }
+        for (office_id, office) in &self.offices {
+            let mut rules = self.employee.clone();
+            office.apply(&mut rules);
+            rules.check_consistency(&format!("offices.{}", office_id))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    // Message of the only broken rule
+    fn single(result: Result<(), ValidationErrors>) -> String {
+        let errors = result.unwrap_err();
+        assert_eq!(errors.errors.len(), 1, "{}", errors);
+        errors.to_string()
+    }
+
+    #[test]
+    fn test_ages_are_relative_to_today() {
+        let rules = EmployeeRules::default();
+        let today = date(2026, 10, 19);
+        assert_eq!(rules.birth_date_range(today), (date(1925, 10, 20), date(2008, 10, 19)));
+        assert!(rules.check("Ida", "Berg", date(2008, 10, 19), today).is_ok());
+        assert_eq!(single(rules.check("Ida", "Berg", date(2008, 10, 20), today)), "Employee must be at least 18 years old");
+        assert_eq!(single(rules.check("Ida", "Berg", date(1925, 10, 19), today)), "Employee cannot be older than 100 years");
+        assert_eq!(single(rules.check("Ida", "Berg", today, today)), "Birth date must be in the past");
+    }
+
+    #[test]
+    fn test_character_classes() {
+        let rules = EmployeeRules::default();
+        let today = date(2026, 10, 19);
+        assert!(rules.check("Anne-Marie J.", "O'Brien-Ærø", date(1990, 1, 1), today).is_ok());
+        assert_eq!(
+            single(rules.check("<b>", "Doe", date(1990, 1, 1), today)),
+            "First name cannot contain '<', allowed are: letter, digit, space, hyphen, apostrophe, period"
+        );
+        assert!(rules.check("Lionel", "van Persie", date(1990, 1, 1), today).is_ok());
+        assert_eq!(
+            single(rules.check(&"a".repeat(101), "Doe", date(1990, 1, 1), today)),
+            "First name cannot be longer than 100 characters"
+        );
+    }
+
+    #[test]
+    fn test_surname_policy() {
+        let today = date(2026, 10, 19);
+        let compound = EmployeeRules::default();
+        assert!(compound.check("Ida", "van der  Berg", date(1990, 1, 1), today).is_ok());
+        assert!(compound.check("Ida", "García Márquez", date(1990, 1, 1), today).is_ok());
+
+        let single_word = EmployeeRules { surname_policy: SurnamePolicy::SingleWord, ..Default::default() };
+        assert_eq!(single(single_word.check("Ida", "van der Berg", date(1990, 1, 1), today)), "Last name cannot contain whitespace");
+        assert!(single_word.check("Ida", "Berg-Hansen", date(1990, 1, 1), today).is_ok());
+        assert!(!single_word.effective_last_name().allowed.contains(&CharClass::Space));
+    }
+
+    #[test]
+    fn test_all_broken_rules_are_reported() {
+        let rules = EmployeeRules { surname_policy: SurnamePolicy::SingleWord, ..Default::default() };
+        let today = date(2026, 10, 19);
+        let errors = rules.check(" ", "van <Persie>", date(2020, 1, 1), today).unwrap_err();
+        assert_eq!(
+            errors.codes(),
+            vec!["first_name.empty", "last_name.whitespace", "last_name.invalid_character", "birth_date.too_young"]
+        );
+        assert_eq!(errors.errors[1].field, "last_name");
+    }
+
+    #[test]
+    fn test_office_overrides_win_over_tenant_overrides() {
+        let rules: ValidationRules = serde_json::from_str(
+            r#"{ "employee": { "min_age": 18 },
+                 "tenants": { "2": { "min_age": 16, "max_age": 70, "duplicates": "block" } },
+                 "offices": { "7": { "min_age": 21, "surname_policy": "single_word" } } }"#,
+        )
+        .unwrap();
+        assert_eq!(rules.employee.max_age, 100);
+        assert_eq!(rules.for_employee(1, Some(7)).min_age, 21);
+        assert_eq!(rules.for_employee(2, None).min_age, 16);
+        let office = rules.for_employee(2, Some(7));
+        assert_eq!((office.min_age, office.max_age), (21, 70));
+        assert_eq!((office.surname_policy, office.duplicates), (SurnamePolicy::SingleWord, DuplicatePolicy::Block));
+        assert_eq!(rules.for_employee(1, None).duplicates, DuplicatePolicy::Warn);
+        assert!(rules.check_consistency().is_ok());
+    }
+
+    #[test]
+    fn test_inconsistent_rules_are_rejected() {
+        let mut rules = ValidationRules::default();
+        rules.offices.insert(3, RuleOverride { min_age: Some(120), ..Default::default() });
+        assert_eq!(rules.check_consistency().unwrap_err(), "offices.3: min_age 120 is above max_age 100");
+
+        let mut rules = ValidationRules::default();
+        rules.employee.last_name.max_length = 255;
+        assert_eq!(rules.check_consistency().unwrap_err(), "employee: last_name.max_length cannot exceed 100");
+    }
+}
@@ -0,0 +1,257 @@
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use utoipa::ToSchema;
+
+use crate::dto::employee_dto::CreateEmployeeRequest;
+use crate::dto::waitlist_dto::{WaitlistEntryResponse, WaitlistEventResponse, WaitlistRequest};
+use crate::entity::employee::Employee;
+
+/// Waitlist entry entity
+/// A hire into a full office, waiting in order of arrival until a seat frees up.
+/// The waitlist job then places the employee, or with confirm set offers the seat and waits for it to be accepted or declined.
+///
+/// database schema:
+/// id SERIAL PRIMARY KEY,
+/// tenant_id INT NOT NULL REFERENCES tenants(id),
+/// office_id INT NOT NULL,
+/// first_name VARCHAR(100) NOT NULL,
+/// last_name VARCHAR(100) NOT NULL,
+/// birth_date DATE NOT NULL,
+/// department_id INT,
+/// manager_id INT,
+/// hire_date DATE,
+/// confirm BOOLEAN NOT NULL DEFAULT false,
+/// status TEXT NOT NULL CHECK (status IN ('waiting', 'offered', 'placed', 'declined', 'cancelled', 'failed')),
+/// employee_id INT,
+/// requested_by VARCHAR(100) NOT NULL,
+/// created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+/// UNIQUE (tenant_id, id),
+/// FOREIGN KEY (tenant_id, office_id) REFERENCES offices(tenant_id, id) ON DELETE CASCADE
+///
+/// tenant_id is not part of the entity, repositories take it per call
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WaitlistEntry {
+    pub id: Option<i32>, // optional as it will be set by the database
+    pub office_id: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub birth_date: NaiveDate,
+    pub department_id: Option<i32>,
+    pub manager_id: Option<i32>,
+    pub hire_date: Option<NaiveDate>, // the day of placement when missing
+    pub confirm: bool, // offer the seat instead of placing right away
+    pub status: WaitlistStatus,
+    pub employee_id: Option<i32>, // the employee once placed
+    pub requested_by: String, // subject that put the hire on the waitlist
+    pub created_at: DateTime<Utc>,
+}
+
+/// Where a waitlist entry is: waiting for a seat, offered one, or done
+/// placed: the employee was created, failed: the hire no longer passed validation when a seat came up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum WaitlistStatus {
+    Waiting,
+    Offered,
+    Placed,
+    Declined,
+    Cancelled,
+    Failed,
+}
+
+/// Something that happened to a waitlist entry
+/// offer_lapsed: the offered seat was taken before the offer was accepted, the entry waits again
+///
+/// database schema:
+/// id SERIAL PRIMARY KEY,
+/// tenant_id INT NOT NULL,
+/// entry_id INT NOT NULL,
+/// event TEXT NOT NULL CHECK (event IN ('enqueued', 'offered', 'placed', 'offer_lapsed', 'declined', 'cancelled', 'failed')),
+/// details TEXT,
+/// created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+/// FOREIGN KEY (tenant_id, entry_id) REFERENCES waitlist_entries(tenant_id, id) ON DELETE CASCADE
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WaitlistEvent {
+    pub id: Option<i32>, // optional as it will be set by the database
+    pub entry_id: i32,
+    pub event: WaitlistEventKind,
+    pub details: Option<String>, // who acted, or why the entry failed
+    pub created_at: DateTime<Utc>,
+}
+
+/// Kind of a waitlist event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum WaitlistEventKind {
+    Enqueued,
+    Offered,
+    Placed,
+    OfferLapsed,
+    Declined,
+    Cancelled,
+    Failed,
+}
+
+impl WaitlistStatus {
+    // Status as stored and serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WaitlistStatus::Waiting => "waiting",
+            WaitlistStatus::Offered => "offered",
+            WaitlistStatus::Placed => "placed",
+            WaitlistStatus::Declined => "declined",
+            WaitlistStatus::Cancelled => "cancelled",
+            WaitlistStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::fmt::Display for WaitlistStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl WaitlistEventKind {
+    // Event as stored and serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WaitlistEventKind::Enqueued => "enqueued",
+            WaitlistEventKind::Offered => "offered",
+            WaitlistEventKind::Placed => "placed",
+            WaitlistEventKind::OfferLapsed => "offer_lapsed",
+            WaitlistEventKind::Declined => "declined",
+            WaitlistEventKind::Cancelled => "cancelled",
+            WaitlistEventKind::Failed => "failed",
+        }
+    }
+}
+
+impl WaitlistEntry {
+    // Converts a WaitlistRequest DTO for the office into a waiting entry
+    pub fn from_request(office_id: i32, req: WaitlistRequest, requested_by: &str) -> Self {
+        WaitlistEntry {
+            id: None,
+            office_id,
+            first_name: req.first_name,
+            last_name: req.last_name,
+            birth_date: req.birth_date,
+            department_id: req.department_id,
+            manager_id: req.manager_id,
+            hire_date: req.hire_date,
+            confirm: req.confirm,
+            status: WaitlistStatus::Waiting,
+            employee_id: None,
+            requested_by: requested_by.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    // The employee the entry places, normalized like a direct hire, hired on the day of placement without a hire date
+    pub fn employee(&self) -> Employee {
+        Employee::from_create_request(CreateEmployeeRequest {
+            first_name: self.first_name.clone(),
+            last_name: self.last_name.clone(),
+            birth_date: self.birth_date,
+            office_id: self.office_id,
+            department_id: self.department_id,
+            manager_id: self.manager_id,
+            hire_date: self.hire_date,
+        })
+    }
+
+    // Converts the entry into a WaitlistEntryResponse DTO with its place in the queue
+    pub fn to_response(&self, position: Option<usize>) -> WaitlistEntryResponse {
+        WaitlistEntryResponse {
+            id: self.id,
+            office_id: self.office_id,
+            first_name: self.first_name.clone(),
+            last_name: self.last_name.clone(),
+            birth_date: self.birth_date,
+            department_id: self.department_id,
+            manager_id: self.manager_id,
+            hire_date: self.hire_date,
+            confirm: self.confirm,
+            status: self.status,
+            position,
+            employee_id: self.employee_id,
+            requested_by: self.requested_by.clone(),
+            created_at: self.created_at,
+        }
+    }
+}
+
+impl WaitlistEvent {
+    // An event of the entry, happening now
+    pub fn new(entry_id: i32, event: WaitlistEventKind, details: Option<String>) -> Self {
+        WaitlistEvent { id: None, entry_id, event, details, created_at: Utc::now() }
+    }
+
+    // Converts the WaitlistEvent entity into a WaitlistEventResponse DTO
+    pub fn to_response(&self) -> WaitlistEventResponse {
+        WaitlistEventResponse {
+            id: self.id,
+            entry_id: self.entry_id,
+            event: self.event,
+            details: self.details.clone(),
+            created_at: self.created_at,
+        }
+    }
+}
+
+// Places in the queue of an office's entries, given in order of arrival: 1 for the next waiting entry,
+// None for entries that no longer wait
+pub fn queue_positions(entries: &[WaitlistEntry]) -> Vec<Option<usize>> {
+    let mut position = 0;
+    entries
+        .iter()
+        .map(|entry| {
+            if entry.status != WaitlistStatus::Waiting {
+                return None;
+            }
+            position += 1;
+            Some(position)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(status: WaitlistStatus) -> WaitlistEntry {
+        WaitlistEntry {
+            status,
+            ..WaitlistEntry::from_request(
+                1,
+                WaitlistRequest {
+                    first_name: " ida ".to_string(),
+                    last_name: "holm".to_string(),
+                    birth_date: NaiveDate::from_ymd_opt(1990, 1, 1).expect("Invalid date"),
+                    department_id: None,
+                    manager_id: None,
+                    hire_date: None,
+                    confirm: false,
+                },
+                "hr",
+            )
+        }
+    }
+
+    #[test]
+    fn test_positions_count_waiting_entries_only() {
+        let entries = [entry(WaitlistStatus::Placed), entry(WaitlistStatus::Waiting), entry(WaitlistStatus::Offered), entry(WaitlistStatus::Waiting)];
+        assert_eq!(queue_positions(&entries), vec![None, Some(1), None, Some(2)]);
+    }
+
+    #[test]
+    fn test_employee_is_placed_in_the_entry_office() {
+        let employee = entry(WaitlistStatus::Waiting).employee();
+        assert_eq!(employee.office_id, 1);
+        assert_eq!(employee.hire_date, Utc::now().date_naive());
+    }
+}
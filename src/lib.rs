@@ -4,4 +4,5 @@ pub mod service;
 pub mod config;
 pub mod dto;
 pub mod utils;
-pub mod controller;
\ No newline at end of file
+pub mod controller;
+pub mod auth;
\ No newline at end of file
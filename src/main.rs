@@ -2,26 +2,71 @@
 use dotenv::dotenv;
 use std::sync::Arc;
 use tokio::net::TcpListener;
-use utoipa::OpenApi;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
 
-mod config;
-mod entity;
-mod repository;
-mod service;
-mod controller;
-mod dto;
-mod utils;
-
-use config::db_settings::Settings;
+use corp_data_api::{auth, config, controller, dto, entity, repository, service, utils};
+use config::db_settings::{Backend, Settings};
+use config::auth_settings::AuthSettings;
+use config::idempotency_settings::IdempotencySettings;
+use config::validation_settings::ValidationSettings;
+use config::transfer_settings::TransferSettings;
+use config::change_request_settings::ChangeRequestSettings;
+use config::waitlist_settings::WaitlistSettings;
 use repository::office_repository::OfficeRepository;
 use repository::employee_repository::EmployeeRepository;
+use repository::office_manager_repository::OfficeManagerRepository;
+use repository::idempotency_repository::IdempotencyRepository;
+use repository::health_repository::HealthRepository;
+use repository::audit_repository::AuditRepository;
+use repository::department_repository::DepartmentRepository;
+use repository::transfer_repository::TransferRepository;
+use repository::change_request_repository::ChangeRequestRepository;
+use repository::waitlist_repository::WaitlistRepository;
+use repository::location_repository::LocationRepository;
+use repository::unit_of_work::PgUnitOfWork;
 use service::office_service::OfficeService;
 use service::employee_service::EmployeeService;
+use service::department_service::DepartmentService;
+use service::transfer_service::{spawn_transfer_job, TransferService};
+use service::change_request_service::ChangeRequestService;
+use service::waitlist_service::{spawn_waitlist_job, WaitlistService};
+use service::location_service::LocationService;
+use service::seat_service::SeatService;
+use service::idempotency_service::IdempotencyService;
+use service::health_service::HealthService;
 use controller::office_controller::create_router as create_office_router;
 use controller::employee_controller::{create_router as create_employee_router};
-use dto::employee_dto::{EmployeeResponse, CreateEmployeeRequest};
+use controller::department_controller::create_router as create_department_router;
+use controller::transfer_controller::create_router as create_transfer_router;
+use controller::change_request_controller::create_router as create_change_request_router;
+use controller::waitlist_controller::create_router as create_waitlist_router;
+use controller::location_controller::create_router as create_location_router;
+use controller::health_controller::create_router as create_health_router;
+use dto::employee_dto::{EmployeeResponse, CreateEmployeeRequest, DuplicateClusterResponse, TerminateEmployeeRequest};
 use dto::office_dto::{OfficeResponse, CreateOfficeRequest};
+use dto::department_dto::{DepartmentResponse, CreateDepartmentRequest};
+use dto::transfer_dto::{RescheduleTransferRequest, TransferResponse};
+use dto::change_request_dto::{ApprovalResponse, ChangeRequestResponse, CommentRequest, CommentResponse, DecisionRequest, SubmitChangeRequest};
+use dto::waitlist_dto::{WaitlistEntryResponse, WaitlistEventResponse, WaitlistRequest};
+use dto::location_dto::{CreateLocationRequest, LocationResponse, UpdateLocationRequest};
+use dto::problem_dto::ProblemDetails;
+use utils::FieldError;
+use dto::health_dto::HealthResponse;
+use dto::validation_dto::ValidationRulesResponse;
+use dto::merge_dto::{MergeEmployeeRequest, MergeEmployeeResponse, MergeFields, MergeSide};
+use entity::validation_rules::{CharClass, DuplicatePolicy, NameRule, SurnamePolicy};
+use entity::employee::EmploymentStatus;
+use entity::transfer::TransferStatus;
+use entity::change_request::{ApprovalStep, ChangeRequestStatus, Decision, ProposedChange};
+use entity::waitlist::{WaitlistEventKind, WaitlistStatus};
+use entity::location::LocationKind;
+use dto::import_dto::{ImportEmployeeRow, ImportMode, ImportReport, ImportRowResult, ImportRowStatus};
+use dto::export_dto::{EmployeeExportRow, OfficeExportRow};
+use dto::diagram_dto::{CompanyDiagram, EmployeeDiagramNode, OfficeDiagramNode};
+use auth::principal::authenticate;
+use controller::request_scope::track_writes;
 
 
 /// OA specs for api
@@ -34,16 +79,90 @@ use dto::office_dto::{OfficeResponse, CreateOfficeRequest};
         controller::employee_controller::list_employees_by_office_id,
         controller::employee_controller::update_employee,
         controller::employee_controller::delete_employee,
+        controller::employee_controller::import_employees,
+        controller::employee_controller::export_employees,
+        controller::employee_controller::get_validation_rules,
+        controller::employee_controller::list_duplicates,
+        controller::employee_controller::merge_employees,
+        controller::employee_controller::list_direct_reports,
+        controller::employee_controller::get_reporting_chain,
+        controller::employee_controller::list_subtree,
+        controller::employee_controller::activate_employee,
+        controller::employee_controller::put_employee_on_leave,
+        controller::employee_controller::terminate_employee,
+        controller::department_controller::create_department,
+        controller::department_controller::get_department_by_id,
+        controller::department_controller::list_all_departments,
+        controller::department_controller::update_department,
+        controller::department_controller::delete_department,
+        controller::transfer_controller::list_office_transfers,
+        controller::transfer_controller::get_transfer_by_id,
+        controller::transfer_controller::cancel_transfer,
+        controller::transfer_controller::reschedule_transfer,
+        controller::change_request_controller::submit_change_request,
+        controller::change_request_controller::list_change_requests,
+        controller::change_request_controller::get_change_request_by_id,
+        controller::change_request_controller::approve_change_request,
+        controller::change_request_controller::reject_change_request,
+        controller::change_request_controller::withdraw_change_request,
+        controller::change_request_controller::add_comment,
+        controller::waitlist_controller::enqueue,
+        controller::waitlist_controller::list_office_waitlist,
+        controller::waitlist_controller::get_entry_by_id,
+        controller::waitlist_controller::list_events,
+        controller::waitlist_controller::accept_offer,
+        controller::waitlist_controller::decline_offer,
+        controller::waitlist_controller::cancel_entry,
+        controller::location_controller::create_location,
+        controller::location_controller::list_locations,
+        controller::location_controller::get_location_by_id,
+        controller::location_controller::update_location,
+        controller::location_controller::delete_location,
+        controller::location_controller::assign_employee,
+        controller::location_controller::unassign_employee,
         controller::office_controller::create_office,
         controller::office_controller::get_office_by_id,
         controller::office_controller::list_all_offices,
+        controller::office_controller::export_offices,
+        controller::office_controller::get_office_diagram,
         controller::office_controller::update_office,
-        controller::office_controller::delete_office
+        controller::office_controller::delete_office,
+        controller::office_controller::list_office_managers,
+        controller::office_controller::assign_office_manager,
+        controller::office_controller::remove_office_manager,
+        controller::health_controller::live,
+        controller::health_controller::ready
     ),
-    components(schemas(EmployeeResponse, CreateEmployeeRequest, OfficeResponse, CreateOfficeRequest))
+    components(schemas(EmployeeResponse, CreateEmployeeRequest, OfficeResponse, CreateOfficeRequest, ProblemDetails, FieldError,
+        ImportEmployeeRow, ImportMode, ImportReport, ImportRowResult, ImportRowStatus,
+        EmployeeExportRow, OfficeExportRow, HealthResponse, ValidationRulesResponse, NameRule, CharClass,
+        SurnamePolicy, DuplicatePolicy, DuplicateClusterResponse,
+        MergeEmployeeRequest, MergeEmployeeResponse, MergeFields, MergeSide,
+        DepartmentResponse, CreateDepartmentRequest, CompanyDiagram, OfficeDiagramNode, EmployeeDiagramNode,
+        EmploymentStatus, TerminateEmployeeRequest,
+        RescheduleTransferRequest, TransferResponse, TransferStatus,
+        SubmitChangeRequest, DecisionRequest, CommentRequest, ChangeRequestResponse, ApprovalResponse, CommentResponse,
+        ProposedChange, ChangeRequestStatus, Decision, ApprovalStep,
+        WaitlistRequest, WaitlistEntryResponse, WaitlistEventResponse, WaitlistStatus, WaitlistEventKind,
+        CreateLocationRequest, UpdateLocationRequest, LocationResponse, LocationKind)),
+    modifiers(&SecurityAddon)
 )]
 struct ApiDoc;
 
+/// Registers the bearer JWT scheme referenced by the `security` section of every path.
+/// The scopes listed per path are the permissions the route requires.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
+
 /// Initializes and runs the Corp Data API server.
 /// Initializes structured logging, loads environment configuration,
 /// establishes a database connection pool, configures the application
@@ -64,21 +183,129 @@ async fn main() -> anyhow::Result<()> {
         e
     })?;
 
-    // Create a Postgres connection pool
-    let pool = settings.create_pool().await.map_err(|e| {
-        tracing::error!("Database connection failed: {}", e);
+    let auth_settings = AuthSettings::load_from_env().map_err(|e| {
+        tracing::error!("Failed to load auth settings: {}", e);
+        e
+    })?;
+
+    let idempotency_settings = IdempotencySettings::load_from_env().map_err(|e| {
+        tracing::error!("Failed to load idempotency settings: {}", e);
+        e
+    })?;
+
+    let validation_settings = ValidationSettings::load_from_env().map_err(|e| {
+        tracing::error!("Failed to load validation settings: {}", e);
+        e
+    })?;
+
+    let transfer_settings = TransferSettings::load_from_env().map_err(|e| {
+        tracing::error!("Failed to load transfer settings: {}", e);
+        e
+    })?;
+
+    let change_request_settings = ChangeRequestSettings::load_from_env().map_err(|e| {
+        tracing::error!("Failed to load change request settings: {}", e);
+        e
+    })?;
+
+    let waitlist_settings = WaitlistSettings::load_from_env().map_err(|e| {
+        tracing::error!("Failed to load waitlist settings: {}", e);
+        e
+    })?;
+
+    // Create a connection pool for the backend named by the URL scheme,
+    // then initialize repository and service layers on top of it,
+    // change requests are applied through the office, employee and transfer services, waitlists are placed through the employee service
+    let backend = settings.backend().map_err(|e| {
+        tracing::error!("Invalid database configuration: {}", e);
         e
     })?;
+    let (office_service, employee_service, department_service, transfer_service, change_request_service, waitlist_service, location_service, idempotency_service, health_service) = match backend {
+        Backend::Postgres => {
+            let pools = settings.create_pools().await.map_err(|e| {
+                tracing::error!("Database connection failed: {}", e);
+                e
+            })?;
+            let pool = pools.primary().clone();
+            let office_repo = OfficeRepository::new(pools.clone());
+            let employee_repo = EmployeeRepository::new(pools.clone());
+            let manager_repo = OfficeManagerRepository::new(pool.clone());
+            let department_repo = DepartmentRepository::new(pools.clone());
+            let unit_of_work = PgUnitOfWork::new(pool.clone());
+            let seats = SeatService::new(employee_repo.clone(), TransferRepository::new(pool.clone()), office_repo.clone(), department_repo.clone(), WaitlistRepository::new(pool.clone()), LocationRepository::new(pool.clone()))
+                .with_validation_rules(validation_settings.rules.clone());
+            let office_service = Arc::new(OfficeService::new(office_repo.clone(), employee_repo.clone(), manager_repo.clone(), seats.clone(), unit_of_work.clone()));
+            let employee_service = Arc::new(
+                EmployeeService::new(employee_repo.clone(), office_repo.clone(), manager_repo.clone(), AuditRepository::new(pool.clone()), department_repo.clone(), seats.clone(), unit_of_work.clone())
+                    .with_validation_rules(validation_settings.rules),
+            );
+            let transfer_service = Arc::new(TransferService::new(TransferRepository::new(pool.clone()), employee_repo.clone(), office_repo.clone(), manager_repo.clone(), AuditRepository::new(pool.clone()), seats.clone(), unit_of_work.clone()));
+            let location_service = LocationService::new(LocationRepository::new(pool.clone()), employee_repo.clone(), office_repo.clone(), manager_repo.clone(), unit_of_work.clone());
+            (
+                office_service.clone(),
+                employee_service.clone(),
+                DepartmentService::new(department_repo),
+                transfer_service.clone(),
+                ChangeRequestService::new(ChangeRequestRepository::new(pool.clone()), manager_repo, unit_of_work.clone(), employee_service.clone(), office_service, transfer_service, change_request_settings.ttl),
+                Arc::new(WaitlistService::new(WaitlistRepository::new(pool.clone()), office_repo, seats, unit_of_work, employee_service)),
+                location_service,
+                IdempotencyService::new(IdempotencyRepository::new(pool.clone()), idempotency_settings.ttl),
+                HealthService::new(HealthRepository::new(pool)),
+            )
+        }
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite => {
+            use repository::sqlite_repository::{SqliteAuditRepository, SqliteChangeRequestRepository, SqliteDepartmentRepository, SqliteEmployeeRepository, SqliteIdempotencyRepository, SqliteHealthRepository, SqliteLocationRepository, SqliteOfficeManagerRepository, SqliteOfficeRepository, SqliteTransferRepository, SqliteUnitOfWork, SqliteWaitlistRepository};
+            let pool = settings.create_sqlite_pool().await.map_err(|e| {
+                tracing::error!("Database connection failed: {}", e);
+                e
+            })?;
+            let office_repo = SqliteOfficeRepository::new(pool.clone());
+            let employee_repo = SqliteEmployeeRepository::new(pool.clone());
+            let manager_repo = SqliteOfficeManagerRepository::new(pool.clone());
+            let department_repo = SqliteDepartmentRepository::new(pool.clone());
+            let unit_of_work = SqliteUnitOfWork::new(pool.clone());
+            let seats = SeatService::new(employee_repo.clone(), SqliteTransferRepository::new(pool.clone()), office_repo.clone(), department_repo.clone(), SqliteWaitlistRepository::new(pool.clone()), SqliteLocationRepository::new(pool.clone()))
+                .with_validation_rules(validation_settings.rules.clone());
+            let office_service = Arc::new(OfficeService::new(office_repo.clone(), employee_repo.clone(), manager_repo.clone(), seats.clone(), unit_of_work.clone()));
+            let employee_service = Arc::new(
+                EmployeeService::new(employee_repo.clone(), office_repo.clone(), manager_repo.clone(), SqliteAuditRepository::new(pool.clone()), department_repo.clone(), seats.clone(), unit_of_work.clone())
+                    .with_validation_rules(validation_settings.rules),
+            );
+            let transfer_service = Arc::new(TransferService::new(SqliteTransferRepository::new(pool.clone()), employee_repo.clone(), office_repo.clone(), manager_repo.clone(), SqliteAuditRepository::new(pool.clone()), seats.clone(), unit_of_work.clone()));
+            let location_service = LocationService::new(SqliteLocationRepository::new(pool.clone()), employee_repo.clone(), office_repo.clone(), manager_repo.clone(), unit_of_work.clone());
+            (
+                office_service.clone(),
+                employee_service.clone(),
+                DepartmentService::new(department_repo),
+                transfer_service.clone(),
+                ChangeRequestService::new(SqliteChangeRequestRepository::new(pool.clone()), manager_repo, unit_of_work.clone(), employee_service.clone(), office_service, transfer_service, change_request_settings.ttl),
+                Arc::new(WaitlistService::new(SqliteWaitlistRepository::new(pool.clone()), office_repo, seats, unit_of_work, employee_service)),
+                location_service,
+                IdempotencyService::new(SqliteIdempotencyRepository::new(pool.clone()), idempotency_settings.ttl),
+                HealthService::new(SqliteHealthRepository::new(pool)),
+            )
+        }
+    };
+    let idempotency_service = Arc::new(idempotency_service);
 
-    // Initialize repository and service layers
-    let office_repo = OfficeRepository::new(pool.clone());
-    let employee_repo = EmployeeRepository::new(pool.clone());
-    let office_service = Arc::new(OfficeService::new(office_repo.clone()));
-    let employee_service = Arc::new(EmployeeService::new(employee_repo, office_repo));
+    // applies transfers that took effect in the background
+    spawn_transfer_job(transfer_service.clone(), transfer_settings.interval);
+    // hands seats freed outside of a transaction that fills the waitlist, e.g. by a termination date, to waiting hires
+    spawn_waitlist_job(waitlist_service.clone(), waitlist_settings.interval);
 
-    // builds HTTP layer
-    let app = create_office_router(office_service)
-        .merge(create_employee_router(employee_service))
+    // builds HTTP layer, API routes sit behind bearer token authentication
+    let api = create_office_router(office_service, idempotency_service.clone())
+        .merge(create_employee_router(employee_service, idempotency_service))
+        .merge(create_department_router(Arc::new(department_service)))
+        .merge(create_transfer_router(transfer_service))
+        .merge(create_change_request_router(Arc::new(change_request_service)))
+        .merge(create_waitlist_router(waitlist_service))
+        .merge(create_location_router(Arc::new(location_service)))
+        .layer(axum::middleware::from_fn_with_state(auth_settings.token_keys(), authenticate))
+        .layer(axum::middleware::from_fn(track_writes));
+    let app = api
+        .merge(create_health_router(Arc::new(health_service)))
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()));
 
     // TCP listener binding to address and HTTP server startup
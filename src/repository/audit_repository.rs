@@ -0,0 +1,59 @@
+use crate::entity::audit_entry::AuditEntry;
+use crate::repository::unit_of_work::Transaction;
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+/// Storage of audit entries, implemented for Postgres (AuditRepository) and in memory (MemoryAuditRepository)
+/// Entries are written in the transaction of the change they record, so both commit or roll back together
+#[async_trait]
+pub trait AuditStore: Send + Sync {
+    /// Inserts an entry inside a transaction and returns it with ID and creation time
+    async fn record_audit_entry_tx(&self, tx: &mut Transaction, tenant_id: i32, entry: &AuditEntry) -> anyhow::Result<AuditEntry>;
+
+    /// Retrieves the entries about an employee, oldest first
+    async fn get_audit_entries_by_employee_id(&self, tenant_id: i32, employee_id: i32) -> anyhow::Result<Vec<AuditEntry>>;
+}
+
+/// Repository for audit entries in the database
+#[derive(Clone)]
+pub struct AuditRepository {
+    pool: PgPool,
+}
+impl AuditRepository {
+    /// Constructor for AuditRepository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AuditStore for AuditRepository {
+    async fn record_audit_entry_tx(&self, tx: &mut Transaction, tenant_id: i32, entry: &AuditEntry) -> anyhow::Result<AuditEntry> {
+        let recorded = sqlx::query_as!(
+            AuditEntry,
+            r#"INSERT INTO audit_entries (tenant_id, subject, action, employee_id, details) VALUES ($1, $2, $3, $4, $5)
+             RETURNING id AS "id?", subject, action, employee_id, details, created_at"#,
+            tenant_id,
+            entry.subject,
+            entry.action,
+            entry.employee_id,
+            entry.details
+        )
+        .fetch_one(tx.postgres()?)
+        .await?;
+        Ok(recorded)
+    }
+
+    async fn get_audit_entries_by_employee_id(&self, tenant_id: i32, employee_id: i32) -> anyhow::Result<Vec<AuditEntry>> {
+        let entries = sqlx::query_as!(
+            AuditEntry,
+            r#"SELECT id AS "id?", subject, action, employee_id, details, created_at FROM audit_entries
+             WHERE tenant_id = $1 AND employee_id = $2 ORDER BY id"#,
+            tenant_id,
+            employee_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+}
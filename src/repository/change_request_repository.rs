@@ -0,0 +1,200 @@
+use crate::entity::change_request::{Approval, ApprovalStep, ChangeRequest, ChangeRequestStatus, Comment, Decision, ProposedChange};
+use crate::repository::unit_of_work::Transaction;
+use crate::utils::CodedError;
+use async_trait::async_trait;
+use sqlx::types::Json;
+use sqlx::{PgExecutor, PgPool};
+
+/// Storage of change requests with their approval steps and comments,
+/// implemented for Postgres (ChangeRequestRepository) and in memory (MemoryChangeRequestRepository)
+/// Steps and comments go along when their change request is deleted
+#[async_trait]
+pub trait ChangeRequestStore: Send + Sync {
+    /// Inserts a change request with one undecided approval per step inside a transaction, returns it with its ID
+    async fn create_change_request_tx(&self, tx: &mut Transaction, tenant_id: i32, request: &ChangeRequest, steps: &[ApprovalStep]) -> anyhow::Result<ChangeRequest>;
+
+    /// Retrieves a change request by its ID
+    async fn get_change_request_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<ChangeRequest>>;
+
+    /// Retrieves a change request by its ID inside a transaction
+    async fn get_change_request_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<ChangeRequest>>;
+
+    /// Retrieves the change requests of a tenant, optionally only those with a stored status, ordered by ID
+    async fn get_change_requests(&self, tenant_id: i32, status: Option<ChangeRequestStatus>) -> anyhow::Result<Vec<ChangeRequest>>;
+
+    /// Updates status, resolved_at and failure_reason of a change request inside a transaction
+    async fn update_change_request_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, request: &ChangeRequest) -> anyhow::Result<ChangeRequest>;
+
+    /// Retrieves the approval steps of a change request in the order they were created
+    async fn get_approvals(&self, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Approval>>;
+
+    /// Retrieves the approval steps of a change request inside a transaction
+    async fn get_approvals_tx(&self, tx: &mut Transaction, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Approval>>;
+
+    /// Records the decision on an undecided step inside a transaction, returns the number of steps decided (0 or 1)
+    async fn decide_approval_tx(&self, tx: &mut Transaction, tenant_id: i32, change_request_id: i32, step: &str, decision: Decision, decided_by: &str) -> anyhow::Result<u64>;
+
+    /// Inserts a comment inside a transaction and returns it with its ID
+    async fn create_comment_tx(&self, tx: &mut Transaction, tenant_id: i32, comment: &Comment) -> anyhow::Result<Comment>;
+
+    /// Retrieves the comments of a change request, oldest first
+    async fn get_comments(&self, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Comment>>;
+}
+
+/// Repository for change requests in the database
+#[derive(Clone)]
+pub struct ChangeRequestRepository {
+    pool: PgPool,
+}
+impl ChangeRequestRepository {
+    /// Constructor for ChangeRequestRepository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ChangeRequestStore for ChangeRequestRepository {
+    async fn create_change_request_tx(&self, tx: &mut Transaction, tenant_id: i32, request: &ChangeRequest, steps: &[ApprovalStep]) -> anyhow::Result<ChangeRequest> {
+        let conn = tx.postgres()?;
+        let created = sqlx::query_as!(
+            ChangeRequest,
+            r#"INSERT INTO change_requests (tenant_id, change, status, requested_by, expires_at)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING id, change AS "change: Json<ProposedChange>", status AS "status: ChangeRequestStatus", requested_by, created_at, expires_at, resolved_at, failure_reason AS "failure_reason: Json<Vec<CodedError>>""#,
+            tenant_id,
+            &request.change as _,
+            request.status.as_str(),
+            request.requested_by,
+            request.expires_at
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        let keys: Vec<String> = steps.iter().map(ApprovalStep::key).collect();
+        sqlx::query!(
+            "INSERT INTO change_request_approvals (tenant_id, change_request_id, step, position)
+             SELECT $1, $2, step, position FROM UNNEST($3::VARCHAR[]) WITH ORDINALITY AS s(step, position)",
+            tenant_id,
+            created.id,
+            &keys
+        )
+        .execute(&mut *conn)
+        .await?;
+        Ok(created)
+    }
+
+    async fn get_change_request_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<ChangeRequest>> {
+        get_change_request_by_id(&self.pool, tenant_id, id).await
+    }
+
+    async fn get_change_request_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<ChangeRequest>> {
+        get_change_request_by_id(tx.postgres()?, tenant_id, id).await
+    }
+
+    async fn get_change_requests(&self, tenant_id: i32, status: Option<ChangeRequestStatus>) -> anyhow::Result<Vec<ChangeRequest>> {
+        let requests = sqlx::query_as!(
+            ChangeRequest,
+            r#"SELECT id, change AS "change: Json<ProposedChange>", status AS "status: ChangeRequestStatus", requested_by, created_at, expires_at, resolved_at, failure_reason AS "failure_reason: Json<Vec<CodedError>>"
+             FROM change_requests WHERE tenant_id = $1 AND ($2::TEXT IS NULL OR status = $2) ORDER BY id"#,
+            tenant_id,
+            status.map(|s| s.as_str())
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(requests)
+    }
+
+    async fn update_change_request_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, request: &ChangeRequest) -> anyhow::Result<ChangeRequest> {
+        let updated = sqlx::query_as!(
+            ChangeRequest,
+            r#"UPDATE change_requests SET status = $1, resolved_at = $2, failure_reason = $3 WHERE tenant_id = $4 AND id = $5
+             RETURNING id, change AS "change: Json<ProposedChange>", status AS "status: ChangeRequestStatus", requested_by, created_at, expires_at, resolved_at, failure_reason AS "failure_reason: Json<Vec<CodedError>>""#,
+            request.status.as_str(),
+            request.resolved_at,
+            &request.failure_reason as _,
+            tenant_id,
+            id
+        )
+        .fetch_one(tx.postgres()?)
+        .await?;
+        Ok(updated)
+    }
+
+    async fn get_approvals(&self, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Approval>> {
+        get_approvals(&self.pool, tenant_id, change_request_id).await
+    }
+
+    async fn get_approvals_tx(&self, tx: &mut Transaction, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Approval>> {
+        get_approvals(tx.postgres()?, tenant_id, change_request_id).await
+    }
+
+    async fn decide_approval_tx(&self, tx: &mut Transaction, tenant_id: i32, change_request_id: i32, step: &str, decision: Decision, decided_by: &str) -> anyhow::Result<u64> {
+        let result = sqlx::query!(
+            "UPDATE change_request_approvals SET decision = $1, decided_by = $2, decided_at = now()
+             WHERE tenant_id = $3 AND change_request_id = $4 AND step = $5 AND decision IS NULL",
+            decision.as_str(),
+            decided_by,
+            tenant_id,
+            change_request_id,
+            step
+        )
+        .execute(tx.postgres()?)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn create_comment_tx(&self, tx: &mut Transaction, tenant_id: i32, comment: &Comment) -> anyhow::Result<Comment> {
+        let created = sqlx::query_as!(
+            Comment,
+            "INSERT INTO change_request_comments (tenant_id, change_request_id, author, body) VALUES ($1, $2, $3, $4)
+             RETURNING id, change_request_id, author, body, created_at",
+            tenant_id,
+            comment.change_request_id,
+            comment.author,
+            comment.body
+        )
+        .fetch_one(tx.postgres()?)
+        .await?;
+        Ok(created)
+    }
+
+    async fn get_comments(&self, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Comment>> {
+        let comments = sqlx::query_as!(
+            Comment,
+            "SELECT id, change_request_id, author, body, created_at FROM change_request_comments
+             WHERE tenant_id = $1 AND change_request_id = $2 ORDER BY id",
+            tenant_id,
+            change_request_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(comments)
+    }
+}
+
+// Queries shared by the pool and the transaction methods
+async fn get_change_request_by_id(executor: impl PgExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Option<ChangeRequest>> {
+    let request = sqlx::query_as!(
+        ChangeRequest,
+        r#"SELECT id, change AS "change: Json<ProposedChange>", status AS "status: ChangeRequestStatus", requested_by, created_at, expires_at, resolved_at, failure_reason AS "failure_reason: Json<Vec<CodedError>>"
+         FROM change_requests WHERE tenant_id = $1 AND id = $2"#,
+        tenant_id,
+        id
+    )
+    .fetch_optional(executor)
+    .await?;
+    Ok(request)
+}
+
+async fn get_approvals(executor: impl PgExecutor<'_>, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Approval>> {
+    let approvals = sqlx::query_as!(
+        Approval,
+        r#"SELECT change_request_id, step, decision AS "decision: Decision", decided_by, decided_at
+         FROM change_request_approvals WHERE tenant_id = $1 AND change_request_id = $2 ORDER BY position"#,
+        tenant_id,
+        change_request_id
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(approvals)
+}
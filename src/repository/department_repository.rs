@@ -0,0 +1,142 @@
+use crate::entity::department::Department;
+use crate::repository::read_routing::PgPools;
+use crate::repository::unit_of_work::Transaction;
+use async_trait::async_trait;
+use sqlx::PgExecutor;
+
+/// Storage of Department entities, implemented for Postgres (DepartmentRepository) and in memory (MemoryDepartmentRepository)
+/// Every method is scoped to one tenant and must enforce unique names per tenant,
+/// a department that employees still belong to cannot be deleted
+#[async_trait]
+pub trait DepartmentStore: Send + Sync {
+    /// Inserts a department and returns the created department with its ID
+    async fn create_department(&self, tenant_id: i32, department: &Department) -> anyhow::Result<Department>;
+
+    /// Retrieves a department by its ID
+    async fn get_department_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Department>>;
+
+    /// Retrieves a department by its name
+    async fn get_department_by_name(&self, tenant_id: i32, name: &str) -> anyhow::Result<Option<Department>>;
+
+    /// Retrieves all departments of the tenant, ordered by ID
+    async fn get_all_departments(&self, tenant_id: i32) -> anyhow::Result<Vec<Department>>;
+
+    /// Updates a department by its ID and returns the updated department
+    async fn update_department_by_id(&self, tenant_id: i32, id: i32, department: &Department) -> anyhow::Result<Department>;
+
+    /// Deletes a department by its ID and returns the number of affected rows
+    async fn delete_department(&self, tenant_id: i32, id: i32) -> anyhow::Result<u64>;
+
+    /// Counts the employees belonging to a department
+    async fn employee_nr_by_department_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<i64>;
+
+    /// get_department_by_id inside a transaction
+    async fn get_department_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Department>>;
+}
+
+/// Repository for Department entities in the database
+/// Every query is filtered by tenant, departments of other tenants are never visible
+/// Listing reads from the replica when one is configured
+#[derive(Clone)]
+pub struct DepartmentRepository {
+    pools: PgPools,
+}
+impl DepartmentRepository {
+    /// Constructor for DepartmentRepository, takes a PgPool or PgPools with a replica
+    pub fn new(pools: impl Into<PgPools>) -> Self {
+        Self { pools: pools.into() }
+    }
+}
+
+#[async_trait]
+impl DepartmentStore for DepartmentRepository {
+    async fn create_department(&self, tenant_id: i32, department: &Department) -> anyhow::Result<Department> {
+        let created = sqlx::query_as!(
+            Department,
+            "INSERT INTO departments (tenant_id, name) VALUES ($1, $2) RETURNING id, name",
+            tenant_id,
+            department.name
+        )
+        .fetch_one(self.pools.writer())
+        .await?;
+        Ok(created)
+    }
+
+    async fn get_department_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Department>> {
+        get_department_by_id(self.pools.primary(), tenant_id, id).await
+    }
+
+    async fn get_department_by_name(&self, tenant_id: i32, name: &str) -> anyhow::Result<Option<Department>> {
+        let department = sqlx::query_as!(
+            Department,
+            "SELECT id, name FROM departments WHERE tenant_id = $1 AND name = $2",
+            tenant_id,
+            name
+        )
+        .fetch_optional(self.pools.primary())
+        .await?;
+        Ok(department)
+    }
+
+    async fn get_all_departments(&self, tenant_id: i32) -> anyhow::Result<Vec<Department>> {
+        self.pools.read(|pool| async move {
+            sqlx::query_as!(
+                Department,
+                "SELECT id, name FROM departments WHERE tenant_id = $1 ORDER BY id",
+                tenant_id
+            )
+            .fetch_all(&pool)
+            .await
+        })
+        .await
+    }
+
+    async fn update_department_by_id(&self, tenant_id: i32, id: i32, department: &Department) -> anyhow::Result<Department> {
+        let updated = sqlx::query_as!(
+            Department,
+            "UPDATE departments SET name = $1 WHERE tenant_id = $2 AND id = $3 RETURNING id, name",
+            department.name,
+            tenant_id,
+            id
+        )
+        .fetch_one(self.pools.writer())
+        .await?;
+        Ok(updated)
+    }
+
+    async fn delete_department(&self, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        let result = sqlx::query!("DELETE FROM departments WHERE tenant_id = $1 AND id = $2", tenant_id, id)
+            .execute(self.pools.writer())
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn employee_nr_by_department_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<i64> {
+        let count = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM employees WHERE tenant_id = $1 AND department_id = $2",
+            tenant_id,
+            id
+        )
+        .fetch_one(self.pools.primary())
+        .await?;
+        Ok(count.unwrap_or(0))
+    }
+
+    async fn get_department_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Department>> {
+        get_department_by_id(tx.postgres()?, tenant_id, id).await
+    }
+}
+
+// Queries shared by the pool and the transaction methods
+
+async fn get_department_by_id(executor: impl PgExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Option<Department>> {
+    let department = sqlx::query_as!(
+        Department,
+        "SELECT id, name FROM departments WHERE tenant_id = $1 AND id = $2",
+        tenant_id,
+        id
+    )
+    .fetch_optional(executor)
+    .await?;
+    Ok(department)
+}
@@ -1,102 +1,584 @@
-use crate::entity::employee::Employee;
-use sqlx::PgPool;
+use crate::entity::employee::{Employee, EmploymentStatus};
+use crate::entity::archived_employee::ArchivedEmployee;
+use crate::dto::export_dto::EmployeeExportRow;
+use futures::stream::{BoxStream, StreamExt};
+use crate::repository::read_routing::PgPools;
+use crate::repository::unit_of_work::Transaction;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use sqlx::PgExecutor;
+
+/// Deepest level the hierarchy queries follow, a guard against cycles that slipped past the service checks
+pub const MAX_HIERARCHY_DEPTH: i32 = 64;
+
+/// Storage of Employee entities, implemented for Postgres (EmployeeRepository) and in memory (MemoryEmployeeRepository)
+/// Every method is scoped to one tenant, employees must reference an office of the same tenant
+/// The `*_tx` methods run inside a transaction from the backend's UnitOfWork
+#[async_trait]
+pub trait EmployeeStore: Send + Sync {
+    /// Inserts employee and returns created employee with ID
+    async fn create_employee(&self, tenant_id: i32, employee: &Employee) -> anyhow::Result<Employee>;
+
+    /// Inserts all employees in one transaction and returns them with IDs
+    /// Either every employee is created or none is
+    async fn create_employees(&self, tenant_id: i32, employees: &[Employee]) -> anyhow::Result<Vec<Employee>>;
+
+    /// Counts the employees holding a seat in an office today
+    async fn current_employee_nr_by_office_id(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<i64>;
+
+    /// Highest number of employees holding a seat in an office on any day from `from` through `until`, open ended when None
+    /// An employee holds the seat from hire_date through termination_date, so future hires count from their start
+    async fn seated_employee_nr_by_office_id(&self, tenant_id: i32, office_id: i32, from: NaiveDate, until: Option<NaiveDate>) -> anyhow::Result<i64>;
+
+    /// Retrieves employee by ID
+    async fn get_employee_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Employee>>;
+
+    /// Retrieves employees by office ID
+    async fn get_employees_by_office_id(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<Employee>>;
+
+    /// Retrieves employees in any of the given offices
+    async fn get_employees_by_office_ids(&self, tenant_id: i32, office_ids: &[i32]) -> anyhow::Result<Vec<Employee>>;
+
+    /// Retrieves all employees
+    async fn get_all_employees(&self, tenant_id: i32) -> anyhow::Result<Vec<Employee>>;
+
+    /// Retrieves employees born on the given date, candidates for the duplicate check
+    async fn get_employees_by_birth_date(&self, tenant_id: i32, birth_date: NaiveDate) -> anyhow::Result<Vec<Employee>>;
+
+    /// Retrieves the employees reporting directly to the manager, ordered by ID
+    async fn get_direct_reports(&self, tenant_id: i32, manager_id: i32) -> anyhow::Result<Vec<Employee>>;
+
+    /// Retrieves the managers above an employee, nearest manager first and the top of the hierarchy last
+    /// Empty when the employee has no manager or does not exist
+    async fn get_reporting_chain(&self, tenant_id: i32, id: i32) -> anyhow::Result<Vec<Employee>>;
+
+    /// Retrieves everyone below a manager (direct and indirect reports), ordered by level, then ID
+    async fn get_subtree(&self, tenant_id: i32, manager_id: i32) -> anyhow::Result<Vec<Employee>>;
+
+    /// Updates employee by ID and returns updated employee
+    async fn update_employee_by_id(&self, tenant_id: i32, id: i32, employee: &Employee) -> anyhow::Result<Employee>;
+
+    /// Deletes employee by ID and returns number of affected rows
+    async fn delete_employee(&self, tenant_id: i32, id: i32) -> anyhow::Result<u64>;
+
+    /// Streams employees row by row for exports, nothing is buffered
+    /// office_id narrows to one office, office_ids to a set of offices (office scope), None means no filter
+    /// include_office joins offices to fill office_name
+    fn stream_employees(
+        &self,
+        tenant_id: i32,
+        office_id: Option<i32>,
+        office_ids: Option<Vec<i32>>,
+        include_office: bool,
+    ) -> BoxStream<'_, anyhow::Result<EmployeeExportRow>>;
+
+    /// create_employee inside a transaction
+    async fn create_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, employee: &Employee) -> anyhow::Result<Employee>;
+
+    /// delete_employee inside a transaction
+    async fn delete_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<u64>;
+
+    /// current_employee_nr_by_office_id inside a transaction
+    async fn current_employee_nr_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<i64>;
+
+    /// seated_employee_nr_by_office_id inside a transaction
+    async fn seated_employee_nr_by_office_id_tx(
+        &self,
+        tx: &mut Transaction,
+        tenant_id: i32,
+        office_id: i32,
+        from: NaiveDate,
+        until: Option<NaiveDate>,
+    ) -> anyhow::Result<i64>;
+
+    /// get_employee_by_id inside a transaction
+    async fn get_employee_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Employee>>;
+
+    /// get_employees_by_birth_date inside a transaction
+    async fn get_employees_by_birth_date_tx(&self, tx: &mut Transaction, tenant_id: i32, birth_date: NaiveDate) -> anyhow::Result<Vec<Employee>>;
+
+    /// get_employees_by_office_id inside a transaction
+    async fn get_employees_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<Employee>>;
+
+    /// update_employee_by_id inside a transaction
+    async fn update_employee_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, employee: &Employee) -> anyhow::Result<Employee>;
+
+    /// get_reporting_chain inside a transaction, used for the cycle check before a manager is set
+    async fn get_reporting_chain_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Vec<Employee>>;
+
+    /// Moves every employee of one office to another and returns the number of moved employees
+    async fn reassign_employees_tx(&self, tx: &mut Transaction, tenant_id: i32, from_office_id: i32, to_office_id: i32) -> anyhow::Result<u64>;
+
+    /// Retrieves an employee that was archived by a merge
+    async fn get_archived_employee_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<ArchivedEmployee>>;
+
+    /// Merges the source employee into the survivor and returns the number of archived employees (0 if the source does not exist)
+    /// Records about the source (audit entries, employees merged into it earlier, its reports, its transfers,
+    /// the waitlist entries it was placed from or is to manage) are moved
+    /// to the survivor, a pending transfer of the source is cancelled first since the survivor stays where it is.
+    /// The source's room goes to a survivor without one that sits in the room's office after the merge
+    /// (survivor_office_id, the survivor is updated afterwards), otherwise it is freed with the seat.
+    /// Then the source is copied to archived_employees and deleted, which releases its seat.
+    /// New tables that reference employees must be re-pointed here as well
+    async fn merge_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, source_id: i32, survivor_id: i32, survivor_office_id: i32) -> anyhow::Result<u64>;
+}
 
 /// Repository for Employee entities in the database
 /// Handles database operations for employees
+/// Every query is filtered by tenant, employees of other tenants are never visible
+/// Listing and exporting read from the replica when one is configured
 
 #[derive(Clone)]
 pub struct EmployeeRepository {
-    pool: PgPool,
+    pools: PgPools,
 }
 impl EmployeeRepository {
-    /// Constructor for EmployeeRepository
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    /// Constructor for EmployeeRepository, takes a PgPool or PgPools with a replica
+    pub fn new(pools: impl Into<PgPools>) -> Self {
+        Self { pools: pools.into() }
     }
+}
 
-    /// Inserts employee and returns created employee with ID
-    pub async fn create_employee(&self, employee: &Employee) -> anyhow::Result<Employee> {
-        let created = sqlx::query_as!(
-            Employee,
-            "INSERT INTO employees (first_name, last_name, birth_date, office_id) VALUES ($1, $2, $3, $4) RETURNING id, first_name, last_name, birth_date, office_id",
-            employee.first_name,
-            employee.last_name,
-            employee.birth_date,
-            employee.office_id
-        )
-        .fetch_one(&self.pool)
-        .await?;
+#[async_trait]
+impl EmployeeStore for EmployeeRepository {
+    async fn create_employee(&self, tenant_id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        create_employee(self.pools.writer(), tenant_id, employee).await
+    }
+
+    async fn create_employees(&self, tenant_id: i32, employees: &[Employee]) -> anyhow::Result<Vec<Employee>> {
+        let mut tx = self.pools.writer().begin().await?;
+        let mut created = Vec::with_capacity(employees.len());
+        for employee in employees {
+            created.push(create_employee(&mut *tx, tenant_id, employee).await?);
+        }
+        tx.commit().await?;
         Ok(created)
     }
 
-    /// Counts current number of employees in an office with given office_id
-    pub async fn current_employee_nr_by_office_id(&self, office_id: i32) -> anyhow::Result<i64> {
-        let count = sqlx::query_scalar!(
-            "SELECT COUNT(*) FROM employees WHERE office_id = $1",
-            office_id
-        )
-        .fetch_one(&self.pool)
-        .await?;
-        Ok(count.unwrap_or(0))
+    async fn current_employee_nr_by_office_id(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<i64> {
+        let today = chrono::Utc::now().date_naive();
+        seated_employee_nr_by_office_id(self.pools.primary(), tenant_id, office_id, today, Some(today)).await
     }
 
-    /// Retrieves employee by ID
-    pub async fn get_employee_by_id(&self, id: i32) -> anyhow::Result<Option<Employee>> {
-        let employee = sqlx::query_as!(
+    async fn seated_employee_nr_by_office_id(&self, tenant_id: i32, office_id: i32, from: NaiveDate, until: Option<NaiveDate>) -> anyhow::Result<i64> {
+        seated_employee_nr_by_office_id(self.pools.primary(), tenant_id, office_id, from, until).await
+    }
+
+    async fn get_employee_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Employee>> {
+        get_employee_by_id(self.pools.primary(), tenant_id, id).await
+    }
+
+    async fn get_employees_by_office_id(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<Employee>> {
+        self.pools.read(|pool| async move {
+            sqlx::query_as!(
+                Employee,
+                r#"SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status AS "status: EmploymentStatus" FROM employees WHERE tenant_id = $1 AND office_id = $2"#,
+                tenant_id,
+                office_id
+            )
+            .fetch_all(&pool)
+            .await
+        })
+        .await
+    }
+
+    async fn get_employees_by_office_ids(&self, tenant_id: i32, office_ids: &[i32]) -> anyhow::Result<Vec<Employee>> {
+        self.pools.read(|pool| async move {
+            sqlx::query_as!(
+                Employee,
+                r#"SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status AS "status: EmploymentStatus" FROM employees WHERE tenant_id = $1 AND office_id = ANY($2)"#,
+                tenant_id,
+                office_ids
+            )
+            .fetch_all(&pool)
+            .await
+        })
+        .await
+    }
+
+    async fn get_all_employees(&self, tenant_id: i32) -> anyhow::Result<Vec<Employee>> {
+        self.pools.read(|pool| async move {
+            sqlx::query_as!(
+                Employee,
+                r#"SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status AS "status: EmploymentStatus" FROM employees WHERE tenant_id = $1"#,
+                tenant_id
+            )
+            .fetch_all(&pool)
+            .await
+        })
+        .await
+    }
+
+    async fn get_employees_by_birth_date(&self, tenant_id: i32, birth_date: NaiveDate) -> anyhow::Result<Vec<Employee>> {
+        get_employees_by_birth_date(self.pools.primary(), tenant_id, birth_date).await
+    }
+
+    async fn get_direct_reports(&self, tenant_id: i32, manager_id: i32) -> anyhow::Result<Vec<Employee>> {
+        let employees = sqlx::query_as!(
             Employee,
-            "SELECT id, first_name, last_name, birth_date, office_id FROM employees WHERE id = $1",
-            id
+            r#"SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status AS "status: EmploymentStatus" FROM employees WHERE tenant_id = $1 AND manager_id = $2 ORDER BY id"#,
+            tenant_id,
+            manager_id
         )
-        .fetch_optional(&self.pool)
+        .fetch_all(self.pools.primary())
         .await?;
-        Ok(employee)
+        Ok(employees)
     }
 
-    /// Retrieves employees by office ID
-    pub async fn get_employees_by_office_id(&self, office_id: i32) -> anyhow::Result<Vec<Employee>> {
+    async fn get_reporting_chain(&self, tenant_id: i32, id: i32) -> anyhow::Result<Vec<Employee>> {
+        get_reporting_chain(self.pools.primary(), tenant_id, id).await
+    }
+
+    async fn get_subtree(&self, tenant_id: i32, manager_id: i32) -> anyhow::Result<Vec<Employee>> {
         let employees = sqlx::query_as!(
             Employee,
-            "SELECT id, first_name, last_name, birth_date, office_id FROM employees WHERE office_id = $1",
-            office_id
+            r#"WITH RECURSIVE subtree AS (
+                   SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status, 1 AS depth
+                   FROM employees WHERE tenant_id = $1 AND manager_id = $2
+                   UNION ALL
+                   SELECT e.id, e.first_name, e.last_name, e.birth_date, e.office_id, e.department_id, e.manager_id, e.hire_date, e.termination_date, e.status, s.depth + 1
+                   FROM employees e JOIN subtree s ON e.manager_id = s.id
+                   WHERE e.tenant_id = $1 AND s.depth < $3
+               )
+               SELECT id AS "id?", first_name AS "first_name!", last_name AS "last_name!", birth_date AS "birth_date!",
+                      office_id AS "office_id!", department_id, manager_id, hire_date AS "hire_date!", termination_date,
+                      status AS "status!: EmploymentStatus"
+               FROM subtree ORDER BY depth, id"#,
+            tenant_id,
+            manager_id,
+            MAX_HIERARCHY_DEPTH
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.pools.primary())
         .await?;
         Ok(employees)
     }
 
-    /// Retrieves all employees
-    pub async fn get_all_employees(&self) -> anyhow::Result<Vec<Employee>> {
+    async fn update_employee_by_id(&self, tenant_id: i32, id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        update_employee_by_id(self.pools.writer(), tenant_id, id, employee).await
+    }
+
+    async fn delete_employee(&self, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        delete_employee(self.pools.writer(), tenant_id, id).await
+    }
+
+    fn stream_employees(
+        &self,
+        tenant_id: i32,
+        office_id: Option<i32>,
+        office_ids: Option<Vec<i32>>,
+        include_office: bool,
+    ) -> BoxStream<'_, anyhow::Result<EmployeeExportRow>> {
+        let rows = if include_office {
+            sqlx::query_as!(
+                EmployeeExportRow,
+                r#"SELECT e.id, e.first_name, e.last_name, e.birth_date, e.office_id, o.name AS "office_name?"
+                   FROM employees e JOIN offices o ON o.id = e.office_id
+                   WHERE e.tenant_id = $1 AND ($2::int IS NULL OR e.office_id = $2) AND ($3::int[] IS NULL OR e.office_id = ANY($3))
+                   ORDER BY e.id"#,
+                tenant_id,
+                office_id,
+                office_ids.as_deref()
+            )
+            .fetch(self.pools.reader())
+        } else {
+            sqlx::query_as!(
+                EmployeeExportRow,
+                r#"SELECT id, first_name, last_name, birth_date, office_id, NULL::text AS "office_name?"
+                   FROM employees
+                   WHERE tenant_id = $1 AND ($2::int IS NULL OR office_id = $2) AND ($3::int[] IS NULL OR office_id = ANY($3))
+                   ORDER BY id"#,
+                tenant_id,
+                office_id,
+                office_ids.as_deref()
+            )
+            .fetch(self.pools.reader())
+        };
+        rows.map(|row| row.map_err(anyhow::Error::from)).boxed()
+    }
+
+    async fn create_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        create_employee(tx.postgres()?, tenant_id, employee).await
+    }
+
+    async fn delete_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        delete_employee(tx.postgres()?, tenant_id, id).await
+    }
+
+    async fn current_employee_nr_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<i64> {
+        let today = chrono::Utc::now().date_naive();
+        seated_employee_nr_by_office_id(tx.postgres()?, tenant_id, office_id, today, Some(today)).await
+    }
+
+    async fn seated_employee_nr_by_office_id_tx(
+        &self,
+        tx: &mut Transaction,
+        tenant_id: i32,
+        office_id: i32,
+        from: NaiveDate,
+        until: Option<NaiveDate>,
+    ) -> anyhow::Result<i64> {
+        seated_employee_nr_by_office_id(tx.postgres()?, tenant_id, office_id, from, until).await
+    }
+
+    async fn get_employee_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Employee>> {
+        get_employee_by_id(tx.postgres()?, tenant_id, id).await
+    }
+
+    async fn get_employees_by_birth_date_tx(&self, tx: &mut Transaction, tenant_id: i32, birth_date: NaiveDate) -> anyhow::Result<Vec<Employee>> {
+        get_employees_by_birth_date(tx.postgres()?, tenant_id, birth_date).await
+    }
+
+    async fn get_employees_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<Employee>> {
         let employees = sqlx::query_as!(
             Employee,
-            "SELECT id, first_name, last_name, birth_date, office_id FROM employees"
+            r#"SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status AS "status: EmploymentStatus" FROM employees WHERE tenant_id = $1 AND office_id = $2"#,
+            tenant_id,
+            office_id
         )
-        .fetch_all(&self.pool)
+        .fetch_all(tx.postgres()?)
         .await?;
         Ok(employees)
     }
 
-    /// Updates employee by ID and returns updated employee
-    pub async fn update_employee_by_id(&self, id: i32, employee: &Employee) -> anyhow::Result<Employee> {
-        let updated = sqlx::query_as!(
-            Employee,
-            "UPDATE employees SET first_name = $1, last_name = $2, birth_date = $3, office_id = $4 WHERE id = $5 RETURNING id, first_name, last_name, birth_date, office_id",
-            employee.first_name,
-            employee.last_name,
-            employee.birth_date,
-            employee.office_id,
+    async fn update_employee_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        update_employee_by_id(tx.postgres()?, tenant_id, id, employee).await
+    }
+
+    async fn get_reporting_chain_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Vec<Employee>> {
+        get_reporting_chain(tx.postgres()?, tenant_id, id).await
+    }
+
+    async fn reassign_employees_tx(&self, tx: &mut Transaction, tenant_id: i32, from_office_id: i32, to_office_id: i32) -> anyhow::Result<u64> {
+        let result = sqlx::query!(
+            "UPDATE employees SET office_id = $1 WHERE tenant_id = $2 AND office_id = $3",
+            to_office_id,
+            tenant_id,
+            from_office_id
+        )
+        .execute(tx.postgres()?)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn get_archived_employee_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<ArchivedEmployee>> {
+        let archived = sqlx::query_as!(
+            ArchivedEmployee,
+            "SELECT id, first_name, last_name, birth_date, office_id, merged_into, archived_at FROM archived_employees WHERE tenant_id = $1 AND id = $2",
+            tenant_id,
             id
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(self.pools.primary())
         .await?;
-        Ok(updated)
+        Ok(archived)
     }
 
-    /// Deletes employee by ID and returns number of affected rows
-    pub async fn delete_employee(&self, id: i32) -> anyhow::Result<u64> {
-        let result = sqlx::query!("DELETE FROM employees WHERE id = $1",id)
-            .execute(&self.pool)
+    async fn merge_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, source_id: i32, survivor_id: i32, survivor_office_id: i32) -> anyhow::Result<u64> {
+        let conn = tx.postgres()?;
+        sqlx::query!(
+            "UPDATE audit_entries SET employee_id = $1 WHERE tenant_id = $2 AND employee_id = $3",
+            survivor_id,
+            tenant_id,
+            source_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query!(
+            "UPDATE archived_employees SET merged_into = $1 WHERE tenant_id = $2 AND merged_into = $3",
+            survivor_id,
+            tenant_id,
+            source_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query!(
+            "UPDATE employees SET manager_id = $1 WHERE tenant_id = $2 AND manager_id = $3 AND id <> $1",
+            survivor_id,
+            tenant_id,
+            source_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query!(
+            "UPDATE transfers SET status = 'cancelled' WHERE tenant_id = $1 AND employee_id = $2 AND status = 'pending'",
+            tenant_id,
+            source_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query!(
+            "UPDATE transfers SET employee_id = $1 WHERE tenant_id = $2 AND employee_id = $3",
+            survivor_id,
+            tenant_id,
+            source_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query!(
+            "UPDATE waitlist_entries SET employee_id = $1 WHERE tenant_id = $2 AND employee_id = $3",
+            survivor_id,
+            tenant_id,
+            source_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query!(
+            "UPDATE waitlist_entries SET manager_id = $1 WHERE tenant_id = $2 AND manager_id = $3",
+            survivor_id,
+            tenant_id,
+            source_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query!(
+            "UPDATE location_assignments a SET employee_id = $1
+             FROM locations r
+             WHERE a.tenant_id = $2 AND a.employee_id = $3
+               AND r.id = a.location_id AND r.office_id = $4
+               AND NOT EXISTS (SELECT 1 FROM location_assignments o WHERE o.employee_id = $1)",
+            survivor_id,
+            tenant_id,
+            source_id,
+            survivor_office_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query!(
+            "INSERT INTO archived_employees (id, tenant_id, first_name, last_name, birth_date, office_id, merged_into)
+             SELECT id, tenant_id, first_name, last_name, birth_date, office_id, $1 FROM employees WHERE tenant_id = $2 AND id = $3",
+            survivor_id,
+            tenant_id,
+            source_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        let result = sqlx::query!("DELETE FROM employees WHERE tenant_id = $1 AND id = $2", tenant_id, source_id)
+            .execute(&mut *conn)
             .await?;
         Ok(result.rows_affected())
     }
+}
+
+// Queries shared by the pool and the transaction methods
+
+// Seats are only taken on hire dates, so the peak is on `from` or on one of the later hire dates within the period
+async fn seated_employee_nr_by_office_id(
+    executor: impl PgExecutor<'_>,
+    tenant_id: i32,
+    office_id: i32,
+    from: NaiveDate,
+    until: Option<NaiveDate>,
+) -> anyhow::Result<i64> {
+    let count = sqlx::query_scalar!(
+        r#"SELECT MAX((SELECT COUNT(*) FROM employees e
+                       WHERE e.tenant_id = $1 AND e.office_id = $2 AND e.hire_date <= days.day
+                         AND (e.termination_date IS NULL OR e.termination_date >= days.day))) AS "count!"
+           FROM (SELECT $3::date AS day
+                 UNION
+                 SELECT hire_date FROM employees
+                 WHERE tenant_id = $1 AND office_id = $2 AND hire_date > $3 AND ($4::date IS NULL OR hire_date <= $4)) AS days"#,
+        tenant_id,
+        office_id,
+        from,
+        until
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(count)
+}
+
+async fn delete_employee(executor: impl PgExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+    let result = sqlx::query!("DELETE FROM employees WHERE tenant_id = $1 AND id = $2", tenant_id, id)
+        .execute(executor)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+async fn create_employee(executor: impl PgExecutor<'_>, tenant_id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+    let created = sqlx::query_as!(
+        Employee,
+        r#"INSERT INTO employees (tenant_id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status AS "status: EmploymentStatus""#,
+        tenant_id,
+        employee.first_name,
+        employee.last_name,
+        employee.birth_date,
+        employee.office_id,
+        employee.department_id,
+        employee.manager_id,
+        employee.hire_date,
+        employee.termination_date,
+        employee.status.as_str()
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(created)
+}
+
+async fn get_employees_by_birth_date(executor: impl PgExecutor<'_>, tenant_id: i32, birth_date: NaiveDate) -> anyhow::Result<Vec<Employee>> {
+    let employees = sqlx::query_as!(
+        Employee,
+        r#"SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status AS "status: EmploymentStatus" FROM employees WHERE tenant_id = $1 AND birth_date = $2"#,
+        tenant_id,
+        birth_date
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(employees)
+}
+
+async fn get_employee_by_id(executor: impl PgExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Option<Employee>> {
+    let employee = sqlx::query_as!(
+        Employee,
+        r#"SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status AS "status: EmploymentStatus" FROM employees WHERE tenant_id = $1 AND id = $2"#,
+        tenant_id,
+        id
+    )
+    .fetch_optional(executor)
+    .await?;
+    Ok(employee)
+}
+
+async fn get_reporting_chain(executor: impl PgExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Vec<Employee>> {
+    let chain = sqlx::query_as!(
+        Employee,
+        r#"WITH RECURSIVE chain AS (
+               SELECT m.id, m.first_name, m.last_name, m.birth_date, m.office_id, m.department_id, m.manager_id, m.hire_date, m.termination_date, m.status, 1 AS depth
+               FROM employees e JOIN employees m ON m.tenant_id = e.tenant_id AND m.id = e.manager_id
+               WHERE e.tenant_id = $1 AND e.id = $2
+               UNION ALL
+               SELECT m.id, m.first_name, m.last_name, m.birth_date, m.office_id, m.department_id, m.manager_id, m.hire_date, m.termination_date, m.status, c.depth + 1
+               FROM employees m JOIN chain c ON m.id = c.manager_id
+               WHERE m.tenant_id = $1 AND c.depth < $3
+           )
+           SELECT id AS "id?", first_name AS "first_name!", last_name AS "last_name!", birth_date AS "birth_date!",
+                  office_id AS "office_id!", department_id, manager_id, hire_date AS "hire_date!", termination_date,
+                  status AS "status!: EmploymentStatus"
+           FROM chain ORDER BY depth"#,
+        tenant_id,
+        id,
+        MAX_HIERARCHY_DEPTH
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(chain)
+}
 
+async fn update_employee_by_id(executor: impl PgExecutor<'_>, tenant_id: i32, id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+    let updated = sqlx::query_as!(
+        Employee,
+        r#"UPDATE employees SET first_name = $1, last_name = $2, birth_date = $3, office_id = $4, department_id = $5, manager_id = $6, hire_date = $7, termination_date = $8, status = $9 WHERE tenant_id = $10 AND id = $11 RETURNING id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status AS "status: EmploymentStatus""#,
+        employee.first_name,
+        employee.last_name,
+        employee.birth_date,
+        employee.office_id,
+        employee.department_id,
+        employee.manager_id,
+        employee.hire_date,
+        employee.termination_date,
+        employee.status.as_str(),
+        tenant_id,
+        id
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(updated)
 }
\ No newline at end of file
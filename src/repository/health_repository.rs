@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+/// Reachability of a storage backend, implemented for Postgres (HealthRepository), SQLite and in memory
+#[async_trait]
+pub trait HealthStore: Send + Sync {
+    /// Runs a trivial query, errors while the database cannot be reached
+    async fn ping(&self) -> anyhow::Result<()>;
+}
+
+/// Repository probing the Postgres primary
+#[derive(Clone)]
+pub struct HealthRepository {
+    pool: PgPool,
+}
+
+impl HealthRepository {
+    /// Constructor for HealthRepository, works with a lazily connecting pool
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl HealthStore for HealthRepository {
+    async fn ping(&self) -> anyhow::Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use crate::entity::idempotency_record::{IdempotencyKey, IdempotencyRecord};
+use chrono::{DateTime, Utc};
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+/// Storage of idempotency records, implemented for Postgres (IdempotencyRepository) and in memory (MemoryIdempotencyRepository)
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync {
+    /// Reserves a key for a new request, replacing an expired record with the same key
+    /// Returns false if an unexpired record already exists
+    async fn reserve_idempotency_key(
+        &self,
+        key: &IdempotencyKey,
+        request_hash: &str,
+        expired_before: DateTime<Utc>,
+    ) -> anyhow::Result<bool>;
+
+    /// Retrieves the record of a key
+    async fn get_idempotency_record(&self, key: &IdempotencyKey) -> anyhow::Result<Option<IdempotencyRecord>>;
+
+    /// Stores the response of a reserved key
+    async fn complete_idempotency_key(
+        &self,
+        key: &IdempotencyKey,
+        status_code: i32,
+        content_type: Option<&str>,
+        response_body: &[u8],
+    ) -> anyhow::Result<()>;
+
+    /// Deletes a key so the request can be retried
+    async fn delete_idempotency_key(&self, key: &IdempotencyKey) -> anyhow::Result<u64>;
+
+    /// Deletes all records reserved before the cutoff and returns number of affected rows
+    async fn delete_expired_idempotency_keys(&self, expired_before: DateTime<Utc>) -> anyhow::Result<u64>;
+}
+
+/// Repository for stored idempotent responses
+/// A key is identified by tenant, subject, route and the client supplied key
+#[derive(Clone)]
+pub struct IdempotencyRepository {
+    pool: PgPool,
+}
+impl IdempotencyRepository {
+    /// Constructor for IdempotencyRepository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for IdempotencyRepository {
+    async fn reserve_idempotency_key(
+        &self,
+        key: &IdempotencyKey,
+        request_hash: &str,
+        expired_before: DateTime<Utc>,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query!(
+            "INSERT INTO idempotency_keys (tenant_id, subject, route, idempotency_key, request_hash) VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (tenant_id, subject, route, idempotency_key) DO UPDATE
+             SET request_hash = EXCLUDED.request_hash, status_code = NULL, content_type = NULL, response_body = NULL, created_at = now()
+             WHERE idempotency_keys.created_at < $6",
+            key.tenant_id,
+            key.subject,
+            key.route,
+            key.key,
+            request_hash,
+            expired_before
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn get_idempotency_record(&self, key: &IdempotencyKey) -> anyhow::Result<Option<IdempotencyRecord>> {
+        let record = sqlx::query_as!(
+            IdempotencyRecord,
+            "SELECT request_hash, status_code, content_type, response_body, created_at FROM idempotency_keys
+             WHERE tenant_id = $1 AND subject = $2 AND route = $3 AND idempotency_key = $4",
+            key.tenant_id,
+            key.subject,
+            key.route,
+            key.key
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record)
+    }
+
+    async fn complete_idempotency_key(
+        &self,
+        key: &IdempotencyKey,
+        status_code: i32,
+        content_type: Option<&str>,
+        response_body: &[u8],
+    ) -> anyhow::Result<()> {
+        sqlx::query!(
+            "UPDATE idempotency_keys SET status_code = $1, content_type = $2, response_body = $3
+             WHERE tenant_id = $4 AND subject = $5 AND route = $6 AND idempotency_key = $7",
+            status_code,
+            content_type,
+            response_body,
+            key.tenant_id,
+            key.subject,
+            key.route,
+            key.key
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_idempotency_key(&self, key: &IdempotencyKey) -> anyhow::Result<u64> {
+        let result = sqlx::query!(
+            "DELETE FROM idempotency_keys WHERE tenant_id = $1 AND subject = $2 AND route = $3 AND idempotency_key = $4",
+            key.tenant_id,
+            key.subject,
+            key.route,
+            key.key
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_expired_idempotency_keys(&self, expired_before: DateTime<Utc>) -> anyhow::Result<u64> {
+        let result = sqlx::query!("DELETE FROM idempotency_keys WHERE created_at < $1", expired_before)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
\ No newline at end of file
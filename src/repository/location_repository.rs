@@ -0,0 +1,198 @@
+use crate::entity::location::{Location, LocationKind};
+use crate::repository::unit_of_work::Transaction;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use sqlx::{PgExecutor, PgPool};
+
+/// Storage of the location tree and the rooms of employees, implemented for Postgres (LocationRepository) and in memory (MemoryLocationRepository)
+/// Every method is scoped to one tenant. Locations are read with the max_occupancy of the office as capacity of a site.
+/// A location with children cannot be deleted, deleting an office takes its site and everything below along.
+/// A room assignment only counts while the employee stays in the office of the room
+#[async_trait]
+pub trait LocationStore: Send + Sync {
+    /// Inserts a location inside a transaction and returns it with its ID
+    async fn create_location_tx(&self, tx: &mut Transaction, tenant_id: i32, location: &Location) -> anyhow::Result<Location>;
+
+    /// Retrieves all locations of the tenant, ordered by ID
+    async fn get_locations(&self, tenant_id: i32) -> anyhow::Result<Vec<Location>>;
+
+    /// get_locations inside a transaction
+    async fn get_locations_tx(&self, tx: &mut Transaction, tenant_id: i32) -> anyhow::Result<Vec<Location>>;
+
+    /// Updates name, own capacity and parent of a location inside a transaction and returns the updated location
+    async fn update_location_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, location: &Location) -> anyhow::Result<Location>;
+
+    /// Deletes a location inside a transaction and returns the number of affected rows
+    async fn delete_location_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<u64>;
+
+    /// Counts the employees holding a seat on or after `from` per location they sit in directly:
+    /// their room, or the site of their office when they have none. Locations without anyone are left out
+    async fn get_occupants(&self, tenant_id: i32, from: NaiveDate) -> anyhow::Result<Vec<(i32, i64)>>;
+
+    /// get_occupants inside a transaction
+    async fn get_occupants_tx(&self, tx: &mut Transaction, tenant_id: i32, from: NaiveDate) -> anyhow::Result<Vec<(i32, i64)>>;
+
+    /// Retrieves the room of an employee inside a transaction, None without one or once the employee left its office
+    async fn get_room_id_tx(&self, tx: &mut Transaction, tenant_id: i32, employee_id: i32) -> anyhow::Result<Option<i32>>;
+
+    /// Puts an employee in a room inside a transaction, replacing the room the employee had
+    async fn assign_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, employee_id: i32, location_id: i32) -> anyhow::Result<()>;
+
+    /// Takes an employee out of a room inside a transaction and returns the number of affected rows
+    async fn unassign_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, employee_id: i32, location_id: i32) -> anyhow::Result<u64>;
+}
+
+/// Repository for the location tree in the database
+#[derive(Clone)]
+pub struct LocationRepository {
+    pool: PgPool,
+}
+impl LocationRepository {
+    /// Constructor for LocationRepository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl LocationStore for LocationRepository {
+    async fn create_location_tx(&self, tx: &mut Transaction, tenant_id: i32, location: &Location) -> anyhow::Result<Location> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO locations (tenant_id, parent_id, kind, name, capacity, office_id) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+            tenant_id,
+            location.parent_id,
+            location.kind.as_str(),
+            location.name,
+            location.capacity,
+            location.office_id
+        )
+        .fetch_one(tx.postgres()?)
+        .await?;
+        get_location_by_id(tx.postgres()?, tenant_id, id).await
+    }
+
+    async fn get_locations(&self, tenant_id: i32) -> anyhow::Result<Vec<Location>> {
+        get_locations(&self.pool, tenant_id).await
+    }
+
+    async fn get_locations_tx(&self, tx: &mut Transaction, tenant_id: i32) -> anyhow::Result<Vec<Location>> {
+        get_locations(tx.postgres()?, tenant_id).await
+    }
+
+    async fn update_location_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, location: &Location) -> anyhow::Result<Location> {
+        let result = sqlx::query!(
+            "UPDATE locations SET name = $1, capacity = CASE WHEN kind = 'site' THEN NULL ELSE $2::int END, parent_id = $3 WHERE tenant_id = $4 AND id = $5",
+            location.name,
+            location.capacity,
+            location.parent_id,
+            tenant_id,
+            id
+        )
+        .execute(tx.postgres()?)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        get_location_by_id(tx.postgres()?, tenant_id, id).await
+    }
+
+    async fn delete_location_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        let result = sqlx::query!("DELETE FROM locations WHERE tenant_id = $1 AND id = $2", tenant_id, id)
+            .execute(tx.postgres()?)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn get_occupants(&self, tenant_id: i32, from: NaiveDate) -> anyhow::Result<Vec<(i32, i64)>> {
+        get_occupants(&self.pool, tenant_id, from).await
+    }
+
+    async fn get_occupants_tx(&self, tx: &mut Transaction, tenant_id: i32, from: NaiveDate) -> anyhow::Result<Vec<(i32, i64)>> {
+        get_occupants(tx.postgres()?, tenant_id, from).await
+    }
+
+    async fn get_room_id_tx(&self, tx: &mut Transaction, tenant_id: i32, employee_id: i32) -> anyhow::Result<Option<i32>> {
+        let room_id = sqlx::query_scalar!(
+            "SELECT r.id FROM location_assignments a
+             JOIN employees e ON e.id = a.employee_id
+             JOIN locations r ON r.id = a.location_id AND r.office_id = e.office_id
+             WHERE a.tenant_id = $1 AND a.employee_id = $2",
+            tenant_id,
+            employee_id
+        )
+        .fetch_optional(tx.postgres()?)
+        .await?;
+        Ok(room_id)
+    }
+
+    async fn assign_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, employee_id: i32, location_id: i32) -> anyhow::Result<()> {
+        sqlx::query!(
+            "INSERT INTO location_assignments (tenant_id, employee_id, location_id) VALUES ($1, $2, $3)
+             ON CONFLICT (employee_id) DO UPDATE SET location_id = EXCLUDED.location_id",
+            tenant_id,
+            employee_id,
+            location_id
+        )
+        .execute(tx.postgres()?)
+        .await?;
+        Ok(())
+    }
+
+    async fn unassign_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, employee_id: i32, location_id: i32) -> anyhow::Result<u64> {
+        let result = sqlx::query!(
+            "DELETE FROM location_assignments WHERE tenant_id = $1 AND employee_id = $2 AND location_id = $3",
+            tenant_id,
+            employee_id,
+            location_id
+        )
+        .execute(tx.postgres()?)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+// Queries shared by the pool and the transaction methods
+
+async fn get_location_by_id(executor: impl PgExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Location> {
+    let location = sqlx::query_as!(
+        Location,
+        r#"SELECT l.id, l.parent_id, l.kind AS "kind: LocationKind", l.name, COALESCE(l.capacity, o.max_occupancy) AS capacity, l.office_id
+         FROM locations l LEFT JOIN offices o ON l.kind = 'site' AND o.id = l.office_id
+         WHERE l.tenant_id = $1 AND l.id = $2"#,
+        tenant_id,
+        id
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(location)
+}
+
+async fn get_locations(executor: impl PgExecutor<'_>, tenant_id: i32) -> anyhow::Result<Vec<Location>> {
+    let locations = sqlx::query_as!(
+        Location,
+        r#"SELECT l.id, l.parent_id, l.kind AS "kind: LocationKind", l.name, COALESCE(l.capacity, o.max_occupancy) AS capacity, l.office_id
+         FROM locations l LEFT JOIN offices o ON l.kind = 'site' AND o.id = l.office_id
+         WHERE l.tenant_id = $1 ORDER BY l.id"#,
+        tenant_id
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(locations)
+}
+
+async fn get_occupants(executor: impl PgExecutor<'_>, tenant_id: i32, from: NaiveDate) -> anyhow::Result<Vec<(i32, i64)>> {
+    let rows = sqlx::query!(
+        r#"SELECT COALESCE(r.id, s.id) AS "location_id!", COUNT(*) AS "occupants!"
+         FROM employees e
+         LEFT JOIN location_assignments a ON a.employee_id = e.id
+         LEFT JOIN locations r ON r.id = a.location_id AND r.office_id = e.office_id
+         LEFT JOIN locations s ON s.kind = 'site' AND s.office_id = e.office_id
+         WHERE e.tenant_id = $1 AND (e.termination_date IS NULL OR e.termination_date >= $2) AND COALESCE(r.id, s.id) IS NOT NULL
+         GROUP BY COALESCE(r.id, s.id)"#,
+        tenant_id,
+        from
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(rows.into_iter().map(|r| (r.location_id, r.occupants)).collect())
+}
\ No newline at end of file
@@ -0,0 +1,38 @@
+use crate::entity::audit_entry::AuditEntry;
+use crate::repository::audit_repository::AuditStore;
+use crate::repository::unit_of_work::Transaction;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::Utc;
+
+use super::{MemoryDatabase, MAX_VARCHAR};
+
+/// In-memory implementation of AuditStore, see MemoryDatabase
+#[derive(Clone)]
+pub struct MemoryAuditRepository {
+    pub(super) db: MemoryDatabase,
+}
+
+#[async_trait]
+impl AuditStore for MemoryAuditRepository {
+    async fn record_audit_entry_tx(&self, tx: &mut Transaction, tenant_id: i32, entry: &AuditEntry) -> anyhow::Result<AuditEntry> {
+        let state = &mut tx.memory()?.state;
+        if entry.subject.chars().count() > MAX_VARCHAR {
+            return Err(anyhow!("value too long for type character varying(100)"));
+        }
+        state.audit_seq += 1;
+        let recorded = AuditEntry { id: Some(state.audit_seq), created_at: Utc::now(), ..entry.clone() };
+        state.audit_entries.insert(state.audit_seq, (tenant_id, recorded.clone()));
+        Ok(recorded)
+    }
+
+    async fn get_audit_entries_by_employee_id(&self, tenant_id: i32, employee_id: i32) -> anyhow::Result<Vec<AuditEntry>> {
+        let state = self.db.lock();
+        Ok(state
+            .audit_entries
+            .values()
+            .filter(|(t, entry)| *t == tenant_id && entry.employee_id == Some(employee_id))
+            .map(|(_, entry)| entry.clone())
+            .collect())
+    }
+}
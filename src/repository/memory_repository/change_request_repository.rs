@@ -0,0 +1,144 @@
+use crate::entity::change_request::{Approval, ApprovalStep, ChangeRequest, ChangeRequestStatus, Comment, Decision};
+use crate::repository::change_request_repository::ChangeRequestStore;
+use crate::repository::unit_of_work::Transaction;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::Utc;
+
+use super::{MemoryDatabase, MemoryState, MAX_VARCHAR};
+
+/// In-memory implementation of ChangeRequestStore, see MemoryDatabase
+/// Only statuses the schema allows are stored, each approval step is decided once
+#[derive(Clone)]
+pub struct MemoryChangeRequestRepository {
+    pub(super) db: MemoryDatabase,
+}
+
+// Approval steps of a change request of the tenant, empty for other tenants
+fn approvals_of(state: &MemoryState, tenant_id: i32, change_request_id: i32) -> Vec<Approval> {
+    if state.change_request(tenant_id, change_request_id).is_none() {
+        return Vec::new();
+    }
+    state.change_request_approvals.get(&change_request_id).cloned().unwrap_or_default()
+}
+
+#[async_trait]
+impl ChangeRequestStore for MemoryChangeRequestRepository {
+    async fn create_change_request_tx(&self, tx: &mut Transaction, tenant_id: i32, request: &ChangeRequest, steps: &[ApprovalStep]) -> anyhow::Result<ChangeRequest> {
+        let state = &mut tx.memory()?.state;
+        state.check_change_request(request)?;
+        state.change_request_seq += 1;
+        let id = state.change_request_seq;
+        let created = ChangeRequest { id: Some(id), created_at: Utc::now(), ..request.clone() };
+        state.change_requests.insert(id, (tenant_id, created.clone()));
+        let mut approvals: Vec<Approval> = Vec::new();
+        for step in steps {
+            let approval = Approval::new(id, *step);
+            if approvals.iter().any(|a| a.step == approval.step) {
+                return Err(anyhow!("duplicate key value violates unique constraint \"change_request_approvals_pkey\""));
+            }
+            approvals.push(approval);
+        }
+        state.change_request_approvals.insert(id, approvals);
+        Ok(created)
+    }
+
+    async fn get_change_request_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<ChangeRequest>> {
+        Ok(self.db.lock().change_request(tenant_id, id))
+    }
+
+    async fn get_change_request_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<ChangeRequest>> {
+        Ok(tx.memory()?.state.change_request(tenant_id, id))
+    }
+
+    async fn get_change_requests(&self, tenant_id: i32, status: Option<ChangeRequestStatus>) -> anyhow::Result<Vec<ChangeRequest>> {
+        let state = self.db.lock();
+        Ok(state.change_requests.values()
+            .filter(|(t, r)| *t == tenant_id && status.is_none_or(|s| r.status == s))
+            .map(|(_, r)| r.clone())
+            .collect())
+    }
+
+    async fn update_change_request_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, request: &ChangeRequest) -> anyhow::Result<ChangeRequest> {
+        let state = &mut tx.memory()?.state;
+        let Some(existing) = state.change_request(tenant_id, id) else {
+            return Err(sqlx::Error::RowNotFound.into());
+        };
+        let updated = ChangeRequest {
+            status: request.status,
+            resolved_at: request.resolved_at,
+            failure_reason: request.failure_reason.clone(),
+            ..existing
+        };
+        state.check_change_request(&updated)?;
+        state.change_requests.insert(id, (tenant_id, updated.clone()));
+        Ok(updated)
+    }
+
+    async fn get_approvals(&self, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Approval>> {
+        Ok(approvals_of(&self.db.lock(), tenant_id, change_request_id))
+    }
+
+    async fn get_approvals_tx(&self, tx: &mut Transaction, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Approval>> {
+        Ok(approvals_of(&tx.memory()?.state, tenant_id, change_request_id))
+    }
+
+    async fn decide_approval_tx(&self, tx: &mut Transaction, tenant_id: i32, change_request_id: i32, step: &str, decision: Decision, decided_by: &str) -> anyhow::Result<u64> {
+        let state = &mut tx.memory()?.state;
+        if decided_by.chars().count() > MAX_VARCHAR {
+            return Err(anyhow!("value too long for type character varying(100)"));
+        }
+        if state.change_request(tenant_id, change_request_id).is_none() {
+            return Ok(0);
+        }
+        let undecided = state.change_request_approvals
+            .get_mut(&change_request_id)
+            .and_then(|approvals| approvals.iter_mut().find(|a| a.step == step && a.decision.is_none()));
+        let Some(approval) = undecided else {
+            return Ok(0);
+        };
+        approval.decision = Some(decision);
+        approval.decided_by = Some(decided_by.to_string());
+        approval.decided_at = Some(Utc::now());
+        Ok(1)
+    }
+
+    async fn create_comment_tx(&self, tx: &mut Transaction, tenant_id: i32, comment: &Comment) -> anyhow::Result<Comment> {
+        let state = &mut tx.memory()?.state;
+        if comment.author.chars().count() > MAX_VARCHAR {
+            return Err(anyhow!("value too long for type character varying(100)"));
+        }
+        if state.change_request(tenant_id, comment.change_request_id).is_none() {
+            return Err(anyhow!("insert or update on table \"change_request_comments\" violates foreign key constraint \"change_request_comments_change_request_fkey\""));
+        }
+        state.comment_seq += 1;
+        let created = Comment { id: Some(state.comment_seq), created_at: Utc::now(), ..comment.clone() };
+        state.change_request_comments.insert(state.comment_seq, (tenant_id, created.clone()));
+        Ok(created)
+    }
+
+    async fn get_comments(&self, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Comment>> {
+        let state = self.db.lock();
+        Ok(state.change_request_comments.values()
+            .filter(|(t, c)| *t == tenant_id && c.change_request_id == change_request_id)
+            .map(|(_, c)| c.clone())
+            .collect())
+    }
+}
+
+impl MemoryState {
+    // CHECK and length constraints of change_requests, expired is never stored
+    pub(super) fn check_change_request(&self, request: &ChangeRequest) -> anyhow::Result<()> {
+        if request.requested_by.chars().count() > MAX_VARCHAR {
+            return Err(anyhow!("value too long for type character varying(100)"));
+        }
+        if request.status == ChangeRequestStatus::Expired {
+            return Err(anyhow!("new row for relation \"change_requests\" violates check constraint \"change_requests_status_check\""));
+        }
+        Ok(())
+    }
+
+    pub(super) fn change_request(&self, tenant_id: i32, id: i32) -> Option<ChangeRequest> {
+        self.change_requests.get(&id).filter(|(t, _)| *t == tenant_id).map(|(_, r)| r.clone())
+    }
+}
@@ -0,0 +1,95 @@
+use crate::entity::department::Department;
+use crate::repository::department_repository::DepartmentStore;
+use crate::repository::unit_of_work::Transaction;
+use anyhow::anyhow;
+use async_trait::async_trait;
+
+use super::{MemoryDatabase, MemoryState, MAX_VARCHAR};
+
+/// In-memory implementation of DepartmentStore, see MemoryDatabase
+/// Department names are unique per tenant, a department with employees cannot be deleted
+#[derive(Clone)]
+pub struct MemoryDepartmentRepository {
+    pub(super) db: MemoryDatabase,
+}
+
+#[async_trait]
+impl DepartmentStore for MemoryDepartmentRepository {
+    async fn create_department(&self, tenant_id: i32, department: &Department) -> anyhow::Result<Department> {
+        let mut state = self.db.write();
+        state.check_department(tenant_id, None, department)?;
+        state.department_seq += 1;
+        let id = state.department_seq;
+        let created = Department { id: Some(id), ..department.clone() };
+        state.departments.insert(id, (tenant_id, created.clone()));
+        Ok(created)
+    }
+
+    async fn get_department_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Department>> {
+        Ok(self.db.lock().department(tenant_id, id))
+    }
+
+    async fn get_department_by_name(&self, tenant_id: i32, name: &str) -> anyhow::Result<Option<Department>> {
+        let state = self.db.lock();
+        Ok(state.departments.values().find(|(t, d)| *t == tenant_id && d.name == name).map(|(_, d)| d.clone()))
+    }
+
+    async fn get_all_departments(&self, tenant_id: i32) -> anyhow::Result<Vec<Department>> {
+        let state = self.db.lock();
+        Ok(state.departments.values().filter(|(t, _)| *t == tenant_id).map(|(_, d)| d.clone()).collect())
+    }
+
+    async fn update_department_by_id(&self, tenant_id: i32, id: i32, department: &Department) -> anyhow::Result<Department> {
+        let mut state = self.db.write();
+        if state.department(tenant_id, id).is_none() {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        state.check_department(tenant_id, Some(id), department)?;
+        let updated = Department { id: Some(id), ..department.clone() };
+        state.departments.insert(id, (tenant_id, updated.clone()));
+        Ok(updated)
+    }
+
+    async fn delete_department(&self, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        let mut state = self.db.write();
+        if state.department(tenant_id, id).is_none() {
+            return Ok(0);
+        }
+        if state.employees.values().any(|(_, e)| e.department_id == Some(id)) {
+            return Err(anyhow!(
+                "update or delete on table \"departments\" violates foreign key constraint \"employees_tenant_id_department_id_fkey\" on table \"employees\""
+            ));
+        }
+        state.departments.remove(&id);
+        Ok(1)
+    }
+
+    async fn employee_nr_by_department_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<i64> {
+        let state = self.db.lock();
+        Ok(state.employees_of(tenant_id).filter(|e| e.department_id == Some(id)).count() as i64)
+    }
+
+    async fn get_department_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Department>> {
+        Ok(tx.memory()?.state.department(tenant_id, id))
+    }
+}
+
+impl MemoryState {
+    // Length and UNIQUE (tenant_id, name) constraints of departments
+    pub(super) fn check_department(&self, tenant_id: i32, id: Option<i32>, department: &Department) -> anyhow::Result<()> {
+        if department.name.chars().count() > MAX_VARCHAR {
+            return Err(anyhow!("value too long for type character varying(100)"));
+        }
+        let taken = self.departments.iter().any(|(other_id, (other_tenant, other))| {
+            *other_tenant == tenant_id && other.name == department.name && Some(*other_id) != id
+        });
+        if taken {
+            return Err(anyhow!("duplicate key value violates unique constraint \"departments_tenant_id_name_key\""));
+        }
+        Ok(())
+    }
+
+    pub(super) fn department(&self, tenant_id: i32, id: i32) -> Option<Department> {
+        self.departments.get(&id).filter(|(t, _)| *t == tenant_id).map(|(_, d)| d.clone())
+    }
+}
@@ -0,0 +1,376 @@
+use crate::dto::export_dto::EmployeeExportRow;
+use crate::entity::archived_employee::ArchivedEmployee;
+use crate::entity::employee::{peak_seats, Employee};
+use crate::entity::transfer::TransferStatus;
+use crate::repository::employee_repository::{EmployeeStore, MAX_HIERARCHY_DEPTH};
+use crate::repository::unit_of_work::Transaction;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use futures::stream::{self, BoxStream, StreamExt};
+
+use super::{MemoryDatabase, MemoryState, MAX_VARCHAR};
+
+/// In-memory implementation of EmployeeStore, see MemoryDatabase
+/// Employees reference an office, department and manager of their own tenant, never manage themselves
+/// and are not terminated before their hire date. Removing one unsets it as manager and merged_into
+#[derive(Clone)]
+pub struct MemoryEmployeeRepository {
+    pub(super) db: MemoryDatabase,
+}
+
+#[async_trait]
+impl EmployeeStore for MemoryEmployeeRepository {
+    async fn create_employee(&self, tenant_id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        let mut state = self.db.write();
+        state.check_employee(tenant_id, employee)?;
+        Ok(state.insert_employee(tenant_id, employee))
+    }
+
+    async fn create_employees(&self, tenant_id: i32, employees: &[Employee]) -> anyhow::Result<Vec<Employee>> {
+        let mut state = self.db.write();
+        // all rows are checked before the first insert, like a rolled back transaction
+        for employee in employees {
+            state.check_employee(tenant_id, employee)?;
+        }
+        Ok(employees.iter().map(|e| state.insert_employee(tenant_id, e)).collect())
+    }
+
+    async fn current_employee_nr_by_office_id(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<i64> {
+        let today = Utc::now().date_naive();
+        Ok(self.db.lock().seated_nr(tenant_id, office_id, today, Some(today)))
+    }
+
+    async fn seated_employee_nr_by_office_id(&self, tenant_id: i32, office_id: i32, from: NaiveDate, until: Option<NaiveDate>) -> anyhow::Result<i64> {
+        Ok(self.db.lock().seated_nr(tenant_id, office_id, from, until))
+    }
+
+    async fn get_employee_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Employee>> {
+        Ok(self.db.lock().employee(tenant_id, id))
+    }
+
+    async fn get_employees_by_office_id(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<Employee>> {
+        let state = self.db.lock();
+        Ok(state.employees_of(tenant_id).filter(|e| e.office_id == office_id).cloned().collect())
+    }
+
+    async fn get_employees_by_office_ids(&self, tenant_id: i32, office_ids: &[i32]) -> anyhow::Result<Vec<Employee>> {
+        let state = self.db.lock();
+        Ok(state.employees_of(tenant_id).filter(|e| office_ids.contains(&e.office_id)).cloned().collect())
+    }
+
+    async fn get_all_employees(&self, tenant_id: i32) -> anyhow::Result<Vec<Employee>> {
+        let state = self.db.lock();
+        Ok(state.employees_of(tenant_id).cloned().collect())
+    }
+
+    async fn get_employees_by_birth_date(&self, tenant_id: i32, birth_date: NaiveDate) -> anyhow::Result<Vec<Employee>> {
+        let state = self.db.lock();
+        Ok(state.employees_of(tenant_id).filter(|employee| employee.birth_date == birth_date).cloned().collect())
+    }
+
+    async fn get_direct_reports(&self, tenant_id: i32, manager_id: i32) -> anyhow::Result<Vec<Employee>> {
+        let state = self.db.lock();
+        Ok(state.employees_of(tenant_id).filter(|e| e.manager_id == Some(manager_id)).cloned().collect())
+    }
+
+    async fn get_reporting_chain(&self, tenant_id: i32, id: i32) -> anyhow::Result<Vec<Employee>> {
+        Ok(self.db.lock().reporting_chain(tenant_id, id))
+    }
+
+    async fn get_subtree(&self, tenant_id: i32, manager_id: i32) -> anyhow::Result<Vec<Employee>> {
+        let state = self.db.lock();
+        // level by level, each level ordered by ID like the recursive query
+        let mut subtree = Vec::new();
+        let mut level: Vec<i32> = vec![manager_id];
+        for _ in 0..MAX_HIERARCHY_DEPTH {
+            let reports: Vec<Employee> = state
+                .employees_of(tenant_id)
+                .filter(|e| e.manager_id.is_some_and(|id| level.contains(&id)))
+                .cloned()
+                .collect();
+            if reports.is_empty() {
+                break;
+            }
+            level = reports.iter().filter_map(|e| e.id).collect();
+            subtree.extend(reports);
+        }
+        Ok(subtree)
+    }
+
+    async fn update_employee_by_id(&self, tenant_id: i32, id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        self.db.write().update_employee(tenant_id, id, employee)
+    }
+
+    async fn delete_employee(&self, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        self.db.write().delete_employee(tenant_id, id)
+    }
+
+    async fn create_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        let state = &mut tx.memory()?.state;
+        state.check_employee(tenant_id, employee)?;
+        Ok(state.insert_employee(tenant_id, employee))
+    }
+
+    async fn delete_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        tx.memory()?.state.delete_employee(tenant_id, id)
+    }
+
+    async fn current_employee_nr_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<i64> {
+        let today = Utc::now().date_naive();
+        Ok(tx.memory()?.state.seated_nr(tenant_id, office_id, today, Some(today)))
+    }
+
+    async fn seated_employee_nr_by_office_id_tx(
+        &self,
+        tx: &mut Transaction,
+        tenant_id: i32,
+        office_id: i32,
+        from: NaiveDate,
+        until: Option<NaiveDate>,
+    ) -> anyhow::Result<i64> {
+        Ok(tx.memory()?.state.seated_nr(tenant_id, office_id, from, until))
+    }
+
+    async fn get_employee_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Employee>> {
+        Ok(tx.memory()?.state.employee(tenant_id, id))
+    }
+
+    async fn get_employees_by_birth_date_tx(&self, tx: &mut Transaction, tenant_id: i32, birth_date: NaiveDate) -> anyhow::Result<Vec<Employee>> {
+        let state = &tx.memory()?.state;
+        Ok(state.employees_of(tenant_id).filter(|employee| employee.birth_date == birth_date).cloned().collect())
+    }
+
+    async fn get_employees_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<Employee>> {
+        let state = &tx.memory()?.state;
+        Ok(state.employees_of(tenant_id).filter(|e| e.office_id == office_id).cloned().collect())
+    }
+
+    async fn update_employee_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        tx.memory()?.state.update_employee(tenant_id, id, employee)
+    }
+
+    async fn get_reporting_chain_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Vec<Employee>> {
+        Ok(tx.memory()?.state.reporting_chain(tenant_id, id))
+    }
+
+    async fn reassign_employees_tx(&self, tx: &mut Transaction, tenant_id: i32, from_office_id: i32, to_office_id: i32) -> anyhow::Result<u64> {
+        tx.memory()?.state.reassign_employees(tenant_id, from_office_id, to_office_id)
+    }
+
+    async fn get_archived_employee_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<ArchivedEmployee>> {
+        let state = self.db.lock();
+        Ok(state.archived_employees.get(&id).filter(|(t, _)| *t == tenant_id).map(|(_, a)| a.clone()))
+    }
+
+    async fn merge_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, source_id: i32, survivor_id: i32, survivor_office_id: i32) -> anyhow::Result<u64> {
+        Ok(tx.memory()?.state.merge_employee(tenant_id, source_id, survivor_id, survivor_office_id))
+    }
+
+    fn stream_employees(
+        &self,
+        tenant_id: i32,
+        office_id: Option<i32>,
+        office_ids: Option<Vec<i32>>,
+        include_office: bool,
+    ) -> BoxStream<'_, anyhow::Result<EmployeeExportRow>> {
+        let state = self.db.lock();
+        let rows: Vec<_> = state
+            .employees_of(tenant_id)
+            .filter(|e| office_id.is_none_or(|id| e.office_id == id))
+            .filter(|e| office_ids.as_ref().is_none_or(|ids| ids.contains(&e.office_id)))
+            .map(|e| {
+                Ok(EmployeeExportRow {
+                    id: e.id.unwrap_or_default(),
+                    first_name: e.first_name.clone(),
+                    last_name: e.last_name.clone(),
+                    birth_date: e.birth_date,
+                    office_id: e.office_id,
+                    office_name: include_office
+                        .then(|| state.offices.get(&e.office_id).map(|(_, o)| o.name.clone()))
+                        .flatten(),
+                })
+            })
+            .collect();
+        stream::iter(rows).boxed()
+    }
+}
+
+impl MemoryState {
+    // CHECK, length and FOREIGN KEY (tenant_id, office_id / department_id / manager_id) constraints of employees
+    pub(super) fn check_employee(&self, tenant_id: i32, employee: &Employee) -> anyhow::Result<()> {
+        if employee.first_name.chars().count() > MAX_VARCHAR || employee.last_name.chars().count() > MAX_VARCHAR {
+            return Err(anyhow!("value too long for type character varying(100)"));
+        }
+        if employee.birth_date >= Utc::now().date_naive() {
+            return Err(anyhow!("new row for relation \"employees\" violates check constraint \"employees_birth_date_check\""));
+        }
+        if !self.office_in_tenant(tenant_id, employee.office_id) {
+            return Err(anyhow!(
+                "insert or update on table \"employees\" violates foreign key constraint \"employees_tenant_id_office_id_fkey\""
+            ));
+        }
+        if employee.department_id.is_some_and(|id| !matches!(self.departments.get(&id), Some((t, _)) if *t == tenant_id)) {
+            return Err(anyhow!(
+                "insert or update on table \"employees\" violates foreign key constraint \"employees_tenant_id_department_id_fkey\""
+            ));
+        }
+        if employee.termination_date.is_some_and(|end| end < employee.hire_date) {
+            return Err(anyhow!("new row for relation \"employees\" violates check constraint \"employees_termination_date_check\""));
+        }
+        if employee.manager_id.is_some() && employee.manager_id == employee.id {
+            return Err(anyhow!("new row for relation \"employees\" violates check constraint \"employees_manager_id_check\""));
+        }
+        if employee.manager_id.is_some_and(|id| self.employee(tenant_id, id).is_none()) {
+            return Err(anyhow!(
+                "insert or update on table \"employees\" violates foreign key constraint \"employees_tenant_id_manager_id_fkey\""
+            ));
+        }
+        Ok(())
+    }
+
+    pub(super) fn employees_of(&self, tenant_id: i32) -> impl Iterator<Item = &Employee> {
+        self.employees.values().filter(move |(t, _)| *t == tenant_id).map(|(_, e)| e)
+    }
+
+    pub(super) fn insert_employee(&mut self, tenant_id: i32, employee: &Employee) -> Employee {
+        self.employee_seq += 1;
+        let created = Employee { id: Some(self.employee_seq), ..employee.clone() };
+        self.employees.insert(self.employee_seq, (tenant_id, created.clone()));
+        created
+    }
+
+    pub(super) fn employee(&self, tenant_id: i32, id: i32) -> Option<Employee> {
+        self.employees.get(&id).filter(|(t, _)| *t == tenant_id).map(|(_, e)| e.clone())
+    }
+
+    pub(super) fn seated_nr(&self, tenant_id: i32, office_id: i32, from: NaiveDate, until: Option<NaiveDate>) -> i64 {
+        let periods: Vec<_> = self.employees_of(tenant_id)
+            .filter(|e| e.office_id == office_id)
+            .map(|e| (e.hire_date, e.termination_date))
+            .collect();
+        peak_seats(&periods, from, until)
+    }
+
+    pub(super) fn update_employee(&mut self, tenant_id: i32, id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        if !matches!(self.employees.get(&id), Some((t, _)) if *t == tenant_id) {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        let updated = Employee { id: Some(id), ..employee.clone() };
+        self.check_employee(tenant_id, &updated)?;
+        self.employees.insert(id, (tenant_id, updated.clone()));
+        Ok(updated)
+    }
+
+    pub(super) fn reassign_employees(&mut self, tenant_id: i32, from_office_id: i32, to_office_id: i32) -> anyhow::Result<u64> {
+        let moved: Vec<Employee> = self
+            .employees_of(tenant_id)
+            .filter(|e| e.office_id == from_office_id)
+            .map(|e| Employee { office_id: to_office_id, ..e.clone() })
+            .collect();
+        for employee in &moved {
+            self.check_employee(tenant_id, employee)?;
+        }
+        for employee in &moved {
+            let id = employee.id.unwrap_or_default();
+            self.employees.insert(id, (tenant_id, employee.clone()));
+        }
+        Ok(moved.len() as u64)
+    }
+
+    // Removes an employee, ON DELETE SET NULL of archived employees merged into it and of its reports,
+    // ON DELETE CASCADE of its transfers and its room
+    pub(super) fn delete_employee(&mut self, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        if !matches!(self.employees.get(&id), Some((t, _)) if *t == tenant_id) {
+            return Ok(0);
+        }
+        self.remove_employee(id);
+        Ok(1)
+    }
+
+    pub(super) fn remove_employee(&mut self, id: i32) {
+        self.employees.remove(&id);
+        self.transfers.retain(|_, (_, t)| t.employee_id != id);
+        self.location_assignments.remove(&id);
+        for (_, archived) in self.archived_employees.values_mut() {
+            if archived.merged_into == Some(id) {
+                archived.merged_into = None;
+            }
+        }
+        for (_, employee) in self.employees.values_mut() {
+            if employee.manager_id == Some(id) {
+                employee.manager_id = None;
+            }
+        }
+    }
+
+    // Managers above an employee, nearest first, bounded like the recursive queries
+    pub(super) fn reporting_chain(&self, tenant_id: i32, id: i32) -> Vec<Employee> {
+        let mut chain = Vec::new();
+        let mut next = self.employee(tenant_id, id).and_then(|e| e.manager_id);
+        while let Some(manager) = next.and_then(|manager_id| self.employee(tenant_id, manager_id)) {
+            if chain.len() >= MAX_HIERARCHY_DEPTH as usize {
+                break;
+            }
+            next = manager.manager_id;
+            chain.push(manager);
+        }
+        chain
+    }
+
+    pub(super) fn merge_employee(&mut self, tenant_id: i32, source_id: i32, survivor_id: i32, survivor_office_id: i32) -> u64 {
+        for (entry_tenant, entry) in self.audit_entries.values_mut() {
+            if *entry_tenant == tenant_id && entry.employee_id == Some(source_id) {
+                entry.employee_id = Some(survivor_id);
+            }
+        }
+        for (archived_tenant, archived) in self.archived_employees.values_mut() {
+            if *archived_tenant == tenant_id && archived.merged_into == Some(source_id) {
+                archived.merged_into = Some(survivor_id);
+            }
+        }
+        let Some(source) = self.employee(tenant_id, source_id) else {
+            return 0;
+        };
+        let archived = ArchivedEmployee {
+            id: source_id,
+            first_name: source.first_name,
+            last_name: source.last_name,
+            birth_date: source.birth_date,
+            office_id: source.office_id,
+            merged_into: Some(survivor_id),
+            archived_at: Utc::now(),
+        };
+        for (employee_tenant, employee) in self.employees.values_mut() {
+            if *employee_tenant == tenant_id && employee.manager_id == Some(source_id) && employee.id != Some(survivor_id) {
+                employee.manager_id = Some(survivor_id);
+            }
+        }
+        for (transfer_tenant, transfer) in self.transfers.values_mut() {
+            if *transfer_tenant == tenant_id && transfer.employee_id == source_id {
+                if transfer.status == TransferStatus::Pending {
+                    transfer.status = TransferStatus::Cancelled;
+                }
+                transfer.employee_id = survivor_id;
+            }
+        }
+        for (entry_tenant, entry) in self.waitlist_entries.values_mut() {
+            if *entry_tenant == tenant_id {
+                if entry.employee_id == Some(source_id) {
+                    entry.employee_id = Some(survivor_id);
+                }
+                if entry.manager_id == Some(source_id) {
+                    entry.manager_id = Some(survivor_id);
+                }
+            }
+        }
+        let room_id = self.location_assignments.get(&source_id).map(|(_, location_id)| *location_id);
+        let same_office = room_id.and_then(|id| self.locations.get(&id)).is_some_and(|(_, room)| room.office_id == Some(survivor_office_id));
+        if let Some(room_id) = room_id.filter(|_| same_office && !self.location_assignments.contains_key(&survivor_id)) {
+            self.location_assignments.insert(survivor_id, (tenant_id, room_id));
+        }
+        self.archived_employees.insert(source_id, (tenant_id, archived));
+        self.remove_employee(source_id);
+        1
+    }
+}
@@ -0,0 +1,76 @@
+use crate::entity::idempotency_record::{IdempotencyKey, IdempotencyRecord};
+use crate::repository::idempotency_repository::IdempotencyStore;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use super::MemoryDatabase;
+
+/// In-memory implementation of IdempotencyStore, see MemoryDatabase
+#[derive(Clone)]
+pub struct MemoryIdempotencyRepository {
+    pub(super) db: MemoryDatabase,
+}
+
+// Primary key of idempotency_keys
+fn record_key(key: &IdempotencyKey) -> (i32, String, String, String) {
+    (key.tenant_id, key.subject.clone(), key.route.clone(), key.key.clone())
+}
+
+#[async_trait]
+impl IdempotencyStore for MemoryIdempotencyRepository {
+    async fn reserve_idempotency_key(
+        &self,
+        key: &IdempotencyKey,
+        request_hash: &str,
+        expired_before: DateTime<Utc>,
+    ) -> anyhow::Result<bool> {
+        let mut state = self.db.write();
+        if let Some(existing) = state.idempotency_keys.get(&record_key(key))
+            && existing.created_at >= expired_before
+        {
+            return Ok(false);
+        }
+        let record = IdempotencyRecord {
+            request_hash: request_hash.to_string(),
+            status_code: None,
+            content_type: None,
+            response_body: None,
+            created_at: Utc::now(),
+        };
+        state.idempotency_keys.insert(record_key(key), record);
+        Ok(true)
+    }
+
+    async fn get_idempotency_record(&self, key: &IdempotencyKey) -> anyhow::Result<Option<IdempotencyRecord>> {
+        let state = self.db.lock();
+        Ok(state.idempotency_keys.get(&record_key(key)).cloned())
+    }
+
+    async fn complete_idempotency_key(
+        &self,
+        key: &IdempotencyKey,
+        status_code: i32,
+        content_type: Option<&str>,
+        response_body: &[u8],
+    ) -> anyhow::Result<()> {
+        let mut state = self.db.write();
+        if let Some(record) = state.idempotency_keys.get_mut(&record_key(key)) {
+            record.status_code = Some(status_code);
+            record.content_type = content_type.map(str::to_string);
+            record.response_body = Some(response_body.to_vec());
+        }
+        Ok(())
+    }
+
+    async fn delete_idempotency_key(&self, key: &IdempotencyKey) -> anyhow::Result<u64> {
+        let mut state = self.db.write();
+        Ok(state.idempotency_keys.remove(&record_key(key)).is_some() as u64)
+    }
+
+    async fn delete_expired_idempotency_keys(&self, expired_before: DateTime<Utc>) -> anyhow::Result<u64> {
+        let mut state = self.db.write();
+        let before = state.idempotency_keys.len();
+        state.idempotency_keys.retain(|_, record| record.created_at >= expired_before);
+        Ok((before - state.idempotency_keys.len()) as u64)
+    }
+}
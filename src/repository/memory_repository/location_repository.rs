@@ -0,0 +1,163 @@
+use crate::entity::employee::Employee;
+use crate::entity::location::{Location, LocationKind};
+use crate::repository::location_repository::LocationStore;
+use crate::repository::unit_of_work::Transaction;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+use super::{MemoryDatabase, MemoryState, MAX_VARCHAR};
+
+/// In-memory implementation of LocationStore, see MemoryDatabase
+/// Locations hang under a parent of their tenant, one site per office, and a location with children cannot be deleted.
+/// Deleting a location removes the room assignments in it
+#[derive(Clone)]
+pub struct MemoryLocationRepository {
+    pub(super) db: MemoryDatabase,
+}
+
+#[async_trait]
+impl LocationStore for MemoryLocationRepository {
+    async fn create_location_tx(&self, tx: &mut Transaction, tenant_id: i32, location: &Location) -> anyhow::Result<Location> {
+        let state = &mut tx.memory()?.state;
+        state.check_location(tenant_id, None, location)?;
+        state.location_seq += 1;
+        let id = state.location_seq;
+        state.locations.insert(id, (tenant_id, Location { id: Some(id), ..location.clone() }));
+        state.location(tenant_id, id).ok_or_else(|| sqlx::Error::RowNotFound.into())
+    }
+
+    async fn get_locations(&self, tenant_id: i32) -> anyhow::Result<Vec<Location>> {
+        Ok(self.db.lock().locations_of(tenant_id))
+    }
+
+    async fn get_locations_tx(&self, tx: &mut Transaction, tenant_id: i32) -> anyhow::Result<Vec<Location>> {
+        Ok(tx.memory()?.state.locations_of(tenant_id))
+    }
+
+    async fn update_location_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, location: &Location) -> anyhow::Result<Location> {
+        let state = &mut tx.memory()?.state;
+        let Some((_, existing)) = state.locations.get(&id).filter(|(t, _)| *t == tenant_id) else {
+            return Err(sqlx::Error::RowNotFound.into());
+        };
+        let capacity = if existing.kind == LocationKind::Site { None } else { location.capacity };
+        let updated = Location { name: location.name.clone(), capacity, parent_id: location.parent_id, ..existing.clone() };
+        state.check_location(tenant_id, Some(id), &updated)?;
+        state.locations.insert(id, (tenant_id, updated));
+        state.location(tenant_id, id).ok_or_else(|| sqlx::Error::RowNotFound.into())
+    }
+
+    async fn delete_location_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        let state = &mut tx.memory()?.state;
+        if !matches!(state.locations.get(&id), Some((t, _)) if *t == tenant_id) {
+            return Ok(0);
+        }
+        if state.locations.values().any(|(_, l)| l.parent_id == Some(id)) {
+            return Err(anyhow!(
+                "update or delete on table \"locations\" violates foreign key constraint \"locations_tenant_id_parent_id_fkey\" on table \"locations\""
+            ));
+        }
+        state.locations.remove(&id);
+        state.location_assignments.retain(|_, (_, location_id)| *location_id != id); // ON DELETE CASCADE
+        Ok(1)
+    }
+
+    async fn get_occupants(&self, tenant_id: i32, from: NaiveDate) -> anyhow::Result<Vec<(i32, i64)>> {
+        Ok(self.db.lock().occupants_of(tenant_id, from))
+    }
+
+    async fn get_occupants_tx(&self, tx: &mut Transaction, tenant_id: i32, from: NaiveDate) -> anyhow::Result<Vec<(i32, i64)>> {
+        Ok(tx.memory()?.state.occupants_of(tenant_id, from))
+    }
+
+    async fn get_room_id_tx(&self, tx: &mut Transaction, tenant_id: i32, employee_id: i32) -> anyhow::Result<Option<i32>> {
+        let state = &tx.memory()?.state;
+        Ok(state.employee(tenant_id, employee_id).and_then(|employee| state.room_of(tenant_id, &employee)))
+    }
+
+    async fn assign_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, employee_id: i32, location_id: i32) -> anyhow::Result<()> {
+        let state = &mut tx.memory()?.state;
+        if state.employee(tenant_id, employee_id).is_none() {
+            return Err(anyhow!("insert or update on table \"location_assignments\" violates foreign key constraint \"location_assignments_tenant_id_employee_id_fkey\""));
+        }
+        if !matches!(state.locations.get(&location_id), Some((t, _)) if *t == tenant_id) {
+            return Err(anyhow!("insert or update on table \"location_assignments\" violates foreign key constraint \"location_assignments_tenant_id_location_id_fkey\""));
+        }
+        state.location_assignments.insert(employee_id, (tenant_id, location_id));
+        Ok(())
+    }
+
+    async fn unassign_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, employee_id: i32, location_id: i32) -> anyhow::Result<u64> {
+        let state = &mut tx.memory()?.state;
+        if state.location_assignments.get(&employee_id) != Some(&(tenant_id, location_id)) {
+            return Ok(0);
+        }
+        state.location_assignments.remove(&employee_id);
+        Ok(1)
+    }
+}
+
+impl MemoryState {
+    // CHECK, length, FOREIGN KEY and UNIQUE site constraints of locations
+    pub(super) fn check_location(&self, tenant_id: i32, id: Option<i32>, location: &Location) -> anyhow::Result<()> {
+        if location.name.chars().count() > MAX_VARCHAR {
+            return Err(anyhow!("value too long for type character varying(100)"));
+        }
+        if location.capacity.is_some_and(|capacity| capacity <= 0 || location.kind == LocationKind::Site) {
+            return Err(anyhow!("new row for relation \"locations\" violates check constraint \"locations_capacity_check\""));
+        }
+        if (location.kind == LocationKind::Region) != location.office_id.is_none() {
+            return Err(anyhow!("new row for relation \"locations\" violates check constraint \"locations_office_id_check\""));
+        }
+        if location.parent_id.is_some_and(|parent_id| !matches!(self.locations.get(&parent_id), Some((t, _)) if *t == tenant_id)) {
+            return Err(anyhow!("insert or update on table \"locations\" violates foreign key constraint \"locations_tenant_id_parent_id_fkey\""));
+        }
+        if location.office_id.is_some_and(|office_id| !self.office_in_tenant(tenant_id, office_id)) {
+            return Err(anyhow!("insert or update on table \"locations\" violates foreign key constraint \"locations_tenant_id_office_id_fkey\""));
+        }
+        let duplicate_site = location.kind == LocationKind::Site
+            && self.locations.iter().any(|(other, (_, l))| Some(*other) != id && l.kind == LocationKind::Site && l.office_id == location.office_id);
+        if duplicate_site {
+            return Err(anyhow!("duplicate key value violates unique constraint \"locations_office_id_site_key\""));
+        }
+        Ok(())
+    }
+
+    // Location as read from the database, a site with the max_occupancy of its office as capacity
+    pub(super) fn location(&self, tenant_id: i32, id: i32) -> Option<Location> {
+        let (_, location) = self.locations.get(&id).filter(|(t, _)| *t == tenant_id)?;
+        let mut location = location.clone();
+        if location.kind == LocationKind::Site {
+            location.capacity = location.office_id.and_then(|office_id| self.office(tenant_id, office_id)).map(|o| o.max_occupancy);
+        }
+        Some(location)
+    }
+
+    pub(super) fn locations_of(&self, tenant_id: i32) -> Vec<Location> {
+        self.locations.iter().filter(|(_, (t, _))| *t == tenant_id).filter_map(|(id, _)| self.location(tenant_id, *id)).collect()
+    }
+
+    // Room of an employee while it stays in the office of the room
+    pub(super) fn room_of(&self, tenant_id: i32, employee: &Employee) -> Option<i32> {
+        let (_, location_id) = employee.id.and_then(|id| self.location_assignments.get(&id)).filter(|(t, _)| *t == tenant_id)?;
+        let (_, room) = self.locations.get(location_id)?;
+        (room.office_id == Some(employee.office_id)).then_some(*location_id)
+    }
+
+    // Employees with a seat on or after `from` per room, or per site of their office without one
+    pub(super) fn occupants_of(&self, tenant_id: i32, from: NaiveDate) -> Vec<(i32, i64)> {
+        let mut occupants: BTreeMap<i32, i64> = BTreeMap::new();
+        for employee in self.employees_of(tenant_id).filter(|e| e.termination_date.is_none_or(|date| date >= from)) {
+            let site = || {
+                self.locations.iter()
+                    .find(|(_, (_, l))| l.kind == LocationKind::Site && l.office_id == Some(employee.office_id))
+                    .map(|(id, _)| *id)
+            };
+            if let Some(location_id) = self.room_of(tenant_id, employee).or_else(site) {
+                *occupants.entry(location_id).or_default() += 1;
+            }
+        }
+        occupants.into_iter().collect()
+    }
+}
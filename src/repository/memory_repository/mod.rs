@@ -0,0 +1,227 @@
+use crate::entity::archived_employee::ArchivedEmployee;
+use crate::entity::audit_entry::AuditEntry;
+use crate::entity::change_request::{Approval, ChangeRequest, Comment};
+use crate::entity::department::Department;
+use crate::entity::employee::Employee;
+use crate::entity::idempotency_record::IdempotencyRecord;
+use crate::entity::location::Location;
+use crate::entity::office::Office;
+use crate::entity::transfer::Transfer;
+use crate::entity::waitlist::{WaitlistEntry, WaitlistEvent};
+use crate::repository::health_repository::HealthStore;
+use crate::repository::unit_of_work::{SerializationConflict, Transaction, UnitOfWork};
+use async_trait::async_trait;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+mod audit_repository;
+mod change_request_repository;
+mod department_repository;
+mod employee_repository;
+mod idempotency_repository;
+mod location_repository;
+mod office_manager_repository;
+mod office_repository;
+mod transfer_repository;
+mod waitlist_repository;
+
+pub use audit_repository::MemoryAuditRepository;
+pub use change_request_repository::MemoryChangeRequestRepository;
+pub use department_repository::MemoryDepartmentRepository;
+pub use employee_repository::MemoryEmployeeRepository;
+pub use idempotency_repository::MemoryIdempotencyRepository;
+pub use location_repository::MemoryLocationRepository;
+pub use office_manager_repository::MemoryOfficeManagerRepository;
+pub use office_repository::MemoryOfficeRepository;
+pub use transfer_repository::MemoryTransferRepository;
+pub use waitlist_repository::MemoryWaitlistRepository;
+
+const MAX_VARCHAR: usize = 100;
+
+/// In-memory storage backend
+/// All tables live in one process-local database behind a mutex, the repositories created from it share that state.
+/// Each repository module enforces the constraints of its Postgres tables, so services behave identically on both backends;
+/// office capacity is checked by the services, as with Postgres.
+/// Transactions work on a copy of the whole database that replaces it on commit,
+/// a commit after any other write since begin fails with SerializationConflict.
+/// Meant for tests and local runs, data is gone when the process ends.
+#[derive(Clone, Default)]
+pub struct MemoryDatabase {
+    state: Arc<Mutex<MemoryState>>,
+}
+
+#[derive(Clone, Default)]
+struct MemoryState {
+    offices: BTreeMap<i32, (i32, Office)>, // id -> (tenant_id, office)
+    employees: BTreeMap<i32, (i32, Employee)>, // id -> (tenant_id, employee)
+    office_managers: BTreeSet<(String, i32)>, // (subject, office_id)
+    idempotency_keys: HashMap<(i32, String, String, String), IdempotencyRecord>,
+    archived_employees: BTreeMap<i32, (i32, ArchivedEmployee)>, // id -> (tenant_id, archived employee)
+    audit_entries: BTreeMap<i64, (i32, AuditEntry)>, // id -> (tenant_id, entry)
+    departments: BTreeMap<i32, (i32, Department)>, // id -> (tenant_id, department)
+    transfers: BTreeMap<i32, (i32, Transfer)>, // id -> (tenant_id, transfer)
+    change_requests: BTreeMap<i32, (i32, ChangeRequest)>, // id -> (tenant_id, change request)
+    change_request_approvals: BTreeMap<i32, Vec<Approval>>, // change request id -> steps in order
+    change_request_comments: BTreeMap<i32, (i32, Comment)>, // id -> (tenant_id, comment)
+    waitlist_entries: BTreeMap<i32, (i32, WaitlistEntry)>, // id -> (tenant_id, entry)
+    waitlist_events: BTreeMap<i32, (i32, WaitlistEvent)>, // id -> (tenant_id, event)
+    locations: BTreeMap<i32, (i32, Location)>, // id -> (tenant_id, location)
+    location_assignments: BTreeMap<i32, (i32, i32)>, // employee_id -> (tenant_id, location_id)
+    office_seq: i32,
+    employee_seq: i32,
+    audit_seq: i64,
+    department_seq: i32,
+    transfer_seq: i32,
+    change_request_seq: i32,
+    comment_seq: i32,
+    waitlist_entry_seq: i32,
+    waitlist_event_seq: i32,
+    location_seq: i32,
+    version: u64, // bumped by every write, transactions compare it on commit
+}
+
+impl MemoryDatabase {
+    /// Constructor for an empty MemoryDatabase
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Office repository on this database
+    pub fn office_repository(&self) -> MemoryOfficeRepository {
+        MemoryOfficeRepository { db: self.clone() }
+    }
+
+    /// Employee repository on this database
+    pub fn employee_repository(&self) -> MemoryEmployeeRepository {
+        MemoryEmployeeRepository { db: self.clone() }
+    }
+
+    /// Office manager repository on this database
+    pub fn office_manager_repository(&self) -> MemoryOfficeManagerRepository {
+        MemoryOfficeManagerRepository { db: self.clone() }
+    }
+
+    /// Idempotency repository on this database
+    pub fn idempotency_repository(&self) -> MemoryIdempotencyRepository {
+        MemoryIdempotencyRepository { db: self.clone() }
+    }
+
+    /// Audit repository on this database
+    pub fn audit_repository(&self) -> MemoryAuditRepository {
+        MemoryAuditRepository { db: self.clone() }
+    }
+
+    /// Department repository on this database
+    pub fn department_repository(&self) -> MemoryDepartmentRepository {
+        MemoryDepartmentRepository { db: self.clone() }
+    }
+
+    /// Transfer repository on this database
+    pub fn transfer_repository(&self) -> MemoryTransferRepository {
+        MemoryTransferRepository { db: self.clone() }
+    }
+
+    /// Change request repository on this database
+    pub fn change_request_repository(&self) -> MemoryChangeRequestRepository {
+        MemoryChangeRequestRepository { db: self.clone() }
+    }
+
+    /// Waitlist repository on this database
+    pub fn waitlist_repository(&self) -> MemoryWaitlistRepository {
+        MemoryWaitlistRepository { db: self.clone() }
+    }
+
+    /// Location repository on this database
+    pub fn location_repository(&self) -> MemoryLocationRepository {
+        MemoryLocationRepository { db: self.clone() }
+    }
+
+    /// Unit of work on this database
+    pub fn unit_of_work(&self) -> MemoryUnitOfWork {
+        MemoryUnitOfWork { db: self.clone() }
+    }
+
+    /// Health probe on this database, always reachable
+    pub fn health_repository(&self) -> MemoryHealthRepository {
+        MemoryHealthRepository
+    }
+
+    // A panic while holding the lock cannot leave a table half written, so a poisoned lock is still usable
+    fn lock(&self) -> MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Lock for a write, open transactions on the old version will fail to commit
+    fn write(&self) -> MutexGuard<'_, MemoryState> {
+        let mut state = self.lock();
+        state.version += 1;
+        state
+    }
+}
+
+/// Open in-memory transaction, a private copy of the database until commit
+pub struct MemoryTransaction {
+    db: MemoryDatabase,
+    state: Box<MemoryState>, // boxed, the copy is far larger than the other Transaction variants
+}
+
+impl MemoryTransaction {
+    /// Replaces the database with the working copy unless another write happened since begin
+    pub fn commit(self) -> anyhow::Result<()> {
+        let mut state = self.db.lock();
+        if state.version != self.state.version {
+            return Err(SerializationConflict.into());
+        }
+        *state = *self.state;
+        state.version += 1;
+        Ok(())
+    }
+}
+
+/// In-memory implementation of UnitOfWork, see MemoryDatabase
+#[derive(Clone)]
+pub struct MemoryUnitOfWork {
+    db: MemoryDatabase,
+}
+
+#[async_trait]
+impl UnitOfWork for MemoryUnitOfWork {
+    async fn begin(&self) -> anyhow::Result<Transaction> {
+        let state = Box::new(self.db.lock().clone());
+        Ok(Transaction::Memory(MemoryTransaction { db: self.db.clone(), state }))
+    }
+}
+
+/// In-memory implementation of HealthStore
+#[derive(Clone)]
+pub struct MemoryHealthRepository;
+
+#[async_trait]
+impl HealthStore for MemoryHealthRepository {
+    async fn ping(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::office_repository::OfficeStore;
+    use crate::repository::unit_of_work::is_serialization_failure;
+
+    #[tokio::test]
+    async fn test_commit_after_concurrent_write_conflicts() {
+        let db = MemoryDatabase::new();
+        let offices = db.office_repository();
+        let office = offices.create_office(1, &Office { id: None, name: "Aalborg".into(), max_occupancy: 5, ..Default::default() }).await.unwrap();
+        let id = office.id.unwrap();
+
+        let mut tx = db.unit_of_work().begin().await.unwrap();
+        offices.update_office_by_id_tx(&mut tx, 1, id, &Office { max_occupancy: 6, ..office.clone() }).await.unwrap();
+        offices.update_office_by_id(1, id, &Office { max_occupancy: 7, ..office.clone() }).await.unwrap();
+
+        let conflict = tx.commit().await.unwrap_err();
+        assert!(is_serialization_failure(&conflict));
+        assert_eq!(offices.get_office_by_id(1, id).await.unwrap().unwrap().max_occupancy, 7);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::repository::office_manager_repository::OfficeManagerStore;
+use anyhow::anyhow;
+use async_trait::async_trait;
+
+use super::{MemoryDatabase, MAX_VARCHAR};
+
+/// In-memory implementation of OfficeManagerStore, see MemoryDatabase
+/// Grants only exist for offices of the tenant
+#[derive(Clone)]
+pub struct MemoryOfficeManagerRepository {
+    pub(super) db: MemoryDatabase,
+}
+
+#[async_trait]
+impl OfficeManagerStore for MemoryOfficeManagerRepository {
+    async fn get_office_ids_by_subject(&self, tenant_id: i32, subject: &str) -> anyhow::Result<Vec<i32>> {
+        let state = self.db.lock();
+        let mut office_ids: Vec<i32> = state
+            .office_managers
+            .iter()
+            .filter(|(s, office_id)| s == subject && state.office_in_tenant(tenant_id, *office_id))
+            .map(|(_, office_id)| *office_id)
+            .collect();
+        office_ids.sort();
+        Ok(office_ids)
+    }
+
+    async fn get_subjects_by_office_id(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<String>> {
+        let state = self.db.lock();
+        if !state.office_in_tenant(tenant_id, office_id) {
+            return Ok(vec![]);
+        }
+        // the set is ordered by subject already
+        Ok(state.office_managers.iter().filter(|(_, id)| *id == office_id).map(|(s, _)| s.clone()).collect())
+    }
+
+    async fn add_office_manager(&self, tenant_id: i32, subject: &str, office_id: i32) -> anyhow::Result<()> {
+        let mut state = self.db.write();
+        if subject.chars().count() > MAX_VARCHAR {
+            return Err(anyhow!("value too long for type character varying(100)"));
+        }
+        if state.office_in_tenant(tenant_id, office_id) {
+            state.office_managers.insert((subject.to_string(), office_id));
+        }
+        Ok(())
+    }
+
+    async fn delete_office_manager(&self, tenant_id: i32, subject: &str, office_id: i32) -> anyhow::Result<u64> {
+        let mut state = self.db.write();
+        if !state.office_in_tenant(tenant_id, office_id) {
+            return Ok(0);
+        }
+        Ok(state.office_managers.remove(&(subject.to_string(), office_id)) as u64)
+    }
+}
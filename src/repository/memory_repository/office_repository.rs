@@ -0,0 +1,176 @@
+use crate::dto::export_dto::OfficeExportRow;
+use crate::dto::office_dto::SearchArea;
+use crate::entity::office::Office;
+use crate::repository::office_repository::OfficeStore;
+use crate::repository::unit_of_work::Transaction;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+
+use super::{MemoryDatabase, MemoryState, MAX_VARCHAR};
+
+/// In-memory implementation of OfficeStore, see MemoryDatabase
+/// Office names are unique per tenant, max_occupancy is positive and the address columns keep their lengths and checks.
+/// An office with employees cannot be deleted, its manager grants, transfers, waitlist and locations go along
+#[derive(Clone)]
+pub struct MemoryOfficeRepository {
+    pub(super) db: MemoryDatabase,
+}
+
+#[async_trait]
+impl OfficeStore for MemoryOfficeRepository {
+    async fn create_office(&self, tenant_id: i32, office: &Office) -> anyhow::Result<Office> {
+        self.db.write().create_office(tenant_id, office)
+    }
+
+    async fn get_office_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Office>> {
+        Ok(self.db.lock().office(tenant_id, id))
+    }
+
+    async fn get_all_offices(&self, tenant_id: i32) -> anyhow::Result<Vec<Office>> {
+        let state = self.db.lock();
+        Ok(state.offices.values().filter(|(t, _)| *t == tenant_id).map(|(_, o)| o.clone()).collect())
+    }
+
+    async fn get_offices_near(&self, tenant_id: i32, area: &SearchArea) -> anyhow::Result<Vec<(Office, f64)>> {
+        let state = self.db.lock();
+        let mut offices: Vec<_> = state
+            .offices
+            .values()
+            .filter(|(t, _)| *t == tenant_id)
+            .filter_map(|(_, o)| o.distance_km(area.latitude, area.longitude).map(|d| (o.clone(), d)))
+            .filter(|(_, d)| *d <= area.radius_km)
+            .collect();
+        offices.sort_by(|(a, da), (b, db)| da.total_cmp(db).then(a.id.cmp(&b.id)));
+        Ok(offices)
+    }
+
+    async fn get_office_by_name(&self, tenant_id: i32, name: &str) -> anyhow::Result<Option<Office>> {
+        let state = self.db.lock();
+        Ok(state.offices.values().find(|(t, o)| *t == tenant_id && o.name == name).map(|(_, o)| o.clone()))
+    }
+
+    async fn update_office_by_id(&self, tenant_id: i32, id: i32, office: &Office) -> anyhow::Result<Office> {
+        self.db.write().update_office(tenant_id, id, office)
+    }
+
+    async fn delete_office(&self, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        self.db.write().delete_office(tenant_id, id)
+    }
+
+    async fn create_office_tx(&self, tx: &mut Transaction, tenant_id: i32, office: &Office) -> anyhow::Result<Office> {
+        tx.memory()?.state.create_office(tenant_id, office)
+    }
+
+    async fn get_office_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Office>> {
+        Ok(tx.memory()?.state.office(tenant_id, id))
+    }
+
+    async fn get_office_by_name_tx(&self, tx: &mut Transaction, tenant_id: i32, name: &str) -> anyhow::Result<Option<Office>> {
+        let state = &tx.memory()?.state;
+        Ok(state.offices.values().find(|(t, o)| *t == tenant_id && o.name == name).map(|(_, o)| o.clone()))
+    }
+
+    async fn update_office_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, office: &Office) -> anyhow::Result<Office> {
+        tx.memory()?.state.update_office(tenant_id, id, office)
+    }
+
+    async fn delete_office_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        tx.memory()?.state.delete_office(tenant_id, id)
+    }
+
+    fn stream_offices(&self, tenant_id: i32) -> BoxStream<'_, anyhow::Result<OfficeExportRow>> {
+        let state = self.db.lock();
+        let rows: Vec<_> = state
+            .offices
+            .iter()
+            .filter(|(_, (t, _))| *t == tenant_id)
+            .map(|(id, (_, o))| Ok(OfficeExportRow { id: *id, name: o.name.clone(), max_occupancy: o.max_occupancy }))
+            .collect();
+        stream::iter(rows).boxed()
+    }
+}
+
+impl MemoryState {
+    // CHECK, length and UNIQUE (tenant_id, name) constraints of offices
+    pub(super) fn check_office(&self, tenant_id: i32, id: Option<i32>, office: &Office) -> anyhow::Result<()> {
+        if office.name.chars().count() > MAX_VARCHAR {
+            return Err(anyhow!("value too long for type character varying(100)"));
+        }
+        if office.max_occupancy <= 0 {
+            return Err(anyhow!("new row for relation \"offices\" violates check constraint \"offices_max_occupancy_check\""));
+        }
+        if [&office.street, &office.city].iter().any(|v| v.as_ref().is_some_and(|v| v.chars().count() > MAX_VARCHAR)) {
+            return Err(anyhow!("value too long for type character varying(100)"));
+        }
+        if office.postal_code.as_ref().is_some_and(|v| v.chars().count() > 20) {
+            return Err(anyhow!("value too long for type character varying(20)"));
+        }
+        if office.country.as_ref().is_some_and(|c| !(c.len() == 2 && c.chars().all(|ch| ch.is_ascii_uppercase()))) {
+            return Err(anyhow!("new row for relation \"offices\" violates check constraint \"offices_country_check\""));
+        }
+        if office.latitude.is_some_and(|lat| !(-90.0..=90.0).contains(&lat)) {
+            return Err(anyhow!("new row for relation \"offices\" violates check constraint \"offices_latitude_check\""));
+        }
+        if office.longitude.is_some_and(|lon| !(-180.0..=180.0).contains(&lon)) {
+            return Err(anyhow!("new row for relation \"offices\" violates check constraint \"offices_longitude_check\""));
+        }
+        if office.latitude.is_some() != office.longitude.is_some() {
+            return Err(anyhow!("new row for relation \"offices\" violates check constraint \"offices_coordinates_check\""));
+        }
+        let taken = self.offices.iter().any(|(other_id, (other_tenant, other))| {
+            *other_tenant == tenant_id && other.name == office.name && Some(*other_id) != id
+        });
+        if taken {
+            return Err(anyhow!("duplicate key value violates unique constraint \"offices_tenant_id_name_key\""));
+        }
+        Ok(())
+    }
+
+    fn create_office(&mut self, tenant_id: i32, office: &Office) -> anyhow::Result<Office> {
+        self.check_office(tenant_id, None, office)?;
+        self.office_seq += 1;
+        let created = Office { id: Some(self.office_seq), ..office.clone() };
+        self.offices.insert(self.office_seq, (tenant_id, created.clone()));
+        Ok(created)
+    }
+
+    pub(super) fn office_in_tenant(&self, tenant_id: i32, office_id: i32) -> bool {
+        matches!(self.offices.get(&office_id), Some((office_tenant, _)) if *office_tenant == tenant_id)
+    }
+
+    pub(super) fn office(&self, tenant_id: i32, id: i32) -> Option<Office> {
+        self.offices.get(&id).filter(|(t, _)| *t == tenant_id).map(|(_, o)| o.clone())
+    }
+
+    pub(super) fn update_office(&mut self, tenant_id: i32, id: i32, office: &Office) -> anyhow::Result<Office> {
+        if !self.office_in_tenant(tenant_id, id) {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        self.check_office(tenant_id, Some(id), office)?;
+        let updated = Office { id: Some(id), ..office.clone() };
+        self.offices.insert(id, (tenant_id, updated.clone()));
+        Ok(updated)
+    }
+
+    pub(super) fn delete_office(&mut self, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        if !self.office_in_tenant(tenant_id, id) {
+            return Ok(0);
+        }
+        if self.employees.values().any(|(_, e)| e.office_id == id) {
+            return Err(anyhow!(
+                "update or delete on table \"offices\" violates foreign key constraint \"employees_tenant_id_office_id_fkey\" on table \"employees\""
+            ));
+        }
+        self.offices.remove(&id);
+        self.office_managers.retain(|(_, office_id)| *office_id != id); // ON DELETE CASCADE
+        self.transfers.retain(|_, (_, t)| t.from_office_id != id && t.to_office_id != id); // ON DELETE CASCADE
+        self.waitlist_entries.retain(|_, (_, e)| e.office_id != id); // ON DELETE CASCADE, and of their events
+        let entries = &self.waitlist_entries;
+        self.waitlist_events.retain(|_, (_, e)| entries.contains_key(&e.entry_id));
+        self.locations.retain(|_, (_, l)| l.office_id != Some(id)); // ON DELETE CASCADE, and of their assignments
+        let locations = &self.locations;
+        self.location_assignments.retain(|_, (_, location_id)| locations.contains_key(location_id));
+        Ok(1)
+    }
+}
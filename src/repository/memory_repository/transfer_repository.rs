@@ -0,0 +1,115 @@
+use crate::entity::transfer::{Transfer, TransferStatus};
+use crate::repository::transfer_repository::TransferStore;
+use crate::repository::unit_of_work::Transaction;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+
+use super::{MemoryDatabase, MemoryState, MAX_VARCHAR};
+
+/// In-memory implementation of TransferStore, see MemoryDatabase
+/// Transfers go between two different offices of the employee's tenant, one pending transfer per employee
+#[derive(Clone)]
+pub struct MemoryTransferRepository {
+    pub(super) db: MemoryDatabase,
+}
+
+// Ordering of transfer lists, by effective date and ID
+fn sorted_transfers(mut transfers: Vec<Transfer>) -> Vec<Transfer> {
+    transfers.sort_by_key(|t| (t.effective_date, t.id));
+    transfers
+}
+
+#[async_trait]
+impl TransferStore for MemoryTransferRepository {
+    async fn create_transfer_tx(&self, tx: &mut Transaction, tenant_id: i32, transfer: &Transfer) -> anyhow::Result<Transfer> {
+        let state = &mut tx.memory()?.state;
+        state.check_transfer(tenant_id, None, transfer)?;
+        state.transfer_seq += 1;
+        let created = Transfer { id: Some(state.transfer_seq), created_at: Utc::now(), ..transfer.clone() };
+        state.transfers.insert(state.transfer_seq, (tenant_id, created.clone()));
+        Ok(created)
+    }
+
+    async fn get_transfer_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Transfer>> {
+        Ok(self.db.lock().transfer(tenant_id, id))
+    }
+
+    async fn get_transfer_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Transfer>> {
+        Ok(tx.memory()?.state.transfer(tenant_id, id))
+    }
+
+    async fn get_transfers_by_office_id(&self, tenant_id: i32, office_id: i32, status: Option<TransferStatus>) -> anyhow::Result<Vec<Transfer>> {
+        let state = self.db.lock();
+        let transfers = state.transfers.values()
+            .filter(|(t, _)| *t == tenant_id)
+            .map(|(_, transfer)| transfer)
+            .filter(|t| (t.from_office_id == office_id || t.to_office_id == office_id) && status.is_none_or(|s| t.status == s))
+            .cloned()
+            .collect();
+        Ok(sorted_transfers(transfers))
+    }
+
+    async fn get_pending_transfers_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<Transfer>> {
+        let state = &tx.memory()?.state;
+        let transfers = state.transfers.values()
+            .filter(|(t, _)| *t == tenant_id)
+            .map(|(_, transfer)| transfer)
+            .filter(|t| t.status == TransferStatus::Pending)
+            .filter(|t| t.to_office_id == office_id || state.employee(tenant_id, t.employee_id).is_some_and(|e| e.office_id == office_id))
+            .cloned()
+            .collect();
+        Ok(sorted_transfers(transfers))
+    }
+
+    async fn update_transfer_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, transfer: &Transfer) -> anyhow::Result<Transfer> {
+        let state = &mut tx.memory()?.state;
+        let Some(existing) = state.transfer(tenant_id, id) else {
+            return Err(sqlx::Error::RowNotFound.into());
+        };
+        let updated = Transfer { effective_date: transfer.effective_date, status: transfer.status, ..existing };
+        state.check_transfer(tenant_id, Some(id), &updated)?;
+        state.transfers.insert(id, (tenant_id, updated.clone()));
+        Ok(updated)
+    }
+
+    async fn get_due_transfers(&self, on: NaiveDate) -> anyhow::Result<Vec<(i32, Transfer)>> {
+        let state = self.db.lock();
+        let mut due: Vec<(i32, Transfer)> = state.transfers.values()
+            .filter(|(_, t)| t.status == TransferStatus::Pending && t.effective_date <= on)
+            .cloned()
+            .collect();
+        due.sort_by_key(|(_, t)| (t.effective_date, t.id));
+        Ok(due)
+    }
+}
+
+impl MemoryState {
+    // CHECK, length, FOREIGN KEY and the partial UNIQUE (employee_id) WHERE status = 'pending' constraints of transfers
+    pub(super) fn check_transfer(&self, tenant_id: i32, id: Option<i32>, transfer: &Transfer) -> anyhow::Result<()> {
+        if transfer.requested_by.chars().count() > MAX_VARCHAR {
+            return Err(anyhow!("value too long for type character varying(100)"));
+        }
+        if transfer.from_office_id == transfer.to_office_id {
+            return Err(anyhow!("new row for relation \"transfers\" violates check constraint \"transfers_office_check\""));
+        }
+        if self.employee(tenant_id, transfer.employee_id).is_none() {
+            return Err(anyhow!("insert or update on table \"transfers\" violates foreign key constraint \"transfers_tenant_id_employee_id_fkey\""));
+        }
+        if !self.office_in_tenant(tenant_id, transfer.from_office_id) || !self.office_in_tenant(tenant_id, transfer.to_office_id) {
+            return Err(anyhow!("insert or update on table \"transfers\" violates foreign key constraint \"transfers_tenant_id_to_office_id_fkey\""));
+        }
+        let pending_twice = transfer.status == TransferStatus::Pending
+            && self.transfers.iter().any(|(other_id, (_, other))| {
+                Some(*other_id) != id && other.employee_id == transfer.employee_id && other.status == TransferStatus::Pending
+            });
+        if pending_twice {
+            return Err(anyhow!("duplicate key value violates unique constraint \"transfers_employee_id_pending_key\""));
+        }
+        Ok(())
+    }
+
+    pub(super) fn transfer(&self, tenant_id: i32, id: i32) -> Option<Transfer> {
+        self.transfers.get(&id).filter(|(t, _)| *t == tenant_id).map(|(_, t)| t.clone())
+    }
+}
@@ -0,0 +1,108 @@
+use crate::entity::waitlist::{WaitlistEntry, WaitlistEvent, WaitlistStatus};
+use crate::repository::unit_of_work::Transaction;
+use crate::repository::waitlist_repository::WaitlistStore;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::BTreeSet;
+
+use super::{MemoryDatabase, MemoryState, MAX_VARCHAR};
+
+/// In-memory implementation of WaitlistStore, see MemoryDatabase
+/// Entries wait for an office of their tenant, events belong to an entry
+#[derive(Clone)]
+pub struct MemoryWaitlistRepository {
+    pub(super) db: MemoryDatabase,
+}
+
+#[async_trait]
+impl WaitlistStore for MemoryWaitlistRepository {
+    async fn create_entry_tx(&self, tx: &mut Transaction, tenant_id: i32, entry: &WaitlistEntry) -> anyhow::Result<WaitlistEntry> {
+        let state = &mut tx.memory()?.state;
+        state.check_waitlist_entry(tenant_id, entry)?;
+        state.waitlist_entry_seq += 1;
+        let created = WaitlistEntry { id: Some(state.waitlist_entry_seq), employee_id: None, created_at: Utc::now(), ..entry.clone() };
+        state.waitlist_entries.insert(state.waitlist_entry_seq, (tenant_id, created.clone()));
+        Ok(created)
+    }
+
+    async fn get_entry_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<WaitlistEntry>> {
+        Ok(self.db.lock().waitlist_entry(tenant_id, id))
+    }
+
+    async fn get_entry_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<WaitlistEntry>> {
+        Ok(tx.memory()?.state.waitlist_entry(tenant_id, id))
+    }
+
+    async fn get_entries_by_office_id(&self, tenant_id: i32, office_id: i32, status: Option<WaitlistStatus>) -> anyhow::Result<Vec<WaitlistEntry>> {
+        Ok(self.db.lock().waitlist_of(tenant_id, office_id, status))
+    }
+
+    async fn get_entries_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<WaitlistEntry>> {
+        Ok(tx.memory()?.state.waitlist_of(tenant_id, office_id, None))
+    }
+
+    async fn update_entry_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, entry: &WaitlistEntry) -> anyhow::Result<WaitlistEntry> {
+        let state = &mut tx.memory()?.state;
+        let Some(existing) = state.waitlist_entry(tenant_id, id) else {
+            return Err(sqlx::Error::RowNotFound.into());
+        };
+        let updated = WaitlistEntry { status: entry.status, employee_id: entry.employee_id, ..existing };
+        state.waitlist_entries.insert(id, (tenant_id, updated.clone()));
+        Ok(updated)
+    }
+
+    async fn get_waiting_office_ids(&self) -> anyhow::Result<Vec<(i32, i32)>> {
+        let state = self.db.lock();
+        let offices: BTreeSet<(i32, i32)> = state.waitlist_entries.values()
+            .filter(|(_, e)| e.status == WaitlistStatus::Waiting)
+            .map(|(t, e)| (*t, e.office_id))
+            .collect();
+        Ok(offices.into_iter().collect())
+    }
+
+    async fn create_event_tx(&self, tx: &mut Transaction, tenant_id: i32, event: &WaitlistEvent) -> anyhow::Result<WaitlistEvent> {
+        let state = &mut tx.memory()?.state;
+        if state.waitlist_entry(tenant_id, event.entry_id).is_none() {
+            return Err(anyhow!("insert or update on table \"waitlist_events\" violates foreign key constraint \"waitlist_events_entry_fkey\""));
+        }
+        state.waitlist_event_seq += 1;
+        let created = WaitlistEvent { id: Some(state.waitlist_event_seq), created_at: Utc::now(), ..event.clone() };
+        state.waitlist_events.insert(state.waitlist_event_seq, (tenant_id, created.clone()));
+        Ok(created)
+    }
+
+    async fn get_events(&self, tenant_id: i32, entry_id: i32) -> anyhow::Result<Vec<WaitlistEvent>> {
+        let state = self.db.lock();
+        Ok(state.waitlist_events.values()
+            .filter(|(t, e)| *t == tenant_id && e.entry_id == entry_id)
+            .map(|(_, e)| e.clone())
+            .collect())
+    }
+}
+
+impl MemoryState {
+    // Length and FOREIGN KEY constraints of waitlist_entries
+    pub(super) fn check_waitlist_entry(&self, tenant_id: i32, entry: &WaitlistEntry) -> anyhow::Result<()> {
+        let too_long = [&entry.first_name, &entry.last_name, &entry.requested_by].iter().any(|v| v.chars().count() > MAX_VARCHAR);
+        if too_long {
+            return Err(anyhow!("value too long for type character varying(100)"));
+        }
+        if !self.office_in_tenant(tenant_id, entry.office_id) {
+            return Err(anyhow!("insert or update on table \"waitlist_entries\" violates foreign key constraint \"waitlist_entries_tenant_id_office_id_fkey\""));
+        }
+        Ok(())
+    }
+
+    pub(super) fn waitlist_entry(&self, tenant_id: i32, id: i32) -> Option<WaitlistEntry> {
+        self.waitlist_entries.get(&id).filter(|(t, _)| *t == tenant_id).map(|(_, e)| e.clone())
+    }
+
+    // Waitlist of an office in order of arrival
+    pub(super) fn waitlist_of(&self, tenant_id: i32, office_id: i32, status: Option<WaitlistStatus>) -> Vec<WaitlistEntry> {
+        self.waitlist_entries.values()
+            .filter(|(t, e)| *t == tenant_id && e.office_id == office_id && status.is_none_or(|s| e.status == s))
+            .map(|(_, e)| e.clone())
+            .collect()
+    }
+}
@@ -1,2 +1,17 @@
 pub mod office_repository;
-pub mod employee_repository;
\ No newline at end of file
+pub mod employee_repository;
+pub mod office_manager_repository;
+pub mod tenant_repository;
+pub mod idempotency_repository;
+pub mod health_repository;
+pub mod audit_repository;
+pub mod department_repository;
+pub mod transfer_repository;
+pub mod change_request_repository;
+pub mod waitlist_repository;
+pub mod location_repository;
+pub mod read_routing;
+pub mod unit_of_work;
+pub mod memory_repository;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_repository;
\ No newline at end of file
@@ -0,0 +1,87 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+/// Storage of office manager grants, implemented for Postgres (OfficeManagerRepository) and in memory (MemoryOfficeManagerRepository)
+/// Grants disappear with their office
+#[async_trait]
+pub trait OfficeManagerStore: Send + Sync {
+    /// Retrieves the IDs of the offices managed by a subject
+    async fn get_office_ids_by_subject(&self, tenant_id: i32, subject: &str) -> anyhow::Result<Vec<i32>>;
+
+    /// Retrieves the subjects managing an office
+    async fn get_subjects_by_office_id(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<String>>;
+
+    /// Grants a subject rights on an office of the tenant, granting twice is a no-op
+    async fn add_office_manager(&self, tenant_id: i32, subject: &str, office_id: i32) -> anyhow::Result<()>;
+
+    /// Revokes a subject's rights on an office and returns number of affected rows
+    async fn delete_office_manager(&self, tenant_id: i32, subject: &str, office_id: i32) -> anyhow::Result<u64>;
+}
+
+/// Repository for the principal-to-office mapping of office managers
+/// A row grants the subject of a token scoped rights on one office
+/// Lookups join offices so only the current tenant's offices are returned
+///
+/// database schema:
+/// subject VARCHAR(100) NOT NULL,
+/// office_id INT NOT NULL REFERENCES offices(id) ON DELETE CASCADE,
+/// PRIMARY KEY (subject, office_id)
+#[derive(Clone)]
+pub struct OfficeManagerRepository {
+    pool: PgPool,
+}
+impl OfficeManagerRepository {
+    /// Constructor for OfficeManagerRepository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl OfficeManagerStore for OfficeManagerRepository {
+    async fn get_office_ids_by_subject(&self, tenant_id: i32, subject: &str) -> anyhow::Result<Vec<i32>> {
+        let office_ids = sqlx::query_scalar!(
+            "SELECT m.office_id FROM office_managers m JOIN offices o ON o.id = m.office_id WHERE o.tenant_id = $1 AND m.subject = $2 ORDER BY m.office_id",
+            tenant_id,
+            subject
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(office_ids)
+    }
+
+    async fn get_subjects_by_office_id(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<String>> {
+        let subjects = sqlx::query_scalar!(
+            "SELECT m.subject FROM office_managers m JOIN offices o ON o.id = m.office_id WHERE o.tenant_id = $1 AND m.office_id = $2 ORDER BY m.subject",
+            tenant_id,
+            office_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(subjects)
+    }
+
+    async fn add_office_manager(&self, tenant_id: i32, subject: &str, office_id: i32) -> anyhow::Result<()> {
+        sqlx::query!(
+            "INSERT INTO office_managers (subject, office_id) SELECT $1, id FROM offices WHERE tenant_id = $2 AND id = $3 ON CONFLICT DO NOTHING",
+            subject,
+            tenant_id,
+            office_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_office_manager(&self, tenant_id: i32, subject: &str, office_id: i32) -> anyhow::Result<u64> {
+        let result = sqlx::query!(
+            "DELETE FROM office_managers m USING offices o WHERE o.id = m.office_id AND o.tenant_id = $1 AND m.subject = $2 AND m.office_id = $3",
+            tenant_id,
+            subject,
+            office_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
\ No newline at end of file
@@ -1,86 +1,258 @@
-use crate::entity::office::Office;
-use sqlx::PgPool;
+use crate::entity::office::{Office, EARTH_RADIUS_KM};
+use crate::dto::export_dto::OfficeExportRow;
+use crate::dto::office_dto::SearchArea;
+use futures::stream::{BoxStream, StreamExt};
+use crate::repository::read_routing::PgPools;
+use crate::repository::unit_of_work::Transaction;
+use async_trait::async_trait;
+use sqlx::PgExecutor;
+
+/// Storage of Office entities, implemented for Postgres (OfficeRepository) and in memory (MemoryOfficeRepository)
+/// Every method is scoped to one tenant and must enforce unique names per tenant
+/// The `*_tx` methods run inside a transaction from the backend's UnitOfWork
+#[async_trait]
+pub trait OfficeStore: Send + Sync {
+    /// Inserts an office and returns the created office with its ID
+    async fn create_office(&self, tenant_id: i32, office: &Office) -> anyhow::Result<Office>;
+
+    /// Retrieves an office by its ID
+    async fn get_office_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Office>>;
+
+    /// Retrieves all offices of the tenant
+    async fn get_all_offices(&self, tenant_id: i32) -> anyhow::Result<Vec<Office>>;
+
+    /// Retrieves the offices with coordinates within the area, nearest first, each with its distance in kilometres
+    /// The distance is the haversine great-circle distance, Postgres computes it in SQL
+    async fn get_offices_near(&self, tenant_id: i32, area: &SearchArea) -> anyhow::Result<Vec<(Office, f64)>>;
+
+    /// Retrieves an office by its name
+    async fn get_office_by_name(&self, tenant_id: i32, name: &str) -> anyhow::Result<Option<Office>>;
+
+    /// Updates an office by its ID and returns the updated office
+    async fn update_office_by_id(&self, tenant_id: i32, id: i32, office: &Office) -> anyhow::Result<Office>;
+
+    /// Deletes an office by its ID and returns the number of affected rows
+    async fn delete_office(&self, tenant_id: i32, id: i32) -> anyhow::Result<u64>;
+
+    /// Streams offices row by row for exports, nothing is buffered
+    fn stream_offices(&self, tenant_id: i32) -> BoxStream<'_, anyhow::Result<OfficeExportRow>>;
+
+    /// create_office inside a transaction
+    async fn create_office_tx(&self, tx: &mut Transaction, tenant_id: i32, office: &Office) -> anyhow::Result<Office>;
+
+    /// get_office_by_id inside a transaction
+    async fn get_office_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Office>>;
+
+    /// get_office_by_name inside a transaction
+    async fn get_office_by_name_tx(&self, tx: &mut Transaction, tenant_id: i32, name: &str) -> anyhow::Result<Option<Office>>;
+
+    /// update_office_by_id inside a transaction
+    async fn update_office_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, office: &Office) -> anyhow::Result<Office>;
+
+    /// delete_office inside a transaction
+    async fn delete_office_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<u64>;
+}
 
 /// Repository for Office entities in the database
 /// Handles database operations for offices
+/// Every query is filtered by tenant, offices of other tenants are never visible
+/// Listing and exporting read from the replica when one is configured
 #[derive(Clone)]
 pub struct OfficeRepository {
-    pool: PgPool,
+    pools: PgPools,
 }
 impl OfficeRepository {
-    /// Constructor for OfficeRepository
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    /// Constructor for OfficeRepository, takes a PgPool or PgPools with a replica
+    pub fn new(pools: impl Into<PgPools>) -> Self {
+        Self { pools: pools.into() }
     }
+}
 
-    /// Inserts an office and returns the created office with its ID
-    pub async fn create_office(&self, office: &Office) -> anyhow::Result<Office> {
-        let created = sqlx::query_as!(
-            Office,
-            "INSERT INTO offices (name, max_occupancy) VALUES ($1, $2) RETURNING id, name, max_occupancy",
-            office.name,
-            office.max_occupancy
-        )
-        .fetch_one(&self.pool)
-        .await?;
-        Ok(created)
+#[async_trait]
+impl OfficeStore for OfficeRepository {
+    async fn create_office(&self, tenant_id: i32, office: &Office) -> anyhow::Result<Office> {
+        create_office(self.pools.writer(), tenant_id, office).await
     }
 
-    /// Retrieves an office by its ID
-    pub async fn get_office_by_id(&self, id: i32) -> anyhow::Result<Option<Office>> {
-        let office = sqlx::query_as!(
-            Office,
-            "SELECT id, name, max_occupancy FROM offices WHERE id = $1",
-            id
-        )
-        .fetch_optional(&self.pool)
-        .await?;
-        Ok(office)
+    async fn get_office_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Office>> {
+        get_office_by_id(self.pools.primary(), tenant_id, id).await
     }
 
-    /// Retrieves all offices from the database
-    pub async fn get_all_offices(&self) -> anyhow::Result<Vec<Office>> {
-        let offices = sqlx::query_as!(
-            Office,
-            "SELECT id, name, max_occupancy FROM offices"
-        )
-        .fetch_all(&self.pool)
-        .await?;
-        Ok(offices)
+    async fn get_all_offices(&self, tenant_id: i32) -> anyhow::Result<Vec<Office>> {
+        self.pools.read(|pool| async move {
+            sqlx::query_as!(
+                Office,
+                "SELECT id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude FROM offices WHERE tenant_id = $1",
+                tenant_id
+            )
+            .fetch_all(&pool)
+            .await
+        })
+        .await
     }
 
-    /// Retrieves an office by its name
-    pub async fn get_office_by_name(&self, name: &str) -> anyhow::Result<Option<Office>> {
-        let office = sqlx::query_as!(
-            Office,
-            "SELECT id, name, max_occupancy FROM offices WHERE name = $1",
-            name
-        )
-        .fetch_optional(&self.pool)
-        .await?;
-        
-        Ok(office)
+    async fn get_offices_near(&self, tenant_id: i32, area: &SearchArea) -> anyhow::Result<Vec<(Office, f64)>> {
+        let area = *area;
+        self.pools.read(|pool| async move {
+            let rows = sqlx::query!(
+                r#"SELECT id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude,
+                          distance_km AS "distance_km!"
+                   FROM (
+                       SELECT *, 2 * $4::float8 * asin(least(1, sqrt(
+                           power(sin(radians(latitude - $2) / 2), 2)
+                           + cos(radians($2)) * cos(radians(latitude)) * power(sin(radians(longitude - $3) / 2), 2)
+                       ))) AS distance_km
+                       FROM offices
+                       WHERE tenant_id = $1 AND latitude IS NOT NULL AND longitude IS NOT NULL
+                   ) located
+                   WHERE distance_km <= $5
+                   ORDER BY distance_km, id"#,
+                tenant_id,
+                area.latitude,
+                area.longitude,
+                EARTH_RADIUS_KM,
+                area.radius_km
+            )
+            .fetch_all(&pool)
+            .await?;
+            Ok(rows
+                .into_iter()
+                .map(|r| {
+                    let office = Office {
+                        id: Some(r.id),
+                        name: r.name,
+                        max_occupancy: r.max_occupancy,
+                        street: r.street,
+                        postal_code: r.postal_code,
+                        city: r.city,
+                        country: r.country,
+                        timezone: r.timezone,
+                        latitude: r.latitude,
+                        longitude: r.longitude,
+                    };
+                    (office, r.distance_km)
+                })
+                .collect())
+        })
+        .await
     }
 
-    /// Updates an office by its ID and returns the updated office
-    pub async fn update_office_by_id(&self, id: i32, office: &Office) -> anyhow::Result<Office> {
-        let updated = sqlx::query_as!(
-            Office,
-            "UPDATE offices SET name = $1, max_occupancy = $2 WHERE id = $3 RETURNING id, name, max_occupancy",
-            office.name, 
-            office.max_occupancy, 
-            id
+    async fn get_office_by_name(&self, tenant_id: i32, name: &str) -> anyhow::Result<Option<Office>> {
+        get_office_by_name(self.pools.primary(), tenant_id, name).await
+    }
+
+    async fn update_office_by_id(&self, tenant_id: i32, id: i32, office: &Office) -> anyhow::Result<Office> {
+        update_office_by_id(self.pools.writer(), tenant_id, id, office).await
+    }
+
+    async fn delete_office(&self, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        delete_office(self.pools.writer(), tenant_id, id).await
+    }
+
+    fn stream_offices(&self, tenant_id: i32) -> BoxStream<'_, anyhow::Result<OfficeExportRow>> {
+        sqlx::query_as!(
+            OfficeExportRow,
+            "SELECT id, name, max_occupancy FROM offices WHERE tenant_id = $1 ORDER BY id",
+            tenant_id
         )
-        .fetch_one(&self.pool)
-        .await?;
-        Ok(updated)
+        .fetch(self.pools.reader())
+        .map(|row| row.map_err(anyhow::Error::from))
+        .boxed()
     }
 
-    /// Deletes an office by its ID and returns the number of affected rows
-    pub async fn delete_office(&self, id: i32) -> anyhow::Result<u64> {
-        let result = sqlx::query!("DELETE FROM offices WHERE id = $1", id)
-            .execute(&self.pool)
-            .await?;
-        Ok(result.rows_affected())
+    async fn create_office_tx(&self, tx: &mut Transaction, tenant_id: i32, office: &Office) -> anyhow::Result<Office> {
+        create_office(tx.postgres()?, tenant_id, office).await
+    }
+
+    async fn get_office_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Office>> {
+        get_office_by_id(tx.postgres()?, tenant_id, id).await
+    }
+
+    async fn get_office_by_name_tx(&self, tx: &mut Transaction, tenant_id: i32, name: &str) -> anyhow::Result<Option<Office>> {
+        get_office_by_name(tx.postgres()?, tenant_id, name).await
     }
+
+    async fn update_office_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, office: &Office) -> anyhow::Result<Office> {
+        update_office_by_id(tx.postgres()?, tenant_id, id, office).await
+    }
+
+    async fn delete_office_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        delete_office(tx.postgres()?, tenant_id, id).await
+    }
+}
+
+// Queries shared by the pool and the transaction methods
+
+async fn create_office(executor: impl PgExecutor<'_>, tenant_id: i32, office: &Office) -> anyhow::Result<Office> {
+    let created = sqlx::query_as!(
+        Office,
+        "INSERT INTO offices (tenant_id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude",
+        tenant_id,
+        office.name,
+        office.max_occupancy,
+        office.street,
+        office.postal_code,
+        office.city,
+        office.country,
+        office.timezone,
+        office.latitude,
+        office.longitude
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(created)
+}
+
+async fn get_office_by_id(executor: impl PgExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Option<Office>> {
+    let office = sqlx::query_as!(
+        Office,
+        "SELECT id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude FROM offices WHERE tenant_id = $1 AND id = $2",
+        tenant_id,
+        id
+    )
+    .fetch_optional(executor)
+    .await?;
+    Ok(office)
+}
+
+async fn get_office_by_name(executor: impl PgExecutor<'_>, tenant_id: i32, name: &str) -> anyhow::Result<Option<Office>> {
+    let office = sqlx::query_as!(
+        Office,
+        "SELECT id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude FROM offices WHERE tenant_id = $1 AND name = $2",
+        tenant_id,
+        name
+    )
+    .fetch_optional(executor)
+    .await?;
+    Ok(office)
+}
+
+async fn update_office_by_id(executor: impl PgExecutor<'_>, tenant_id: i32, id: i32, office: &Office) -> anyhow::Result<Office> {
+    let updated = sqlx::query_as!(
+        Office,
+        "UPDATE offices SET name = $1, max_occupancy = $2, street = $3, postal_code = $4, city = $5, country = $6, timezone = $7,
+         latitude = $8, longitude = $9 WHERE tenant_id = $10 AND id = $11 RETURNING id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude",
+        office.name,
+        office.max_occupancy,
+        office.street,
+        office.postal_code,
+        office.city,
+        office.country,
+        office.timezone,
+        office.latitude,
+        office.longitude,
+        tenant_id,
+        id
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(updated)
+}
+
+async fn delete_office(executor: impl PgExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+    let result = sqlx::query!("DELETE FROM offices WHERE tenant_id = $1 AND id = $2", tenant_id, id)
+        .execute(executor)
+        .await?;
+    Ok(result.rows_affected())
 }
\ No newline at end of file
@@ -0,0 +1,142 @@
+use sqlx::PgPool;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+/// How long a replica that failed a query is skipped before it is tried again
+pub const REPLICA_RETRY_SECS: i64 = 30;
+
+tokio::task_local! {
+    // Set once the current request wrote to the primary, see request_scope
+    static REQUEST_WROTE: Cell<bool>;
+}
+
+/// Runs a request with write tracking, reads after a write in it go to the primary when read-your-writes is on
+pub async fn request_scope<F: Future>(request: F) -> F::Output {
+    REQUEST_WROTE.scope(Cell::new(false), request).await
+}
+
+/// Records that the current request wrote to the primary, a no-op outside of request_scope
+pub fn mark_written() {
+    let _ = REQUEST_WROTE.try_with(|wrote| wrote.set(true));
+}
+
+fn request_wrote() -> bool {
+    REQUEST_WROTE.try_with(|wrote| wrote.get()).unwrap_or(false)
+}
+
+/// Postgres pools of the repositories: the primary for writes and lookups,
+/// an optional replica for list and report queries.
+/// A replica failing with a connection error is skipped for REPLICA_RETRY_SECS and the query rerun on the primary
+#[derive(Clone)]
+pub struct PgPools {
+    primary: PgPool,
+    replica: Option<Replica>,
+    read_your_writes: bool,
+}
+
+#[derive(Clone)]
+struct Replica {
+    pool: PgPool,
+    down_until: Arc<AtomicI64>, // unix seconds, 0 while healthy
+}
+
+impl From<PgPool> for PgPools {
+    fn from(primary: PgPool) -> Self {
+        Self { primary, replica: None, read_your_writes: true }
+    }
+}
+
+impl PgPools {
+    /// Adds a read replica, `read_your_writes` sends reads of a request that already wrote to the primary
+    pub fn with_replica(mut self, replica: PgPool, read_your_writes: bool) -> Self {
+        self.replica = Some(Replica { pool: replica, down_until: Arc::new(AtomicI64::new(0)) });
+        self.read_your_writes = read_your_writes;
+        self
+    }
+
+    /// Pool for reads that must see the latest data
+    pub fn primary(&self) -> &PgPool {
+        &self.primary
+    }
+
+    /// Pool for writes, marks the request as having written
+    pub fn writer(&self) -> &PgPool {
+        mark_written();
+        &self.primary
+    }
+
+    // The replica if one is configured, up, and the request has not written yet (with read-your-writes)
+    fn replica(&self) -> Option<&Replica> {
+        let replica = self.replica.as_ref()?;
+        if self.read_your_writes && request_wrote() {
+            return None;
+        }
+        (replica.down_until.load(Ordering::Relaxed) <= chrono::Utc::now().timestamp()).then_some(replica)
+    }
+
+    /// Pool for a streaming read, replica when available
+    /// Streams cannot be rerun once rows were sent, so there is no fallback mid-stream
+    pub fn reader(&self) -> &PgPool {
+        self.replica().map(|replica| &replica.pool).unwrap_or(&self.primary)
+    }
+
+    /// Runs a read query on the replica when available, falling back to the primary on connection errors
+    pub async fn read<T, F, Fut>(&self, query: F) -> anyhow::Result<T>
+    where
+        F: Fn(PgPool) -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        if let Some(replica) = self.replica() {
+            match query(replica.pool.clone()).await {
+                Err(e) if is_unavailable(&e) => {
+                    tracing::warn!("Read replica unavailable, using primary for {}s: {}", REPLICA_RETRY_SECS, e);
+                    replica.down_until.store(chrono::Utc::now().timestamp() + REPLICA_RETRY_SECS, Ordering::Relaxed);
+                }
+                result => return Ok(result?),
+            }
+        }
+        Ok(query(self.primary.clone()).await?)
+    }
+}
+
+// Connection level failures, a query error on a reachable replica is returned as is
+fn is_unavailable(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::PoolTimedOut
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => true,
+        // connection_exception (08) and operator_intervention (57P), e.g. a replica still starting up
+        sqlx::Error::Database(db_error) => {
+            db_error.code().is_some_and(|code| code.starts_with("08") || code.starts_with("57P"))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_errors_are_unavailable() {
+        assert!(is_unavailable(&sqlx::Error::PoolTimedOut));
+        assert!(is_unavailable(&sqlx::Error::Io(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))));
+        assert!(!is_unavailable(&sqlx::Error::RowNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_writes_are_tracked_per_request() {
+        mark_written(); // outside of a request, ignored
+        assert!(!request_wrote());
+        request_scope(async {
+            assert!(!request_wrote());
+            mark_written();
+            assert!(request_wrote());
+        })
+        .await;
+        request_scope(async { assert!(!request_wrote()) }).await;
+    }
+}
@@ -0,0 +1,1513 @@
+use crate::dto::export_dto::{EmployeeExportRow, OfficeExportRow};
+use crate::dto::office_dto::SearchArea;
+use crate::entity::archived_employee::ArchivedEmployee;
+use crate::entity::audit_entry::AuditEntry;
+use crate::entity::change_request::{Approval, ApprovalStep, ChangeRequest, ChangeRequestStatus, Comment, Decision};
+use crate::entity::department::Department;
+use crate::entity::employee::Employee;
+use crate::entity::idempotency_record::{IdempotencyKey, IdempotencyRecord};
+use crate::entity::location::Location;
+use crate::entity::office::{Office, EARTH_RADIUS_KM};
+use crate::entity::transfer::{Transfer, TransferStatus};
+use crate::entity::waitlist::{WaitlistEntry, WaitlistEvent, WaitlistStatus};
+use crate::repository::audit_repository::AuditStore;
+use crate::repository::change_request_repository::ChangeRequestStore;
+use crate::repository::department_repository::DepartmentStore;
+use crate::repository::employee_repository::{EmployeeStore, MAX_HIERARCHY_DEPTH};
+use crate::repository::health_repository::HealthStore;
+use crate::repository::idempotency_repository::IdempotencyStore;
+use crate::repository::location_repository::LocationStore;
+use crate::repository::office_manager_repository::OfficeManagerStore;
+use crate::repository::office_repository::OfficeStore;
+use crate::repository::transfer_repository::TransferStore;
+use crate::repository::unit_of_work::{Transaction, UnitOfWork};
+use crate::repository::waitlist_repository::WaitlistStore;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::stream::{BoxStream, StreamExt};
+use sqlx::{SqliteExecutor, SqlitePool};
+
+// SQLite repositories, compiled with the `sqlite` feature
+// Same queries as the Postgres repositories, schema in migrations_sqlite.
+// The query! macros are checked against Postgres at build time, so these use the runtime checked query functions.
+// Lists of IDs are bound as a JSON array and expanded with json_each, SQLite has no array type
+
+// Binds a list of office IDs for `IN (SELECT value FROM json_each(?))`
+fn json_ids(ids: &[i32]) -> String {
+    serde_json::to_string(ids).expect("serializing i32 list")
+}
+
+/// SQLite implementation of OfficeStore
+#[derive(Clone)]
+pub struct SqliteOfficeRepository {
+    pool: SqlitePool,
+}
+impl SqliteOfficeRepository {
+    /// Constructor for SqliteOfficeRepository
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl OfficeStore for SqliteOfficeRepository {
+    async fn create_office(&self, tenant_id: i32, office: &Office) -> anyhow::Result<Office> {
+        create_office(&self.pool, tenant_id, office).await
+    }
+
+    async fn get_office_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Office>> {
+        get_office_by_id(&self.pool, tenant_id, id).await
+    }
+
+    async fn get_all_offices(&self, tenant_id: i32) -> anyhow::Result<Vec<Office>> {
+        let offices = sqlx::query_as::<_, Office>("SELECT id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude FROM offices WHERE tenant_id = ?1")
+            .bind(tenant_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(offices)
+    }
+
+    // The bundled SQLite has no trigonometric functions, so SQL narrows the offices to a bounding box around the area
+    // and the haversine distance is computed on the candidates
+    async fn get_offices_near(&self, tenant_id: i32, area: &SearchArea) -> anyhow::Result<Vec<(Office, f64)>> {
+        let (min_lat, max_lat, min_lon, max_lon) = bounding_box(area);
+        let candidates = sqlx::query_as::<_, Office>(
+            "SELECT id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude FROM offices
+             WHERE tenant_id = ?1 AND latitude BETWEEN ?2 AND ?3 AND longitude BETWEEN ?4 AND ?5",
+        )
+        .bind(tenant_id)
+        .bind(min_lat)
+        .bind(max_lat)
+        .bind(min_lon)
+        .bind(max_lon)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut offices: Vec<_> = candidates
+            .into_iter()
+            .filter_map(|o| o.distance_km(area.latitude, area.longitude).map(|d| (o, d)))
+            .filter(|(_, d)| *d <= area.radius_km)
+            .collect();
+        offices.sort_by(|(a, da), (b, db)| da.total_cmp(db).then(a.id.cmp(&b.id)));
+        Ok(offices)
+    }
+
+    async fn get_office_by_name(&self, tenant_id: i32, name: &str) -> anyhow::Result<Option<Office>> {
+        get_office_by_name(&self.pool, tenant_id, name).await
+    }
+
+    async fn update_office_by_id(&self, tenant_id: i32, id: i32, office: &Office) -> anyhow::Result<Office> {
+        update_office_by_id(&self.pool, tenant_id, id, office).await
+    }
+
+    async fn delete_office(&self, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        delete_office(&self.pool, tenant_id, id).await
+    }
+
+    fn stream_offices(&self, tenant_id: i32) -> BoxStream<'_, anyhow::Result<OfficeExportRow>> {
+        sqlx::query_as::<_, OfficeExportRow>("SELECT id, name, max_occupancy FROM offices WHERE tenant_id = ?1 ORDER BY id")
+            .bind(tenant_id)
+            .fetch(&self.pool)
+            .map(|row| row.map_err(anyhow::Error::from))
+            .boxed()
+    }
+
+    async fn create_office_tx(&self, tx: &mut Transaction, tenant_id: i32, office: &Office) -> anyhow::Result<Office> {
+        create_office(tx.sqlite()?, tenant_id, office).await
+    }
+
+    async fn get_office_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Office>> {
+        get_office_by_id(tx.sqlite()?, tenant_id, id).await
+    }
+
+    async fn get_office_by_name_tx(&self, tx: &mut Transaction, tenant_id: i32, name: &str) -> anyhow::Result<Option<Office>> {
+        get_office_by_name(tx.sqlite()?, tenant_id, name).await
+    }
+
+    async fn update_office_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, office: &Office) -> anyhow::Result<Office> {
+        update_office_by_id(tx.sqlite()?, tenant_id, id, office).await
+    }
+
+    async fn delete_office_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        delete_office(tx.sqlite()?, tenant_id, id).await
+    }
+}
+
+// Latitude and longitude bounds enclosing every point within radius_km of the area's center.
+// Longitude is left unbounded when the circle reaches a pole or crosses the antimeridian
+fn bounding_box(area: &SearchArea) -> (f64, f64, f64, f64) {
+    let angle = area.radius_km / EARTH_RADIUS_KM;
+    let lat_delta = angle.to_degrees();
+    let (min_lat, max_lat) = (area.latitude - lat_delta, area.latitude + lat_delta);
+    if min_lat <= -90.0 || max_lat >= 90.0 {
+        return (min_lat.max(-90.0), max_lat.min(90.0), -180.0, 180.0);
+    }
+    let lon_delta = (angle.sin() / area.latitude.to_radians().cos()).min(1.0).asin().to_degrees();
+    let (min_lon, max_lon) = (area.longitude - lon_delta, area.longitude + lon_delta);
+    if min_lon < -180.0 || max_lon > 180.0 {
+        return (min_lat, max_lat, -180.0, 180.0);
+    }
+    (min_lat, max_lat, min_lon, max_lon)
+}
+
+async fn create_office(executor: impl SqliteExecutor<'_>, tenant_id: i32, office: &Office) -> anyhow::Result<Office> {
+    let created = sqlx::query_as::<_, Office>(
+        "INSERT INTO offices (tenant_id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10) RETURNING id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude",
+    )
+    .bind(tenant_id)
+    .bind(&office.name)
+    .bind(office.max_occupancy)
+    .bind(&office.street)
+    .bind(&office.postal_code)
+    .bind(&office.city)
+    .bind(&office.country)
+    .bind(&office.timezone)
+    .bind(office.latitude)
+    .bind(office.longitude)
+    .fetch_one(executor)
+    .await?;
+    Ok(created)
+}
+
+async fn get_office_by_id(executor: impl SqliteExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Option<Office>> {
+    let office = sqlx::query_as::<_, Office>(
+        "SELECT id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude FROM offices WHERE tenant_id = ?1 AND id = ?2",
+    )
+        .bind(tenant_id)
+        .bind(id)
+        .fetch_optional(executor)
+        .await?;
+    Ok(office)
+}
+
+async fn get_office_by_name(executor: impl SqliteExecutor<'_>, tenant_id: i32, name: &str) -> anyhow::Result<Option<Office>> {
+    let office = sqlx::query_as::<_, Office>(
+        "SELECT id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude FROM offices WHERE tenant_id = ?1 AND name = ?2",
+    )
+        .bind(tenant_id)
+        .bind(name)
+        .fetch_optional(executor)
+        .await?;
+    Ok(office)
+}
+
+async fn update_office_by_id(executor: impl SqliteExecutor<'_>, tenant_id: i32, id: i32, office: &Office) -> anyhow::Result<Office> {
+    let updated = sqlx::query_as::<_, Office>(
+        "UPDATE offices SET name = ?1, max_occupancy = ?2, street = ?3, postal_code = ?4, city = ?5, country = ?6, timezone = ?7,
+         latitude = ?8, longitude = ?9 WHERE tenant_id = ?10 AND id = ?11 RETURNING id, name, max_occupancy, street, postal_code, city, country, timezone, latitude, longitude",
+    )
+    .bind(&office.name)
+    .bind(office.max_occupancy)
+    .bind(&office.street)
+    .bind(&office.postal_code)
+    .bind(&office.city)
+    .bind(&office.country)
+    .bind(&office.timezone)
+    .bind(office.latitude)
+    .bind(office.longitude)
+    .bind(tenant_id)
+    .bind(id)
+    .fetch_one(executor)
+    .await?;
+    Ok(updated)
+}
+
+async fn delete_office(executor: impl SqliteExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+    let result = sqlx::query("DELETE FROM offices WHERE tenant_id = ?1 AND id = ?2")
+        .bind(tenant_id)
+        .bind(id)
+        .execute(executor)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// SQLite implementation of EmployeeStore
+#[derive(Clone)]
+pub struct SqliteEmployeeRepository {
+    pool: SqlitePool,
+}
+impl SqliteEmployeeRepository {
+    /// Constructor for SqliteEmployeeRepository
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+const INSERT_EMPLOYEE: &str = "INSERT INTO employees (tenant_id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status)
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+     RETURNING id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status";
+
+#[async_trait]
+impl EmployeeStore for SqliteEmployeeRepository {
+    async fn create_employee(&self, tenant_id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        create_employee(&self.pool, tenant_id, employee).await
+    }
+
+    async fn create_employees(&self, tenant_id: i32, employees: &[Employee]) -> anyhow::Result<Vec<Employee>> {
+        let mut tx = self.pool.begin().await?;
+        let mut created = Vec::with_capacity(employees.len());
+        for employee in employees {
+            created.push(create_employee(&mut *tx, tenant_id, employee).await?);
+        }
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    async fn current_employee_nr_by_office_id(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<i64> {
+        let today = chrono::Utc::now().date_naive();
+        seated_employee_nr_by_office_id(&self.pool, tenant_id, office_id, today, Some(today)).await
+    }
+
+    async fn seated_employee_nr_by_office_id(&self, tenant_id: i32, office_id: i32, from: NaiveDate, until: Option<NaiveDate>) -> anyhow::Result<i64> {
+        seated_employee_nr_by_office_id(&self.pool, tenant_id, office_id, from, until).await
+    }
+
+    async fn get_employee_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Employee>> {
+        get_employee_by_id(&self.pool, tenant_id, id).await
+    }
+
+    async fn get_employees_by_office_id(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<Employee>> {
+        let employees = sqlx::query_as::<_, Employee>(
+            "SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status FROM employees WHERE tenant_id = ?1 AND office_id = ?2",
+        )
+        .bind(tenant_id)
+        .bind(office_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(employees)
+    }
+
+    async fn get_employees_by_office_ids(&self, tenant_id: i32, office_ids: &[i32]) -> anyhow::Result<Vec<Employee>> {
+        let employees = sqlx::query_as::<_, Employee>(
+            "SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status FROM employees
+             WHERE tenant_id = ?1 AND office_id IN (SELECT value FROM json_each(?2))",
+        )
+        .bind(tenant_id)
+        .bind(json_ids(office_ids))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(employees)
+    }
+
+    async fn get_all_employees(&self, tenant_id: i32) -> anyhow::Result<Vec<Employee>> {
+        let employees = sqlx::query_as::<_, Employee>(
+            "SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status FROM employees WHERE tenant_id = ?1",
+        )
+        .bind(tenant_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(employees)
+    }
+
+    async fn get_employees_by_birth_date(&self, tenant_id: i32, birth_date: NaiveDate) -> anyhow::Result<Vec<Employee>> {
+        get_employees_by_birth_date(&self.pool, tenant_id, birth_date).await
+    }
+
+    async fn get_direct_reports(&self, tenant_id: i32, manager_id: i32) -> anyhow::Result<Vec<Employee>> {
+        let employees = sqlx::query_as::<_, Employee>(
+            "SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status FROM employees WHERE tenant_id = ?1 AND manager_id = ?2 ORDER BY id",
+        )
+        .bind(tenant_id)
+        .bind(manager_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(employees)
+    }
+
+    async fn get_reporting_chain(&self, tenant_id: i32, id: i32) -> anyhow::Result<Vec<Employee>> {
+        get_reporting_chain(&self.pool, tenant_id, id).await
+    }
+
+    async fn get_subtree(&self, tenant_id: i32, manager_id: i32) -> anyhow::Result<Vec<Employee>> {
+        let employees = sqlx::query_as::<_, Employee>(
+            "WITH RECURSIVE subtree AS (
+                 SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status, 1 AS depth
+                 FROM employees WHERE tenant_id = ?1 AND manager_id = ?2
+                 UNION ALL
+                 SELECT e.id, e.first_name, e.last_name, e.birth_date, e.office_id, e.department_id, e.manager_id, e.hire_date, e.termination_date, e.status, s.depth + 1
+                 FROM employees e JOIN subtree s ON e.manager_id = s.id
+                 WHERE e.tenant_id = ?1 AND s.depth < ?3
+             )
+             SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status FROM subtree ORDER BY depth, id",
+        )
+        .bind(tenant_id)
+        .bind(manager_id)
+        .bind(MAX_HIERARCHY_DEPTH)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(employees)
+    }
+
+    async fn update_employee_by_id(&self, tenant_id: i32, id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        update_employee_by_id(&self.pool, tenant_id, id, employee).await
+    }
+
+    async fn delete_employee(&self, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        delete_employee(&self.pool, tenant_id, id).await
+    }
+
+    fn stream_employees(
+        &self,
+        tenant_id: i32,
+        office_id: Option<i32>,
+        office_ids: Option<Vec<i32>>,
+        include_office: bool,
+    ) -> BoxStream<'_, anyhow::Result<EmployeeExportRow>> {
+        let sql = if include_office {
+            "SELECT e.id, e.first_name, e.last_name, e.birth_date, e.office_id, o.name AS office_name
+             FROM employees e JOIN offices o ON o.id = e.office_id
+             WHERE e.tenant_id = ?1 AND (?2 IS NULL OR e.office_id = ?2) AND (?3 IS NULL OR e.office_id IN (SELECT value FROM json_each(?3)))
+             ORDER BY e.id"
+        } else {
+            "SELECT id, first_name, last_name, birth_date, office_id, NULL AS office_name
+             FROM employees
+             WHERE tenant_id = ?1 AND (?2 IS NULL OR office_id = ?2) AND (?3 IS NULL OR office_id IN (SELECT value FROM json_each(?3)))
+             ORDER BY id"
+        };
+        sqlx::query_as::<_, EmployeeExportRow>(sql)
+            .bind(tenant_id)
+            .bind(office_id)
+            .bind(office_ids.as_deref().map(json_ids))
+            .fetch(&self.pool)
+            .map(|row| row.map_err(anyhow::Error::from))
+            .boxed()
+    }
+
+    async fn create_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        create_employee(tx.sqlite()?, tenant_id, employee).await
+    }
+
+    async fn delete_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        delete_employee(tx.sqlite()?, tenant_id, id).await
+    }
+
+    async fn current_employee_nr_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<i64> {
+        let today = chrono::Utc::now().date_naive();
+        seated_employee_nr_by_office_id(tx.sqlite()?, tenant_id, office_id, today, Some(today)).await
+    }
+
+    async fn seated_employee_nr_by_office_id_tx(
+        &self,
+        tx: &mut Transaction,
+        tenant_id: i32,
+        office_id: i32,
+        from: NaiveDate,
+        until: Option<NaiveDate>,
+    ) -> anyhow::Result<i64> {
+        seated_employee_nr_by_office_id(tx.sqlite()?, tenant_id, office_id, from, until).await
+    }
+
+    async fn get_employee_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Employee>> {
+        get_employee_by_id(tx.sqlite()?, tenant_id, id).await
+    }
+
+    async fn get_employees_by_birth_date_tx(&self, tx: &mut Transaction, tenant_id: i32, birth_date: NaiveDate) -> anyhow::Result<Vec<Employee>> {
+        get_employees_by_birth_date(tx.sqlite()?, tenant_id, birth_date).await
+    }
+
+    async fn get_employees_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<Employee>> {
+        let employees = sqlx::query_as::<_, Employee>(
+            "SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status FROM employees WHERE tenant_id = ?1 AND office_id = ?2",
+        )
+        .bind(tenant_id)
+        .bind(office_id)
+        .fetch_all(tx.sqlite()?)
+        .await?;
+        Ok(employees)
+    }
+
+    async fn update_employee_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+        update_employee_by_id(tx.sqlite()?, tenant_id, id, employee).await
+    }
+
+    async fn get_reporting_chain_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Vec<Employee>> {
+        get_reporting_chain(tx.sqlite()?, tenant_id, id).await
+    }
+
+    async fn reassign_employees_tx(&self, tx: &mut Transaction, tenant_id: i32, from_office_id: i32, to_office_id: i32) -> anyhow::Result<u64> {
+        let result = sqlx::query("UPDATE employees SET office_id = ?1 WHERE tenant_id = ?2 AND office_id = ?3")
+            .bind(to_office_id)
+            .bind(tenant_id)
+            .bind(from_office_id)
+            .execute(tx.sqlite()?)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn get_archived_employee_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<ArchivedEmployee>> {
+        let archived = sqlx::query_as::<_, ArchivedEmployee>(
+            "SELECT id, first_name, last_name, birth_date, office_id, merged_into, archived_at FROM archived_employees WHERE tenant_id = ?1 AND id = ?2",
+        )
+        .bind(tenant_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(archived)
+    }
+
+    async fn merge_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, source_id: i32, survivor_id: i32, survivor_office_id: i32) -> anyhow::Result<u64> {
+        let conn = tx.sqlite()?;
+        sqlx::query("UPDATE audit_entries SET employee_id = ?1 WHERE tenant_id = ?2 AND employee_id = ?3")
+            .bind(survivor_id)
+            .bind(tenant_id)
+            .bind(source_id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("UPDATE archived_employees SET merged_into = ?1 WHERE tenant_id = ?2 AND merged_into = ?3")
+            .bind(survivor_id)
+            .bind(tenant_id)
+            .bind(source_id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("UPDATE employees SET manager_id = ?1 WHERE tenant_id = ?2 AND manager_id = ?3 AND id <> ?1")
+            .bind(survivor_id)
+            .bind(tenant_id)
+            .bind(source_id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("UPDATE transfers SET status = 'cancelled' WHERE tenant_id = ?1 AND employee_id = ?2 AND status = 'pending'")
+            .bind(tenant_id)
+            .bind(source_id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("UPDATE transfers SET employee_id = ?1 WHERE tenant_id = ?2 AND employee_id = ?3")
+            .bind(survivor_id)
+            .bind(tenant_id)
+            .bind(source_id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("UPDATE waitlist_entries SET employee_id = ?1 WHERE tenant_id = ?2 AND employee_id = ?3")
+            .bind(survivor_id)
+            .bind(tenant_id)
+            .bind(source_id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("UPDATE waitlist_entries SET manager_id = ?1 WHERE tenant_id = ?2 AND manager_id = ?3")
+            .bind(survivor_id)
+            .bind(tenant_id)
+            .bind(source_id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query(
+            "UPDATE location_assignments SET employee_id = ?1
+             WHERE tenant_id = ?2 AND employee_id = ?3
+               AND location_id IN (SELECT id FROM locations WHERE office_id = ?4)
+               AND NOT EXISTS (SELECT 1 FROM location_assignments o WHERE o.employee_id = ?1)",
+        )
+        .bind(survivor_id)
+        .bind(tenant_id)
+        .bind(source_id)
+        .bind(survivor_office_id)
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query(
+            "INSERT INTO archived_employees (id, tenant_id, first_name, last_name, birth_date, office_id, merged_into, archived_at)
+             SELECT id, tenant_id, first_name, last_name, birth_date, office_id, ?1, ?2 FROM employees WHERE tenant_id = ?3 AND id = ?4",
+        )
+        .bind(survivor_id)
+        .bind(Utc::now())
+        .bind(tenant_id)
+        .bind(source_id)
+        .execute(&mut *conn)
+        .await?;
+        let result = sqlx::query("DELETE FROM employees WHERE tenant_id = ?1 AND id = ?2")
+            .bind(tenant_id)
+            .bind(source_id)
+            .execute(&mut *conn)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+// Same peak as the Postgres query, dates are stored as YYYY-MM-DD text and compare in order
+async fn seated_employee_nr_by_office_id(
+    executor: impl SqliteExecutor<'_>,
+    tenant_id: i32,
+    office_id: i32,
+    from: NaiveDate,
+    until: Option<NaiveDate>,
+) -> anyhow::Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
+        "SELECT MAX((SELECT COUNT(*) FROM employees e
+                     WHERE e.tenant_id = ?1 AND e.office_id = ?2 AND e.hire_date <= days.day
+                       AND (e.termination_date IS NULL OR e.termination_date >= days.day)))
+         FROM (SELECT ?3 AS day
+               UNION
+               SELECT hire_date FROM employees
+               WHERE tenant_id = ?1 AND office_id = ?2 AND hire_date > ?3 AND (?4 IS NULL OR hire_date <= ?4)) AS days",
+    )
+    .bind(tenant_id)
+    .bind(office_id)
+    .bind(from)
+    .bind(until)
+    .fetch_one(executor)
+    .await?;
+    Ok(count)
+}
+
+async fn delete_employee(executor: impl SqliteExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+    let result = sqlx::query("DELETE FROM employees WHERE tenant_id = ?1 AND id = ?2")
+        .bind(tenant_id)
+        .bind(id)
+        .execute(executor)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+async fn create_employee(executor: impl SqliteExecutor<'_>, tenant_id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+    let created = sqlx::query_as::<_, Employee>(INSERT_EMPLOYEE)
+        .bind(tenant_id)
+        .bind(&employee.first_name)
+        .bind(&employee.last_name)
+        .bind(employee.birth_date)
+        .bind(employee.office_id)
+        .bind(employee.department_id)
+        .bind(employee.manager_id)
+        .bind(employee.hire_date)
+        .bind(employee.termination_date)
+        .bind(employee.status.as_str())
+        .fetch_one(executor)
+        .await?;
+    Ok(created)
+}
+
+async fn get_employees_by_birth_date(executor: impl SqliteExecutor<'_>, tenant_id: i32, birth_date: NaiveDate) -> anyhow::Result<Vec<Employee>> {
+    let employees = sqlx::query_as::<_, Employee>(
+        "SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status FROM employees WHERE tenant_id = ?1 AND birth_date = ?2",
+    )
+    .bind(tenant_id)
+    .bind(birth_date)
+    .fetch_all(executor)
+    .await?;
+    Ok(employees)
+}
+
+async fn get_employee_by_id(executor: impl SqliteExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Option<Employee>> {
+    let employee = sqlx::query_as::<_, Employee>(
+        "SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status FROM employees WHERE tenant_id = ?1 AND id = ?2",
+    )
+    .bind(tenant_id)
+    .bind(id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(employee)
+}
+
+async fn get_reporting_chain(executor: impl SqliteExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Vec<Employee>> {
+    let chain = sqlx::query_as::<_, Employee>(
+        "WITH RECURSIVE chain AS (
+             SELECT m.id, m.first_name, m.last_name, m.birth_date, m.office_id, m.department_id, m.manager_id, m.hire_date, m.termination_date, m.status, 1 AS depth
+             FROM employees e JOIN employees m ON m.tenant_id = e.tenant_id AND m.id = e.manager_id
+             WHERE e.tenant_id = ?1 AND e.id = ?2
+             UNION ALL
+             SELECT m.id, m.first_name, m.last_name, m.birth_date, m.office_id, m.department_id, m.manager_id, m.hire_date, m.termination_date, m.status, c.depth + 1
+             FROM employees m JOIN chain c ON m.id = c.manager_id
+             WHERE m.tenant_id = ?1 AND c.depth < ?3
+         )
+         SELECT id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status FROM chain ORDER BY depth",
+    )
+    .bind(tenant_id)
+    .bind(id)
+    .bind(MAX_HIERARCHY_DEPTH)
+    .fetch_all(executor)
+    .await?;
+    Ok(chain)
+}
+
+async fn update_employee_by_id(executor: impl SqliteExecutor<'_>, tenant_id: i32, id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+    let updated = sqlx::query_as::<_, Employee>(
+        "UPDATE employees SET first_name = ?1, last_name = ?2, birth_date = ?3, office_id = ?4, department_id = ?5, manager_id = ?6,
+             hire_date = ?7, termination_date = ?8, status = ?9
+         WHERE tenant_id = ?10 AND id = ?11
+         RETURNING id, first_name, last_name, birth_date, office_id, department_id, manager_id, hire_date, termination_date, status",
+    )
+    .bind(&employee.first_name)
+    .bind(&employee.last_name)
+    .bind(employee.birth_date)
+    .bind(employee.office_id)
+    .bind(employee.department_id)
+    .bind(employee.manager_id)
+    .bind(employee.hire_date)
+    .bind(employee.termination_date)
+    .bind(employee.status.as_str())
+    .bind(tenant_id)
+    .bind(id)
+    .fetch_one(executor)
+    .await?;
+    Ok(updated)
+}
+
+/// SQLite implementation of DepartmentStore
+#[derive(Clone)]
+pub struct SqliteDepartmentRepository {
+    pool: SqlitePool,
+}
+impl SqliteDepartmentRepository {
+    /// Constructor for SqliteDepartmentRepository
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl DepartmentStore for SqliteDepartmentRepository {
+    async fn create_department(&self, tenant_id: i32, department: &Department) -> anyhow::Result<Department> {
+        let created = sqlx::query_as::<_, Department>("INSERT INTO departments (tenant_id, name) VALUES (?1, ?2) RETURNING id, name")
+            .bind(tenant_id)
+            .bind(&department.name)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(created)
+    }
+
+    async fn get_department_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Department>> {
+        get_department_by_id(&self.pool, tenant_id, id).await
+    }
+
+    async fn get_department_by_name(&self, tenant_id: i32, name: &str) -> anyhow::Result<Option<Department>> {
+        let department = sqlx::query_as::<_, Department>("SELECT id, name FROM departments WHERE tenant_id = ?1 AND name = ?2")
+            .bind(tenant_id)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(department)
+    }
+
+    async fn get_all_departments(&self, tenant_id: i32) -> anyhow::Result<Vec<Department>> {
+        let departments = sqlx::query_as::<_, Department>("SELECT id, name FROM departments WHERE tenant_id = ?1 ORDER BY id")
+            .bind(tenant_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(departments)
+    }
+
+    async fn update_department_by_id(&self, tenant_id: i32, id: i32, department: &Department) -> anyhow::Result<Department> {
+        let updated = sqlx::query_as::<_, Department>("UPDATE departments SET name = ?1 WHERE tenant_id = ?2 AND id = ?3 RETURNING id, name")
+            .bind(&department.name)
+            .bind(tenant_id)
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(updated)
+    }
+
+    async fn delete_department(&self, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        let result = sqlx::query("DELETE FROM departments WHERE tenant_id = ?1 AND id = ?2")
+            .bind(tenant_id)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn employee_nr_by_department_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM employees WHERE tenant_id = ?1 AND department_id = ?2")
+            .bind(tenant_id)
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count)
+    }
+
+    async fn get_department_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Department>> {
+        get_department_by_id(tx.sqlite()?, tenant_id, id).await
+    }
+}
+
+async fn get_department_by_id(executor: impl SqliteExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Option<Department>> {
+    let department = sqlx::query_as::<_, Department>("SELECT id, name FROM departments WHERE tenant_id = ?1 AND id = ?2")
+        .bind(tenant_id)
+        .bind(id)
+        .fetch_optional(executor)
+        .await?;
+    Ok(department)
+}
+
+/// SQLite implementation of OfficeManagerStore
+#[derive(Clone)]
+pub struct SqliteOfficeManagerRepository {
+    pool: SqlitePool,
+}
+impl SqliteOfficeManagerRepository {
+    /// Constructor for SqliteOfficeManagerRepository
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl OfficeManagerStore for SqliteOfficeManagerRepository {
+    async fn get_office_ids_by_subject(&self, tenant_id: i32, subject: &str) -> anyhow::Result<Vec<i32>> {
+        let office_ids = sqlx::query_scalar::<_, i32>(
+            "SELECT m.office_id FROM office_managers m JOIN offices o ON o.id = m.office_id WHERE o.tenant_id = ?1 AND m.subject = ?2 ORDER BY m.office_id",
+        )
+        .bind(tenant_id)
+        .bind(subject)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(office_ids)
+    }
+
+    async fn get_subjects_by_office_id(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<String>> {
+        let subjects = sqlx::query_scalar::<_, String>(
+            "SELECT m.subject FROM office_managers m JOIN offices o ON o.id = m.office_id WHERE o.tenant_id = ?1 AND m.office_id = ?2 ORDER BY m.subject",
+        )
+        .bind(tenant_id)
+        .bind(office_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(subjects)
+    }
+
+    async fn add_office_manager(&self, tenant_id: i32, subject: &str, office_id: i32) -> anyhow::Result<()> {
+        sqlx::query("INSERT INTO office_managers (subject, office_id) SELECT ?1, id FROM offices WHERE tenant_id = ?2 AND id = ?3 ON CONFLICT DO NOTHING")
+            .bind(subject)
+            .bind(tenant_id)
+            .bind(office_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_office_manager(&self, tenant_id: i32, subject: &str, office_id: i32) -> anyhow::Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM office_managers WHERE subject = ?1 AND office_id = ?2
+             AND office_id IN (SELECT id FROM offices WHERE tenant_id = ?3)",
+        )
+        .bind(subject)
+        .bind(office_id)
+        .bind(tenant_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// SQLite implementation of IdempotencyStore
+#[derive(Clone)]
+pub struct SqliteIdempotencyRepository {
+    pool: SqlitePool,
+}
+impl SqliteIdempotencyRepository {
+    /// Constructor for SqliteIdempotencyRepository
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for SqliteIdempotencyRepository {
+    async fn reserve_idempotency_key(
+        &self,
+        key: &IdempotencyKey,
+        request_hash: &str,
+        expired_before: DateTime<Utc>,
+    ) -> anyhow::Result<bool> {
+        // created_at is bound from here so every row uses the same text format and compares correctly
+        let result = sqlx::query(
+            "INSERT INTO idempotency_keys (tenant_id, subject, route, idempotency_key, request_hash, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (tenant_id, subject, route, idempotency_key) DO UPDATE
+             SET request_hash = excluded.request_hash, status_code = NULL, content_type = NULL, response_body = NULL, created_at = excluded.created_at
+             WHERE idempotency_keys.created_at < ?7",
+        )
+        .bind(key.tenant_id)
+        .bind(&key.subject)
+        .bind(&key.route)
+        .bind(&key.key)
+        .bind(request_hash)
+        .bind(Utc::now())
+        .bind(expired_before)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn get_idempotency_record(&self, key: &IdempotencyKey) -> anyhow::Result<Option<IdempotencyRecord>> {
+        let record = sqlx::query_as::<_, IdempotencyRecord>(
+            "SELECT request_hash, status_code, content_type, response_body, created_at FROM idempotency_keys
+             WHERE tenant_id = ?1 AND subject = ?2 AND route = ?3 AND idempotency_key = ?4",
+        )
+        .bind(key.tenant_id)
+        .bind(&key.subject)
+        .bind(&key.route)
+        .bind(&key.key)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record)
+    }
+
+    async fn complete_idempotency_key(
+        &self,
+        key: &IdempotencyKey,
+        status_code: i32,
+        content_type: Option<&str>,
+        response_body: &[u8],
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE idempotency_keys SET status_code = ?1, content_type = ?2, response_body = ?3
+             WHERE tenant_id = ?4 AND subject = ?5 AND route = ?6 AND idempotency_key = ?7",
+        )
+        .bind(status_code)
+        .bind(content_type)
+        .bind(response_body)
+        .bind(key.tenant_id)
+        .bind(&key.subject)
+        .bind(&key.route)
+        .bind(&key.key)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_idempotency_key(&self, key: &IdempotencyKey) -> anyhow::Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM idempotency_keys WHERE tenant_id = ?1 AND subject = ?2 AND route = ?3 AND idempotency_key = ?4",
+        )
+        .bind(key.tenant_id)
+        .bind(&key.subject)
+        .bind(&key.route)
+        .bind(&key.key)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_expired_idempotency_keys(&self, expired_before: DateTime<Utc>) -> anyhow::Result<u64> {
+        let result = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < ?1")
+            .bind(expired_before)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// SQLite implementation of AuditStore
+#[derive(Clone)]
+pub struct SqliteAuditRepository {
+    pool: SqlitePool,
+}
+impl SqliteAuditRepository {
+    /// Constructor for SqliteAuditRepository
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AuditStore for SqliteAuditRepository {
+    async fn record_audit_entry_tx(&self, tx: &mut Transaction, tenant_id: i32, entry: &AuditEntry) -> anyhow::Result<AuditEntry> {
+        let recorded = sqlx::query_as::<_, AuditEntry>(
+            "INSERT INTO audit_entries (tenant_id, subject, action, employee_id, details, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             RETURNING id, subject, action, employee_id, details, created_at",
+        )
+        .bind(tenant_id)
+        .bind(&entry.subject)
+        .bind(&entry.action)
+        .bind(entry.employee_id)
+        .bind(&entry.details)
+        .bind(Utc::now())
+        .fetch_one(tx.sqlite()?)
+        .await?;
+        Ok(recorded)
+    }
+
+    async fn get_audit_entries_by_employee_id(&self, tenant_id: i32, employee_id: i32) -> anyhow::Result<Vec<AuditEntry>> {
+        let entries = sqlx::query_as::<_, AuditEntry>(
+            "SELECT id, subject, action, employee_id, details, created_at FROM audit_entries WHERE tenant_id = ?1 AND employee_id = ?2 ORDER BY id",
+        )
+        .bind(tenant_id)
+        .bind(employee_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+}
+
+/// SQLite implementation of TransferStore
+#[derive(Clone)]
+pub struct SqliteTransferRepository {
+    pool: SqlitePool,
+}
+impl SqliteTransferRepository {
+    /// Constructor for SqliteTransferRepository
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Row of get_due_transfers, the transfer with its tenant
+#[derive(sqlx::FromRow)]
+struct DueTransferRow {
+    tenant_id: i32,
+    #[sqlx(flatten)]
+    transfer: Transfer,
+}
+
+#[async_trait]
+impl TransferStore for SqliteTransferRepository {
+    async fn create_transfer_tx(&self, tx: &mut Transaction, tenant_id: i32, transfer: &Transfer) -> anyhow::Result<Transfer> {
+        let created = sqlx::query_as::<_, Transfer>(
+            "INSERT INTO transfers (tenant_id, employee_id, from_office_id, to_office_id, effective_date, status, requested_by, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             RETURNING id, employee_id, from_office_id, to_office_id, effective_date, status, requested_by, created_at",
+        )
+        .bind(tenant_id)
+        .bind(transfer.employee_id)
+        .bind(transfer.from_office_id)
+        .bind(transfer.to_office_id)
+        .bind(transfer.effective_date)
+        .bind(transfer.status.as_str())
+        .bind(&transfer.requested_by)
+        .bind(Utc::now())
+        .fetch_one(tx.sqlite()?)
+        .await?;
+        Ok(created)
+    }
+
+    async fn get_transfer_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Transfer>> {
+        get_transfer_by_id(&self.pool, tenant_id, id).await
+    }
+
+    async fn get_transfer_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Transfer>> {
+        get_transfer_by_id(tx.sqlite()?, tenant_id, id).await
+    }
+
+    async fn get_transfers_by_office_id(&self, tenant_id: i32, office_id: i32, status: Option<TransferStatus>) -> anyhow::Result<Vec<Transfer>> {
+        let transfers = sqlx::query_as::<_, Transfer>(
+            "SELECT id, employee_id, from_office_id, to_office_id, effective_date, status, requested_by, created_at
+             FROM transfers WHERE tenant_id = ?1 AND (from_office_id = ?2 OR to_office_id = ?2) AND (?3 IS NULL OR status = ?3)
+             ORDER BY effective_date, id",
+        )
+        .bind(tenant_id)
+        .bind(office_id)
+        .bind(status.map(|s| s.as_str()))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(transfers)
+    }
+
+    async fn get_pending_transfers_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<Transfer>> {
+        let transfers = sqlx::query_as::<_, Transfer>(
+            "SELECT t.id, t.employee_id, t.from_office_id, t.to_office_id, t.effective_date, t.status, t.requested_by, t.created_at
+             FROM transfers t JOIN employees e ON e.tenant_id = t.tenant_id AND e.id = t.employee_id
+             WHERE t.tenant_id = ?1 AND t.status = 'pending' AND (t.to_office_id = ?2 OR e.office_id = ?2)
+             ORDER BY t.effective_date, t.id",
+        )
+        .bind(tenant_id)
+        .bind(office_id)
+        .fetch_all(tx.sqlite()?)
+        .await?;
+        Ok(transfers)
+    }
+
+    async fn update_transfer_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, transfer: &Transfer) -> anyhow::Result<Transfer> {
+        let updated = sqlx::query_as::<_, Transfer>(
+            "UPDATE transfers SET effective_date = ?1, status = ?2 WHERE tenant_id = ?3 AND id = ?4
+             RETURNING id, employee_id, from_office_id, to_office_id, effective_date, status, requested_by, created_at",
+        )
+        .bind(transfer.effective_date)
+        .bind(transfer.status.as_str())
+        .bind(tenant_id)
+        .bind(id)
+        .fetch_one(tx.sqlite()?)
+        .await?;
+        Ok(updated)
+    }
+
+    async fn get_due_transfers(&self, on: NaiveDate) -> anyhow::Result<Vec<(i32, Transfer)>> {
+        let rows = sqlx::query_as::<_, DueTransferRow>(
+            "SELECT tenant_id, id, employee_id, from_office_id, to_office_id, effective_date, status, requested_by, created_at
+             FROM transfers WHERE status = 'pending' AND effective_date <= ?1 ORDER BY effective_date, id",
+        )
+        .bind(on)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| (row.tenant_id, row.transfer)).collect())
+    }
+}
+
+async fn get_transfer_by_id(executor: impl SqliteExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Option<Transfer>> {
+    let transfer = sqlx::query_as::<_, Transfer>(
+        "SELECT id, employee_id, from_office_id, to_office_id, effective_date, status, requested_by, created_at FROM transfers WHERE tenant_id = ?1 AND id = ?2",
+    )
+    .bind(tenant_id)
+    .bind(id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(transfer)
+}
+
+/// SQLite implementation of ChangeRequestStore
+#[derive(Clone)]
+pub struct SqliteChangeRequestRepository {
+    pool: SqlitePool,
+}
+impl SqliteChangeRequestRepository {
+    /// Constructor for SqliteChangeRequestRepository
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ChangeRequestStore for SqliteChangeRequestRepository {
+    async fn create_change_request_tx(&self, tx: &mut Transaction, tenant_id: i32, request: &ChangeRequest, steps: &[ApprovalStep]) -> anyhow::Result<ChangeRequest> {
+        let conn = tx.sqlite()?;
+        let created = sqlx::query_as::<_, ChangeRequest>(
+            "INSERT INTO change_requests (tenant_id, change, status, requested_by, created_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             RETURNING id, change, status, requested_by, created_at, expires_at, resolved_at, failure_reason",
+        )
+        .bind(tenant_id)
+        .bind(&request.change)
+        .bind(request.status.as_str())
+        .bind(&request.requested_by)
+        .bind(Utc::now())
+        .bind(request.expires_at)
+        .fetch_one(&mut *conn)
+        .await?;
+        for (position, step) in steps.iter().enumerate() {
+            sqlx::query("INSERT INTO change_request_approvals (tenant_id, change_request_id, step, position) VALUES (?1, ?2, ?3, ?4)")
+                .bind(tenant_id)
+                .bind(created.id)
+                .bind(step.key())
+                .bind(position as i64 + 1)
+                .execute(&mut *conn)
+                .await?;
+        }
+        Ok(created)
+    }
+
+    async fn get_change_request_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<ChangeRequest>> {
+        get_change_request_by_id(&self.pool, tenant_id, id).await
+    }
+
+    async fn get_change_request_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<ChangeRequest>> {
+        get_change_request_by_id(tx.sqlite()?, tenant_id, id).await
+    }
+
+    async fn get_change_requests(&self, tenant_id: i32, status: Option<ChangeRequestStatus>) -> anyhow::Result<Vec<ChangeRequest>> {
+        let requests = sqlx::query_as::<_, ChangeRequest>(
+            "SELECT id, change, status, requested_by, created_at, expires_at, resolved_at, failure_reason
+             FROM change_requests WHERE tenant_id = ?1 AND (?2 IS NULL OR status = ?2) ORDER BY id",
+        )
+        .bind(tenant_id)
+        .bind(status.map(|s| s.as_str()))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(requests)
+    }
+
+    async fn update_change_request_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, request: &ChangeRequest) -> anyhow::Result<ChangeRequest> {
+        let updated = sqlx::query_as::<_, ChangeRequest>(
+            "UPDATE change_requests SET status = ?1, resolved_at = ?2, failure_reason = ?3 WHERE tenant_id = ?4 AND id = ?5
+             RETURNING id, change, status, requested_by, created_at, expires_at, resolved_at, failure_reason",
+        )
+        .bind(request.status.as_str())
+        .bind(request.resolved_at)
+        .bind(&request.failure_reason)
+        .bind(tenant_id)
+        .bind(id)
+        .fetch_one(tx.sqlite()?)
+        .await?;
+        Ok(updated)
+    }
+
+    async fn get_approvals(&self, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Approval>> {
+        get_approvals(&self.pool, tenant_id, change_request_id).await
+    }
+
+    async fn get_approvals_tx(&self, tx: &mut Transaction, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Approval>> {
+        get_approvals(tx.sqlite()?, tenant_id, change_request_id).await
+    }
+
+    async fn decide_approval_tx(&self, tx: &mut Transaction, tenant_id: i32, change_request_id: i32, step: &str, decision: Decision, decided_by: &str) -> anyhow::Result<u64> {
+        let result = sqlx::query(
+            "UPDATE change_request_approvals SET decision = ?1, decided_by = ?2, decided_at = ?3
+             WHERE tenant_id = ?4 AND change_request_id = ?5 AND step = ?6 AND decision IS NULL",
+        )
+        .bind(decision.as_str())
+        .bind(decided_by)
+        .bind(Utc::now())
+        .bind(tenant_id)
+        .bind(change_request_id)
+        .bind(step)
+        .execute(tx.sqlite()?)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn create_comment_tx(&self, tx: &mut Transaction, tenant_id: i32, comment: &Comment) -> anyhow::Result<Comment> {
+        let created = sqlx::query_as::<_, Comment>(
+            "INSERT INTO change_request_comments (tenant_id, change_request_id, author, body, created_at) VALUES (?1, ?2, ?3, ?4, ?5)
+             RETURNING id, change_request_id, author, body, created_at",
+        )
+        .bind(tenant_id)
+        .bind(comment.change_request_id)
+        .bind(&comment.author)
+        .bind(&comment.body)
+        .bind(Utc::now())
+        .fetch_one(tx.sqlite()?)
+        .await?;
+        Ok(created)
+    }
+
+    async fn get_comments(&self, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Comment>> {
+        let comments = sqlx::query_as::<_, Comment>(
+            "SELECT id, change_request_id, author, body, created_at FROM change_request_comments
+             WHERE tenant_id = ?1 AND change_request_id = ?2 ORDER BY id",
+        )
+        .bind(tenant_id)
+        .bind(change_request_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(comments)
+    }
+}
+
+async fn get_change_request_by_id(executor: impl SqliteExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Option<ChangeRequest>> {
+    let request = sqlx::query_as::<_, ChangeRequest>(
+        "SELECT id, change, status, requested_by, created_at, expires_at, resolved_at, failure_reason
+         FROM change_requests WHERE tenant_id = ?1 AND id = ?2",
+    )
+    .bind(tenant_id)
+    .bind(id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(request)
+}
+
+async fn get_approvals(executor: impl SqliteExecutor<'_>, tenant_id: i32, change_request_id: i32) -> anyhow::Result<Vec<Approval>> {
+    let approvals = sqlx::query_as::<_, Approval>(
+        "SELECT change_request_id, step, decision, decided_by, decided_at
+         FROM change_request_approvals WHERE tenant_id = ?1 AND change_request_id = ?2 ORDER BY position",
+    )
+    .bind(tenant_id)
+    .bind(change_request_id)
+    .fetch_all(executor)
+    .await?;
+    Ok(approvals)
+}
+
+/// SQLite implementation of WaitlistStore
+#[derive(Clone)]
+pub struct SqliteWaitlistRepository {
+    pool: SqlitePool,
+}
+impl SqliteWaitlistRepository {
+    /// Constructor for SqliteWaitlistRepository
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl WaitlistStore for SqliteWaitlistRepository {
+    async fn create_entry_tx(&self, tx: &mut Transaction, tenant_id: i32, entry: &WaitlistEntry) -> anyhow::Result<WaitlistEntry> {
+        let created = sqlx::query_as::<_, WaitlistEntry>(
+            "INSERT INTO waitlist_entries (tenant_id, office_id, first_name, last_name, birth_date, department_id, manager_id, hire_date, confirm, status, requested_by, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+             RETURNING id, office_id, first_name, last_name, birth_date, department_id, manager_id, hire_date, confirm, status, employee_id, requested_by, created_at",
+        )
+        .bind(tenant_id)
+        .bind(entry.office_id)
+        .bind(&entry.first_name)
+        .bind(&entry.last_name)
+        .bind(entry.birth_date)
+        .bind(entry.department_id)
+        .bind(entry.manager_id)
+        .bind(entry.hire_date)
+        .bind(entry.confirm)
+        .bind(entry.status.as_str())
+        .bind(&entry.requested_by)
+        .bind(Utc::now())
+        .fetch_one(tx.sqlite()?)
+        .await?;
+        Ok(created)
+    }
+
+    async fn get_entry_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<WaitlistEntry>> {
+        get_waitlist_entry_by_id(&self.pool, tenant_id, id).await
+    }
+
+    async fn get_entry_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<WaitlistEntry>> {
+        get_waitlist_entry_by_id(tx.sqlite()?, tenant_id, id).await
+    }
+
+    async fn get_entries_by_office_id(&self, tenant_id: i32, office_id: i32, status: Option<WaitlistStatus>) -> anyhow::Result<Vec<WaitlistEntry>> {
+        get_waitlist_entries_by_office_id(&self.pool, tenant_id, office_id, status).await
+    }
+
+    async fn get_entries_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<WaitlistEntry>> {
+        get_waitlist_entries_by_office_id(tx.sqlite()?, tenant_id, office_id, None).await
+    }
+
+    async fn update_entry_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, entry: &WaitlistEntry) -> anyhow::Result<WaitlistEntry> {
+        let updated = sqlx::query_as::<_, WaitlistEntry>(
+            "UPDATE waitlist_entries SET status = ?1, employee_id = ?2 WHERE tenant_id = ?3 AND id = ?4
+             RETURNING id, office_id, first_name, last_name, birth_date, department_id, manager_id, hire_date, confirm, status, employee_id, requested_by, created_at",
+        )
+        .bind(entry.status.as_str())
+        .bind(entry.employee_id)
+        .bind(tenant_id)
+        .bind(id)
+        .fetch_one(tx.sqlite()?)
+        .await?;
+        Ok(updated)
+    }
+
+    async fn get_waiting_office_ids(&self) -> anyhow::Result<Vec<(i32, i32)>> {
+        let offices = sqlx::query_as::<_, (i32, i32)>(
+            "SELECT DISTINCT tenant_id, office_id FROM waitlist_entries WHERE status = 'waiting' ORDER BY tenant_id, office_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(offices)
+    }
+
+    async fn create_event_tx(&self, tx: &mut Transaction, tenant_id: i32, event: &WaitlistEvent) -> anyhow::Result<WaitlistEvent> {
+        let created = sqlx::query_as::<_, WaitlistEvent>(
+            "INSERT INTO waitlist_events (tenant_id, entry_id, event, details, created_at) VALUES (?1, ?2, ?3, ?4, ?5)
+             RETURNING id, entry_id, event, details, created_at",
+        )
+        .bind(tenant_id)
+        .bind(event.entry_id)
+        .bind(event.event.as_str())
+        .bind(&event.details)
+        .bind(Utc::now())
+        .fetch_one(tx.sqlite()?)
+        .await?;
+        Ok(created)
+    }
+
+    async fn get_events(&self, tenant_id: i32, entry_id: i32) -> anyhow::Result<Vec<WaitlistEvent>> {
+        let events = sqlx::query_as::<_, WaitlistEvent>(
+            "SELECT id, entry_id, event, details, created_at FROM waitlist_events WHERE tenant_id = ?1 AND entry_id = ?2 ORDER BY id",
+        )
+        .bind(tenant_id)
+        .bind(entry_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(events)
+    }
+}
+
+async fn get_waitlist_entry_by_id(executor: impl SqliteExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Option<WaitlistEntry>> {
+    let entry = sqlx::query_as::<_, WaitlistEntry>(
+        "SELECT id, office_id, first_name, last_name, birth_date, department_id, manager_id, hire_date, confirm, status, employee_id, requested_by, created_at
+         FROM waitlist_entries WHERE tenant_id = ?1 AND id = ?2",
+    )
+    .bind(tenant_id)
+    .bind(id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(entry)
+}
+
+async fn get_waitlist_entries_by_office_id(executor: impl SqliteExecutor<'_>, tenant_id: i32, office_id: i32, status: Option<WaitlistStatus>) -> anyhow::Result<Vec<WaitlistEntry>> {
+    let entries = sqlx::query_as::<_, WaitlistEntry>(
+        "SELECT id, office_id, first_name, last_name, birth_date, department_id, manager_id, hire_date, confirm, status, employee_id, requested_by, created_at
+         FROM waitlist_entries WHERE tenant_id = ?1 AND office_id = ?2 AND (?3 IS NULL OR status = ?3) ORDER BY id",
+    )
+    .bind(tenant_id)
+    .bind(office_id)
+    .bind(status.map(|s| s.as_str()))
+    .fetch_all(executor)
+    .await?;
+    Ok(entries)
+}
+
+/// SQLite implementation of LocationStore
+#[derive(Clone)]
+pub struct SqliteLocationRepository {
+    pool: SqlitePool,
+}
+impl SqliteLocationRepository {
+    /// Constructor for SqliteLocationRepository
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl LocationStore for SqliteLocationRepository {
+    async fn create_location_tx(&self, tx: &mut Transaction, tenant_id: i32, location: &Location) -> anyhow::Result<Location> {
+        let id = sqlx::query_scalar::<_, i32>(
+            "INSERT INTO locations (tenant_id, parent_id, kind, name, capacity, office_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6) RETURNING id",
+        )
+        .bind(tenant_id)
+        .bind(location.parent_id)
+        .bind(location.kind.as_str())
+        .bind(&location.name)
+        .bind(location.capacity)
+        .bind(location.office_id)
+        .fetch_one(tx.sqlite()?)
+        .await?;
+        get_location_by_id(tx.sqlite()?, tenant_id, id).await
+    }
+
+    async fn get_locations(&self, tenant_id: i32) -> anyhow::Result<Vec<Location>> {
+        get_locations(&self.pool, tenant_id).await
+    }
+
+    async fn get_locations_tx(&self, tx: &mut Transaction, tenant_id: i32) -> anyhow::Result<Vec<Location>> {
+        get_locations(tx.sqlite()?, tenant_id).await
+    }
+
+    async fn update_location_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, location: &Location) -> anyhow::Result<Location> {
+        let result = sqlx::query(
+            "UPDATE locations SET name = ?1, capacity = CASE WHEN kind = 'site' THEN NULL ELSE ?2 END, parent_id = ?3 WHERE tenant_id = ?4 AND id = ?5",
+        )
+        .bind(&location.name)
+        .bind(location.capacity)
+        .bind(location.parent_id)
+        .bind(tenant_id)
+        .bind(id)
+        .execute(tx.sqlite()?)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        get_location_by_id(tx.sqlite()?, tenant_id, id).await
+    }
+
+    async fn delete_location_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<u64> {
+        let result = sqlx::query("DELETE FROM locations WHERE tenant_id = ?1 AND id = ?2")
+            .bind(tenant_id)
+            .bind(id)
+            .execute(tx.sqlite()?)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn get_occupants(&self, tenant_id: i32, from: NaiveDate) -> anyhow::Result<Vec<(i32, i64)>> {
+        get_occupants(&self.pool, tenant_id, from).await
+    }
+
+    async fn get_occupants_tx(&self, tx: &mut Transaction, tenant_id: i32, from: NaiveDate) -> anyhow::Result<Vec<(i32, i64)>> {
+        get_occupants(tx.sqlite()?, tenant_id, from).await
+    }
+
+    async fn get_room_id_tx(&self, tx: &mut Transaction, tenant_id: i32, employee_id: i32) -> anyhow::Result<Option<i32>> {
+        let room_id = sqlx::query_scalar::<_, i32>(
+            "SELECT r.id FROM location_assignments a
+             JOIN employees e ON e.id = a.employee_id
+             JOIN locations r ON r.id = a.location_id AND r.office_id = e.office_id
+             WHERE a.tenant_id = ?1 AND a.employee_id = ?2",
+        )
+        .bind(tenant_id)
+        .bind(employee_id)
+        .fetch_optional(tx.sqlite()?)
+        .await?;
+        Ok(room_id)
+    }
+
+    async fn assign_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, employee_id: i32, location_id: i32) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO location_assignments (tenant_id, employee_id, location_id) VALUES (?1, ?2, ?3)
+             ON CONFLICT (employee_id) DO UPDATE SET location_id = excluded.location_id",
+        )
+        .bind(tenant_id)
+        .bind(employee_id)
+        .bind(location_id)
+        .execute(tx.sqlite()?)
+        .await?;
+        Ok(())
+    }
+
+    async fn unassign_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, employee_id: i32, location_id: i32) -> anyhow::Result<u64> {
+        let result = sqlx::query("DELETE FROM location_assignments WHERE tenant_id = ?1 AND employee_id = ?2 AND location_id = ?3")
+            .bind(tenant_id)
+            .bind(employee_id)
+            .bind(location_id)
+            .execute(tx.sqlite()?)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+async fn get_location_by_id(executor: impl SqliteExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Location> {
+    let location = sqlx::query_as::<_, Location>(
+        "SELECT l.id, l.parent_id, l.kind, l.name, COALESCE(l.capacity, o.max_occupancy) AS capacity, l.office_id
+         FROM locations l LEFT JOIN offices o ON l.kind = 'site' AND o.id = l.office_id
+         WHERE l.tenant_id = ?1 AND l.id = ?2",
+    )
+    .bind(tenant_id)
+    .bind(id)
+    .fetch_one(executor)
+    .await?;
+    Ok(location)
+}
+
+async fn get_locations(executor: impl SqliteExecutor<'_>, tenant_id: i32) -> anyhow::Result<Vec<Location>> {
+    let locations = sqlx::query_as::<_, Location>(
+        "SELECT l.id, l.parent_id, l.kind, l.name, COALESCE(l.capacity, o.max_occupancy) AS capacity, l.office_id
+         FROM locations l LEFT JOIN offices o ON l.kind = 'site' AND o.id = l.office_id
+         WHERE l.tenant_id = ?1 ORDER BY l.id",
+    )
+    .bind(tenant_id)
+    .fetch_all(executor)
+    .await?;
+    Ok(locations)
+}
+
+async fn get_occupants(executor: impl SqliteExecutor<'_>, tenant_id: i32, from: NaiveDate) -> anyhow::Result<Vec<(i32, i64)>> {
+    let occupants = sqlx::query_as::<_, (i32, i64)>(
+        "SELECT COALESCE(r.id, s.id), COUNT(*)
+         FROM employees e
+         LEFT JOIN location_assignments a ON a.employee_id = e.id
+         LEFT JOIN locations r ON r.id = a.location_id AND r.office_id = e.office_id
+         LEFT JOIN locations s ON s.kind = 'site' AND s.office_id = e.office_id
+         WHERE e.tenant_id = ?1 AND (e.termination_date IS NULL OR e.termination_date >= ?2) AND COALESCE(r.id, s.id) IS NOT NULL
+         GROUP BY COALESCE(r.id, s.id)",
+    )
+    .bind(tenant_id)
+    .bind(from)
+    .fetch_all(executor)
+    .await?;
+    Ok(occupants)
+}
+
+/// SQLite implementation of HealthStore
+#[derive(Clone)]
+pub struct SqliteHealthRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteHealthRepository {
+    /// Constructor for SqliteHealthRepository
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl HealthStore for SqliteHealthRepository {
+    async fn ping(&self) -> anyhow::Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+}
+
+/// SQLite implementation of UnitOfWork
+/// BEGIN IMMEDIATE takes the write lock up front, so transactions run one after the other
+/// and only a busy database (another process holding the lock) leads to a retry
+#[derive(Clone)]
+pub struct SqliteUnitOfWork {
+    pool: SqlitePool,
+}
+
+impl SqliteUnitOfWork {
+    /// Constructor for SqliteUnitOfWork
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl UnitOfWork for SqliteUnitOfWork {
+    async fn begin(&self) -> anyhow::Result<Transaction> {
+        let tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        Ok(Transaction::Sqlite(tx))
+    }
+}
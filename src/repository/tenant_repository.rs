@@ -0,0 +1,39 @@
+use crate::entity::tenant::Tenant;
+use sqlx::PgPool;
+
+/// Repository for Tenant entities in the database
+/// Tenants are provisioned by operators, the API itself only reads them
+#[derive(Clone)]
+pub struct TenantRepository {
+    pool: PgPool,
+}
+impl TenantRepository {
+    /// Constructor for TenantRepository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Inserts a tenant and returns the created tenant with its ID
+    pub async fn create_tenant(&self, tenant: &Tenant) -> anyhow::Result<Tenant> {
+        let created = sqlx::query_as!(
+            Tenant,
+            "INSERT INTO tenants (name) VALUES ($1) RETURNING id, name",
+            tenant.name
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(created)
+    }
+
+    /// Retrieves a tenant by its ID
+    pub async fn get_tenant_by_id(&self, id: i32) -> anyhow::Result<Option<Tenant>> {
+        let tenant = sqlx::query_as!(
+            Tenant,
+            "SELECT id, name FROM tenants WHERE id = $1",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(tenant)
+    }
+}
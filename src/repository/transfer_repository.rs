@@ -0,0 +1,161 @@
+use crate::entity::transfer::{Transfer, TransferStatus};
+use crate::repository::unit_of_work::Transaction;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use sqlx::{PgExecutor, PgPool};
+
+/// Storage of Transfer entities, implemented for Postgres (TransferRepository) and in memory (MemoryTransferRepository)
+/// Every method but get_due_transfers is scoped to one tenant. An employee has at most one pending transfer,
+/// transfers go along when their employee or one of their offices is deleted
+#[async_trait]
+pub trait TransferStore: Send + Sync {
+    /// Inserts a transfer inside a transaction and returns it with its ID
+    async fn create_transfer_tx(&self, tx: &mut Transaction, tenant_id: i32, transfer: &Transfer) -> anyhow::Result<Transfer>;
+
+    /// Retrieves a transfer by its ID
+    async fn get_transfer_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Transfer>>;
+
+    /// Retrieves a transfer by its ID inside a transaction
+    async fn get_transfer_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Transfer>>;
+
+    /// Retrieves the transfers into or out of an office, optionally only those with a status,
+    /// ordered by effective date and ID
+    async fn get_transfers_by_office_id(&self, tenant_id: i32, office_id: i32, status: Option<TransferStatus>) -> anyhow::Result<Vec<Transfer>>;
+
+    /// Retrieves the pending transfers into an office and those of employees sitting in it now, inside a transaction
+    async fn get_pending_transfers_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<Transfer>>;
+
+    /// Updates effective date and status of a transfer inside a transaction and returns the updated transfer
+    async fn update_transfer_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, transfer: &Transfer) -> anyhow::Result<Transfer>;
+
+    /// Retrieves the pending transfers of all tenants that take effect on or before a day, with their tenant,
+    /// ordered by effective date and ID. Used by the transfer job only
+    async fn get_due_transfers(&self, on: NaiveDate) -> anyhow::Result<Vec<(i32, Transfer)>>;
+}
+
+/// Repository for Transfer entities in the database
+#[derive(Clone)]
+pub struct TransferRepository {
+    pool: PgPool,
+}
+impl TransferRepository {
+    /// Constructor for TransferRepository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TransferStore for TransferRepository {
+    async fn create_transfer_tx(&self, tx: &mut Transaction, tenant_id: i32, transfer: &Transfer) -> anyhow::Result<Transfer> {
+        let created = sqlx::query_as!(
+            Transfer,
+            r#"INSERT INTO transfers (tenant_id, employee_id, from_office_id, to_office_id, effective_date, status, requested_by)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING id, employee_id, from_office_id, to_office_id, effective_date, status AS "status: TransferStatus", requested_by, created_at"#,
+            tenant_id,
+            transfer.employee_id,
+            transfer.from_office_id,
+            transfer.to_office_id,
+            transfer.effective_date,
+            transfer.status.as_str(),
+            transfer.requested_by
+        )
+        .fetch_one(tx.postgres()?)
+        .await?;
+        Ok(created)
+    }
+
+    async fn get_transfer_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<Transfer>> {
+        get_transfer_by_id(&self.pool, tenant_id, id).await
+    }
+
+    async fn get_transfer_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<Transfer>> {
+        get_transfer_by_id(tx.postgres()?, tenant_id, id).await
+    }
+
+    async fn get_transfers_by_office_id(&self, tenant_id: i32, office_id: i32, status: Option<TransferStatus>) -> anyhow::Result<Vec<Transfer>> {
+        let transfers = sqlx::query_as!(
+            Transfer,
+            r#"SELECT id, employee_id, from_office_id, to_office_id, effective_date, status AS "status: TransferStatus", requested_by, created_at
+             FROM transfers WHERE tenant_id = $1 AND (from_office_id = $2 OR to_office_id = $2) AND ($3::TEXT IS NULL OR status = $3)
+             ORDER BY effective_date, id"#,
+            tenant_id,
+            office_id,
+            status.map(|s| s.as_str())
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(transfers)
+    }
+
+    async fn get_pending_transfers_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<Transfer>> {
+        let transfers = sqlx::query_as!(
+            Transfer,
+            r#"SELECT t.id, t.employee_id, t.from_office_id, t.to_office_id, t.effective_date, t.status AS "status: TransferStatus", t.requested_by, t.created_at
+             FROM transfers t JOIN employees e ON e.tenant_id = t.tenant_id AND e.id = t.employee_id
+             WHERE t.tenant_id = $1 AND t.status = 'pending' AND (t.to_office_id = $2 OR e.office_id = $2)
+             ORDER BY t.effective_date, t.id"#,
+            tenant_id,
+            office_id
+        )
+        .fetch_all(tx.postgres()?)
+        .await?;
+        Ok(transfers)
+    }
+
+    async fn update_transfer_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, transfer: &Transfer) -> anyhow::Result<Transfer> {
+        let updated = sqlx::query_as!(
+            Transfer,
+            r#"UPDATE transfers SET effective_date = $1, status = $2 WHERE tenant_id = $3 AND id = $4
+             RETURNING id, employee_id, from_office_id, to_office_id, effective_date, status AS "status: TransferStatus", requested_by, created_at"#,
+            transfer.effective_date,
+            transfer.status.as_str(),
+            tenant_id,
+            id
+        )
+        .fetch_one(tx.postgres()?)
+        .await?;
+        Ok(updated)
+    }
+
+    async fn get_due_transfers(&self, on: NaiveDate) -> anyhow::Result<Vec<(i32, Transfer)>> {
+        let rows = sqlx::query!(
+            r#"SELECT tenant_id, id, employee_id, from_office_id, to_office_id, effective_date, status AS "status: TransferStatus", requested_by, created_at
+             FROM transfers WHERE status = 'pending' AND effective_date <= $1 ORDER BY effective_date, id"#,
+            on
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| {
+                let transfer = Transfer {
+                    id: Some(r.id),
+                    employee_id: r.employee_id,
+                    from_office_id: r.from_office_id,
+                    to_office_id: r.to_office_id,
+                    effective_date: r.effective_date,
+                    status: r.status,
+                    requested_by: r.requested_by,
+                    created_at: r.created_at,
+                };
+                (r.tenant_id, transfer)
+            })
+            .collect())
+    }
+}
+
+// Query shared by the pool and the transaction method
+async fn get_transfer_by_id(executor: impl PgExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Option<Transfer>> {
+    let transfer = sqlx::query_as!(
+        Transfer,
+        r#"SELECT id, employee_id, from_office_id, to_office_id, effective_date, status AS "status: TransferStatus", requested_by, created_at
+         FROM transfers WHERE tenant_id = $1 AND id = $2"#,
+        tenant_id,
+        id
+    )
+    .fetch_optional(executor)
+    .await?;
+    Ok(transfer)
+}
@@ -0,0 +1,216 @@
+use crate::repository::memory_repository::MemoryTransaction;
+use crate::repository::read_routing::mark_written;
+use async_trait::async_trait;
+use sqlx::{PgConnection, PgPool};
+use std::time::Duration;
+
+/// An open database transaction
+/// Services get one from a UnitOfWork and pass it to the `*_tx` repository methods,
+/// so writes through several repositories commit or roll back together.
+/// Dropping it without commit rolls back.
+pub enum Transaction {
+    Postgres(sqlx::Transaction<'static, sqlx::Postgres>),
+    #[cfg(feature = "sqlite")]
+    Sqlite(sqlx::Transaction<'static, sqlx::Sqlite>),
+    Memory(MemoryTransaction),
+}
+
+impl Transaction {
+    /// Makes all writes of the transaction visible
+    pub async fn commit(self) -> anyhow::Result<()> {
+        match self {
+            Transaction::Postgres(tx) => tx.commit().await?,
+            #[cfg(feature = "sqlite")]
+            Transaction::Sqlite(tx) => tx.commit().await?,
+            Transaction::Memory(tx) => tx.commit()?,
+        }
+        Ok(())
+    }
+
+    /// Discards all writes of the transaction
+    pub async fn rollback(self) -> anyhow::Result<()> {
+        match self {
+            Transaction::Postgres(tx) => tx.rollback().await?,
+            #[cfg(feature = "sqlite")]
+            Transaction::Sqlite(tx) => tx.rollback().await?,
+            Transaction::Memory(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Connection of a Postgres transaction, errors when the transaction belongs to another backend
+    pub fn postgres(&mut self) -> anyhow::Result<&mut PgConnection> {
+        match self {
+            Transaction::Postgres(tx) => Ok(&mut **tx),
+            _ => Err(wrong_backend("Postgres")),
+        }
+    }
+
+    /// Connection of a SQLite transaction, errors when the transaction belongs to another backend
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite(&mut self) -> anyhow::Result<&mut sqlx::SqliteConnection> {
+        match self {
+            Transaction::Sqlite(tx) => Ok(&mut **tx),
+            _ => Err(wrong_backend("SQLite")),
+        }
+    }
+
+    /// Working copy of an in-memory transaction, errors when the transaction belongs to another backend
+    pub fn memory(&mut self) -> anyhow::Result<&mut MemoryTransaction> {
+        match self {
+            Transaction::Memory(tx) => Ok(tx),
+            _ => Err(wrong_backend("in-memory")),
+        }
+    }
+}
+
+fn wrong_backend(expected: &str) -> anyhow::Error {
+    anyhow::anyhow!("Transaction was not opened on the {} backend", expected)
+}
+
+/// Opens transactions on one storage backend
+#[async_trait]
+pub trait UnitOfWork: Send + Sync {
+    /// Starts a transaction, serializable on every backend
+    async fn begin(&self) -> anyhow::Result<Transaction>;
+
+    /// How often and how long to wait before retrying a transaction that lost a serialization conflict
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::default()
+    }
+}
+
+/// Bounded exponential backoff for transactions that failed on a serialization conflict
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32, // attempts including the first one
+    pub base_delay: Duration, // wait after the first failed attempt, doubled per further attempt
+    pub max_delay: Duration, // upper bound of a single wait
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 5, base_delay: Duration::from_millis(10), max_delay: Duration::from_millis(200) }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before the given retry, retry 1 is the second attempt
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32.checked_shl(retry.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Error of an in-memory transaction whose data was changed by someone else before commit
+#[derive(Debug)]
+pub struct SerializationConflict;
+
+impl std::fmt::Display for SerializationConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "could not serialize access due to concurrent update")
+    }
+}
+
+impl std::error::Error for SerializationConflict {}
+
+/// True for errors that go away by running the transaction again:
+/// Postgres serialization failures (40001) and deadlocks (40P01), SQLite busy/locked and in-memory conflicts
+pub fn is_serialization_failure(error: &anyhow::Error) -> bool {
+    if error.downcast_ref::<SerializationConflict>().is_some() {
+        return true;
+    }
+    let Some(sqlx::Error::Database(db_error)) = error.downcast_ref::<sqlx::Error>() else {
+        return false;
+    };
+    // SQLite reports extended result codes, the primary code is the low byte
+    matches!(db_error.code().as_deref(), Some("40001" | "40P01"))
+        || (cfg!(feature = "sqlite")
+            && db_error.code().and_then(|code| code.parse::<i32>().ok()).is_some_and(|code| matches!(code & 0xff, 5 | 6)))
+}
+
+/// Runs `work` in a transaction and commits it
+/// `work` gets the open transaction and hands it back together with its result, an error drops
+/// (rolls back) the transaction. When `work` or the commit failed on a serialization conflict,
+/// the whole unit runs again in a fresh transaction, up to the backend's retry policy,
+/// so `work` must not have side effects outside the transaction.
+pub async fn in_transaction<T, F, Fut>(uow: &dyn UnitOfWork, mut work: F) -> anyhow::Result<T>
+where
+    F: FnMut(Transaction) -> Fut,
+    Fut: Future<Output = anyhow::Result<(Transaction, T)>>,
+{
+    let policy = uow.retry_policy();
+    let mut attempt = 1;
+    loop {
+        let tx = uow.begin().await?;
+        let result = match work(tx).await {
+            Ok((tx, value)) => tx.commit().await.map(|_| value),
+            Err(e) => Err(e),
+        };
+        match result {
+            Err(e) if attempt < policy.max_attempts && is_serialization_failure(&e) => {
+                let delay = policy.delay(attempt);
+                tracing::info!("Transaction attempt {} hit a serialization conflict, retrying in {:?}", attempt, delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Postgres implementation of UnitOfWork
+/// Transactions run with SERIALIZABLE isolation, conflicting ones fail with 40001 and are retried by in_transaction
+#[derive(Clone)]
+pub struct PgUnitOfWork {
+    pool: PgPool,
+    retry_policy: RetryPolicy,
+}
+
+impl PgUnitOfWork {
+    /// Constructor for PgUnitOfWork with the default retry policy
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool, retry_policy: RetryPolicy::default() }
+    }
+
+    /// Replaces the retry policy
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+}
+
+#[async_trait]
+impl UnitOfWork for PgUnitOfWork {
+    async fn begin(&self) -> anyhow::Result<Transaction> {
+        mark_written();
+        let tx = self.pool.begin_with("BEGIN ISOLATION LEVEL SERIALIZABLE").await?;
+        Ok(Transaction::Postgres(tx))
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_max() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(1), Duration::from_millis(10));
+        assert_eq!(policy.delay(2), Duration::from_millis(20));
+        assert_eq!(policy.delay(4), Duration::from_millis(80));
+        assert_eq!(policy.delay(6), Duration::from_millis(200));
+        assert_eq!(policy.delay(40), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_serialization_failures_are_recognized() {
+        assert!(is_serialization_failure(&anyhow::Error::new(SerializationConflict)));
+        assert!(!is_serialization_failure(&anyhow::anyhow!("Office with ID 1 does not exist")));
+        assert!(!is_serialization_failure(&anyhow::Error::from(sqlx::Error::RowNotFound)));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::entity::waitlist::{WaitlistEntry, WaitlistEvent, WaitlistEventKind, WaitlistStatus};
+use crate::repository::unit_of_work::Transaction;
+use async_trait::async_trait;
+use sqlx::{PgExecutor, PgPool};
+
+/// Storage of waitlist entries and their events, implemented for Postgres (WaitlistRepository) and in memory (MemoryWaitlistRepository)
+/// Every method but get_waiting_office_ids is scoped to one tenant. Entries go along when their office is deleted,
+/// events when their entry is
+#[async_trait]
+pub trait WaitlistStore: Send + Sync {
+    /// Inserts a waitlist entry inside a transaction and returns it with its ID
+    async fn create_entry_tx(&self, tx: &mut Transaction, tenant_id: i32, entry: &WaitlistEntry) -> anyhow::Result<WaitlistEntry>;
+
+    /// Retrieves a waitlist entry by its ID
+    async fn get_entry_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<WaitlistEntry>>;
+
+    /// Retrieves a waitlist entry by its ID inside a transaction
+    async fn get_entry_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<WaitlistEntry>>;
+
+    /// Retrieves the waitlist of an office, optionally only entries with a status, in order of arrival
+    async fn get_entries_by_office_id(&self, tenant_id: i32, office_id: i32, status: Option<WaitlistStatus>) -> anyhow::Result<Vec<WaitlistEntry>>;
+
+    /// Retrieves the waitlist of an office in order of arrival inside a transaction
+    async fn get_entries_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<WaitlistEntry>>;
+
+    /// Updates status and employee_id of an entry inside a transaction and returns the updated entry
+    async fn update_entry_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, entry: &WaitlistEntry) -> anyhow::Result<WaitlistEntry>;
+
+    /// Retrieves the offices of all tenants with waiting entries, with their tenant, ordered by tenant and office.
+    /// Used by the waitlist job only
+    async fn get_waiting_office_ids(&self) -> anyhow::Result<Vec<(i32, i32)>>;
+
+    /// Inserts an event inside a transaction and returns it with its ID
+    async fn create_event_tx(&self, tx: &mut Transaction, tenant_id: i32, event: &WaitlistEvent) -> anyhow::Result<WaitlistEvent>;
+
+    /// Retrieves the events of an entry, oldest first
+    async fn get_events(&self, tenant_id: i32, entry_id: i32) -> anyhow::Result<Vec<WaitlistEvent>>;
+}
+
+/// Repository for waitlist entries in the database
+#[derive(Clone)]
+pub struct WaitlistRepository {
+    pool: PgPool,
+}
+impl WaitlistRepository {
+    /// Constructor for WaitlistRepository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl WaitlistStore for WaitlistRepository {
+    async fn create_entry_tx(&self, tx: &mut Transaction, tenant_id: i32, entry: &WaitlistEntry) -> anyhow::Result<WaitlistEntry> {
+        let created = sqlx::query_as!(
+            WaitlistEntry,
+            r#"INSERT INTO waitlist_entries (tenant_id, office_id, first_name, last_name, birth_date, department_id, manager_id, hire_date, confirm, status, requested_by)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+             RETURNING id, office_id, first_name, last_name, birth_date, department_id, manager_id, hire_date, confirm,
+                 status AS "status: WaitlistStatus", employee_id, requested_by, created_at"#,
+            tenant_id,
+            entry.office_id,
+            entry.first_name,
+            entry.last_name,
+            entry.birth_date,
+            entry.department_id,
+            entry.manager_id,
+            entry.hire_date,
+            entry.confirm,
+            entry.status.as_str(),
+            entry.requested_by
+        )
+        .fetch_one(tx.postgres()?)
+        .await?;
+        Ok(created)
+    }
+
+    async fn get_entry_by_id(&self, tenant_id: i32, id: i32) -> anyhow::Result<Option<WaitlistEntry>> {
+        get_entry_by_id(&self.pool, tenant_id, id).await
+    }
+
+    async fn get_entry_by_id_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<Option<WaitlistEntry>> {
+        get_entry_by_id(tx.postgres()?, tenant_id, id).await
+    }
+
+    async fn get_entries_by_office_id(&self, tenant_id: i32, office_id: i32, status: Option<WaitlistStatus>) -> anyhow::Result<Vec<WaitlistEntry>> {
+        get_entries_by_office_id(&self.pool, tenant_id, office_id, status).await
+    }
+
+    async fn get_entries_by_office_id_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<Vec<WaitlistEntry>> {
+        get_entries_by_office_id(tx.postgres()?, tenant_id, office_id, None).await
+    }
+
+    async fn update_entry_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, entry: &WaitlistEntry) -> anyhow::Result<WaitlistEntry> {
+        let updated = sqlx::query_as!(
+            WaitlistEntry,
+            r#"UPDATE waitlist_entries SET status = $1, employee_id = $2 WHERE tenant_id = $3 AND id = $4
+             RETURNING id, office_id, first_name, last_name, birth_date, department_id, manager_id, hire_date, confirm,
+                 status AS "status: WaitlistStatus", employee_id, requested_by, created_at"#,
+            entry.status.as_str(),
+            entry.employee_id,
+            tenant_id,
+            id
+        )
+        .fetch_one(tx.postgres()?)
+        .await?;
+        Ok(updated)
+    }
+
+    async fn get_waiting_office_ids(&self) -> anyhow::Result<Vec<(i32, i32)>> {
+        let rows = sqlx::query!("SELECT DISTINCT tenant_id, office_id FROM waitlist_entries WHERE status = 'waiting' ORDER BY tenant_id, office_id")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(|r| (r.tenant_id, r.office_id)).collect())
+    }
+
+    async fn create_event_tx(&self, tx: &mut Transaction, tenant_id: i32, event: &WaitlistEvent) -> anyhow::Result<WaitlistEvent> {
+        let created = sqlx::query_as!(
+            WaitlistEvent,
+            r#"INSERT INTO waitlist_events (tenant_id, entry_id, event, details) VALUES ($1, $2, $3, $4)
+             RETURNING id, entry_id, event AS "event: WaitlistEventKind", details, created_at"#,
+            tenant_id,
+            event.entry_id,
+            event.event.as_str(),
+            event.details
+        )
+        .fetch_one(tx.postgres()?)
+        .await?;
+        Ok(created)
+    }
+
+    async fn get_events(&self, tenant_id: i32, entry_id: i32) -> anyhow::Result<Vec<WaitlistEvent>> {
+        let events = sqlx::query_as!(
+            WaitlistEvent,
+            r#"SELECT id, entry_id, event AS "event: WaitlistEventKind", details, created_at FROM waitlist_events
+             WHERE tenant_id = $1 AND entry_id = $2 ORDER BY id"#,
+            tenant_id,
+            entry_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(events)
+    }
+}
+
+// Queries shared by the pool and the transaction methods
+async fn get_entry_by_id(executor: impl PgExecutor<'_>, tenant_id: i32, id: i32) -> anyhow::Result<Option<WaitlistEntry>> {
+    let entry = sqlx::query_as!(
+        WaitlistEntry,
+        r#"SELECT id, office_id, first_name, last_name, birth_date, department_id, manager_id, hire_date, confirm,
+             status AS "status: WaitlistStatus", employee_id, requested_by, created_at
+         FROM waitlist_entries WHERE tenant_id = $1 AND id = $2"#,
+        tenant_id,
+        id
+    )
+    .fetch_optional(executor)
+    .await?;
+    Ok(entry)
+}
+
+async fn get_entries_by_office_id(executor: impl PgExecutor<'_>, tenant_id: i32, office_id: i32, status: Option<WaitlistStatus>) -> anyhow::Result<Vec<WaitlistEntry>> {
+    let entries = sqlx::query_as!(
+        WaitlistEntry,
+        r#"SELECT id, office_id, first_name, last_name, birth_date, department_id, manager_id, hire_date, confirm,
+             status AS "status: WaitlistStatus", employee_id, requested_by, created_at
+         FROM waitlist_entries WHERE tenant_id = $1 AND office_id = $2 AND ($3::TEXT IS NULL OR status = $3) ORDER BY id"#,
+        tenant_id,
+        office_id,
+        status.map(|s| s.as_str())
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(entries)
+}
@@ -0,0 +1,373 @@
+use crate::entity::change_request::{outcome, Approval, ApprovalStep, ChangeRequest, ChangeRequestDetails, ChangeRequestStatus, Comment, Decision, ProposedChange};
+use crate::entity::employee::Employee;
+use crate::entity::office::Office;
+use crate::repository::change_request_repository::ChangeRequestStore;
+use crate::repository::office_manager_repository::OfficeManagerStore;
+use crate::repository::unit_of_work::{in_transaction, Transaction, UnitOfWork};
+use crate::service::employee_service::EmployeeService;
+use crate::service::office_service::OfficeService;
+use crate::service::transfer_service::TransferService;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use crate::auth::{permission::{Permission, Role}, principal::Principal, scope::OfficeScope};
+use crate::utils::{CodedError, ValidationErrors};
+
+/// Service for change requests
+/// Office and employee changes that need sign-off before they are made
+///
+/// A request gets its approval steps on submission (see ProposedChange::approval_steps): the managers of the offices
+/// an employee leaves and joins, and for office changes facilities as well. A facilities admin signs off facilities steps,
+/// an office manager the steps of the offices they manage, an admin any step; nobody decides their own request.
+/// Once every step approved, the change is made through the service that makes it without approval, in the name
+/// of the requester, with the same validation and in the transaction of the last approval; a change that no longer
+/// passes leaves the request failed.
+/// Requests are visible within the caller's office scope when one of their offices is
+#[derive(Clone)]
+pub struct ChangeRequestService {
+    repo: Arc<dyn ChangeRequestStore>,
+    manager_repo: Arc<dyn OfficeManagerStore>,
+    unit_of_work: Arc<dyn UnitOfWork>,
+    employee_service: Arc<EmployeeService>,
+    office_service: Arc<OfficeService>,
+    transfer_service: Arc<TransferService>,
+    ttl: chrono::Duration,
+}
+
+impl ChangeRequestService {
+    /// Constructor for ChangeRequestService, works with any storage backend
+    /// ttl is the expiry of requests that name none
+    pub fn new(
+        repo: impl ChangeRequestStore + 'static,
+        manager_repo: impl OfficeManagerStore + 'static,
+        unit_of_work: impl UnitOfWork + 'static,
+        employee_service: Arc<EmployeeService>,
+        office_service: Arc<OfficeService>,
+        transfer_service: Arc<TransferService>,
+        ttl: chrono::Duration,
+    ) -> Self {
+        Self {
+            repo: Arc::new(repo),
+            manager_repo: Arc::new(manager_repo),
+            unit_of_work: Arc::new(unit_of_work),
+            employee_service,
+            office_service,
+            transfer_service,
+            ttl,
+        }
+    }
+
+    // Offices the principal may act on with the permission, see EmployeeService::office_scope
+    async fn office_scope(&self, principal: &Principal, permission: Permission) -> anyhow::Result<OfficeScope> {
+        if !principal.is_office_scoped(permission) {
+            return Ok(OfficeScope::All);
+        }
+        let office_ids = self.manager_repo.get_office_ids_by_subject(principal.tenant_id, &principal.subject).await?;
+        Ok(OfficeScope::Offices(office_ids))
+    }
+
+    /// Submits a change for approval, optionally with a first comment giving the reason
+    /// The employee or office changed must be within scope and the office an employee moves to must exist.
+    /// The change itself is validated when it is applied. The request expires after the configured time unless it names an expiry
+    pub async fn submit_change_request(&self, principal: &Principal, change: ProposedChange, comment: Option<&str>, expires_at: Option<DateTime<Utc>>) -> anyhow::Result<ChangeRequestDetails> {
+        tracing::info!("Attempting to submit change request by {}", principal.subject);
+        let scope = self.office_scope(principal, Permission::ChangesSubmit).await?;
+        let tenant_id = principal.tenant_id;
+
+        let office_id = match &change {
+            ProposedChange::OfficeUpdate { office_id, .. } => {
+                self.office_service.find_office_by_id(principal, *office_id)
+                    .await?
+                    .filter(|_| scope.allows(*office_id))
+                    .ok_or_else(|| CodedError::new("office.not_found").arg("id", office_id))?;
+                *office_id
+            }
+            ProposedChange::Transfer { employee_id, .. } | ProposedChange::EmployeeUpdate { employee_id, .. } => {
+                self.employee_service.find_employee_by_id(principal, *employee_id)
+                    .await?
+                    .filter(|e| scope.allows(e.office_id))
+                    .ok_or_else(|| CodedError::new("employee.not_found").arg("id", employee_id))?
+                    .office_id
+            }
+        };
+        // the destination needs no scope, its managers sign off the move
+        if let Some(to_office_id) = change.destination_office_id()
+            && self.office_service.find_office_by_id(principal, to_office_id).await?.is_none()
+        {
+            return Err(CodedError::new("office.not_found").arg("id", to_office_id).into());
+        }
+
+        let steps = change.approval_steps(office_id);
+        let request = ChangeRequest::new(change, &principal.subject, expires_at.unwrap_or_else(|| Utc::now() + self.ttl));
+        request.check()?;
+        let comment = comment.map(|body| Comment::new(0, &principal.subject, body));
+        if let Some(comment) = &comment {
+            comment.check()?;
+        }
+        let steps = &steps;
+        let request = &request;
+        let comment = &comment;
+
+        let created = in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let created = self.repo.create_change_request_tx(&mut tx, tenant_id, request, steps).await?;
+            if let Some(comment) = comment {
+                let comment = Comment { change_request_id: created.id.unwrap_or_default(), ..comment.clone() };
+                self.repo.create_comment_tx(&mut tx, tenant_id, &comment).await?;
+            }
+            Ok((tx, created))
+        })
+        .await?;
+        tracing::info!("Submitted change request {:?} needing {} approvals", created.id, steps.len());
+        self.details(tenant_id, created).await
+    }
+
+    /// Finds a change request by ID, None if it does not exist or is out of scope
+    pub async fn find_change_request_by_id(&self, principal: &Principal, id: i32) -> anyhow::Result<Option<ChangeRequestDetails>> {
+        tracing::info!("Attempting to find change request with id: {}", id);
+        let scope = self.office_scope(principal, Permission::ChangesRead).await?;
+        let Some(request) = self.repo.get_change_request_by_id(principal.tenant_id, id).await? else {
+            return Ok(None);
+        };
+        let details = self.details(principal.tenant_id, request).await?;
+        Ok(Some(details).filter(|d| visible(&scope, d)))
+    }
+
+    /// Lists the change requests within scope, optionally only those with a status as of now, ordered by ID
+    pub async fn list_change_requests(&self, principal: &Principal, status: Option<ChangeRequestStatus>) -> anyhow::Result<Vec<ChangeRequestDetails>> {
+        tracing::info!("Listing change requests with status {:?}", status);
+        let scope = self.office_scope(principal, Permission::ChangesRead).await?;
+        let now = Utc::now();
+        // expired requests are stored as pending
+        let stored = status.map(|s| if s == ChangeRequestStatus::Expired { ChangeRequestStatus::Pending } else { s });
+        let mut found = Vec::new();
+        for request in self.repo.get_change_requests(principal.tenant_id, stored).await? {
+            if status.is_some_and(|s| request.status_at(now) != s) {
+                continue;
+            }
+            let details = self.details(principal.tenant_id, request).await?;
+            if visible(&scope, &details) {
+                found.push(details);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Approves every open step of a pending request the principal may sign off, optionally with a comment
+    /// When that was the last open step, the change is applied and the request ends up applied or failed
+    pub async fn approve_change_request(&self, principal: &Principal, id: i32, comment: Option<&str>) -> anyhow::Result<ChangeRequestDetails> {
+        self.decide(principal, id, Decision::Approved, comment).await
+    }
+
+    /// Rejects every open step of a pending request the principal may sign off, which rejects the request
+    pub async fn reject_change_request(&self, principal: &Principal, id: i32, comment: Option<&str>) -> anyhow::Result<ChangeRequestDetails> {
+        self.decide(principal, id, Decision::Rejected, comment).await
+    }
+
+    // Records the principal's decision on the steps they may sign off and resolves the request once decided
+    // The final approval and the change commit in one transaction. A change that no longer passes its checks rolls
+    // back together with the decision, which is then recorded again with the request failed, so an approved request
+    // is never left without its change
+    async fn decide(&self, principal: &Principal, id: i32, decision: Decision, comment: Option<&str>) -> anyhow::Result<ChangeRequestDetails> {
+        tracing::info!("Attempting to decide change request {} as {}", id, decision.as_str());
+        let scope = self.office_scope(principal, Permission::ChangesRead).await?;
+        let managed = self.manager_repo.get_office_ids_by_subject(principal.tenant_id, &principal.subject).await?;
+        let comment = comment.map(|body| Comment::new(id, &principal.subject, body));
+        if let Some(comment) = &comment {
+            comment.check()?;
+        }
+        let decide = Decide { principal, id, decision, scope: &scope, managed: &managed, comment: &comment };
+
+        let decided = match self.record_decision(&decide, None).await {
+            Err(e) if e.is::<NotApplied>() => {
+                tracing::warn!("Approved change request {} cannot be applied: {}", id, e);
+                let reasons = e.downcast::<NotApplied>()?.0;
+                self.record_decision(&decide, Some(&reasons)).await?
+            }
+            decided => decided?,
+        };
+        self.details(principal.tenant_id, decided).await
+    }
+
+    // One attempt at recording the decision, applying the change when it was the last approval
+    // With failure reasons the change is not tried again and the request is marked failed with them
+    async fn record_decision(&self, decide: &Decide<'_>, failure: Option<&[CodedError]>) -> anyhow::Result<ChangeRequest> {
+        let Decide { principal, id, decision, scope, managed, comment } = *decide;
+        let tenant_id = principal.tenant_id;
+
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let (request, approvals) = self.pending_request_tx(&mut tx, tenant_id, scope, id).await?;
+            if request.requested_by == principal.subject {
+                return Err(CodedError::new("change_request.own_request").arg("id", id).into());
+            }
+            let open: Vec<&Approval> = approvals
+                .iter()
+                .filter(|a| a.decision.is_none() && a.approval_step().is_some_and(|step| may_sign_off(principal, managed, step)))
+                .collect();
+            if open.is_empty() {
+                return Err(CodedError::new("change_request.not_approver").arg("id", id).into());
+            }
+            for approval in open {
+                self.repo.decide_approval_tx(&mut tx, tenant_id, id, &approval.step, decision, &principal.subject).await?;
+            }
+            if let Some(comment) = comment {
+                self.repo.create_comment_tx(&mut tx, tenant_id, comment).await?;
+            }
+
+            let approvals = self.repo.get_approvals_tx(&mut tx, tenant_id, id).await?;
+            let resolved = match (outcome(&approvals), failure) {
+                (None, _) => return Ok((tx, request)),
+                (Some(Decision::Rejected), _) => ChangeRequest { status: ChangeRequestStatus::Rejected, ..request },
+                (Some(Decision::Approved), Some(reasons)) => {
+                    ChangeRequest { status: ChangeRequestStatus::Failed, failure_reason: Some(Json(reasons.to_vec())), ..request }
+                }
+                (Some(Decision::Approved), None) => {
+                    let details = ChangeRequestDetails { request, approvals, comments: Vec::new() };
+                    self.apply_tx(&mut tx, tenant_id, &details).await?;
+                    tracing::info!("Applied change request {}", id);
+                    ChangeRequest { status: ChangeRequestStatus::Applied, ..details.request }
+                }
+            };
+            let resolved = ChangeRequest { resolved_at: Some(Utc::now()), ..resolved };
+            let updated = self.repo.update_change_request_tx(&mut tx, tenant_id, id, &resolved).await?;
+            Ok((tx, updated))
+        })
+        .await
+    }
+
+    /// Withdraws a pending request, only the requester or an admin may
+    pub async fn withdraw_change_request(&self, principal: &Principal, id: i32) -> anyhow::Result<ChangeRequestDetails> {
+        tracing::info!("Attempting to withdraw change request with id: {}", id);
+        let scope = self.office_scope(principal, Permission::ChangesRead).await?;
+        let scope = &scope;
+        let tenant_id = principal.tenant_id;
+
+        let withdrawn = in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let (request, _) = self.pending_request_tx(&mut tx, tenant_id, scope, id).await?;
+            if request.requested_by != principal.subject && !principal.roles.contains(&Role::Admin) {
+                return Err(CodedError::new("change_request.not_requester").arg("id", id).into());
+            }
+            let withdrawn = ChangeRequest { status: ChangeRequestStatus::Withdrawn, resolved_at: Some(Utc::now()), ..request };
+            let updated = self.repo.update_change_request_tx(&mut tx, tenant_id, id, &withdrawn).await?;
+            Ok((tx, updated))
+        })
+        .await?;
+        self.details(tenant_id, withdrawn).await
+    }
+
+    /// Adds a comment to a pending request
+    pub async fn add_comment(&self, principal: &Principal, id: i32, body: &str) -> anyhow::Result<Comment> {
+        tracing::info!("Attempting to comment on change request with id: {}", id);
+        let scope = self.office_scope(principal, Permission::ChangesRead).await?;
+        let scope = &scope;
+        let comment = Comment::new(id, &principal.subject, body);
+        comment.check()?;
+        let comment = &comment;
+        let tenant_id = principal.tenant_id;
+
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            self.pending_request_tx(&mut tx, tenant_id, scope, id).await?;
+            let created = self.repo.create_comment_tx(&mut tx, tenant_id, comment).await?;
+            Ok((tx, created))
+        })
+        .await
+    }
+
+    // Makes an approved change in the name of the requester, inside the transaction of the final approval
+    // A change that no longer passes its checks fails with NotApplied, any other error is passed on as it is
+    async fn apply_tx(&self, tx: &mut Transaction, tenant_id: i32, details: &ChangeRequestDetails) -> anyhow::Result<()> {
+        let request = &details.request;
+        // the approvals vouch for the offices whose managers signed off, the change may touch those only
+        let scope = OfficeScope::Offices(details.office_ids());
+        let result = match &request.change.0 {
+            ProposedChange::Transfer { employee_id, to_office_id, effective_date } => {
+                self.transfer_service.schedule_transfer_approved_tx(tx, tenant_id, &request.requested_by, &scope, *employee_id, *to_office_id, *effective_date).await.map(|_| ())
+            }
+            ProposedChange::EmployeeUpdate { employee_id, employee } => {
+                let hire_date = employee.hire_date;
+                let employee = Employee::from_create_request(employee.clone());
+                self.employee_service.update_employee_approved_tx(tx, tenant_id, &scope, *employee_id, &employee, hire_date).await.map(|_| ())
+            }
+            ProposedChange::OfficeUpdate { office_id, office } => {
+                let office = Office::from_create_request(office.clone());
+                self.office_service.update_office_approved_tx(tx, tenant_id, &scope, *office_id, &office).await.map(|_| ())
+            }
+        };
+        result.map_err(|e| match NotApplied::from_error(&e) {
+            Some(not_applied) => not_applied.into(),
+            None => e,
+        })
+    }
+
+    // A pending request within scope with its steps, change_request.not_found or change_request.not_pending
+    async fn pending_request_tx(&self, tx: &mut Transaction, tenant_id: i32, scope: &OfficeScope, id: i32) -> anyhow::Result<(ChangeRequest, Vec<Approval>)> {
+        let not_found = || CodedError::new("change_request.not_found").arg("id", id);
+        let request = self.repo.get_change_request_by_id_tx(tx, tenant_id, id).await?.ok_or_else(not_found)?;
+        let approvals = self.repo.get_approvals_tx(tx, tenant_id, id).await?;
+        let details = ChangeRequestDetails { request, approvals, comments: Vec::new() };
+        if !visible(scope, &details) {
+            return Err(not_found().into());
+        }
+        details.request.check_pending(Utc::now())?;
+        Ok((details.request, details.approvals))
+    }
+
+    // The request with its steps and comments
+    async fn details(&self, tenant_id: i32, request: ChangeRequest) -> anyhow::Result<ChangeRequestDetails> {
+        let id = request.id.unwrap_or_default();
+        let approvals = self.repo.get_approvals(tenant_id, id).await?;
+        let comments = self.repo.get_comments(tenant_id, id).await?;
+        Ok(ChangeRequestDetails { request, approvals, comments })
+    }
+}
+
+// What a decision is about, shared by its attempts
+#[derive(Clone, Copy)]
+struct Decide<'a> {
+    principal: &'a Principal,
+    id: i32,
+    decision: Decision,
+    scope: &'a OfficeScope,
+    managed: &'a [i32],
+    comment: &'a Option<Comment>,
+}
+
+// An approved change that no longer passes its checks, carries the reasons as codes so they can be localized later
+#[derive(Debug)]
+struct NotApplied(Vec<CodedError>);
+
+impl NotApplied {
+    // The reasons of a coded or validation error, None for any other error
+    fn from_error(error: &anyhow::Error) -> Option<Self> {
+        if let Some(coded) = error.downcast_ref::<CodedError>() {
+            return Some(Self(vec![coded.clone()]));
+        }
+        error.downcast_ref::<ValidationErrors>().map(|errors| {
+            Self(errors.errors.iter().map(|e| CodedError { code: e.code.clone(), args: e.args.clone() }).collect())
+        })
+    }
+}
+
+impl std::fmt::Display for NotApplied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reasons: Vec<String> = self.0.iter().map(CodedError::to_string).collect();
+        write!(f, "{}", reasons.join("; "))
+    }
+}
+
+impl std::error::Error for NotApplied {}
+
+// True if the request concerns an office within the scope
+fn visible(scope: &OfficeScope, details: &ChangeRequestDetails) -> bool {
+    *scope == OfficeScope::All || details.office_ids().into_iter().any(|office_id| scope.allows(office_id))
+}
+
+// True if the principal may sign off the step, given the offices they manage
+fn may_sign_off(principal: &Principal, managed: &[i32], step: ApprovalStep) -> bool {
+    if principal.roles.contains(&Role::Admin) {
+        return true;
+    }
+    match step {
+        ApprovalStep::Facilities => principal.roles.contains(&Role::FacilitiesAdmin),
+        ApprovalStep::OfficeManager { office_id } => principal.roles.contains(&Role::OfficeManager) && managed.contains(&office_id),
+    }
+}
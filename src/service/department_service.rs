@@ -0,0 +1,75 @@
+use crate::entity::department::Department;
+use crate::repository::department_repository::DepartmentStore;
+use std::sync::Arc;
+use crate::utils::{CodedError, Validate};
+use crate::auth::principal::Principal;
+
+/// Service for Department entities
+/// Handles business logic related to departments
+/// Departments live within the caller's tenant, names only need to be unique per tenant
+#[derive(Clone)]
+pub struct DepartmentService {
+    repo: Arc<dyn DepartmentStore>,
+}
+
+impl DepartmentService {
+    /// Constructor for DepartmentService, works with any storage backend
+    pub fn new(repo: impl DepartmentStore + 'static) -> Self {
+        Self { repo: Arc::new(repo) }
+    }
+
+    /// Adds a new department after validating and checking for duplicate names
+    pub async fn add_department(&self, principal: &Principal, department: &Department) -> anyhow::Result<Department> {
+        tracing::info!("Attempting to add department with name: {}", department.name);
+
+        department.validate()?;
+
+        if self.repo.get_department_by_name(principal.tenant_id, &department.name).await?.is_some() {
+            return Err(CodedError::new("department.name_exists").arg("name", &department.name).into());
+        }
+
+        self.repo.create_department(principal.tenant_id, department).await
+    }
+
+    /// Finds a department by ID
+    pub async fn find_department_by_id(&self, principal: &Principal, id: i32) -> anyhow::Result<Option<Department>> {
+        tracing::info!("Attempting to find department with id: {}", id);
+        self.repo.get_department_by_id(principal.tenant_id, id).await
+    }
+
+    /// Lists all departments
+    pub async fn list_all_departments(&self, principal: &Principal) -> anyhow::Result<Vec<Department>> {
+        tracing::info!("Listing all departments");
+        self.repo.get_all_departments(principal.tenant_id).await
+    }
+
+    /// Renames an existing department after validating and checking for duplicate names
+    pub async fn update_department(&self, principal: &Principal, id: i32, department: &Department) -> anyhow::Result<Department> {
+        tracing::info!("Attempting to update department with id: {}", id);
+
+        department.validate()?;
+
+        if self.repo.get_department_by_id(principal.tenant_id, id).await?.is_none() {
+            return Err(CodedError::new("department.not_found").arg("id", id).into());
+        }
+        if let Some(existing) = self.repo.get_department_by_name(principal.tenant_id, &department.name).await?
+            && existing.id != Some(id)
+        {
+            return Err(CodedError::new("department.name_exists").arg("name", &department.name).into());
+        }
+
+        self.repo.update_department_by_id(principal.tenant_id, id, department).await
+    }
+
+    /// Removes a department by ID, false if it does not exist
+    /// A department that employees still belong to is kept, they have to be moved first
+    pub async fn remove_department(&self, principal: &Principal, id: i32) -> anyhow::Result<bool> {
+        tracing::info!("Deleting department id: {}", id);
+        let employee_nr = self.repo.employee_nr_by_department_id(principal.tenant_id, id).await?;
+        if employee_nr > 0 {
+            return Err(CodedError::new("department.has_employees").arg("count", employee_nr).into());
+        }
+        let rows = self.repo.delete_department(principal.tenant_id, id).await?;
+        Ok(rows > 0)
+    }
+}
@@ -1,99 +1,713 @@
-use crate::entity::employee::Employee;
-use crate::repository::employee_repository::EmployeeRepository;
-use crate::repository::office_repository::OfficeRepository;
-use anyhow::{anyhow};
-use crate::utils::Validate;
+use crate::entity::employee::{peak_seats, Employee, EmploymentStatus};
+use crate::entity::office::Office;
+use crate::entity::audit_entry::AuditEntry;
+use crate::entity::validation_rules::{DuplicatePolicy, EmployeeRules, ValidationRules};
+use crate::dto::import_dto::{ImportEmployeeRow, ImportMode, ImportReport, ImportRowResult, ImportRowStatus};
+use crate::dto::export_dto::EmployeeExportRow;
+use crate::dto::merge_dto::MergeEmployeeRequest;
+use futures::stream::{BoxStream, StreamExt};
+use tokio_stream::wrappers::ReceiverStream;
+use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+use chrono::NaiveDate;
+use crate::repository::employee_repository::EmployeeStore;
+use crate::repository::office_repository::OfficeStore;
+use crate::repository::office_manager_repository::OfficeManagerStore;
+use crate::repository::audit_repository::AuditStore;
+use crate::repository::department_repository::DepartmentStore;
+use crate::repository::unit_of_work::{in_transaction, Transaction, UnitOfWork};
+use crate::service::seat_service::SeatService;
+use std::sync::Arc;
+use crate::auth::{permission::Permission, principal::Principal, scope::OfficeScope};
+use crate::utils::{CodedError, ValidationErrors};
+use crate::utils::i18n::Language;
+
+/// Rows buffered between the export cursor and the response body
+/// The cursor waits when the client reads slower than the database delivers
+pub const EXPORT_BUFFER_ROWS: usize = 256;
 
 /// Service for Employee entities
 /// Handles business logic related to employees
-
+///
+/// Every operation is limited to the caller's tenant and office scope:
+/// employees and offices outside of it are reported as not existing
 #[derive(Clone)]
 pub struct EmployeeService {
-    repo: EmployeeRepository,
-    office_repo: OfficeRepository,
+    repo: Arc<dyn EmployeeStore>,
+    office_repo: Arc<dyn OfficeStore>,
+    manager_repo: Arc<dyn OfficeManagerStore>,
+    audit_repo: Arc<dyn AuditStore>,
+    department_repo: Arc<dyn DepartmentStore>,
+    seats: SeatService,
+    unit_of_work: Arc<dyn UnitOfWork>,
+    validation_rules: Arc<ValidationRules>,
 }
 
 impl EmployeeService {
-    /// Constructor for EmployeeService
-    pub fn new(repo: EmployeeRepository, office_repo: OfficeRepository) -> Self {
-        Self { repo, office_repo }
+    /// Constructor for EmployeeService, works with any storage backend
+    pub fn new(
+        repo: impl EmployeeStore + 'static,
+        office_repo: impl OfficeStore + 'static,
+        manager_repo: impl OfficeManagerStore + 'static,
+        audit_repo: impl AuditStore + 'static,
+        department_repo: impl DepartmentStore + 'static,
+        seats: SeatService,
+        unit_of_work: impl UnitOfWork + 'static,
+    ) -> Self {
+        Self {
+            repo: Arc::new(repo),
+            office_repo: Arc::new(office_repo),
+            manager_repo: Arc::new(manager_repo),
+            audit_repo: Arc::new(audit_repo),
+            department_repo: Arc::new(department_repo),
+            seats,
+            unit_of_work: Arc::new(unit_of_work),
+            validation_rules: Arc::new(ValidationRules::default()),
+        }
+    }
+
+    /// Replaces the default validation rules with configured ones
+    pub fn with_validation_rules(mut self, validation_rules: ValidationRules) -> Self {
+        self.validation_rules = Arc::new(validation_rules);
+        self
+    }
+
+    // Checks an employee against the rules of its tenant and office, ages counted today
+    fn validate(&self, tenant_id: i32, employee: &Employee) -> Result<(), ValidationErrors> {
+        let rules = self.validation_rules.for_employee(tenant_id, Some(employee.office_id));
+        employee.validate_with(&rules, chrono::Utc::now().date_naive())
+    }
+
+    /// Effective validation rules of the caller's tenant, with the overrides of the office if given
+    /// An unknown or out-of-scope office is reported as not existing
+    pub async fn validation_rules(&self, principal: &Principal, office_id: Option<i32>) -> anyhow::Result<EmployeeRules> {
+        if let Some(office_id) = office_id {
+            let scope = self.office_scope(principal, Permission::EmployeesRead).await?;
+            let office_id_exist = self.office_repo.get_office_by_id(principal.tenant_id, office_id).await?;
+            if office_id_exist.is_none() || !scope.allows(office_id) {
+                return Err(CodedError::new("office.not_found").arg("id", office_id).into());
+            }
+        }
+        Ok(self.validation_rules.for_employee(principal.tenant_id, office_id))
+    }
+
+    /// Resolves which offices the principal may use the permission on
+    pub async fn office_scope(&self, principal: &Principal, permission: Permission) -> anyhow::Result<OfficeScope> {
+        if !principal.is_office_scoped(permission) {
+            return Ok(OfficeScope::All);
+        }
+        let office_ids = self.manager_repo.get_office_ids_by_subject(principal.tenant_id, &principal.subject).await?;
+        tracing::debug!("Principal {} scoped to offices {:?}", principal.subject, office_ids);
+        Ok(OfficeScope::Offices(office_ids))
+    }
+
+    // Existing employees of the tenant with the same normalized name and birth date
+    async fn same_person_tx(&self, tx: &mut Transaction, tenant_id: i32, employee: &Employee) -> anyhow::Result<Vec<Employee>> {
+        let key = employee.duplicate_key();
+        let candidates = self.repo.get_employees_by_birth_date_tx(tx, tenant_id, employee.birth_date).await?;
+        Ok(candidates.into_iter().filter(|candidate| candidate.id != employee.id && candidate.duplicate_key() == key).collect())
+    }
+
+    // The department must exist in the tenant, departments are not office scoped
+    // Read in the transaction of the write, a department deleted meanwhile is reported instead of failing the insert
+    async fn check_department_tx(&self, tx: &mut Transaction, tenant_id: i32, employee: &Employee) -> anyhow::Result<()> {
+        if let Some(department_id) = employee.department_id
+            && self.department_repo.get_department_by_id_tx(tx, tenant_id, department_id).await?.is_none()
+        {
+            return Err(CodedError::new("department.not_found").arg("id", department_id).into());
+        }
+        Ok(())
+    }
+
+    // The manager of an existing employee must exist in the tenant (in any office) and must not report to the employee,
+    // directly or further down, which would close a cycle
+    async fn check_manager_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32, manager_id: Option<i32>) -> anyhow::Result<()> {
+        let Some(manager_id) = manager_id else {
+            return Ok(());
+        };
+        let cycle = || CodedError::new("employee.manager_cycle").arg("id", id).arg("manager_id", manager_id);
+        if manager_id == id {
+            return Err(cycle().into());
+        }
+        if self.repo.get_employee_by_id_tx(tx, tenant_id, manager_id).await?.is_none() {
+            return Err(CodedError::new("employee.manager_not_found").arg("id", manager_id).into());
+        }
+        let chain = self.repo.get_reporting_chain_tx(tx, tenant_id, manager_id).await?;
+        if chain.iter().any(|manager| manager.id == Some(id)) {
+            return Err(cycle().into());
+        }
+        Ok(())
     }
 
     /// Adds a new employee after validating and checking office capacity
-    pub async fn add_employee(&self, employee: &Employee) -> anyhow::Result<Employee> {
+    pub async fn add_employee(&self, principal: &Principal, employee: &Employee) -> anyhow::Result<Employee> {
+        self.add_employee_checked(principal, employee).await.map(|(created, _)| created)
+    }
+
+    /// Adds a new employee like add_employee and returns the IDs of in-scope employees that are probably the same person
+    /// Depending on the duplicate policy of the tenant and office the check is skipped, only reported, or blocks the create.
+    /// An office with hires on its waitlist takes no direct hires, office.waitlisted. Besides the office, the levels
+    /// above its site in the location tree need a free seat (location.full)
+    pub async fn add_employee_checked(&self, principal: &Principal, employee: &Employee) -> anyhow::Result<(Employee, Vec<i32>)> {
         tracing::info!("Attempting to add employee with name: {} {}", employee.first_name, employee.last_name);
 
-        employee.validate().map_err(|e| anyhow::anyhow!(e))?; // validates last name and birth date
+        self.validate(principal.tenant_id, employee)?; // validates names and age, all broken rules at once
 
-        let office = self.office_repo.get_office_by_id(employee.office_id)
-            .await?
-            .ok_or_else(|| anyhow!("Office with ID {} does not exist", employee.office_id))?;
+        let scope = self.office_scope(principal, Permission::EmployeesCreate).await?;
+        if !scope.allows(employee.office_id) {
+            return Err(CodedError::new("office.not_found").arg("id", employee.office_id).into());
+        }
+
+        let policy = self.validation_rules.for_employee(principal.tenant_id, Some(employee.office_id)).duplicates;
+        let scope = &scope;
+        let tenant_id = principal.tenant_id;
+
+        // the capacity check and the insert share a transaction, concurrent creates cannot overfill the office
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            self.check_department_tx(&mut tx, tenant_id, employee).await?;
+            // a new employee has no reports yet, so any existing manager is free of cycles
+            if let Some(manager_id) = employee.manager_id
+                && self.repo.get_employee_by_id_tx(&mut tx, tenant_id, manager_id).await?.is_none()
+            {
+                return Err(CodedError::new("employee.manager_not_found").arg("id", manager_id).into());
+            }
+
+            // a person is the same across offices, so the whole tenant is searched, but only in-scope IDs are reported
+            let mut duplicates = Vec::new();
+            if policy != DuplicatePolicy::Off {
+                let same_person = self.same_person_tx(&mut tx, tenant_id, employee).await?;
+                if !same_person.is_empty() && policy == DuplicatePolicy::Block {
+                    return Err(duplicate_error(employee).into());
+                }
+                duplicates = same_person.iter().filter(|e| scope.allows(e.office_id)).filter_map(|e| e.id).collect();
+                if !same_person.is_empty() {
+                    tracing::warn!("Employee {} {} is probably a duplicate of {} existing employee(s)", employee.first_name, employee.last_name, same_person.len());
+                }
+            }
 
-        let current_employee_nr = self.repo.current_employee_nr_by_office_id(employee.office_id).await?;
+            let office = self.office_repo.get_office_by_id_tx(&mut tx, tenant_id, employee.office_id)
+                .await?
+                .ok_or_else(|| CodedError::new("office.not_found").arg("id", employee.office_id))?;
+            // freed seats go to the waitlist first, a direct hire would jump the queue
+            let waiting = self.seats.waiting_tx(&mut tx, tenant_id, employee.office_id).await?;
+            if waiting > 0 {
+                return Err(waitlisted_error(&office, waiting).into());
+            }
+            self.seats.check_locations_tx(&mut tx, tenant_id, employee.office_id, None).await?;
 
-        if current_employee_nr >= office.max_occupancy as i64 {
-            return Err(anyhow!(
-                "Office {} is at full capacity: {}/{} employees", 
-                office.name, 
-                office.max_occupancy,
-                office.max_occupancy
-            ));
+            let created = self.repo.create_employee_tx(&mut tx, tenant_id, employee).await?;
+
+            // a future hire needs a free seat from the hire date on, not today, counted with the hire in place
+            let today = chrono::Utc::now().date_naive();
+            let seated = self.seats.peak_tx(&mut tx, tenant_id, employee.office_id, employee.seat_from(today), employee.termination_date).await?;
+            if seated > office.max_occupancy as i64 {
+                return Err(CodedError::new("office.full").arg("name", &office.name).arg("max", office.max_occupancy).into());
+            }
+            Ok((tx, (created, duplicates)))
+        })
+        .await
+    }
+
+    /// Lists clusters of in-scope employees that are probably the same person:
+    /// same birth date and the same name once case, accents, spaces, hyphens and apostrophes are ignored
+    /// Clusters are ordered by birth date, employees in a cluster by ID
+    pub async fn find_duplicates(&self, principal: &Principal) -> anyhow::Result<Vec<(chrono::NaiveDate, Vec<Employee>)>> {
+        tracing::info!("Searching for duplicate employees");
+        let mut clusters: BTreeMap<(chrono::NaiveDate, String), Vec<Employee>> = BTreeMap::new();
+        for employee in self.list_all_employees(principal).await? {
+            clusters.entry((employee.birth_date, employee.duplicate_key())).or_default().push(employee);
         }
-        self.repo.create_employee(employee).await
+        Ok(clusters
+            .into_iter()
+            .filter(|(_, employees)| employees.len() > 1)
+            .map(|((birth_date, _), mut employees)| {
+                employees.sort_by_key(|e| e.id);
+                (birth_date, employees)
+            })
+            .collect())
     }
 
-    /// Finds an employee by ID
-    pub async fn find_employee_by_id(&self, id: i32) -> anyhow::Result<Option<Employee>> {
+    /// Finds an employee by ID, None if it does not exist or is out of scope
+    pub async fn find_employee_by_id(&self, principal: &Principal, id: i32) -> anyhow::Result<Option<Employee>> {
         tracing::info!("Attempting to find employee with id: {}", id);
-        self.repo.get_employee_by_id(id).await
+        let scope = self.office_scope(principal, Permission::EmployeesRead).await?;
+        let employee = self.repo.get_employee_by_id(principal.tenant_id, id).await?;
+        Ok(employee.filter(|e| scope.allows(e.office_id)))
     }
 
-    /// Lists all employees
-    pub async fn list_all_employees(&self) -> anyhow::Result<Vec<Employee>> {
+    /// Lists all employees within scope
+    pub async fn list_all_employees(&self, principal: &Principal) -> anyhow::Result<Vec<Employee>> {
         tracing::info!("Listing all employees");
-        self.repo.get_all_employees().await
+        match self.office_scope(principal, Permission::EmployeesRead).await? {
+            OfficeScope::All => self.repo.get_all_employees(principal.tenant_id).await,
+            OfficeScope::Offices(office_ids) => self.repo.get_employees_by_office_ids(principal.tenant_id, &office_ids).await,
+        }
     }
 
     /// Lists employees by office ID
-    pub async fn list_employees_by_office_id(&self, office_id: i32) -> anyhow::Result<Vec<Employee>> {
+    pub async fn list_employees_by_office_id(&self, principal: &Principal, office_id: i32) -> anyhow::Result<Vec<Employee>> {
         tracing::info!("Listing employees for office id: {}", office_id);
 
-        let office_id_exist = self.office_repo.get_office_by_id(office_id).await?;
-        if office_id_exist.is_none() {
-            return Err(anyhow::anyhow!("Office with ID {} does not exist", office_id));
+        let scope = self.office_scope(principal, Permission::EmployeesRead).await?;
+        let office_id_exist = self.office_repo.get_office_by_id(principal.tenant_id, office_id).await?;
+        if office_id_exist.is_none() || !scope.allows(office_id) {
+            return Err(CodedError::new("office.not_found").arg("id", office_id).into());
         }
 
-        self.repo.get_employees_by_office_id(office_id).await
+        self.repo.get_employees_by_office_id(principal.tenant_id, office_id).await
     }
 
     /// Updates an existing employee after validating and checking office capacity
-    pub async fn update_employee(&self, id: i32, employee: &Employee) -> anyhow::Result<Employee> {
+    /// Moving to another office needs sign-off, it is refused with employee.move_needs_approval and made through
+    /// a change request instead (see update_employee_approved).
+    /// The manager is checked for cycles in the same transaction as the update.
+    /// Status and termination_date are kept, they only change through transitions, the hire date is kept unless one is given
+    pub async fn update_employee(&self, principal: &Principal, id: i32, employee: &Employee, hire_date: Option<NaiveDate>) -> anyhow::Result<Employee> {
         tracing::info!("Attempting to update employee with id: {}", id);
+        let scope = self.office_scope(principal, Permission::EmployeesUpdate).await?;
+        let scope = &scope;
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let updated = self.change_employee_tx(&mut tx, principal.tenant_id, scope, id, employee, hire_date, false).await?;
+            Ok((tx, updated))
+        })
+        .await
+    }
 
-        employee.validate().map_err(|e| anyhow::anyhow!(e))?; // validates last name and birth date
+    /// Updates an employee as an approved change request, a move to another office included
+    /// The current and the new office must be within the scope the approvals cover, a move needs a free seat in the
+    /// levels above the new site that do not hold the employee yet
+    pub async fn update_employee_approved(&self, tenant_id: i32, scope: &OfficeScope, id: i32, employee: &Employee, hire_date: Option<NaiveDate>) -> anyhow::Result<Employee> {
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let updated = self.update_employee_approved_tx(&mut tx, tenant_id, scope, id, employee, hire_date).await?;
+            Ok((tx, updated))
+        })
+        .await
+    }
 
-        let office = self.office_repo.get_office_by_id(employee.office_id)
+    /// update_employee_approved inside the caller's transaction, the change commits together with the approval
+    pub async fn update_employee_approved_tx(&self, tx: &mut Transaction, tenant_id: i32, scope: &OfficeScope, id: i32, employee: &Employee, hire_date: Option<NaiveDate>) -> anyhow::Result<Employee> {
+        tracing::info!("Applying approved update of employee with id: {}", id);
+        self.change_employee_tx(tx, tenant_id, scope, id, employee, hire_date, true).await
+    }
+
+    // Moving to another office requires scope on both the current and the new office
+    // The capacity check and the move share the transaction, concurrent moves cannot overfill the office
+    // The seat left behind goes to the old office's waitlist in the same transaction
+    #[allow(clippy::too_many_arguments)]
+    async fn change_employee_tx(&self, tx: &mut Transaction, tenant_id: i32, scope: &OfficeScope, id: i32, employee: &Employee, hire_date: Option<NaiveDate>, approved: bool) -> anyhow::Result<Employee> {
+        let existing = self.repo.get_employee_by_id_tx(tx, tenant_id, id)
             .await?
-            .ok_or_else(|| anyhow!("Office with ID {} does not exist", employee.office_id))?;
+            .filter(|e| scope.allows(e.office_id))
+            .ok_or_else(|| CodedError::new("employee.not_found").arg("id", id))?;
+        let employee = Employee {
+            hire_date: hire_date.unwrap_or(existing.hire_date),
+            status: existing.status,
+            termination_date: existing.termination_date,
+            ..employee.clone()
+        };
+        self.validate(tenant_id, &employee)?; // validates names, age and employment period, all broken rules at once
+        if !scope.allows(employee.office_id) {
+            tracing::warn!("Move of employee {} from office {} to out-of-scope office {}", id, existing.office_id, employee.office_id);
+            return Err(CodedError::new("office.not_found").arg("id", employee.office_id).into());
+        }
+        if existing.office_id != employee.office_id && !approved {
+            tracing::warn!("Move of employee {} from office {} to office {} without approval", id, existing.office_id, employee.office_id);
+            return Err(CodedError::new("employee.move_needs_approval").arg("id", id).into());
+        }
 
-        let current_employee_nr = self.repo.current_employee_nr_by_office_id(employee.office_id).await?;
+        let office = self.office_repo.get_office_by_id_tx(tx, tenant_id, employee.office_id)
+            .await?
+            .ok_or_else(|| CodedError::new("office.not_found").arg("id", employee.office_id))?;
 
-        if current_employee_nr >= office.max_occupancy as i64 {
-            return Err(anyhow!(
-                "Office {} is at full capacity: {}/{} employees", 
-                office.name, 
-                office.max_occupancy,
-                office.max_occupancy
-            ));
+        self.check_department_tx(tx, tenant_id, &employee).await?;
+        self.check_manager_tx(tx, tenant_id, id, employee.manager_id).await?;
+        if existing.office_id != employee.office_id {
+            self.seats.check_locations_tx(tx, tenant_id, employee.office_id, Some(existing.office_id)).await?;
         }
 
-        self.repo.update_employee_by_id(id, employee).await
+        let updated = self.repo.update_employee_by_id_tx(tx, tenant_id, id, &employee).await?;
+
+        // staying in the same office for the same period does not need a free seat, otherwise the office
+        // must not be over capacity on any day of the new seat period, counted with the employee in place
+        if existing.office_id != employee.office_id || existing.hire_date != employee.hire_date {
+            let today = chrono::Utc::now().date_naive();
+            let seated = self.seats.peak_tx(tx, tenant_id, employee.office_id, employee.seat_from(today), employee.termination_date).await?;
+            if seated > office.max_occupancy as i64 {
+                return Err(CodedError::new("office.full").arg("name", &office.name).arg("max", office.max_occupancy).into());
+            }
+        }
+        if existing.office_id != employee.office_id {
+            self.seats.fill_waitlist_tx(tx, tenant_id, existing.office_id).await?;
+        }
+        Ok(updated)
     }
 
-    /// Removes an employee by ID
-    pub async fn remove_employee(&self, id: i32) -> anyhow::Result<bool> {
+    /// Removes an employee by ID, false if it does not exist or is out of scope
+    /// The freed seat goes to the office's waitlist in the same transaction
+    pub async fn remove_employee(&self, principal: &Principal, id: i32) -> anyhow::Result<bool> {
         tracing::info!("Deleting employee id: {}", id);
-        let rows = self.repo.delete_employee(id).await?;
-        Ok(rows > 0)
+        let scope = self.office_scope(principal, Permission::EmployeesDelete).await?;
+        let scope = &scope;
+        let tenant_id = principal.tenant_id;
+
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let Some(existing) = self.repo.get_employee_by_id_tx(&mut tx, tenant_id, id).await?.filter(|e| scope.allows(e.office_id)) else {
+                return Ok((tx, false));
+            };
+            let rows = self.repo.delete_employee_tx(&mut tx, tenant_id, id).await?;
+            self.seats.fill_waitlist_tx(&mut tx, tenant_id, existing.office_id).await?;
+            Ok((tx, rows > 0))
+        })
+        .await
     }
+
+    // An employee within the read scope, or employee.not_found
+    async fn employee_in_scope(&self, principal: &Principal, id: i32) -> anyhow::Result<(Employee, OfficeScope)> {
+        let scope = self.office_scope(principal, Permission::EmployeesRead).await?;
+        let employee = self.repo.get_employee_by_id(principal.tenant_id, id)
+            .await?
+            .filter(|e| scope.allows(e.office_id))
+            .ok_or_else(|| CodedError::new("employee.not_found").arg("id", id))?;
+        Ok((employee, scope))
+    }
+
+    /// Lists the employees reporting directly to an employee, ordered by ID
+    /// Reports outside the caller's scope are left out
+    pub async fn list_direct_reports(&self, principal: &Principal, id: i32) -> anyhow::Result<Vec<Employee>> {
+        tracing::info!("Listing direct reports of employee id: {}", id);
+        let (_, scope) = self.employee_in_scope(principal, id).await?;
+        let reports = self.repo.get_direct_reports(principal.tenant_id, id).await?;
+        Ok(reports.into_iter().filter(|e| scope.allows(e.office_id)).collect())
+    }
+
+    /// Lists the managers above an employee, nearest first and the top of the hierarchy last
+    /// Managers outside the caller's scope are left out
+    pub async fn list_reporting_chain(&self, principal: &Principal, id: i32) -> anyhow::Result<Vec<Employee>> {
+        tracing::info!("Listing reporting chain of employee id: {}", id);
+        let (_, scope) = self.employee_in_scope(principal, id).await?;
+        let chain = self.repo.get_reporting_chain(principal.tenant_id, id).await?;
+        Ok(chain.into_iter().filter(|e| scope.allows(e.office_id)).collect())
+    }
+
+    /// Lists everyone below an employee, level by level and by ID within a level
+    /// Employees outside the caller's scope are left out, their own reports are still listed when in scope
+    pub async fn list_subtree(&self, principal: &Principal, id: i32) -> anyhow::Result<Vec<Employee>> {
+        tracing::info!("Listing subtree of employee id: {}", id);
+        let (_, scope) = self.employee_in_scope(principal, id).await?;
+        let subtree = self.repo.get_subtree(principal.tenant_id, id).await?;
+        Ok(subtree.into_iter().filter(|e| scope.allows(e.office_id)).collect())
+    }
+
+    /// Moves an employee to the next status of the employment lifecycle, see EmploymentStatus::can_become
+    /// A pending employee can only become active on or after the hire date. Terminating sets the last day
+    /// in the office, the seat is free from the day after. The change is recorded in the audit log
+    pub async fn transition_employee(
+        &self,
+        principal: &Principal,
+        id: i32,
+        next: EmploymentStatus,
+        termination_date: Option<chrono::NaiveDate>,
+    ) -> anyhow::Result<Employee> {
+        tracing::info!("Attempting to move employee {} to status {}", id, next);
+        let scope = self.office_scope(principal, Permission::EmployeesUpdate).await?;
+        let scope = &scope;
+        let tenant_id = principal.tenant_id;
+
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let existing = self.repo.get_employee_by_id_tx(&mut tx, tenant_id, id)
+                .await?
+                .filter(|e| scope.allows(e.office_id))
+                .ok_or_else(|| CodedError::new("employee.not_found").arg("id", id))?;
+            if !existing.status.can_become(next) {
+                return Err(CodedError::new("employee.invalid_transition").arg("id", id).arg("from", existing.status).arg("to", next).into());
+            }
+            let today = chrono::Utc::now().date_naive();
+            if existing.status == EmploymentStatus::Pending && next == EmploymentStatus::Active && existing.hire_date > today {
+                return Err(CodedError::new("employee.not_started").arg("id", id).arg("hire_date", existing.hire_date).into());
+            }
+
+            let changed = Employee {
+                status: next,
+                termination_date: if next == EmploymentStatus::Terminated { termination_date } else { existing.termination_date },
+                ..existing.clone()
+            };
+            let mut errors = ValidationErrors::new();
+            changed.check_employment(today, &mut errors);
+            errors.into_result()?;
+            let updated = self.repo.update_employee_by_id_tx(&mut tx, tenant_id, id, &changed).await?;
+
+            let details = serde_json::json!({ "from": existing.status, "to": next, "termination_date": changed.termination_date });
+            let entry = AuditEntry {
+                id: None,
+                subject: principal.subject.clone(),
+                action: "employee.status_changed".to_string(),
+                employee_id: Some(id),
+                details: details.to_string(),
+                created_at: chrono::Utc::now(),
+            };
+            self.audit_repo.record_audit_entry_tx(&mut tx, tenant_id, &entry).await?;
+            Ok((tx, updated))
+        })
+        .await
+    }
+
+    /// Merges the source employee of the request into the target employee, in one transaction
+    /// Field values survive by the request's strategy and are validated like an update. Records about the source
+    /// move to the target, the source is archived and an "employee.merged" audit entry is written.
+    /// Only the source's seat is released: taking the source's office hands its seat over and frees the target's.
+    /// The freed seat goes to the office's waitlist in the same transaction.
+    /// The source's reports report to the target afterwards, a manager that would close a cycle fails the merge.
+    /// Both employees must be within scope
+    pub async fn merge_employees(&self, principal: &Principal, target_id: i32, request: &MergeEmployeeRequest) -> anyhow::Result<(Employee, AuditEntry)> {
+        tracing::info!("Attempting to merge employee {} into {}", request.source_id, target_id);
+        if request.source_id == target_id {
+            return Err(CodedError::new("employee.merge_self").arg("id", target_id).into());
+        }
+
+        let scope = self.office_scope(principal, Permission::EmployeesUpdate).await?;
+        let scope = &scope;
+        let tenant_id = principal.tenant_id;
+
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let mut in_scope = async |id: i32| -> anyhow::Result<Employee> {
+                self.repo.get_employee_by_id_tx(&mut tx, tenant_id, id)
+                    .await?
+                    .filter(|e| scope.allows(e.office_id))
+                    .ok_or_else(|| CodedError::new("employee.not_found").arg("id", id).into())
+            };
+            let target = in_scope(target_id).await?;
+            let source = in_scope(request.source_id).await?;
+
+            let merged = request.merged(&target, &source);
+            self.validate(tenant_id, &merged)?;
+
+            // the source leaves before the target may take its office, so no office is ever over capacity
+            self.repo.merge_employee_tx(&mut tx, tenant_id, request.source_id, target_id, merged.office_id).await?;
+            self.check_manager_tx(&mut tx, tenant_id, target_id, merged.manager_id).await?;
+            let survivor = self.repo.update_employee_by_id_tx(&mut tx, tenant_id, target_id, &merged).await?;
+            // the freed seat is in the source's office, or in the target's if the survivor took the source's
+            self.seats.fill_waitlist_tx(&mut tx, tenant_id, source.office_id).await?;
+            if target.office_id != survivor.office_id {
+                self.seats.fill_waitlist_tx(&mut tx, tenant_id, target.office_id).await?;
+            }
+
+            let details = serde_json::json!({ "source": source, "target": target, "result": survivor, "request": request });
+            let entry = AuditEntry {
+                id: None,
+                subject: principal.subject.clone(),
+                action: "employee.merged".to_string(),
+                employee_id: Some(target_id),
+                details: details.to_string(),
+                created_at: chrono::Utc::now(),
+            };
+            let entry = self.audit_repo.record_audit_entry_tx(&mut tx, tenant_id, &entry).await?;
+            Ok((tx, (survivor, entry)))
+        })
+        .await
+    }
+
+    /// Imports a batch of employees
+    /// Every row is resolved (office by id or name), validated and checked against office capacity
+    /// counting the rows before it in the same batch. Atomic mode writes nothing if any row fails,
+    /// partial mode writes the valid rows. Dry run only reports.
+    /// Row errors are written in the given language
+    pub async fn import_employees(
+        &self,
+        principal: &Principal,
+        rows: Vec<Result<ImportEmployeeRow, String>>,
+        mode: ImportMode,
+        dry_run: bool,
+        language: Language,
+    ) -> anyhow::Result<ImportReport> {
+        tracing::info!("Importing {} employee rows, mode {:?}, dry run {}", rows.len(), mode, dry_run);
+
+        let scope = self.office_scope(principal, Permission::EmployeesCreate).await?;
+        let scope = &scope;
+        let tenant_id = principal.tenant_id;
+
+        // office names are resolved up front, the rows only carry the ID into the transaction
+        let mut office_ids_by_name: HashMap<String, Option<i32>> = HashMap::new();
+        for row in rows.iter().flatten() {
+            if let (None, Some(name)) = (row.office_id, row.office_name.as_deref()) {
+                let name = name.trim().to_string();
+                if let Entry::Vacant(slot) = office_ids_by_name.entry(name.clone()) {
+                    let found = self.office_repo.get_office_by_name(tenant_id, &name).await?;
+                    slot.insert(found.and_then(|o| o.id));
+                }
+            }
+        }
+        let rows = &rows;
+        let office_ids_by_name = &office_ids_by_name;
+
+        // the capacity checks and the inserts share a transaction, a concurrent import cannot overfill an office
+        let report = in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let mut offices_by_id: HashMap<i32, Option<Office>> = HashMap::new();
+            let mut seat_periods: HashMap<i32, Vec<(NaiveDate, Option<NaiveDate>)>> = HashMap::new();
+            let mut waiting_by_office: HashMap<i32, usize> = HashMap::new();
+            let locations = self.seats.locations_tx(&mut tx, tenant_id).await?;
+            let mut region_arrivals: HashMap<i32, i64> = HashMap::new();
+
+            let mut results = Vec::with_capacity(rows.len());
+            let mut accepted: Vec<(usize, Employee)> = Vec::new();
+
+            for (index, row) in rows.iter().enumerate() {
+                let number = index + 1;
+                let row = match row {
+                    Ok(row) => row,
+                    Err(e) => {
+                        results.push(ImportRowResult { row: number, status: ImportRowStatus::Failed, employee: None, errors: vec![e.clone()] });
+                        continue;
+                    }
+                };
+
+                // resolve office by id, else by name
+                let office_id = match (row.office_id, row.office_name.as_deref()) {
+                    (Some(id), _) => Some(id),
+                    (None, Some(name)) => office_ids_by_name[name.trim()],
+                    (None, None) => None,
+                };
+                let Some(office_id) = office_id else {
+                    let error = match &row.office_name {
+                        Some(name) => CodedError::new("office.name_not_found").arg("name", name.trim()),
+                        None => CodedError::new("import.office_missing"),
+                    };
+                    results.push(ImportRowResult { row: number, status: ImportRowStatus::Failed, employee: None, errors: vec![error.message(language)] });
+                    continue;
+                };
+                if let Entry::Vacant(slot) = offices_by_id.entry(office_id) {
+                    let office = self.office_repo.get_office_by_id_tx(&mut tx, tenant_id, office_id).await?;
+                    slot.insert(office.filter(|_| scope.allows(office_id)));
+                }
+                let Some(office) = offices_by_id[&office_id].clone() else {
+                    let error = CodedError::new("office.not_found").arg("id", office_id);
+                    results.push(ImportRowResult { row: number, status: ImportRowStatus::Failed, employee: None, errors: vec![error.message(language)] });
+                    continue;
+                };
+
+                let employee = Employee::from_import_row(row, office_id);
+                if let Err(e) = self.validate(tenant_id, &employee) {
+                    results.push(ImportRowResult { row: number, status: ImportRowStatus::Failed, employee: None, errors: e.localized(language).messages() });
+                    continue;
+                }
+
+                // with the block policy a row may match neither an existing employee nor a row accepted earlier in this batch
+                if self.validation_rules.for_employee(tenant_id, Some(office_id)).duplicates == DuplicatePolicy::Block {
+                    let key = (employee.birth_date, employee.duplicate_key());
+                    let in_batch = accepted.iter().any(|(_, e)| (e.birth_date, e.duplicate_key()) == key);
+                    if in_batch || !self.same_person_tx(&mut tx, tenant_id, &employee).await?.is_empty() {
+                        results.push(ImportRowResult { row: number, status: ImportRowStatus::Failed, employee: None, errors: vec![duplicate_error(&employee).message(language)] });
+                        continue;
+                    }
+                }
+
+                // like a direct hire a row does not jump the waitlist
+                if let Entry::Vacant(slot) = waiting_by_office.entry(office_id) {
+                    slot.insert(self.seats.waiting_tx(&mut tx, tenant_id, office_id).await?);
+                }
+                if waiting_by_office[&office_id] > 0 {
+                    let error = waitlisted_error(&office, waiting_by_office[&office_id]);
+                    results.push(ImportRowResult { row: number, status: ImportRowStatus::Failed, employee: None, errors: vec![error.message(language)] });
+                    continue;
+                }
+
+                // capacity counts the projected occupancy (future hires and pending transfers included) plus rows accepted
+                // earlier in this batch, from the day the row takes its seat like a direct hire
+                if let Entry::Vacant(slot) = seat_periods.entry(office_id) {
+                    slot.insert(self.seats.seat_periods_tx(&mut tx, tenant_id, office_id, None).await?);
+                }
+                let periods = seat_periods.get_mut(&office_id).expect("seats loaded above");
+                let today = chrono::Utc::now().date_naive();
+                if peak_seats(periods, employee.seat_from(today), employee.termination_date) >= office.max_occupancy as i64 {
+                    let error = CodedError::new("office.full").arg("name", &office.name).arg("max", office.max_occupancy);
+                    results.push(ImportRowResult { row: number, status: ImportRowStatus::Failed, employee: None, errors: vec![error.message(language)] });
+                    continue;
+                }
+                // so do the levels above the office's site
+                let region_id = locations.site_of(office_id).and_then(|site| site.parent_id);
+                let arriving = region_id.and_then(|id| region_arrivals.get(&id)).copied().unwrap_or(0) + 1;
+                if let Some((location, capacity)) = locations.first_full_above_site(office_id, None, arriving) {
+                    let error = location.full(capacity);
+                    results.push(ImportRowResult { row: number, status: ImportRowStatus::Failed, employee: None, errors: vec![error.message(language)] });
+                    continue;
+                }
+                periods.push((employee.hire_date, employee.termination_date));
+                if let Some(region_id) = region_id {
+                    *region_arrivals.entry(region_id).or_default() += 1;
+                }
+
+                results.push(ImportRowResult { row: number, status: ImportRowStatus::Valid, employee: Some(employee.to_response()), errors: vec![] });
+                accepted.push((results.len() - 1, employee));
+            }
+
+            let failed = results.iter().filter(|r| r.status == ImportRowStatus::Failed).count();
+            let aborted = mode == ImportMode::Atomic && failed > 0;
+            let write = !dry_run && !aborted;
+            let mut imported = 0;
+
+            if write {
+                for (result_index, employee) in &accepted {
+                    let created = self.repo.create_employee_tx(&mut tx, tenant_id, employee).await?;
+                    let result = &mut results[*result_index];
+                    result.status = ImportRowStatus::Imported;
+                    result.employee = Some(created.to_response());
+                }
+                imported = accepted.len();
+            }
+            Ok((tx, ImportReport { mode, dry_run, total: results.len(), imported, failed, rows: results }))
+        })
+        .await?;
+        tracing::info!("Import finished: {} imported, {} failed", report.imported, report.failed);
+
+        Ok(report)
+    }
+
+    /// Streams employees within scope for export, optionally limited to one office
+    /// The office filter is checked up front so an unknown or out-of-scope office fails before streaming starts.
+    /// Rows are read by a background task from a database cursor and handed over through a bounded channel,
+    /// so the stream owns nothing borrowed and memory stays flat regardless of table size
+    pub async fn export_employees(
+        &self,
+        principal: &Principal,
+        office_id: Option<i32>,
+        include_office: bool,
+    ) -> anyhow::Result<BoxStream<'static, anyhow::Result<EmployeeExportRow>>> {
+        tracing::info!("Exporting employees, office filter {:?}, include office {}", office_id, include_office);
+
+        let scope = self.office_scope(principal, Permission::EmployeesRead).await?;
+        if let Some(office_id) = office_id {
+            let office_id_exist = self.office_repo.get_office_by_id(principal.tenant_id, office_id).await?;
+            if office_id_exist.is_none() || !scope.allows(office_id) {
+                return Err(CodedError::new("office.not_found").arg("id", office_id).into());
+            }
+        }
+        let office_ids = match scope {
+            OfficeScope::All => None,
+            OfficeScope::Offices(office_ids) => Some(office_ids),
+        };
+
+        let repo = self.repo.clone();
+        let tenant_id = principal.tenant_id;
+        let (tx, rx) = tokio::sync::mpsc::channel(EXPORT_BUFFER_ROWS);
+        tokio::spawn(async move {
+            let mut rows = repo.stream_employees(tenant_id, office_id, office_ids, include_office);
+            while let Some(row) = rows.next().await {
+                if tx.send(row).await.is_err() {
+                    tracing::info!("Employee export cancelled by client");
+                    break;
+                }
+            }
+        });
+        Ok(ReceiverStream::new(rx).boxed())
+    }
+}
+
+// Error of a direct hire into an office with hires waiting for a seat
+fn waitlisted_error(office: &Office, waiting: usize) -> CodedError {
+    CodedError::new("office.waitlisted").arg("name", &office.name).arg("count", waiting)
+}
+
+// Error of a create blocked by the duplicate policy, names no IDs as the match may be outside the caller's scope
+fn duplicate_error(employee: &Employee) -> CodedError {
+    CodedError::new("employee.duplicate")
+        .arg("name", format!("{} {}", employee.first_name, employee.last_name))
+        .arg("birth_date", employee.birth_date)
 }
\ No newline at end of file
@@ -0,0 +1,35 @@
+use crate::repository::health_repository::HealthStore;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a readiness probe waits for the database before reporting not ready
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Service for liveness and readiness probes
+/// Ready means the database answers a query, so a lazily connecting server reports not ready until it is reachable
+#[derive(Clone)]
+pub struct HealthService {
+    repo: Arc<dyn HealthStore>,
+}
+
+impl HealthService {
+    /// Constructor for HealthService, works with any storage backend
+    pub fn new(repo: impl HealthStore + 'static) -> Self {
+        Self { repo: Arc::new(repo) }
+    }
+
+    /// True when the database answered within READINESS_TIMEOUT
+    pub async fn is_ready(&self) -> bool {
+        match tokio::time::timeout(READINESS_TIMEOUT, self.repo.ping()).await {
+            Ok(Ok(())) => true,
+            Ok(Err(e)) => {
+                tracing::debug!("Readiness check failed: {}", e);
+                false
+            }
+            Err(_) => {
+                tracing::debug!("Readiness check timed out after {:?}", READINESS_TIMEOUT);
+                false
+            }
+        }
+    }
+}
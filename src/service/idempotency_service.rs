@@ -0,0 +1,86 @@
+use crate::entity::idempotency_record::{IdempotencyKey, IdempotencyRecord};
+use crate::repository::idempotency_repository::IdempotencyStore;
+use std::sync::Arc;
+use sha2::{Digest, Sha256};
+
+/// Service for Idempotency-Key handling
+/// The first request with a key reserves it, its response is stored and replayed for retries.
+/// A retry with a different body is refused, keys expire after the configured window.
+#[derive(Clone)]
+pub struct IdempotencyService {
+    repo: Arc<dyn IdempotencyStore>,
+    ttl: chrono::Duration,
+}
+
+/// Outcome of presenting a key with a request
+#[derive(Debug)]
+pub enum IdempotencyOutcome {
+    Proceed, // key reserved, run the request and store its response
+    Replay(IdempotencyRecord), // key already answered, return the stored response
+    InProgress, // first request with the key has not finished yet
+    Mismatch, // key was used with a different request body
+}
+
+impl IdempotencyService {
+    /// Constructor for IdempotencyService, works with any storage backend
+    pub fn new(repo: impl IdempotencyStore + 'static, ttl: chrono::Duration) -> Self {
+        Self { repo: Arc::new(repo), ttl }
+    }
+
+    /// Hex encoded sha256 of a request body
+    pub fn hash_body(body: &[u8]) -> String {
+        hex::encode(Sha256::digest(body))
+    }
+
+    /// Reserves the key or reports how an earlier request with it went
+    pub async fn begin(&self, key: &IdempotencyKey, request_hash: &str) -> anyhow::Result<IdempotencyOutcome> {
+        let expired_before = chrono::Utc::now() - self.ttl;
+        let purged = self.repo.delete_expired_idempotency_keys(expired_before).await?;
+        if purged > 0 {
+            tracing::debug!("Purged {} expired idempotency keys", purged);
+        }
+
+        if self.repo.reserve_idempotency_key(key, request_hash, expired_before).await? {
+            return Ok(IdempotencyOutcome::Proceed);
+        }
+
+        let Some(record) = self.repo.get_idempotency_record(key).await? else {
+            // released by a concurrent failing request between reserve and lookup
+            return Ok(IdempotencyOutcome::InProgress);
+        };
+
+        if record.request_hash != request_hash {
+            tracing::warn!("Idempotency key {} reused with a different body on {}", key.key, key.route);
+            return Ok(IdempotencyOutcome::Mismatch);
+        }
+        if record.status_code.is_none() {
+            return Ok(IdempotencyOutcome::InProgress);
+        }
+        tracing::info!("Replaying stored response for idempotency key {} on {}", key.key, key.route);
+        Ok(IdempotencyOutcome::Replay(record))
+    }
+
+    /// Stores the response of a reserved key
+    pub async fn complete(&self, key: &IdempotencyKey, status_code: u16, content_type: Option<&str>, body: &[u8]) -> anyhow::Result<()> {
+        self.repo.complete_idempotency_key(key, status_code as i32, content_type, body).await
+    }
+
+    /// Releases a reserved key without storing a response, so the client can retry
+    pub async fn release(&self, key: &IdempotencyKey) -> anyhow::Result<()> {
+        self.repo.delete_idempotency_key(key).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_body_is_stable_and_body_sensitive() {
+        let a = IdempotencyService::hash_body(br#"{"name":"Aalborg"}"#);
+        assert_eq!(a, IdempotencyService::hash_body(br#"{"name":"Aalborg"}"#));
+        assert_ne!(a, IdempotencyService::hash_body(br#"{"name":"Aarhus"}"#));
+        assert_eq!(a.len(), 64);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::dto::location_dto::{CreateLocationRequest, LocationResponse, UpdateLocationRequest};
+use crate::entity::location::{Location, LocationKind, LocationTree};
+use crate::repository::location_repository::LocationStore;
+use crate::repository::employee_repository::EmployeeStore;
+use crate::repository::office_repository::OfficeStore;
+use crate::repository::office_manager_repository::OfficeManagerStore;
+use crate::repository::unit_of_work::{in_transaction, Transaction, UnitOfWork};
+use std::sync::Arc;
+use crate::auth::{permission::Permission, principal::Principal, scope::OfficeScope};
+use crate::utils::{CodedError, Validate, ValidationErrors};
+
+/// Service for the location tree
+/// Keeps regions, sites, buildings, floors and rooms of a tenant. Every office is a site, the migration made one
+/// for each office that existed, and a room belongs to the office of its site.
+///
+/// Employees sit in their room, or in the site of their office until they get one. Capacity can be set on any level
+/// but a site, whose capacity is the office's max_occupancy; a level without one takes the sum of its children's.
+/// Putting an employee in a room checks every level from the room up that does not hold the employee already
+#[derive(Clone)]
+pub struct LocationService {
+    repo: Arc<dyn LocationStore>,
+    employee_repo: Arc<dyn EmployeeStore>,
+    office_repo: Arc<dyn OfficeStore>,
+    manager_repo: Arc<dyn OfficeManagerStore>,
+    unit_of_work: Arc<dyn UnitOfWork>,
+}
+
+impl LocationService {
+    /// Constructor for LocationService, works with any storage backend
+    pub fn new(
+        repo: impl LocationStore + 'static,
+        employee_repo: impl EmployeeStore + 'static,
+        office_repo: impl OfficeStore + 'static,
+        manager_repo: impl OfficeManagerStore + 'static,
+        unit_of_work: impl UnitOfWork + 'static,
+    ) -> Self {
+        Self {
+            repo: Arc::new(repo),
+            employee_repo: Arc::new(employee_repo),
+            office_repo: Arc::new(office_repo),
+            manager_repo: Arc::new(manager_repo),
+            unit_of_work: Arc::new(unit_of_work),
+        }
+    }
+
+    // Offices the principal may act on with the permission, see EmployeeService::office_scope
+    async fn office_scope(&self, principal: &Principal, permission: Permission) -> anyhow::Result<OfficeScope> {
+        if !principal.is_office_scoped(permission) {
+            return Ok(OfficeScope::All);
+        }
+        let office_ids = self.manager_repo.get_office_ids_by_subject(principal.tenant_id, &principal.subject).await?;
+        Ok(OfficeScope::Offices(office_ids))
+    }
+
+    /// Lists the locations of the tenant in tree order with rolled up capacity and occupancy
+    pub async fn list_locations(&self, principal: &Principal) -> anyhow::Result<Vec<LocationResponse>> {
+        tracing::info!("Listing all locations");
+        let tree = self.tree(principal.tenant_id).await?;
+        Ok(tree.in_order().into_iter().map(|location| tree.to_response(location)).collect())
+    }
+
+    /// Finds a location by ID with rolled up capacity and occupancy
+    pub async fn find_location(&self, principal: &Principal, id: i32) -> anyhow::Result<Option<LocationResponse>> {
+        tracing::info!("Attempting to find location with id: {}", id);
+        let tree = self.tree(principal.tenant_id).await?;
+        Ok(tree.get(id).map(|location| tree.to_response(location)))
+    }
+
+    /// Adds a location under a parent of the kind above it
+    /// A site names an office without a site and may stand at the top of the tree or in a region,
+    /// buildings, floors and rooms take the office of their parent
+    pub async fn create_location(&self, principal: &Principal, req: CreateLocationRequest) -> anyhow::Result<LocationResponse> {
+        tracing::info!("Attempting to add {} location with name: {}", req.kind, req.name);
+        let location = Location::from_create_request(req);
+        location.validate()?;
+        if location.kind != LocationKind::Site && location.office_id.is_some() {
+            let mut errors = ValidationErrors::new();
+            errors.add("office_id", "not_for_kind", &[("kind", &location.kind)]);
+            return Err(errors.into());
+        }
+
+        let tenant_id = principal.tenant_id;
+        let location = &location;
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let mut location = location.clone();
+            let tree = self.tree_tx(&mut tx, tenant_id).await?;
+            let parent = self.parent(&tree, location.parent_id)?;
+            let mut errors = ValidationErrors::new();
+            location.check_parent(parent, &mut errors);
+            errors.into_result()?;
+
+            if location.kind == LocationKind::Site {
+                let office_id = location.office_id.unwrap_or_default();
+                let office = self.office_repo.get_office_by_id_tx(&mut tx, tenant_id, office_id)
+                    .await?
+                    .ok_or_else(|| CodedError::new("office.not_found").arg("id", office_id))?;
+                if let Some(site) = tree.site_of(office_id) {
+                    return Err(CodedError::new("location.site_exists").arg("name", &office.name).arg("id", site.id.unwrap_or_default()).into());
+                }
+            } else {
+                location.office_id = parent.and_then(|p| p.office_id);
+            }
+
+            let created = self.repo.create_location_tx(&mut tx, tenant_id, &location).await?;
+            let created = self.response_tx(&mut tx, tenant_id, created.id.unwrap_or_default()).await?;
+            Ok((tx, created))
+        })
+        .await
+    }
+
+    /// Renames a location and sets or removes its own capacity, which cannot drop below its occupancy
+    /// Only a site moves, to another region or to the top of the tree; a region with its own capacity must have room
+    /// for everyone sitting in the site
+    pub async fn update_location(&self, principal: &Principal, id: i32, req: UpdateLocationRequest) -> anyhow::Result<LocationResponse> {
+        tracing::info!("Attempting to update location with id: {}", id);
+        let tenant_id = principal.tenant_id;
+        let req = &req;
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let tree = self.tree_tx(&mut tx, tenant_id).await?;
+            let existing = tree.get(id).ok_or_else(|| CodedError::new("location.not_found").arg("id", id))?;
+            let changed = Location { name: req.name.trim().to_string(), capacity: req.capacity, parent_id: req.parent_id, ..existing.clone() };
+            changed.validate()?;
+
+            let usage = tree.usage(id);
+            if req.parent_id != existing.parent_id {
+                let mut errors = ValidationErrors::new();
+                if existing.kind != LocationKind::Site {
+                    errors.add("parent_id", "fixed", &[("kind", &existing.kind)]);
+                    return Err(errors.into());
+                }
+                let parent = self.parent(&tree, req.parent_id)?;
+                changed.check_parent(parent, &mut errors);
+                errors.into_result()?;
+                if let Some((region, region_capacity)) = parent.and_then(|p| Some((p, p.capacity?))) {
+                    let occupancy = tree.usage(region.id.unwrap_or_default()).occupancy + usage.occupancy;
+                    if occupancy > region_capacity as i64 {
+                        return Err(region.full(region_capacity as i64).into());
+                    }
+                }
+            }
+            if let Some(own) = req.capacity
+                && (own as i64) < usage.occupancy
+            {
+                return Err(CodedError::new("location.capacity_below_occupancy").arg("count", usage.occupancy).arg("capacity", own).into());
+            }
+
+            self.repo.update_location_tx(&mut tx, tenant_id, id, &changed).await?;
+            let updated = self.response_tx(&mut tx, tenant_id, id).await?;
+            Ok((tx, updated))
+        })
+        .await
+    }
+
+    /// Removes a location that has nothing below it and nobody sitting in it, false if it does not exist
+    pub async fn delete_location(&self, principal: &Principal, id: i32) -> anyhow::Result<bool> {
+        tracing::info!("Deleting location id: {}", id);
+        let tenant_id = principal.tenant_id;
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let tree = self.tree_tx(&mut tx, tenant_id).await?;
+            let Some(location) = tree.get(id) else {
+                return Ok((tx, false));
+            };
+            if tree.has_children(id) || tree.usage(id).occupancy > 0 {
+                return Err(CodedError::new("location.not_empty").arg("name", &location.name).into());
+            }
+            let rows = self.repo.delete_location_tx(&mut tx, tenant_id, id).await?;
+            Ok((tx, rows > 0))
+        })
+        .await
+    }
+
+    /// Puts an employee in a room of its office, replacing the room it had
+    /// Every level from the room up that does not hold the employee yet must have a free seat, otherwise location.full
+    /// names the first level without one. The employee must be within scope for updating employees
+    pub async fn assign_employee(&self, principal: &Principal, location_id: i32, employee_id: i32) -> anyhow::Result<LocationResponse> {
+        tracing::info!("Attempting to put employee {} in location {}", employee_id, location_id);
+        let scope = self.office_scope(principal, Permission::EmployeesUpdate).await?;
+        let scope = &scope;
+        let tenant_id = principal.tenant_id;
+
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let employee = self.employee_repo.get_employee_by_id_tx(&mut tx, tenant_id, employee_id)
+                .await?
+                .filter(|e| scope.allows(e.office_id))
+                .ok_or_else(|| CodedError::new("employee.not_found").arg("id", employee_id))?;
+            let tree = self.tree_tx(&mut tx, tenant_id).await?;
+            let room = tree.get(location_id).ok_or_else(|| CodedError::new("location.not_found").arg("id", location_id))?;
+            if room.kind != LocationKind::Room {
+                return Err(CodedError::new("location.not_room").arg("name", &room.name).arg("kind", room.kind).into());
+            }
+            if room.office_id != Some(employee.office_id) {
+                return Err(CodedError::new("location.other_office").arg("id", employee_id).arg("name", &room.name).into());
+            }
+
+            let current = match self.repo.get_room_id_tx(&mut tx, tenant_id, employee_id).await? {
+                Some(room_id) => Some(room_id),
+                None => tree.site_of(employee.office_id).and_then(|site| site.id),
+            };
+            if let Some((location, capacity)) = tree.first_full(location_id, current, 1) {
+                return Err(location.full(capacity).into());
+            }
+
+            self.repo.assign_employee_tx(&mut tx, tenant_id, employee_id, location_id).await?;
+            let room = self.response_tx(&mut tx, tenant_id, location_id).await?;
+            Ok((tx, room))
+        })
+        .await
+    }
+
+    /// Takes an employee out of a room, the employee sits in the site of its office again.
+    /// false if the employee is not in the room or out of scope
+    pub async fn unassign_employee(&self, principal: &Principal, location_id: i32, employee_id: i32) -> anyhow::Result<bool> {
+        tracing::info!("Taking employee {} out of location {}", employee_id, location_id);
+        let scope = self.office_scope(principal, Permission::EmployeesUpdate).await?;
+        let scope = &scope;
+        let tenant_id = principal.tenant_id;
+
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let in_scope = self.employee_repo.get_employee_by_id_tx(&mut tx, tenant_id, employee_id)
+                .await?
+                .is_some_and(|e| scope.allows(e.office_id));
+            if !in_scope {
+                return Ok((tx, false));
+            }
+            let rows = self.repo.unassign_employee_tx(&mut tx, tenant_id, employee_id, location_id).await?;
+            Ok((tx, rows > 0))
+        })
+        .await
+    }
+
+    async fn tree(&self, tenant_id: i32) -> anyhow::Result<LocationTree> {
+        let today = chrono::Utc::now().date_naive();
+        let locations = self.repo.get_locations(tenant_id).await?;
+        let occupants = self.repo.get_occupants(tenant_id, today).await?;
+        Ok(LocationTree::new(locations, &occupants))
+    }
+
+    async fn tree_tx(&self, tx: &mut Transaction, tenant_id: i32) -> anyhow::Result<LocationTree> {
+        let today = chrono::Utc::now().date_naive();
+        let locations = self.repo.get_locations_tx(tx, tenant_id).await?;
+        let occupants = self.repo.get_occupants_tx(tx, tenant_id, today).await?;
+        Ok(LocationTree::new(locations, &occupants))
+    }
+
+    // A location as changed within the transaction, with the figures of the whole tree
+    async fn response_tx(&self, tx: &mut Transaction, tenant_id: i32, id: i32) -> anyhow::Result<LocationResponse> {
+        let tree = self.tree_tx(tx, tenant_id).await?;
+        tree.get(id)
+            .map(|location| tree.to_response(location))
+            .ok_or_else(|| CodedError::new("location.not_found").arg("id", id).into())
+    }
+
+    // The parent a location is placed under, location.not_found for an unknown ID
+    fn parent<'a>(&self, tree: &'a LocationTree, parent_id: Option<i32>) -> anyhow::Result<Option<&'a Location>> {
+        match parent_id {
+            Some(parent_id) => Ok(Some(tree.get(parent_id).ok_or_else(|| CodedError::new("location.not_found").arg("id", parent_id))?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -1,2 +1,10 @@
 pub mod office_service;
-pub mod employee_service;
\ No newline at end of file
+pub mod employee_service;
+pub mod department_service;
+pub mod transfer_service;
+pub mod change_request_service;
+pub mod waitlist_service;
+pub mod location_service;
+pub mod seat_service;
+pub mod idempotency_service;
+pub mod health_service;
\ No newline at end of file
@@ -1,69 +1,286 @@
 use crate::entity::office::Office;
-use crate::repository::office_repository::OfficeRepository;
-use anyhow::{anyhow};
-use crate::utils::Validate;
+use crate::repository::office_repository::OfficeStore;
+use crate::repository::office_manager_repository::OfficeManagerStore;
+use crate::repository::employee_repository::EmployeeStore;
+use crate::repository::unit_of_work::{in_transaction, Transaction, UnitOfWork};
+use crate::service::seat_service::SeatService;
+use std::sync::Arc;
+use crate::utils::{CodedError, Validate};
+use crate::auth::{permission::Permission, principal::Principal, scope::OfficeScope};
+use crate::dto::export_dto::OfficeExportRow;
+use crate::dto::office_dto::SearchArea;
+use crate::dto::diagram_dto::{CompanyDiagram, EmployeeDiagramNode, OfficeDiagramNode};
+use crate::service::employee_service::EXPORT_BUFFER_ROWS;
+use futures::stream::{BoxStream, StreamExt};
+use tokio_stream::wrappers::ReceiverStream;
 
 /// Service for Office entities
 /// Handles business logic related to offices
+/// Offices live within the caller's tenant, names only need to be unique per tenant
+/// Changes that depend on the employees of an office run in one transaction with them
 #[derive(Clone)]
 pub struct OfficeService {
-    repo: OfficeRepository,
+    repo: Arc<dyn OfficeStore>,
+    employee_repo: Arc<dyn EmployeeStore>,
+    manager_repo: Arc<dyn OfficeManagerStore>,
+    seats: SeatService,
+    unit_of_work: Arc<dyn UnitOfWork>,
 }
 
 impl OfficeService {
-    /// Constructor for OfficeService
-    pub fn new(repo: OfficeRepository) -> Self {
-        Self { repo }
+    /// Constructor for OfficeService, works with any storage backend
+    pub fn new(
+        repo: impl OfficeStore + 'static,
+        employee_repo: impl EmployeeStore + 'static,
+        manager_repo: impl OfficeManagerStore + 'static,
+        seats: SeatService,
+        unit_of_work: impl UnitOfWork + 'static,
+    ) -> Self {
+        Self {
+            repo: Arc::new(repo),
+            employee_repo: Arc::new(employee_repo),
+            manager_repo: Arc::new(manager_repo),
+            seats,
+            unit_of_work: Arc::new(unit_of_work),
+        }
     }
 
     /// Adds a new office after validating and checking for duplicate names
-    pub async fn add_office(&self, office: &Office) -> anyhow::Result<Office> {
+    /// The office gets its site in the location tree in the same transaction
+    pub async fn add_office(&self, principal: &Principal, office: &Office) -> anyhow::Result<Office> {
         tracing::info!("Attempting to add office_id with name: {}", office.name);
 
-        office.validate().map_err(|e| anyhow::anyhow!(e))?;
-
-        if office.max_occupancy <= 0 {
-            return Err(anyhow!("Max occupancy must be greater than 0"));
-        }
+        office.validate()?;
         
-        if self.repo.get_office_by_name(&office.name).await?.is_some() {
-            return Err(anyhow::anyhow!("Office with name '{}' already exists", office.name));
+        if self.repo.get_office_by_name(principal.tenant_id, &office.name).await?.is_some() {
+            return Err(CodedError::new("office.name_exists").arg("name", &office.name).into());
         }
 
-        self.repo.create_office(office).await
+        let tenant_id = principal.tenant_id;
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let created = self.repo.create_office_tx(&mut tx, tenant_id, office).await?;
+            self.seats.add_site_tx(&mut tx, tenant_id, &created).await?;
+            Ok((tx, created))
+        })
+        .await
     }
 
     /// Finds an office by ID
-    pub async fn find_office_by_id(&self, id: i32) -> anyhow::Result<Option<Office>> {
+    pub async fn find_office_by_id(&self, principal: &Principal, id: i32) -> anyhow::Result<Option<Office>> {
         tracing::info!("Attempting to find office with id: {}", id);
-        self.repo.get_office_by_id(id).await
+        self.repo.get_office_by_id(principal.tenant_id, id).await
     }
 
     /// Lists all offices
-    pub async fn list_all_offices(&self) -> anyhow::Result<Vec<Office>> {
+    pub async fn list_all_offices(&self, principal: &Principal) -> anyhow::Result<Vec<Office>> {
         tracing::info!("Listing all offices");
-        self.repo.get_all_offices().await
+        self.repo.get_all_offices(principal.tenant_id).await
+    }
+
+    /// Lists the offices within the area, nearest first, with their distance in kilometres
+    /// Offices without coordinates are left out
+    pub async fn list_offices_near(&self, principal: &Principal, area: &SearchArea) -> anyhow::Result<Vec<(Office, f64)>> {
+        tracing::info!("Listing offices within {} km of {},{}", area.radius_km, area.latitude, area.longitude);
+        self.repo.get_offices_near(principal.tenant_id, area).await
     }
 
     /// Updates an existing office after validating and checking for duplicate names
-    pub async fn update_office(&self, id: i32, office: &Office) -> anyhow::Result<Office> {
+    /// A change of max_occupancy needs sign-off, it is refused with office.capacity_needs_approval and made through
+    /// a change request instead (see update_office_approved)
+    pub async fn update_office(&self, principal: &Principal, id: i32, office: &Office) -> anyhow::Result<Office> {
         tracing::info!("Attempting to update office with id: {}", id);
+        let tenant_id = principal.tenant_id;
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let updated = self.change_office_tx(&mut tx, tenant_id, &OfficeScope::All, id, office, false).await?;
+            Ok((tx, updated))
+        })
+        .await
+    }
 
-        office.validate().map_err(|e| anyhow::anyhow!(e))?;
+    /// Updates an office as an approved change request, max_occupancy included
+    /// The office must be within the scope the approvals cover
+    pub async fn update_office_approved(&self, tenant_id: i32, scope: &OfficeScope, id: i32, office: &Office) -> anyhow::Result<Office> {
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let updated = self.update_office_approved_tx(&mut tx, tenant_id, scope, id, office).await?;
+            Ok((tx, updated))
+        })
+        .await
+    }
 
-        if let Some(existing) = self.repo.get_office_by_name(&office.name).await? {
-            if existing.id != Some(id) {
-                return Err(anyhow::anyhow!("Name '{}' already taken", office.name));
-            }
+    /// update_office_approved inside the caller's transaction, the change commits together with the approval
+    pub async fn update_office_approved_tx(&self, tx: &mut Transaction, tenant_id: i32, scope: &OfficeScope, id: i32, office: &Office) -> anyhow::Result<Office> {
+        tracing::info!("Applying approved update of office with id: {}", id);
+        self.change_office_tx(tx, tenant_id, scope, id, office, true).await
+    }
+
+    // max_occupancy cannot drop below the number of seats taken today or later, future hires and pending transfers
+    // into the office included (see SeatService). The count, the update and handing raised capacity to the waitlist
+    // share the transaction
+    async fn change_office_tx(&self, tx: &mut Transaction, tenant_id: i32, scope: &OfficeScope, id: i32, office: &Office, approved: bool) -> anyhow::Result<Office> {
+        office.validate()?;
+        if !scope.allows(id) {
+            return Err(CodedError::new("office.not_found").arg("id", id).into());
         }
-        
-        self.repo.update_office_by_id(id, office).await
+
+        if let Some(existing) = self.repo.get_office_by_name_tx(tx, tenant_id, &office.name).await?
+            && existing.id != Some(id)
+        {
+            return Err(CodedError::new("office.name_taken").arg("name", &office.name).into());
+        }
+
+        let previous = self.repo.get_office_by_id_tx(tx, tenant_id, id).await?.map(|o| o.max_occupancy);
+        if let Some(max_occupancy) = previous
+            && max_occupancy != office.max_occupancy
+            && !approved
+        {
+            tracing::warn!("Change of max_occupancy of office {} from {} to {} without approval", id, max_occupancy, office.max_occupancy);
+            return Err(CodedError::new("office.capacity_needs_approval").arg("id", id).into());
+        }
+        let today = chrono::Utc::now().date_naive();
+        let employee_nr = self.seats.peak_tx(tx, tenant_id, id, today, None).await?;
+        if employee_nr > office.max_occupancy as i64 {
+            return Err(CodedError::new("office.occupancy_below_headcount").arg("count", employee_nr).arg("max", office.max_occupancy).into());
+        }
+        let updated = self.repo.update_office_by_id_tx(tx, tenant_id, id, office).await?;
+        // the new seats go to the waitlist before anyone else can take them
+        if previous.is_some_and(|max_occupancy| max_occupancy < office.max_occupancy) {
+            self.seats.fill_waitlist_tx(tx, tenant_id, id).await?;
+        }
+        Ok(updated)
     }
 
     /// Removes an office by ID
-    pub async fn remove_office(&self, id: i32) -> anyhow::Result<bool> {
+    pub async fn remove_office(&self, principal: &Principal, id: i32) -> anyhow::Result<bool> {
         tracing::info!("Deleting office id: {}", id);
-        let rows = self.repo.delete_office(id).await?;
+        let rows = self.repo.delete_office(principal.tenant_id, id).await?;
+        Ok(rows > 0)
+    }
+
+    /// Moves all employees of an office to another office of the tenant and removes it, in one transaction
+    /// Fails without changes when the target does not exist, is the same office or lacks room for them.
+    /// false if the office itself does not exist
+    pub async fn remove_office_reassigning(&self, principal: &Principal, id: i32, target_id: i32) -> anyhow::Result<bool> {
+        tracing::info!("Deleting office id: {}, moving its employees to office id: {}", id, target_id);
+        if id == target_id {
+            return Err(CodedError::new("office.reassign_to_self").into());
+        }
+
+        let tenant_id = principal.tenant_id;
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            if self.repo.get_office_by_id_tx(&mut tx, tenant_id, id).await?.is_none() {
+                return Ok((tx, false));
+            }
+            let target = self.repo.get_office_by_id_tx(&mut tx, tenant_id, target_id)
+                .await?
+                .ok_or_else(|| CodedError::new("office.not_found").arg("id", target_id))?;
+
+            // peaks of both offices from today on, added up as if they fell on the same day
+            let today = chrono::Utc::now().date_naive();
+            let moving = self.seats.peak_tx(&mut tx, tenant_id, id, today, None).await?;
+            let present = self.seats.peak_tx(&mut tx, tenant_id, target_id, today, None).await?;
+            if present + moving > target.max_occupancy as i64 {
+                return Err(CodedError::new("office.reassign_no_room")
+                    .arg("name", &target.name)
+                    .arg("free", (target.max_occupancy as i64 - present).max(0))
+                    .arg("moving", moving).into());
+            }
+
+            self.employee_repo.reassign_employees_tx(&mut tx, tenant_id, id, target_id).await?;
+            let rows = self.repo.delete_office_tx(&mut tx, tenant_id, id).await?;
+            Ok((tx, rows > 0))
+        })
+        .await
+    }
+
+    /// Lists the subjects managing an office
+    pub async fn list_office_managers(&self, principal: &Principal, office_id: i32) -> anyhow::Result<Vec<String>> {
+        tracing::info!("Listing managers of office id: {}", office_id);
+        if self.repo.get_office_by_id(principal.tenant_id, office_id).await?.is_none() {
+            return Err(CodedError::new("office.not_found").arg("id", office_id).into());
+        }
+        self.manager_repo.get_subjects_by_office_id(principal.tenant_id, office_id).await
+    }
+
+    /// Grants a subject office manager rights on an office
+    pub async fn assign_office_manager(&self, principal: &Principal, office_id: i32, subject: &str) -> anyhow::Result<()> {
+        tracing::info!("Assigning {} as manager of office id: {}", subject, office_id);
+        if subject.trim().is_empty() {
+            return Err(CodedError::new("manager.subject_empty").into());
+        }
+        if self.repo.get_office_by_id(principal.tenant_id, office_id).await?.is_none() {
+            return Err(CodedError::new("office.not_found").arg("id", office_id).into());
+        }
+        self.manager_repo.add_office_manager(principal.tenant_id, subject, office_id).await
+    }
+
+    /// Revokes a subject's office manager rights on an office
+    pub async fn remove_office_manager(&self, principal: &Principal, office_id: i32, subject: &str) -> anyhow::Result<bool> {
+        tracing::info!("Removing {} as manager of office id: {}", subject, office_id);
+        let rows = self.manager_repo.delete_office_manager(principal.tenant_id, subject, office_id).await?;
         Ok(rows > 0)
     }
+
+    /// Builds the company layout: every office with the employees holding a seat there today, ordered like get_all_offices
+    /// Only offices whose employees the caller may read are included. Asking for an unknown or
+    /// out-of-scope office in office_ids is reported as not existing
+    pub async fn office_diagram(&self, principal: &Principal, office_ids: Option<&[i32]>) -> anyhow::Result<CompanyDiagram> {
+        tracing::info!("Building office diagram for offices {:?}", office_ids);
+        let tenant_id = principal.tenant_id;
+        let scope = if principal.is_office_scoped(Permission::EmployeesRead) {
+            OfficeScope::Offices(self.manager_repo.get_office_ids_by_subject(tenant_id, &principal.subject).await?)
+        } else {
+            OfficeScope::All
+        };
+        let offices: Vec<Office> = self.repo.get_all_offices(tenant_id).await?
+            .into_iter()
+            .filter(|office| office.id.is_some_and(|id| scope.allows(id)))
+            .collect();
+        if let Some(office_ids) = office_ids
+            && let Some(missing) = office_ids.iter().find(|id| !offices.iter().any(|office| office.id == Some(**id)))
+        {
+            return Err(CodedError::new("office.not_found").arg("id", missing).into());
+        }
+
+        let today = chrono::Utc::now().date_naive();
+        let mut nodes = Vec::new();
+        for office in offices {
+            let id = office.id.unwrap_or_default();
+            if office_ids.is_some_and(|office_ids| !office_ids.contains(&id)) {
+                continue;
+            }
+            let employees: Vec<EmployeeDiagramNode> = self.employee_repo.get_employees_by_office_id(tenant_id, id).await?
+                .into_iter()
+                .filter(|e| e.hire_date <= today && e.termination_date.is_none_or(|end| end >= today))
+                .map(|e| EmployeeDiagramNode { id: e.id.unwrap_or_default(), first_name: e.first_name, last_name: e.last_name })
+                .collect();
+            let occupancy = employees.len() as i64;
+            nodes.push(OfficeDiagramNode {
+                id,
+                name: office.name,
+                occupancy,
+                max_occupancy: office.max_occupancy,
+                full: occupancy >= office.max_occupancy as i64,
+                employees,
+            });
+        }
+        Ok(CompanyDiagram { offices: nodes })
+    }
+
+    /// Streams all offices for export, see EmployeeService::export_employees
+    pub fn export_offices(&self, principal: &Principal) -> BoxStream<'static, anyhow::Result<OfficeExportRow>> {
+        tracing::info!("Exporting offices");
+        let repo = self.repo.clone();
+        let tenant_id = principal.tenant_id;
+        let (tx, rx) = tokio::sync::mpsc::channel(EXPORT_BUFFER_ROWS);
+        tokio::spawn(async move {
+            let mut rows = repo.stream_offices(tenant_id);
+            while let Some(row) = rows.next().await {
+                if tx.send(row).await.is_err() {
+                    tracing::info!("Office export cancelled by client");
+                    break;
+                }
+            }
+        });
+        ReceiverStream::new(rx).boxed()
+    }
 }
\ No newline at end of file
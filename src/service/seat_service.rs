@@ -0,0 +1,231 @@
+use crate::entity::employee::{peak_seats, Employee};
+use crate::entity::location::{Location, LocationTree};
+use crate::entity::office::Office;
+use crate::entity::transfer::Transfer;
+use crate::entity::validation_rules::{DuplicatePolicy, ValidationRules};
+use crate::entity::waitlist::{WaitlistEntry, WaitlistEvent, WaitlistEventKind, WaitlistStatus};
+use crate::repository::department_repository::DepartmentStore;
+use crate::repository::employee_repository::EmployeeStore;
+use crate::repository::location_repository::LocationStore;
+use crate::repository::office_repository::OfficeStore;
+use crate::repository::transfer_repository::TransferStore;
+use crate::repository::waitlist_repository::WaitlistStore;
+use crate::repository::unit_of_work::Transaction;
+use crate::service::waitlist_service::WaitlistRun;
+use crate::utils::{CodedError, ValidationErrors};
+use std::sync::Arc;
+use chrono::NaiveDate;
+
+/// Service for the seats of offices, shared by every service that puts employees in an office or frees a seat
+/// Has no operations of its own, the others call it inside their transactions
+///
+/// Occupancy is projected: the employees sitting in an office keep their seat until a pending transfer takes them
+/// elsewhere, and those of pending transfers into it take theirs from the effective date. Every capacity check counts
+/// the same way, so a seat planned for a transfer is not handed out again by a hire, a move or a lower max_occupancy
+///
+/// Regions of the location tree may limit the seats of the offices in them as well, so every employee arriving in an
+/// office needs a seat in the levels above its site. A new office gets its site here, in the transaction creating it
+///
+/// A freed seat goes to the office's waitlist in the same transaction that frees it, so waiting hires come first.
+/// They are placed in the office they waited for and nowhere else, with the checks of a direct hire
+#[derive(Clone)]
+pub struct SeatService {
+    employee_repo: Arc<dyn EmployeeStore>,
+    transfer_repo: Arc<dyn TransferStore>,
+    office_repo: Arc<dyn OfficeStore>,
+    department_repo: Arc<dyn DepartmentStore>,
+    waitlist_repo: Arc<dyn WaitlistStore>,
+    location_repo: Arc<dyn LocationStore>,
+    validation_rules: Arc<ValidationRules>,
+}
+
+impl SeatService {
+    /// Constructor for SeatService, works with any storage backend
+    pub fn new(
+        employee_repo: impl EmployeeStore + 'static,
+        transfer_repo: impl TransferStore + 'static,
+        office_repo: impl OfficeStore + 'static,
+        department_repo: impl DepartmentStore + 'static,
+        waitlist_repo: impl WaitlistStore + 'static,
+        location_repo: impl LocationStore + 'static,
+    ) -> Self {
+        Self {
+            employee_repo: Arc::new(employee_repo),
+            transfer_repo: Arc::new(transfer_repo),
+            office_repo: Arc::new(office_repo),
+            department_repo: Arc::new(department_repo),
+            waitlist_repo: Arc::new(waitlist_repo),
+            location_repo: Arc::new(location_repo),
+            validation_rules: Arc::new(ValidationRules::default()),
+        }
+    }
+
+    /// Replaces the default validation rules that waiting hires are checked against when placed,
+    /// the same ones EmployeeService checks direct hires against
+    pub fn with_validation_rules(mut self, validation_rules: ValidationRules) -> Self {
+        self.validation_rules = Arc::new(validation_rules);
+        self
+    }
+
+    /// Highest projected number of seats taken in an office on any day from `from` through `until`, open ended when None
+    pub async fn peak_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32, from: NaiveDate, until: Option<NaiveDate>) -> anyhow::Result<i64> {
+        let periods = self.seat_periods_tx(tx, tenant_id, office_id, None).await?;
+        Ok(peak_seats(&periods, from, until))
+    }
+
+    /// Seat periods of an office as the pending transfers leave it, the transfer `skip` is left out
+    pub async fn seat_periods_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32, skip: Option<i32>) -> anyhow::Result<Vec<(NaiveDate, Option<NaiveDate>)>> {
+        let pending: Vec<Transfer> = self.transfer_repo.get_pending_transfers_by_office_id_tx(tx, tenant_id, office_id)
+            .await?
+            .into_iter()
+            .filter(|t| skip.is_none() || t.id != skip)
+            .collect();
+
+        let mut periods = Vec::new();
+        for seated in self.employee_repo.get_employees_by_office_id_tx(tx, tenant_id, office_id).await? {
+            let leaving = pending.iter().find(|t| Some(t.employee_id) == seated.id && t.to_office_id != office_id);
+            let last_day = match leaving.and_then(|t| t.effective_date.pred_opt()) {
+                Some(day_before) => Some(seated.termination_date.map_or(day_before, |end| end.min(day_before))),
+                None => seated.termination_date,
+            };
+            periods.push((seated.hire_date, last_day));
+        }
+        for arriving in pending.iter().filter(|t| t.to_office_id == office_id) {
+            if let Some(other) = self.employee_repo.get_employee_by_id_tx(tx, tenant_id, arriving.employee_id).await? {
+                periods.push(arriving.seat_in_destination(&other));
+            }
+        }
+        Ok(periods)
+    }
+
+    /// Adds the site of a new office at the top of the location tree, its employees count in the tree from the start
+    pub async fn add_site_tx(&self, tx: &mut Transaction, tenant_id: i32, office: &Office) -> anyhow::Result<Location> {
+        self.location_repo.create_location_tx(tx, tenant_id, &Location::site_for(office)).await
+    }
+
+    /// The location tree of the tenant with occupancy as of today
+    pub async fn locations_tx(&self, tx: &mut Transaction, tenant_id: i32) -> anyhow::Result<LocationTree> {
+        let today = chrono::Utc::now().date_naive();
+        let locations = self.location_repo.get_locations_tx(tx, tenant_id).await?;
+        let occupants = self.location_repo.get_occupants_tx(tx, tenant_id, today).await?;
+        Ok(LocationTree::new(locations, &occupants))
+    }
+
+    /// Checks the levels above the site of an office for a seat for one more employee, coming from the office
+    /// `from_office_id` or new. location.full names the first level without one
+    pub async fn check_locations_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32, from_office_id: Option<i32>) -> anyhow::Result<()> {
+        let tree = self.locations_tx(tx, tenant_id).await?;
+        match tree.first_full_above_site(office_id, from_office_id, 1) {
+            Some((location, capacity)) => Err(location.full(capacity).into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Number of hires waiting for a seat in an office, direct hires are turned away while there are any
+    pub async fn waiting_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<usize> {
+        let entries = self.waitlist_repo.get_entries_by_office_id_tx(tx, tenant_id, office_id).await?;
+        Ok(entries.iter().filter(|e| e.status == WaitlistStatus::Waiting).count())
+    }
+
+    /// Hands the free seats of an office to its waiting entries, strictly in order of arrival
+    /// Seats offered but not accepted yet count as taken. An entry whose hire no longer passes validation fails
+    /// and the next one gets the seat; the first entry without a seat stops the run
+    pub async fn fill_waitlist_tx(&self, tx: &mut Transaction, tenant_id: i32, office_id: i32) -> anyhow::Result<WaitlistRun> {
+        let mut run = WaitlistRun::default();
+        let Some(office) = self.office_repo.get_office_by_id_tx(tx, tenant_id, office_id).await? else {
+            return Ok(run);
+        };
+        let entries = self.waitlist_repo.get_entries_by_office_id_tx(tx, tenant_id, office_id).await?;
+        let mut offered = entries.iter().filter(|e| e.status == WaitlistStatus::Offered).count() as i64;
+
+        for entry in entries.into_iter().filter(|e| e.status == WaitlistStatus::Waiting) {
+            let id = entry.id.unwrap_or_default();
+            if entry.confirm {
+                if !self.has_seat_tx(tx, tenant_id, &office, &entry.employee(), offered).await? {
+                    break;
+                }
+                let offer = WaitlistEntry { status: WaitlistStatus::Offered, ..entry };
+                self.settle_tx(tx, tenant_id, &offer, WaitlistEventKind::Offered, None).await?;
+                offered += 1;
+                run.offered += 1;
+                continue;
+            }
+
+            match self.place_tx(tx, tenant_id, &office, &entry, offered).await {
+                Ok(Some(_)) => {
+                    tracing::info!("Placed waitlist entry {} in office {}", id, office_id);
+                    run.placed += 1;
+                }
+                Ok(None) => break,
+                Err(e) if e.downcast_ref::<CodedError>().is_some() || e.downcast_ref::<ValidationErrors>().is_some() => {
+                    tracing::warn!("Waitlist entry {} cannot be placed in office {}: {}", id, office_id, e);
+                    let failed = WaitlistEntry { status: WaitlistStatus::Failed, ..entry };
+                    self.settle_tx(tx, tenant_id, &failed, WaitlistEventKind::Failed, Some(e.to_string())).await?;
+                    run.failed += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(run)
+    }
+
+    /// Creates the employee of a waiting or offered entry in the entry's office and marks the entry placed
+    /// None without changes when the office has no seat for it, counting `held` seats offered to other entries.
+    /// The hire is checked like a direct one (validation rules, department, manager, blocked duplicates) before anything
+    /// is written, so an error leaves the transaction usable
+    pub async fn place_tx(&self, tx: &mut Transaction, tenant_id: i32, office: &Office, entry: &WaitlistEntry, held: i64) -> anyhow::Result<Option<WaitlistEntry>> {
+        let employee = entry.employee();
+        if !self.has_seat_tx(tx, tenant_id, office, &employee, held).await? {
+            return Ok(None);
+        }
+
+        let rules = self.validation_rules.for_employee(tenant_id, Some(entry.office_id));
+        employee.validate_with(&rules, chrono::Utc::now().date_naive())?;
+        if let Some(department_id) = employee.department_id
+            && self.department_repo.get_department_by_id_tx(tx, tenant_id, department_id).await?.is_none()
+        {
+            return Err(CodedError::new("department.not_found").arg("id", department_id).into());
+        }
+        if let Some(manager_id) = employee.manager_id
+            && self.employee_repo.get_employee_by_id_tx(tx, tenant_id, manager_id).await?.is_none()
+        {
+            return Err(CodedError::new("employee.manager_not_found").arg("id", manager_id).into());
+        }
+        if rules.duplicates == DuplicatePolicy::Block {
+            let key = employee.duplicate_key();
+            let candidates = self.employee_repo.get_employees_by_birth_date_tx(tx, tenant_id, employee.birth_date).await?;
+            if candidates.iter().any(|candidate| candidate.duplicate_key() == key) {
+                return Err(CodedError::new("employee.duplicate")
+                    .arg("name", format!("{} {}", employee.first_name, employee.last_name))
+                    .arg("birth_date", employee.birth_date)
+                    .into());
+            }
+        }
+
+        let created = self.employee_repo.create_employee_tx(tx, tenant_id, &employee).await?;
+        let placed = WaitlistEntry { status: WaitlistStatus::Placed, employee_id: created.id, ..entry.clone() };
+        let details = Some(format!("employee {}", created.id.unwrap_or_default()));
+        self.settle_tx(tx, tenant_id, &placed, WaitlistEventKind::Placed, details).await?;
+        Ok(Some(placed))
+    }
+
+    // True if the office has a seat for the hire on every day of its seat period and the levels above its site have one
+    // as well, `held` seats counted as taken
+    async fn has_seat_tx(&self, tx: &mut Transaction, tenant_id: i32, office: &Office, employee: &Employee, held: i64) -> anyhow::Result<bool> {
+        let today = chrono::Utc::now().date_naive();
+        let seated = self.peak_tx(tx, tenant_id, employee.office_id, employee.seat_from(today), employee.termination_date).await?;
+        if seated + held >= office.max_occupancy as i64 {
+            return Ok(false);
+        }
+        let tree = self.locations_tx(tx, tenant_id).await?;
+        Ok(tree.first_full_above_site(employee.office_id, None, held + 1).is_none())
+    }
+
+    // Stores the new status of an entry together with the event that led to it
+    async fn settle_tx(&self, tx: &mut Transaction, tenant_id: i32, entry: &WaitlistEntry, event: WaitlistEventKind, details: Option<String>) -> anyhow::Result<()> {
+        let id = entry.id.unwrap_or_default();
+        self.waitlist_repo.update_entry_tx(tx, tenant_id, id, entry).await?;
+        self.waitlist_repo.create_event_tx(tx, tenant_id, &WaitlistEvent::new(id, event, details)).await?;
+        Ok(())
+    }
+}
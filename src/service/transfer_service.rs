@@ -0,0 +1,344 @@
+use crate::entity::employee::{peak_seats, Employee};
+use crate::entity::office::Office;
+use crate::entity::transfer::{Transfer, TransferStatus};
+use crate::entity::audit_entry::AuditEntry;
+use crate::repository::transfer_repository::TransferStore;
+use crate::repository::employee_repository::EmployeeStore;
+use crate::repository::office_repository::OfficeStore;
+use crate::repository::office_manager_repository::OfficeManagerStore;
+use crate::repository::audit_repository::AuditStore;
+use crate::repository::unit_of_work::{in_transaction, Transaction, UnitOfWork};
+use crate::service::seat_service::SeatService;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::NaiveDate;
+use crate::auth::{permission::Permission, principal::Principal, scope::OfficeScope};
+use crate::utils::{CodedError, ValidationErrors};
+
+/// Outcome of one run of the transfer job
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransferRun {
+    pub applied: usize,
+    pub failed: usize, // transfers that can no longer be applied, e.g. because the destination filled up
+}
+
+/// Service for Transfer entities
+/// Plans moves of employees to other offices and applies them on their effective date
+///
+/// A transfer is checked against the destination's projected occupancy (see SeatService): the employees sitting there,
+/// minus those leaving through a pending transfer and plus those arriving through one, each for their seat period.
+/// Transfers are visible within the caller's office scope when their source or destination office is
+#[derive(Clone)]
+pub struct TransferService {
+    repo: Arc<dyn TransferStore>,
+    employee_repo: Arc<dyn EmployeeStore>,
+    office_repo: Arc<dyn OfficeStore>,
+    manager_repo: Arc<dyn OfficeManagerStore>,
+    audit_repo: Arc<dyn AuditStore>,
+    seats: SeatService,
+    unit_of_work: Arc<dyn UnitOfWork>,
+}
+
+impl TransferService {
+    /// Constructor for TransferService, works with any storage backend
+    pub fn new(
+        repo: impl TransferStore + 'static,
+        employee_repo: impl EmployeeStore + 'static,
+        office_repo: impl OfficeStore + 'static,
+        manager_repo: impl OfficeManagerStore + 'static,
+        audit_repo: impl AuditStore + 'static,
+        seats: SeatService,
+        unit_of_work: impl UnitOfWork + 'static,
+    ) -> Self {
+        Self {
+            repo: Arc::new(repo),
+            employee_repo: Arc::new(employee_repo),
+            office_repo: Arc::new(office_repo),
+            manager_repo: Arc::new(manager_repo),
+            audit_repo: Arc::new(audit_repo),
+            seats,
+            unit_of_work: Arc::new(unit_of_work),
+        }
+    }
+
+    // Offices the principal may act on with the permission, see EmployeeService::office_scope
+    async fn office_scope(&self, principal: &Principal, permission: Permission) -> anyhow::Result<OfficeScope> {
+        if !principal.is_office_scoped(permission) {
+            return Ok(OfficeScope::All);
+        }
+        let office_ids = self.manager_repo.get_office_ids_by_subject(principal.tenant_id, &principal.subject).await?;
+        Ok(OfficeScope::Offices(office_ids))
+    }
+
+    /// Plans a transfer of an employee to another office from the effective date on, as an approved change request
+    /// The employee and the destination must be within the scope the approvals cover, the effective date today or
+    /// later and not after the employee's termination date. The destination must have a seat for the employee on every
+    /// day from then, counting the other pending transfers, and the levels above its site that do not hold the employee
+    /// yet a seat today. An employee has one pending transfer at a time
+    pub async fn schedule_transfer_approved(&self, tenant_id: i32, requested_by: &str, scope: &OfficeScope, employee_id: i32, to_office_id: i32, effective_date: NaiveDate) -> anyhow::Result<Transfer> {
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let created = self.schedule_transfer_approved_tx(&mut tx, tenant_id, requested_by, scope, employee_id, to_office_id, effective_date).await?;
+            Ok((tx, created))
+        })
+        .await
+    }
+
+    /// schedule_transfer_approved inside the caller's transaction, the transfer commits together with the approval
+    #[allow(clippy::too_many_arguments)]
+    pub async fn schedule_transfer_approved_tx(&self, tx: &mut Transaction, tenant_id: i32, requested_by: &str, scope: &OfficeScope, employee_id: i32, to_office_id: i32, effective_date: NaiveDate) -> anyhow::Result<Transfer> {
+        tracing::info!("Applying approved transfer of employee {} to office {} on {}", employee_id, to_office_id, effective_date);
+        let employee = self.employee_repo.get_employee_by_id_tx(tx, tenant_id, employee_id)
+            .await?
+            .filter(|e| scope.allows(e.office_id))
+            .ok_or_else(|| CodedError::new("employee.not_found").arg("id", employee_id))?;
+        let office = self.destination_tx(tx, tenant_id, scope, to_office_id).await?;
+
+        let transfer = Transfer::new(&employee, to_office_id, effective_date, requested_by);
+        transfer.check(&employee, chrono::Utc::now().date_naive())?;
+        let leaving = self.repo.get_pending_transfers_by_office_id_tx(tx, tenant_id, employee.office_id).await?;
+        if let Some(pending) = leaving.iter().find(|t| t.employee_id == employee_id) {
+            return Err(CodedError::new("transfer.pending_exists").arg("id", employee_id).arg("transfer_id", pending.id.unwrap_or_default()).into());
+        }
+        self.check_capacity_tx(tx, tenant_id, &office, &transfer, &employee).await?;
+        self.seats.check_locations_tx(tx, tenant_id, to_office_id, Some(employee.office_id)).await?;
+
+        self.repo.create_transfer_tx(tx, tenant_id, &transfer).await
+    }
+
+    /// Finds a transfer by ID, None if it does not exist or is out of scope
+    pub async fn find_transfer_by_id(&self, principal: &Principal, id: i32) -> anyhow::Result<Option<Transfer>> {
+        tracing::info!("Attempting to find transfer with id: {}", id);
+        let scope = self.office_scope(principal, Permission::EmployeesRead).await?;
+        let transfer = self.repo.get_transfer_by_id(principal.tenant_id, id).await?;
+        Ok(transfer.filter(|t| visible(&scope, t)))
+    }
+
+    /// Lists the transfers into and out of an office, optionally only those with a status, by effective date
+    /// An unknown or out-of-scope office is reported as not existing
+    pub async fn list_office_transfers(&self, principal: &Principal, office_id: i32, status: Option<TransferStatus>) -> anyhow::Result<Vec<Transfer>> {
+        tracing::info!("Listing transfers of office id: {}", office_id);
+        let scope = self.office_scope(principal, Permission::EmployeesRead).await?;
+        let office = self.office_repo.get_office_by_id(principal.tenant_id, office_id).await?;
+        if office.is_none() || !scope.allows(office_id) {
+            return Err(CodedError::new("office.not_found").arg("id", office_id).into());
+        }
+        self.repo.get_transfers_by_office_id(principal.tenant_id, office_id, status).await
+    }
+
+    /// Cancels a pending transfer, transfers that were applied, cancelled or failed already stay as they are
+    /// The employee then keeps the seat in the current office, which must still be free from the effective date on.
+    /// The seat planned in the destination goes to its waitlist in the same transaction
+    pub async fn cancel_transfer(&self, principal: &Principal, id: i32) -> anyhow::Result<Transfer> {
+        tracing::info!("Attempting to cancel transfer with id: {}", id);
+        let scope = self.office_scope(principal, Permission::EmployeesUpdate).await?;
+        let scope = &scope;
+        let tenant_id = principal.tenant_id;
+
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let transfer = self.pending_transfer_tx(&mut tx, tenant_id, scope, id).await?;
+            let employee = self.employee_repo.get_employee_by_id_tx(&mut tx, tenant_id, transfer.employee_id)
+                .await?
+                .ok_or_else(|| CodedError::new("employee.not_found").arg("id", transfer.employee_id))?;
+            self.check_source_tx(&mut tx, tenant_id, &transfer, &employee, None).await?;
+            let cancelled = Transfer { status: TransferStatus::Cancelled, ..transfer };
+            let updated = self.repo.update_transfer_tx(&mut tx, tenant_id, id, &cancelled).await?;
+            self.seats.fill_waitlist_tx(&mut tx, tenant_id, updated.to_office_id).await?;
+            Ok((tx, updated))
+        })
+        .await
+    }
+
+    /// Moves a pending transfer to another effective date, checked like a new transfer
+    /// A later date keeps the employee in the current office longer, which must have the seat until then
+    pub async fn reschedule_transfer(&self, principal: &Principal, id: i32, effective_date: NaiveDate) -> anyhow::Result<Transfer> {
+        tracing::info!("Attempting to reschedule transfer {} to {}", id, effective_date);
+        let scope = self.office_scope(principal, Permission::EmployeesUpdate).await?;
+        let scope = &scope;
+        let tenant_id = principal.tenant_id;
+
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let transfer = self.pending_transfer_tx(&mut tx, tenant_id, scope, id).await?;
+            let employee = self.employee_repo.get_employee_by_id_tx(&mut tx, tenant_id, transfer.employee_id)
+                .await?
+                .ok_or_else(|| CodedError::new("employee.not_found").arg("id", transfer.employee_id))?;
+            let office = self.destination_tx(&mut tx, tenant_id, scope, transfer.to_office_id).await?;
+
+            let rescheduled = Transfer { effective_date, ..transfer.clone() };
+            rescheduled.check(&employee, chrono::Utc::now().date_naive())?;
+            self.check_capacity_tx(&mut tx, tenant_id, &office, &rescheduled, &employee).await?;
+            if effective_date > transfer.effective_date {
+                self.check_source_tx(&mut tx, tenant_id, &transfer, &employee, effective_date.pred_opt()).await?;
+            }
+
+            let updated = self.repo.update_transfer_tx(&mut tx, tenant_id, id, &rescheduled).await?;
+            Ok((tx, updated))
+        })
+        .await
+    }
+
+    /// Applies the pending transfers of all tenants that take effect on or before the day, one transaction each
+    /// A transfer that can no longer be applied, e.g. because the destination filled up or the employee left before,
+    /// is marked failed. Other errors leave it pending for the next run
+    pub async fn apply_due_transfers(&self, today: NaiveDate) -> anyhow::Result<TransferRun> {
+        let due = self.repo.get_due_transfers(today).await?;
+        let mut run = TransferRun::default();
+        for (tenant_id, transfer) in due {
+            let id = transfer.id.unwrap_or_default();
+            match self.apply_transfer(tenant_id, id, today).await {
+                Ok(true) => run.applied += 1,
+                Ok(false) => {}
+                Err(e) if e.downcast_ref::<CodedError>().is_some() || e.downcast_ref::<ValidationErrors>().is_some() => {
+                    tracing::warn!("Transfer {} of employee {} cannot be applied: {}", id, transfer.employee_id, e);
+                    self.fail_transfer(tenant_id, &transfer, &e.to_string()).await?;
+                    run.failed += 1;
+                }
+                Err(e) => tracing::error!("Failed to apply transfer {}, retrying on the next run: {}", id, e),
+            }
+        }
+        Ok(run)
+    }
+
+    // Moves the employee of a due transfer, false if the transfer is no longer pending
+    // The seat left behind goes to the waitlist of the old office in the same transaction
+    async fn apply_transfer(&self, tenant_id: i32, id: i32, today: NaiveDate) -> anyhow::Result<bool> {
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let Some(transfer) = self.repo.get_transfer_by_id_tx(&mut tx, tenant_id, id).await?.filter(|t| t.status == TransferStatus::Pending) else {
+                return Ok((tx, false));
+            };
+            let employee = self.employee_repo.get_employee_by_id_tx(&mut tx, tenant_id, transfer.employee_id)
+                .await?
+                .ok_or_else(|| CodedError::new("employee.not_found").arg("id", transfer.employee_id))?;
+            let office = self.office_repo.get_office_by_id_tx(&mut tx, tenant_id, transfer.to_office_id)
+                .await?
+                .ok_or_else(|| CodedError::new("office.not_found").arg("id", transfer.to_office_id))?;
+            // counted as of the effective date, a late run does not make the transfer past due
+            transfer.check(&employee, transfer.effective_date)?;
+            self.seats.check_locations_tx(&mut tx, tenant_id, transfer.to_office_id, Some(employee.office_id)).await?;
+
+            let moved = Employee { office_id: transfer.to_office_id, ..employee.clone() };
+            self.employee_repo.update_employee_by_id_tx(&mut tx, tenant_id, transfer.employee_id, &moved).await?;
+            // the employee sits there now, the transfer is not counted a second time
+            let periods = self.seats.seat_periods_tx(&mut tx, tenant_id, transfer.to_office_id, transfer.id).await?;
+            if peak_seats(&periods, moved.seat_from(today), moved.termination_date) > office.max_occupancy as i64 {
+                return Err(CodedError::new("office.full").arg("name", &office.name).arg("max", office.max_occupancy).into());
+            }
+
+            let applied = Transfer { status: TransferStatus::Applied, ..transfer };
+            self.repo.update_transfer_tx(&mut tx, tenant_id, id, &applied).await?;
+            let details = serde_json::json!({ "transfer_id": id, "from": employee.office_id, "to": applied.to_office_id, "effective_date": applied.effective_date });
+            self.record_tx(&mut tx, tenant_id, &applied, "employee.transferred", details).await?;
+            tracing::info!("Transferred employee {} from office {} to {}", applied.employee_id, employee.office_id, applied.to_office_id);
+            self.seats.fill_waitlist_tx(&mut tx, tenant_id, employee.office_id).await?;
+            Ok((tx, true))
+        })
+        .await
+    }
+
+    // Marks a due transfer failed with the reason in the audit log
+    async fn fail_transfer(&self, tenant_id: i32, transfer: &Transfer, reason: &str) -> anyhow::Result<()> {
+        let id = transfer.id.unwrap_or_default();
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let failed = Transfer { status: TransferStatus::Failed, ..transfer.clone() };
+            self.repo.update_transfer_tx(&mut tx, tenant_id, id, &failed).await?;
+            let details = serde_json::json!({ "transfer_id": id, "to": transfer.to_office_id, "reason": reason });
+            self.record_tx(&mut tx, tenant_id, transfer, "employee.transfer_failed", details).await?;
+            Ok((tx, ()))
+        })
+        .await
+    }
+
+    // Audit entry about the transfer's employee, recorded in the name of who planned it
+    async fn record_tx(&self, tx: &mut Transaction, tenant_id: i32, transfer: &Transfer, action: &str, details: serde_json::Value) -> anyhow::Result<()> {
+        let entry = AuditEntry {
+            id: None,
+            subject: transfer.requested_by.clone(),
+            action: action.to_string(),
+            employee_id: Some(transfer.employee_id),
+            details: details.to_string(),
+            created_at: chrono::Utc::now(),
+        };
+        self.audit_repo.record_audit_entry_tx(tx, tenant_id, &entry).await?;
+        Ok(())
+    }
+
+    // A pending transfer within scope, transfer.not_found or transfer.not_pending
+    async fn pending_transfer_tx(&self, tx: &mut Transaction, tenant_id: i32, scope: &OfficeScope, id: i32) -> anyhow::Result<Transfer> {
+        let transfer = self.repo.get_transfer_by_id_tx(tx, tenant_id, id)
+            .await?
+            .filter(|t| visible(scope, t))
+            .ok_or_else(|| CodedError::new("transfer.not_found").arg("id", id))?;
+        if transfer.status != TransferStatus::Pending {
+            return Err(CodedError::new("transfer.not_pending").arg("id", id).arg("status", transfer.status).into());
+        }
+        Ok(transfer)
+    }
+
+    // The destination office within scope, or office.not_found
+    async fn destination_tx(&self, tx: &mut Transaction, tenant_id: i32, scope: &OfficeScope, office_id: i32) -> anyhow::Result<Office> {
+        if !scope.allows(office_id) {
+            return Err(CodedError::new("office.not_found").arg("id", office_id).into());
+        }
+        self.office_repo.get_office_by_id_tx(tx, tenant_id, office_id)
+            .await?
+            .ok_or_else(|| CodedError::new("office.not_found").arg("id", office_id).into())
+    }
+
+    // The destination must seat the employee on every day of the seat period the transfer gives
+    async fn check_capacity_tx(&self, tx: &mut Transaction, tenant_id: i32, office: &Office, transfer: &Transfer, employee: &Employee) -> anyhow::Result<()> {
+        let mut periods = self.seats.seat_periods_tx(tx, tenant_id, transfer.to_office_id, transfer.id).await?;
+        let (from, until) = transfer.seat_in_destination(employee);
+        periods.push((from, until));
+
+        if peak_seats(&periods, from, until) > office.max_occupancy as i64 {
+            return Err(CodedError::new("office.full").arg("name", &office.name).arg("max", office.max_occupancy).into());
+        }
+        Ok(())
+    }
+
+    // The current office must seat the employee from the effective date of a transfer through the new last day there,
+    // the termination date when the transfer is cancelled. Others may have been planned into the seat being kept
+    async fn check_source_tx(&self, tx: &mut Transaction, tenant_id: i32, transfer: &Transfer, employee: &Employee, stays_until: Option<NaiveDate>) -> anyhow::Result<()> {
+        let from = transfer.effective_date.max(employee.hire_date);
+        let until = match (stays_until, employee.termination_date) {
+            (Some(stays_until), Some(end)) => Some(stays_until.min(end)),
+            (stays_until, end) => stays_until.or(end),
+        };
+        if until.is_some_and(|until| until < from) {
+            return Ok(());
+        }
+        let office = self.office_repo.get_office_by_id_tx(tx, tenant_id, employee.office_id)
+            .await?
+            .ok_or_else(|| CodedError::new("office.not_found").arg("id", employee.office_id))?;
+        // without its transfer the employee keeps the seat throughout
+        let periods = self.seats.seat_periods_tx(tx, tenant_id, employee.office_id, transfer.id).await?;
+
+        if peak_seats(&periods, from, until) > office.max_occupancy as i64 {
+            return Err(CodedError::new("office.full").arg("name", &office.name).arg("max", office.max_occupancy).into());
+        }
+        Ok(())
+    }
+}
+
+// Transfers are visible when their source or destination office is within scope
+fn visible(scope: &OfficeScope, transfer: &Transfer) -> bool {
+    scope.allows(transfer.from_office_id) || scope.allows(transfer.to_office_id)
+}
+
+/// Runs apply_due_transfers for today every interval until the process ends, the first run right away
+pub fn spawn_transfer_job(service: Arc<TransferService>, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let today = chrono::Utc::now().date_naive();
+            match service.apply_due_transfers(today).await {
+                Ok(run) if run != TransferRun::default() => {
+                    tracing::info!("Transfer job applied {} and failed {} transfers due by {}", run.applied, run.failed, today)
+                }
+                Ok(_) => tracing::debug!("Transfer job found no transfers due by {}", today),
+                Err(e) => tracing::error!("Transfer job failed: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,283 @@
+use crate::dto::waitlist_dto::WaitlistRequest;
+use crate::entity::office::Office;
+use crate::entity::waitlist::{WaitlistEntry, WaitlistEvent, WaitlistEventKind, WaitlistStatus};
+use crate::repository::waitlist_repository::WaitlistStore;
+use crate::repository::office_repository::OfficeStore;
+use crate::repository::unit_of_work::{in_transaction, Transaction, UnitOfWork};
+use crate::service::employee_service::EmployeeService;
+use crate::service::seat_service::SeatService;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::auth::{permission::Permission, principal::Principal};
+use crate::utils::CodedError;
+
+/// Outcome of one run of the waitlist job
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WaitlistRun {
+    pub placed: usize,
+    pub offered: usize,
+    pub failed: usize, // entries that no longer pass validation, e.g. because their manager left
+}
+
+/// Service for waitlists of full offices
+/// Keeps hires that add_employee turned away for lack of a seat and hands out seats in order of arrival
+///
+/// Deleting an employee, applying or cancelling a transfer and raising max_occupancy hand the freed seat to the
+/// waitlist in the same transaction (see SeatService): as long as a seat is free, counting the seats offered but not
+/// accepted yet, the first waiting entry is placed in its office, or offered when it asked for confirmation.
+/// Direct hires into an office with waiting entries are turned away. The waitlist job catches seats freed otherwise,
+/// e.g. by a termination date passing. An offer does not hold the seat against a hire made before it was offered:
+/// accepting it after the seat was taken puts the entry back in line
+#[derive(Clone)]
+pub struct WaitlistService {
+    repo: Arc<dyn WaitlistStore>,
+    office_repo: Arc<dyn OfficeStore>,
+    seats: SeatService,
+    unit_of_work: Arc<dyn UnitOfWork>,
+    employee_service: Arc<EmployeeService>,
+}
+
+impl WaitlistService {
+    /// Constructor for WaitlistService, works with any storage backend
+    pub fn new(
+        repo: impl WaitlistStore + 'static,
+        office_repo: impl OfficeStore + 'static,
+        seats: SeatService,
+        unit_of_work: impl UnitOfWork + 'static,
+        employee_service: Arc<EmployeeService>,
+    ) -> Self {
+        Self {
+            repo: Arc::new(repo),
+            office_repo: Arc::new(office_repo),
+            seats,
+            unit_of_work: Arc::new(unit_of_work),
+            employee_service,
+        }
+    }
+
+    /// Puts a hire on the waitlist of a full office
+    /// The office must be within scope and the hire pass the validation rules of the office now, as a direct hire would.
+    /// An office with a free seat and nobody waiting takes the hire directly instead, reported as waitlist.office_has_room
+    pub async fn enqueue(&self, principal: &Principal, office_id: i32, req: WaitlistRequest) -> anyhow::Result<(WaitlistEntry, Option<usize>)> {
+        tracing::info!("Attempting to put {} {} on the waitlist of office {}", req.first_name, req.last_name, office_id);
+        let office = self.office_in_scope(principal, Permission::EmployeesCreate, office_id).await?;
+
+        let entry = WaitlistEntry::from_request(office_id, req, &principal.subject);
+        let employee = entry.employee();
+        let rules = self.employee_service.validation_rules(principal, Some(office_id)).await?;
+        employee.validate_with(&rules, chrono::Utc::now().date_naive())?;
+
+        let tenant_id = principal.tenant_id;
+        let (entry, employee, office) = (&entry, &employee, &office);
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let queued = self.repo.get_entries_by_office_id_tx(&mut tx, tenant_id, office_id).await?;
+            let ahead = queued.iter().filter(|e| e.status == WaitlistStatus::Waiting).count();
+            let offered = queued.iter().filter(|e| e.status == WaitlistStatus::Offered).count();
+            let today = chrono::Utc::now().date_naive();
+            let seated = self.seats.peak_tx(&mut tx, tenant_id, office_id, employee.seat_from(today), None).await?;
+            if ahead == 0 && seated + (offered as i64) < office.max_occupancy as i64 {
+                return Err(CodedError::new("waitlist.office_has_room").arg("name", &office.name).into());
+            }
+
+            let created = self.repo.create_entry_tx(&mut tx, tenant_id, entry).await?;
+            let id = created.id.unwrap_or_default();
+            self.event_tx(&mut tx, tenant_id, id, WaitlistEventKind::Enqueued, Some(principal.subject.clone())).await?;
+            Ok((tx, (created, Some(ahead + 1))))
+        })
+        .await
+    }
+
+    /// Lists the waitlist of an office in order of arrival with the queue position of each waiting entry,
+    /// optionally only entries with a status. An unknown or out-of-scope office is reported as not existing
+    pub async fn list_office_waitlist(&self, principal: &Principal, office_id: i32, status: Option<WaitlistStatus>) -> anyhow::Result<Vec<(WaitlistEntry, Option<usize>)>> {
+        tracing::info!("Listing waitlist of office id: {}", office_id);
+        self.office_in_scope(principal, Permission::EmployeesRead, office_id).await?;
+        // positions count every waiting entry, whatever the filter
+        let entries = self.repo.get_entries_by_office_id(principal.tenant_id, office_id, None).await?;
+        let positions = crate::entity::waitlist::queue_positions(&entries);
+        Ok(entries
+            .into_iter()
+            .zip(positions)
+            .filter(|(entry, _)| status.is_none_or(|s| entry.status == s))
+            .collect())
+    }
+
+    /// Finds a waitlist entry by ID with its queue position, None if it does not exist or its office is out of scope
+    pub async fn find_entry_by_id(&self, principal: &Principal, id: i32) -> anyhow::Result<Option<(WaitlistEntry, Option<usize>)>> {
+        tracing::info!("Attempting to find waitlist entry with id: {}", id);
+        let scope = self.employee_service.office_scope(principal, Permission::EmployeesRead).await?;
+        let Some(entry) = self.repo.get_entry_by_id(principal.tenant_id, id).await?.filter(|e| scope.allows(e.office_id)) else {
+            return Ok(None);
+        };
+        let entries = self.repo.get_entries_by_office_id(principal.tenant_id, entry.office_id, Some(WaitlistStatus::Waiting)).await?;
+        let position = entries.iter().position(|e| e.id == entry.id).map(|index| index + 1);
+        Ok(Some((entry, position)))
+    }
+
+    /// Lists what happened to a waitlist entry, oldest first
+    pub async fn list_events(&self, principal: &Principal, id: i32) -> anyhow::Result<Vec<WaitlistEvent>> {
+        tracing::info!("Listing events of waitlist entry id: {}", id);
+        let scope = self.employee_service.office_scope(principal, Permission::EmployeesRead).await?;
+        if self.repo.get_entry_by_id(principal.tenant_id, id).await?.filter(|e| scope.allows(e.office_id)).is_none() {
+            return Err(CodedError::new("waitlist.not_found").arg("id", id).into());
+        }
+        self.repo.get_events(principal.tenant_id, id).await
+    }
+
+    /// Accepts the seat offered to an entry: the employee is created in the entry's office and the entry placed
+    /// When the seat was taken in the meantime the offer lapses, the entry waits again in its old place and office.full is returned
+    pub async fn accept(&self, principal: &Principal, id: i32) -> anyhow::Result<WaitlistEntry> {
+        tracing::info!("Attempting to accept the offer of waitlist entry {}", id);
+        self.entry_with_status(principal, id, WaitlistStatus::Offered).await?;
+        let tenant_id = principal.tenant_id;
+
+        let outcome = in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let entry = self.repo.get_entry_by_id_tx(&mut tx, tenant_id, id)
+                .await?
+                .ok_or_else(|| CodedError::new("waitlist.not_found").arg("id", id))?;
+            if entry.status != WaitlistStatus::Offered {
+                return Err(CodedError::new("waitlist.not_offered").arg("id", id).arg("status", entry.status).into());
+            }
+            let office = self.office_repo.get_office_by_id_tx(&mut tx, tenant_id, entry.office_id)
+                .await?
+                .ok_or_else(|| CodedError::new("office.not_found").arg("id", entry.office_id))?;
+            let queued = self.repo.get_entries_by_office_id_tx(&mut tx, tenant_id, entry.office_id).await?;
+            let held = queued.iter().filter(|e| e.status == WaitlistStatus::Offered && e.id != entry.id).count() as i64;
+
+            if let Some(placed) = self.seats.place_tx(&mut tx, tenant_id, &office, &entry, held).await? {
+                return Ok((tx, Ok(placed)));
+            }
+            let full = CodedError::new("office.full").arg("name", &office.name).arg("max", office.max_occupancy);
+            let waiting = WaitlistEntry { status: WaitlistStatus::Waiting, ..entry };
+            self.repo.update_entry_tx(&mut tx, tenant_id, id, &waiting).await?;
+            self.event_tx(&mut tx, tenant_id, id, WaitlistEventKind::OfferLapsed, Some(full.to_string())).await?;
+            Ok((tx, Err(full)))
+        })
+        .await?;
+        Ok(outcome?)
+    }
+
+    /// Declines the seat offered to an entry, which leaves the waitlist. The seat goes to the next entry right away
+    pub async fn decline(&self, principal: &Principal, id: i32) -> anyhow::Result<WaitlistEntry> {
+        tracing::info!("Attempting to decline the offer of waitlist entry {}", id);
+        let entry = self.entry_with_status(principal, id, WaitlistStatus::Offered).await?;
+        let office_id = entry.office_id;
+        let declined = WaitlistEntry { status: WaitlistStatus::Declined, ..entry };
+        let updated = self.transition(principal.tenant_id, id, WaitlistStatus::Offered, &declined, WaitlistEventKind::Declined, Some(principal.subject.clone())).await?;
+        self.fill_office(principal.tenant_id, office_id).await?;
+        Ok(updated)
+    }
+
+    /// Takes a waiting or offered entry off the waitlist, a seat it was offered goes to the next entry right away
+    pub async fn cancel(&self, principal: &Principal, id: i32) -> anyhow::Result<WaitlistEntry> {
+        tracing::info!("Attempting to cancel waitlist entry {}", id);
+        let entry = self.entry_in_scope(principal, Permission::EmployeesCreate, id).await?;
+        if !matches!(entry.status, WaitlistStatus::Waiting | WaitlistStatus::Offered) {
+            return Err(CodedError::new("waitlist.not_open").arg("id", id).arg("status", entry.status).into());
+        }
+        let (office_id, from) = (entry.office_id, entry.status);
+        let cancelled = WaitlistEntry { status: WaitlistStatus::Cancelled, ..entry };
+        let updated = self.transition(principal.tenant_id, id, from, &cancelled, WaitlistEventKind::Cancelled, Some(principal.subject.clone())).await?;
+        if from == WaitlistStatus::Offered {
+            self.fill_office(principal.tenant_id, office_id).await?;
+        }
+        Ok(updated)
+    }
+
+    /// Hands the free seats of every office with waiting entries to its waitlist, see fill_office
+    /// An office that cannot be filled now, e.g. because of a storage error, is retried on the next run
+    pub async fn fill_waitlists(&self) -> anyhow::Result<WaitlistRun> {
+        let offices = self.repo.get_waiting_office_ids().await?;
+        let mut run = WaitlistRun::default();
+        for (tenant_id, office_id) in offices {
+            match self.fill_office(tenant_id, office_id).await {
+                Ok(office_run) => {
+                    run.placed += office_run.placed;
+                    run.offered += office_run.offered;
+                    run.failed += office_run.failed;
+                }
+                Err(e) => tracing::error!("Failed to fill the waitlist of office {}, retrying on the next run: {}", office_id, e),
+            }
+        }
+        Ok(run)
+    }
+
+    /// Hands the free seats of an office to its waiting entries in one transaction, see SeatService::fill_waitlist_tx
+    pub async fn fill_office(&self, tenant_id: i32, office_id: i32) -> anyhow::Result<WaitlistRun> {
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let run = self.seats.fill_waitlist_tx(&mut tx, tenant_id, office_id).await?;
+            Ok((tx, run))
+        })
+        .await
+    }
+
+    // The office within the caller's scope for the permission, or office.not_found
+    async fn office_in_scope(&self, principal: &Principal, permission: Permission, office_id: i32) -> anyhow::Result<Office> {
+        let scope = self.employee_service.office_scope(principal, permission).await?;
+        if !scope.allows(office_id) {
+            return Err(CodedError::new("office.not_found").arg("id", office_id).into());
+        }
+        self.office_repo.get_office_by_id(principal.tenant_id, office_id)
+            .await?
+            .ok_or_else(|| CodedError::new("office.not_found").arg("id", office_id).into())
+    }
+
+    // An entry whose office is within the caller's scope for the permission, or waitlist.not_found
+    async fn entry_in_scope(&self, principal: &Principal, permission: Permission, id: i32) -> anyhow::Result<WaitlistEntry> {
+        let scope = self.employee_service.office_scope(principal, permission).await?;
+        self.repo.get_entry_by_id(principal.tenant_id, id)
+            .await?
+            .filter(|e| scope.allows(e.office_id))
+            .ok_or_else(|| CodedError::new("waitlist.not_found").arg("id", id).into())
+    }
+
+    // An entry within scope for creating employees that has the status, waitlist.not_found or waitlist.not_offered
+    async fn entry_with_status(&self, principal: &Principal, id: i32, status: WaitlistStatus) -> anyhow::Result<WaitlistEntry> {
+        let entry = self.entry_in_scope(principal, Permission::EmployeesCreate, id).await?;
+        if entry.status != status {
+            return Err(CodedError::new("waitlist.not_offered").arg("id", id).arg("status", entry.status).into());
+        }
+        Ok(entry)
+    }
+
+    // Stores the new status of an entry together with the event that led to it. The entry must still have the status
+    // it was read with, otherwise a concurrent decision came first and waitlist.not_open is returned
+    async fn transition(&self, tenant_id: i32, id: i32, from: WaitlistStatus, entry: &WaitlistEntry, event: WaitlistEventKind, details: Option<String>) -> anyhow::Result<WaitlistEntry> {
+        let details = &details;
+        in_transaction(self.unit_of_work.as_ref(), |mut tx| async move {
+            let current = self.repo.get_entry_by_id_tx(&mut tx, tenant_id, id)
+                .await?
+                .ok_or_else(|| CodedError::new("waitlist.not_found").arg("id", id))?;
+            if current.status != from {
+                return Err(CodedError::new("waitlist.not_open").arg("id", id).arg("status", current.status).into());
+            }
+            let updated = self.repo.update_entry_tx(&mut tx, tenant_id, id, entry).await?;
+            self.event_tx(&mut tx, tenant_id, id, event, details.clone()).await?;
+            Ok((tx, updated))
+        })
+        .await
+    }
+
+    async fn event_tx(&self, tx: &mut Transaction, tenant_id: i32, entry_id: i32, event: WaitlistEventKind, details: Option<String>) -> anyhow::Result<()> {
+        self.repo.create_event_tx(tx, tenant_id, &WaitlistEvent::new(entry_id, event, details)).await?;
+        Ok(())
+    }
+}
+
+/// Runs fill_waitlists every interval until the process ends, the first run right away
+pub fn spawn_waitlist_job(service: Arc<WaitlistService>, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match service.fill_waitlists().await {
+                Ok(run) if run != WaitlistRun::default() => {
+                    tracing::info!("Waitlist job placed {}, offered {} and failed {} entries", run.placed, run.offered, run.failed)
+                }
+                Ok(_) => tracing::debug!("Waitlist job found no free seats for waiting entries"),
+                Err(e) => tracing::error!("Waitlist job failed: {}", e),
+            }
+        }
+    })
+}
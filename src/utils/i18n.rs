@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use crate::utils::{CodedError, ValidationErrors};
+
+/// Message catalogs, one flat JSON object per language mapping error codes to templates
+/// Templates name their arguments in braces, e.g. "Office with ID {id} does not exist"
+static ENGLISH: LazyLock<HashMap<String, String>> = LazyLock::new(|| parse_catalog(include_str!("../../locales/en.json")));
+static DANISH: LazyLock<HashMap<String, String>> = LazyLock::new(|| parse_catalog(include_str!("../../locales/da.json")));
+
+fn parse_catalog(json: &str) -> HashMap<String, String> {
+    serde_json::from_str(json).expect("message catalog is a JSON object of strings")
+}
+
+/// Languages with a shipped catalog, English is the fallback for anything else
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    Danish,
+}
+
+impl Language {
+    // Maps a language tag to a catalog, only the primary subtag counts ("da-DK" is Danish)
+    fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split(['-', '_']).next().unwrap_or("").trim().to_ascii_lowercase();
+        match primary.as_str() {
+            "en" => Some(Language::English),
+            "da" => Some(Language::Danish),
+            _ => None,
+        }
+    }
+
+    /// Picks the most preferred supported language of an Accept-Language header
+    /// Entries with q=0 are skipped, English when none is supported or the header is missing
+    pub fn from_accept_language(header: Option<&str>) -> Self {
+        let Some(header) = header else {
+            return Language::English;
+        };
+        let mut entries: Vec<(&str, f32)> = header
+            .split(',')
+            .map(|entry| {
+                let mut parts = entry.split(';');
+                let tag = parts.next().unwrap_or("").trim();
+                let q = parts
+                    .find_map(|param| param.trim().strip_prefix("q=").and_then(|q| q.trim().parse::<f32>().ok()))
+                    .unwrap_or(1.0);
+                (tag, q)
+            })
+            .filter(|(_, q)| *q > 0.0)
+            .collect();
+        // stable sort keeps header order for equal q
+        entries.sort_by(|a, b| b.1.total_cmp(&a.1));
+        entries.into_iter().find_map(|(tag, _)| Language::from_tag(tag)).unwrap_or_default()
+    }
+
+    fn catalog(self) -> &'static HashMap<String, String> {
+        match self {
+            Language::English => &ENGLISH,
+            Language::Danish => &DANISH,
+        }
+    }
+}
+
+/// Message for an error code in the language, filled with the named arguments
+/// Falls back to the English template, then to the code itself
+pub fn translate(language: Language, code: &str, args: &[(String, String)]) -> String {
+    let Some(template) = language.catalog().get(code).or_else(|| ENGLISH.get(code)) else {
+        tracing::warn!("No message for error code {}", code);
+        return code.to_string();
+    };
+    args.iter().fold(template.clone(), |message, (name, value)| message.replace(&format!("{{{}}}", name), value))
+}
+
+/// Message of a service error in the language
+/// Coded errors and validation errors come from the catalogs, any other error keeps its own text
+pub fn localize(error: &anyhow::Error, language: Language) -> String {
+    if let Some(coded) = error.downcast_ref::<CodedError>() {
+        return coded.message(language);
+    }
+    if let Some(errors) = error.downcast_ref::<ValidationErrors>() {
+        return errors.localized(language).to_string();
+    }
+    error.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogs_have_the_same_codes() {
+        let mut english: Vec<_> = ENGLISH.keys().collect();
+        let mut danish: Vec<_> = DANISH.keys().collect();
+        english.sort();
+        danish.sort();
+        assert_eq!(english, danish);
+    }
+
+    #[test]
+    fn test_accept_language_preference() {
+        assert_eq!(Language::from_accept_language(None), Language::English);
+        assert_eq!(Language::from_accept_language(Some("da-DK,da;q=0.9,en;q=0.8")), Language::Danish);
+        assert_eq!(Language::from_accept_language(Some("en;q=0.5, da")), Language::Danish);
+        assert_eq!(Language::from_accept_language(Some("de-DE, fr;q=0.8")), Language::English);
+        assert_eq!(Language::from_accept_language(Some("da;q=0, en")), Language::English);
+    }
+
+    #[test]
+    fn test_arguments_are_filled_in() {
+        let args = vec![("id".to_string(), "7".to_string())];
+        assert_eq!(translate(Language::Danish, "office.not_found", &args), "Kontor med ID 7 findes ikke");
+        assert_eq!(translate(Language::English, "office.not_found", &args), "Office with ID 7 does not exist");
+        assert_eq!(translate(Language::Danish, "unknown.code", &args), "unknown.code");
+    }
+}
@@ -1,6 +1,148 @@
-/// Validation trait, mostly just to show interface usage
-/// used for entity validation before DB operations
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use i18n::{translate, Language};
+
+pub mod i18n;
+pub mod names;
 
+/// Validation trait, mostly just to show interface usage
+/// used for entity validation before DB operations, reports every broken rule instead of stopping at the first
 pub trait Validate {
-    fn validate(&self) -> Result<(), String>;
-}
\ No newline at end of file
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+/// One broken validation rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String, // path of the offending field, e.g. "last_name"
+    pub code: String, // machine readable "<field>.<rule>", e.g. "last_name.whitespace"
+    pub message: String, // human readable explanation, from the message catalog of the code
+    #[serde(skip)]
+    pub args: Vec<(String, String)>, // named values filled into the message
+}
+
+/// Every broken rule of one entity
+/// Is an error type of its own so it travels through anyhow, controllers answer it with 422 and the list of errors
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    /// Empty collection, add violations with `add`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a violation of `rule` on `field`, the code becomes "<field>.<rule>"
+    /// The message is the English one, see `localized` for others
+    pub fn add(&mut self, field: &str, rule: &str, args: &[(&str, &dyn std::fmt::Display)]) {
+        let code = format!("{}.{}", field, rule);
+        let args: Vec<(String, String)> = args.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        let message = translate(Language::English, &code, &args);
+        self.errors.push(FieldError { field: field.to_string(), code, message, args });
+    }
+
+    /// Same violations with their messages in the language
+    pub fn localized(&self, language: Language) -> Self {
+        let errors = self.errors.iter()
+            .map(|e| FieldError { message: translate(language, &e.code, &e.args), ..e.clone() })
+            .collect();
+        Self { errors }
+    }
+
+    /// True if no rule was broken
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Ok when empty, the collected violations otherwise
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() { Ok(()) } else { Err(self) }
+    }
+
+    /// Messages of all violations in the order they were found
+    pub fn messages(&self) -> Vec<String> {
+        self.errors.iter().map(|e| e.message.clone()).collect()
+    }
+
+    /// Codes of all violations in the order they were found
+    pub fn codes(&self) -> Vec<&str> {
+        self.errors.iter().map(|e| e.code.as_str()).collect()
+    }
+}
+
+impl std::fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.messages().join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// Service error identified by a machine code, e.g. "office.not_found", with named arguments
+/// Its text comes from the message catalogs, Display gives the English one
+/// Serializable, so a reason can be stored and shown later in the reader's language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodedError {
+    pub code: String,
+    pub args: Vec<(String, String)>,
+}
+
+impl CodedError {
+    /// Error without arguments
+    pub fn new(code: &str) -> Self {
+        Self { code: code.to_string(), args: Vec::new() }
+    }
+
+    /// Adds a named argument for the message template
+    pub fn arg(mut self, name: &str, value: impl std::fmt::Display) -> Self {
+        self.args.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Message in the language
+    pub fn message(&self, language: Language) -> String {
+        translate(language, &self.code, &self.args)
+    }
+
+    /// Whether the error names a missing record, e.g. "employee.not_found" or "employee.manager_not_found"
+    pub fn is_not_found(&self) -> bool {
+        self.code.ends_with("not_found")
+    }
+}
+
+impl std::fmt::Display for CodedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message(Language::English))
+    }
+}
+
+impl std::error::Error for CodedError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_are_collected_in_order() {
+        let mut errors = ValidationErrors::new();
+        assert_eq!(errors.clone().into_result(), Ok(()));
+        errors.add("first_name", "empty", &[]);
+        errors.add("last_name", "whitespace", &[]);
+        assert_eq!(errors.codes(), vec!["first_name.empty", "last_name.whitespace"]);
+        assert_eq!(errors.to_string(), "First name cannot be empty; Last name cannot contain whitespace");
+
+        assert_eq!(errors.localized(Language::Danish).messages(), vec!["Fornavn skal udfyldes", "Efternavn må ikke indeholde mellemrum"]);
+
+        let error = anyhow::Error::new(errors);
+        assert_eq!(error.downcast_ref::<ValidationErrors>().map(|e| e.errors.len()), Some(2));
+    }
+
+    #[test]
+    fn test_coded_error_messages() {
+        let error = CodedError::new("office.full").arg("name", "Aalborg").arg("max", 3);
+        assert_eq!(error.to_string(), "Office Aalborg is at full capacity: 3/3 employees");
+        assert_eq!(error.message(Language::Danish), "Kontoret Aalborg er fuldt: 3/3 medarbejdere");
+    }
+}
@@ -0,0 +1,42 @@
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Canonical form of a person's name as stored
+/// Unicode NFC, so "é" typed as e + combining accent equals the precomposed "é",
+/// trimmed, and every run of whitespace inside the name replaced by one space ("van  der\tBerg" -> "van der Berg")
+pub fn normalize_name(name: &str) -> String {
+    name.nfc().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Key under which two spellings of the same name count as one person
+/// Case, accents, spaces, hyphens and apostrophes are ignored: "Van der Berg", "vanderberg" and "Vän-der Berg" share a key.
+/// Letters that are not an accented form of another letter (æ, ø, å) are kept
+pub fn duplicate_key(first_name: &str, last_name: &str) -> String {
+    let key = |name: &str| -> String {
+        name.nfd()
+            .filter(|c| !is_combining_mark(*c))
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    format!("{}|{}", key(first_name), key(last_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_are_nfc_with_single_spaces() {
+        let decomposed = "Rene\u{301}";
+        assert_eq!(normalize_name(decomposed), "Ren\u{e9}");
+        assert_eq!(normalize_name("  van \u{a0} der\tBerg "), "van der Berg");
+    }
+
+    #[test]
+    fn test_duplicate_keys_ignore_case_accents_and_separators() {
+        assert_eq!(duplicate_key("René", "Van der Berg"), duplicate_key("rene", "vanderberg"));
+        assert_eq!(duplicate_key("Anne-Marie", "O'Brien"), duplicate_key("Anne Marie", "OBrien"));
+        assert_ne!(duplicate_key("Søren", "Berg"), duplicate_key("Soren", "Berg"));
+        assert_ne!(duplicate_key("Anne", "Berg"), duplicate_key("Berg", "Anne"));
+    }
+}
@@ -0,0 +1,905 @@
+mod utils;
+use std::sync::Arc;
+use futures::StreamExt;
+
+use corp_data_api::config::db_settings::Settings;
+use corp_data_api::entity::change_request::{ApprovalStep, ChangeRequest, ChangeRequestStatus, Comment, Decision, ProposedChange};
+use corp_data_api::entity::waitlist::{WaitlistEntry, WaitlistEvent, WaitlistEventKind, WaitlistStatus};
+use corp_data_api::entity::location::{Location, LocationKind};
+use corp_data_api::dto::waitlist_dto::WaitlistRequest;
+use corp_data_api::dto::office_dto::SearchArea;
+use corp_data_api::entity::{audit_entry::AuditEntry, department::Department, employee::{Employee, EmploymentStatus}, office::Office, tenant::{Tenant, DEFAULT_TENANT_ID}, transfer::{Transfer, TransferStatus}};
+use corp_data_api::repository::audit_repository::{AuditRepository, AuditStore};
+use corp_data_api::repository::change_request_repository::{ChangeRequestRepository, ChangeRequestStore};
+use corp_data_api::repository::department_repository::{DepartmentRepository, DepartmentStore};
+use corp_data_api::repository::employee_repository::{EmployeeRepository, EmployeeStore};
+use corp_data_api::repository::location_repository::{LocationRepository, LocationStore};
+use corp_data_api::repository::office_manager_repository::{OfficeManagerRepository, OfficeManagerStore};
+use corp_data_api::repository::office_repository::{OfficeRepository, OfficeStore};
+use corp_data_api::repository::memory_repository::MemoryDatabase;
+use corp_data_api::repository::tenant_repository::TenantRepository;
+use corp_data_api::repository::transfer_repository::{TransferRepository, TransferStore};
+use corp_data_api::repository::unit_of_work::{in_transaction, PgUnitOfWork, UnitOfWork};
+use corp_data_api::repository::waitlist_repository::{WaitlistRepository, WaitlistStore};
+
+// Conformance suite for the storage backends
+// Every check runs against Postgres and the in-memory backend, both must behave the same.
+// With `--features sqlite` the checks also run against an in-memory SQLite database
+
+/// Repositories of one backend plus a second tenant for isolation checks
+struct Backend {
+    offices: Arc<dyn OfficeStore>,
+    employees: Arc<dyn EmployeeStore>,
+    managers: Arc<dyn OfficeManagerStore>,
+    audit: Arc<dyn AuditStore>,
+    departments: Arc<dyn DepartmentStore>,
+    transfers: Arc<dyn TransferStore>,
+    change_requests: Arc<dyn ChangeRequestStore>,
+    waitlist: Arc<dyn WaitlistStore>,
+    locations: Arc<dyn LocationStore>,
+    unit_of_work: Arc<dyn UnitOfWork>,
+    other_tenant: i32,
+}
+
+fn memory_backend() -> Backend {
+    let db = MemoryDatabase::new();
+    Backend {
+        offices: Arc::new(db.office_repository()),
+        employees: Arc::new(db.employee_repository()),
+        managers: Arc::new(db.office_manager_repository()),
+        audit: Arc::new(db.audit_repository()),
+        departments: Arc::new(db.department_repository()),
+        transfers: Arc::new(db.transfer_repository()),
+        change_requests: Arc::new(db.change_request_repository()),
+        waitlist: Arc::new(db.waitlist_repository()),
+        locations: Arc::new(db.location_repository()),
+        unit_of_work: Arc::new(db.unit_of_work()),
+        other_tenant: DEFAULT_TENANT_ID + 1,
+    }
+}
+
+async fn postgres_backend() -> (Backend, sqlx::PgPool) {
+    dotenv::from_filename(".env.test").ok();
+    let pool = Settings::connect_from_env().unwrap().create_pool().await.unwrap();
+    utils::clean_db(&pool).await;
+    let other_tenant = TenantRepository::new(pool.clone())
+        .create_tenant(&Tenant { id: None, name: "Conformance".into() })
+        .await
+        .unwrap()
+        .id
+        .unwrap();
+    let backend = Backend {
+        offices: Arc::new(OfficeRepository::new(pool.clone())),
+        employees: Arc::new(EmployeeRepository::new(pool.clone())),
+        managers: Arc::new(OfficeManagerRepository::new(pool.clone())),
+        audit: Arc::new(AuditRepository::new(pool.clone())),
+        departments: Arc::new(DepartmentRepository::new(pool.clone())),
+        transfers: Arc::new(TransferRepository::new(pool.clone())),
+        change_requests: Arc::new(ChangeRequestRepository::new(pool.clone())),
+        waitlist: Arc::new(WaitlistRepository::new(pool.clone())),
+        locations: Arc::new(LocationRepository::new(pool.clone())),
+        unit_of_work: Arc::new(PgUnitOfWork::new(pool.clone())),
+        other_tenant,
+    };
+    (backend, pool)
+}
+
+#[cfg(feature = "sqlite")]
+async fn sqlite_backend() -> Backend {
+    use corp_data_api::repository::sqlite_repository::{
+        SqliteAuditRepository, SqliteChangeRequestRepository, SqliteDepartmentRepository, SqliteEmployeeRepository, SqliteLocationRepository, SqliteOfficeManagerRepository, SqliteOfficeRepository,
+        SqliteTransferRepository, SqliteUnitOfWork, SqliteWaitlistRepository,
+    };
+    let settings = Settings::from_url("sqlite::memory:");
+    let pool = settings.create_sqlite_pool().await.unwrap();
+    let other_tenant = sqlx::query_scalar::<_, i32>("INSERT INTO tenants (name) VALUES ('Conformance') RETURNING id")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    Backend {
+        offices: Arc::new(SqliteOfficeRepository::new(pool.clone())),
+        employees: Arc::new(SqliteEmployeeRepository::new(pool.clone())),
+        managers: Arc::new(SqliteOfficeManagerRepository::new(pool.clone())),
+        audit: Arc::new(SqliteAuditRepository::new(pool.clone())),
+        departments: Arc::new(SqliteDepartmentRepository::new(pool.clone())),
+        transfers: Arc::new(SqliteTransferRepository::new(pool.clone())),
+        change_requests: Arc::new(SqliteChangeRequestRepository::new(pool.clone())),
+        waitlist: Arc::new(SqliteWaitlistRepository::new(pool.clone())),
+        locations: Arc::new(SqliteLocationRepository::new(pool.clone())),
+        unit_of_work: Arc::new(SqliteUnitOfWork::new(pool)),
+        other_tenant,
+    }
+}
+
+// Runs each listed check once per backend
+macro_rules! conformance {
+    ($($check:ident),* $(,)?) => {
+        mod memory {
+            $(
+                #[tokio::test]
+                async fn $check() {
+                    super::$check(super::memory_backend()).await;
+                }
+            )*
+        }
+
+        mod postgres {
+            use serial_test::serial;
+            $(
+                #[tokio::test]
+                #[serial]
+                async fn $check() {
+                    let (backend, pool) = super::postgres_backend().await;
+                    super::$check(backend).await;
+                    super::utils::clean_db(&pool).await;
+                }
+            )*
+        }
+
+        #[cfg(feature = "sqlite")]
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $check() {
+                    super::$check(super::sqlite_backend().await).await;
+                }
+            )*
+        }
+    };
+}
+
+conformance!(
+    office_crud,
+    office_names_unique_per_tenant,
+    office_max_occupancy_must_be_positive,
+    employee_needs_office_of_own_tenant,
+    employee_birth_date_in_past,
+    employees_by_birth_date,
+    office_with_employees_cannot_be_deleted,
+    manager_grants_follow_office,
+    batch_insert_is_atomic,
+    tenants_are_isolated,
+    streams_are_ordered_and_filtered,
+    transactions_commit_or_roll_back,
+    concurrent_transactions_are_retried,
+    merge_archives_source,
+    departments_are_unique_and_kept_while_used,
+    manager_hierarchy_queries,
+    merge_moves_reports_to_survivor,
+    merge_keeps_transfers_of_source,
+    seats_follow_employment_period,
+    transfers_are_pending_once_and_follow_deletes,
+    change_requests_keep_steps_in_order_and_decide_once,
+    waitlists_keep_arrival_order_and_follow_office,
+    offices_near_are_ordered_by_distance,
+    locations_read_site_capacity_and_follow_office,
+    merge_hands_room_to_survivor,
+    merge_repoints_waitlist_entries,
+);
+
+fn office(name: &str, max_occupancy: i32) -> Office {
+    Office { id: None, name: name.into(), max_occupancy, ..Default::default() }
+}
+
+fn employee(last_name: &str, office_id: i32) -> Employee {
+    Employee {
+        id: None,
+        first_name: "Test".into(),
+        last_name: last_name.into(),
+        birth_date: chrono::NaiveDate::from_ymd_opt(1980, 1, 1).expect("Invalid date"),
+        office_id,
+        department_id: None,
+        manager_id: None,
+        hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"),
+        termination_date: None,
+        status: EmploymentStatus::Active,
+    }
+}
+
+/// Create, read, update and delete of an office
+async fn office_crud(b: Backend) {
+    let created = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 5)).await.unwrap();
+    let id = created.id.unwrap();
+    assert_eq!(b.offices.get_office_by_id(DEFAULT_TENANT_ID, id).await.unwrap().unwrap().name, "Aalborg");
+    assert_eq!(b.offices.get_office_by_name(DEFAULT_TENANT_ID, "Aalborg").await.unwrap().unwrap().id, Some(id));
+
+    let updated = b.offices.update_office_by_id(DEFAULT_TENANT_ID, id, &office("Aalborg Øst", 7)).await.unwrap();
+    assert_eq!((updated.id, updated.max_occupancy), (Some(id), 7));
+    assert!(b.offices.update_office_by_id(DEFAULT_TENANT_ID, id + 1000, &office("Nowhere", 1)).await.is_err());
+    assert_eq!(b.offices.get_all_offices(DEFAULT_TENANT_ID).await.unwrap().len(), 1);
+
+    assert_eq!(b.offices.delete_office(DEFAULT_TENANT_ID, id).await.unwrap(), 1);
+    assert_eq!(b.offices.delete_office(DEFAULT_TENANT_ID, id).await.unwrap(), 0);
+    assert!(b.offices.get_office_by_id(DEFAULT_TENANT_ID, id).await.unwrap().is_none());
+}
+
+/// Office names are unique within a tenant only
+async fn office_names_unique_per_tenant(b: Backend) {
+    b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 5)).await.unwrap();
+    let aarhus = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aarhus", 5)).await.unwrap();
+    assert!(b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 3)).await.is_err());
+    assert!(b.offices.update_office_by_id(DEFAULT_TENANT_ID, aarhus.id.unwrap(), &office("Aalborg", 5)).await.is_err());
+    assert!(b.offices.create_office(b.other_tenant, &office("Aalborg", 3)).await.is_ok());
+}
+
+/// max_occupancy has a CHECK (> 0)
+async fn office_max_occupancy_must_be_positive(b: Backend) {
+    assert!(b.offices.create_office(DEFAULT_TENANT_ID, &office("Empty", 0)).await.is_err());
+    assert!(b.offices.get_all_offices(DEFAULT_TENANT_ID).await.unwrap().is_empty());
+}
+
+/// Employees reference an existing office of their own tenant
+async fn employee_needs_office_of_own_tenant(b: Backend) {
+    let own = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 5)).await.unwrap().id.unwrap();
+    let foreign = b.offices.create_office(b.other_tenant, &office("Skagen", 5)).await.unwrap().id.unwrap();
+
+    assert!(b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Missing", own + foreign + 1000)).await.is_err());
+    assert!(b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Foreign", foreign)).await.is_err());
+    let created = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Own", own)).await.unwrap();
+    let id = created.id.unwrap();
+    assert!(b.employees.update_employee_by_id(DEFAULT_TENANT_ID, id, &employee("Moved", foreign)).await.is_err());
+    assert_eq!(b.employees.get_employee_by_id(DEFAULT_TENANT_ID, id).await.unwrap().unwrap().office_id, own);
+}
+
+/// birth_date has a CHECK (< CURRENT_DATE)
+async fn employee_birth_date_in_past(b: Backend) {
+    let own = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 5)).await.unwrap().id.unwrap();
+    let mut unborn = employee("Unborn", own);
+    unborn.birth_date = chrono::Utc::now().date_naive() + chrono::Duration::days(1);
+    assert!(b.employees.create_employee(DEFAULT_TENANT_ID, &unborn).await.is_err());
+}
+
+/// Birth date lookup of the duplicate check matches the exact date within the tenant
+async fn employees_by_birth_date(b: Backend) {
+    let own = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 5)).await.unwrap().id.unwrap();
+    let foreign = b.offices.create_office(b.other_tenant, &office("Skagen", 5)).await.unwrap().id.unwrap();
+    let mut younger = employee("Younger", own);
+    younger.birth_date = younger.birth_date.succ_opt().unwrap();
+    b.employees.create_employees(DEFAULT_TENANT_ID, &[employee("Same", own), younger]).await.unwrap();
+    b.employees.create_employee(b.other_tenant, &employee("Same", foreign)).await.unwrap();
+
+    let found = b.employees.get_employees_by_birth_date(DEFAULT_TENANT_ID, employee("Same", own).birth_date).await.unwrap();
+    assert_eq!(found.iter().map(|e| e.last_name.as_str()).collect::<Vec<_>>(), vec!["Same"]);
+}
+
+/// The employee foreign key blocks deleting an office that still has employees
+async fn office_with_employees_cannot_be_deleted(b: Backend) {
+    let own = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 5)).await.unwrap().id.unwrap();
+    let staff = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Staff", own)).await.unwrap();
+    assert!(b.offices.delete_office(DEFAULT_TENANT_ID, own).await.is_err());
+
+    assert_eq!(b.employees.delete_employee(DEFAULT_TENANT_ID, staff.id.unwrap()).await.unwrap(), 1);
+    assert_eq!(b.offices.delete_office(DEFAULT_TENANT_ID, own).await.unwrap(), 1);
+}
+
+/// Manager grants need an office of the tenant and are removed with it
+async fn manager_grants_follow_office(b: Backend) {
+    let own = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 5)).await.unwrap().id.unwrap();
+    let foreign = b.offices.create_office(b.other_tenant, &office("Skagen", 5)).await.unwrap().id.unwrap();
+
+    b.managers.add_office_manager(DEFAULT_TENANT_ID, "manager", own).await.unwrap();
+    b.managers.add_office_manager(DEFAULT_TENANT_ID, "manager", own).await.unwrap();
+    b.managers.add_office_manager(DEFAULT_TENANT_ID, "manager", foreign).await.unwrap();
+    assert_eq!(b.managers.get_office_ids_by_subject(DEFAULT_TENANT_ID, "manager").await.unwrap(), vec![own]);
+    assert_eq!(b.managers.get_subjects_by_office_id(DEFAULT_TENANT_ID, own).await.unwrap(), vec!["manager".to_string()]);
+
+    b.offices.delete_office(DEFAULT_TENANT_ID, own).await.unwrap();
+    assert!(b.managers.get_office_ids_by_subject(DEFAULT_TENANT_ID, "manager").await.unwrap().is_empty());
+    assert_eq!(b.managers.delete_office_manager(DEFAULT_TENANT_ID, "manager", own).await.unwrap(), 0);
+}
+
+/// create_employees writes every row or none, employee counts feed the capacity check
+async fn batch_insert_is_atomic(b: Backend) {
+    let own = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 5)).await.unwrap().id.unwrap();
+    let batch = vec![employee("First", own), employee("Broken", own + 1000)];
+    assert!(b.employees.create_employees(DEFAULT_TENANT_ID, &batch).await.is_err());
+    assert_eq!(b.employees.current_employee_nr_by_office_id(DEFAULT_TENANT_ID, own).await.unwrap(), 0);
+
+    let batch = vec![employee("First", own), employee("Second", own)];
+    let created = b.employees.create_employees(DEFAULT_TENANT_ID, &batch).await.unwrap();
+    assert!(created.iter().all(|e| e.id.is_some()));
+    assert_eq!(b.employees.current_employee_nr_by_office_id(DEFAULT_TENANT_ID, own).await.unwrap(), 2);
+}
+
+/// Nothing of another tenant is visible or changeable
+async fn tenants_are_isolated(b: Backend) {
+    let foreign = b.offices.create_office(b.other_tenant, &office("Skagen", 5)).await.unwrap().id.unwrap();
+    let staff = b.employees.create_employee(b.other_tenant, &employee("Foreign", foreign)).await.unwrap().id.unwrap();
+
+    assert!(b.offices.get_office_by_id(DEFAULT_TENANT_ID, foreign).await.unwrap().is_none());
+    assert!(b.offices.get_all_offices(DEFAULT_TENANT_ID).await.unwrap().is_empty());
+    assert!(b.employees.get_employee_by_id(DEFAULT_TENANT_ID, staff).await.unwrap().is_none());
+    assert!(b.employees.get_all_employees(DEFAULT_TENANT_ID).await.unwrap().is_empty());
+    assert_eq!(b.employees.delete_employee(DEFAULT_TENANT_ID, staff).await.unwrap(), 0);
+    assert_eq!(b.offices.delete_office(DEFAULT_TENANT_ID, foreign).await.unwrap(), 0);
+    assert_eq!(b.employees.get_employees_by_office_ids(b.other_tenant, &[foreign]).await.unwrap().len(), 1);
+}
+
+/// Export streams are ordered by id and honor the office filters
+async fn streams_are_ordered_and_filtered(b: Backend) {
+    let aalborg = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 5)).await.unwrap().id.unwrap();
+    let aarhus = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aarhus", 5)).await.unwrap().id.unwrap();
+    for (last_name, office_id) in [("A", aalborg), ("B", aarhus), ("C", aalborg)] {
+        b.employees.create_employee(DEFAULT_TENANT_ID, &employee(last_name, office_id)).await.unwrap();
+    }
+
+    let offices: Vec<_> = b.offices.stream_offices(DEFAULT_TENANT_ID).map(|r| r.unwrap().name).collect().await;
+    assert_eq!(offices, vec!["Aalborg", "Aarhus"]);
+
+    let all: Vec<_> = b.employees.stream_employees(DEFAULT_TENANT_ID, None, None, true).map(|r| r.unwrap()).collect().await;
+    assert_eq!(all.iter().map(|e| e.last_name.as_str()).collect::<Vec<_>>(), vec!["A", "B", "C"]);
+    assert_eq!(all[1].office_name.as_deref(), Some("Aarhus"));
+
+    let one_office: Vec<_> = b.employees.stream_employees(DEFAULT_TENANT_ID, Some(aalborg), None, false).map(|r| r.unwrap()).collect().await;
+    assert_eq!(one_office.len(), 2);
+    assert!(one_office.iter().all(|e| e.office_name.is_none()));
+
+    let scoped: Vec<_> = b.employees.stream_employees(DEFAULT_TENANT_ID, None, Some(vec![aarhus]), false).map(|r| r.unwrap()).collect().await;
+    assert_eq!(scoped.len(), 1);
+}
+
+/// Writes through several repositories in one transaction become visible together on commit, never on rollback
+async fn transactions_commit_or_roll_back(b: Backend) {
+    let aalborg = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 5)).await.unwrap().id.unwrap();
+    let aarhus = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aarhus", 5)).await.unwrap().id.unwrap();
+    b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Staff", aalborg)).await.unwrap();
+
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert_eq!(b.employees.reassign_employees_tx(&mut tx, DEFAULT_TENANT_ID, aalborg, aarhus).await.unwrap(), 1);
+    assert_eq!(b.offices.delete_office_tx(&mut tx, DEFAULT_TENANT_ID, aalborg).await.unwrap(), 1);
+    assert!(b.offices.get_office_by_id_tx(&mut tx, DEFAULT_TENANT_ID, aalborg).await.unwrap().is_none());
+    let hired = b.employees.create_employee_tx(&mut tx, DEFAULT_TENANT_ID, &employee("Hired", aarhus)).await.unwrap();
+    let born = b.employees.get_employees_by_birth_date_tx(&mut tx, DEFAULT_TENANT_ID, hired.birth_date).await.unwrap();
+    assert!(born.iter().any(|e| e.id == hired.id));
+    tx.rollback().await.unwrap();
+    assert!(b.offices.get_office_by_id(DEFAULT_TENANT_ID, aalborg).await.unwrap().is_some());
+    assert_eq!(b.employees.current_employee_nr_by_office_id(DEFAULT_TENANT_ID, aalborg).await.unwrap(), 1);
+    assert_eq!(b.employees.current_employee_nr_by_office_id(DEFAULT_TENANT_ID, aarhus).await.unwrap(), 0);
+
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    b.employees.reassign_employees_tx(&mut tx, DEFAULT_TENANT_ID, aalborg, aarhus).await.unwrap();
+    b.offices.delete_office_tx(&mut tx, DEFAULT_TENANT_ID, aalborg).await.unwrap();
+    tx.commit().await.unwrap();
+    assert!(b.offices.get_office_by_id(DEFAULT_TENANT_ID, aalborg).await.unwrap().is_none());
+    assert_eq!(b.employees.current_employee_nr_by_office_id(DEFAULT_TENANT_ID, aarhus).await.unwrap(), 1);
+}
+
+/// Concurrent read-modify-write transactions on one office all take effect, conflicting attempts are retried
+async fn concurrent_transactions_are_retried(b: Backend) {
+    let id = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 1)).await.unwrap().id.unwrap();
+    let grow = || {
+        in_transaction(b.unit_of_work.as_ref(), |mut tx| async {
+            let current = b.offices.get_office_by_id_tx(&mut tx, DEFAULT_TENANT_ID, id).await?.unwrap();
+            let grown = office(&current.name, current.max_occupancy + 1);
+            b.offices.update_office_by_id_tx(&mut tx, DEFAULT_TENANT_ID, id, &grown).await?;
+            Ok((tx, ()))
+        })
+    };
+    let results = futures::future::join_all([grow(), grow(), grow()]).await;
+    assert!(results.iter().all(|r| r.is_ok()), "{:?}", results);
+    assert_eq!(b.offices.get_office_by_id(DEFAULT_TENANT_ID, id).await.unwrap().unwrap().max_occupancy, 4);
+}
+
+/// Merging archives the source, releases its seat and moves records about it to the survivor
+async fn merge_archives_source(b: Backend) {
+    let own = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 5)).await.unwrap().id.unwrap();
+    let batch = vec![employee("Keep", own), employee("First", own), employee("Second", own)];
+    let ids: Vec<i32> = b.employees.create_employees(DEFAULT_TENANT_ID, &batch).await.unwrap().iter().map(|e| e.id.unwrap()).collect();
+    let (keep, first, second) = (ids[0], ids[1], ids[2]);
+    let entry = AuditEntry { id: None, subject: "hr".into(), action: "test".into(), employee_id: Some(second), details: "{}".into(), created_at: chrono::Utc::now() };
+
+    // first is merged into second, then second into keep: everything ends at keep
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert!(b.audit.record_audit_entry_tx(&mut tx, DEFAULT_TENANT_ID, &entry).await.unwrap().id.is_some());
+    assert_eq!(b.employees.merge_employee_tx(&mut tx, DEFAULT_TENANT_ID, first, second, own).await.unwrap(), 1);
+    tx.commit().await.unwrap();
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert_eq!(b.employees.merge_employee_tx(&mut tx, DEFAULT_TENANT_ID, second, keep, own).await.unwrap(), 1);
+    assert_eq!(b.employees.merge_employee_tx(&mut tx, DEFAULT_TENANT_ID, second, keep, own).await.unwrap(), 0);
+    tx.commit().await.unwrap();
+
+    assert_eq!(b.employees.current_employee_nr_by_office_id(DEFAULT_TENANT_ID, own).await.unwrap(), 1);
+    assert!(b.employees.get_employee_by_id(DEFAULT_TENANT_ID, second).await.unwrap().is_none());
+    let archived = b.employees.get_archived_employee_by_id(DEFAULT_TENANT_ID, second).await.unwrap().unwrap();
+    assert_eq!((archived.last_name.as_str(), archived.merged_into), ("Second", Some(keep)));
+    assert_eq!(b.employees.get_archived_employee_by_id(DEFAULT_TENANT_ID, first).await.unwrap().unwrap().merged_into, Some(keep));
+    assert!(b.employees.get_archived_employee_by_id(b.other_tenant, first).await.unwrap().is_none());
+    assert_eq!(b.audit.get_audit_entries_by_employee_id(DEFAULT_TENANT_ID, keep).await.unwrap().len(), 1);
+    assert!(b.audit.get_audit_entries_by_employee_id(DEFAULT_TENANT_ID, second).await.unwrap().is_empty());
+
+    // the archive outlives the survivor
+    assert_eq!(b.employees.delete_employee(DEFAULT_TENANT_ID, keep).await.unwrap(), 1);
+    assert_eq!(b.employees.get_archived_employee_by_id(DEFAULT_TENANT_ID, first).await.unwrap().unwrap().merged_into, None);
+}
+
+/// Department names are unique per tenant, employees need a department of their own tenant
+/// and a department cannot be deleted while employees belong to it
+async fn departments_are_unique_and_kept_while_used(b: Backend) {
+    let own = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 5)).await.unwrap().id.unwrap();
+    let sales = b.departments.create_department(DEFAULT_TENANT_ID, &Department { id: None, name: "Salg".into() }).await.unwrap().id.unwrap();
+    assert!(b.departments.create_department(DEFAULT_TENANT_ID, &Department { id: None, name: "Salg".into() }).await.is_err());
+    let foreign = b.departments.create_department(b.other_tenant, &Department { id: None, name: "Salg".into() }).await.unwrap().id.unwrap();
+
+    let renamed = b.departments.update_department_by_id(DEFAULT_TENANT_ID, sales, &Department { id: None, name: "Salg og marketing".into() }).await.unwrap();
+    assert_eq!(renamed.name, "Salg og marketing");
+    assert_eq!(b.departments.get_department_by_name(DEFAULT_TENANT_ID, "Salg og marketing").await.unwrap().unwrap().id, Some(sales));
+    assert!(b.departments.get_department_by_id(b.other_tenant, sales).await.unwrap().is_none());
+    assert_eq!(b.departments.get_all_departments(DEFAULT_TENANT_ID).await.unwrap().len(), 1);
+
+    let mut member = employee("Member", own);
+    member.department_id = Some(foreign);
+    assert!(b.employees.create_employee(DEFAULT_TENANT_ID, &member).await.is_err());
+    member.department_id = Some(sales);
+    let member = b.employees.create_employee(DEFAULT_TENANT_ID, &member).await.unwrap();
+    assert_eq!(member.department_id, Some(sales));
+    assert_eq!(b.departments.employee_nr_by_department_id(DEFAULT_TENANT_ID, sales).await.unwrap(), 1);
+
+    assert!(b.departments.delete_department(DEFAULT_TENANT_ID, sales).await.is_err());
+    b.employees.delete_employee(DEFAULT_TENANT_ID, member.id.unwrap()).await.unwrap();
+    assert_eq!(b.departments.delete_department(DEFAULT_TENANT_ID, sales).await.unwrap(), 1);
+    assert_eq!(b.departments.delete_department(DEFAULT_TENANT_ID, sales).await.unwrap(), 0);
+}
+
+/// Direct reports, the reporting chain and the subtree follow manager_id within the tenant,
+/// an employee cannot manage itself and deleting a manager leaves the reports without one
+async fn manager_hierarchy_queries(b: Backend) {
+    let own = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 10)).await.unwrap().id.unwrap();
+    let reporting_to = |last_name: &str, manager_id: Option<i32>| Employee { manager_id, ..employee(last_name, own) };
+    let ceo = b.employees.create_employee(DEFAULT_TENANT_ID, &reporting_to("Ceo", None)).await.unwrap().id.unwrap();
+    let cto = b.employees.create_employee(DEFAULT_TENANT_ID, &reporting_to("Cto", Some(ceo))).await.unwrap().id.unwrap();
+    let cfo = b.employees.create_employee(DEFAULT_TENANT_ID, &reporting_to("Cfo", Some(ceo))).await.unwrap().id.unwrap();
+    let dev = b.employees.create_employee(DEFAULT_TENANT_ID, &reporting_to("Dev", Some(cto))).await.unwrap().id.unwrap();
+    let ids = |employees: Vec<Employee>| employees.iter().map(|e| e.id.unwrap()).collect::<Vec<_>>();
+
+    assert_eq!(ids(b.employees.get_direct_reports(DEFAULT_TENANT_ID, ceo).await.unwrap()), vec![cto, cfo]);
+    assert_eq!(ids(b.employees.get_reporting_chain(DEFAULT_TENANT_ID, dev).await.unwrap()), vec![cto, ceo]);
+    assert!(b.employees.get_reporting_chain(DEFAULT_TENANT_ID, ceo).await.unwrap().is_empty());
+    assert_eq!(ids(b.employees.get_subtree(DEFAULT_TENANT_ID, ceo).await.unwrap()), vec![cto, cfo, dev]);
+    assert!(b.employees.get_subtree(b.other_tenant, ceo).await.unwrap().is_empty());
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert_eq!(ids(b.employees.get_reporting_chain_tx(&mut tx, DEFAULT_TENANT_ID, dev).await.unwrap()), vec![cto, ceo]);
+    tx.rollback().await.unwrap();
+
+    let foreign_office = b.offices.create_office(b.other_tenant, &office("Oslo", 5)).await.unwrap().id.unwrap();
+    let foreign = Employee { manager_id: Some(ceo), ..employee("Foreign", foreign_office) };
+    assert!(b.employees.create_employee(b.other_tenant, &foreign).await.is_err());
+    assert!(b.employees.update_employee_by_id(DEFAULT_TENANT_ID, ceo, &reporting_to("Ceo", Some(ceo))).await.is_err());
+
+    assert_eq!(b.employees.delete_employee(DEFAULT_TENANT_ID, cto).await.unwrap(), 1);
+    assert_eq!(b.employees.get_employee_by_id(DEFAULT_TENANT_ID, dev).await.unwrap().unwrap().manager_id, None);
+    assert_eq!(ids(b.employees.get_subtree(DEFAULT_TENANT_ID, ceo).await.unwrap()), vec![cfo]);
+}
+
+/// Merging moves the source's reports to the survivor
+async fn merge_moves_reports_to_survivor(b: Backend) {
+    let own = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 10)).await.unwrap().id.unwrap();
+    let keep = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Keep", own)).await.unwrap().id.unwrap();
+    let gone = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Gone", own)).await.unwrap().id.unwrap();
+    let report = Employee { manager_id: Some(gone), ..employee("Report", own) };
+    let report = b.employees.create_employee(DEFAULT_TENANT_ID, &report).await.unwrap().id.unwrap();
+    let survivor = Employee { manager_id: Some(gone), ..employee("Keep", own) };
+    b.employees.update_employee_by_id(DEFAULT_TENANT_ID, keep, &survivor).await.unwrap();
+
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert_eq!(b.employees.merge_employee_tx(&mut tx, DEFAULT_TENANT_ID, gone, keep, own).await.unwrap(), 1);
+    tx.commit().await.unwrap();
+
+    assert_eq!(b.employees.get_employee_by_id(DEFAULT_TENANT_ID, report).await.unwrap().unwrap().manager_id, Some(keep));
+    // the survivor reported to the source, it cannot report to itself
+    assert_eq!(b.employees.get_employee_by_id(DEFAULT_TENANT_ID, keep).await.unwrap().unwrap().manager_id, None);
+}
+
+/// Merging moves the source's transfers to the survivor, a pending one is cancelled and no longer due
+async fn merge_keeps_transfers_of_source(b: Backend) {
+    let from = b.offices.create_office(DEFAULT_TENANT_ID, &office("Herning", 10)).await.unwrap().id.unwrap();
+    let to = b.offices.create_office(DEFAULT_TENANT_ID, &office("Viborg", 10)).await.unwrap().id.unwrap();
+    let keep = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Keep", from)).await.unwrap();
+    let gone = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Gone", from)).await.unwrap();
+    let day = |d: u32| chrono::NaiveDate::from_ymd_opt(2030, 6, d).expect("Invalid date");
+
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let applied = b.transfers.create_transfer_tx(&mut tx, DEFAULT_TENANT_ID, &Transfer::new(&gone, to, day(1), "hr")).await.unwrap();
+    let applied = Transfer { status: TransferStatus::Applied, ..applied };
+    b.transfers.update_transfer_tx(&mut tx, DEFAULT_TENANT_ID, applied.id.unwrap(), &applied).await.unwrap();
+    let pending = b.transfers.create_transfer_tx(&mut tx, DEFAULT_TENANT_ID, &Transfer::new(&gone, to, day(10), "hr")).await.unwrap();
+    // the survivor's own pending transfer stays the only pending one
+    let own = b.transfers.create_transfer_tx(&mut tx, DEFAULT_TENANT_ID, &Transfer::new(&keep, to, day(20), "hr")).await.unwrap();
+    assert_eq!(b.employees.merge_employee_tx(&mut tx, DEFAULT_TENANT_ID, gone.id.unwrap(), keep.id.unwrap(), from).await.unwrap(), 1);
+    tx.commit().await.unwrap();
+
+    let transfers = &b.transfers;
+    let moved = |id: Option<i32>| async move { transfers.get_transfer_by_id(DEFAULT_TENANT_ID, id.unwrap()).await.unwrap().unwrap() };
+    let (applied, pending, own) = (moved(applied.id).await, moved(pending.id).await, moved(own.id).await);
+    assert_eq!((applied.employee_id, applied.status), (keep.id.unwrap(), TransferStatus::Applied));
+    assert_eq!((pending.employee_id, pending.status), (keep.id.unwrap(), TransferStatus::Cancelled));
+    assert_eq!((own.employee_id, own.status), (keep.id.unwrap(), TransferStatus::Pending));
+    let due = b.transfers.get_due_transfers(day(15)).await.unwrap();
+    assert!(due.iter().all(|(_, t)| t.id != pending.id));
+}
+
+/// A seat is held from hire_date through termination_date: future hires count from their start,
+/// terminated employees stop counting after their last day and a termination before the hire date is refused
+async fn seats_follow_employment_period(b: Backend) {
+    let own = b.offices.create_office(DEFAULT_TENANT_ID, &office("Aalborg", 10)).await.unwrap().id.unwrap();
+    let day = |d: u32| chrono::NaiveDate::from_ymd_opt(2030, 6, d).expect("Invalid date");
+    let period = |last_name: &str, hire_date, termination_date| Employee { hire_date, termination_date, ..employee(last_name, own) };
+    b.employees.create_employee(DEFAULT_TENANT_ID, &period("Leaving", day(1), Some(day(10)))).await.unwrap();
+    b.employees.create_employee(DEFAULT_TENANT_ID, &period("Starting", day(11), None)).await.unwrap();
+    b.employees.create_employee(DEFAULT_TENANT_ID, &period("Later", day(20), None)).await.unwrap();
+
+    let seated = |from, until| b.employees.seated_employee_nr_by_office_id(DEFAULT_TENANT_ID, own, from, until);
+    assert_eq!(seated(day(5), Some(day(5))).await.unwrap(), 1);
+    assert_eq!(seated(day(5), Some(day(15))).await.unwrap(), 1);
+    assert_eq!(seated(day(5), None).await.unwrap(), 2);
+    assert_eq!(seated(day(10), Some(day(11))).await.unwrap(), 1);
+    assert_eq!(seated(day(25), None).await.unwrap(), 2);
+    assert_eq!(b.employees.seated_employee_nr_by_office_id(b.other_tenant, own, day(5), None).await.unwrap(), 0);
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert_eq!(b.employees.seated_employee_nr_by_office_id_tx(&mut tx, DEFAULT_TENANT_ID, own, day(1), None).await.unwrap(), 2);
+    tx.rollback().await.unwrap();
+
+    assert!(b.employees.create_employee(DEFAULT_TENANT_ID, &period("Backwards", day(10), Some(day(9)))).await.is_err());
+}
+
+/// An employee has at most one pending transfer, listings cover both offices and due transfers span tenants;
+/// transfers go along when their employee or office is deleted
+async fn transfers_are_pending_once_and_follow_deletes(b: Backend) {
+    let from = b.offices.create_office(DEFAULT_TENANT_ID, &office("Herning", 10)).await.unwrap().id.unwrap();
+    let to = b.offices.create_office(DEFAULT_TENANT_ID, &office("Viborg", 10)).await.unwrap().id.unwrap();
+    let spare = b.offices.create_office(DEFAULT_TENANT_ID, &office("Skive", 10)).await.unwrap().id.unwrap();
+    let moving = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Moving", from)).await.unwrap();
+    let staying = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Staying", from)).await.unwrap();
+    let day = |d: u32| chrono::NaiveDate::from_ymd_opt(2030, 6, d).expect("Invalid date");
+
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let planned = b.transfers.create_transfer_tx(&mut tx, DEFAULT_TENANT_ID, &Transfer::new(&moving, to, day(10), "hr")).await.unwrap();
+    let later = b.transfers.create_transfer_tx(&mut tx, DEFAULT_TENANT_ID, &Transfer::new(&staying, spare, day(20), "hr")).await.unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!((planned.status, planned.from_office_id, planned.requested_by.as_str()), (TransferStatus::Pending, from, "hr"));
+
+    // a second pending transfer of the same employee is refused
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert!(b.transfers.create_transfer_tx(&mut tx, DEFAULT_TENANT_ID, &Transfer::new(&moving, spare, day(12), "hr")).await.is_err());
+    tx.rollback().await.unwrap();
+
+    let ids = |transfers: Vec<Transfer>| transfers.into_iter().map(|t| t.id.unwrap()).collect::<Vec<_>>();
+    let (planned_id, later_id) = (planned.id.unwrap(), later.id.unwrap());
+    assert_eq!(ids(b.transfers.get_transfers_by_office_id(DEFAULT_TENANT_ID, from, None).await.unwrap()), vec![planned_id, later_id]);
+    assert_eq!(ids(b.transfers.get_transfers_by_office_id(DEFAULT_TENANT_ID, to, Some(TransferStatus::Pending)).await.unwrap()), vec![planned_id]);
+    assert!(b.transfers.get_transfers_by_office_id(DEFAULT_TENANT_ID, to, Some(TransferStatus::Applied)).await.unwrap().is_empty());
+    assert!(b.transfers.get_transfers_by_office_id(b.other_tenant, from, None).await.unwrap().is_empty());
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert_eq!(ids(b.transfers.get_pending_transfers_by_office_id_tx(&mut tx, DEFAULT_TENANT_ID, to).await.unwrap()), vec![planned_id]);
+    assert_eq!(ids(b.transfers.get_pending_transfers_by_office_id_tx(&mut tx, DEFAULT_TENANT_ID, from).await.unwrap()), vec![planned_id, later_id]);
+    tx.rollback().await.unwrap();
+
+    let due = b.transfers.get_due_transfers(day(15)).await.unwrap();
+    assert_eq!(due.iter().map(|(tenant, t)| (*tenant, t.id.unwrap())).collect::<Vec<_>>(), vec![(DEFAULT_TENANT_ID, planned_id)]);
+
+    // once settled the employee may get a new pending transfer
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let cancelled = Transfer { status: TransferStatus::Cancelled, ..planned.clone() };
+    let updated = b.transfers.update_transfer_tx(&mut tx, DEFAULT_TENANT_ID, planned_id, &cancelled).await.unwrap();
+    assert_eq!(updated.status, TransferStatus::Cancelled);
+    b.transfers.create_transfer_tx(&mut tx, DEFAULT_TENANT_ID, &Transfer::new(&moving, spare, day(12), "hr")).await.unwrap();
+    tx.commit().await.unwrap();
+    assert!(b.transfers.get_due_transfers(day(11)).await.unwrap().is_empty());
+    assert_eq!(b.transfers.get_transfer_by_id(DEFAULT_TENANT_ID, planned_id).await.unwrap().unwrap().status, TransferStatus::Cancelled);
+    assert!(b.transfers.get_transfer_by_id(b.other_tenant, planned_id).await.unwrap().is_none());
+
+    // deleting the destination or the employee deletes their transfers
+    b.offices.delete_office(DEFAULT_TENANT_ID, to).await.unwrap();
+    assert!(b.transfers.get_transfer_by_id(DEFAULT_TENANT_ID, planned_id).await.unwrap().is_none());
+    b.employees.delete_employee(DEFAULT_TENANT_ID, staying.id.unwrap()).await.unwrap();
+    assert!(b.transfers.get_transfer_by_id(DEFAULT_TENANT_ID, later_id).await.unwrap().is_none());
+    assert_eq!(b.transfers.get_transfers_by_office_id(DEFAULT_TENANT_ID, spare, None).await.unwrap().len(), 1);
+}
+
+/// Change requests with their steps in order, each step decided once, comments and statuses
+async fn change_requests_keep_steps_in_order_and_decide_once(b: Backend) {
+    let change = ProposedChange::OfficeUpdate { office_id: 4, office: corp_data_api::dto::office_dto::CreateOfficeRequest { name: "Nord".into(), max_occupancy: 20, ..Default::default() } };
+    let steps = change.approval_steps(4);
+    let expires_at = chrono::Utc::now() + chrono::Duration::days(7);
+
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let created = b.change_requests.create_change_request_tx(&mut tx, DEFAULT_TENANT_ID, &ChangeRequest::new(change, "hr", expires_at), &steps).await.unwrap();
+    let id = created.id.unwrap();
+    b.change_requests.create_comment_tx(&mut tx, DEFAULT_TENANT_ID, &Comment::new(id, "hr", "More desks")).await.unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!((created.status, created.requested_by.as_str()), (ChangeRequestStatus::Pending, "hr"));
+    assert!(matches!(created.change.0, ProposedChange::OfficeUpdate { office_id: 4, .. }));
+
+    let approvals = b.change_requests.get_approvals(DEFAULT_TENANT_ID, id).await.unwrap();
+    assert_eq!(approvals.iter().map(|a| a.approval_step().unwrap()).collect::<Vec<_>>(), vec![ApprovalStep::Facilities, ApprovalStep::OfficeManager { office_id: 4 }]);
+    assert!(approvals.iter().all(|a| a.decision.is_none() && a.decided_by.is_none()));
+
+    // a step is decided once
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert_eq!(b.change_requests.decide_approval_tx(&mut tx, DEFAULT_TENANT_ID, id, "facilities", Decision::Approved, "fac").await.unwrap(), 1);
+    assert_eq!(b.change_requests.decide_approval_tx(&mut tx, DEFAULT_TENANT_ID, id, "facilities", Decision::Rejected, "other").await.unwrap(), 0);
+    assert_eq!(b.change_requests.decide_approval_tx(&mut tx, b.other_tenant, id, "office_manager:4", Decision::Approved, "boss").await.unwrap(), 0);
+    b.change_requests.create_comment_tx(&mut tx, DEFAULT_TENANT_ID, &Comment::new(id, "fac", "Fine by me")).await.unwrap();
+    let decided = b.change_requests.get_approvals_tx(&mut tx, DEFAULT_TENANT_ID, id).await.unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!((decided[0].decision, decided[0].decided_by.as_deref()), (Some(Decision::Approved), Some("fac")));
+    assert!(decided[0].decided_at.is_some());
+    assert!(decided[1].decision.is_none());
+    let comments = b.change_requests.get_comments(DEFAULT_TENANT_ID, id).await.unwrap();
+    assert_eq!(comments.iter().map(|c| (c.author.as_str(), c.body.as_str())).collect::<Vec<_>>(), vec![("hr", "More desks"), ("fac", "Fine by me")]);
+
+    // status changes, expired is never stored
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let rejected = ChangeRequest { status: ChangeRequestStatus::Rejected, resolved_at: Some(chrono::Utc::now()), ..created.clone() };
+    let updated = b.change_requests.update_change_request_tx(&mut tx, DEFAULT_TENANT_ID, id, &rejected).await.unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!(updated.status, ChangeRequestStatus::Rejected);
+    assert!(updated.resolved_at.is_some());
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let expired = ChangeRequest { status: ChangeRequestStatus::Expired, ..created.clone() };
+    assert!(b.change_requests.update_change_request_tx(&mut tx, DEFAULT_TENANT_ID, id, &expired).await.is_err());
+    tx.rollback().await.unwrap();
+
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let second = b.change_requests.create_change_request_tx(&mut tx, DEFAULT_TENANT_ID, &ChangeRequest::new(created.change.0.clone(), "hr", expires_at), &steps).await.unwrap();
+    tx.commit().await.unwrap();
+    let ids = |requests: Vec<ChangeRequest>| requests.into_iter().map(|r| r.id.unwrap()).collect::<Vec<_>>();
+    assert_eq!(ids(b.change_requests.get_change_requests(DEFAULT_TENANT_ID, None).await.unwrap()), vec![id, second.id.unwrap()]);
+    assert_eq!(ids(b.change_requests.get_change_requests(DEFAULT_TENANT_ID, Some(ChangeRequestStatus::Pending)).await.unwrap()), vec![second.id.unwrap()]);
+    assert!(b.change_requests.get_change_requests(b.other_tenant, None).await.unwrap().is_empty());
+    assert!(b.change_requests.get_change_request_by_id(b.other_tenant, id).await.unwrap().is_none());
+    assert!(b.change_requests.get_approvals(b.other_tenant, id).await.unwrap().is_empty());
+    assert_eq!(b.change_requests.get_change_request_by_id(DEFAULT_TENANT_ID, id).await.unwrap().unwrap().status, ChangeRequestStatus::Rejected);
+}
+
+fn waitlist_entry(last_name: &str, office_id: i32) -> WaitlistEntry {
+    let req = WaitlistRequest {
+        first_name: "Test".into(),
+        last_name: last_name.into(),
+        birth_date: chrono::NaiveDate::from_ymd_opt(1985, 1, 1).expect("Invalid date"),
+        department_id: None,
+        manager_id: None,
+        hire_date: None,
+        confirm: false,
+    };
+    WaitlistEntry::from_request(office_id, req, "hr")
+}
+
+async fn waitlists_keep_arrival_order_and_follow_office(b: Backend) {
+    let full = b.offices.create_office(DEFAULT_TENANT_ID, &office("Ringsted", 1)).await.unwrap().id.unwrap();
+    let other = b.offices.create_office(DEFAULT_TENANT_ID, &office("Slagelse", 1)).await.unwrap().id.unwrap();
+
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let first = b.waitlist.create_entry_tx(&mut tx, DEFAULT_TENANT_ID, &waitlist_entry("First", full)).await.unwrap();
+    let second = b.waitlist.create_entry_tx(&mut tx, DEFAULT_TENANT_ID, &WaitlistEntry { confirm: true, ..waitlist_entry("Second", full) }).await.unwrap();
+    let elsewhere = b.waitlist.create_entry_tx(&mut tx, DEFAULT_TENANT_ID, &waitlist_entry("Elsewhere", other)).await.unwrap();
+    let (first_id, second_id) = (first.id.unwrap(), second.id.unwrap());
+    b.waitlist.create_event_tx(&mut tx, DEFAULT_TENANT_ID, &WaitlistEvent::new(first_id, WaitlistEventKind::Enqueued, Some("hr".into()))).await.unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!((first.status, first.employee_id, first.requested_by.as_str()), (WaitlistStatus::Waiting, None, "hr"));
+    assert!(second.confirm);
+
+    // entries need an office of their own tenant, events an entry
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert!(b.waitlist.create_entry_tx(&mut tx, b.other_tenant, &waitlist_entry("Stranger", full)).await.is_err());
+    tx.rollback().await.unwrap();
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert!(b.waitlist.create_event_tx(&mut tx, b.other_tenant, &WaitlistEvent::new(first_id, WaitlistEventKind::Cancelled, None)).await.is_err());
+    tx.rollback().await.unwrap();
+
+    let ids = |entries: Vec<WaitlistEntry>| entries.into_iter().map(|e| e.id.unwrap()).collect::<Vec<_>>();
+    assert_eq!(ids(b.waitlist.get_entries_by_office_id(DEFAULT_TENANT_ID, full, None).await.unwrap()), vec![first_id, second_id]);
+    assert!(b.waitlist.get_entries_by_office_id(b.other_tenant, full, None).await.unwrap().is_empty());
+    assert_eq!(b.waitlist.get_waiting_office_ids().await.unwrap(), vec![(DEFAULT_TENANT_ID, full), (DEFAULT_TENANT_ID, other)]);
+
+    // placing the first entry leaves the second to wait
+    let seated = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("First", full)).await.unwrap();
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let placed = WaitlistEntry { status: WaitlistStatus::Placed, employee_id: seated.id, ..first.clone() };
+    let updated = b.waitlist.update_entry_tx(&mut tx, DEFAULT_TENANT_ID, first_id, &placed).await.unwrap();
+    b.waitlist.create_event_tx(&mut tx, DEFAULT_TENANT_ID, &WaitlistEvent::new(first_id, WaitlistEventKind::Placed, None)).await.unwrap();
+    assert_eq!(ids(b.waitlist.get_entries_by_office_id_tx(&mut tx, DEFAULT_TENANT_ID, full).await.unwrap()), vec![first_id, second_id]);
+    tx.commit().await.unwrap();
+    assert_eq!((updated.status, updated.employee_id, updated.last_name.as_str()), (WaitlistStatus::Placed, seated.id, "First"));
+    assert_eq!(ids(b.waitlist.get_entries_by_office_id(DEFAULT_TENANT_ID, full, Some(WaitlistStatus::Waiting)).await.unwrap()), vec![second_id]);
+    let events = b.waitlist.get_events(DEFAULT_TENANT_ID, first_id).await.unwrap();
+    assert_eq!(events.iter().map(|e| e.event).collect::<Vec<_>>(), vec![WaitlistEventKind::Enqueued, WaitlistEventKind::Placed]);
+    assert_eq!(events[0].details.as_deref(), Some("hr"));
+    assert!(b.waitlist.get_events(b.other_tenant, first_id).await.unwrap().is_empty());
+    assert!(b.waitlist.get_entry_by_id(b.other_tenant, first_id).await.unwrap().is_none());
+
+    // deleting the office deletes its waitlist with the events
+    b.employees.delete_employee(DEFAULT_TENANT_ID, seated.id.unwrap()).await.unwrap();
+    b.offices.delete_office(DEFAULT_TENANT_ID, full).await.unwrap();
+    assert!(b.waitlist.get_entry_by_id(DEFAULT_TENANT_ID, first_id).await.unwrap().is_none());
+    assert!(b.waitlist.get_events(DEFAULT_TENANT_ID, first_id).await.unwrap().is_empty());
+    assert_eq!(b.waitlist.get_waiting_office_ids().await.unwrap(), vec![(DEFAULT_TENANT_ID, other)]);
+    assert_eq!(b.waitlist.get_entry_by_id(DEFAULT_TENANT_ID, elsewhere.id.unwrap()).await.unwrap().unwrap().status, WaitlistStatus::Waiting);
+}
+
+/// Location fields round trip, coordinates are range checked and paired, nearby offices come nearest first within the radius
+async fn offices_near_are_ordered_by_distance(b: Backend) {
+    let located = |name: &str, latitude: f64, longitude: f64| Office {
+        country: Some("DK".into()),
+        timezone: Some("Europe/Copenhagen".into()),
+        latitude: Some(latitude),
+        longitude: Some(longitude),
+        ..office(name, 5)
+    };
+    let aarhus = b.offices.create_office(DEFAULT_TENANT_ID, &located("Aarhus", 56.1629, 10.2039)).await.unwrap();
+    assert_eq!((aarhus.country.as_deref(), aarhus.latitude), (Some("DK"), Some(56.1629)));
+    let aalborg = b.offices.create_office(DEFAULT_TENANT_ID, &Office { city: Some("Aalborg".into()), ..located("Aalborg", 57.048, 9.9187) }).await.unwrap();
+    b.offices.create_office(DEFAULT_TENANT_ID, &located("København", 55.6761, 12.5683)).await.unwrap();
+    b.offices.create_office(DEFAULT_TENANT_ID, &office("Uden adresse", 5)).await.unwrap();
+    b.offices.create_office(b.other_tenant, &located("Aalborg", 57.048, 9.9187)).await.unwrap();
+
+    assert!(b.offices.create_office(DEFAULT_TENANT_ID, &located("Nordpolen", 91.0, 0.0)).await.is_err());
+    assert!(b.offices.create_office(DEFAULT_TENANT_ID, &Office { longitude: None, ..located("Halv", 56.0, 10.0) }).await.is_err());
+    assert!(b.offices.create_office(DEFAULT_TENANT_ID, &Office { country: Some("Danmark".into()), ..office("Land", 5) }).await.is_err());
+
+    // from Randers, Aarhus is about 35 km away, Aalborg about 66 km and København far outside the radius
+    let area = SearchArea { latitude: 56.4607, longitude: 10.0364, radius_km: 100.0 };
+    let near = b.offices.get_offices_near(DEFAULT_TENANT_ID, &area).await.unwrap();
+    let found: Vec<_> = near.iter().map(|(o, _)| o.id).collect();
+    assert_eq!(found, vec![aarhus.id, aalborg.id]);
+    assert!((near[0].1 - 34.7).abs() < 1.0, "{}", near[0].1);
+    assert!((near[1].1 - 65.7).abs() < 1.0, "{}", near[1].1);
+    assert_eq!(near[1].0.city.as_deref(), Some("Aalborg"));
+
+    let area = SearchArea { radius_km: 30.0, ..area };
+    assert!(b.offices.get_offices_near(DEFAULT_TENANT_ID, &area).await.unwrap().is_empty());
+}
+
+fn location(kind: LocationKind, name: &str, parent_id: Option<i32>, capacity: Option<i32>, office_id: Option<i32>) -> Location {
+    Location { id: None, parent_id, kind, name: name.into(), capacity, office_id }
+}
+
+/// Sites read the office's max_occupancy as capacity, employees count in their room while they stay in its office
+/// and in the site of their office otherwise, locations go together with their office
+async fn locations_read_site_capacity_and_follow_office(b: Backend) {
+    let ringsted = b.offices.create_office(DEFAULT_TENANT_ID, &office("Ringsted", 5)).await.unwrap().id.unwrap();
+    let slagelse = b.offices.create_office(DEFAULT_TENANT_ID, &office("Slagelse", 5)).await.unwrap().id.unwrap();
+
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let region = b.locations.create_location_tx(&mut tx, DEFAULT_TENANT_ID, &location(LocationKind::Region, "Sjælland", None, Some(20), None)).await.unwrap();
+    let site = b.locations.create_location_tx(&mut tx, DEFAULT_TENANT_ID, &location(LocationKind::Site, "Ringsted", None, None, Some(ringsted))).await.unwrap();
+    let site_id = site.id.unwrap();
+    let building = b.locations.create_location_tx(&mut tx, DEFAULT_TENANT_ID, &location(LocationKind::Building, "A", Some(site_id), None, Some(ringsted))).await.unwrap();
+    let floor = b.locations.create_location_tx(&mut tx, DEFAULT_TENANT_ID, &location(LocationKind::Floor, "1", building.id, None, Some(ringsted))).await.unwrap();
+    let room = b.locations.create_location_tx(&mut tx, DEFAULT_TENANT_ID, &location(LocationKind::Room, "1.01", floor.id, Some(2), Some(ringsted))).await.unwrap();
+    tx.commit().await.unwrap();
+    let room_id = room.id.unwrap();
+    assert_eq!((site.kind, site.capacity, site.office_id), (LocationKind::Site, Some(5), Some(ringsted)));
+    assert_eq!((room.parent_id, room.capacity), (floor.id, Some(2)));
+    assert_eq!(region.office_id, None);
+
+    // one site per office, no own capacity on a site, parents and offices of the own tenant
+    for broken in [
+        location(LocationKind::Site, "Again", None, None, Some(ringsted)),
+        location(LocationKind::Site, "Sized", None, Some(3), Some(slagelse)),
+        location(LocationKind::Room, "Empty", floor.id, Some(0), Some(ringsted)),
+        location(LocationKind::Building, "Officeless", Some(site_id), None, None),
+    ] {
+        let mut tx = b.unit_of_work.begin().await.unwrap();
+        assert!(b.locations.create_location_tx(&mut tx, DEFAULT_TENANT_ID, &broken).await.is_err(), "{}", broken.name);
+        tx.rollback().await.unwrap();
+    }
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert!(b.locations.create_location_tx(&mut tx, b.other_tenant, &location(LocationKind::Region, "Fremmed", region.id, None, None)).await.is_err());
+    tx.rollback().await.unwrap();
+
+    // a site keeps reading the office's capacity and moves into the region
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let moved = b.locations.update_location_tx(&mut tx, DEFAULT_TENANT_ID, site_id, &Location { parent_id: region.id, name: "Ringsted st.".into(), ..site.clone() }).await.unwrap();
+    assert!(b.locations.delete_location_tx(&mut tx, DEFAULT_TENANT_ID, floor.id.unwrap()).await.is_err());
+    tx.rollback().await.unwrap();
+    assert_eq!((moved.parent_id, moved.name.as_str(), moved.capacity), (region.id, "Ringsted st.", Some(5)));
+    let ids: Vec<_> = b.locations.get_locations(DEFAULT_TENANT_ID).await.unwrap().into_iter().map(|l| l.id).collect();
+    assert_eq!(ids, vec![region.id, site.id, building.id, floor.id, room.id]);
+    assert!(b.locations.get_locations(b.other_tenant).await.unwrap().is_empty());
+
+    // employees sit in the site until they get a room, terminated ones no longer count
+    let today = chrono::Utc::now().date_naive();
+    let first = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("First", ringsted)).await.unwrap();
+    let second = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Second", ringsted)).await.unwrap();
+    let left = Employee {
+        termination_date: chrono::NaiveDate::from_ymd_opt(2021, 1, 1),
+        status: EmploymentStatus::Terminated,
+        ..employee("Left", ringsted)
+    };
+    let left = b.employees.create_employee(DEFAULT_TENANT_ID, &left).await.unwrap();
+    let occupants = |mut occupants: Vec<(i32, i64)>| {
+        occupants.sort_unstable();
+        occupants
+    };
+    assert_eq!(occupants(b.locations.get_occupants(DEFAULT_TENANT_ID, today).await.unwrap()), vec![(site_id, 2)]);
+
+    let (first_id, second_id) = (first.id.unwrap(), second.id.unwrap());
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    b.locations.assign_employee_tx(&mut tx, DEFAULT_TENANT_ID, first_id, room_id).await.unwrap();
+    b.locations.assign_employee_tx(&mut tx, DEFAULT_TENANT_ID, left.id.unwrap(), room_id).await.unwrap();
+    assert!(b.locations.assign_employee_tx(&mut tx, b.other_tenant, second_id, room_id).await.is_err());
+    tx.rollback().await.unwrap();
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    b.locations.assign_employee_tx(&mut tx, DEFAULT_TENANT_ID, first_id, room_id).await.unwrap();
+    b.locations.assign_employee_tx(&mut tx, DEFAULT_TENANT_ID, second_id, room_id).await.unwrap();
+    assert_eq!(b.locations.get_room_id_tx(&mut tx, DEFAULT_TENANT_ID, first_id).await.unwrap(), Some(room_id));
+    assert_eq!(b.locations.unassign_employee_tx(&mut tx, DEFAULT_TENANT_ID, second_id, site_id).await.unwrap(), 0);
+    assert_eq!(b.locations.unassign_employee_tx(&mut tx, DEFAULT_TENANT_ID, second_id, room_id).await.unwrap(), 1);
+    tx.commit().await.unwrap();
+    assert_eq!(occupants(b.locations.get_occupants(DEFAULT_TENANT_ID, today).await.unwrap()), vec![(site_id, 1), (room_id, 1)]);
+
+    // the room stops counting once the employee moves to another office
+    b.employees.update_employee_by_id(DEFAULT_TENANT_ID, first_id, &Employee { office_id: slagelse, ..first.clone() }).await.unwrap();
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert_eq!(b.locations.get_room_id_tx(&mut tx, DEFAULT_TENANT_ID, first_id).await.unwrap(), None);
+    tx.rollback().await.unwrap();
+    assert_eq!(occupants(b.locations.get_occupants(DEFAULT_TENANT_ID, today).await.unwrap()), vec![(site_id, 1)]);
+
+    // deleting the office deletes its site with everything below, the region stays
+    for id in [first_id, second_id, left.id.unwrap()] {
+        b.employees.delete_employee(DEFAULT_TENANT_ID, id).await.unwrap();
+    }
+    b.offices.delete_office(DEFAULT_TENANT_ID, ringsted).await.unwrap();
+    let ids: Vec<_> = b.locations.get_locations(DEFAULT_TENANT_ID).await.unwrap().into_iter().map(|l| l.id).collect();
+    assert_eq!(ids, vec![region.id]);
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    assert_eq!(b.locations.delete_location_tx(&mut tx, DEFAULT_TENANT_ID, region.id.unwrap()).await.unwrap(), 1);
+    assert_eq!(b.locations.delete_location_tx(&mut tx, DEFAULT_TENANT_ID, region.id.unwrap()).await.unwrap(), 0);
+    tx.commit().await.unwrap();
+}
+
+/// Merging gives the source's room to a survivor without one that sits in the room's office after the merge,
+/// also when the survivor takes over the source's office, otherwise the room is freed
+async fn merge_hands_room_to_survivor(b: Backend) {
+    let holbaek = b.offices.create_office(DEFAULT_TENANT_ID, &office("Holbæk", 10)).await.unwrap().id.unwrap();
+    let kalundborg = b.offices.create_office(DEFAULT_TENANT_ID, &office("Kalundborg", 10)).await.unwrap().id.unwrap();
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let site = b.locations.create_location_tx(&mut tx, DEFAULT_TENANT_ID, &location(LocationKind::Site, "Holbæk", None, None, Some(holbaek))).await.unwrap();
+    let building = b.locations.create_location_tx(&mut tx, DEFAULT_TENANT_ID, &location(LocationKind::Building, "A", site.id, None, Some(holbaek))).await.unwrap();
+    let floor = b.locations.create_location_tx(&mut tx, DEFAULT_TENANT_ID, &location(LocationKind::Floor, "1", building.id, None, Some(holbaek))).await.unwrap();
+    let room_id = b.locations.create_location_tx(&mut tx, DEFAULT_TENANT_ID, &location(LocationKind::Room, "1.01", floor.id, Some(4), Some(holbaek))).await.unwrap().id.unwrap();
+    tx.commit().await.unwrap();
+
+    let id = |e: Employee| e.id.unwrap();
+    let keep = id(b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Keep", holbaek)).await.unwrap());
+    let gone = id(b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Gone", holbaek)).await.unwrap());
+    let elsewhere = id(b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Elsewhere", kalundborg)).await.unwrap());
+    let also_gone = id(b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Also Gone", holbaek)).await.unwrap());
+    let mover = id(b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Mover", kalundborg)).await.unwrap());
+    let taken_over = id(b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Taken Over", holbaek)).await.unwrap());
+
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    b.locations.assign_employee_tx(&mut tx, DEFAULT_TENANT_ID, gone, room_id).await.unwrap();
+    b.locations.assign_employee_tx(&mut tx, DEFAULT_TENANT_ID, also_gone, room_id).await.unwrap();
+    b.locations.assign_employee_tx(&mut tx, DEFAULT_TENANT_ID, taken_over, room_id).await.unwrap();
+    assert_eq!(b.employees.merge_employee_tx(&mut tx, DEFAULT_TENANT_ID, gone, keep, holbaek).await.unwrap(), 1);
+    assert_eq!(b.employees.merge_employee_tx(&mut tx, DEFAULT_TENANT_ID, also_gone, elsewhere, kalundborg).await.unwrap(), 1);
+    // the mover sits in Kalundborg until the merged survivor is written with Holbæk
+    assert_eq!(b.employees.merge_employee_tx(&mut tx, DEFAULT_TENANT_ID, taken_over, mover, holbaek).await.unwrap(), 1);
+    assert_eq!(b.locations.get_room_id_tx(&mut tx, DEFAULT_TENANT_ID, keep).await.unwrap(), Some(room_id));
+    assert_eq!(b.locations.get_room_id_tx(&mut tx, DEFAULT_TENANT_ID, elsewhere).await.unwrap(), None);
+    b.employees.update_employee_by_id_tx(&mut tx, DEFAULT_TENANT_ID, mover, &employee("Mover", holbaek)).await.unwrap();
+    assert_eq!(b.locations.get_room_id_tx(&mut tx, DEFAULT_TENANT_ID, mover).await.unwrap(), Some(room_id));
+    tx.commit().await.unwrap();
+    let today = chrono::Utc::now().date_naive();
+    let room = b.locations.get_occupants(DEFAULT_TENANT_ID, today).await.unwrap().into_iter().find(|(id, _)| *id == room_id);
+    assert_eq!(room, Some((room_id, 2)));
+}
+
+/// Merging points the waitlist entries placed as the source or managed by it at the survivor
+async fn merge_repoints_waitlist_entries(b: Backend) {
+    let own = b.offices.create_office(DEFAULT_TENANT_ID, &office("Nykøbing", 1)).await.unwrap().id.unwrap();
+    let keep = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Keep", own)).await.unwrap().id.unwrap();
+    let gone = b.employees.create_employee(DEFAULT_TENANT_ID, &employee("Gone", own)).await.unwrap().id.unwrap();
+
+    let mut tx = b.unit_of_work.begin().await.unwrap();
+    let placed = b.waitlist.create_entry_tx(&mut tx, DEFAULT_TENANT_ID, &waitlist_entry("Gone", own)).await.unwrap();
+    let placed = WaitlistEntry { status: WaitlistStatus::Placed, employee_id: Some(gone), ..placed };
+    let placed = b.waitlist.update_entry_tx(&mut tx, DEFAULT_TENANT_ID, placed.id.unwrap(), &placed).await.unwrap().id.unwrap();
+    let managed = b.waitlist.create_entry_tx(&mut tx, DEFAULT_TENANT_ID, &WaitlistEntry { manager_id: Some(gone), ..waitlist_entry("Report", own) }).await.unwrap().id.unwrap();
+    assert_eq!(b.employees.merge_employee_tx(&mut tx, DEFAULT_TENANT_ID, gone, keep, own).await.unwrap(), 1);
+    tx.commit().await.unwrap();
+
+    assert_eq!(b.waitlist.get_entry_by_id(DEFAULT_TENANT_ID, placed).await.unwrap().unwrap().employee_id, Some(keep));
+    assert_eq!(b.waitlist.get_entry_by_id(DEFAULT_TENANT_ID, managed).await.unwrap().unwrap().manager_id, Some(keep));
+}
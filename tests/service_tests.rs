@@ -1,69 +1,827 @@
 mod utils;
-use utils::clean_db;
-use serial_test::serial;
+use utils::{backends, principal, TestDatabase};
+use futures::StreamExt;
 
-use corp_data_api::entity::{office::Office, employee::Employee};
-use corp_data_api::repository::{office_repository::OfficeRepository, employee_repository::EmployeeRepository};
-use corp_data_api::config::db_settings::Settings;
+use corp_data_api::entity::{office::Office, employee::{Employee, EmploymentStatus}};
+use corp_data_api::repository::{office_repository::OfficeStore, employee_repository::EmployeeStore, office_manager_repository::OfficeManagerStore};
+use corp_data_api::auth::{permission::Role, scope::OfficeScope};
+use corp_data_api::entity::tenant::DEFAULT_TENANT_ID;
 use corp_data_api::service::employee_service::EmployeeService;
+use corp_data_api::service::office_service::OfficeService;
+use corp_data_api::dto::import_dto::{ImportEmployeeRow, ImportMode, ImportRowStatus};
+use corp_data_api::entity::validation_rules::{DuplicatePolicy, RuleOverride, ValidationRules};
+use corp_data_api::utils::i18n::Language;
+use corp_data_api::dto::merge_dto::{MergeEmployeeRequest, MergeFields, MergeSide};
+use corp_data_api::repository::audit_repository::AuditStore;
+use corp_data_api::service::transfer_service::{TransferRun, TransferService};
+use corp_data_api::entity::transfer::TransferStatus;
+use corp_data_api::utils::ValidationErrors;
+use corp_data_api::service::change_request_service::ChangeRequestService;
+use corp_data_api::entity::change_request::{ChangeRequestStatus, Decision, ProposedChange};
+use corp_data_api::dto::office_dto::CreateOfficeRequest;
+use corp_data_api::dto::employee_dto::CreateEmployeeRequest;
+use corp_data_api::service::waitlist_service::{WaitlistRun, WaitlistService};
+use corp_data_api::entity::waitlist::{WaitlistEventKind, WaitlistStatus};
+use corp_data_api::dto::waitlist_dto::WaitlistRequest;
+use corp_data_api::service::location_service::LocationService;
+use corp_data_api::entity::location::LocationKind;
+use corp_data_api::dto::location_dto::{CreateLocationRequest, UpdateLocationRequest};
+use std::sync::Arc;
 
-/// Tests service layer
-/// Should cover everything in service layer but only some basics are tested
+// Tests service layer
+// Should cover everything in service layer but only some basics are tested
+// Runs on the in-memory backend and, with `--features sqlite`, on SQLite, so no Postgres is needed and tests run in parallel
 
+backends!(
+    employee_office_max_occ_test,
+    list_employees_by_office_service_test,
+    office_manager_scope_service_test,
+    import_employees_service_test,
+    export_employees_service_test,
+    office_reassign_and_shrink_service_test,
+    validation_rule_overrides_service_test,
+    duplicate_detection_service_test,
+    merge_employees_service_test,
+    manager_hierarchy_service_test,
+    office_diagram_service_test,
+    employment_lifecycle_service_test,
+    transfer_projection_and_job_service_test,
+    change_request_approval_service_test,
+    waitlist_fills_freed_seats_in_order_service_test,
+    location_capacity_checked_at_every_level_service_test,
+);
 
-/// Add two users to office of space 1, expects error for second entry
-#[tokio::test]
-#[serial]
-async fn employee_office_max_occ_test() {
-    dotenv::from_filename(".env.test").ok();
-    let pool = Settings::connect_from_env().unwrap().create_pool().await.unwrap();
-    clean_db(&pool).await;
 
-    let office_repo = OfficeRepository::new(pool.clone());
-    let employee_repo = EmployeeRepository::new(pool.clone());
-    let service = EmployeeService::new(employee_repo.clone(), office_repo.clone());
+/// Add two users to office of space 1, expects error for second entry
+async fn employee_office_max_occ_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let service = EmployeeService::new(employee_repo.clone(), office_repo.clone(), db.office_manager_repository(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work());
+    let hr = principal("hr", &[Role::HrEditor]);
 
-    let office = Office { id: None, name: "Vester Hassing".into(), max_occupancy: 1 };
-    let office_created = office_repo.create_office(&office).await.unwrap();
+    let office = Office { id: None, name: "Vester Hassing".into(), max_occupancy: 1, ..Default::default() };
+    let office_created = office_repo.create_office(DEFAULT_TENANT_ID, &office).await.unwrap();
 
-    let emp1 = Employee { id: None, first_name: "Kristoffer".into(), last_name: "Første".into(), birth_date: chrono::NaiveDate::from_ymd_opt(1950, 1, 1).expect("Invalid date"), office_id: office_created.id.unwrap() };
-    service.add_employee(&emp1).await.unwrap();
+    let emp1 = Employee { id: None, first_name: "Kristoffer".into(), last_name: "Første".into(), birth_date: chrono::NaiveDate::from_ymd_opt(1950, 1, 1).expect("Invalid date"), office_id: office_created.id.unwrap(), department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active };
+    service.add_employee(&hr, &emp1).await.unwrap();
 
-    let emp2 = Employee { id: None, first_name: "Kristoffer".into(), last_name: "Anden".into(), birth_date: chrono::NaiveDate::from_ymd_opt(1950, 12, 23).expect("Invalid date"), office_id: office_created.id.unwrap() };
-    let res = service.add_employee(&emp2).await;
+    let emp2 = Employee { id: None, first_name: "Kristoffer".into(), last_name: "Anden".into(), birth_date: chrono::NaiveDate::from_ymd_opt(1950, 12, 23).expect("Invalid date"), office_id: office_created.id.unwrap(), department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active };
+    let res = service.add_employee(&hr, &emp2).await;
     assert!(res.is_err());
-
-    clean_db(&pool).await;
 }
 
 /// List employees by office id, expects 2 employees for created office, error for non existent
-#[tokio::test]
-#[serial]
-async fn list_employees_by_office_service_test() {
-    dotenv::from_filename(".env.test").ok();
-    let pool = Settings::connect_from_env().unwrap().create_pool().await.unwrap();
-    clean_db(&pool).await;
-
-    let office_repo = OfficeRepository::new(pool.clone());
-    let employee_repo = EmployeeRepository::new(pool.clone());
-    let service = EmployeeService::new(employee_repo.clone(), office_repo.clone());
+async fn list_employees_by_office_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let service = EmployeeService::new(employee_repo.clone(), office_repo.clone(), db.office_manager_repository(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work());
+    let hr = principal("hr", &[Role::HrEditor]);
 
-    let office = Office { id: None, name: "TestOffice".into(), max_occupancy: 5 };
-    let office_created = office_repo.create_office(&office).await.unwrap();
+    let office = Office { id: None, name: "TestOffice".into(), max_occupancy: 5, ..Default::default() };
+    let office_created = office_repo.create_office(DEFAULT_TENANT_ID, &office).await.unwrap();
 
-    let emp1 = Employee { id: None, first_name: "Kristoffer".into(), last_name: "Første".into(), birth_date: chrono::NaiveDate::from_ymd_opt(1950, 1, 1).expect("Invalid date"), office_id: office_created.id.unwrap() };
-    let emp2 = Employee { id: None, first_name: "Kristoffer2".into(), last_name: "Anden".into(), birth_date: chrono::NaiveDate::from_ymd_opt(1950, 12, 23).expect("Invalid date"), office_id: office_created.id.unwrap() };
+    let emp1 = Employee { id: None, first_name: "Kristoffer".into(), last_name: "Første".into(), birth_date: chrono::NaiveDate::from_ymd_opt(1950, 1, 1).expect("Invalid date"), office_id: office_created.id.unwrap(), department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active };
+    let emp2 = Employee { id: None, first_name: "Kristoffer2".into(), last_name: "Anden".into(), birth_date: chrono::NaiveDate::from_ymd_opt(1950, 12, 23).expect("Invalid date"), office_id: office_created.id.unwrap(), department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active };
 
-    service.add_employee(&emp1).await.unwrap();
-    service.add_employee(&emp2).await.unwrap();
+    service.add_employee(&hr, &emp1).await.unwrap();
+    service.add_employee(&hr, &emp2).await.unwrap();
 
-    let employees = service.list_employees_by_office_id(office_created.id.unwrap()).await.unwrap();
+    let employees = service.list_employees_by_office_id(&hr, office_created.id.unwrap()).await.unwrap();
     assert_eq!(employees.len(), 2);
     assert!(employees.iter().any(|e| e.first_name == "Kristoffer"));
     assert!(employees.iter().any(|e| e.first_name == "Kristoffer2"));
 
-    let result = service.list_employees_by_office_id(999333).await;
+    let result = service.list_employees_by_office_id(&hr, 999333).await;
     assert!(result.is_err());
+}
+
+/// Office manager of one office only sees and edits that office's employees
+/// Expects other offices' employees to look non-existent and a move out of scope to fail
+async fn office_manager_scope_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let manager_repo = db.office_manager_repository();
+    let service = EmployeeService::new(employee_repo.clone(), office_repo.clone(), manager_repo.clone(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work());
+    let hr = principal("hr", &[Role::HrEditor]);
+    let manager = principal("manager", &[Role::OfficeManager]);
+
+    let managed = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Aalborg".into(), max_occupancy: 5, ..Default::default() }).await.unwrap();
+    let other = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Aarhus".into(), max_occupancy: 5, ..Default::default() }).await.unwrap();
+    manager_repo.add_office_manager(DEFAULT_TENANT_ID, "manager", managed.id.unwrap()).await.unwrap();
+
+    let birth_date = chrono::NaiveDate::from_ymd_opt(1980, 1, 1).expect("Invalid date");
+    let mine = service.add_employee(&hr, &Employee { id: None, first_name: "Mads".into(), last_name: "Mine".into(), birth_date, office_id: managed.id.unwrap(), department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active }).await.unwrap();
+    let theirs = service.add_employee(&hr, &Employee { id: None, first_name: "Tine".into(), last_name: "Theirs".into(), birth_date, office_id: other.id.unwrap(), department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active }).await.unwrap();
+
+    let visible = service.list_all_employees(&manager).await.unwrap();
+    assert_eq!(visible.len(), 1);
+    assert_eq!(visible[0].id, mine.id);
+    assert!(service.find_employee_by_id(&manager, theirs.id.unwrap()).await.unwrap().is_none());
+    assert!(service.list_employees_by_office_id(&manager, other.id.unwrap()).await.is_err());
+
+    let mut moved = mine.clone();
+    moved.office_id = other.id.unwrap();
+    let res = service.update_employee(&manager, mine.id.unwrap(), &moved, None).await;
+    assert!(res.unwrap_err().to_string().contains("does not exist"));
+    let err = service.update_employee(&hr, mine.id.unwrap(), &moved, None).await.unwrap_err();
+    assert_eq!(err.to_string(), format!("Moving employee with ID {} to another office needs approval, submit a change request", mine.id.unwrap()));
+
+    assert!(!service.remove_employee(&manager, theirs.id.unwrap()).await.unwrap());
+    assert!(service.find_employee_by_id(&hr, theirs.id.unwrap()).await.unwrap().is_some());
+}
+
+/// Import of 4 rows into an office with 2 free seats: one invalid row, one over capacity
+/// Expects atomic and dry runs to write nothing and partial mode to write the 2 valid rows
+async fn import_employees_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let service = EmployeeService::new(employee_repo.clone(), office_repo.clone(), db.office_manager_repository(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work());
+    let hr = principal("hr", &[Role::HrEditor]);
+
+    let office = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Aalborg".into(), max_occupancy: 2, ..Default::default() }).await.unwrap();
+    let csv = format!(
+        "first_name,last_name,birth_date,office_id,office_name\n\
+         Anders,Andersen,1980-01-01,{id},\n\
+         Bente,,1980-01-01,{id},\n\
+         Carl,Carlsen,1980-01-01,,Aalborg\n\
+         Dorte,Dortesen,1980-01-01,{id},\n",
+        id = office.id.unwrap()
+    );
+
+    let report = service.import_employees(&hr, ImportEmployeeRow::parse_csv(csv.as_bytes()), ImportMode::Atomic, false, Language::English).await.unwrap();
+    assert_eq!((report.imported, report.failed), (0, 2));
+    assert_eq!(report.rows[0].status, ImportRowStatus::Valid);
+    assert_eq!(report.rows[1].status, ImportRowStatus::Failed);
+    assert_eq!(report.rows[3].status, ImportRowStatus::Failed);
+    assert!(report.rows[3].errors[0].contains("full capacity"));
+
+    let report = service.import_employees(&hr, ImportEmployeeRow::parse_csv(csv.as_bytes()), ImportMode::Partial, true, Language::Danish).await.unwrap();
+    assert_eq!((report.imported, report.failed), (0, 2));
+    assert_eq!(report.rows[1].errors, vec!["Efternavn skal udfyldes"]);
+    assert!(employee_repo.get_all_employees(DEFAULT_TENANT_ID).await.unwrap().is_empty());
+
+    let report = service.import_employees(&hr, ImportEmployeeRow::parse_csv(csv.as_bytes()), ImportMode::Partial, false, Language::English).await.unwrap();
+    assert_eq!((report.imported, report.failed), (2, 2));
+    assert_eq!(report.rows[2].status, ImportRowStatus::Imported);
+    assert_eq!(employee_repo.get_all_employees(DEFAULT_TENANT_ID).await.unwrap().len(), 2);
+
+    // rows keep their hire date, a future hire only needs the seat from its start
+    let today = chrono::Utc::now().date_naive();
+    let small = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Skagen".into(), max_occupancy: 1, ..Default::default() }).await.unwrap().id.unwrap();
+    let leaving = Employee {
+        id: None, first_name: "Lone".into(), last_name: "Lund".into(), birth_date: chrono::NaiveDate::from_ymd_opt(1980, 1, 1).expect("Invalid date"), office_id: small,
+        department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2015, 1, 1).expect("Invalid date"), termination_date: Some(today + chrono::Duration::days(30)), status: EmploymentStatus::Active,
+    };
+    employee_repo.create_employee(DEFAULT_TENANT_ID, &leaving).await.unwrap();
+    let csv = format!(
+        "first_name,last_name,birth_date,office_id,office_name,hire_date\n\
+         Eva,Egholm,1980-01-01,{id},,2019-08-01\n\
+         Finn,Friis,1980-01-01,{id},,{later}\n",
+        id = small,
+        later = today + chrono::Duration::days(60)
+    );
+    let report = service.import_employees(&hr, ImportEmployeeRow::parse_csv(csv.as_bytes()), ImportMode::Partial, false, Language::English).await.unwrap();
+    assert_eq!((report.imported, report.failed), (1, 1));
+    assert!(report.rows[0].errors[0].contains("full capacity"));
+    let finn = report.rows[1].employee.as_ref().unwrap();
+    assert_eq!((finn.hire_date, finn.status), (today + chrono::Duration::days(60), EmploymentStatus::Pending));
+}
+
+/// Export streams employees within scope, office filter and office name join
+/// Expects the office manager's export limited to the managed office and an out-of-scope filter to fail
+async fn export_employees_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let manager_repo = db.office_manager_repository();
+    let service = EmployeeService::new(employee_repo.clone(), office_repo.clone(), manager_repo.clone(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work());
+    let hr = principal("hr", &[Role::HrEditor]);
+    let manager = principal("manager", &[Role::OfficeManager]);
+
+    let managed = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Aalborg".into(), max_occupancy: 5, ..Default::default() }).await.unwrap();
+    let other = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Aarhus".into(), max_occupancy: 5, ..Default::default() }).await.unwrap();
+    manager_repo.add_office_manager(DEFAULT_TENANT_ID, "manager", managed.id.unwrap()).await.unwrap();
+
+    let birth_date = chrono::NaiveDate::from_ymd_opt(1980, 1, 1).expect("Invalid date");
+    for (last_name, office_id) in [("Mine", managed.id.unwrap()), ("Theirs", other.id.unwrap()), ("Also", other.id.unwrap())] {
+        service.add_employee(&hr, &Employee { id: None, first_name: "Test".into(), last_name: last_name.into(), birth_date, office_id, department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active }).await.unwrap();
+    }
+
+    let all: Vec<_> = service.export_employees(&hr, None, true).await.unwrap().collect().await;
+    assert_eq!(all.len(), 3);
+    assert!(all.iter().all(|row| row.as_ref().unwrap().office_name.is_some()));
+
+    let filtered: Vec<_> = service.export_employees(&hr, other.id, false).await.unwrap().collect().await;
+    assert_eq!(filtered.len(), 2);
+    assert!(filtered.iter().all(|row| row.as_ref().unwrap().office_name.is_none()));
+
+    let scoped: Vec<_> = service.export_employees(&manager, None, true).await.unwrap().collect().await;
+    assert_eq!(scoped.len(), 1);
+    assert_eq!(scoped[0].as_ref().unwrap().office_name.as_deref(), Some("Aalborg"));
+    assert!(service.export_employees(&manager, other.id, false).await.is_err());
+}
+
+/// Office changes that depend on employees run in one transaction
+/// Expects shrinking below the headcount and reassigning to a full office to fail without changes,
+/// and a reassigning delete to move the staff and remove the office
+async fn office_reassign_and_shrink_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let service = OfficeService::new(office_repo.clone(), employee_repo.clone(), db.office_manager_repository(), db.seat_service(), db.unit_of_work());
+    let admin = principal("admin", &[Role::Admin]);
+
+    let closing = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Aalborg".into(), max_occupancy: 5, ..Default::default() }).await.unwrap().id.unwrap();
+    let small = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Skagen".into(), max_occupancy: 1, ..Default::default() }).await.unwrap().id.unwrap();
+    let large = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Aarhus".into(), max_occupancy: 3, ..Default::default() }).await.unwrap().id.unwrap();
+    let birth_date = chrono::NaiveDate::from_ymd_opt(1980, 1, 1).expect("Invalid date");
+    for last_name in ["Første", "Anden"] {
+        employee_repo.create_employee(DEFAULT_TENANT_ID, &Employee { id: None, first_name: "Test".into(), last_name: last_name.into(), birth_date, office_id: closing, department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active }).await.unwrap();
+    }
+
+    // max_occupancy only changes through an approved change request
+    let unapproved = service.update_office(&admin, closing, &Office { id: None, name: "Aalborg".into(), max_occupancy: 2, ..Default::default() }).await;
+    assert_eq!(unapproved.unwrap_err().to_string(), format!("Changing max occupancy of office with ID {} needs approval, submit a change request", closing));
+    assert!(service.update_office(&admin, closing, &Office { id: None, name: "Aalborg N".into(), max_occupancy: 5, ..Default::default() }).await.is_ok());
+    let shrink = service.update_office_approved(DEFAULT_TENANT_ID, &OfficeScope::All, closing, &Office { id: None, name: "Aalborg".into(), max_occupancy: 1, ..Default::default() }).await;
+    assert!(shrink.unwrap_err().to_string().contains("cannot be lowered"));
+    assert!(service.update_office_approved(DEFAULT_TENANT_ID, &OfficeScope::Offices(vec![small]), closing, &Office { id: None, name: "Aalborg".into(), max_occupancy: 2, ..Default::default() }).await.is_err());
+    assert!(service.update_office_approved(DEFAULT_TENANT_ID, &OfficeScope::Offices(vec![closing]), closing, &Office { id: None, name: "Aalborg".into(), max_occupancy: 2, ..Default::default() }).await.is_ok());
+
+    assert!(service.remove_office_reassigning(&admin, closing, small).await.is_err());
+    assert!(service.remove_office_reassigning(&admin, closing, closing).await.is_err());
+    assert_eq!(employee_repo.current_employee_nr_by_office_id(DEFAULT_TENANT_ID, closing).await.unwrap(), 2);
+
+    assert!(service.remove_office_reassigning(&admin, closing, large).await.unwrap());
+    assert!(office_repo.get_office_by_id(DEFAULT_TENANT_ID, closing).await.unwrap().is_none());
+    assert_eq!(employee_repo.current_employee_nr_by_office_id(DEFAULT_TENANT_ID, large).await.unwrap(), 2);
+    assert!(!service.remove_office_reassigning(&admin, closing, large).await.unwrap());
+}
+
+/// Office override raises the minimum age, expects it applied in that office only and reported by validation_rules
+async fn validation_rule_overrides_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let strict = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Strict".into(), max_occupancy: 5, ..Default::default() }).await.unwrap().id.unwrap();
+    let open = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Open".into(), max_occupancy: 5, ..Default::default() }).await.unwrap().id.unwrap();
+
+    let mut rules = ValidationRules::default();
+    rules.offices.insert(strict, RuleOverride { min_age: Some(30), ..Default::default() });
+    let service = EmployeeService::new(db.employee_repository(), office_repo, db.office_manager_repository(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work())
+        .with_validation_rules(rules);
+    let hr = principal("hr", &[Role::HrEditor]);
+
+    let birth_date = chrono::Utc::now().date_naive() - chrono::Months::new(25 * 12);
+    let young = |office_id| Employee { id: None, first_name: "Ida".into(), last_name: "Berg".into(), birth_date, office_id, department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active };
+    let err = service.add_employee(&hr, &young(strict)).await.unwrap_err();
+    assert_eq!(err.to_string(), "Employee must be at least 30 years old");
+    service.add_employee(&hr, &young(open)).await.unwrap();
+
+    assert_eq!(service.validation_rules(&hr, Some(strict)).await.unwrap().min_age, 30);
+    assert_eq!(service.validation_rules(&hr, None).await.unwrap().min_age, 18);
+    assert!(service.validation_rules(&hr, Some(9999)).await.is_err());
+}
+
+/// Same person entered twice with different spelling of the name, once per duplicate policy
+/// Expects warn to create and report the match, block to reject create and import, and find_duplicates to cluster them
+async fn duplicate_detection_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let office_id = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Odense".into(), max_occupancy: 10, ..Default::default() }).await.unwrap().id.unwrap();
+    let hr = principal("hr", &[Role::HrEditor]);
+    let birth_date = chrono::NaiveDate::from_ymd_opt(1985, 3, 14).unwrap();
+    let person = |first_name: &str, last_name: &str| Employee { id: None, first_name: first_name.into(), last_name: last_name.into(), birth_date, office_id, department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active };
+
+    let service = EmployeeService::new(db.employee_repository(), db.office_repository(), db.office_manager_repository(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work());
+    let (first, duplicates) = service.add_employee_checked(&hr, &person("René", "van der Berg")).await.unwrap();
+    assert!(duplicates.is_empty());
+    let (second, duplicates) = service.add_employee_checked(&hr, &person("Rene", "Van Der-Berg")).await.unwrap();
+    assert_eq!(duplicates, vec![first.id.unwrap()]);
+    let (_, duplicates) = service.add_employee_checked(&hr, &Employee { birth_date: birth_date.succ_opt().unwrap(), ..person("René", "van der Berg") }).await.unwrap();
+    assert!(duplicates.is_empty());
+
+    let clusters = service.find_duplicates(&hr).await.unwrap();
+    assert_eq!(clusters.len(), 1);
+    assert_eq!(clusters[0].0, birth_date);
+    assert_eq!(clusters[0].1.iter().map(|e| e.id).collect::<Vec<_>>(), vec![first.id, second.id]);
+
+    let mut rules = ValidationRules::default();
+    rules.tenants.insert(DEFAULT_TENANT_ID, RuleOverride { duplicates: Some(DuplicatePolicy::Block), ..Default::default() });
+    let blocking = EmployeeService::new(db.employee_repository(), db.office_repository(), db.office_manager_repository(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work())
+        .with_validation_rules(rules);
+    let err = blocking.add_employee(&hr, &person("RENÉ", "Vanderberg")).await.unwrap_err();
+    assert_eq!(err.to_string(), "An employee named RENÉ Vanderberg born 1985-03-14 already exists");
+
+    let csv = format!(
+        "first_name,last_name,birth_date,office_id,office_name\n\
+         Rene,van der Berg,1985-03-14,{id},\n\
+         Mette,Holm,1990-05-05,{id},\n\
+         Mette,Holm,1990-05-05,{id},\n",
+        id = office_id
+    );
+    let report = blocking.import_employees(&hr, ImportEmployeeRow::parse_csv(csv.as_bytes()), ImportMode::Partial, true, Language::English).await.unwrap();
+    let statuses: Vec<_> = report.rows.iter().map(|r| r.status).collect();
+    assert_eq!(statuses, vec![ImportRowStatus::Failed, ImportRowStatus::Valid, ImportRowStatus::Failed]);
+    assert_eq!(report.rows[2].errors, vec!["An employee named Mette Holm born 1990-05-05 already exists"]);
+}
+
+/// Duplicate in another office merged into the survivor, keeping the survivor's names and the source's office
+/// Expects one seat released in total, the source archived, an audit entry, and self or out-of-scope merges to fail
+async fn merge_employees_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let aalborg = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Aalborg".into(), max_occupancy: 1, ..Default::default() }).await.unwrap().id.unwrap();
+    let aarhus = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Aarhus".into(), max_occupancy: 1, ..Default::default() }).await.unwrap().id.unwrap();
+    db.office_manager_repository().add_office_manager(DEFAULT_TENANT_ID, "manager", aalborg).await.unwrap();
+
+    let service = EmployeeService::new(employee_repo.clone(), office_repo.clone(), db.office_manager_repository(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work());
+    let hr = principal("hr", &[Role::HrEditor]);
+    let birth_date = chrono::NaiveDate::from_ymd_opt(1975, 6, 1).unwrap();
+    let target = service.add_employee(&hr, &Employee { id: None, first_name: "Jens".into(), last_name: "Jensen".into(), birth_date, office_id: aalborg, department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active }).await.unwrap().id.unwrap();
+    let source = service.add_employee(&hr, &Employee { id: None, first_name: "jens".into(), last_name: "jensen".into(), birth_date, office_id: aarhus, department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active }).await.unwrap().id.unwrap();
+
+    let request = MergeEmployeeRequest { source_id: source, strategy: MergeSide::Target, fields: MergeFields { office_id: Some(MergeSide::Source), ..Default::default() } };
+    let manager = principal("manager", &[Role::OfficeManager]);
+    let err = service.merge_employees(&manager, target, &request).await.unwrap_err();
+    assert_eq!(err.to_string(), format!("Employee with ID {} does not exist", source));
+    let err = service.merge_employees(&hr, target, &MergeEmployeeRequest { source_id: target, ..request.clone() }).await.unwrap_err();
+    assert_eq!(err.to_string(), format!("Employee with ID {} cannot be merged into itself", target));
+
+    // both offices are full, taking over the source's seat needs no free one
+    let (survivor, entry) = service.merge_employees(&hr, target, &request).await.unwrap();
+    assert_eq!((survivor.id, survivor.first_name.as_str(), survivor.office_id), (Some(target), "Jens", aarhus));
+    assert_eq!(employee_repo.current_employee_nr_by_office_id(DEFAULT_TENANT_ID, aalborg).await.unwrap(), 0);
+    assert_eq!(employee_repo.current_employee_nr_by_office_id(DEFAULT_TENANT_ID, aarhus).await.unwrap(), 1);
+    assert_eq!(employee_repo.get_archived_employee_by_id(DEFAULT_TENANT_ID, source).await.unwrap().unwrap().merged_into, Some(target));
+
+    let entries = db.audit_repository().get_audit_entries_by_employee_id(DEFAULT_TENANT_ID, target).await.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!((entries[0].id, entries[0].action.as_str(), entries[0].subject.as_str()), (entry.id, "employee.merged", "hr"));
+    let details: serde_json::Value = serde_json::from_str(&entries[0].details).unwrap();
+    assert_eq!(details["source"]["first_name"], "jens");
+
+    let err = service.merge_employees(&hr, target, &request).await.unwrap_err();
+    assert_eq!(err.to_string(), format!("Employee with ID {} does not exist", source));
+}
+
+/// Build a small hierarchy through the service, expects unknown managers and departments and every cycle to be rejected
+/// and out-of-scope employees to be left out of the hierarchy
+async fn manager_hierarchy_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let aalborg = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Aalborg".into(), max_occupancy: 10, ..Default::default() }).await.unwrap().id.unwrap();
+    let aarhus = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Aarhus".into(), max_occupancy: 10, ..Default::default() }).await.unwrap().id.unwrap();
+    db.office_manager_repository().add_office_manager(DEFAULT_TENANT_ID, "manager", aalborg).await.unwrap();
+
+    let service = EmployeeService::new(db.employee_repository(), office_repo, db.office_manager_repository(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work());
+    let hr = principal("hr", &[Role::HrEditor]);
+    let birth_date = chrono::NaiveDate::from_ymd_opt(1975, 6, 1).unwrap();
+    let person = |last_name: &str, office_id: i32, manager_id: Option<i32>| Employee { id: None, first_name: "Ida".into(), last_name: last_name.into(), birth_date, office_id, department_id: None, manager_id, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active };
+
+    let err = service.add_employee(&hr, &person("Berg", aalborg, Some(999))).await.unwrap_err();
+    assert_eq!(err.to_string(), "Manager with ID 999 does not exist");
+    let err = service.add_employee(&hr, &Employee { department_id: Some(7), ..person("Berg", aalborg, None) }).await.unwrap_err();
+    assert_eq!(err.to_string(), "Department with ID 7 does not exist");
+
+    let ceo = service.add_employee(&hr, &person("Top", aarhus, None)).await.unwrap().id.unwrap();
+    let lead = service.add_employee(&hr, &person("Lead", aalborg, Some(ceo))).await.unwrap().id.unwrap();
+    let dev = service.add_employee(&hr, &person("Dev", aalborg, Some(lead))).await.unwrap().id.unwrap();
+
+    // self, direct and indirect cycles
+    let err = service.update_employee(&hr, lead, &person("Lead", aalborg, Some(lead)), None).await.unwrap_err();
+    assert_eq!(err.to_string(), format!("Employee with ID {} cannot report to {}, the reporting chain would become a cycle", lead, lead));
+    assert!(service.update_employee(&hr, lead, &person("Lead", aalborg, Some(dev)), None).await.is_err());
+    assert!(service.update_employee(&hr, ceo, &person("Top", aarhus, Some(dev)), None).await.is_err());
+    assert!(service.update_employee(&hr, dev, &person("Dev", aalborg, Some(ceo)), None).await.is_ok());
+
+    let ids = |employees: Vec<Employee>| employees.iter().map(|e| e.id.unwrap()).collect::<Vec<_>>();
+    assert_eq!(ids(service.list_subtree(&hr, ceo).await.unwrap()), vec![lead, dev]);
+    assert_eq!(ids(service.list_reporting_chain(&hr, lead).await.unwrap()), vec![ceo]);
+
+    // the office manager only sees Aalborg: the CEO in Aarhus is neither listed nor reachable
+    let manager = principal("manager", &[Role::OfficeManager]);
+    assert!(service.list_reporting_chain(&manager, lead).await.unwrap().is_empty());
+    let err = service.list_direct_reports(&manager, ceo).await.unwrap_err();
+    assert_eq!(err.to_string(), format!("Employee with ID {} does not exist", ceo));
+}
+
+/// Office diagram with a full and a half full office, seen by hr and by an office manager of one of them
+/// Expects occupancy and full flags, the manager to only see the managed office and office_ids to limit and check offices
+async fn office_diagram_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let manager_repo = db.office_manager_repository();
+    let service = OfficeService::new(office_repo.clone(), employee_repo.clone(), manager_repo.clone(), db.seat_service(), db.unit_of_work());
+    let hr = principal("hr", &[Role::HrEditor]);
+    let manager = principal("manager", &[Role::OfficeManager]);
+
+    let full = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Skagen".into(), max_occupancy: 1, ..Default::default() }).await.unwrap();
+    let roomy = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Tønder".into(), max_occupancy: 4, ..Default::default() }).await.unwrap();
+    manager_repo.add_office_manager(DEFAULT_TENANT_ID, "manager", roomy.id.unwrap()).await.unwrap();
+    let birth_date = chrono::NaiveDate::from_ymd_opt(1980, 1, 1).expect("Invalid date");
+    for (last_name, office) in [("Nord", &full), ("Syd", &roomy), ("Vest", &roomy)] {
+        employee_repo.create_employee(DEFAULT_TENANT_ID, &Employee { id: None, first_name: "Ole".into(), last_name: last_name.into(), birth_date, office_id: office.id.unwrap(), department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active }).await.unwrap();
+    }
+
+    let diagram = service.office_diagram(&hr, None).await.unwrap();
+    let summary: Vec<_> = diagram.offices.iter().map(|o| (o.name.as_str(), o.occupancy, o.full, o.employees.len())).collect();
+    assert_eq!(summary, vec![("Skagen", 1, true, 1), ("Tønder", 2, false, 2)]);
+
+    let scoped = service.office_diagram(&manager, None).await.unwrap();
+    assert_eq!(scoped.offices.len(), 1);
+    assert_eq!(scoped.offices[0].id, roomy.id.unwrap());
+
+    let limited = service.office_diagram(&hr, Some(&[roomy.id.unwrap()])).await.unwrap();
+    assert_eq!(limited.offices.len(), 1);
+    let res = service.office_diagram(&manager, Some(&[full.id.unwrap()])).await;
+    assert!(res.unwrap_err().to_string().contains("does not exist"));
+}
+
+/// Employment lifecycle in an office of one seat: a terminated employee frees the seat after the last day,
+/// a future hire reserves it from the start and the transitions follow the state machine
+async fn employment_lifecycle_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let service = EmployeeService::new(employee_repo.clone(), office_repo.clone(), db.office_manager_repository(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work());
+    let hr = principal("hr", &[Role::HrEditor]);
+
+    let office = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Ribe".into(), max_occupancy: 1, ..Default::default() }).await.unwrap();
+    let today = chrono::Utc::now().date_naive();
+    let person = |last_name: &str, hire_date, status| Employee { id: None, first_name: "Signe".into(), last_name: last_name.into(), birth_date: chrono::NaiveDate::from_ymd_opt(1985, 3, 3).expect("Invalid date"), office_id: office.id.unwrap(), department_id: None, manager_id: None, hire_date, termination_date: None, status };
+    let leaving = service.add_employee(&hr, &person("Gammel", chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), EmploymentStatus::Active)).await.unwrap().id.unwrap();
+
+    let on_leave = service.transition_employee(&hr, leaving, EmploymentStatus::OnLeave, None).await.unwrap();
+    assert_eq!(on_leave.status, EmploymentStatus::OnLeave);
+    let err = service.transition_employee(&hr, leaving, EmploymentStatus::OnLeave, None).await.unwrap_err();
+    assert_eq!(err.to_string(), format!("Employee with ID {} cannot go from on_leave to on_leave", leaving));
+    let err = service.transition_employee(&hr, leaving, EmploymentStatus::Terminated, Some(chrono::NaiveDate::from_ymd_opt(2019, 1, 1).expect("Invalid date"))).await;
+    assert!(err.is_err());
+
+    let last_day = today + chrono::Days::new(10);
+    let terminated = service.transition_employee(&hr, leaving, EmploymentStatus::Terminated, Some(last_day)).await.unwrap();
+    assert_eq!((terminated.status, terminated.termination_date), (EmploymentStatus::Terminated, Some(last_day)));
+    assert!(service.transition_employee(&hr, leaving, EmploymentStatus::Active, None).await.is_err());
+
+    // the seat is taken through the last day, a hire starting the day after fits
+    assert!(service.add_employee(&hr, &person("Tidlig", today + chrono::Days::new(5), EmploymentStatus::Pending)).await.is_err());
+    let starting = service.add_employee(&hr, &person("Ny", today + chrono::Days::new(11), EmploymentStatus::Pending)).await.unwrap().id.unwrap();
+    let err = service.transition_employee(&hr, starting, EmploymentStatus::Active, None).await.unwrap_err();
+    assert!(err.to_string().contains("cannot be activated before"));
+    assert!(service.add_employee(&hr, &person("Senere", today + chrono::Days::new(30), EmploymentStatus::Pending)).await.is_err());
+}
+
+/// Transfers are checked against the projected occupancy of both offices, applied by the job once due
+/// and marked failed when the destination filled up in the meantime
+async fn transfer_projection_and_job_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let service = TransferService::new(db.transfer_repository(), employee_repo.clone(), office_repo.clone(), db.office_manager_repository(), db.audit_repository(), db.seat_service(), db.unit_of_work());
+    let hr = principal("hr", &[Role::HrEditor]);
+
+    let odense = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Odense".into(), max_occupancy: 5, ..Default::default() }).await.unwrap().id.unwrap();
+    let vejle = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Vejle".into(), max_occupancy: 1, ..Default::default() }).await.unwrap().id.unwrap();
+    let kolding = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Kolding".into(), max_occupancy: 1, ..Default::default() }).await.unwrap().id.unwrap();
+    let person = |last_name: &str, office_id| Employee { id: None, first_name: "Mads".into(), last_name: last_name.into(), birth_date: chrono::NaiveDate::from_ymd_opt(1975, 5, 5).expect("Invalid date"), office_id, department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active };
+    let anna = employee_repo.create_employee(DEFAULT_TENANT_ID, &person("Anna", odense)).await.unwrap().id.unwrap();
+    let bent = employee_repo.create_employee(DEFAULT_TENANT_ID, &person("Bent", odense)).await.unwrap().id.unwrap();
+    let carl = employee_repo.create_employee(DEFAULT_TENANT_ID, &person("Carl", vejle)).await.unwrap().id.unwrap();
+    let today = chrono::Utc::now().date_naive();
+    let day = |n: u64| today + chrono::Days::new(n);
+
+    // transfers are planned as approved change requests only
+    let all = &OfficeScope::All;
+    let schedule = |employee_id: i32, to_office_id: i32, effective_date| service.schedule_transfer_approved(DEFAULT_TENANT_ID, "hr", all, employee_id, to_office_id, effective_date);
+
+    // Vejle only has a seat once Carl has left
+    assert!(schedule(anna, vejle, day(10)).await.is_err());
+    let carl_leaves = schedule(carl, odense, day(5)).await.unwrap().id.unwrap();
+    assert!(schedule(anna, vejle, day(4)).await.is_err());
+    let anna_moves = schedule(anna, vejle, day(5)).await.unwrap();
+    assert_eq!((anna_moves.from_office_id, anna_moves.status), (odense, TransferStatus::Pending));
+    let err = schedule(anna, kolding, day(6)).await.unwrap_err();
+    assert_eq!(err.to_string(), format!("Employee with ID {} has pending transfer {} already", anna, anna_moves.id.unwrap()));
+    let err = schedule(bent, odense, today.pred_opt().unwrap()).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<ValidationErrors>().unwrap().codes(), vec!["to_office_id.same_office", "effective_date.past"]);
+
+    // Carl keeping his seat longer would leave no seat for Anna
+    assert!(service.reschedule_transfer(&hr, carl_leaves, day(8)).await.is_err());
+    assert!(service.cancel_transfer(&hr, carl_leaves).await.is_err());
+    assert_eq!(service.reschedule_transfer(&hr, carl_leaves, day(2)).await.unwrap().effective_date, day(2));
+    assert_eq!(service.list_office_transfers(&hr, vejle, None).await.unwrap().len(), 2);
+    assert!(service.list_office_transfers(&hr, vejle + 100, None).await.is_err());
+
+    // Bent is planned into Kolding, which is filled up behind the service's back
+    let bent_moves = schedule(bent, kolding, day(6)).await.unwrap().id.unwrap();
+
+    // planned seats are taken: Kolding has no seat for a hire, Odense gets Carl back on day 2 next to Anna and Bent
+    let employees = EmployeeService::new(employee_repo.clone(), office_repo.clone(), db.office_manager_repository(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work());
+    let offices = OfficeService::new(office_repo.clone(), employee_repo.clone(), db.office_manager_repository(), db.seat_service(), db.unit_of_work());
+    let err = employees.add_employee(&hr, &person("Eva", kolding)).await.unwrap_err();
+    assert_eq!(err.to_string(), "Office Kolding is at full capacity: 1/1 employees");
+    let smaller = Office { id: None, name: "Odense".into(), max_occupancy: 2, ..Default::default() };
+    let err = offices.update_office_approved(DEFAULT_TENANT_ID, all, odense, &smaller).await.unwrap_err();
+    assert_eq!(err.to_string(), "Office has 3 employees, max occupancy cannot be lowered to 2");
+    employee_repo.create_employee(DEFAULT_TENANT_ID, &person("Dorte", kolding)).await.unwrap();
+
+    assert_eq!(service.apply_due_transfers(day(1)).await.unwrap(), TransferRun::default());
+    assert_eq!(service.apply_due_transfers(day(2)).await.unwrap(), TransferRun { applied: 1, failed: 0 });
+    assert_eq!(employee_repo.get_employee_by_id(DEFAULT_TENANT_ID, carl).await.unwrap().unwrap().office_id, odense);
+    assert_eq!(service.apply_due_transfers(day(7)).await.unwrap(), TransferRun { applied: 1, failed: 1 });
+    assert_eq!(employee_repo.get_employee_by_id(DEFAULT_TENANT_ID, anna).await.unwrap().unwrap().office_id, vejle);
+    assert_eq!(employee_repo.get_employee_by_id(DEFAULT_TENANT_ID, bent).await.unwrap().unwrap().office_id, odense);
+    assert_eq!(service.find_transfer_by_id(&hr, bent_moves).await.unwrap().unwrap().status, TransferStatus::Failed);
+    let actions: Vec<_> = db.audit_repository().get_audit_entries_by_employee_id(DEFAULT_TENANT_ID, bent).await.unwrap().into_iter().map(|a| a.action).collect();
+    assert_eq!(actions, vec!["employee.transfer_failed"]);
+    assert!(service.cancel_transfer(&hr, bent_moves).await.unwrap_err().to_string().contains("is failed"));
+}
+
+/// Change requests are applied once every step approved, through the services that make the change
+/// Expects office changes to need facilities and the office manager, the requester not to approve
+/// and an approved change that no longer passes validation to end up failed
+async fn change_request_approval_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let manager_repo = db.office_manager_repository();
+    let office_service = Arc::new(OfficeService::new(office_repo.clone(), employee_repo.clone(), manager_repo.clone(), db.seat_service(), db.unit_of_work()));
+    let employee_service = Arc::new(EmployeeService::new(employee_repo.clone(), office_repo.clone(), manager_repo.clone(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work()));
+    let transfer_service = Arc::new(TransferService::new(db.transfer_repository(), employee_repo.clone(), office_repo.clone(), manager_repo.clone(), db.audit_repository(), db.seat_service(), db.unit_of_work()));
+    let service = ChangeRequestService::new(db.change_request_repository(), manager_repo.clone(), db.unit_of_work(), employee_service, office_service, transfer_service.clone(), chrono::Duration::days(14));
+    let hr = principal("hr", &[Role::HrEditor]);
+    let facilities = principal("facilities", &[Role::FacilitiesAdmin]);
+    let aarhus_manager = principal("aarhus_manager", &[Role::OfficeManager]);
+    let randers_manager = principal("randers_manager", &[Role::OfficeManager]);
+
+    let aarhus = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Aarhus".into(), max_occupancy: 2, ..Default::default() }).await.unwrap().id.unwrap();
+    let randers = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Randers".into(), max_occupancy: 2, ..Default::default() }).await.unwrap().id.unwrap();
+    manager_repo.add_office_manager(DEFAULT_TENANT_ID, "aarhus_manager", aarhus).await.unwrap();
+    manager_repo.add_office_manager(DEFAULT_TENANT_ID, "randers_manager", randers).await.unwrap();
+    let person = |last_name: &str| Employee { id: None, first_name: "Sofie".into(), last_name: last_name.into(), birth_date: chrono::NaiveDate::from_ymd_opt(1985, 3, 3).expect("Invalid date"), office_id: aarhus, department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active };
+    let anna = employee_repo.create_employee(DEFAULT_TENANT_ID, &person("Anna")).await.unwrap().id.unwrap();
+    let bent = employee_repo.create_employee(DEFAULT_TENANT_ID, &person("Bent")).await.unwrap().id.unwrap();
+
+    // more room in Aarhus needs facilities and the Aarhus manager
+    let grow = ProposedChange::OfficeUpdate { office_id: aarhus, office: CreateOfficeRequest { name: "Aarhus".into(), max_occupancy: 10, ..Default::default() } };
+    let request = service.submit_change_request(&aarhus_manager, grow, Some("New floor"), None).await.unwrap();
+    let id = request.request.id.unwrap();
+    assert_eq!(request.approvals.len(), 2);
+    assert_eq!(request.comments[0].body, "New floor");
+    let err = service.approve_change_request(&aarhus_manager, id, None).await.unwrap_err();
+    assert_eq!(err.to_string(), format!("Change request with ID {} was submitted by you and needs another approver", id));
+    assert!(service.approve_change_request(&randers_manager, id, None).await.is_err());
+    let halfway = service.approve_change_request(&facilities, id, Some("Budget is there")).await.unwrap();
+    assert_eq!(halfway.request.status, ChangeRequestStatus::Pending);
+    assert_eq!(office_repo.get_office_by_id(DEFAULT_TENANT_ID, aarhus).await.unwrap().unwrap().max_occupancy, 2);
+    assert!(service.approve_change_request(&facilities, id, None).await.is_err());
+
+    let admin = principal("admin", &[Role::Admin]);
+    let applied = service.approve_change_request(&admin, id, None).await.unwrap();
+    assert_eq!(applied.request.status, ChangeRequestStatus::Applied);
+    assert_eq!(office_repo.get_office_by_id(DEFAULT_TENANT_ID, aarhus).await.unwrap().unwrap().max_occupancy, 10);
+    assert!(service.reject_change_request(&admin, id, None).await.unwrap_err().to_string().contains("is applied"));
+
+    // a move needs both office managers, one rejection rejects it
+    let effective_date = chrono::Utc::now().date_naive() + chrono::Days::new(3);
+    let moving = ProposedChange::Transfer { employee_id: anna, to_office_id: randers, effective_date };
+    let request = service.submit_change_request(&hr, moving.clone(), None, None).await.unwrap();
+    let rejected = service.reject_change_request(&randers_manager, request.request.id.unwrap(), Some("No desks")).await.unwrap();
+    assert_eq!(rejected.request.status, ChangeRequestStatus::Rejected);
+    assert!(rejected.request.resolved_at.is_some());
+    let request = service.submit_change_request(&hr, moving, None, None).await.unwrap().request.id.unwrap();
+    assert_eq!(service.list_change_requests(&randers_manager, Some(ChangeRequestStatus::Pending)).await.unwrap().len(), 1);
+    service.approve_change_request(&aarhus_manager, request, None).await.unwrap();
+    assert_eq!(service.approve_change_request(&randers_manager, request, None).await.unwrap().request.status, ChangeRequestStatus::Applied);
+    assert_eq!(transfer_service.list_office_transfers(&admin, randers, Some(TransferStatus::Pending)).await.unwrap().len(), 1);
+
+    // the approved shrink no longer fits the headcount
+    let shrink = ProposedChange::OfficeUpdate { office_id: aarhus, office: CreateOfficeRequest { name: "Aarhus".into(), max_occupancy: 1, ..Default::default() } };
+    let request = service.submit_change_request(&hr, shrink, None, None).await.unwrap().request.id.unwrap();
+    service.approve_change_request(&facilities, request, None).await.unwrap();
+    let failed = service.approve_change_request(&aarhus_manager, request, None).await.unwrap();
+    assert_eq!(failed.request.status, ChangeRequestStatus::Failed);
+    let reason = &failed.request.failure_reason.as_ref().unwrap().0[0];
+    assert_eq!((reason.code.as_str(), reason.to_string()), ("office.occupancy_below_headcount", "Office has 2 employees, max occupancy cannot be lowered to 1".to_string()));
+
+    // the move is written before the seat count finds Randers full, the whole change rolls back and the decision stays
+    employee_repo.create_employee(DEFAULT_TENANT_ID, &Employee { office_id: randers, ..person("Carl") }).await.unwrap();
+    let employee = CreateEmployeeRequest { first_name: "Sofie".into(), last_name: "Bent".into(), birth_date: chrono::NaiveDate::from_ymd_opt(1985, 3, 3).expect("Invalid date"), office_id: randers, department_id: None, manager_id: None, hire_date: None };
+    let request = service.submit_change_request(&hr, ProposedChange::EmployeeUpdate { employee_id: bent, employee }, None, None).await.unwrap().request.id.unwrap();
+    service.approve_change_request(&aarhus_manager, request, None).await.unwrap();
+    let failed = service.approve_change_request(&randers_manager, request, None).await.unwrap();
+    assert_eq!(failed.request.status, ChangeRequestStatus::Failed);
+    assert_eq!(failed.request.failure_reason.as_ref().unwrap().0[0].to_string(), "Office Randers is at full capacity: 2/2 employees");
+    assert!(failed.approvals.iter().all(|a| a.decision == Some(Decision::Approved)));
+    assert_eq!(employee_repo.get_employee_by_id(DEFAULT_TENANT_ID, bent).await.unwrap().unwrap().office_id, aarhus);
+
+    // withdrawn by the requester only, expired requests cannot be decided
+    let rename = ProposedChange::OfficeUpdate { office_id: randers, office: CreateOfficeRequest { name: "Randers C".into(), max_occupancy: 2, ..Default::default() } };
+    let request = service.submit_change_request(&hr, rename.clone(), None, None).await.unwrap().request.id.unwrap();
+    assert!(service.withdraw_change_request(&facilities, request).await.is_err());
+    assert_eq!(service.withdraw_change_request(&hr, request).await.unwrap().request.status, ChangeRequestStatus::Withdrawn);
+    assert!(service.add_comment(&hr, request, "Too late").await.is_err());
+    let expires_at = chrono::Utc::now() + chrono::Duration::milliseconds(50);
+    let request = service.submit_change_request(&hr, rename.clone(), None, Some(expires_at)).await.unwrap().request.id.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+    assert!(service.approve_change_request(&facilities, request, None).await.unwrap_err().to_string().contains("is expired"));
+    assert_eq!(service.list_change_requests(&admin, Some(ChangeRequestStatus::Expired)).await.unwrap().len(), 1);
+    let past = chrono::Utc::now() - chrono::Duration::days(1);
+    let err = service.submit_change_request(&hr, rename, None, Some(past)).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<ValidationErrors>().unwrap().codes(), vec!["expires_at.past"]);
+    // office managers only see requests about their offices
+    assert!(service.list_change_requests(&aarhus_manager, None).await.unwrap().iter().all(|r| r.office_ids().contains(&aarhus)));
+}
+
+/// Freed seats go to the waitlist in order of arrival, in the transaction that frees them (a delete, a move or a merge):
+/// placed directly, or offered when confirmation was asked for. Direct hires wait their turn
+async fn waitlist_fills_freed_seats_in_order_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let manager_repo = db.office_manager_repository();
+    let employee_service = Arc::new(EmployeeService::new(employee_repo.clone(), office_repo.clone(), manager_repo.clone(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work()));
+    let service = WaitlistService::new(db.waitlist_repository(), office_repo.clone(), db.seat_service(), db.unit_of_work(), employee_service.clone());
+    let hr = principal("hr", &[Role::HrEditor]);
+    let other_manager = principal("other_manager", &[Role::OfficeManager]);
+
+    let odense = Office { id: None, name: "Odense".into(), max_occupancy: 1, ..Default::default() };
+    let odense_id = office_repo.create_office(DEFAULT_TENANT_ID, &odense).await.unwrap().id.unwrap();
+    let birth_date = chrono::NaiveDate::from_ymd_opt(1990, 5, 5).expect("Invalid date");
+    let hire = |last_name: &str| Employee::from_create_request(CreateEmployeeRequest {
+        first_name: "Mette".into(), last_name: last_name.into(), birth_date, office_id: odense_id, department_id: None, manager_id: None, hire_date: None,
+    });
+    let request = |last_name: &str, confirm: bool| WaitlistRequest { first_name: "Mette".into(), last_name: last_name.into(), birth_date, department_id: None, manager_id: None, hire_date: None, confirm };
+    let resize = |max_occupancy: i32| Office { id: Some(odense_id), name: "Odense".into(), max_occupancy, ..Default::default() };
+
+    // an office with room takes hires directly
+    let err = service.enqueue(&hr, odense_id, request("Early", false)).await.unwrap_err();
+    assert_eq!(err.to_string(), "Office Odense has a free seat and nobody waiting, add the employee directly");
+    let anna = employee_service.add_employee(&hr, &hire("Anna")).await.unwrap().id.unwrap();
+
+    let (ib, position) = service.enqueue(&hr, odense_id, request("Ib", false)).await.unwrap();
+    assert_eq!((ib.status, position), (WaitlistStatus::Waiting, Some(1)));
+    let (jens, position) = service.enqueue(&hr, odense_id, request("Jens", true)).await.unwrap();
+    assert_eq!(position, Some(2));
+    let too_young = WaitlistRequest { birth_date: chrono::Utc::now().date_naive() - chrono::Duration::days(365), ..request("Young", false) };
+    let err = service.enqueue(&hr, odense_id, too_young).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<ValidationErrors>().unwrap().codes(), vec!["birth_date.too_young"]);
+    assert!(service.enqueue(&other_manager, odense_id, request("Scoped", false)).await.is_err());
+    assert_eq!(service.fill_waitlists().await.unwrap(), WaitlistRun::default());
+    let err = employee_service.add_employee(&hr, &hire("Direct")).await.unwrap_err();
+    assert_eq!(err.to_string(), "Office Odense has 2 hires on its waitlist, put the employee on the waitlist");
+
+    // a delete hands the seat to the first entry right away, the job finds nothing left to do
+    employee_service.remove_employee(&hr, anna).await.unwrap();
+    assert_eq!(service.fill_waitlists().await.unwrap(), WaitlistRun::default());
+    let (placed, _) = service.find_entry_by_id(&hr, ib.id.unwrap()).await.unwrap().unwrap();
+    assert_eq!(placed.status, WaitlistStatus::Placed);
+    let ib_employee = employee_repo.get_employee_by_id(DEFAULT_TENANT_ID, placed.employee_id.unwrap()).await.unwrap().unwrap();
+    assert_eq!((ib_employee.last_name.as_str(), ib_employee.office_id), ("Ib", odense_id));
+    assert_eq!(service.find_entry_by_id(&hr, jens.id.unwrap()).await.unwrap().unwrap().1, Some(1));
+
+    // more capacity set outside the services is left to the job, which offers the seat to the entry asking for confirmation, the offer counts as taken
+    office_repo.update_office_by_id(DEFAULT_TENANT_ID, odense_id, &resize(2)).await.unwrap();
+    assert_eq!(service.fill_waitlists().await.unwrap(), WaitlistRun { placed: 0, offered: 1, failed: 0 });
+    assert_eq!(service.fill_office(DEFAULT_TENANT_ID, odense_id).await.unwrap(), WaitlistRun::default());
+
+    // a direct hire takes the offered seat, accepting then puts the entry back in line
+    let karl = employee_service.add_employee(&hr, &hire("Karl")).await.unwrap().id.unwrap();
+    let err = service.accept(&hr, jens.id.unwrap()).await.unwrap_err();
+    assert_eq!(err.to_string(), "Office Odense is at full capacity: 2/2 employees");
+    let (lapsed, position) = service.find_entry_by_id(&hr, jens.id.unwrap()).await.unwrap().unwrap();
+    assert_eq!((lapsed.status, position), (WaitlistStatus::Waiting, Some(1)));
+
+    office_repo.update_office_by_id(DEFAULT_TENANT_ID, odense_id, &resize(3)).await.unwrap();
+    assert_eq!(service.fill_waitlists().await.unwrap().offered, 1);
+    let declined = service.decline(&hr, jens.id.unwrap()).await.unwrap();
+    assert_eq!(declined.status, WaitlistStatus::Declined);
+    let err = service.accept(&hr, jens.id.unwrap()).await.unwrap_err();
+    assert_eq!(err.to_string(), format!("Waitlist entry with ID {} is declined, no seat is offered to it", jens.id.unwrap()));
+    let events = service.list_events(&hr, jens.id.unwrap()).await.unwrap();
+    assert_eq!(
+        events.iter().map(|e| e.event).collect::<Vec<_>>(),
+        vec![WaitlistEventKind::Enqueued, WaitlistEventKind::Offered, WaitlistEventKind::OfferLapsed, WaitlistEventKind::Offered, WaitlistEventKind::Declined]
+    );
+
+    // an entry that no longer passes fails and the next one gets the seat
+    let mads = employee_service.add_employee(&hr, &hire("Mads")).await.unwrap().id.unwrap();
+    let reports_to_ib = WaitlistRequest { manager_id: ib_employee.id, ..request("Lis", false) };
+    let (lis, _) = service.enqueue(&hr, odense_id, reports_to_ib).await.unwrap();
+    let (ole, _) = service.enqueue(&hr, odense_id, request("Ole", false)).await.unwrap();
+    let (per, _) = service.enqueue(&hr, odense_id, request("Per", false)).await.unwrap();
+    employee_service.remove_employee(&hr, ib_employee.id.unwrap()).await.unwrap();
+    assert_eq!(service.fill_waitlists().await.unwrap(), WaitlistRun::default());
+    let failed = service.list_events(&hr, lis.id.unwrap()).await.unwrap();
+    assert_eq!(failed.last().unwrap().event, WaitlistEventKind::Failed);
+    assert!(failed.last().unwrap().details.as_deref().unwrap().contains("does not exist"));
+    assert_eq!(service.find_entry_by_id(&hr, ole.id.unwrap()).await.unwrap().unwrap().0.status, WaitlistStatus::Placed);
+
+    let waiting = service.list_office_waitlist(&hr, odense_id, Some(WaitlistStatus::Waiting)).await.unwrap();
+    assert_eq!(waiting.iter().map(|(e, position)| (e.id, *position)).collect::<Vec<_>>(), vec![(per.id, Some(1))]);
+    assert_eq!(service.cancel(&hr, per.id.unwrap()).await.unwrap().status, WaitlistStatus::Cancelled);
+    assert!(service.cancel(&hr, per.id.unwrap()).await.is_err());
+    assert_eq!(service.list_office_waitlist(&hr, odense_id, None).await.unwrap().len(), 5);
+
+    // a move to another office and a merge free their seats in Odense for the waitlist as well
+    let svendborg = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Svendborg".into(), max_occupancy: 5, ..Default::default() }).await.unwrap().id.unwrap();
+    let (rie, _) = service.enqueue(&hr, odense_id, request("Rie", false)).await.unwrap();
+    let (sus, _) = service.enqueue(&hr, odense_id, request("Sus", false)).await.unwrap();
+    employee_service.update_employee_approved(DEFAULT_TENANT_ID, &OfficeScope::All, karl, &Employee { office_id: svendborg, ..hire("Karl") }, None).await.unwrap();
+    assert_eq!(service.find_entry_by_id(&hr, rie.id.unwrap()).await.unwrap().unwrap().0.status, WaitlistStatus::Placed);
+    let merge = MergeEmployeeRequest { source_id: mads, strategy: MergeSide::Target, fields: MergeFields::default() };
+    employee_service.merge_employees(&hr, karl, &merge).await.unwrap();
+    assert_eq!(service.find_entry_by_id(&hr, sus.id.unwrap()).await.unwrap().unwrap().0.status, WaitlistStatus::Placed);
+    assert_eq!(service.fill_waitlists().await.unwrap(), WaitlistRun::default());
+}
+
+/// Putting an employee in a room checks every level above it the employee does not sit in yet, capacity rolls up to the parents
+async fn location_capacity_checked_at_every_level_service_test(db: impl TestDatabase) {
+    let office_repo = db.office_repository();
+    let employee_repo = db.employee_repository();
+    let service = LocationService::new(db.location_repository(), employee_repo.clone(), office_repo.clone(), db.office_manager_repository(), db.unit_of_work());
+    let facilities = principal("facilities", &[Role::FacilitiesAdmin]);
+    let hr = principal("hr", &[Role::HrEditor]);
+    let other_manager = principal("other_manager", &[Role::OfficeManager]);
+
+    let koege = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Køge".into(), max_occupancy: 3, ..Default::default() }).await.unwrap().id.unwrap();
+    let roskilde = office_repo.create_office(DEFAULT_TENANT_ID, &Office { id: None, name: "Roskilde".into(), max_occupancy: 5, ..Default::default() }).await.unwrap().id.unwrap();
+    let hire = |last_name: &str, office_id: i32| Employee { id: None, first_name: "Sofie".into(), last_name: last_name.into(), birth_date: chrono::NaiveDate::from_ymd_opt(1985, 3, 3).expect("Invalid date"), office_id, department_id: None, manager_id: None, hire_date: chrono::NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date"), termination_date: None, status: EmploymentStatus::Active };
+    let mut koege_ids = Vec::new();
+    for last_name in ["Ahl", "Bak", "Dam"] {
+        koege_ids.push(employee_repo.create_employee(DEFAULT_TENANT_ID, &hire(last_name, koege)).await.unwrap().id.unwrap());
+    }
+    let (ahl, bak, dam) = (koege_ids[0], koege_ids[1], koege_ids[2]);
+    let guest = employee_repo.create_employee(DEFAULT_TENANT_ID, &hire("Gæst", roskilde)).await.unwrap().id.unwrap();
+
+    let create = |kind: LocationKind, name: &str, parent_id: Option<i32>, capacity: Option<i32>| CreateLocationRequest { kind, name: name.into(), parent_id, capacity, office_id: None };
+    let region = service.create_location(&facilities, create(LocationKind::Region, "Sjælland", None, None)).await.unwrap().id;
+    let site = service.create_location(&facilities, CreateLocationRequest { office_id: Some(koege), ..create(LocationKind::Site, "Køge", region, None) }).await.unwrap();
+    assert_eq!((site.total_capacity, site.occupancy), (Some(3), 3));
+    let building = service.create_location(&facilities, create(LocationKind::Building, "Havnen", site.id, None)).await.unwrap();
+    assert_eq!(building.office_id, Some(koege));
+    let floor = service.create_location(&facilities, create(LocationKind::Floor, "1. sal", building.id, Some(2))).await.unwrap().id.unwrap();
+    let small = service.create_location(&facilities, create(LocationKind::Room, "1.01", Some(floor), Some(1))).await.unwrap().id.unwrap();
+    let large = service.create_location(&facilities, create(LocationKind::Room, "1.02", Some(floor), Some(2))).await.unwrap().id.unwrap();
+
+    // every level the employee arrives in needs a free seat, the first full one is named
+    assert_eq!(service.assign_employee(&hr, small, ahl).await.unwrap().occupancy, 1);
+    let err = service.assign_employee(&hr, small, bak).await.unwrap_err();
+    assert_eq!(err.to_string(), "1.01 (room) is at full capacity: 1/1 employees");
+    service.assign_employee(&hr, large, bak).await.unwrap();
+    let err = service.assign_employee(&hr, large, dam).await.unwrap_err();
+    assert_eq!(err.to_string(), "1. sal (floor) is at full capacity: 2/2 employees");
+    // moving within the floor only needs a seat in the new room
+    assert_eq!(service.assign_employee(&hr, large, ahl).await.unwrap().occupancy, 2);
+
+    let code = |e: anyhow::Error| e.downcast_ref::<corp_data_api::utils::CodedError>().unwrap().code.clone();
+    assert_eq!(code(service.assign_employee(&hr, floor, dam).await.unwrap_err()), "location.not_room");
+    assert_eq!(code(service.assign_employee(&hr, small, guest).await.unwrap_err()), "location.other_office");
+    assert_eq!(code(service.assign_employee(&other_manager, small, dam).await.unwrap_err()), "employee.not_found");
+
+    // capacity rolls up from the floor, occupancy from the rooms and the site itself
+    let locations = service.list_locations(&hr).await.unwrap();
+    let figures: Vec<_> = locations.iter().map(|l| (l.kind, l.total_capacity, l.occupancy)).collect();
+    assert_eq!(figures, vec![
+        (LocationKind::Region, Some(3), 3),
+        (LocationKind::Site, Some(3), 3),
+        (LocationKind::Building, Some(2), 2),
+        (LocationKind::Floor, Some(2), 2),
+        (LocationKind::Room, Some(1), 0),
+        (LocationKind::Room, Some(2), 2),
+    ]);
+
+    // the tree keeps its shape
+    let codes = |e: anyhow::Error| e.downcast_ref::<ValidationErrors>().unwrap().codes().into_iter().map(String::from).collect::<Vec<_>>();
+    assert_eq!(codes(service.create_location(&facilities, create(LocationKind::Room, "Løs", None, None)).await.unwrap_err()), vec!["parent_id.missing"]);
+    assert_eq!(codes(service.create_location(&facilities, create(LocationKind::Room, "Hal", building.id, None)).await.unwrap_err()), vec!["parent_id.wrong_kind"]);
+    let sized_site = CreateLocationRequest { office_id: Some(roskilde), ..create(LocationKind::Site, "Roskilde", None, Some(5)) };
+    assert_eq!(codes(service.create_location(&facilities, sized_site).await.unwrap_err()), vec!["capacity.on_site"]);
+    let again = CreateLocationRequest { office_id: Some(koege), ..create(LocationKind::Site, "Køge igen", None, None) };
+    assert_eq!(code(service.create_location(&facilities, again).await.unwrap_err()), "location.site_exists");
+    let update = |name: &str, capacity: Option<i32>, parent_id: Option<i32>| UpdateLocationRequest { name: name.into(), capacity, parent_id };
+    assert_eq!(codes(service.update_location(&facilities, floor, update("1. sal", Some(2), site.id)).await.unwrap_err()), vec!["parent_id.fixed"]);
+    assert_eq!(code(service.update_location(&facilities, floor, update("1. sal", Some(1), building.id)).await.unwrap_err()), "location.capacity_below_occupancy");
+
+    // a site only moves into a region with room for everyone in it
+    let small_region = service.create_location(&facilities, create(LocationKind::Region, "Lolland", None, Some(2))).await.unwrap().id;
+    assert_eq!(code(service.update_location(&facilities, site.id.unwrap(), update("Køge", None, small_region)).await.unwrap_err()), "location.full");
+    let top = service.update_location(&facilities, site.id.unwrap(), update("Køge Havn", None, None)).await.unwrap();
+    assert_eq!((top.parent_id, top.name.as_str(), top.capacity), (None, "Køge Havn", Some(3)));
+
+    // only empty locations are deleted, a room without employees is
+    assert_eq!(code(service.delete_location(&facilities, floor).await.unwrap_err()), "location.not_empty");
+    assert!(service.delete_location(&facilities, small).await.unwrap());
+    assert!(!service.delete_location(&facilities, small).await.unwrap());
+    assert!(service.unassign_employee(&hr, large, bak).await.unwrap());
+    assert!(!service.unassign_employee(&hr, large, bak).await.unwrap());
+    assert_eq!(service.find_location(&hr, large).await.unwrap().unwrap().occupancy, 1);
+
+    // an office added through the service has its site from the start
+    let offices = OfficeService::new(office_repo.clone(), employee_repo.clone(), db.office_manager_repository(), db.seat_service(), db.unit_of_work());
+    let employees = EmployeeService::new(employee_repo.clone(), office_repo.clone(), db.office_manager_repository(), db.audit_repository(), db.department_repository(), db.seat_service(), db.unit_of_work());
+    let naestved = offices.add_office(&principal("admin", &[Role::Admin]), &Office { id: None, name: "Næstved".into(), max_occupancy: 5, ..Default::default() }).await.unwrap().id.unwrap();
+    let locations = service.list_locations(&hr).await.unwrap();
+    let naestved_site = locations.iter().find(|l| l.office_id == Some(naestved)).unwrap();
+    assert_eq!((naestved_site.kind, naestved_site.parent_id, naestved_site.total_capacity), (LocationKind::Site, None, Some(5)));
 
-    clean_db(&pool).await;
+    // hires and moves into a site need a seat in its region as well
+    let tight = service.create_location(&facilities, create(LocationKind::Region, "Sydsjælland", None, Some(1))).await.unwrap().id;
+    service.update_location(&facilities, naestved_site.id.unwrap(), update("Næstved", None, tight)).await.unwrap();
+    employees.add_employee(&hr, &hire("Eg", naestved)).await.unwrap();
+    let err = employees.add_employee(&hr, &hire("Fog", naestved)).await.unwrap_err();
+    assert_eq!(err.to_string(), "Sydsjælland (region) is at full capacity: 1/1 employees");
+    let err = employees.update_employee_approved(DEFAULT_TENANT_ID, &OfficeScope::All, guest, &hire("Gæst", naestved), None).await.unwrap_err();
+    assert_eq!(code(err), "location.full");
 }
\ No newline at end of file
@@ -1,6 +1,268 @@
+use std::sync::Arc;
+use axum::Router;
+
+use corp_data_api::auth::{permission::Role, principal::{authenticate, Principal}, token::TokenKeys};
+use corp_data_api::entity::tenant::DEFAULT_TENANT_ID;
+use corp_data_api::repository::{
+    audit_repository::AuditStore, change_request_repository::ChangeRequestStore, department_repository::DepartmentStore,
+    employee_repository::EmployeeStore, health_repository::HealthStore, idempotency_repository::IdempotencyStore,
+    location_repository::LocationStore, office_manager_repository::OfficeManagerStore, office_repository::OfficeStore,
+    transfer_repository::TransferStore, unit_of_work::UnitOfWork, waitlist_repository::WaitlistStore,
+};
+use corp_data_api::repository::memory_repository::{
+    MemoryAuditRepository, MemoryChangeRequestRepository, MemoryDatabase, MemoryDepartmentRepository, MemoryEmployeeRepository,
+    MemoryHealthRepository, MemoryIdempotencyRepository, MemoryLocationRepository, MemoryOfficeManagerRepository,
+    MemoryOfficeRepository, MemoryTransferRepository, MemoryUnitOfWork, MemoryWaitlistRepository,
+};
+use corp_data_api::service::idempotency_service::IdempotencyService;
+use corp_data_api::service::seat_service::SeatService;
+
+const TEST_JWT_SECRET: &str = "test_secret";
 
 // helper to clean db when testing
+#[allow(dead_code)]
 pub async fn clean_db(pool: &sqlx::PgPool) {
+    sqlx::query!("TRUNCATE TABLE idempotency_keys").execute(pool).await.unwrap();
+    sqlx::query!("TRUNCATE TABLE audit_entries").execute(pool).await.unwrap();
+    sqlx::query!("TRUNCATE TABLE change_requests CASCADE").execute(pool).await.unwrap();
     sqlx::query!("TRUNCATE TABLE employees CASCADE").execute(pool).await.unwrap();
+    sqlx::query!("TRUNCATE TABLE departments CASCADE").execute(pool).await.unwrap();
     sqlx::query!("TRUNCATE TABLE offices CASCADE").execute(pool).await.unwrap();
-}
\ No newline at end of file
+    sqlx::query!("DELETE FROM tenants WHERE id <> 1").execute(pool).await.unwrap();
+}
+
+// helper to build an idempotency service with the default 24 hour window
+#[allow(dead_code)]
+pub fn idempotency_service(db: &impl TestDatabase) -> Arc<IdempotencyService> {
+    Arc::new(IdempotencyService::new(db.idempotency_repository(), chrono::Duration::hours(24)))
+}
+
+// helper to put a router behind the same authentication layer as main
+#[allow(dead_code)]
+pub fn with_auth(router: Router) -> Router {
+    let keys = Arc::new(TokenKeys::from_secret(TEST_JWT_SECRET));
+    router.layer(axum::middleware::from_fn_with_state(keys, authenticate))
+}
+
+// helper to build an Authorization header value for the given roles
+#[allow(dead_code)]
+pub fn bearer(roles: &[Role]) -> String {
+    let token = TokenKeys::from_secret(TEST_JWT_SECRET)
+        .issue("test_user", roles, Some(DEFAULT_TENANT_ID), chrono::Duration::minutes(5))
+        .unwrap();
+    format!("Bearer {}", token)
+}
+
+// helper to build an Authorization header value for another subject than test_user
+#[allow(dead_code)]
+pub fn bearer_as(subject: &str, roles: &[Role]) -> String {
+    let token = TokenKeys::from_secret(TEST_JWT_SECRET)
+        .issue(subject, roles, Some(DEFAULT_TENANT_ID), chrono::Duration::minutes(5))
+        .unwrap();
+    format!("Bearer {}", token)
+}
+
+// helper to build an Authorization header value of a token without tenant_id claim
+#[allow(dead_code)]
+pub fn bearer_without_tenant(roles: &[Role]) -> String {
+    let token = TokenKeys::from_secret(TEST_JWT_SECRET)
+        .issue("test_user", roles, None, chrono::Duration::minutes(5))
+        .unwrap();
+    format!("Bearer {}", token)
+}
+
+// helper to build an Authorization header value bound to a tenant
+#[allow(dead_code)]
+pub fn bearer_for_tenant(roles: &[Role], tenant_id: i32) -> String {
+    let token = TokenKeys::from_secret(TEST_JWT_SECRET)
+        .issue("test_user", roles, Some(tenant_id), chrono::Duration::minutes(5))
+        .unwrap();
+    format!("Bearer {}", token)
+}
+
+// helper to build a principal of the default tenant for service tests
+#[allow(dead_code)]
+pub fn principal(subject: &str, roles: &[Role]) -> Principal {
+    Principal { subject: subject.to_string(), roles: roles.to_vec(), tenant_id: DEFAULT_TENANT_ID }
+}
+
+/// Repositories of one storage backend, named like the MemoryDatabase constructors
+/// Service and endpoint tests take one and run once per backend, see `backends!`
+#[allow(dead_code, async_fn_in_trait)]
+pub trait TestDatabase {
+    type Offices: OfficeStore + Clone + 'static;
+    type Employees: EmployeeStore + Clone + 'static;
+    type Managers: OfficeManagerStore + Clone + 'static;
+    type Audit: AuditStore + Clone + 'static;
+    type Departments: DepartmentStore + Clone + 'static;
+    type Transfers: TransferStore + Clone + 'static;
+    type ChangeRequests: ChangeRequestStore + Clone + 'static;
+    type Waitlist: WaitlistStore + Clone + 'static;
+    type Locations: LocationStore + Clone + 'static;
+    type Idempotency: IdempotencyStore + Clone + 'static;
+    type Health: HealthStore + Clone + 'static;
+    type UnitOfWork: UnitOfWork + Clone + 'static;
+
+    fn office_repository(&self) -> Self::Offices;
+    fn employee_repository(&self) -> Self::Employees;
+    fn office_manager_repository(&self) -> Self::Managers;
+    fn audit_repository(&self) -> Self::Audit;
+    fn department_repository(&self) -> Self::Departments;
+    fn transfer_repository(&self) -> Self::Transfers;
+    fn change_request_repository(&self) -> Self::ChangeRequests;
+    fn waitlist_repository(&self) -> Self::Waitlist;
+    fn location_repository(&self) -> Self::Locations;
+    fn idempotency_repository(&self) -> Self::Idempotency;
+    fn health_repository(&self) -> Self::Health;
+    fn unit_of_work(&self) -> Self::UnitOfWork;
+
+    /// A tenant besides the default one, for isolation checks
+    async fn other_tenant(&self) -> i32;
+
+    /// Seat accounting on the repositories of this backend, as the services share it
+    fn seat_service(&self) -> SeatService {
+        SeatService::new(
+            self.employee_repository(),
+            self.transfer_repository(),
+            self.office_repository(),
+            self.department_repository(),
+            self.waitlist_repository(),
+            self.location_repository(),
+        )
+    }
+}
+
+impl TestDatabase for MemoryDatabase {
+    type Offices = MemoryOfficeRepository;
+    type Employees = MemoryEmployeeRepository;
+    type Managers = MemoryOfficeManagerRepository;
+    type Audit = MemoryAuditRepository;
+    type Departments = MemoryDepartmentRepository;
+    type Transfers = MemoryTransferRepository;
+    type ChangeRequests = MemoryChangeRequestRepository;
+    type Waitlist = MemoryWaitlistRepository;
+    type Locations = MemoryLocationRepository;
+    type Idempotency = MemoryIdempotencyRepository;
+    type Health = MemoryHealthRepository;
+    type UnitOfWork = MemoryUnitOfWork;
+
+    fn office_repository(&self) -> Self::Offices { MemoryDatabase::office_repository(self) }
+    fn employee_repository(&self) -> Self::Employees { MemoryDatabase::employee_repository(self) }
+    fn office_manager_repository(&self) -> Self::Managers { MemoryDatabase::office_manager_repository(self) }
+    fn audit_repository(&self) -> Self::Audit { MemoryDatabase::audit_repository(self) }
+    fn department_repository(&self) -> Self::Departments { MemoryDatabase::department_repository(self) }
+    fn transfer_repository(&self) -> Self::Transfers { MemoryDatabase::transfer_repository(self) }
+    fn change_request_repository(&self) -> Self::ChangeRequests { MemoryDatabase::change_request_repository(self) }
+    fn waitlist_repository(&self) -> Self::Waitlist { MemoryDatabase::waitlist_repository(self) }
+    fn location_repository(&self) -> Self::Locations { MemoryDatabase::location_repository(self) }
+    fn idempotency_repository(&self) -> Self::Idempotency { MemoryDatabase::idempotency_repository(self) }
+    fn health_repository(&self) -> Self::Health { MemoryDatabase::health_repository(self) }
+    fn unit_of_work(&self) -> Self::UnitOfWork { MemoryDatabase::unit_of_work(self) }
+
+    // the in-memory backend has no tenants table, any other id will do
+    async fn other_tenant(&self) -> i32 {
+        DEFAULT_TENANT_ID + 1
+    }
+}
+
+/// SQLite database file of one test, removed again when the test is done
+/// A file rather than sqlite::memory:, whose single connection would block reads while a transaction is open
+#[cfg(feature = "sqlite")]
+pub struct SqliteTestDatabase {
+    pool: sqlx::SqlitePool,
+    path: std::path::PathBuf,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteTestDatabase {
+    #[allow(dead_code)]
+    pub async fn new() -> Self {
+        static NEXT: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+        let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("corp_data_api_test_{}_{}.db", std::process::id(), n));
+        let _ = std::fs::remove_file(&path);
+        let settings = corp_data_api::config::db_settings::Settings::from_url(format!("sqlite:{}", path.display()));
+        let pool = settings.create_sqlite_pool().await.unwrap();
+        Self { pool, path }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Drop for SqliteTestDatabase {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+        let _ = std::fs::remove_file(self.path.with_extension("db-journal"));
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite_backend {
+    use corp_data_api::repository::sqlite_repository::{
+        SqliteAuditRepository, SqliteChangeRequestRepository, SqliteDepartmentRepository, SqliteEmployeeRepository, SqliteHealthRepository,
+        SqliteIdempotencyRepository, SqliteLocationRepository, SqliteOfficeManagerRepository, SqliteOfficeRepository, SqliteTransferRepository,
+        SqliteUnitOfWork, SqliteWaitlistRepository,
+    };
+
+    impl super::TestDatabase for super::SqliteTestDatabase {
+        type Offices = SqliteOfficeRepository;
+        type Employees = SqliteEmployeeRepository;
+        type Managers = SqliteOfficeManagerRepository;
+        type Audit = SqliteAuditRepository;
+        type Departments = SqliteDepartmentRepository;
+        type Transfers = SqliteTransferRepository;
+        type ChangeRequests = SqliteChangeRequestRepository;
+        type Waitlist = SqliteWaitlistRepository;
+        type Locations = SqliteLocationRepository;
+        type Idempotency = SqliteIdempotencyRepository;
+        type Health = SqliteHealthRepository;
+        type UnitOfWork = SqliteUnitOfWork;
+
+        fn office_repository(&self) -> Self::Offices { SqliteOfficeRepository::new(self.pool.clone()) }
+        fn employee_repository(&self) -> Self::Employees { SqliteEmployeeRepository::new(self.pool.clone()) }
+        fn office_manager_repository(&self) -> Self::Managers { SqliteOfficeManagerRepository::new(self.pool.clone()) }
+        fn audit_repository(&self) -> Self::Audit { SqliteAuditRepository::new(self.pool.clone()) }
+        fn department_repository(&self) -> Self::Departments { SqliteDepartmentRepository::new(self.pool.clone()) }
+        fn transfer_repository(&self) -> Self::Transfers { SqliteTransferRepository::new(self.pool.clone()) }
+        fn change_request_repository(&self) -> Self::ChangeRequests { SqliteChangeRequestRepository::new(self.pool.clone()) }
+        fn waitlist_repository(&self) -> Self::Waitlist { SqliteWaitlistRepository::new(self.pool.clone()) }
+        fn location_repository(&self) -> Self::Locations { SqliteLocationRepository::new(self.pool.clone()) }
+        fn idempotency_repository(&self) -> Self::Idempotency { SqliteIdempotencyRepository::new(self.pool.clone()) }
+        fn health_repository(&self) -> Self::Health { SqliteHealthRepository::new(self.pool.clone()) }
+        fn unit_of_work(&self) -> Self::UnitOfWork { SqliteUnitOfWork::new(self.pool.clone()) }
+
+        async fn other_tenant(&self) -> i32 {
+            sqlx::query_scalar::<_, i32>("INSERT INTO tenants (name) VALUES ('Other') RETURNING id")
+                .fetch_one(&self.pool)
+                .await
+                .unwrap()
+        }
+    }
+}
+
+// Runs each listed test once per backend: in memory and, with `--features sqlite`, on a SQLite file
+// Every test is an async fn taking the TestDatabase to run on
+#[allow(unused_macros)]
+macro_rules! backends {
+    ($($test:ident),* $(,)?) => {
+        mod memory {
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    super::$test(corp_data_api::repository::memory_repository::MemoryDatabase::new()).await;
+                }
+            )*
+        }
+
+        #[cfg(feature = "sqlite")]
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    super::$test(super::utils::SqliteTestDatabase::new().await).await;
+                }
+            )*
+        }
+    };
+}
+#[allow(unused_imports)]
+pub(crate) use backends;